@@ -1,12 +1,15 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::validator_status::ValidatorStatus;
 use anyhow::Result;
 use diem_logger::json_log::JsonLogEntry;
+use diem_mempool::ScriptFilterRules;
 use reqwest::blocking;
 use std::collections::HashMap;
 
 pub mod node_debug_service;
+pub mod validator_status;
 
 /// Implement default utility client for NodeDebugInterface
 pub struct NodeDebugClient {
@@ -77,6 +80,44 @@ impl NodeDebugClient {
 
         Ok(response.json()?)
     }
+
+    /// Consensus participation, mempool, last commit and 0L standing of the node's validator.
+    pub fn get_validator_status(&mut self) -> Result<ValidatorStatus> {
+        let response = self
+            .client
+            .get(&format!("{}/validator/status", self.addr))
+            .send()?
+            .error_for_status()?;
+
+        Ok(response.json()?)
+    }
+
+    /// Only served to clients on the node's own host.
+    pub fn get_mempool_script_filter(&mut self) -> Result<ScriptFilterRules> {
+        let response = self
+            .client
+            .get(&format!("{}/mempool/script-filter", self.addr))
+            .send()?
+            .error_for_status()?;
+
+        Ok(response.json()?)
+    }
+
+    /// Replaces the node's mempool script filter, returning the rules now in effect. Only served
+    /// to clients on the node's own host.
+    pub fn set_mempool_script_filter(
+        &mut self,
+        rules: &ScriptFilterRules,
+    ) -> Result<ScriptFilterRules> {
+        let response = self
+            .client
+            .post(&format!("{}/mempool/script-filter", self.addr))
+            .json(rules)
+            .send()?
+            .error_for_status()?;
+
+        Ok(response.json()?)
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
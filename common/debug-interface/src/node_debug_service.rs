@@ -3,10 +3,117 @@
 
 //! Debug interface to access information in a specific node.
 
-use diem_logger::{info, json_log, Filter, Logger};
+use crate::validator_status::validator_status;
+use diem_logger::{info, json_log, Filter, LevelFilter, LogEntry, Logger};
+use diem_mempool::{
+    MempoolPeerRequest, MempoolPeerSender, MempoolSnapshot, MempoolSnapshotRequest,
+    MempoolSnapshotSender, PeerBroadcastStatus, ScriptFilter, ScriptFilterRules,
+    SubmissionStatusBundle,
+};
+use diem_types::{account_address::AccountAddress, PeerId};
+use futures::{channel::oneshot, SinkExt};
+use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, sync::Arc};
+use storage_interface::DbReader;
 use tokio::runtime::{Builder, Runtime};
-use warp::Filter as _;
+use warp::{http::StatusCode, reply::Response, Filter as _, Reply};
+
+/// Number of recent log entries returned by GET /log/entries if no limit is given.
+const DEFAULT_LOG_ENTRIES_LIMIT: usize = 100;
+
+/// The filters currently applied by the logger, as directives strings.
+#[derive(Serialize)]
+struct LogFilters {
+    local: String,
+    remote: String,
+}
+
+/// Query params of GET /log/entries, selecting the entries to return.
+#[derive(Deserialize)]
+struct LogEntriesParams {
+    /// Only entries with this `name` field, i.e. the `LogEntry` of the component's log schema.
+    name: Option<String>,
+    /// Only entries whose `peer` field contains this string.
+    peer: Option<String>,
+    /// At most this many entries, the most recent ones.
+    limit: Option<usize>,
+}
+
+impl LogEntriesParams {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let field = |key: &str| entry.data().get(key).and_then(|value| value.as_str());
+        self.name
+            .as_deref()
+            .map_or(true, |name| field("name") == Some(name))
+            && self.peer.as_deref().map_or(true, |peer| {
+                field("peer").map_or(false, |field| field.contains(peer))
+            })
+    }
+}
+
+/// The outcome of importing a transaction of a mempool snapshot.
+#[derive(Serialize)]
+struct ImportStatus {
+    sender: String,
+    sequence_number: u64,
+    mempool_status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vm_status: Option<String>,
+}
+
+impl From<SubmissionStatusBundle> for ImportStatus {
+    fn from((txn, (mempool_status, vm_status)): SubmissionStatusBundle) -> Self {
+        Self {
+            sender: txn.sender().to_string(),
+            sequence_number: txn.sequence_number(),
+            mempool_status: format!("{}: {}", mempool_status.code, mempool_status.message),
+            vm_status: vm_status.map(|vm_status| format!("{:?}", vm_status)),
+        }
+    }
+}
+
+async fn export_mempool_snapshot(mut sender: MempoolSnapshotSender) -> anyhow::Result<Vec<u8>> {
+    let (callback, callback_rcv) = oneshot::channel();
+    sender
+        .send(MempoolSnapshotRequest::Export(callback))
+        .await?;
+    Ok(bcs::to_bytes(&callback_rcv.await?)?)
+}
+
+async fn import_mempool_snapshot(
+    mut sender: MempoolSnapshotSender,
+    snapshot: MempoolSnapshot,
+) -> anyhow::Result<Vec<ImportStatus>> {
+    let (callback, callback_rcv) = oneshot::channel();
+    sender
+        .send(MempoolSnapshotRequest::Import(snapshot, callback))
+        .await?;
+    Ok(callback_rcv
+        .await??
+        .into_iter()
+        .map(ImportStatus::from)
+        .collect())
+}
+
+async fn send_peer_request(
+    mut sender: MempoolPeerSender,
+    request: impl FnOnce(oneshot::Sender<Vec<PeerBroadcastStatus>>) -> MempoolPeerRequest,
+) -> anyhow::Result<Vec<PeerBroadcastStatus>> {
+    let (callback, callback_rcv) = oneshot::channel();
+    sender.send(request(callback)).await?;
+    Ok(callback_rcv.await?)
+}
+
+fn peer_request_response(result: anyhow::Result<Vec<PeerBroadcastStatus>>) -> Response {
+    match result {
+        Ok(statuses) => warp::reply::json(&statuses).into_response(),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+fn error_response(status: StatusCode, error: impl ToString) -> Response {
+    warp::reply::with_status(warp::reply::json(&error.to_string()), status).into_response()
+}
 
 #[derive(Debug)]
 pub struct NodeDebugService {
@@ -14,7 +121,15 @@ pub struct NodeDebugService {
 }
 
 impl NodeDebugService {
-    pub fn new(address: SocketAddr, logger: Option<Arc<Logger>>) -> Self {
+    pub fn new(
+        address: SocketAddr,
+        logger: Option<Arc<Logger>>,
+        mempool_script_filter: Arc<ScriptFilter>,
+        mempool_snapshot_sender: MempoolSnapshotSender,
+        mempool_peer_sender: MempoolPeerSender,
+        db: Arc<dyn DbReader>,
+        validator_account: Option<AccountAddress>,
+    ) -> Self {
         let runtime = Builder::new_multi_thread()
             .thread_name("nodedebug")
             .enable_all()
@@ -28,6 +143,72 @@ impl NodeDebugService {
         // GET /events
         let events = warp::path("events").map(|| warp::reply::json(&json_log::pop_last_entries()));
 
+        // GET /log/filter
+        let get_filters = {
+            let logger = logger.clone();
+
+            warp::get().and(warp::path!("log" / "filter")).map(move || {
+                warp::reply::json(&logger.as_ref().map(|logger| LogFilters {
+                    local: logger.filter(),
+                    remote: logger.remote_filter(),
+                }))
+            })
+        };
+
+        // GET /log/entries
+        let get_entries = {
+            let logger = logger.clone();
+
+            warp::get()
+                .and(warp::path!("log" / "entries"))
+                .and(warp::query())
+                .map(move |params: LogEntriesParams| {
+                    let entries = logger.as_ref().map_or_else(Vec::new, |logger| {
+                        logger.recent_entries(
+                            |entry| params.matches(entry),
+                            params.limit.unwrap_or(DEFAULT_LOG_ENTRIES_LIMIT),
+                        )
+                    });
+                    warp::reply::json(&entries)
+                })
+        };
+
+        // Post /log/filter/<module>, setting the level of a single module
+        let module_filter = {
+            let logger = logger.clone();
+
+            warp::path!("filter" / String)
+                .and(warp::body::content_length_limit(1024))
+                .and(warp::body::bytes())
+                .map(move |module: String, bytes: bytes::Bytes| {
+                    let level = ::std::str::from_utf8(&bytes)
+                        .ok()
+                        .and_then(|level| level.trim().parse::<LevelFilter>().ok());
+                    match (&logger, level) {
+                        (Some(logger), Some(level)) => {
+                            info!(
+                                module = module,
+                                level = ?level,
+                                "Updating local logging filter of module"
+                            );
+                            logger.set_filter_level(Some(&module), level);
+                            warp::reply::with_status(
+                                warp::reply::json(&logger.filter()),
+                                StatusCode::OK,
+                            )
+                        }
+                        (None, _) => warp::reply::with_status(
+                            warp::reply::json(&"logger not configured"),
+                            StatusCode::NOT_FOUND,
+                        ),
+                        (_, None) => warp::reply::with_status(
+                            warp::reply::json(&"invalid level"),
+                            StatusCode::BAD_REQUEST,
+                        ),
+                    }
+                })
+        };
+
         // Post /log/filter
         let local_filter = {
             let logger = logger.clone();
@@ -63,9 +244,167 @@ impl NodeDebugService {
         // Post /log
         let log = warp::post()
             .and(warp::path("log"))
-            .and(local_filter.or(remote_filter));
+            .and(module_filter.or(local_filter).or(remote_filter));
+
+        // The mempool script filter, transactions and peers can only be read and changed from the
+        // node's own host.
+        let from_loopback =
+            warp::addr::remote().and_then(|remote: Option<SocketAddr>| async move {
+                match remote {
+                    Some(remote) if remote.ip().is_loopback() => Ok(remote),
+                    _ => Err(warp::reject::not_found()),
+                }
+            });
+        let script_filter_path =
+            warp::path!("mempool" / "script-filter").and(from_loopback.clone());
+        let snapshot_path = warp::path!("mempool" / "snapshot").and(from_loopback.clone());
+
+        // GET /mempool/snapshot, the BCS serialized `MempoolSnapshot` of the pending transactions
+        let export_snapshot = {
+            let snapshot_sender = mempool_snapshot_sender.clone();
+            warp::get().and(snapshot_path).and_then(move |_remote| {
+                let snapshot_sender = snapshot_sender.clone();
+                async move {
+                    let response = match export_mempool_snapshot(snapshot_sender).await {
+                        Ok(bytes) => bytes.into_response(),
+                        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+                    };
+                    Ok::<_, warp::Rejection>(response)
+                }
+            })
+        };
+
+        // POST /mempool/snapshot, submitting the transactions of a BCS serialized `MempoolSnapshot`
+        let import_snapshot = warp::post()
+            .and(snapshot_path)
+            .and(warp::body::content_length_limit(1024 * 1024 * 1024))
+            .and(warp::body::bytes())
+            .and_then(move |remote: SocketAddr, bytes: bytes::Bytes| {
+                let snapshot_sender = mempool_snapshot_sender.clone();
+                async move {
+                    let snapshot = match bcs::from_bytes::<MempoolSnapshot>(&bytes) {
+                        Ok(snapshot) => snapshot,
+                        Err(e) => {
+                            return Ok::<_, warp::Rejection>(error_response(
+                                StatusCode::BAD_REQUEST,
+                                e,
+                            ))
+                        }
+                    };
+                    info!(
+                        remote = remote.to_string(),
+                        num_txns = snapshot.transactions.len(),
+                        "Importing mempool snapshot"
+                    );
+                    let response = match import_mempool_snapshot(snapshot_sender, snapshot).await {
+                        Ok(statuses) => warp::reply::json(&statuses).into_response(),
+                        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+                    };
+                    Ok(response)
+                }
+            });
+
+        // GET /mempool/script-filter
+        let get_script_filter = {
+            let script_filter = mempool_script_filter.clone();
+            warp::get()
+                .and(script_filter_path.clone())
+                .map(move |_remote| warp::reply::json(&script_filter.rules()))
+        };
+
+        // POST /mempool/script-filter
+        let set_script_filter = warp::post()
+            .and(script_filter_path)
+            .and(warp::body::content_length_limit(1024 * 1024))
+            .and(warp::body::json())
+            .map(move |remote: SocketAddr, rules: ScriptFilterRules| {
+                match mempool_script_filter.update(rules, &remote.to_string()) {
+                    Ok(()) => warp::reply::with_status(
+                        warp::reply::json(&mempool_script_filter.rules()),
+                        StatusCode::OK,
+                    ),
+                    Err(e) => warp::reply::with_status(
+                        warp::reply::json(&e.to_string()),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    ),
+                }
+            });
+
+        // GET /mempool/peers, the broadcast state of all upstream peers
+        let get_peers = {
+            let peer_sender = mempool_peer_sender.clone();
+            warp::get()
+                .and(warp::path!("mempool" / "peers"))
+                .and(from_loopback.clone())
+                .and_then(move |_remote| {
+                    let peer_sender = peer_sender.clone();
+                    async move {
+                        let result = send_peer_request(peer_sender, MempoolPeerRequest::List).await;
+                        Ok::<_, warp::Rejection>(peer_request_response(result))
+                    }
+                })
+        };
+
+        // POST /mempool/peers/<peer_id>/{pause,resume,flush-retries}, controlling broadcasts to
+        // the peer on all networks it is connected on
+        let control_peer = warp::post()
+            .and(warp::path!("mempool" / "peers" / String / String))
+            .and(from_loopback)
+            .and_then(move |peer_id: String, action: String, remote: SocketAddr| {
+                let peer_sender = mempool_peer_sender.clone();
+                async move {
+                    let peer_id = match peer_id.trim_start_matches("0x").parse::<PeerId>() {
+                        Ok(peer_id) => peer_id,
+                        Err(e) => {
+                            return Ok::<_, warp::Rejection>(error_response(
+                                StatusCode::BAD_REQUEST,
+                                e,
+                            ))
+                        }
+                    };
+                    let request: fn(PeerId, _) -> MempoolPeerRequest = match action.as_str() {
+                        "pause" => MempoolPeerRequest::Pause,
+                        "resume" => MempoolPeerRequest::Resume,
+                        "flush-retries" => MempoolPeerRequest::FlushRetries,
+                        _ => {
+                            return Ok(error_response(
+                                StatusCode::NOT_FOUND,
+                                format!("unknown peer action {}", action),
+                            ))
+                        }
+                    };
+                    info!(
+                        remote = remote.to_string(),
+                        peer_id = peer_id.to_string(),
+                        action = action,
+                        "Controlling mempool broadcasts to peer"
+                    );
+                    let result =
+                        send_peer_request(peer_sender, |callback| request(peer_id, callback)).await;
+                    Ok(peer_request_response(result))
+                }
+            });
+
+        // GET /validator/status, consensus participation, mempool, last commit and 0L standing of
+        // this validator in one response
+        let get_validator_status = warp::get().and(warp::path!("validator" / "status")).map(
+            move || match validator_status(&*db, validator_account) {
+                Ok(status) => warp::reply::json(&status).into_response(),
+                Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+            },
+        );
 
-        let routes = log.or(warp::get().and(metrics.or(events)));
+        let routes = log
+            .or(get_filters)
+            .or(get_entries)
+            .or(warp::get().and(metrics.or(events)))
+            .or(get_script_filter)
+            .or(set_script_filter)
+            .or(export_snapshot)
+            .or(import_snapshot)
+            .or(get_peers)
+            .or(control_peer)
+            .or(get_validator_status);
 
         runtime
             .handle()
@@ -0,0 +1,212 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Status of this validator, aggregated from storage and metrics so operator dashboards need a
+//! single request instead of scraping the metrics, JSON-RPC and the chain separately.
+
+use anyhow::Result;
+use diem_types::{
+    account_address::AccountAddress, account_config::diem_root_address,
+    account_state::AccountState, ol_validators_stats::ValidatorStats, transaction::Version,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use storage_interface::DbReader;
+
+/// The status returned by GET /validator/status.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ValidatorStatus {
+    /// Account of this validator, `None` if the node isn't a validator.
+    pub account: Option<AccountAddress>,
+    pub epoch: u64,
+    /// Whether `account` is in the validator set of `epoch`.
+    pub in_validator_set: bool,
+    pub last_commit: LastCommit,
+    pub consensus: ConsensusStatus,
+    pub mempool: MempoolStatus,
+    /// 0L standing of `account`, `None` if the node isn't a validator.
+    pub standing: Option<Standing>,
+}
+
+/// The latest ledger info committed by this node.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LastCommit {
+    pub version: Version,
+    pub round: u64,
+    pub timestamp_usecs: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ConsensusStatus {
+    pub current_round: u64,
+    /// Proposals of this validator counted on chain in the current epoch.
+    pub epoch_proposals: Option<u64>,
+    /// Votes of this validator counted on chain in the current epoch.
+    pub epoch_votes: Option<u64>,
+    /// Proposals sent since the node started.
+    pub proposals_sent: u64,
+    /// Votes for nil blocks, i.e. on round timeouts, since the node started.
+    pub nil_votes: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MempoolStatus {
+    /// Transactions ready to be proposed.
+    pub ready_txns: u64,
+    /// Transactions waiting for a lower sequence number of their sender.
+    pub parked_txns: u64,
+    pub capacity: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Standing {
+    /// None if the account never submitted a tower proof.
+    pub tower_height: Option<u64>,
+    pub proofs_in_epoch: Option<u64>,
+    pub epochs_validating_and_mining: Option<u64>,
+    /// Validators vouching for the account, None if it has no vouch resource.
+    pub vouches: Option<Vec<AccountAddress>>,
+}
+
+/// Reads the status of `account` at the latest ledger info.
+pub fn validator_status(
+    db: &dyn DbReader,
+    account: Option<AccountAddress>,
+) -> Result<ValidatorStatus> {
+    let ledger_info = db.get_latest_ledger_info()?;
+    let ledger_info = ledger_info.ledger_info();
+    let version = ledger_info.version();
+    let root_state = account_state(db, diem_root_address(), version)?;
+
+    let in_validator_set = match (account, root_state.get_validator_set()?) {
+        (Some(account), Some(validator_set)) => validator_set
+            .payload()
+            .iter()
+            .any(|info| *info.account_address() == account),
+        _ => false,
+    };
+    let epoch_stats = match (account, root_state.get_validators_stats()?) {
+        (Some(account), Some(stats)) => stats.get_validator_current_stats(account).ok(),
+        _ => None,
+    };
+    let standing = account
+        .map(|account| standing(db, account, version))
+        .transpose()?;
+
+    Ok(ValidatorStatus {
+        account,
+        epoch: ledger_info.epoch(),
+        in_validator_set,
+        last_commit: LastCommit {
+            version,
+            round: ledger_info.round(),
+            timestamp_usecs: ledger_info.timestamp_usecs(),
+        },
+        consensus: consensus_status(epoch_stats.as_ref()),
+        mempool: mempool_status(),
+        standing,
+    })
+}
+
+fn consensus_status(epoch_stats: Option<&ValidatorStats>) -> ConsensusStatus {
+    ConsensusStatus {
+        current_round: metric_value("diem_consensus_current_round", &[]),
+        epoch_proposals: epoch_stats.map(|stats| stats.prop_count),
+        epoch_votes: epoch_stats.map(|stats| stats.vote_count),
+        proposals_sent: metric_value("diem_consensus_proposals_count", &[]),
+        nil_votes: metric_value("diem_consensus_vote_nil_count", &[]),
+    }
+}
+
+fn mempool_status() -> MempoolStatus {
+    MempoolStatus {
+        ready_txns: metric_value("diem_core_mempool_index_size", &[("index", "priority")]),
+        parked_txns: metric_value("diem_core_mempool_index_size", &[("index", "parking_lot")]),
+        capacity: metric_value("diem_core_mempool_capacity", &[]),
+    }
+}
+
+/// Returns the value of the metric `name` with the given labels, 0 if it isn't registered.
+fn metric_value(name: &str, labels: &[(&str, &str)]) -> u64 {
+    diem_metrics::get_metric_values(name)
+        .into_iter()
+        .find(|(metric_labels, _)| {
+            labels
+                .iter()
+                .all(|(name, value)| metric_labels.get(*name).map(String::as_str) == Some(*value))
+        })
+        .map_or(0, |(_, value)| value as u64)
+}
+
+fn standing(db: &dyn DbReader, account: AccountAddress, version: Version) -> Result<Standing> {
+    let state = account_state(db, account, version)?;
+    let tower = state.get_miner_state()?;
+    Ok(Standing {
+        tower_height: tower.as_ref().map(|tower| tower.verified_tower_height),
+        proofs_in_epoch: tower.as_ref().map(|tower| tower.count_proofs_in_epoch),
+        epochs_validating_and_mining: tower
+            .as_ref()
+            .map(|tower| tower.epochs_validating_and_mining),
+        vouches: state.get_vouch_resource()?.map(|vouch| vouch.vals),
+    })
+}
+
+/// The state of `address` at `version`, empty if the account doesn't exist.
+fn account_state(
+    db: &dyn DbReader,
+    address: AccountAddress,
+    version: Version,
+) -> Result<AccountState> {
+    let (blob, _proof) = db.get_account_state_with_proof_by_version(address, version)?;
+    blob.as_ref()
+        .map(AccountState::try_from)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_metrics::{register_int_counter, register_int_gauge, register_int_gauge_vec};
+
+    #[test]
+    fn test_metrics_status() {
+        // Not registered yet.
+        assert_eq!(mempool_status().capacity, 0);
+
+        register_int_gauge!("diem_consensus_current_round", "current round")
+            .unwrap()
+            .set(42);
+        register_int_counter!("diem_consensus_proposals_count", "proposals")
+            .unwrap()
+            .inc_by(3);
+        register_int_counter!("diem_consensus_vote_nil_count", "nil votes")
+            .unwrap()
+            .inc();
+        let index_size =
+            register_int_gauge_vec!("diem_core_mempool_index_size", "index size", &["index"])
+                .unwrap();
+        index_size.with_label_values(&["priority"]).set(10);
+        index_size.with_label_values(&["parking_lot"]).set(5);
+        index_size.with_label_values(&["timeline"]).set(7);
+        register_int_gauge!("diem_core_mempool_capacity", "capacity")
+            .unwrap()
+            .set(1_000);
+
+        let consensus = consensus_status(Some(&ValidatorStats {
+            vote_count: 8,
+            prop_count: 2,
+        }));
+        assert_eq!(consensus.current_round, 42);
+        assert_eq!(consensus.epoch_proposals, Some(2));
+        assert_eq!(consensus.epoch_votes, Some(8));
+        assert_eq!(consensus.proposals_sent, 3);
+        assert_eq!(consensus.nil_votes, 1);
+        assert_eq!(consensus_status(None).epoch_votes, None);
+
+        let mempool = mempool_status();
+        assert_eq!(mempool.ready_txns, 10);
+        assert_eq!(mempool.parked_txns, 5);
+        assert_eq!(mempool.capacity, 1_000);
+    }
+}
@@ -15,11 +15,11 @@ use crate::{
 };
 use backtrace::Backtrace;
 use chrono::{SecondsFormat, Utc};
-use diem_infallible::RwLock;
+use diem_infallible::{Mutex, RwLock};
 use once_cell::sync::Lazy;
 use serde::Serialize;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     env, fmt,
     io::Write,
     sync::{
@@ -32,10 +32,12 @@ use std::{
 const RUST_LOG: &str = "RUST_LOG";
 /// Default size of log write channel, if the channel is full, logs will be dropped
 pub const CHANNEL_SIZE: usize = 10000;
+/// Default number of recent log entries kept in memory by nodes to be queried
+pub const RECENT_ENTRIES_SIZE: usize = 1000;
 const NUM_SEND_RETRIES: u8 = 1;
 
 /// A single log entry emitted by a logging macro with associated metadata
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct LogEntry {
     #[serde(flatten)]
     metadata: Metadata,
@@ -157,6 +159,7 @@ pub struct DiemLoggerBuilder {
     printer: Option<Box<dyn Writer>>,
     is_async: bool,
     custom_format: Option<fn(&LogEntry) -> Result<String, fmt::Error>>,
+    recent_entries_size: usize,
 }
 
 impl DiemLoggerBuilder {
@@ -170,6 +173,7 @@ impl DiemLoggerBuilder {
             printer: Some(Box::new(StderrWriter)),
             is_async: false,
             custom_format: None,
+            recent_entries_size: 0,
         }
     }
 
@@ -218,6 +222,13 @@ impl DiemLoggerBuilder {
         self
     }
 
+    /// Keeps the last `recent_entries_size` logged entries in memory to be queried through
+    /// `DiemLogger::recent_entries`, none by default.
+    pub fn recent_entries_size(&mut self, recent_entries_size: usize) -> &mut Self {
+        self.recent_entries_size = recent_entries_size;
+        self
+    }
+
     pub fn init(&mut self) {
         self.build();
     }
@@ -253,6 +264,7 @@ impl DiemLoggerBuilder {
             }
         };
 
+        let recent_entries = RecentEntries::new(self.recent_entries_size);
         let logger = if self.is_async {
             let (sender, receiver) = mpsc::sync_channel(self.channel_size);
             let logger = Arc::new(DiemLogger {
@@ -260,6 +272,7 @@ impl DiemLoggerBuilder {
                 printer: None,
                 filter: RwLock::new(filter),
                 formatter: self.custom_format.take().unwrap_or(default_format),
+                recent_entries,
             });
             let service = LoggerService {
                 receiver,
@@ -276,6 +289,7 @@ impl DiemLoggerBuilder {
                 printer: self.printer.take(),
                 filter: RwLock::new(filter),
                 formatter: self.custom_format.take().unwrap_or(default_format),
+                recent_entries,
             })
         };
 
@@ -298,11 +312,39 @@ impl DiemFilter {
     }
 }
 
+/// A ring buffer of the last logged entries
+struct RecentEntries {
+    capacity: usize,
+    entries: Mutex<VecDeque<LogEntry>>,
+}
+
+impl RecentEntries {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn push(&self, entry: &LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry.clone());
+    }
+}
+
 pub struct DiemLogger {
     sender: Option<SyncSender<LoggerServiceEvent>>,
     printer: Option<Box<dyn Writer>>,
     filter: RwLock<DiemFilter>,
     pub(crate) formatter: fn(&LogEntry) -> Result<String, fmt::Error>,
+    recent_entries: RecentEntries,
 }
 
 impl DiemLogger {
@@ -334,6 +376,40 @@ impl DiemLogger {
         self.filter.write().remote_filter = filter;
     }
 
+    /// Sets the most verbose level logged locally by the given module (if any), leaving the
+    /// levels of other modules as they are.
+    pub fn set_filter_level(&self, module: Option<&str>, level: LevelFilter) {
+        self.filter.write().local_filter.set_level(module, level);
+    }
+
+    /// Returns the local filter as a directives string.
+    pub fn filter(&self) -> String {
+        self.filter.read().local_filter.to_string()
+    }
+
+    /// Returns the remote filter as a directives string.
+    pub fn remote_filter(&self) -> String {
+        self.filter.read().remote_filter.to_string()
+    }
+
+    /// Returns the most recent logged entries matching `predicate`, oldest first, at most `limit`
+    /// of them. Entries are only kept if enabled with `DiemLoggerBuilder::recent_entries_size`.
+    pub fn recent_entries<F>(&self, predicate: F, limit: usize) -> Vec<LogEntry>
+    where
+        F: Fn(&LogEntry) -> bool,
+    {
+        let entries = self.recent_entries.entries.lock();
+        let mut matching = entries
+            .iter()
+            .rev()
+            .filter(|entry| predicate(entry))
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+        matching.reverse();
+        matching
+    }
+
     fn send_entry(&self, entry: LogEntry) {
         if let Some(printer) = &self.printer {
             let s = (self.formatter)(&entry).expect("Unable to format");
@@ -356,6 +432,7 @@ impl Logger for DiemLogger {
 
     fn record(&self, event: &Event) {
         let entry = LogEntry::new(event, ::std::thread::current().name());
+        self.recent_entries.push(&entry);
 
         self.send_entry(entry)
     }
@@ -4,7 +4,7 @@
 //! Filtering definitions for controlling what modules and levels are logged
 
 use crate::{Level, Metadata};
-use std::{env, str::FromStr};
+use std::{env, fmt, str::FromStr};
 
 pub struct FilterParseError;
 
@@ -40,6 +40,20 @@ impl FromStr for LevelFilter {
     }
 }
 
+impl fmt::Display for LevelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let level = match self {
+            LevelFilter::Off => return f.pad("OFF"),
+            LevelFilter::Error => Level::Error,
+            LevelFilter::Warn => Level::Warn,
+            LevelFilter::Info => Level::Info,
+            LevelFilter::Debug => Level::Debug,
+            LevelFilter::Trace => Level::Trace,
+        };
+        fmt::Display::fmt(&level, f)
+    }
+}
+
 impl From<Level> for LevelFilter {
     fn from(level: Level) -> Self {
         match level {
@@ -107,13 +121,7 @@ impl Builder {
             // Add the default filter if none exist
             self.filter_level(LevelFilter::Error);
         } else {
-            // Sort the directives by length of their name, this allows a
-            // little more efficient lookup at runtime.
-            self.directives.sort_by(|a, b| {
-                let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
-                let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
-                alen.cmp(&blen)
-            });
+            sort_directives(&mut self.directives);
         }
 
         Filter {
@@ -143,6 +151,42 @@ impl Filter {
         }
         false
     }
+
+    /// Sets the most verbose level logged by the given module (if any), replacing the directive
+    /// previously set for it. If no module is provided then the level applies to all log messages
+    /// not matched by a module directive.
+    pub fn set_level(&mut self, module: Option<&str>, level: LevelFilter) {
+        self.directives
+            .retain(|directive| directive.name.as_deref() != module);
+        self.directives.push(Directive::new(module, level));
+        sort_directives(&mut self.directives);
+    }
+}
+
+/// Formats the filter as a directives string, which parses back into the same filter.
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match &directive.name {
+                Some(name) => write!(f, "{}={}", name, directive.level)?,
+                None => write!(f, "{}", directive.level)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sorts the directives by length of their name, this allows a little more efficient lookup at
+/// runtime.
+fn sort_directives(directives: &mut [Directive]) {
+    directives.sort_by(|a, b| {
+        let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
+        let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
+        alen.cmp(&blen)
+    });
 }
 
 /// A `Filter` directive for which logs to keep based on a module `name` based filter
@@ -351,4 +395,33 @@ mod tests {
         assert_eq!(dirs[1].name.as_deref(), Some("crate2"));
         assert_eq!(dirs[1].level, LevelFilter::Debug);
     }
+
+    #[test]
+    fn set_level() {
+        let mut logger = Builder::new()
+            .filter(None, LevelFilter::Info)
+            .filter(Some("crate1::mod1"), LevelFilter::Warn)
+            .build();
+        logger.set_level(Some("crate1::mod1"), LevelFilter::Debug);
+        logger.set_level(Some("crate1"), LevelFilter::Off);
+        assert!(logger.enabled(&make_metadata(Level::Debug, "crate1::mod1")));
+        assert!(!logger.enabled(&make_metadata(Level::Error, "crate1::mod2")));
+        assert!(logger.enabled(&make_metadata(Level::Info, "crate2")));
+
+        logger.set_level(None, LevelFilter::Warn);
+        assert!(!logger.enabled(&make_metadata(Level::Info, "crate2")));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let logger = Builder::new().parse("warn,crate1=off,crate2::mod1").build();
+        assert_eq!(logger.to_string(), "WARN,crate1=OFF,crate2::mod1=TRACE");
+        assert_eq!(
+            Builder::new()
+                .parse(&logger.to_string())
+                .build()
+                .to_string(),
+            logger.to_string()
+        );
+    }
 }
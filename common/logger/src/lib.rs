@@ -158,7 +158,8 @@ mod security;
 mod struct_log;
 
 pub use crate::diem_logger::{
-    DiemLogger, DiemLogger as Logger, DiemLoggerBuilder, Writer, CHANNEL_SIZE,
+    DiemLogger, DiemLogger as Logger, DiemLoggerBuilder, LogEntry, Writer, CHANNEL_SIZE,
+    RECENT_ENTRIES_SIZE,
 };
 pub use event::Event;
 pub use filter::{Filter, LevelFilter};
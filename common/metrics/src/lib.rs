@@ -183,6 +183,31 @@ pub fn get_all_metrics() -> HashMap<String, String> {
     all_metrics
 }
 
+/// Returns the values of the counters or gauges of the metric family `name`, each with its labels.
+/// The result is empty if no metric of the family has been registered.
+pub fn get_metric_values(name: &str) -> Vec<(HashMap<String, String>, f64)> {
+    diem_metrics_core::gather()
+        .iter()
+        .filter(|metric_family| metric_family.get_name() == name)
+        .flat_map(|metric_family| {
+            let field_type = metric_family.get_field_type();
+            metric_family.get_metric().iter().filter_map(move |m| {
+                let value = match field_type {
+                    MetricType::COUNTER => m.get_counter().get_value(),
+                    MetricType::GAUGE => m.get_gauge().get_value(),
+                    _ => return None,
+                };
+                let labels = m
+                    .get_label()
+                    .iter()
+                    .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+                    .collect();
+                Some((labels, value))
+            })
+        })
+        .collect()
+}
+
 // Launches a background thread which will periodically collect metrics
 // every interval and write them to the provided file
 pub fn dump_all_metrics_to_file_periodically<P: AsRef<Path>>(
@@ -26,6 +26,22 @@ fn gather_metrics_test() {
 }
 }
 
+#[test]
+fn get_metric_values_test() {
+    let gauge = register_int_gauge_vec!("diem_test_metric_values", "A gauge", &["kind"]).unwrap();
+    gauge.with_label_values(&["a"]).set(3);
+    gauge.with_label_values(&["b"]).set(5);
+
+    let mut values = get_metric_values("diem_test_metric_values");
+    values.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].0.get("kind").unwrap(), "a");
+    assert_approx_eq!(values[0].1, 3.0);
+    assert_approx_eq!(values[1].1, 5.0);
+
+    assert!(get_metric_values("diem_test_metric_values_unknown").is_empty());
+}
+
 // To test if the placeholder static metrics registered in Registry, a counter type metric,
 // has been successfully published to prometheus and the result gathered reflect the value change.
 #[test]
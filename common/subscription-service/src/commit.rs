@@ -0,0 +1,239 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Node-internal bus for commit events.
+//!
+//! State sync publishes a [`CommitEvent`] after every commit, whether the transactions came from
+//! consensus or from a synced chunk. Components that react to commits (mempool, indexers,
+//! metrics, ...) subscribe to the [`CommitEventBus`] instead of being handed a dedicated channel
+//! by state sync.
+//!
+//! A subscription either keeps the latest events only, dropping the oldest ones when it falls
+//! behind, or acknowledges every event: state sync then waits for the acknowledgement before
+//! answering the commit request of consensus, so a commit can't be missed silently.
+
+use anyhow::{format_err, Result};
+use channel::{
+    diem_channel::{self, Receiver},
+    message_queues::QueueStyle,
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
+use diem_metrics::IntCounterVec;
+use diem_types::{account_address::AccountAddress, transaction::Version};
+use futures::{
+    channel::{mpsc, oneshot},
+    Future,
+};
+use std::sync::Arc;
+
+/// A commit of one or more transactions to storage.
+#[derive(Clone, Debug)]
+pub struct CommitEvent {
+    /// Version of the last committed transaction.
+    pub version: Version,
+    /// Timestamp of the latest committed ledger info.
+    pub block_timestamp_usecs: u64,
+    /// Hashes of the committed transactions, in version order.
+    pub transaction_hashes: Vec<HashValue>,
+    /// Sender and sequence number of every committed user transaction.
+    pub user_transactions: Vec<(AccountAddress, u64)>,
+    /// The epoch the node moved to, if the commit ended an epoch.
+    pub new_epoch: Option<u64>,
+}
+
+/// A commit event sent to an acknowledged subscription, see
+/// [`CommitEventBus::subscribe_acknowledged`].
+#[derive(Debug)]
+pub struct CommitNotification {
+    pub event: CommitEvent,
+    /// Acknowledges the event once the subscriber has processed it.
+    pub callback: oneshot::Sender<()>,
+}
+
+enum SubscriberSender {
+    Latest(diem_channel::Sender<(), CommitEvent>),
+    Acknowledged(mpsc::Sender<CommitNotification>),
+}
+
+struct Subscriber {
+    name: String,
+    sender: SubscriberSender,
+}
+
+/// Fans out commit events to every subscriber. Cloning the bus yields a handle to the same set
+/// of subscribers, so it can be handed to the publisher and to components that subscribe later.
+#[derive(Clone, Default)]
+pub struct CommitEventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl CommitEventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes `name` to all commit events published from now on. Up to `capacity` events are
+    /// queued for the subscriber; when it falls further behind, its oldest events are dropped.
+    /// `counters` records the events queued, dequeued and dropped.
+    pub fn subscribe(
+        &self,
+        name: &str,
+        capacity: usize,
+        counters: Option<&'static IntCounterVec>,
+    ) -> Receiver<(), CommitEvent> {
+        let (sender, receiver) = diem_channel::new(QueueStyle::KLAST, capacity, counters);
+        self.add(name, SubscriberSender::Latest(sender));
+        receiver
+    }
+
+    /// Subscribes `name` to all commit events published from now on, each of which it must
+    /// acknowledge. Up to `capacity` events are queued for the subscriber; an event published
+    /// while the queue is full is not delivered, and its publication fails.
+    pub fn subscribe_acknowledged(
+        &self,
+        name: &str,
+        capacity: usize,
+    ) -> mpsc::Receiver<CommitNotification> {
+        let (sender, receiver) = mpsc::channel(capacity);
+        self.add(name, SubscriberSender::Acknowledged(sender));
+        receiver
+    }
+
+    fn add(&self, name: &str, sender: SubscriberSender) {
+        self.subscribers.lock().push(Subscriber {
+            name: name.to_string(),
+            sender,
+        });
+    }
+
+    /// Publishes `event` to every subscriber. Subscribers whose receiver was dropped are removed.
+    /// The returned future resolves once every acknowledged subscriber has acknowledged the event,
+    /// or fails with the first one that could not be sent the event or dropped it.
+    pub fn publish(&self, event: CommitEvent) -> impl Future<Output = Result<()>> + Send {
+        let mut subscribers = self.subscribers.lock();
+        let mut acks = vec![];
+        let mut failure = None;
+        let live = subscribers
+            .drain(..)
+            .filter_map(|mut subscriber| {
+                match &mut subscriber.sender {
+                    SubscriberSender::Latest(sender) => sender.push((), event.clone()).ok()?,
+                    SubscriberSender::Acknowledged(sender) => {
+                        let (callback, ack) = oneshot::channel();
+                        let notification = CommitNotification {
+                            event: event.clone(),
+                            callback,
+                        };
+                        match sender.try_send(notification) {
+                            Ok(()) => acks.push((subscriber.name.clone(), ack)),
+                            Err(error) if error.is_disconnected() => return None,
+                            Err(_) => {
+                                failure.get_or_insert_with(|| {
+                                    format_err!("{} is too far behind", subscriber.name)
+                                });
+                            }
+                        }
+                    }
+                }
+                Some(subscriber)
+            })
+            .collect();
+        *subscribers = live;
+
+        async move {
+            if let Some(error) = failure {
+                return Err(error);
+            }
+            for (name, ack) in acks {
+                ack.await
+                    .map_err(|_| format_err!("{} dropped the commit event", name))?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Names of the current subscribers.
+    pub fn subscribers(&self) -> Vec<String> {
+        self.subscribers
+            .lock()
+            .iter()
+            .map(|subscriber| subscriber.name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, StreamExt};
+
+    fn commit_event(version: Version) -> CommitEvent {
+        CommitEvent {
+            version,
+            block_timestamp_usecs: version,
+            transaction_hashes: vec![HashValue::random()],
+            user_transactions: vec![(AccountAddress::random(), 0)],
+            new_epoch: None,
+        }
+    }
+
+    #[test]
+    fn test_publish_to_all_subscribers() {
+        let bus = CommitEventBus::new();
+        let mut mempool = bus.subscribe("mempool", 10, None);
+        let indexer = bus.subscribe("indexer", 10, None);
+
+        block_on(bus.publish(commit_event(1))).unwrap();
+        assert_eq!(block_on(mempool.next()).unwrap().version, 1);
+
+        // Dropped subscribers are removed on the next publish.
+        drop(indexer);
+        block_on(bus.publish(commit_event(2))).unwrap();
+        assert_eq!(bus.subscribers(), vec!["mempool".to_string()]);
+        assert_eq!(block_on(mempool.next()).unwrap().version, 2);
+    }
+
+    #[test]
+    fn test_slow_subscriber_keeps_latest_events() {
+        let bus = CommitEventBus::new();
+        let mut subscriber = bus.subscribe("metrics", 2, None);
+        for version in 1..=3 {
+            block_on(bus.publish(commit_event(version))).unwrap();
+        }
+        assert_eq!(block_on(subscriber.next()).unwrap().version, 2);
+        assert_eq!(block_on(subscriber.next()).unwrap().version, 3);
+    }
+
+    #[test]
+    fn test_publish_waits_for_acknowledgements() {
+        let bus = CommitEventBus::new();
+        let mut mempool = bus.subscribe_acknowledged("mempool", 1);
+
+        let published = bus.publish(commit_event(1));
+        let notification = block_on(mempool.next()).unwrap();
+        assert_eq!(notification.event.version, 1);
+        notification.callback.send(()).unwrap();
+        block_on(published).unwrap();
+
+        // An event dropped without acknowledgement fails the publication.
+        let published = bus.publish(commit_event(2));
+        drop(block_on(mempool.next()).unwrap());
+        block_on(published).unwrap_err();
+    }
+
+    #[test]
+    fn test_publish_fails_when_acknowledged_subscriber_is_behind() {
+        let bus = CommitEventBus::new();
+        let mut mempool = bus.subscribe_acknowledged("mempool", 0);
+
+        // The channel holds one event per sender on top of its capacity.
+        let _first = bus.publish(commit_event(1));
+        block_on(bus.publish(commit_event(2))).unwrap_err();
+
+        // The event that could not be queued is not delivered later.
+        assert_eq!(block_on(mempool.next()).unwrap().event.version, 1);
+        drop(bus);
+        assert!(block_on(mempool.next()).is_none());
+    }
+}
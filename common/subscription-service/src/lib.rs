@@ -5,6 +5,11 @@
 
 //! Generic pub/sub service framework
 
+mod commit;
+mod reconfig;
+pub use commit::{CommitEvent, CommitEventBus, CommitNotification};
+pub use reconfig::{ReconfigBus, ReconfigHandler};
+
 use anyhow::Result;
 use channel::{
     diem_channel::{self, Receiver, Sender},
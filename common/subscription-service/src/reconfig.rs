@@ -0,0 +1,212 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Node-internal bus for on-chain config changes.
+//!
+//! State sync publishes the on-chain configs on the [`ReconfigBus`] when it starts, then after
+//! every commit that changes them. Components that depend on on-chain configs (mempool's
+//! validator, consensus, network discovery, ...) subscribe to the configs and events they care
+//! about, or register a [`ReconfigHandler`], instead of fetching the configs themselves.
+//!
+//! A subscription added after the configs were first published gets the latest ones right away,
+//! so components started after state sync need no fetch of their own. Every subscription gets the
+//! payloads in the order they were published: one that falls behind skips to the latest payload,
+//! but never gets an older payload after a newer one.
+
+use crate::ReconfigSubscription;
+use anyhow::Result;
+use channel::diem_channel::Receiver;
+use diem_infallible::Mutex;
+use diem_types::{
+    event::EventKey,
+    on_chain_config::{ConfigID, OnChainConfigPayload},
+};
+use futures::{Future, StreamExt};
+use std::{collections::HashSet, sync::Arc};
+
+/// Component reacting to on-chain config changes, see [`ReconfigBus::register`].
+pub trait ReconfigHandler: Send {
+    /// Handles the on-chain configs at registration, then after each change.
+    fn handle(&mut self, payload: OnChainConfigPayload);
+}
+
+impl<F: FnMut(OnChainConfigPayload) + Send> ReconfigHandler for F {
+    fn handle(&mut self, payload: OnChainConfigPayload) {
+        self(payload)
+    }
+}
+
+#[derive(Default)]
+struct Subscriptions {
+    /// Last payload published, `None` until state sync publishes the initial configs
+    latest: Option<OnChainConfigPayload>,
+    subscriptions: Vec<ReconfigSubscription>,
+}
+
+/// Fans out on-chain config changes to subscriptions. Cloning the bus yields a handle to the same
+/// set of subscriptions, so it can be handed to the publisher and to components that subscribe
+/// later.
+#[derive(Clone, Default)]
+pub struct ReconfigBus {
+    inner: Arc<Mutex<Subscriptions>>,
+}
+
+impl ReconfigBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes `name` to changes in `configs` and emissions of `events`.
+    pub fn subscribe(
+        &self,
+        name: &str,
+        configs: Vec<ConfigID>,
+        events: Vec<EventKey>,
+    ) -> Receiver<(), OnChainConfigPayload> {
+        let (subscription, receiver) = ReconfigSubscription::subscribe_all(name, configs, events);
+        self.add(subscription);
+        receiver
+    }
+
+    /// Adds `subscription`, handing it the latest configs if they were published. A subscription
+    /// whose receiver is gone is reported by the next publish.
+    pub fn add(&self, mut subscription: ReconfigSubscription) {
+        let mut inner = self.inner.lock();
+        if let Some(latest) = &inner.latest {
+            let _ = subscription.publish(latest.clone());
+        }
+        inner.subscriptions.push(subscription);
+    }
+
+    /// Registers `handler` for changes in `configs`. Returns the future handing it the configs,
+    /// in order, for the caller to spawn on its runtime.
+    pub fn register<H: ReconfigHandler>(
+        &self,
+        name: &str,
+        configs: Vec<ConfigID>,
+        mut handler: H,
+    ) -> impl Future<Output = ()> + Send {
+        let mut receiver = self.subscribe(name, configs, vec![]);
+        async move {
+            while let Some(payload) = receiver.next().await {
+                handler.handle(payload);
+            }
+        }
+    }
+
+    /// Publishes `payload`, the on-chain configs after a commit that emitted events with
+    /// `event_keys`, to the subscriptions to any config changed since the last payload or to any
+    /// of those events. The first payload is published to every subscription. Returns the name of
+    /// each subscription published to, with the outcome.
+    pub fn publish(
+        &self,
+        payload: OnChainConfigPayload,
+        event_keys: &HashSet<EventKey>,
+    ) -> Vec<(String, Result<()>)> {
+        let mut inner = self.inner.lock();
+        let Subscriptions {
+            latest,
+            subscriptions,
+        } = &mut *inner;
+        let changed_configs = latest.as_ref().map(|latest| {
+            payload
+                .configs()
+                .iter()
+                .filter(|(id, config)| latest.configs().get(id) != Some(config))
+                .map(|(id, _)| *id)
+                .collect::<HashSet<_>>()
+        });
+        let results = subscriptions
+            .iter_mut()
+            .filter(|subscription| {
+                changed_configs.as_ref().map_or(true, |changed_configs| {
+                    let subscribed_items = subscription.subscribed_items();
+                    !changed_configs.is_disjoint(&subscribed_items.configs)
+                        || !event_keys.is_disjoint(&subscribed_items.events)
+                })
+            })
+            .map(|subscription| {
+                let result = subscription.publish(payload.clone());
+                (subscription.name.clone(), result)
+            })
+            .collect();
+        *latest = Some(payload);
+        results
+    }
+
+    /// The latest configs published, if any.
+    pub fn latest(&self) -> Option<OnChainConfigPayload> {
+        self.inner.lock().latest.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_types::on_chain_config::{DiemVersion, OnChainConfig, VMConfig};
+    use futures::{executor::block_on, FutureExt};
+    use std::collections::HashMap;
+
+    fn payload(epoch: u64, diem_version: u8, vm_config: u8) -> OnChainConfigPayload {
+        let configs: HashMap<_, _> = vec![
+            (DiemVersion::CONFIG_ID, vec![diem_version]),
+            (VMConfig::CONFIG_ID, vec![vm_config]),
+        ]
+        .into_iter()
+        .collect();
+        OnChainConfigPayload::new(epoch, Arc::new(configs))
+    }
+
+    #[test]
+    fn test_publish_changed_configs() {
+        let bus = ReconfigBus::new();
+        let mut version = bus.subscribe("version", vec![DiemVersion::CONFIG_ID], vec![]);
+        let mut vm = bus.subscribe("vm", vec![VMConfig::CONFIG_ID], vec![]);
+
+        // The initial configs go to every subscription.
+        assert_eq!(bus.publish(payload(1, 0, 0), &HashSet::new()).len(), 2);
+        assert_eq!(block_on(version.next()).unwrap().epoch(), 1);
+        assert_eq!(block_on(vm.next()).unwrap().epoch(), 1);
+
+        let results = bus.publish(payload(2, 1, 0), &HashSet::new());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "version");
+        assert_eq!(block_on(version.next()).unwrap().epoch(), 2);
+        assert!(vm.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn test_late_subscription_gets_latest() {
+        let bus = ReconfigBus::new();
+        bus.publish(payload(1, 0, 0), &HashSet::new());
+        bus.publish(payload(2, 1, 0), &HashSet::new());
+
+        let mut late = bus.subscribe("late", vec![VMConfig::CONFIG_ID], vec![]);
+        assert_eq!(block_on(late.next()).unwrap().epoch(), 2);
+
+        // A subscription that falls behind skips to the latest payload.
+        bus.publish(payload(3, 1, 1), &HashSet::new());
+        bus.publish(payload(4, 1, 2), &HashSet::new());
+        assert_eq!(block_on(late.next()).unwrap().epoch(), 4);
+        assert!(late.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn test_register_handler() {
+        let bus = ReconfigBus::new();
+        bus.publish(payload(1, 0, 0), &HashSet::new());
+        let epochs = Arc::new(Mutex::new(vec![]));
+        let handler_epochs = epochs.clone();
+        let handler = bus.register(
+            "handler",
+            vec![DiemVersion::CONFIG_ID],
+            move |payload: OnChainConfigPayload| handler_epochs.lock().push(payload.epoch()),
+        );
+        bus.publish(payload(2, 1, 0), &HashSet::new());
+
+        // The handler runs until the bus is dropped.
+        drop(bus);
+        block_on(handler);
+        assert_eq!(*epochs.lock(), vec![2]);
+    }
+}
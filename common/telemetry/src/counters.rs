@@ -0,0 +1,24 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+use once_cell::sync::Lazy;
+
+/// Cumulative number of heartbeats, by what became of them
+pub static HEARTBEATS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_telemetry_heartbeats",
+        "Cumulative number of telemetry heartbeats",
+        &["result"] // published / failed / dropped
+    )
+    .unwrap()
+});
+
+/// Number of heartbeats awaiting publication
+pub static BUFFERED_HEARTBEATS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_telemetry_buffered_heartbeats",
+        "Number of telemetry heartbeats awaiting publication"
+    )
+    .unwrap()
+});
@@ -0,0 +1,342 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Heartbeats a node publishes to a telemetry collector, so the health of the network can be
+//! mapped without scraping nodes.
+//!
+//! Telemetry is opt-in: nothing is published unless both a collector and a signing key are
+//! configured. Heartbeats only carry the fields the configuration allows, and fields not allowed
+//! are never read. Each heartbeat is signed, so the collector can tell nodes apart and reject
+//! forgeries. The signing key may be kept in secure storage and rotated there: heartbeats are
+//! signed with its new version once the rotation is seen. Heartbeats the collector couldn't be
+//! reached for are buffered and published oldest first once it can; the oldest are dropped when
+//! the buffer is full.
+
+#![forbid(unsafe_code)]
+
+mod counters;
+
+use anyhow::Result;
+use diem_config::config::{HeartbeatField, TelemetryConfig};
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    traits::Signature,
+};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_logger::{info, warn};
+use diem_metrics::get_metric_values;
+use diem_secure_storage::OperationalKey;
+use diem_types::transaction::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use storage_interface::DbReader;
+use tokio::runtime::{Builder, Runtime};
+
+/// Time the collector has to accept a heartbeat
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Name of the signing key when it is written in the config
+const SIGNING_KEY_NAME: &str = "telemetry_signing_key";
+
+/// State of the node at a point in time. Fields the configuration doesn't allow are `None`.
+#[derive(
+    Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize, CryptoHasher, BCSCryptoHash,
+)]
+pub struct Heartbeat {
+    /// When the heartbeat was taken, in microseconds since the Unix epoch
+    pub timestamp_usecs: u64,
+    pub node_version: Option<String>,
+    pub ledger_version: Option<Version>,
+    pub epoch: Option<u64>,
+    pub peer_count: Option<u64>,
+}
+
+/// Body of the requests posted to the collector
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedHeartbeat {
+    pub heartbeat: Heartbeat,
+    /// Hex encoded public key of the node
+    pub public_key: String,
+    /// Hex encoded signature of the heartbeat, by the node
+    pub signature: String,
+}
+
+impl SignedHeartbeat {
+    pub fn new(heartbeat: Heartbeat, signing_key: &OperationalKey) -> Result<Self> {
+        let signature = signing_key.sign(&heartbeat)?;
+        Ok(Self {
+            heartbeat,
+            public_key: hex::encode(signing_key.public_key().to_bytes()),
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    /// Checks the signature of the heartbeat, and returns the public key of the node
+    pub fn verify(&self) -> Result<Ed25519PublicKey> {
+        let public_key = Ed25519PublicKey::try_from(hex::decode(&self.public_key)?.as_slice())?;
+        let signature = Ed25519Signature::try_from(hex::decode(&self.signature)?.as_slice())?;
+        signature.verify(&self.heartbeat, &public_key)?;
+        Ok(public_key)
+    }
+}
+
+struct Publisher {
+    collector_url: String,
+    signing_key: OperationalKey,
+    fields: Vec<HeartbeatField>,
+    max_buffered_heartbeats: usize,
+    node_version: String,
+    db: Arc<dyn DbReader>,
+    client: reqwest::Client,
+    /// Heartbeats not published yet, oldest first
+    buffer: VecDeque<SignedHeartbeat>,
+}
+
+impl Publisher {
+    fn new(
+        collector_url: String,
+        signing_key: OperationalKey,
+        config: &TelemetryConfig,
+        node_version: &str,
+        db: Arc<dyn DbReader>,
+    ) -> Self {
+        Self {
+            collector_url,
+            signing_key,
+            fields: config.fields.clone(),
+            max_buffered_heartbeats: config.max_buffered_heartbeats,
+            node_version: node_version.to_string(),
+            db,
+            client: reqwest::Client::builder()
+                .timeout(PUBLISH_TIMEOUT)
+                .build()
+                .expect("[telemetry] failed to create client"),
+            buffer: VecDeque::new(),
+        }
+    }
+
+    /// Takes a heartbeat, reading only the fields allowed
+    fn take_heartbeat(&self, timestamp_usecs: u64) -> Result<Heartbeat> {
+        let mut heartbeat = Heartbeat {
+            timestamp_usecs,
+            ..Heartbeat::default()
+        };
+        if self.fields.contains(&HeartbeatField::NodeVersion) {
+            heartbeat.node_version = Some(self.node_version.clone());
+        }
+        if self.fields.contains(&HeartbeatField::LedgerVersion)
+            || self.fields.contains(&HeartbeatField::Epoch)
+        {
+            let ledger_info = self.db.get_latest_ledger_info()?;
+            let ledger_info = ledger_info.ledger_info();
+            if self.fields.contains(&HeartbeatField::LedgerVersion) {
+                heartbeat.ledger_version = Some(ledger_info.version());
+            }
+            if self.fields.contains(&HeartbeatField::Epoch) {
+                heartbeat.epoch = Some(ledger_info.epoch());
+            }
+        }
+        if self.fields.contains(&HeartbeatField::PeerCount) {
+            heartbeat.peer_count = Some(
+                get_metric_values("diem_connections")
+                    .into_iter()
+                    .map(|(_labels, value)| value as u64)
+                    .sum(),
+            );
+        }
+        Ok(heartbeat)
+    }
+
+    /// Buffers `heartbeat`, dropping the oldest heartbeat if the buffer is full
+    fn buffer(&mut self, heartbeat: SignedHeartbeat) {
+        if self.max_buffered_heartbeats == 0 {
+            counters::HEARTBEATS.with_label_values(&["dropped"]).inc();
+            return;
+        }
+        while self.buffer.len() >= self.max_buffered_heartbeats {
+            self.buffer.pop_front();
+            counters::HEARTBEATS.with_label_values(&["dropped"]).inc();
+        }
+        self.buffer.push_back(heartbeat);
+        counters::BUFFERED_HEARTBEATS.set(self.buffer.len() as i64);
+    }
+
+    /// Publishes the buffered heartbeats oldest first, until the collector fails to accept one
+    async fn flush(&mut self) {
+        while let Some(heartbeat) = self.buffer.front() {
+            let result = self
+                .client
+                .post(&self.collector_url)
+                .json(heartbeat)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(err) = result {
+                counters::HEARTBEATS.with_label_values(&["failed"]).inc();
+                warn!(
+                    buffered = self.buffer.len(),
+                    "Failed to publish heartbeat: {}", err
+                );
+                break;
+            }
+            counters::HEARTBEATS.with_label_values(&["published"]).inc();
+            self.buffer.pop_front();
+        }
+        counters::BUFFERED_HEARTBEATS.set(self.buffer.len() as i64);
+    }
+
+    async fn run(mut self, interval: Duration) {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            let timestamp_usecs = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("now is after the Unix epoch")
+                .as_micros() as u64;
+            // the key may have been rotated in storage, e.g. by the key manager
+            if let Err(err) = self.signing_key.refresh() {
+                warn!("Failed to read telemetry signing key: {}", err);
+            }
+            let heartbeat = self
+                .take_heartbeat(timestamp_usecs)
+                .and_then(|heartbeat| SignedHeartbeat::new(heartbeat, &self.signing_key));
+            match heartbeat {
+                Ok(heartbeat) => self.buffer(heartbeat),
+                Err(err) => warn!("Failed to take heartbeat: {}", err),
+            }
+            self.flush().await;
+        }
+    }
+}
+
+/// Starts publishing heartbeats if `config` enables telemetry. `node_version` is the version of
+/// the node software heartbeats report.
+pub fn start_telemetry(
+    config: &TelemetryConfig,
+    node_version: &str,
+    db: Arc<dyn DbReader>,
+) -> Option<Runtime> {
+    let (collector_url, signing_key) = match (&config.collector_url, &config.signing_key) {
+        (Some(collector_url), Some(signing_key)) => (
+            collector_url.clone(),
+            signing_key
+                .load(SIGNING_KEY_NAME)
+                .expect("[telemetry] failed to open signing key"),
+        ),
+        _ => return None,
+    };
+    signing_key.on_rotation(|public_key| {
+        info!(
+            public_key = public_key.to_string(),
+            "Telemetry signing key rotated."
+        )
+    });
+    let publisher = Publisher::new(collector_url, signing_key, config, node_version, db);
+
+    let runtime = Builder::new_multi_thread()
+        .thread_name("telemetry")
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .expect("[telemetry] failed to create runtime");
+    runtime.spawn(publisher.run(Duration::from_millis(config.interval_ms)));
+    info!("Telemetry publisher spawned.");
+    Some(runtime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use rand::{rngs::StdRng, SeedableRng};
+    use storage_interface::mock::MockDbReader;
+
+    fn signing_key() -> OperationalKey {
+        OperationalKey::from_private_key(
+            SIGNING_KEY_NAME,
+            Ed25519PrivateKey::generate(&mut StdRng::from_seed([0u8; 32])),
+        )
+    }
+
+    fn new_publisher(fields: Vec<HeartbeatField>, max_buffered_heartbeats: usize) -> Publisher {
+        let config = TelemetryConfig {
+            fields,
+            max_buffered_heartbeats,
+            ..TelemetryConfig::default()
+        };
+        Publisher::new(
+            "http://127.0.0.1:1".to_string(),
+            signing_key(),
+            &config,
+            "1.2.3",
+            Arc::new(MockDbReader),
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signing_key = signing_key();
+        let heartbeat = Heartbeat {
+            timestamp_usecs: 42,
+            node_version: Some("1.2.3".to_string()),
+            ledger_version: Some(100),
+            epoch: Some(2),
+            peer_count: Some(5),
+        };
+        let signed = SignedHeartbeat::new(heartbeat, &signing_key).unwrap();
+        assert_eq!(signed.verify().unwrap(), signing_key.public_key());
+
+        let mut tampered = signed.clone();
+        tampered.heartbeat.ledger_version = Some(101);
+        assert!(tampered.verify().is_err());
+
+        let mut forged = signed;
+        let other_key = Ed25519PrivateKey::generate(&mut StdRng::from_seed([1u8; 32]));
+        forged.public_key = hex::encode(other_key.public_key().to_bytes());
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn test_fields_allowlist() {
+        // the mock storage panics if read: the ledger must not be read unless allowed
+        let publisher = new_publisher(vec![HeartbeatField::NodeVersion], 1);
+        assert_eq!(
+            publisher.take_heartbeat(42).unwrap(),
+            Heartbeat {
+                timestamp_usecs: 42,
+                node_version: Some("1.2.3".to_string()),
+                ..Heartbeat::default()
+            }
+        );
+
+        let publisher = new_publisher(vec![], 1);
+        assert_eq!(
+            publisher.take_heartbeat(42).unwrap(),
+            Heartbeat {
+                timestamp_usecs: 42,
+                ..Heartbeat::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_buffer_drops_oldest() {
+        let mut publisher = new_publisher(vec![], 2);
+        for timestamp_usecs in 0..3 {
+            let heartbeat = publisher.take_heartbeat(timestamp_usecs).unwrap();
+            let heartbeat = SignedHeartbeat::new(heartbeat, &publisher.signing_key).unwrap();
+            publisher.buffer(heartbeat);
+        }
+        let buffered: Vec<_> = publisher
+            .buffer
+            .iter()
+            .map(|heartbeat| heartbeat.heartbeat.timestamp_usecs)
+            .collect();
+        assert_eq!(buffered, vec![1, 2]);
+    }
+}
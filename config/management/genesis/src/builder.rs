@@ -0,0 +1,265 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Composes a genesis transaction in-process from validator configs, initial balances, framework
+//! modules and chain parameters, without going through shared storage and the genesis commands.
+
+use crate::waypoint::extract_waypoint;
+use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_management::error::Error;
+use diem_transaction_builder::stdlib as transaction_builder;
+use diem_types::{
+    account_address,
+    chain_id::ChainId,
+    on_chain_config::VMPublishingOption,
+    transaction::{authenticator::AuthenticationKey, Transaction, WriteSetPayload},
+    waypoint::Waypoint,
+};
+use ol_types::genesis_proof::GenesisMiningProof;
+use vm_genesis::{InitialBalance, OperatorAssignment, OperatorRegistration};
+
+/// Everything genesis needs to know about one validator: its owner, its operator and the config
+/// the operator registers on the owner's behalf.
+#[derive(Clone, Debug)]
+pub struct GenesisValidator {
+    pub owner_name: Vec<u8>,
+    pub owner_key: Ed25519PublicKey,
+    pub operator_name: Vec<u8>,
+    pub operator_key: Ed25519PublicKey,
+    pub consensus_key: Ed25519PublicKey,
+    /// BCS-encoded (and, for production networks, encrypted) validator network addresses, as
+    /// produced by the `validator-config` command.
+    pub validator_network_addresses: Vec<u8>,
+    /// BCS-encoded full node network addresses.
+    pub fullnode_network_addresses: Vec<u8>,
+    pub proof: GenesisMiningProof,
+}
+
+impl GenesisValidator {
+    fn operator_assignment(&self) -> OperatorAssignment {
+        let operator_account = account_address::from_public_key(&self.operator_key);
+        let set_operator_script =
+            transaction_builder::encode_set_validator_operator_script_function(
+                self.operator_name.clone(),
+                operator_account,
+            )
+            .into_script_function();
+        (
+            Some(self.owner_key.clone()),
+            self.owner_name.clone(),
+            set_operator_script,
+            self.proof.clone(),
+            operator_account,
+        )
+    }
+
+    fn operator_registration(&self) -> OperatorRegistration {
+        let owner_account = account_address::from_public_key(&self.owner_key);
+        let register_script =
+            transaction_builder::encode_register_validator_config_script_function(
+                owner_account,
+                self.consensus_key.to_bytes().to_vec(),
+                self.validator_network_addresses.clone(),
+                self.fullnode_network_addresses.clone(),
+            )
+            .into_script_function();
+        (
+            self.operator_key.clone(),
+            self.operator_name.clone(),
+            register_script,
+            account_address::from_public_key(&self.operator_key),
+        )
+    }
+}
+
+/// Builds a genesis transaction and its waypoint.
+///
+/// ```ignore
+/// let (genesis, waypoint) = GenesisBuilder::new(ChainId::test())
+///     .add_validator(validator)
+///     .build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct GenesisBuilder {
+    chain_id: ChainId,
+    diem_root_key: Option<Ed25519PublicKey>,
+    validators: Vec<GenesisValidator>,
+    initial_balances: Vec<InitialBalance>,
+    framework_modules: Vec<Vec<u8>>,
+    vm_publishing_option: VMPublishingOption,
+}
+
+impl GenesisBuilder {
+    /// Starts from the compiled framework release with an open publishing policy, matching the
+    /// genesis command.
+    pub fn new(chain_id: ChainId) -> Self {
+        Self {
+            chain_id,
+            diem_root_key: None,
+            validators: Vec::new(),
+            initial_balances: Vec::new(),
+            framework_modules: diem_framework_releases::current_module_blobs().to_vec(),
+            vm_publishing_option: VMPublishingOption::open(),
+        }
+    }
+
+    pub fn diem_root_key(mut self, key: Ed25519PublicKey) -> Self {
+        self.diem_root_key = Some(key);
+        self
+    }
+
+    pub fn add_validator(mut self, validator: GenesisValidator) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    /// Creates the account for `auth_key` at genesis, if needed, and mints `amount` GAS into it.
+    pub fn add_balance(mut self, auth_key: AuthenticationKey, amount: u64) -> Self {
+        self.initial_balances.push((auth_key, amount));
+        self
+    }
+
+    /// Replaces the framework modules published at genesis, e.g. with a freshly compiled
+    /// framework.
+    pub fn framework_modules(mut self, modules: Vec<Vec<u8>>) -> Self {
+        self.framework_modules = modules;
+        self
+    }
+
+    pub fn vm_publishing_option(mut self, option: VMPublishingOption) -> Self {
+        self.vm_publishing_option = option;
+        self
+    }
+
+    pub fn build(&self) -> Result<(Transaction, Waypoint), Error> {
+        if self.validators.is_empty() {
+            return Err(Error::CommandArgumentError(
+                "genesis requires at least one validator".into(),
+            ));
+        }
+        if !self.initial_balances.is_empty()
+            && !vm_genesis::supports_initial_balances(&self.framework_modules)
+        {
+            return Err(Error::CommandArgumentError(
+                "the framework modules cannot seed initial balances, use a framework release \
+                 that includes DiemAccount::genesis_fund_account"
+                    .into(),
+            ));
+        }
+
+        let operator_assignments: Vec<_> = self
+            .validators
+            .iter()
+            .map(GenesisValidator::operator_assignment)
+            .collect();
+        let operator_registrations: Vec<_> = self
+            .validators
+            .iter()
+            .map(GenesisValidator::operator_registration)
+            .collect();
+
+        let change_set = vm_genesis::encode_genesis_change_set(
+            self.diem_root_key.as_ref(),
+            None,
+            &operator_assignments,
+            &operator_registrations,
+            &self.initial_balances,
+            &self.framework_modules,
+            self.vm_publishing_option.clone(),
+            self.chain_id,
+        );
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(change_set));
+        let waypoint = extract_waypoint(genesis.clone())?;
+        Ok((genesis, waypoint))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_config::config::RocksdbConfig;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_temppath::TempPath;
+    use diem_types::{
+        account_config::{from_currency_code_string, GAS_NAME},
+        account_state::AccountState,
+    };
+    use diem_vm::DiemVM;
+    use diemdb::DiemDB;
+    use executor::db_bootstrapper;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::convert::TryFrom;
+    use storage_interface::DbReaderWriter;
+
+    fn validator(rng: &mut StdRng, name: &str) -> GenesisValidator {
+        GenesisValidator {
+            owner_name: name.as_bytes().to_vec(),
+            owner_key: Ed25519PrivateKey::generate(rng).public_key(),
+            operator_name: format!("{}-operator", name).into_bytes(),
+            operator_key: Ed25519PrivateKey::generate(rng).public_key(),
+            consensus_key: Ed25519PrivateKey::generate(rng).public_key(),
+            validator_network_addresses: bcs::to_bytes(&[0u8; 0]).unwrap(),
+            fullnode_network_addresses: bcs::to_bytes(&[0u8; 0]).unwrap(),
+            proof: GenesisMiningProof::default(),
+        }
+    }
+
+    #[test]
+    fn test_build_genesis() {
+        let mut rng = StdRng::from_seed([7u8; 32]);
+        let builder = GenesisBuilder::new(ChainId::test())
+            .add_validator(validator(&mut rng, "alice"))
+            .add_validator(validator(&mut rng, "bob"));
+
+        let (genesis, waypoint) = builder.build().unwrap();
+        assert_eq!(waypoint.version(), 0);
+        assert_eq!(extract_waypoint(genesis).unwrap(), waypoint);
+
+        // The same inputs always produce the same genesis.
+        assert_eq!(builder.build().unwrap().1, waypoint);
+    }
+
+    #[test]
+    fn test_build_genesis_with_initial_balances() {
+        let mut rng = StdRng::from_seed([7u8; 32]);
+        let account_key = Ed25519PrivateKey::generate(&mut rng).public_key();
+        let auth_key = AuthenticationKey::ed25519(&account_key);
+        let (genesis, waypoint) = GenesisBuilder::new(ChainId::test())
+            .add_validator(validator(&mut rng, "alice"))
+            .add_balance(auth_key, 1_000_000)
+            .build()
+            .unwrap();
+
+        let path = TempPath::new();
+        let db_rw = DbReaderWriter::new(
+            DiemDB::open(&path, false, None, RocksdbConfig::default()).unwrap(),
+        );
+        db_bootstrapper::maybe_bootstrap::<DiemVM>(&db_rw, &genesis, waypoint).unwrap();
+
+        let blob = db_rw
+            .reader
+            .get_latest_account_state(auth_key.derived_address())
+            .unwrap()
+            .expect("funded account exists after genesis");
+        let balances = AccountState::try_from(&blob)
+            .unwrap()
+            .get_balance_resources()
+            .unwrap();
+        let gas = from_currency_code_string(GAS_NAME).unwrap();
+        assert_eq!(balances[&gas].coin(), 1_000_000);
+    }
+
+    #[test]
+    fn test_build_genesis_rejects_invalid_inputs() {
+        let mut rng = StdRng::from_seed([7u8; 32]);
+        GenesisBuilder::new(ChainId::test()).build().unwrap_err();
+
+        let account_key = Ed25519PrivateKey::generate(&mut rng).public_key();
+        GenesisBuilder::new(ChainId::test())
+            .add_validator(validator(&mut rng, "alice"))
+            .add_balance(AuthenticationKey::ed25519(&account_key), 1_000_000)
+            .framework_modules(vec![])
+            .build()
+            .unwrap_err();
+    }
+}
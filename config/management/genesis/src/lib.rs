@@ -13,6 +13,7 @@ mod validator_operator;
 mod verify;
 
 //////// 0L ////////
+pub mod builder;
 pub mod init;
 pub mod ol_node_files;
 mod ol_mining;
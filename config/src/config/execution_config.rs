@@ -23,6 +23,13 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// How often to pre-execute the transactions at the top of mempool to warm the block
+    /// executor, 0 disables pre-execution.
+    pub pre_execution_interval_ms: u64,
+    /// Maximum number of transactions pulled from mempool for every pre-execution round.
+    pub pre_execution_batch_size: u64,
+    /// Maximum number of pre-executed transactions whose reads are kept.
+    pub pre_execution_cache_size: usize,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -57,6 +64,9 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            pre_execution_interval_ms: 0,
+            pre_execution_batch_size: 100,
+            pre_execution_cache_size: 10_000,
         }
     }
 }
@@ -1,9 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{keys::ConfigKey, utils};
+use diem_crypto::ed25519::Ed25519PrivateKey;
+use diem_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -14,6 +16,173 @@ pub struct JsonRpcConfig {
     pub content_length_limit: usize,
     pub tls_cert_path: Option<String>,
     pub tls_key_path: Option<String>,
+    /// How long a read carrying `min_version` waits for storage to catch up before failing
+    pub min_version_wait_ms: u64,
+    /// TOML file naming abort codes the framework error descriptions don't cover, used to
+    /// explain Move aborts in VM statuses
+    pub abort_codes_path: Option<PathBuf>,
+    /// Consumers identified by the API key they send, and their rate limits
+    pub api_keys: ApiKeysConfig,
+    /// Key signing the responses to requests carrying a `diem_client_nonce`, so clients holding
+    /// its public key can tell responses relayed untouched from forged or replayed ones
+    pub response_signing_key: Option<ConfigKey<Ed25519PrivateKey>>,
+    /// URLs notified when transactions submitted through this node are accepted into mempool,
+    /// committed or expired
+    pub webhooks: WebhooksConfig,
+    /// Budgets of the costly queries of each client address, so clients scanning the history
+    /// can't starve the others
+    pub query_budgets: QueryBudgetsConfig,
+    /// Compression of the responses to clients accepting it
+    pub response_compression: ResponseCompressionConfig,
+    /// Watches of account resources, whose changes are posted to the URL the client registered
+    /// them with
+    pub resource_watches: ResourceWatchesConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResourceWatchesConfig {
+    /// Whether clients may register watches. Nodes serving untrusted clients should leave this
+    /// off, as the node then posts to any URL.
+    pub enabled: bool,
+    /// Number of watches registered at most
+    pub max_watches: usize,
+    /// Number of resources a single watch covers at most
+    pub max_resources_per_watch: usize,
+}
+
+impl Default for ResourceWatchesConfig {
+    fn default() -> ResourceWatchesConfig {
+        ResourceWatchesConfig {
+            enabled: false,
+            max_watches: 1_000,
+            max_resources_per_watch: 100,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResponseCompressionConfig {
+    /// Size of the responses from which they are compressed, when the client accepts it. Smaller
+    /// responses are sent as is, compressing them saving little for the CPU spent. Responses are
+    /// never compressed when not set.
+    pub min_response_bytes: Option<usize>,
+    /// gzip compression level, from 0 (none) to 9 (smallest)
+    pub gzip_level: u32,
+    /// zstd compression level, from 1 to 21 (smallest)
+    pub zstd_level: i32,
+}
+
+impl Default for ResponseCompressionConfig {
+    fn default() -> ResponseCompressionConfig {
+        ResponseCompressionConfig {
+            min_response_bytes: Some(16 * 1024),
+            gzip_level: 6,
+            zstd_level: 3,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryBudgetsConfig {
+    /// Credits each client address may spend per second on queries of ranges of transactions,
+    /// one credit per item requested. Unlimited when not set.
+    pub range_scan_credits_per_sec: Option<usize>,
+    /// Credits each client address may spend per second on queries of events, one credit per
+    /// item requested. Unlimited when not set.
+    pub event_scan_credits_per_sec: Option<usize>,
+    /// How long a query over its client's budget is queued waiting for credits, before it's
+    /// rejected
+    pub max_queue_wait_ms: u64,
+}
+
+impl Default for QueryBudgetsConfig {
+    fn default() -> QueryBudgetsConfig {
+        QueryBudgetsConfig {
+            range_scan_credits_per_sec: None,
+            event_scan_credits_per_sec: None,
+            max_queue_wait_ms: 1_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksConfig {
+    /// URLs notified of every transaction submitted through this node
+    pub sinks: Vec<String>,
+    /// Whether a submission may carry a `callback_url` notified of that transaction only. Nodes
+    /// serving untrusted clients should leave this off, as the node then posts to any URL.
+    pub allow_client_callbacks: bool,
+    /// How often storage is checked for the outcome of accepted transactions
+    pub poll_interval_ms: u64,
+    /// Number of accepted transactions awaiting their outcome at most. Transactions accepted
+    /// beyond it are only notified as accepted.
+    pub max_watched_transactions: usize,
+}
+
+impl WebhooksConfig {
+    pub fn enabled(&self) -> bool {
+        !self.sinks.is_empty() || self.allow_client_callbacks
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> WebhooksConfig {
+        WebhooksConfig {
+            sinks: vec![],
+            allow_client_callbacks: false,
+            poll_interval_ms: 1_000,
+            max_watched_transactions: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiKeysConfig {
+    /// Limits of the requests sent without an API key. Such requests are rejected when this is
+    /// not set.
+    pub anonymous: Option<ApiKeyConfig>,
+    /// Consumers keyed by the API key they send in the `X-Diem-Api-Key` header. Requests with
+    /// any other API key are rejected.
+    pub keys: BTreeMap<String, ApiKeyConfig>,
+}
+
+impl Default for ApiKeysConfig {
+    fn default() -> ApiKeysConfig {
+        ApiKeysConfig {
+            anonymous: Some(ApiKeyConfig {
+                name: "anonymous".to_string(),
+                requests_per_sec: None,
+                credits_per_sec: None,
+                allowed_senders: None,
+            }),
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Name the consumer's usage is reported under
+    pub name: String,
+    /// Requests served per second, a batch counting as many requests as it contains. Unlimited
+    /// when not set.
+    #[serde(default)]
+    pub requests_per_sec: Option<usize>,
+    /// Credits spent per second. Queries of ranges spend one credit per item requested, other
+    /// requests one credit. Unlimited when not set.
+    #[serde(default)]
+    pub credits_per_sec: Option<usize>,
+    /// Senders of the transactions the consumer may submit, e.g. the accounts of its organization
+    /// on a permissioned network. Submissions signed by any other sender are rejected before
+    /// reaching mempool. Any sender may be submitted when not set.
+    #[serde(default)]
+    pub allowed_senders: Option<Vec<AccountAddress>>,
 }
 
 pub const DEFAULT_JSON_RPC_ADDRESS: &str = "127.0.0.1";
@@ -22,6 +191,7 @@ pub const DEFAULT_BATCH_SIZE_LIMIT: u16 = 20;
 pub const DEFAULT_PAGE_SIZE_LIMIT: u16 = 1000;
 /// 0L increased the json-RPC length so we can include stdlib in upgrade oracle.
 pub const DEFAULT_CONTENT_LENGTH_LIMIT: usize = 4096 * 1024; //////// 0L ////////
+pub const DEFAULT_MIN_VERSION_WAIT_MS: u64 = 1_000;
 
 impl Default for JsonRpcConfig {
     fn default() -> JsonRpcConfig {
@@ -34,6 +204,14 @@ impl Default for JsonRpcConfig {
             content_length_limit: DEFAULT_CONTENT_LENGTH_LIMIT,
             tls_cert_path: None,
             tls_key_path: None,
+            min_version_wait_ms: DEFAULT_MIN_VERSION_WAIT_MS,
+            abort_codes_path: None,
+            api_keys: ApiKeysConfig::default(),
+            response_signing_key: None,
+            webhooks: WebhooksConfig::default(),
+            query_budgets: QueryBudgetsConfig::default(),
+            response_compression: ResponseCompressionConfig::default(),
+            resource_watches: ResourceWatchesConfig::default(),
         }
     }
 }
@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_logger::{Level, CHANNEL_SIZE};
+use diem_logger::{Level, CHANNEL_SIZE, RECENT_ENTRIES_SIZE};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -13,6 +13,8 @@ pub struct LoggerConfig {
     pub is_async: bool,
     // The default logging level for slog.
     pub level: Level,
+    // Number of recent log entries kept in memory for the debug interface.
+    pub recent_entries_size: usize,
 }
 
 impl Default for LoggerConfig {
@@ -21,6 +23,7 @@ impl Default for LoggerConfig {
             chan_size: CHANNEL_SIZE,
             is_async: true,
             level: Level::Info,
+            recent_entries_size: RECENT_ENTRIES_SIZE,
         }
     }
 }
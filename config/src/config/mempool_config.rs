@@ -1,6 +1,8 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::config::{invariant, Error};
+use diem_types::{account_address::AccountAddress, transaction::TransactionPayload};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -10,6 +12,13 @@ pub struct MempoolConfig {
     pub capacity: usize,
     /// How many txs can each user have in the mempool at a given time.
     pub capacity_per_user: usize,
+    /// How many client-submitted txs of a user at `capacity_per_user` are held back locally and
+    /// fed into mempool as the user's earlier txs commit. 0 rejects txs over the cap instead.
+    pub overflow_capacity_per_user: usize,
+    /// Which of two different txs with the same sender and sequence number is kept, when the
+    /// second arrives while the first is in mempool. A tx only raising the gas price of the first
+    /// replaces it under any policy.
+    pub conflict_resolution: ConflictResolution,
     // a threshold for fullnodes to determine which peers to broadcast to.
     // peers which are go over this threshold, will receive broadcasts.
     // number of failovers to broadcast to when the primary network is alive
@@ -18,40 +27,339 @@ pub struct MempoolConfig {
     pub max_broadcasts_per_peer: usize,
     // how often to snapshot the mempool for analytics purposes.
     pub mempool_snapshot_interval_secs: u64,
-    // how long to wait for a peer after a broadcast was submitted, before we mark it as unacknowledged.
-    pub shared_mempool_ack_timeout_ms: u64,
-    // if peer_manager is in backoff mode mempool/src/shared_mempool/peer_manager.rs
-    // this is the base interval for backing off.
-    pub shared_mempool_backoff_interval_ms: u64,
-
-    // size of batch from mempool timeline to broadcast to peers.
-    pub shared_mempool_batch_size: usize,
-    // Number of workers to be spawned to receive inbound shared mempool broadcasts.
-    pub shared_mempool_max_concurrent_inbound_syncs: usize,
-    // the default interval to execute shared mempool broadcasts to peers.
-    // this is overriden when peer is in backoff mode.
-    pub shared_mempool_tick_interval_ms: u64,
+    /// Broadcasts of transactions between the mempools of peers.
+    pub shared_mempool: SharedMempoolConfig,
     /// when a transaction gets automatically garbage collected by system. Different than user tx expiry which has separate GC
     pub system_transaction_timeout_secs: u64,
-    /// tick interval for system GC.
+    /// tick interval for system GC while mempool is empty. The interval shrinks as mempool fills
+    /// up, down to `system_transaction_gc_min_interval_ms` when it is full.
     pub system_transaction_gc_interval_ms: u64,
+    /// tick interval for system GC while mempool is full. Setting it to
+    /// `system_transaction_gc_interval_ms` disables adaptation.
+    pub system_transaction_gc_min_interval_ms: u64,
+    /// Interleave the transactions of different senders round-robin in blocks proposed to
+    /// consensus and in broadcast batches, so a prolific sender can't monopolize them under load.
+    /// Blocks are then built from the whole queue rather than from its highest ranked transactions.
+    pub sender_fair_ordering: bool,
+    /// Transactions expiring less than this many seconds after they are received are rejected,
+    /// as they would likely expire before they're committed. 0 accepts any expiration time.
+    pub min_expiration_window_secs: u64,
+    /// Transactions expiring more than this many seconds after they are received are rejected,
+    /// so they can't occupy mempool for long. 0 accepts any expiration time.
+    pub max_expiration_window_secs: u64,
+    /// Senders mempool allocates sequence numbers to through the `allocate_sequence_number`
+    /// JSON-RPC method, e.g. the accounts of high-throughput submitters. Sequence numbers are
+    /// allocated to no sender when empty.
+    pub sequence_number_allocation_senders: Vec<AccountAddress>,
+    /// Time each sequence number allocated to a sender is reserved for. A number that hasn't
+    /// reached mempool once it passes is allocated again, so it doesn't leave a gap.
+    pub sequence_number_allocation_ttl_secs: u64,
+    /// Minimum gas unit price of the transactions accepted, by payload type.
+    pub min_gas_unit_price: MinGasUnitPriceConfig,
+
+    // Settings of `shared_mempool` as spelled at the top level by config files written before it
+    // was introduced. `sanitize` moves them into it.
+    #[serde(skip_serializing)]
+    pub shared_mempool_ack_timeout_ms: Option<u64>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_backoff_interval_ms: Option<u64>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_batch_size: Option<usize>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_min_batch_size: Option<usize>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_max_batch_size: Option<usize>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_max_concurrent_inbound_syncs: Option<usize>,
+    #[serde(skip_serializing)]
+    pub shared_mempool_tick_interval_ms: Option<u64>,
 }
 
 impl Default for MempoolConfig {
     fn default() -> MempoolConfig {
         MempoolConfig {
-            shared_mempool_tick_interval_ms: 5_000, //////// 0L //////// 
-            shared_mempool_backoff_interval_ms: 3_000, //////// 0L ////////
-            shared_mempool_batch_size: 100,
-            shared_mempool_ack_timeout_ms: 20_000, ///////// 0L /////////
-            shared_mempool_max_concurrent_inbound_syncs: 10,  ///////// 0L /////////
+            shared_mempool: SharedMempoolConfig::default(),
             max_broadcasts_per_peer: 5, //////// 0L ////////
             mempool_snapshot_interval_secs: 180,
             capacity: 100, ///////// 0L //////// Reduce size of mempool due to VDF cost.
             capacity_per_user: 1, // no reason for a given user to be ablet to submit more than tree txs to mempool.
+            overflow_capacity_per_user: 0,
+            conflict_resolution: ConflictResolution::FirstWins,
             default_failovers: 3,
             system_transaction_timeout_secs: 1000, //////// 0L //////// transacitons should timeout under this time
             system_transaction_gc_interval_ms: 1000, /////// 0L //////// increase rate of GC
+            system_transaction_gc_min_interval_ms: 100,
+            sender_fair_ordering: false,
+            min_expiration_window_secs: 0,
+            max_expiration_window_secs: 0,
+            sequence_number_allocation_senders: vec![],
+            sequence_number_allocation_ttl_secs: 30,
+            min_gas_unit_price: MinGasUnitPriceConfig::default(),
+            shared_mempool_ack_timeout_ms: None,
+            shared_mempool_backoff_interval_ms: None,
+            shared_mempool_batch_size: None,
+            shared_mempool_min_batch_size: None,
+            shared_mempool_max_batch_size: None,
+            shared_mempool_max_concurrent_inbound_syncs: None,
+            shared_mempool_tick_interval_ms: None,
+        }
+    }
+}
+
+impl MempoolConfig {
+    /// Moves the top-level `shared_mempool_*` settings of older config files into
+    /// `shared_mempool`, then validates the config.
+    pub fn sanitize(&mut self) -> Result<(), Error> {
+        invariant(
+            self.max_expiration_window_secs == 0
+                || self.min_expiration_window_secs <= self.max_expiration_window_secs,
+            format!(
+                "mempool.min_expiration_window_secs ({}) must not exceed \
+                 max_expiration_window_secs ({})",
+                self.min_expiration_window_secs, self.max_expiration_window_secs
+            ),
+        )?;
+        invariant(
+            self.sequence_number_allocation_senders.is_empty()
+                || self.sequence_number_allocation_ttl_secs > 0,
+            "mempool.sequence_number_allocation_ttl_secs must be positive".into(),
+        )?;
+
+        let shared_mempool = &mut self.shared_mempool;
+        if let Some(ack_timeout_ms) = self.shared_mempool_ack_timeout_ms.take() {
+            shared_mempool.ack_timeout_ms = ack_timeout_ms;
+        }
+        if let Some(backoff_interval_ms) = self.shared_mempool_backoff_interval_ms.take() {
+            shared_mempool.backoff_interval_ms = backoff_interval_ms;
+        }
+        if let Some(batch_size) = self.shared_mempool_batch_size.take() {
+            shared_mempool.batch_size = batch_size;
+        }
+        if let Some(min_batch_size) = self.shared_mempool_min_batch_size.take() {
+            shared_mempool.min_batch_size = min_batch_size;
+        }
+        if let Some(max_batch_size) = self.shared_mempool_max_batch_size.take() {
+            shared_mempool.max_batch_size = max_batch_size;
+        }
+        if let Some(max_concurrent_inbound_syncs) =
+            self.shared_mempool_max_concurrent_inbound_syncs.take()
+        {
+            shared_mempool.max_concurrent_inbound_syncs = max_concurrent_inbound_syncs;
+        }
+        if let Some(tick_interval_ms) = self.shared_mempool_tick_interval_ms.take() {
+            shared_mempool.tick_interval_ms = tick_interval_ms;
+        }
+        shared_mempool.validate()
+    }
+}
+
+/// Policy deciding which of two conflicting txs, with the same sender and sequence number, mempool
+/// keeps.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// The tx that arrived first is kept.
+    FirstWins,
+    /// The tx with the higher gas price is kept, the first one on a tie.
+    GasPriceWins,
+    /// A tx submitted by a client replaces one received from a peer. Otherwise the first is kept.
+    ClientWins,
+}
+
+impl ConflictResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictResolution::FirstWins => "first_wins",
+            ConflictResolution::GasPriceWins => "gas_price_wins",
+            ConflictResolution::ClientWins => "client_wins",
+        }
+    }
+}
+
+/// Minimum gas unit price of the transactions mempool accepts, by payload type, so publishing
+/// modules, which is far more expensive to validate and execute than running a script, can be
+/// priced higher. Transactions paying less are rejected before they are validated. 0 accepts any
+/// price.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MinGasUnitPriceConfig {
+    /// Transactions running a script or a script function.
+    pub script: u64,
+    /// Transactions publishing a module or a module bundle.
+    pub module: u64,
+    /// Write set transactions.
+    pub write_set: u64,
+}
+
+impl MinGasUnitPriceConfig {
+    /// Minimum gas unit price of the transactions with `payload`.
+    pub fn for_payload(&self, payload: &TransactionPayload) -> u64 {
+        match payload {
+            TransactionPayload::Script(_) | TransactionPayload::ScriptFunction(_) => self.script,
+            TransactionPayload::Module(_) | TransactionPayload::ModuleBundle(_) => self.module,
+            TransactionPayload::WriteSet(_) => self.write_set,
+        }
+    }
+}
+
+/// Broadcasts of transactions between the mempools of peers. The broadcast intervals and batch
+/// sizes can be changed while the node runs, see `reload`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SharedMempoolConfig {
+    /// Interval between two broadcasts to a peer.
+    pub tick_interval_ms: u64,
+    /// Interval between two broadcasts to a peer in backoff mode, i.e. whose mempool is full.
+    pub backoff_interval_ms: u64,
+    /// Time a peer has to acknowledge a broadcast before it's sent again.
+    pub ack_timeout_ms: u64,
+    /// Number of transactions first broadcast at once to a peer. It then adapts to the peer's ACK
+    /// round-trip time and retry ratio, within `min_batch_size` and `max_batch_size`. Setting
+    /// both bounds to `batch_size` disables adaptation.
+    pub batch_size: usize,
+    pub min_batch_size: usize,
+    pub max_batch_size: usize,
+    /// Number of workers processing the broadcasts received from peers.
+    pub max_concurrent_inbound_syncs: usize,
+}
+
+impl Default for SharedMempoolConfig {
+    fn default() -> SharedMempoolConfig {
+        SharedMempoolConfig {
+            tick_interval_ms: 5_000,    //////// 0L ////////
+            backoff_interval_ms: 3_000, //////// 0L ////////
+            ack_timeout_ms: 20_000,     //////// 0L ////////
+            batch_size: 100,
+            min_batch_size: 10,
+            max_batch_size: 500,
+            max_concurrent_inbound_syncs: 10, //////// 0L ////////
+        }
+    }
+}
+
+impl SharedMempoolConfig {
+    pub fn validate(&self) -> Result<(), Error> {
+        invariant(
+            self.tick_interval_ms > 0,
+            "shared_mempool.tick_interval_ms must be positive".into(),
+        )?;
+        invariant(
+            self.backoff_interval_ms > 0,
+            "shared_mempool.backoff_interval_ms must be positive".into(),
+        )?;
+        invariant(
+            0 < self.min_batch_size
+                && self.min_batch_size <= self.batch_size
+                && self.batch_size <= self.max_batch_size,
+            format!(
+                "shared_mempool batch sizes must satisfy 0 < min_batch_size ({}) <= batch_size \
+                 ({}) <= max_batch_size ({})",
+                self.min_batch_size, self.batch_size, self.max_batch_size
+            ),
+        )?;
+        invariant(
+            self.max_concurrent_inbound_syncs > 0,
+            "shared_mempool.max_concurrent_inbound_syncs must be positive".into(),
+        )
+    }
+
+    /// Takes the broadcast intervals and batch sizes of `new`, a validated config, keeping the
+    /// other settings: they only take effect on restart. Returns the names of the settings `new`
+    /// changes that were kept.
+    pub fn reload(&mut self, new: &SharedMempoolConfig) -> Vec<&'static str> {
+        let mut kept = vec![];
+        if new.ack_timeout_ms != self.ack_timeout_ms {
+            kept.push("ack_timeout_ms");
         }
+        if new.max_concurrent_inbound_syncs != self.max_concurrent_inbound_syncs {
+            kept.push("max_concurrent_inbound_syncs");
+        }
+        *self = SharedMempoolConfig {
+            ack_timeout_ms: self.ack_timeout_ms,
+            max_concurrent_inbound_syncs: self.max_concurrent_inbound_syncs,
+            ..new.clone()
+        };
+        kept
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_types::{
+        transaction::{ChangeSet, Module, Script, WriteSetPayload},
+        write_set::WriteSet,
+    };
+
+    #[test]
+    fn test_legacy_shared_mempool_settings() {
+        let mut config: MempoolConfig = serde_yaml::from_str(
+            "shared_mempool_tick_interval_ms: 50\nshared_mempool_batch_size: 20\n",
+        )
+        .unwrap();
+        config.sanitize().unwrap();
+        assert_eq!(config.shared_mempool.tick_interval_ms, 50);
+        assert_eq!(config.shared_mempool.batch_size, 20);
+        assert_eq!(
+            config.shared_mempool.backoff_interval_ms,
+            SharedMempoolConfig::default().backoff_interval_ms
+        );
+
+        // Saved in the current layout
+        let saved = serde_yaml::to_string(&config).unwrap();
+        assert!(!saved.contains("shared_mempool_"));
+        let reloaded: MempoolConfig = serde_yaml::from_str(&saved).unwrap();
+        assert_eq!(reloaded, config);
+
+        // Validated
+        let mut config: MempoolConfig =
+            serde_yaml::from_str("shared_mempool_min_batch_size: 200\n").unwrap();
+        config.sanitize().unwrap_err();
+    }
+
+    #[test]
+    fn test_expiration_window_bounds() {
+        let mut config = MempoolConfig {
+            min_expiration_window_secs: 600,
+            max_expiration_window_secs: 60,
+            ..MempoolConfig::default()
+        };
+        config.sanitize().unwrap_err();
+        config.max_expiration_window_secs = 0;
+        config.sanitize().unwrap();
+    }
+
+    #[test]
+    fn test_min_gas_unit_price_by_payload() {
+        let config: MempoolConfig =
+            serde_yaml::from_str("min_gas_unit_price:\n  module: 10\n  write_set: 5\n").unwrap();
+        let min_gas_unit_price = &config.min_gas_unit_price;
+        let script = TransactionPayload::Script(Script::new(vec![], vec![], vec![]));
+        assert_eq!(min_gas_unit_price.for_payload(&script), 0);
+        let module = TransactionPayload::Module(Module::new(vec![]));
+        assert_eq!(min_gas_unit_price.for_payload(&module), 10);
+        let write_set = TransactionPayload::WriteSet(WriteSetPayload::Direct(ChangeSet::new(
+            WriteSet::default(),
+            vec![],
+        )));
+        assert_eq!(min_gas_unit_price.for_payload(&write_set), 5);
+    }
+
+    #[test]
+    fn test_reload_shared_mempool_config() {
+        let mut config = SharedMempoolConfig::default();
+        let new = SharedMempoolConfig {
+            tick_interval_ms: 100,
+            batch_size: 50,
+            ack_timeout_ms: 1_000,
+            ..SharedMempoolConfig::default()
+        };
+        assert_eq!(config.reload(&new), vec!["ack_timeout_ms"]);
+        assert_eq!(config.tick_interval_ms, 100);
+        assert_eq!(config.batch_size, 50);
+        assert_eq!(
+            config.ack_timeout_ms,
+            SharedMempoolConfig::default().ack_timeout_ms
+        );
     }
 }
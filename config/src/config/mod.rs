@@ -31,6 +31,8 @@ mod mempool_config;
 pub use mempool_config::*;
 mod network_config;
 pub use network_config::*;
+mod operational_key_config;
+pub use operational_key_config::*;
 mod json_rpc_config;
 pub use json_rpc_config::*;
 mod secure_backend_config;
@@ -39,6 +41,8 @@ mod state_sync_config;
 pub use state_sync_config::*;
 mod storage_config;
 pub use storage_config::*;
+mod telemetry_config;
+pub use telemetry_config::*;
 mod safety_rules_config;
 pub use safety_rules_config::*;
 mod upstream_config;
@@ -79,6 +83,8 @@ pub struct NodeConfig {
     #[serde(default)]
     pub storage: StorageConfig,
     #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
     pub test: Option<TestConfig>,
     #[serde(default)]
     pub upstream: UpstreamConfig, //////// 0L ////////
@@ -233,6 +239,7 @@ impl NodeConfig {
 
         let input_dir = RootPath::new(input_path);
         config.execution.load(&input_dir)?;
+        config.mempool.sanitize()?;
 
         let mut config = config.validate_network_configs()?;
         config.set_data_dir(config.data_dir().to_path_buf());
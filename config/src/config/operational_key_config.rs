@@ -0,0 +1,57 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{config::SecureBackend, keys::ConfigKey};
+use diem_crypto::ed25519::Ed25519PrivateKey;
+use diem_secure_storage::{Error, OperationalKey, Storage};
+use serde::{Deserialize, Serialize};
+
+/// Where an operational key of the node, e.g. the key it signs its telemetry with, is kept.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OperationalKeyConfig {
+    /// The key is written in the config, which is then a raw key file. Meant for tests.
+    FromConfig(OperationalKeyFromConfig),
+    /// The key is kept in secure storage, and created there if missing. Vault signs with it
+    /// without the key ever leaving Vault, and the key manager may rotate it.
+    FromStorage(OperationalKeyFromStorage),
+}
+
+impl OperationalKeyConfig {
+    pub fn from_config(key: Ed25519PrivateKey) -> Self {
+        OperationalKeyConfig::FromConfig(OperationalKeyFromConfig {
+            key: ConfigKey::new(key),
+        })
+    }
+
+    pub fn from_storage(key_name: String, backend: SecureBackend) -> Self {
+        OperationalKeyConfig::FromStorage(OperationalKeyFromStorage { backend, key_name })
+    }
+
+    /// Opens the key. Keys written in the config are named `name`.
+    pub fn load(&self, name: &str) -> Result<OperationalKey, Error> {
+        match self {
+            OperationalKeyConfig::FromConfig(config) => Ok(OperationalKey::from_private_key(
+                name,
+                config.key.private_key(),
+            )),
+            OperationalKeyConfig::FromStorage(config) => {
+                OperationalKey::new(Storage::from(&config.backend), &config.key_name)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperationalKeyFromConfig {
+    #[serde(flatten)]
+    pub key: ConfigKey<Ed25519PrivateKey>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperationalKeyFromStorage {
+    pub backend: SecureBackend,
+    pub key_name: String,
+}
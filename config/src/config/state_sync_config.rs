@@ -15,6 +15,14 @@ pub struct StateSyncConfig {
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
     pub max_chunk_limit: u64,
+    // The maximum number of chunk requests in flight at once. When the node is more than a chunk
+    // behind its sync target, the chunks after the next one are requested concurrently, striped
+    // across the best upstream peers. At least 1 (chunks are requested one at a time).
+    pub max_concurrent_chunk_requests: u64,
+    // The maximum number of chunks being verified, executed or committed at once. Chunks are
+    // applied in a pipeline, so that the next chunk can be executed while the previous one is
+    // still being committed. At least 1 (chunks are applied one at a time).
+    pub max_pending_chunks: u64,
     // valid maximum timeout limit for sanity check
     // This timeout applies to the process_request_for_target_and_highest
     // if the chunk cannot be applied now, then insert it in a subscription to appply. The subscription expires at max_timeout_ms
@@ -41,6 +49,8 @@ impl Default for StateSyncConfig {
             client_commit_timeout_ms: 5_000,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1_000,
+            max_concurrent_chunk_requests: 4,
+            max_pending_chunks: 4,
             max_timeout_ms: 1_200_000,
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
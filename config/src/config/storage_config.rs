@@ -32,6 +32,52 @@ impl Default for RocksdbConfig {
     }
 }
 
+/// Structured log of the requests served by the storage service, one JSON object per line, for
+/// usage accounting and abuse forensics on nodes serving many clients.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageAuditLogConfig {
+    /// Relative paths are under the data directory.
+    pub path: PathBuf,
+    /// Once the log would grow past this size, it is rotated: renamed with the suffix `.1`, the
+    /// previously rotated logs shifting to `.2`, `.3` and so on.
+    pub max_file_bytes: u64,
+    /// Number of rotated logs kept, the oldest being deleted.
+    pub max_rotated_files: usize,
+}
+
+impl Default for StorageAuditLogConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("storage_audit.log"),
+            max_file_bytes: 100 * 1024 * 1024,
+            max_rotated_files: 10,
+        }
+    }
+}
+
+/// Storage of an archive node, keeping the full history for explorers and the like. Recent DB
+/// files stay on the disk holding the DB directory, and older files are moved to a slower, cheaper
+/// disk as they are compacted.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// Directory on the cold storage disk. Relative paths are under the data directory.
+    pub cold_storage_dir: PathBuf,
+    /// Size of the DB files kept on the disk holding the DB directory. Once they grow past it,
+    /// files of the oldest data are written to cold storage instead.
+    pub hot_storage_bytes: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            cold_storage_dir: PathBuf::from("db_cold"),
+            hot_storage_bytes: 200 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageConfig {
@@ -48,6 +94,39 @@ pub struct StorageConfig {
     pub timeout_ms: u64,
     /// Rocksdb-specific configurations
     pub rocksdb_config: RocksdbConfig,
+    /// None disables load shedding. When a commit takes longer than this many milliseconds, the
+    /// storage service rejects low priority account state reads, e.g. from exports, with a
+    /// retriable `ServiceBusy` error for as long as that commit took, so bulk read traffic can't
+    /// hold up consensus and state sync writes. Executor reads are never shed.
+    pub read_shedding_commit_latency_ms: Option<u64>,
+    /// Longest a read snapshot handed out by the storage service stays usable. Pinned snapshot
+    /// versions are exempt from pruning, so this bounds how far a forgotten snapshot can hold
+    /// back the pruner.
+    pub max_read_snapshot_ttl_ms: u64,
+    /// None disables the scrubber. Otherwise the node re-reads this many versions of committed
+    /// data per second in the background, checking them against the ledger's hashes, and reports
+    /// corrupted versions through metrics and the JSON-RPC health check.
+    pub scrub_versions_per_sec: Option<u64>,
+    /// Whether the storage service serves account state reads that skip proofs, saving their size
+    /// and CPU for trusted consumers co-located with the node, e.g. an indexer. Keep it off when
+    /// the storage service is reachable by clients that must verify what they read.
+    pub allow_skip_proofs: bool,
+    /// None constructs the proofs of a read of many transactions or events, e.g. for state sync or
+    /// JSON-RPC, one item after the other. Otherwise large reads construct the proofs of their
+    /// items in parallel on a pool of this many threads, shared by all reads so they can't take
+    /// more CPU than that from consensus and execution.
+    pub proof_threads: Option<usize>,
+    /// None disables the audit log of the storage service.
+    pub audit_log: Option<StorageAuditLogConfig>,
+    /// Some runs the node as an archive node: nothing is pruned, whatever `prune_window`, and the
+    /// DB files are tiered between the DB directory and cold storage.
+    pub archive: Option<ArchiveConfig>,
+    /// None writes, at every version, the state tree nodes the version creates. Otherwise versions
+    /// only write the account states they change, and the full state tree is written in the
+    /// background once this many versions went by since it last was, cutting the write
+    /// amplification of busy nodes. Reading the state at a version in between replays the changes
+    /// since the latest full tree in memory.
+    pub state_checkpoint_interval: Option<u64>,
 }
 
 impl Default for StorageConfig {
@@ -70,6 +149,14 @@ impl Default for StorageConfig {
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
             rocksdb_config: RocksdbConfig::default(),
+            read_shedding_commit_latency_ms: None,
+            max_read_snapshot_ttl_ms: 300_000,
+            scrub_versions_per_sec: Some(1_000),
+            allow_skip_proofs: false,
+            proof_threads: Some(4),
+            audit_log: None,
+            archive: None,
+            state_checkpoint_interval: None,
         }
     }
 }
@@ -83,6 +170,28 @@ impl StorageConfig {
         }
     }
 
+    /// Path of the storage service audit log, if it is enabled.
+    pub fn audit_log_path(&self) -> Option<PathBuf> {
+        self.audit_log.as_ref().map(|audit_log| {
+            if audit_log.path.is_relative() {
+                self.data_dir.join(&audit_log.path)
+            } else {
+                audit_log.path.clone()
+            }
+        })
+    }
+
+    /// Path of the cold storage directory, if the node is an archive node.
+    pub fn cold_storage_dir(&self) -> Option<PathBuf> {
+        self.archive.as_ref().map(|archive| {
+            if archive.cold_storage_dir.is_relative() {
+                self.data_dir.join(&archive.cold_storage_dir)
+            } else {
+                archive.cold_storage_dir.clone()
+            }
+        })
+    }
+
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
         self.data_dir = data_dir;
     }
@@ -0,0 +1,61 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config::OperationalKeyConfig;
+use serde::{Deserialize, Serialize};
+
+/// Heartbeats the node publishes to a collector, so the health of the network can be mapped
+/// without scraping nodes. Off unless both a collector and a signing key are configured.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// URL heartbeats are posted to
+    pub collector_url: Option<String>,
+    /// Key signing the heartbeats. Its public key identifies the node to the collector, so it
+    /// should be dedicated to telemetry rather than shared with consensus or the network.
+    pub signing_key: Option<OperationalKeyConfig>,
+    /// How often a heartbeat is taken
+    pub interval_ms: u64,
+    /// Fields heartbeats carry, besides when they were taken. Fields not listed are never read,
+    /// let alone published.
+    pub fields: Vec<HeartbeatField>,
+    /// Number of heartbeats kept while the collector can't be reached, to publish once it can.
+    /// The oldest are dropped beyond it.
+    pub max_buffered_heartbeats: usize,
+}
+
+impl TelemetryConfig {
+    pub fn enabled(&self) -> bool {
+        self.collector_url.is_some() && self.signing_key.is_some()
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> TelemetryConfig {
+        TelemetryConfig {
+            collector_url: None,
+            signing_key: None,
+            interval_ms: 60_000,
+            fields: vec![
+                HeartbeatField::NodeVersion,
+                HeartbeatField::LedgerVersion,
+                HeartbeatField::Epoch,
+                HeartbeatField::PeerCount,
+            ],
+            max_buffered_heartbeats: 60,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartbeatField {
+    /// Version of the node software
+    NodeVersion,
+    /// Version of the latest ledger info
+    LedgerVersion,
+    /// Epoch of the latest ledger info
+    Epoch,
+    /// Number of peers connected, over all networks
+    PeerCount,
+}
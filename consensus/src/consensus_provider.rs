@@ -17,6 +17,7 @@ use diem_logger::prelude::*;
 use diem_mempool::ConsensusRequest;
 use diem_types::on_chain_config::OnChainConfigPayload;
 use execution_correctness::ExecutionCorrectnessManager;
+use executor::pre_execution::PreExecutionCache;
 use futures::channel::mpsc;
 use state_sync::client::StateSyncClient;
 use std::sync::Arc;
@@ -32,6 +33,7 @@ pub fn start_consensus(
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     diem_db: Arc<dyn DbReader>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    pre_execution_cache: Option<Arc<PreExecutionCache>>,
 ) -> Runtime {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
@@ -45,7 +47,8 @@ pub fn start_consensus(
         node_config.consensus.mempool_txn_pull_timeout_ms,
         node_config.consensus.mempool_executed_txn_timeout_ms,
     ));
-    let execution_correctness_manager = ExecutionCorrectnessManager::new(node_config);
+    let execution_correctness_manager =
+        ExecutionCorrectnessManager::new_with_pre_execution_cache(node_config, pre_execution_cache);
     let state_computer = Arc::new(ExecutionProxy::new(
         execution_correctness_manager.client(),
         state_sync_client,
@@ -71,6 +71,17 @@ pub static PROPOSALS_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!("diem_consensus_proposals_count", "Count of the block proposals sent by this validator since last restart (both primary and secondary)").unwrap()
 });
 
+/// Count of the proposal payloads prefetched from mempool a round ahead, by whether the proposal
+/// used them (hit) or had to pull its transactions on the critical path (miss)
+pub static PREFETCHED_PAYLOADS_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_consensus_prefetched_payloads_count",
+        "Count of the proposal payloads prefetched from mempool, by result (hit or miss)",
+        &["result"]
+    )
+    .unwrap()
+});
+
 /// Count the number of times a validator voted for a nil block since last restart.
 pub static VOTE_NIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -12,6 +12,7 @@ use consensus_types::{
     quorum_cert::QuorumCert,
 };
 
+use diem_crypto::HashValue;
 use diem_infallible::Mutex;
 use std::sync::Arc;
 
@@ -121,11 +122,18 @@ impl ProposalGenerator {
             // the local time exceeds it.
             let timestamp = self.time_service.get_current_timestamp();
 
-            let payload = self
+            let prefetched = self
                 .txn_manager
-                .pull_txns(self.max_block_size, exclude_payload)
-                .await
-                .context("Fail to retrieve txn")?;
+                .take_prefetched_txns(round, hqc.certified_block().id())
+                .await;
+            let payload = match prefetched {
+                Some(payload) => payload,
+                None => self
+                    .txn_manager
+                    .pull_txns(self.max_block_size, exclude_payload)
+                    .await
+                    .context("Fail to retrieve txn")?,
+            };
 
             (payload, timestamp.as_micros() as u64)
         };
@@ -140,6 +148,35 @@ impl ProposalGenerator {
         ))
     }
 
+    /// Prefetches the payload of the proposal for `round` extending `parent_id`: called by a
+    /// validator expecting to propose at `round` once `parent_id` is certified, so that the
+    /// TxnManager pulls the transactions while the parent is being voted on.
+    /// The payload is only used if the proposal does extend `parent_id`.
+    pub fn prefetch_payload(&self, round: Round, parent_id: HashValue) {
+        if *self.last_round_generated.lock() >= round {
+            return;
+        }
+        let parent = match self.block_store.get_block(parent_id) {
+            Some(parent) => parent,
+            None => return,
+        };
+        // The proposals after a reconfiguration are empty
+        if parent.block_info().has_reconfiguration() {
+            return;
+        }
+        let mut pending_blocks = match self.block_store.path_from_root(parent_id) {
+            Some(pending_blocks) => pending_blocks,
+            None => return,
+        };
+        pending_blocks.push(self.block_store.root());
+        let exclude_payload: Vec<&Vec<_>> = pending_blocks
+            .iter()
+            .flat_map(|block| block.payload())
+            .collect();
+        self.txn_manager
+            .prefetch_txns(round, parent_id, self.max_block_size, exclude_payload);
+    }
+
     fn ensure_highest_quorum_cert(&self, round: Round) -> anyhow::Result<Arc<QuorumCert>> {
         let hqc = self.block_store.highest_quorum_cert();
         ensure!(
@@ -95,3 +95,38 @@ async fn test_old_proposal_generation() {
     let proposal_err = proposal_generator.generate_proposal(1).await.err();
     assert!(proposal_err.is_some());
 }
+
+#[tokio::test]
+async fn test_proposal_generation_prefetched_payload() {
+    let mut inserter = TreeInserter::default();
+    let block_store = inserter.block_store();
+    let txn_manager = Arc::new(MockTransactionManager::new(None));
+    let mut proposal_generator = ProposalGenerator::new(
+        inserter.signer().author(),
+        block_store.clone(),
+        txn_manager.clone(),
+        Arc::new(SimulatedTimeService::new()),
+        1,
+    );
+    let genesis = block_store.root();
+    let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
+
+    // The payload prefetched on top of a1 is proposed once a1 is certified
+    proposal_generator.prefetch_payload(2, a1.id());
+    let prefetched = txn_manager.prefetched_payload().unwrap();
+    inserter.insert_qc_for_block(a1.as_ref(), None);
+    let a1_child_res = proposal_generator.generate_proposal(2).await.unwrap();
+    assert_eq!(a1_child_res.parent_id(), a1.id());
+    assert_eq!(a1_child_res.payload(), Some(&prefetched));
+
+    // A payload prefetched for another round is discarded
+    proposal_generator.prefetch_payload(3, a1.id());
+    let prefetched = txn_manager.prefetched_payload().unwrap();
+    let a1_child_res = proposal_generator.generate_proposal(4).await.unwrap();
+    assert_ne!(a1_child_res.payload(), Some(&prefetched));
+    assert!(txn_manager.prefetched_payload().is_none());
+
+    // Nothing is prefetched for the rounds already proposed
+    proposal_generator.prefetch_payload(4, a1.id());
+    assert!(txn_manager.prefetched_payload().is_none());
+}
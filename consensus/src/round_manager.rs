@@ -495,6 +495,8 @@ impl RoundManager {
     /// 3. Try to vote for it following the safety rules.
     /// 4. In case a validator chooses to vote, send the vote to the representatives at the next
     /// round.
+    /// 5. In case the validator is the proposer of the next round, prefetch the payload of its
+    /// proposal.
     async fn process_proposal(&mut self, proposal: Block) -> Result<()> {
         let author = proposal
             .author()
@@ -534,6 +536,11 @@ impl RoundManager {
         let recipients = self
             .proposer_election
             .get_valid_proposer(proposal_round + 1);
+        // Leading the next round: pull its payload from mempool while the vote is aggregated
+        if recipients == self.proposal_generator.author() {
+            self.proposal_generator
+                .prefetch_payload(proposal_round + 1, vote.vote_data().proposed().id());
+        }
         
         debug!(self.new_log(LogEvent::Vote).remote_peer(author), "{}", vote);
 
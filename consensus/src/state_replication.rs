@@ -3,7 +3,10 @@
 
 use crate::error::{MempoolError, StateSyncError};
 use anyhow::Result;
-use consensus_types::{block::Block, common::Payload};
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+};
 use diem_crypto::HashValue;
 use diem_types::ledger_info::LedgerInfoWithSignatures;
 use executor_types::{Error as ExecutionError, StateComputeResult};
@@ -20,6 +23,24 @@ pub trait TxnManager: Send + Sync {
         exclude: Vec<&Payload>,
     ) -> Result<Payload, MempoolError>;
 
+    /// Starts pulling the transactions of the block proposed at `round` on top of `parent_id`
+    /// without waiting for them, so that the pull overlaps with the previous round.
+    /// The `exclude` list is the one `pull_txns` would be given for that parent.
+    fn prefetch_txns(
+        &self,
+        _round: Round,
+        _parent_id: HashValue,
+        _max_size: u64,
+        _exclude: Vec<&Payload>,
+    ) {
+    }
+
+    /// Returns the transactions prefetched for `round` on top of `parent_id`, if any, and
+    /// discards prefetched transactions for another round or parent.
+    async fn take_prefetched_txns(&self, _round: Round, _parent_id: HashValue) -> Option<Payload> {
+        None
+    }
+
     /// Notifies TxnManager about the executed result of the block,
     /// which includes the specifics of what transactions succeeded and failed.
     async fn notify(
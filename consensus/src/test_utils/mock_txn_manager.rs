@@ -5,8 +5,10 @@ use crate::{error::MempoolError, state_replication::TxnManager, txn_manager::Mem
 use anyhow::Result;
 use consensus_types::{
     block::{block_test_utils::random_payload, Block},
-    common::Payload,
+    common::{Payload, Round},
 };
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
 use diem_mempool::ConsensusRequest;
 use diem_types::{
     transaction::TransactionStatus,
@@ -15,12 +17,14 @@ use diem_types::{
 use executor_types::StateComputeResult;
 use futures::channel::mpsc;
 use rand::Rng;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct MockTransactionManager {
     rejected_txns: Payload,
     // used non-mocked TxnManager to test interaction with shared mempool
     mempool_proxy: Option<MempoolProxy>,
+    prefetched: Arc<Mutex<Option<(Round, HashValue, Payload)>>>,
 }
 
 impl MockTransactionManager {
@@ -29,8 +33,17 @@ impl MockTransactionManager {
         Self {
             rejected_txns: vec![],
             mempool_proxy,
+            prefetched: Arc::new(Mutex::new(None)),
         }
     }
+
+    /// The payload prefetched and not taken yet
+    pub fn prefetched_payload(&self) -> Option<Payload> {
+        self.prefetched
+            .lock()
+            .as_ref()
+            .map(|(_, _, payload)| payload.clone())
+    }
 }
 
 // mock transaction status on the fly
@@ -59,6 +72,24 @@ impl TxnManager for MockTransactionManager {
         Ok(random_payload(10))
     }
 
+    fn prefetch_txns(
+        &self,
+        round: Round,
+        parent_id: HashValue,
+        _max_size: u64,
+        _exclude_txns: Vec<&Payload>,
+    ) {
+        *self.prefetched.lock() = Some((round, parent_id, random_payload(10)));
+    }
+
+    async fn take_prefetched_txns(&self, round: Round, parent_id: HashValue) -> Option<Payload> {
+        self.prefetched
+            .lock()
+            .take()
+            .filter(|(r, id, _)| *r == round && *id == parent_id)
+            .map(|(_, _, payload)| payload)
+    }
+
     async fn notify(
         &self,
         block: &Block,
@@ -1,9 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{error::MempoolError, state_replication::TxnManager};
+use crate::{counters, error::MempoolError, state_replication::TxnManager};
 use anyhow::{format_err, Result};
-use consensus_types::{block::Block, common::Payload};
+use consensus_types::{
+    block::Block,
+    common::{Payload, Round},
+};
+use diem_crypto::HashValue;
+use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_mempool::{
     CommittedTransaction, ConsensusRequest, ConsensusResponse, TransactionExclusion,
@@ -14,11 +19,18 @@ use executor_types::StateComputeResult;
 use fail::fail_point;
 use futures::channel::{mpsc, oneshot};
 use itertools::Itertools;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use tokio::time::{sleep, timeout};
 
 const NO_TXN_DELAY: u64 = 30;
 
+/// A block pull sent to mempool ahead of the proposal it is for.
+struct PrefetchedTxns {
+    round: Round,
+    parent_id: HashValue,
+    callback_rcv: oneshot::Receiver<Result<ConsensusResponse>>,
+}
+
 /// Proxy interface to mempool
 #[derive(Clone)]
 pub struct MempoolProxy {
@@ -28,6 +40,8 @@ pub struct MempoolProxy {
     mempool_executed_txn_timeout_ms: u64,
     /// Timeout for consensus to pull transactions from mempool and get a response (in milliseconds)
     mempool_txn_pull_timeout_ms: u64,
+    /// The pending prefetch, shared by the clones of the proxy
+    prefetched: Arc<Mutex<Option<PrefetchedTxns>>>,
 }
 
 impl MempoolProxy {
@@ -46,21 +60,49 @@ impl MempoolProxy {
             poll_count,
             mempool_executed_txn_timeout_ms,
             mempool_txn_pull_timeout_ms,
+            prefetched: Arc::new(Mutex::new(None)),
         }
     }
 
-    async fn pull_internal(
+    fn exclusions(exclude_payloads: Vec<&Payload>) -> Vec<TransactionExclusion> {
+        exclude_payloads
+            .into_iter()
+            .flatten()
+            .map(|transaction| TransactionExclusion {
+                sender: transaction.sender(),
+                sequence_number: transaction.sequence_number(),
+            })
+            .collect()
+    }
+
+    fn send_get_block_request(
         &self,
         max_size: u64,
         exclude_txns: Vec<TransactionExclusion>,
-    ) -> Result<Payload, MempoolError> {
+    ) -> Result<oneshot::Receiver<Result<ConsensusResponse>>> {
         let (callback, callback_rcv) = oneshot::channel();
-        let req = ConsensusRequest::GetBlockRequest(max_size, exclude_txns.clone(), callback);
+        let req = ConsensusRequest::GetBlockRequest(max_size, exclude_txns, callback);
         // send to shared mempool
         self.consensus_to_mempool_sender
             .clone()
             .try_send(req)
             .map_err(anyhow::Error::from)?;
+        Ok(callback_rcv)
+    }
+
+    async fn pull_internal(
+        &self,
+        max_size: u64,
+        exclude_txns: Vec<TransactionExclusion>,
+    ) -> Result<Payload, MempoolError> {
+        let callback_rcv = self.send_get_block_request(max_size, exclude_txns)?;
+        self.receive_block(callback_rcv).await
+    }
+
+    async fn receive_block(
+        &self,
+        callback_rcv: oneshot::Receiver<Result<ConsensusResponse>>,
+    ) -> Result<Payload, MempoolError> {
         // wait for response
         match monitor!(
             "pull_txn",
@@ -93,15 +135,7 @@ impl TxnManager for MempoolProxy {
         fail_point!("consensus::pull_txns", |_| {
             Err(anyhow::anyhow!("Injected error in pull_txns").into())
         });
-        let mut exclude_txns = vec![];
-        for payload in exclude_payloads {
-            for transaction in payload {
-                exclude_txns.push(TransactionExclusion {
-                    sender: transaction.sender(),
-                    sequence_number: transaction.sequence_number(),
-                });
-            }
-        }
+        let exclude_txns = Self::exclusions(exclude_payloads);
         let no_pending_txns = exclude_txns.is_empty();
         // keep polling mempool until there's txn available or there's still pending txns
         let mut count = self.poll_count;
@@ -121,6 +155,46 @@ impl TxnManager for MempoolProxy {
         Ok(txns)
     }
 
+    fn prefetch_txns(
+        &self,
+        round: Round,
+        parent_id: HashValue,
+        max_size: u64,
+        exclude_payloads: Vec<&Payload>,
+    ) {
+        let exclude_txns = Self::exclusions(exclude_payloads);
+        match self.send_get_block_request(max_size, exclude_txns) {
+            Ok(callback_rcv) => {
+                *self.prefetched.lock() = Some(PrefetchedTxns {
+                    round,
+                    parent_id,
+                    callback_rcv,
+                });
+            }
+            Err(e) => warn!(error = ?e, round = round, "Fail to prefetch txns from mempool"),
+        }
+    }
+
+    async fn take_prefetched_txns(&self, round: Round, parent_id: HashValue) -> Option<Payload> {
+        let prefetched = self.prefetched.lock().take()?;
+        let txns = if prefetched.round == round && prefetched.parent_id == parent_id {
+            match self.receive_block(prefetched.callback_rcv).await {
+                // an empty prefetch is no hit: pulling again polls mempool for new txns
+                Ok(txns) => Some(txns).filter(|txns| !txns.is_empty()),
+                Err(e) => {
+                    warn!(error = ?e, round = round, "Fail to receive prefetched txns");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        counters::PREFETCHED_PAYLOADS_COUNT
+            .with_label_values(&[if txns.is_some() { "hit" } else { "miss" }])
+            .inc();
+        txns
+    }
+
     // Consensus notifies mempool of executed transactions
     async fn notify(
         &self,
@@ -30,5 +30,21 @@ fn verify(c: &mut Criterion) {
     });
 }
 
-criterion_group!(ed25519_benches, verify);
+fn batch_verify(c: &mut Criterion) {
+    let mut csprng: ThreadRng = thread_rng();
+    let msg = TestDiemCrypto("".to_string());
+    let keys_and_signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = (0..100)
+        .map(|_| {
+            let priv_key = Ed25519PrivateKey::generate(&mut csprng);
+            let sig = priv_key.sign(&msg);
+            ((&priv_key).into(), sig)
+        })
+        .collect();
+
+    c.bench_function("Ed25519 batch verification of 100 signatures", move |b| {
+        b.iter(|| Ed25519Signature::batch_verify_strict(&msg, &keys_and_signatures))
+    });
+}
+
+criterion_group!(ed25519_benches, verify, batch_verify);
 criterion_main!(ed25519_benches);
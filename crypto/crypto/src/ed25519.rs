@@ -37,9 +37,16 @@ use crate::{
 };
 use anyhow::{anyhow, Result};
 use core::convert::TryFrom;
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
 use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
 use mirai_annotations::*;
 use serde::Serialize;
+use sha2::{Digest, Sha512};
 use std::{cmp::Ordering, fmt};
 
 pub use ed25519_dalek;
@@ -57,6 +64,9 @@ const L: [u8; 32] = [
     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
 ];
 
+/// Domain separator of the coefficients derived in [`Ed25519Signature::batch_verify_strict`].
+const BATCH_VERIFY_DOMAIN: &[u8] = b"DIEM::Ed25519BatchVerifyStrict";
+
 /// An Ed25519 private key
 #[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
 pub struct Ed25519PrivateKey(ed25519_dalek::SecretKey);
@@ -220,6 +230,80 @@ impl Ed25519Signature {
         }
         Ok(())
     }
+
+    /// Verifies signatures of the same message by several public keys at once, by checking
+    /// [Σ z_i·S_i]B = Σ z_i·R_i + Σ (z_i·k_i)A_i, where k_i = H(R_i || A_i || M).
+    ///
+    /// Unlike the batch verification of ed25519-dalek, the coefficients z_i are not random but
+    /// derived from the message and the whole batch, and every signature first goes through
+    /// the checks of `verify`: S must be canonical, and neither the public key A nor the R
+    /// component may be a non-canonical encoding or a point of small order.
+    ///
+    /// The components of order 8 of mixed-order points are not checked though: signatures that
+    /// `verify` rejects may pass together if their signers make these components cancel out.
+    /// Code that must agree with `verify` on every signature, e.g. consensus or state sync,
+    /// should not use this. A failed batch does not tell which signature is invalid either.
+    ///
+    /// Ledger info signatures, including the light client's in `TrustedState`, are still
+    /// verified one at a time: the derivation of the coefficients needs an independent
+    /// cryptographic review first.
+    pub fn batch_verify_strict<T: CryptoHash + Serialize>(
+        message: &T,
+        keys_and_signatures: &[(Ed25519PublicKey, Ed25519Signature)],
+    ) -> Result<()> {
+        let mut message_bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut message_bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)?;
+
+        let mut transcript = Sha512::new();
+        transcript.update(BATCH_VERIFY_DOMAIN);
+        transcript.update(&message_bytes);
+        let mut parsed = Vec::with_capacity(keys_and_signatures.len());
+        for (public_key, signature) in keys_and_signatures {
+            let signature_bytes = signature.to_bytes();
+            Ed25519Signature::check_malleability(&signature_bytes)?;
+            let mut r_bytes = [0u8; 32];
+            r_bytes.copy_from_slice(&signature_bytes[..32]);
+            let mut s_bytes = [0u8; 32];
+            s_bytes.copy_from_slice(&signature_bytes[32..]);
+            let public_key_bytes = public_key.to_bytes();
+
+            let a = decompress_strict(&public_key_bytes)?;
+            let r = decompress_strict(&r_bytes)?;
+            let s = Scalar::from_canonical_bytes(s_bytes)
+                .ok_or(CryptoMaterialError::CanonicalRepresentationError)?;
+            let mut h = Sha512::new();
+            h.update(&r_bytes);
+            h.update(&public_key_bytes);
+            h.update(&message_bytes);
+            let k = scalar_from_hash(h);
+
+            transcript.update(&public_key_bytes);
+            transcript.update(&signature_bytes);
+            parsed.push((a, r, s, k));
+        }
+        let transcript = transcript.finalize();
+
+        let mut basepoint_scalar = Scalar::zero();
+        let mut scalars = Vec::with_capacity(2 * parsed.len() + 1);
+        let mut points = Vec::with_capacity(2 * parsed.len() + 1);
+        for (index, (a, r, s, k)) in parsed.into_iter().enumerate() {
+            let z = batch_coefficient(&transcript, index);
+            basepoint_scalar -= z * s;
+            scalars.push(z);
+            points.push(r);
+            scalars.push(z * k);
+            points.push(a);
+        }
+        scalars.push(basepoint_scalar);
+        points.push(ED25519_BASEPOINT_POINT);
+
+        if EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity() {
+            Ok(())
+        } else {
+            Err(anyhow!("Ed25519 batch verification failed"))
+        }
+    }
 }
 
 ///////////////////////
@@ -443,35 +527,6 @@ impl Signature for Ed25519Signature {
     fn to_bytes(&self) -> Vec<u8> {
         self.0.to_bytes().to_vec()
     }
-
-    /// Batch signature verification as described in the original EdDSA article
-    /// by Bernstein et al. "High-speed high-security signatures". Current implementation works for
-    /// signatures on the same message and it checks for malleability.
-    #[cfg(feature = "batch")]
-    fn batch_verify<T: CryptoHash + Serialize>(
-        message: &T,
-        keys_and_signatures: Vec<(Self::VerifyingKeyMaterial, Self)>,
-    ) -> Result<()> {
-        for (_, sig) in keys_and_signatures.iter() {
-            Ed25519Signature::check_malleability(&sig.to_bytes())?
-        }
-        let mut message_bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
-        bcs::serialize_into(&mut message_bytes, &message)
-            .map_err(|_| CryptoMaterialError::SerializationError)?;
-
-        let batch_argument = keys_and_signatures
-            .iter()
-            .map(|(key, signature)| (key.0, signature.0));
-        let (dalek_public_keys, dalek_signatures): (Vec<_>, Vec<_>) = batch_argument.unzip();
-        let message_ref = &(&message_bytes)[..];
-        // The original batching algorithm works for different messages and it expects as many
-        // messages as the number of signatures. In our case, we just populate the same
-        // message to meet dalek's api requirements.
-        let messages = vec![message_ref; dalek_signatures.len()];
-        ed25519_dalek::verify_batch(&messages[..], &dalek_signatures[..], &dalek_public_keys[..])
-            .map_err(|e| anyhow!("{}", e))?;
-        Ok(())
-    }
 }
 
 impl Length for Ed25519Signature {
@@ -523,6 +578,40 @@ impl fmt::Debug for Ed25519Signature {
     }
 }
 
+/// Decompresses a public key or the R component of a signature, rejecting non-canonical encodings
+/// and points of small order.
+fn decompress_strict(bytes: &[u8; 32]) -> std::result::Result<EdwardsPoint, CryptoMaterialError> {
+    let point = CompressedEdwardsY(*bytes)
+        .decompress()
+        .ok_or(CryptoMaterialError::PointNotOnCurveError)?;
+    if point.compress().as_bytes() != bytes {
+        return Err(CryptoMaterialError::CanonicalRepresentationError);
+    }
+    if point.is_small_order() {
+        return Err(CryptoMaterialError::SmallSubgroupError);
+    }
+    Ok(point)
+}
+
+// curve25519_dalek is stuck on an old version of digest, so we can't use Scalar::from_hash.
+fn scalar_from_hash(hash: Sha512) -> Scalar {
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(&hash.finalize());
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// The coefficient of the `index`-th signature of a batch: a 128-bit value derived from the
+/// batch transcript, multiplied by 8 and incremented so that it is 1 modulo 8, i.e. it never
+/// cancels out the component of order 8 of a single invalid signature.
+fn batch_coefficient(transcript: &[u8], index: usize) -> Scalar {
+    let mut h = Sha512::new();
+    h.update(transcript);
+    h.update(&(index as u64).to_le_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&h.finalize()[..16]);
+    Scalar::from(u128::from_le_bytes(bytes)) * Scalar::from(8u64) + Scalar::one()
+}
+
 /// Check if S < L to capture invalid signatures.
 fn check_s_lt_l(s: &[u8]) -> bool {
     for i in (0..32).rev() {
@@ -14,6 +14,7 @@ pub mod hash;
 pub mod hkdf;
 pub mod multi_ed25519;
 pub mod noise;
+pub mod secp256k1;
 pub mod test_utils;
 pub mod traits;
 pub mod x25519;
@@ -0,0 +1,374 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an API for ECDSA signatures over the secp256k1 curve, the scheme of the
+//! keys held by most hardware wallets.
+//!
+//! Messages are hashed with SHA3-256 before signing. Public keys are serialized in compressed
+//! form, and signatures as `r || s` with a low `s`: verification rejects high-`s` signatures, so
+//! a third party can't derive a second valid signature from a given one.
+//!
+//! # Examples
+//!
+//! ```
+//! use diem_crypto_derive::{CryptoHasher, BCSCryptoHash};
+//! use diem_crypto::{
+//!     secp256k1::*,
+//!     traits::{Signature, SigningKey, Uniform},
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+//! pub struct TestCryptoDocTest(String);
+//! let message = TestCryptoDocTest("Test message".to_string());
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_key = Secp256k1PrivateKey::generate(&mut rng);
+//! let public_key: Secp256k1PublicKey = (&private_key).into();
+//! let signature = private_key.sign(&message);
+//! assert!(signature.verify(&message, &public_key).is_ok());
+//! ```
+
+use crate::{
+    hash::{CryptoHash, CryptoHasher},
+    traits::*,
+    HashValue,
+};
+use anyhow::{anyhow, Result};
+use core::convert::TryFrom;
+use diem_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use serde::Serialize;
+use std::fmt;
+
+/// The length of the Secp256k1PrivateKey
+pub const SECP256K1_PRIVATE_KEY_LENGTH: usize = libsecp256k1::util::SECRET_KEY_SIZE;
+/// The length of the Secp256k1PublicKey, in compressed form
+pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = libsecp256k1::util::COMPRESSED_PUBLIC_KEY_SIZE;
+/// The length of the Secp256k1Signature
+pub const SECP256K1_SIGNATURE_LENGTH: usize = libsecp256k1::util::SIGNATURE_SIZE;
+
+/// A secp256k1 private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct Secp256k1PrivateKey(libsecp256k1::SecretKey);
+
+#[cfg(feature = "assert-private-keys-not-cloneable")]
+static_assertions::assert_not_impl_any!(Secp256k1PrivateKey: Clone);
+
+#[cfg(any(test, feature = "cloneable-private-keys"))]
+impl Clone for Secp256k1PrivateKey {
+    fn clone(&self) -> Self {
+        let serialized: &[u8] = &(self.to_bytes());
+        Secp256k1PrivateKey::try_from(serialized).unwrap()
+    }
+}
+
+/// A secp256k1 public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256k1PublicKey(libsecp256k1::PublicKey);
+
+/// A secp256k1 ECDSA signature
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256k1Signature(libsecp256k1::Signature);
+
+impl Secp256k1PrivateKey {
+    /// The length of the Secp256k1PrivateKey
+    pub const LENGTH: usize = SECP256K1_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a Secp256k1PrivateKey.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PRIVATE_KEY_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Private function signing the SHA3-256 hash of `message`, shared by the sign methods of
+    /// the SigningKey implementation.
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Secp256k1Signature {
+        let (signature, _recovery_id) = libsecp256k1::sign(&message_digest(message), &self.0);
+        Secp256k1Signature(signature)
+    }
+}
+
+impl Secp256k1PublicKey {
+    /// Serialize a Secp256k1PublicKey in compressed form.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PUBLIC_KEY_LENGTH] {
+        self.0.serialize_compressed()
+    }
+}
+
+impl Secp256k1Signature {
+    /// The length of the Secp256k1Signature
+    pub const LENGTH: usize = SECP256K1_SIGNATURE_LENGTH;
+
+    /// Serialize a Secp256k1Signature.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_SIGNATURE_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Check for correct size and for signature malleability: for any valid signature `(r, s)`,
+    /// `(r, -s)` is valid too, so only the signature with the lower `s` is accepted.
+    pub fn check_malleability(bytes: &[u8]) -> std::result::Result<(), CryptoMaterialError> {
+        let signature = libsecp256k1::Signature::parse_standard_slice(bytes)
+            .map_err(|_| CryptoMaterialError::DeserializationError)?;
+        if signature.s.is_high() {
+            return Err(CryptoMaterialError::CanonicalRepresentationError);
+        }
+        Ok(())
+    }
+}
+
+/// The SHA3-256 hash of `message`, which is what is actually signed.
+fn message_digest(message: &[u8]) -> libsecp256k1::Message {
+    libsecp256k1::Message::parse(HashValue::sha3_256_of(message).as_ref())
+}
+
+///////////////////////
+// PrivateKey Traits //
+///////////////////////
+
+impl PrivateKey for Secp256k1PrivateKey {
+    type PublicKeyMaterial = Secp256k1PublicKey;
+}
+
+impl SigningKey for Secp256k1PrivateKey {
+    type VerifyingKeyMaterial = Secp256k1PublicKey;
+    type SignatureMaterial = Secp256k1Signature;
+
+    fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> Secp256k1Signature {
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)
+            .expect("Serialization of signable material should not fail.");
+        Secp256k1PrivateKey::sign_arbitrary_message(&self, bytes.as_ref())
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Secp256k1Signature {
+        Secp256k1PrivateKey::sign_arbitrary_message(self, message)
+    }
+}
+
+impl Uniform for Secp256k1PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ::rand::CryptoRng,
+    {
+        // Nearly all 32-byte strings are valid keys, only zero and those over the curve order
+        // aren't.
+        loop {
+            let mut bytes = [0u8; SECP256K1_PRIVATE_KEY_LENGTH];
+            rng.fill_bytes(&mut bytes);
+            if let Ok(secret_key) = libsecp256k1::SecretKey::parse(&bytes) {
+                return Secp256k1PrivateKey(secret_key);
+            }
+        }
+    }
+}
+
+impl PartialEq<Self> for Secp256k1PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Secp256k1PrivateKey {}
+
+impl TryFrom<&[u8]> for Secp256k1PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PrivateKey, checking it is a valid scalar.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1PrivateKey, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        libsecp256k1::SecretKey::parse_slice(bytes)
+            .map(Secp256k1PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl Length for Secp256k1PrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl Genesis for Secp256k1PrivateKey {
+    fn genesis() -> Self {
+        let mut buf = [0u8; SECP256K1_PRIVATE_KEY_LENGTH];
+        buf[SECP256K1_PRIVATE_KEY_LENGTH - 1] = 1;
+        Self::try_from(buf.as_ref()).unwrap()
+    }
+}
+
+//////////////////////
+// PublicKey Traits //
+//////////////////////
+
+impl From<&Secp256k1PrivateKey> for Secp256k1PublicKey {
+    fn from(private_key: &Secp256k1PrivateKey) -> Self {
+        Secp256k1PublicKey(libsecp256k1::PublicKey::from_secret_key(&private_key.0))
+    }
+}
+
+impl PublicKey for Secp256k1PublicKey {
+    type PrivateKeyMaterial = Secp256k1PrivateKey;
+}
+
+impl std::hash::Hash for Secp256k1PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl PartialEq for Secp256k1PublicKey {
+    fn eq(&self, other: &Secp256k1PublicKey) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Secp256k1PublicKey {}
+
+impl VerifyingKey for Secp256k1PublicKey {
+    type SigningKeyMaterial = Secp256k1PrivateKey;
+    type SignatureMaterial = Secp256k1Signature;
+}
+
+impl fmt::Display for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256k1PublicKey({})", self)
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PublicKey from its compressed form, checking the point is on the
+    /// curve.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1PublicKey, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_PUBLIC_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        libsecp256k1::PublicKey::parse_slice(bytes, Some(libsecp256k1::PublicKeyFormat::Compressed))
+            .map(Secp256k1PublicKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl Length for Secp256k1PublicKey {
+    fn length(&self) -> usize {
+        SECP256K1_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// Signature Traits //
+//////////////////////
+
+impl Signature for Secp256k1Signature {
+    type VerifyingKeyMaterial = Secp256k1PublicKey;
+    type SigningKeyMaterial = Secp256k1PrivateKey;
+
+    fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        public_key: &Secp256k1PublicKey,
+    ) -> Result<()> {
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)?;
+        Self::verify_arbitrary_msg(self, &bytes, public_key)
+    }
+
+    fn verify_arbitrary_msg(&self, message: &[u8], public_key: &Secp256k1PublicKey) -> Result<()> {
+        Secp256k1Signature::check_malleability(&self.to_bytes())?;
+        if libsecp256k1::verify(&message_digest(message), &self.0, &public_key.0) {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid secp256k1 signature"))
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl Length for Secp256k1Signature {
+    fn length(&self) -> usize {
+        SECP256K1_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1Signature {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl std::hash::Hash for Secp256k1Signature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl TryFrom<&[u8]> for Secp256k1Signature {
+    type Error = CryptoMaterialError;
+
+    fn try_from(bytes: &[u8]) -> std::result::Result<Secp256k1Signature, CryptoMaterialError> {
+        Secp256k1Signature::check_malleability(bytes)?;
+        libsecp256k1::Signature::parse_standard_slice(bytes)
+            .map(Secp256k1Signature)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl PartialEq for Secp256k1Signature {
+    fn eq(&self, other: &Secp256k1Signature) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Secp256k1Signature {}
+
+impl fmt::Display for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256k1Signature({})", self)
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use crate::test_utils::{self, KeyPair};
+
+/// Produces a uniformly random secp256k1 keypair from a seed
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn keypair_strategy() -> impl Strategy<Value = KeyPair<Secp256k1PrivateKey, Secp256k1PublicKey>>
+{
+    test_utils::uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>()
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::prelude::*;
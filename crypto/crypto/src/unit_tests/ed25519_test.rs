@@ -318,6 +318,62 @@ proptest! {
         prop_assert!(Ed25519Signature::batch_verify(&message, signatures).is_err());
     }
 
+    #[test]
+    fn test_batch_verify_strict(
+        message in random_serializable_struct(),
+        keypairs in proptest::array::uniform10(uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>())
+    ) {
+        let mut signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = keypairs.iter().map(|keypair| {
+            (keypair.public_key.clone(), keypair.private_key.sign(&message))
+        }).collect();
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &[]).is_ok());
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &signatures).is_ok());
+        // The coefficients depend on the order of the batch, which must not matter
+        signatures.reverse();
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &signatures).is_ok());
+        // We swap message and signature for the last element,
+        // resulting in an incorrect signature
+        let (key, _sig) = signatures.pop().unwrap();
+        let other_sig = signatures.last().unwrap().clone().1;
+        signatures.push((key, other_sig));
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &signatures).is_err());
+    }
+
+    // The signature of `verify_sig_strict_torsion`, with a public key and an R component of
+    // small order, satisfies the batch equation but must be rejected beforehand, alone or along
+    // with valid signatures.
+    #[test]
+    fn test_batch_verify_strict_torsion(
+        keypair in uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>(),
+        idx in 0usize..8usize
+    ) {
+        let message = CryptoHashable(0);
+        let mut message_bytes = <CryptoHashableHasher as diem_crypto::hash::CryptoHasher>::seed().to_vec();
+        bcs::serialize_into(&mut message_bytes, &message).unwrap();
+
+        let bad_component_1 = curve25519_dalek::edwards::CompressedEdwardsY(EIGHT_TORSION[idx]).decompress().unwrap();
+        let bad_component_2 = bad_component_1.neg();
+        let bad_signature_bytes = [
+            &bad_component_2.compress().to_bytes()[..],
+            &curve25519_dalek::scalar::Scalar::zero().to_bytes()[..]
+        ].concat();
+
+        let dalek_key = ed25519_dalek::PublicKey::from_bytes(&EIGHT_TORSION[idx]).unwrap();
+        let dalek_signature = ed25519_dalek::Signature::from_bytes(&bad_signature_bytes).unwrap();
+        // Seek k = H(R, A, M) ≡ 1 [8]
+        prop_assume!(dalek_key.verify(&message_bytes, &dalek_signature).is_ok());
+
+        let bad_key = Ed25519PublicKey::from_bytes_unchecked(&EIGHT_TORSION[idx]).unwrap();
+        let bad_signature = Ed25519Signature::try_from(&bad_signature_bytes[..]).unwrap();
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &[(bad_key.clone(), bad_signature.clone())]).is_err());
+
+        let signatures = vec![
+            (keypair.public_key.clone(), keypair.private_key.sign(&message)),
+            (bad_key, bad_signature),
+        ];
+        prop_assert!(Ed25519Signature::batch_verify_strict(&message, &signatures).is_err());
+    }
+
     #[test]
     fn test_keys_custom_serialisation(
         keypair in uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>()
@@ -0,0 +1,63 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    secp256k1::{
+        Secp256k1PrivateKey, Secp256k1PublicKey, Secp256k1Signature, SECP256K1_PUBLIC_KEY_LENGTH,
+        SECP256K1_SIGNATURE_LENGTH,
+    },
+    test_utils::{random_serializable_struct, uniform_keypair_strategy, KeyPair},
+    traits::*,
+    CryptoMaterialError,
+};
+use core::convert::TryFrom;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn test_sign_verify(
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>(),
+        other_keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>(),
+        message in random_serializable_struct(),
+    ) {
+        let KeyPair { private_key, public_key } = keypair;
+        let signature = private_key.sign(&message);
+        prop_assert!(signature.verify(&message, &public_key).is_ok());
+        prop_assert!(signature.verify(&message, &other_keypair.public_key).is_err());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip(
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>(),
+        message in random_serializable_struct(),
+    ) {
+        let KeyPair { private_key, public_key } = keypair;
+        let public_key_bytes = public_key.to_bytes();
+        prop_assert_eq!(public_key_bytes.len(), SECP256K1_PUBLIC_KEY_LENGTH);
+        prop_assert_eq!(Secp256k1PublicKey::try_from(&public_key_bytes[..]).unwrap(), public_key);
+        prop_assert_eq!(
+            &Secp256k1PrivateKey::try_from(&private_key.to_bytes()[..]).unwrap(),
+            &private_key
+        );
+
+        let signature = private_key.sign(&message);
+        let signature_bytes = signature.to_bytes();
+        prop_assert_eq!(signature_bytes.len(), SECP256K1_SIGNATURE_LENGTH);
+        prop_assert_eq!(Secp256k1Signature::try_from(&signature_bytes[..]).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_high_s_rejected(
+        keypair in uniform_keypair_strategy::<Secp256k1PrivateKey, Secp256k1PublicKey>(),
+        message in random_serializable_struct(),
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        let mut malleated =
+            libsecp256k1::Signature::parse_standard_slice(&signature.to_bytes()).unwrap();
+        malleated.s = -malleated.s;
+        prop_assert_eq!(
+            Secp256k1Signature::try_from(&malleated.serialize()[..]),
+            Err(CryptoMaterialError::CanonicalRepresentationError)
+        );
+    }
+}
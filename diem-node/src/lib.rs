@@ -5,14 +5,19 @@ use backup_service::start_backup_service;
 use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_config::{
-    config::{NetworkConfig, NodeConfig, PersistableConfig},
+    config::{NetworkConfig, NodeConfig, PersistableConfig, SharedMempoolConfig},
     network_id::NodeNetworkId,
     utils::get_genesis_txn,
 };
+use diem_infallible::RwLock;
 use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
 use diem_logger::{prelude::*, Logger};
-use diem_mempool::gen_mempool_reconfig_subscription;
+use diem_mempool::{
+    gen_mempool_commit_subscription, gen_mempool_reconfig_subscription, ConsensusRequest,
+    ConsensusResponse, MempoolPeerSender, MempoolSnapshotSender, ScriptFilter,
+};
 use diem_metrics::metric_server;
+use diem_telemetry::start_telemetry;
 use diem_time_service::TimeService;
 use diem_types::{
     account_config::diem_root_address, account_state::AccountState, chain_id::ChainId,
@@ -20,9 +25,20 @@ use diem_types::{
 };
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
-use executor::{db_bootstrapper::maybe_bootstrap, Executor};
+use executor::{
+    db_bootstrapper::maybe_bootstrap,
+    pre_execution::{PreExecutionCache, PreExecutor},
+    Executor,
+};
 use executor_types::ChunkExecutor;
-use futures::{channel::mpsc::channel, executor::block_on};
+use futures::{
+    channel::{
+        mpsc::{self, channel},
+        oneshot,
+    },
+    executor::block_on,
+    SinkExt, StreamExt,
+};
 use network_builder::builder::NetworkBuilder;
 use state_sync::bootstrapper::StateSyncBootstrapper;
 use std::{
@@ -35,11 +51,15 @@ use std::{
         Arc,
     },
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
-use storage_interface::DbReaderWriter;
+use storage_interface::{DbReader, DbReaderWriter};
 use storage_service::start_storage_service_with_db;
-use tokio::runtime::{Builder, Runtime};
+use subscription_service::{CommitEventBus, ReconfigBus};
+use tokio::{
+    runtime::{Builder, Runtime},
+    signal::unix::{signal, SignalKind},
+};
 use tokio_stream::wrappers::IntervalStream;
 
 const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
@@ -48,15 +68,20 @@ const MEMPOOL_NETWORK_CHANNEL_BUFFER_SIZE: usize = 1_024;
 
 pub struct DiemHandle {
     _rpc: Runtime,
-    _mempool: Runtime,
+    mempool: Runtime,
+    /// Reloadable settings of the shared mempool
+    shared_mempool_config: Arc<RwLock<SharedMempoolConfig>>,
     _state_sync_bootstrapper: StateSyncBootstrapper,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
     _debug: NodeDebugService,
     _backup: Runtime,
+    _telemetry: Option<Runtime>,
 }
 
-pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
+/// Starts the node. If the path of its config is given, the settings that can change while the
+/// node runs are reloaded from it on SIGHUP.
+pub fn start(config: &NodeConfig, config_path: Option<PathBuf>, log_file: Option<PathBuf>) {
     crash_handler::setup_panic_handler();
 
     let mut logger = diem_logger::Logger::new();
@@ -64,6 +89,7 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
         .channel_size(config.logger.chan_size)
         .is_async(config.logger.is_async)
         .level(config.logger.level)
+        .recent_entries_size(config.logger.recent_entries_size)
         .read_env();
     if let Some(log_file) = log_file {
         logger.printer(Box::new(FileWriter::new(log_file)));
@@ -95,7 +121,13 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
         warn!("failpoints is set in config, but the binary doesn't compile with this feature");
     }
 
-    let _node_handle = setup_environment(&config, logger);
+    let node_handle = setup_environment(&config, logger);
+    if let Some(config_path) = config_path {
+        node_handle.mempool.spawn(reload_config_on_hangup(
+            config_path,
+            node_handle.shared_mempool_config.clone(),
+        ));
+    }
     let term = Arc::new(AtomicBool::new(false));
 
     while !term.load(Ordering::Acquire) {
@@ -103,6 +135,42 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
     }
 }
 
+/// Reloads the config at `config_path` on every SIGHUP, and applies the settings that can change
+/// while the node runs.
+async fn reload_config_on_hangup(
+    config_path: PathBuf,
+    shared_mempool_config: Arc<RwLock<SharedMempoolConfig>>,
+) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!(error = ?e, "Failed to listen for SIGHUP, the config won't be reloaded.");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        let config = match NodeConfig::load(&config_path) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(error = ?e, "Failed to reload the config, keeping the current one.");
+                continue;
+            }
+        };
+        let (current, kept) = {
+            let mut current = shared_mempool_config.write();
+            let kept = current.reload(&config.mempool.shared_mempool);
+            (current.clone(), kept)
+        };
+        info!(config = ?current, "Reloaded the shared mempool config.");
+        for setting in kept {
+            warn!(
+                "The change of shared_mempool.{} takes effect on restart.",
+                setting
+            );
+        }
+    }
+}
+
 fn setup_metrics(peer_id: PeerId, config: &NodeConfig) {
     diem_metrics::dump_all_metrics_to_file_periodically(
         &config.metrics.dir(),
@@ -165,7 +233,11 @@ pub fn load_test_environment(config_path: Option<PathBuf>, random_ports: bool) {
     println!("Diem is running, press ctrl-c to exit");
     println!();
 
-    start(&config, Some(log_file))
+    start(
+        &config,
+        Some(test_config.config_files[0].clone()),
+        Some(log_file),
+    )
 }
 
 // Fetch chain ID from on-chain resource
@@ -193,7 +265,14 @@ fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
     Box::new(Executor::<DiemVM>::new(db))
 }
 
-fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
+fn setup_debug_interface(
+    config: &NodeConfig,
+    logger: Option<Arc<Logger>>,
+    mempool_script_filter: Arc<ScriptFilter>,
+    mempool_snapshot_sender: MempoolSnapshotSender,
+    mempool_peer_sender: MempoolPeerSender,
+    db: Arc<dyn DbReader>,
+) -> NodeDebugService {
     let addr = format!(
         "{}:{}",
         config.debug_interface.address, config.debug_interface.admission_control_node_debug_port,
@@ -203,7 +282,18 @@ fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> No
     .next()
     .unwrap();
 
-    NodeDebugService::new(addr, logger)
+    NodeDebugService::new(
+        addr,
+        logger,
+        mempool_script_filter,
+        mempool_snapshot_sender,
+        mempool_peer_sender,
+        db,
+        config
+            .validator_network
+            .as_ref()
+            .map(|network| network.peer_id()),
+    )
 }
 
 async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
@@ -250,9 +340,46 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
     }
 }
 
-pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
-    let debug_if = setup_debug_interface(&node_config, logger);
+/// Pre-executes the transactions at the top of mempool every `interval_ms`, so that blocks made
+/// of them execute faster.
+async fn pre_execution_job(
+    interval_ms: u64,
+    batch_size: u64,
+    pre_executor: Arc<PreExecutor<DiemVM>>,
+    mut mempool_sender: mpsc::Sender<ConsensusRequest>,
+) {
+    let mut interval =
+        IntervalStream::new(tokio::time::interval(Duration::from_millis(interval_ms)));
+    while interval.next().await.is_some() {
+        let (callback, callback_rcv) = oneshot::channel();
+        let request = ConsensusRequest::GetBlockRequest(batch_size, vec![], callback);
+        if mempool_sender.send(request).await.is_err() {
+            warn!("mempool stopped, stopping pre-execution");
+            return;
+        }
+        let txns = match callback_rcv.await {
+            Ok(Ok(ConsensusResponse::GetBlockResponse(txns))) => txns,
+            _ => continue,
+        };
+        let pre_executor = Arc::clone(&pre_executor);
+        if let Ok(Err(err)) =
+            tokio::task::spawn_blocking(move || pre_executor.pre_execute(txns)).await
+        {
+            warn!("failed to pre-execute mempool transactions: {}", err);
+        }
+    }
+}
 
+pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
+    // operators update the mempool script filter through the debug interface
+    let mempool_script_filter = Arc::new(
+        ScriptFilter::load(node_config.data_dir()).expect("Mempool script filter should load."),
+    );
+    // operators move pending transactions between nodes through the debug interface
+    let (mempool_snapshot_sender, mempool_snapshot_requests) =
+        channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
+    // operators pause broadcasts to peers under maintenance through the debug interface
+    let (mempool_peer_sender, mempool_peer_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
     thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
@@ -263,15 +390,41 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     });
 
     let mut instant = Instant::now();
-    let (diem_db, db_rw) = DbReaderWriter::wrap(
-        DiemDB::open(
+    let mut db = match (
+        &node_config.storage.archive,
+        node_config.storage.cold_storage_dir(),
+    ) {
+        (Some(archive), Some(cold_storage_dir)) => {
+            if node_config.storage.prune_window.is_some() {
+                warn!("Archive node: ignoring prune_window, nothing is pruned.");
+            }
+            DiemDB::open_archive(
+                &node_config.storage.dir(),
+                &cold_storage_dir,
+                archive.hot_storage_bytes,
+                node_config.storage.rocksdb_config,
+            )
+        }
+        _ => DiemDB::open(
             &node_config.storage.dir(),
             false, /* readonly */
             node_config.storage.prune_window,
             node_config.storage.rocksdb_config,
-        )
-        .expect("DB should open."),
-    );
+        ),
+    }
+    .expect("DB should open.");
+    if let Some(versions_per_sec) = node_config.storage.scrub_versions_per_sec {
+        db.start_scrubber(versions_per_sec);
+    }
+    if let Some(num_threads) = node_config.storage.proof_threads {
+        db.enable_parallel_proofs(num_threads)
+            .expect("DB proof pool should start.");
+    }
+    if let Some(checkpoint_interval) = node_config.storage.state_checkpoint_interval {
+        db.enable_state_deltas(checkpoint_interval)
+            .expect("DB state deltas should be enabled.");
+    }
+    let (diem_db, db_rw) = DbReaderWriter::wrap(db);
     let _simple_storage_service = start_storage_service_with_db(&node_config, Arc::clone(&diem_db));
     let backup_service = start_backup_service(
         node_config.storage.backup_service_address,
@@ -287,10 +440,24 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         info!("Genesis txn not provided, it's fine if you don't expect to apply it otherwise please double check config");
     }
 
+    let telemetry = start_telemetry(
+        &node_config.telemetry,
+        env!("CARGO_PKG_VERSION"),
+        Arc::clone(&db_rw.reader),
+    );
+
     debug!(
         "Storage service started in {} ms",
         instant.elapsed().as_millis()
     );
+    let debug_if = setup_debug_interface(
+        &node_config,
+        logger,
+        mempool_script_filter.clone(),
+        mempool_snapshot_sender,
+        mempool_peer_sender,
+        Arc::clone(&db_rw.reader),
+    );
 
     instant = Instant::now();
     let chunk_executor = setup_chunk_executor(db_rw.clone());
@@ -303,16 +470,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
-    let mut reconfig_subscriptions = vec![];
+    // state sync publishes the on-chain configs on the bus at startup and on every change
+    let reconfig_bus = ReconfigBus::new();
 
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
         gen_mempool_reconfig_subscription();
-    reconfig_subscriptions.push(mempool_reconfig_subscription);
+    reconfig_bus.add(mempool_reconfig_subscription);
+    // state sync publishes every commit on the bus; mempool drops committed txns from it
+    let commit_event_bus = CommitEventBus::new();
+    let mempool_commit_events = gen_mempool_commit_subscription(&commit_event_bus);
     // consensus has to subscribe to ALL on-chain configs
     let (consensus_reconfig_subscription, consensus_reconfig_events) =
         gen_consensus_reconfig_subscription();
     if node_config.base.role.is_validator() {
-        reconfig_subscriptions.push(consensus_reconfig_subscription);
+        reconfig_bus.add(consensus_reconfig_subscription);
     }
 
     // Gather all network configs into a single vector.
@@ -367,7 +538,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             );
         }
 
-        reconfig_subscriptions.append(network_builder.reconfig_subscriptions());
+        for subscription in network_builder.reconfig_subscriptions().drain(..) {
+            reconfig_bus.add(subscription);
+        }
 
         network_builders.push(network_builder);
     }
@@ -398,34 +571,43 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // TODO set up on-chain discovery network based on UpstreamConfig.fallback_network
     // and pass network handles to mempool/state sync
 
-    // for state sync to send requests to mempool
-    let (state_sync_to_mempool_sender, state_sync_requests) =
-        channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
     let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
         state_sync_network_handles,
-        state_sync_to_mempool_sender,
+        commit_event_bus.clone(),
         Arc::clone(&db_rw.reader),
         chunk_executor,
         node_config,
         genesis_waypoint,
-        reconfig_subscriptions,
+        reconfig_bus,
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
-    let rpc_runtime = bootstrap_rpc(&node_config, chain_id, diem_db.clone(), mp_client_sender);
+    let rpc_runtime = bootstrap_rpc(
+        &node_config,
+        chain_id,
+        diem_db.clone(),
+        mp_client_sender,
+        &commit_event_bus,
+    );
 
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
     instant = Instant::now();
+    // operators tune shared mempool broadcasts by reloading the config, see `start`
+    let shared_mempool_config = Arc::new(RwLock::new(node_config.mempool.shared_mempool.clone()));
     let mempool = diem_mempool::bootstrap(
         node_config,
         Arc::clone(&db_rw.reader),
         mempool_network_handles,
         mp_client_events,
         consensus_requests,
-        state_sync_requests,
+        mempool_commit_events,
         mempool_reconfig_events,
+        mempool_snapshot_requests,
+        mempool_peer_requests,
+        mempool_script_filter,
+        shared_mempool_config.clone(),
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
@@ -445,9 +627,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             .expect("State sync initialization failure");
         debug!("State sync initialization complete.");
 
+        // Pre-execution warms the executor consensus runs blocks through.
+        let pre_execution_interval_ms = node_config.execution.pre_execution_interval_ms;
+        let pre_execution_cache = if pre_execution_interval_ms > 0 {
+            Some(Arc::new(PreExecutionCache::new(
+                node_config.execution.pre_execution_cache_size,
+            )))
+        } else {
+            None
+        };
+        let pre_execution_mempool_sender = consensus_to_mempool_sender.clone();
+
         // Initialize and start consensus.
         instant = Instant::now();
-        consensus_runtime = Some(start_consensus(
+        let runtime = start_consensus(
             node_config,
             consensus_network_sender,
             consensus_network_events,
@@ -455,8 +648,21 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_to_mempool_sender,
             diem_db,
             consensus_reconfig_events,
-        ));
+            pre_execution_cache.clone(),
+        );
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+
+        if let Some(pre_execution_cache) = pre_execution_cache {
+            let pre_executor =
+                PreExecutor::<DiemVM>::new(Arc::clone(&db_rw.reader), pre_execution_cache);
+            runtime.handle().spawn(pre_execution_job(
+                pre_execution_interval_ms,
+                node_config.execution.pre_execution_batch_size,
+                Arc::new(pre_executor),
+                pre_execution_mempool_sender,
+            ));
+        }
+        consensus_runtime = Some(runtime);
     }
 
     // Spawn a task which will periodically dump some interesting state
@@ -468,10 +674,12 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     DiemHandle {
         _network_runtimes: network_runtimes,
         _rpc: rpc_runtime,
-        _mempool: mempool,
+        mempool,
+        shared_mempool_config,
         _state_sync_bootstrapper: state_sync_bootstrapper,
         _consensus_runtime: consensus_runtime,
         _debug: debug_if,
         _backup: backup_service,
+        _telemetry: telemetry,
     }
 }
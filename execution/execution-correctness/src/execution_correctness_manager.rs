@@ -15,7 +15,7 @@ use diem_global_constants::EXECUTION_KEY;
 use diem_infallible::Mutex;
 use diem_secure_storage::{CryptoStorage, Storage};
 use diem_vm::DiemVM;
-use executor::Executor;
+use executor::{pre_execution::PreExecutionCache, Executor};
 use std::{convert::TryInto, net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
 
@@ -57,6 +57,14 @@ pub struct ExecutionCorrectnessManager {
 
 impl ExecutionCorrectnessManager {
     pub fn new(config: &NodeConfig) -> Self {
+        Self::new_with_pre_execution_cache(config, None)
+    }
+
+    /// Like `new`, with the executor using `pre_execution_cache` when it runs in this process.
+    pub fn new_with_pre_execution_cache(
+        config: &NodeConfig,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
+    ) -> Self {
         if let ExecutionCorrectnessService::Process(remote_service) = &config.execution.service {
             return Self::new_process(
                 remote_service.server_address,
@@ -68,15 +76,24 @@ impl ExecutionCorrectnessManager {
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => {
-                Self::new_local(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Serializer => {
-                Self::new_serializer(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Thread => {
-                Self::new_thread(storage_address, execution_prikey, timeout_ms)
-            }
+            ExecutionCorrectnessService::Local => Self::new_local(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                pre_execution_cache,
+            ),
+            ExecutionCorrectnessService::Serializer => Self::new_serializer(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                pre_execution_cache,
+            ),
+            ExecutionCorrectnessService::Thread => Self::new_thread(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                pre_execution_cache,
+            ),
             _ => unreachable!(
                 "Unimplemented ExecutionCorrectnessService: {:?}",
                 config.execution.service
@@ -88,10 +105,12 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = Box::new(
+            Executor::<DiemVM>::new(StorageClient::new(&storage_address, timeout).into())
+                .with_pre_execution_cache(pre_execution_cache),
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 Mutex::new(LocalService::new(block_executor, execution_prikey)),
@@ -110,10 +129,12 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = Box::new(
+            Executor::<DiemVM>::new(StorageClient::new(&storage_address, timeout).into())
+                .with_pre_execution_cache(pre_execution_cache),
+        );
         let serializer_service = SerializerService::new(block_executor, execution_prikey);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Serializer(Arc::new(
@@ -126,8 +147,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
     ) -> Self {
-        let thread = ThreadService::new(storage_address, execution_prikey, network_timeout);
+        let thread = ThreadService::new(
+            storage_address,
+            execution_prikey,
+            network_timeout,
+            pre_execution_cache,
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Thread(thread),
         }
@@ -39,6 +39,8 @@ impl Process {
             server_addr,
             self.prikey,
             self.network_timeout_ms,
+            // The pre-execution cache lives in the node's process.
+            None,
         );
     }
 }
@@ -8,9 +8,9 @@ use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
 use diem_vm::DiemVM;
-use executor::Executor;
+use executor::{pre_execution::PreExecutionCache, Executor};
 use executor_types::Error;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
 
 pub trait RemoteService {
@@ -30,10 +30,12 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    pre_execution_cache: Option<Arc<PreExecutionCache>>,
 ) {
-    let block_executor = Box::new(Executor::<DiemVM>::new(
-        StorageClient::new(&storage_addr, network_timeout).into(),
-    ));
+    let block_executor = Box::new(
+        Executor::<DiemVM>::new(StorageClient::new(&storage_addr, network_timeout).into())
+            .with_pre_execution_cache(pre_execution_cache),
+    );
     let mut serializer_service = SerializerService::new(block_executor, prikey);
     let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
 
@@ -30,6 +30,6 @@ fn execution_correctness(
     // Timeout value of 5 seconds for network operations.
     let timeout_ms = 5_000;
     let execution_correctness_manager =
-        ExecutionCorrectnessManager::new_local(config.storage.address, prikey, timeout_ms);
+        ExecutionCorrectnessManager::new_local(config.storage.address, prikey, timeout_ms, None);
     (execution_correctness_manager.client(), pubkey)
 }
@@ -10,8 +10,10 @@
 use crate::remote_service::{self, RemoteService};
 use diem_config::utils;
 use diem_crypto::ed25519::Ed25519PrivateKey;
+use executor::pre_execution::PreExecutionCache;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
     thread::{self, JoinHandle},
 };
 
@@ -28,13 +30,20 @@ impl ThreadService {
         storage_addr: SocketAddr,
         prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
     ) -> Self {
         let listen_port = utils::get_available_port();
         let listen_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen_port);
         let server_addr = listen_addr;
 
         let child = thread::spawn(move || {
-            remote_service::execute(storage_addr, listen_addr, prikey, network_timeout)
+            remote_service::execute(
+                storage_addr,
+                listen_addr,
+                prikey,
+                network_timeout,
+                pre_execution_cache,
+            )
         });
 
         Self {
@@ -12,6 +12,7 @@ use diem_crypto::{
     hash::{TransactionAccumulatorHasher, SPARSE_MERKLE_PLACEHOLDER_HASH},
     HashValue,
 };
+use diem_infallible::Mutex;
 use diem_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
@@ -19,13 +20,14 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::{accumulator::InMemoryAccumulator, AccumulatorExtensionProof},
     transaction::{
-        Transaction, TransactionInfo, TransactionListWithProof, TransactionStatus, Version,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionStatus,
+        TransactionToCommit, Version,
     },
 };
 use scratchpad::ProofRead;
 use serde::{Deserialize, Serialize};
 use std::{cmp::max, collections::HashMap, sync::Arc};
-use storage_interface::TreeState;
+use storage_interface::{DbWriter, TreeState};
 
 type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 type SparseMerkleTree = scratchpad::SparseMerkleTree<AccountStateBlob>;
@@ -43,6 +45,25 @@ pub trait ChunkExecutor: Send {
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<Vec<ContractEvent>>;
+
+    /// Executes the transactions on top of the chunks executed before them, whether or not those
+    /// have been committed yet, and returns the result for the caller to commit. Unlike
+    /// `execute_and_commit_chunk`, the proofs are not verified: the caller must have verified
+    /// them against `verified_target_li`. Chunks must be committed in the order they were
+    /// executed.
+    fn execute_verified_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        // Target LI the transactions have been verified against.
+        verified_target_li: LedgerInfoWithSignatures,
+        // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
+        // carrying any epoch change LI.
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ExecutedChunk>;
+
+    /// Forgets the chunks executed by `execute_verified_chunk` and continues from the state in
+    /// storage. Must not be called while any of those chunks is being committed.
+    fn reset_executed_chunks(&mut self) -> Result<()>;
 }
 
 pub trait BlockExecutor: Send {
@@ -291,6 +312,69 @@ impl ExecutedTrees {
     }
 }
 
+/// A chunk of transactions executed by a [`ChunkExecutor`] that has yet to be committed.
+pub struct ExecutedChunk {
+    db: Arc<dyn DbWriter>,
+    first_version: Version,
+    txns_to_commit: Vec<TransactionToCommit>,
+    ledger_info_to_commit: Option<LedgerInfoWithSignatures>,
+    reconfig_events: Vec<ContractEvent>,
+    /// The trees after executing the chunk.
+    executed_trees: ExecutedTrees,
+    /// Where the trees are handed back to the executor once the chunk is committed, so that it
+    /// can read the state they hold from storage from then on.
+    committed_trees: Arc<Mutex<Option<ExecutedTrees>>>,
+}
+
+impl ExecutedChunk {
+    pub fn new(
+        db: Arc<dyn DbWriter>,
+        first_version: Version,
+        txns_to_commit: Vec<TransactionToCommit>,
+        ledger_info_to_commit: Option<LedgerInfoWithSignatures>,
+        reconfig_events: Vec<ContractEvent>,
+        executed_trees: ExecutedTrees,
+        committed_trees: Arc<Mutex<Option<ExecutedTrees>>>,
+    ) -> Self {
+        Self {
+            db,
+            first_version,
+            txns_to_commit,
+            ledger_info_to_commit,
+            reconfig_events,
+            executed_trees,
+            committed_trees,
+        }
+    }
+
+    pub fn first_version(&self) -> Version {
+        self.first_version
+    }
+
+    pub fn ledger_info(&self) -> Option<&LedgerInfoWithSignatures> {
+        self.ledger_info_to_commit.as_ref()
+    }
+
+    /// Returns true if committing the chunk adds neither transactions nor a ledger info to
+    /// storage.
+    pub fn is_empty(&self) -> bool {
+        self.txns_to_commit.is_empty() && self.ledger_info_to_commit.is_none()
+    }
+
+    /// Saves the chunk to storage and returns the reconfiguration events it emitted.
+    pub fn commit(self) -> Result<Vec<ContractEvent>> {
+        if !self.is_empty() {
+            self.db.save_transactions(
+                &self.txns_to_commit,
+                self.first_version,
+                self.ledger_info_to_commit.as_ref(),
+            )?;
+        }
+        *self.committed_trees.lock() = Some(self.executed_trees);
+        Ok(self.reconfig_events)
+    }
+}
+
 pub struct ProofReader {
     account_to_proof: HashMap<HashValue, SparseMerkleProof>,
 }
@@ -8,6 +8,8 @@ use crate::{
         encode_mint_transaction, encode_reconfiguration_transaction, encode_transfer_transaction,
         MockVM, DISCARD_STATUS, KEEP_STATUS,
     },
+    pre_execution::PreExecutor,
+    result_cache::ResultCache,
     BlockExecutor, Executor,
 };
 use diem_crypto::HashValue;
@@ -158,6 +160,107 @@ fn test_executor_one_block() {
     executor.commit_blocks(vec![block_id], ledger_info).unwrap();
 }
 
+#[test]
+fn test_executor_with_pre_execution_cache() {
+    let block_id = gen_block_id(1);
+    let txns = vec![
+        encode_mint_transaction(gen_address(0), 100),
+        encode_transfer_transaction(gen_address(1), gen_address(0), 50),
+    ];
+
+    let mut executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let expected = executor
+        .execute_block((block_id, txns.clone()), parent_block_id)
+        .unwrap();
+
+    let mut executor = TestExecutor::new();
+    let cache = Arc::new(PreExecutionCache::new(10));
+    let pre_executor = PreExecutor::<MockVM>::new(executor.db.reader.clone(), cache.clone());
+    let signed_txns: Vec<_> = txns
+        .iter()
+        .map(|txn| txn.as_signed_user_txn().unwrap().clone())
+        .collect();
+    assert_eq!(pre_executor.pre_execute(signed_txns.clone()).unwrap(), 2);
+    // Transactions already pre-executed against the latest state are skipped.
+    assert_eq!(pre_executor.pre_execute(signed_txns).unwrap(), 0);
+    let (_, state_root) = executor.db.reader.get_latest_state_root().unwrap();
+    assert!(cache
+        .accounts_for(&txns, state_root)
+        .contains_key(&gen_address(0)));
+
+    executor.executor = executor.executor.with_pre_execution_cache(Some(cache));
+    let parent_block_id = executor.committed_block_id();
+    let output = executor
+        .execute_block((block_id, txns), parent_block_id)
+        .unwrap();
+    assert_eq!(output.root_hash(), expected.root_hash());
+}
+
+#[test]
+fn test_executor_with_result_cache() {
+    let block_id = gen_block_id(1);
+    let txns = vec![
+        encode_mint_transaction(gen_address(0), 100),
+        encode_transfer_transaction(gen_address(0), gen_address(1), 50),
+        encode_reconfiguration_transaction(gen_address(2)),
+    ];
+
+    let mut executor = TestExecutor::new();
+    executor.executor = executor.executor.with_result_cache_capacity(0);
+    let parent_block_id = executor.committed_block_id();
+    let expected = executor
+        .execute_block((block_id, txns.clone()), parent_block_id)
+        .unwrap();
+
+    let mut executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let output = executor
+        .execute_block((block_id, txns.clone()), parent_block_id)
+        .unwrap();
+    assert_eq!(output, expected);
+    let parent_state_root = executor.cache.committed_trees().state_root();
+    assert!(executor
+        .result_cache
+        .get(block_id, parent_state_root, &txns)
+        .is_some());
+
+    // As when consensus restarts mid-epoch: the block is executed again from the outputs kept.
+    executor.reset().unwrap();
+    let output = executor
+        .execute_block((block_id, txns.clone()), parent_block_id)
+        .unwrap();
+    assert_eq!(output, expected);
+
+    let ledger_info = gen_ledger_info(3, output.root_hash(), block_id, 1);
+    executor.commit_blocks(vec![block_id], ledger_info).unwrap();
+    assert!(executor
+        .result_cache
+        .get(block_id, parent_state_root, &txns)
+        .is_none());
+}
+
+#[test]
+fn test_result_cache() {
+    let block_id = gen_block_id(1);
+    let state_root = HashValue::random();
+    let txns = vec![encode_mint_transaction(gen_address(0), 100)];
+    let mut cache = ResultCache::new(1);
+    cache.insert(block_id, state_root, &txns, vec![]);
+    assert!(cache.get(block_id, state_root, &txns).is_some());
+
+    // Outputs are only reused on top of the same state, for the same transactions.
+    assert!(cache.get(block_id, HashValue::random(), &txns).is_none());
+    let other_txns = vec![encode_mint_transaction(gen_address(1), 100)];
+    assert!(cache.get(block_id, state_root, &other_txns).is_none());
+
+    // The oldest block is dropped once the cache is full.
+    let other_block_id = gen_block_id(2);
+    cache.insert(other_block_id, state_root, &other_txns, vec![]);
+    assert!(cache.get(block_id, state_root, &txns).is_none());
+    assert!(cache.get(other_block_id, state_root, &other_txns).is_some());
+}
+
 #[test]
 fn test_executor_multiple_blocks() {
     let mut executor = TestExecutor::new();
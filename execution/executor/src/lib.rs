@@ -11,6 +11,8 @@ mod logging;
 pub mod metrics;
 #[cfg(test)]
 mod mock_vm;
+pub mod pre_execution;
+mod result_cache;
 mod speculation_cache;
 mod types;
 
@@ -25,8 +27,11 @@ use crate::{
         DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
         DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_VERIFY_LATENCY,
         DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_EXECUTION_LATENCY,
-        DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SAVE_TX_LATENCY
+        DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SAVE_TX_LATENCY,
+        DIEM_EXECUTOR_RESULT_CACHE,
     },
+    pre_execution::PreExecutionCache,
+    result_cache::{ResultCache, DEFAULT_RESULT_CACHE_CAPACITY},
     speculation_cache::SpeculationCache,
     types::{ProcessedVMOutput, TransactionData},
 };
@@ -35,9 +40,11 @@ use diem_crypto::{
     hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
     HashValue,
 };
+use diem_infallible::Mutex;
 use diem_logger::prelude::*;
-use diem_state_view::StateViewId;
+use diem_state_view::{StateView, StateViewId};
 use diem_types::{
+    access_path::AccessPath,
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
@@ -54,8 +61,8 @@ use diem_types::{
 };
 use diem_vm::VMExecutor;
 use executor_types::{
-    BlockExecutor, ChunkExecutor, Error, ExecutedTrees, ProofReader, StateComputeResult,
-    TransactionReplayer,
+    BlockExecutor, ChunkExecutor, Error, ExecutedChunk, ExecutedTrees, ProofReader,
+    StateComputeResult, TransactionReplayer,
 };
 use fail::fail_point;
 use std::{
@@ -72,6 +79,14 @@ type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 pub struct Executor<V> {
     db: DbReaderWriter,
     cache: SpeculationCache,
+    pre_execution_cache: Option<Arc<PreExecutionCache>>,
+    /// VM outputs of the blocks executed last. Unlike `cache`, it is kept on reset.
+    result_cache: ResultCache,
+    /// The persisted trees chunks executed ahead of their commit read state through to. `None`
+    /// if the synced trees are persisted.
+    chunk_base_trees: Option<ExecutedTrees>,
+    /// The trees of the latest chunk committed since the last chunk was executed.
+    committed_chunk_trees: Arc<Mutex<Option<ExecutedTrees>>>,
     phantom: PhantomData<V>,
 }
 
@@ -94,10 +109,31 @@ where
         Self {
             db,
             cache: SpeculationCache::new_with_startup_info(startup_info),
+            pre_execution_cache: None,
+            result_cache: ResultCache::new(DEFAULT_RESULT_CACHE_CAPACITY),
+            chunk_base_trees: None,
+            committed_chunk_trees: Arc::new(Mutex::new(None)),
             phantom: PhantomData,
         }
     }
 
+    /// Seeds the state views blocks are executed against with the accounts read when their
+    /// transactions were pre-executed, see [`pre_execution`].
+    pub fn with_pre_execution_cache(
+        mut self,
+        pre_execution_cache: Option<Arc<PreExecutionCache>>,
+    ) -> Self {
+        self.pre_execution_cache = pre_execution_cache;
+        self
+    }
+
+    /// Keeps the VM outputs of the last `capacity` blocks executed, so blocks executed again on
+    /// top of the same state aren't re-executed, see [`result_cache`]. 0 disables it.
+    pub fn with_result_cache_capacity(mut self, capacity: usize) -> Self {
+        self.result_cache = ResultCache::new(capacity);
+        self
+    }
+
     fn reset_cache(&mut self) -> Result<(), Error> {
         let startup_info = self
             .db
@@ -105,6 +141,8 @@ where
             .get_startup_info()?
             .ok_or_else(|| format_err!("DB not bootstrapped."))?;
         self.cache = SpeculationCache::new_with_startup_info(startup_info);
+        self.chunk_base_trees = None;
+        *self.committed_chunk_trees.lock() = None;
         Ok(())
     }
 
@@ -112,6 +150,10 @@ where
         Self {
             db,
             cache: SpeculationCache::new_for_db_bootstrapping(tree_state),
+            pre_execution_cache: None,
+            result_cache: ResultCache::new(DEFAULT_RESULT_CACHE_CAPACITY),
+            chunk_base_trees: None,
+            committed_chunk_trees: Arc::new(Mutex::new(None)),
             phantom: PhantomData,
         }
     }
@@ -164,22 +206,15 @@ where
         Ok(None)
     }
 
-    /// Verify input chunk and return transactions to be applied, skipping those already persisted.
-    /// Specifically:
-    ///  1. Verify that input transactions belongs to the ledger represented by the ledger info.
-    ///  2. Verify that transactions to skip match what's already persisted (no fork).
-    ///  3. Return Transactions to be applied.
+    /// Verify input chunk and return transactions to be applied, skipping those already synced.
+    /// The caller must have verified that input transactions belong to the ledger represented by
+    /// the target ledger info. Specifically:
+    ///  1. Verify that transactions to skip match what's already synced (no fork).
+    ///  2. Return Transactions to be applied.
     fn verify_chunk(
         &self,
         txn_list_with_proof: TransactionListWithProof,
-        verified_target_li: &LedgerInfoWithSignatures,
     ) -> Result<(Vec<Transaction>, Vec<TransactionInfo>)> {
-        // 1. Verify that input transactions belongs to the ledger represented by the ledger info.
-        txn_list_with_proof.verify(
-            verified_target_li.ledger_info(),
-            txn_list_with_proof.first_transaction_version,
-        )?;
-
         // Return empty if there's no work to do.
         if txn_list_with_proof.transactions.is_empty() {
             return Ok((Vec::new(), Vec::new()));
@@ -207,7 +242,7 @@ where
             return Ok((Vec::new(), Vec::new()));
         }
 
-        // 2. Verify that skipped transactions match what's already persisted (no fork):
+        // 1. Verify that skipped transactions match what's already synced (no fork):
         let num_txns_to_skip = num_committed_txns - first_txn_version;
 
         debug!(
@@ -244,7 +279,7 @@ where
             "Fork happens because the current synced_trees doesn't match the txn list provided."
         );
 
-        // 3. Return verified transactions to be applied.
+        // 2. Return verified transactions to be applied.
         let mut txns: Vec<_> = txn_list_with_proof.transactions;
         txns.drain(0..num_txns_to_skip as usize);
         let (_, mut txn_infos) = txn_list_with_proof.proof.unpack();
@@ -354,6 +389,7 @@ where
 
             txn_data.push(TransactionData::new(
                 blobs,
+                vm_output.write_set().clone(),
                 vm_output.events().to_vec(),
                 vm_output.status().clone(),
                 state_tree_hash,
@@ -370,6 +406,7 @@ where
                 transactions.len(),
                 TransactionData::new(
                     HashMap::new(),
+                    WriteSet::default(),
                     vec![],
                     TransactionStatus::Retry,
                     current_state_tree.root_hash(),
@@ -416,6 +453,24 @@ where
         ))
     }
 
+    /// Reads into `state_view` the accounts `vm_outputs` write to, and the account holding the
+    /// validator set, which is read when a block ends the epoch.
+    fn read_written_accounts(
+        state_view: &VerifiedStateView,
+        vm_outputs: &[TransactionOutput],
+    ) -> Result<()> {
+        let addresses = vm_outputs
+            .iter()
+            .flat_map(|output| output.write_set().iter())
+            .map(|(access_path, _)| access_path.address)
+            .chain(std::iter::once(on_chain_config::config_address()))
+            .collect::<HashSet<_>>();
+        for address in addresses {
+            state_view.get(&AccessPath::new(address, vec![]))?;
+        }
+        Ok(())
+    }
+
     fn extract_reconfig_events(events: Vec<ContractEvent>) -> Vec<ContractEvent> {
         let new_epoch_event_key = on_chain_config::new_epoch_event_key();
         events
@@ -465,12 +520,17 @@ where
         Vec<Transaction>,
         Vec<TransactionInfo>,
     )> {
-        // Construct a StateView and pass the transactions to VM.
+        // Construct a StateView and pass the transactions to VM. The synced trees are ahead of
+        // storage while chunks executed before this one are still being committed.
+        let persisted_trees = self
+            .chunk_base_trees
+            .as_ref()
+            .unwrap_or_else(|| self.cache.synced_trees());
         let state_view = VerifiedStateView::new(
             StateViewId::ChunkExecution { first_version },
             Arc::clone(&self.db.reader),
-            self.cache.synced_trees().version(),
-            self.cache.synced_trees().state_root(),
+            persisted_trees.version(),
+            persisted_trees.state_root(),
             self.cache.synced_trees().state_tree(),
         );
 
@@ -540,6 +600,7 @@ where
             txns_to_commit.push(TransactionToCommit::new(
                 txn,
                 txn_data.account_blobs().clone(),
+                txn_data.write_set().clone(),
                 txn_data.events().to_vec(),
                 txn_data.gas_used(),
                 recorded_status,
@@ -601,75 +662,50 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
         // 1. Update the cache in executor to be consistent with latest synced state.
         self.reset_cache()?;
 
-        info!(
-            LogSchema::new(LogEntry::ChunkExecutor)
-                .local_synced_version(self.cache.synced_trees().txn_accumulator().num_leaves() - 1)
-                .first_version_in_request(txn_list_with_proof.first_transaction_version)
-                .num_txns_in_request(txn_list_with_proof.transactions.len()),
-            "sync_request_received",
-        );
-        
         // temp time the transaction execution.
         let start_time = Instant::now();
         let metrics_timer_vl = DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_VERIFY_LATENCY.start_timer();
-        // 2. Verify input transaction list.
-        let (transactions, transaction_infos) =
-            self.verify_chunk(txn_list_with_proof, &verified_target_li)?;
-        
+        // 2. Verify that input transactions belong to the ledger represented by the ledger info.
+        txn_list_with_proof.verify(
+            verified_target_li.ledger_info(),
+            txn_list_with_proof.first_transaction_version,
+        )?;
+
         let latency = start_time.elapsed();
         metrics_timer_vl.observe_duration();
         debug!("verify_chunk latency: {:?}", &latency);
 
         // 3. Execute transactions.
-        
-        // temp time the transaction execution.
-        let start_time = Instant::now();
-        let metrics_timer_el = DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_EXECUTION_LATENCY.start_timer();
-
-        let first_version = self.cache.synced_trees().txn_accumulator().num_leaves();
-        let (output, txns_to_commit, reconfig_events) =
-            self.execute_chunk(first_version, transactions, transaction_infos)?;
-
-        let latency = start_time.elapsed();
-        metrics_timer_el.observe_duration();
-        debug!("execute_chunk latency: {:?}", &latency);
-
+        let chunk =
+            self.execute_verified_chunk(txn_list_with_proof, verified_target_li, epoch_change_li)?;
+        if chunk.is_empty() {
+            return chunk.commit();
+        }
 
         // temp time the transaction execution.
         let start_time = Instant::now();
         let metrics_timer_stxl = DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SAVE_TX_LATENCY.start_timer();
 
         // 4. Commit to DB.
-        let ledger_info_to_commit =
-            Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
-        if ledger_info_to_commit.is_none() && txns_to_commit.is_empty() {
-            return Ok(reconfig_events);
-        }
-
         fail_point!("executor::commit_chunk", |_| {
             Err(anyhow::anyhow!("Injected error in commit_chunk"))
         });
-        self.db.writer.save_transactions(
-            &txns_to_commit,
-            first_version,
-            ledger_info_to_commit.as_ref(),
-        )?;
+        let ledger_info_to_commit = chunk.ledger_info().cloned();
+        let reconfig_events = chunk.commit()?;
 
         let latency = start_time.elapsed();
         metrics_timer_stxl.observe_duration();
         debug!("save_transactions latency: {:?}", &latency);
 
         // 5. Cache maintenance.
-        let output_trees = output.executed_trees().clone();
         if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
+            let output_trees = self.cache.synced_trees().clone();
             self.cache.update_block_tree_root(
                 output_trees,
                 ledger_info_with_sigs.ledger_info(),
                 vec![],
                 vec![],
             );
-        } else {
-            self.cache.update_synced_trees(output_trees);
         }
         self.cache.reset();
 
@@ -687,6 +723,61 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
 
         Ok(reconfig_events)
     }
+
+    fn execute_verified_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ExecutedChunk> {
+        // Chunks committed since the last one was executed are in storage now: read the state
+        // they hold from there instead of keeping it in memory.
+        if let Some(committed_trees) = self.committed_chunk_trees.lock().take() {
+            committed_trees.state_tree().prune();
+            self.chunk_base_trees = Some(committed_trees);
+        }
+
+        info!(
+            LogSchema::new(LogEntry::ChunkExecutor)
+                .local_synced_version(self.cache.synced_trees().txn_accumulator().num_leaves() - 1)
+                .first_version_in_request(txn_list_with_proof.first_transaction_version)
+                .num_txns_in_request(txn_list_with_proof.transactions.len()),
+            "sync_request_received",
+        );
+
+        let (transactions, transaction_infos) = self.verify_chunk(txn_list_with_proof)?;
+
+        // temp time the transaction execution.
+        let start_time = Instant::now();
+        let metrics_timer_el = DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_EXECUTION_LATENCY.start_timer();
+
+        let first_version = self.cache.synced_trees().txn_accumulator().num_leaves();
+        let (output, txns_to_commit, reconfig_events) =
+            self.execute_chunk(first_version, transactions, transaction_infos)?;
+
+        let latency = start_time.elapsed();
+        metrics_timer_el.observe_duration();
+        debug!("execute_chunk latency: {:?}", &latency);
+
+        let ledger_info_to_commit =
+            Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
+        let executed_trees = output.executed_trees().clone();
+        self.cache.update_synced_trees(executed_trees.clone());
+
+        Ok(ExecutedChunk::new(
+            Arc::clone(&self.db.writer),
+            first_version,
+            txns_to_commit,
+            ledger_info_to_commit,
+            reconfig_events,
+            executed_trees,
+            Arc::clone(&self.committed_chunk_trees),
+        ))
+    }
+
+    fn reset_executed_chunks(&mut self) -> Result<()> {
+        Ok(self.reset_cache()?)
+    }
 }
 
 impl<V: VMExecutor> TransactionReplayer for Executor<V> {
@@ -792,19 +883,47 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
 
             let parent_block_executed_trees = self.get_executed_trees(parent_block_id)?;
 
-            let state_view = self.get_executed_state_view(
+            let mut state_view = self.get_executed_state_view(
                 StateViewId::BlockExecution { block_id },
                 &parent_block_executed_trees,
             );
+            if let Some(pre_execution_cache) = &self.pre_execution_cache {
+                state_view = state_view.with_prefetched_accounts(
+                    pre_execution_cache
+                        .accounts_for(&transactions, self.cache.committed_trees().state_root()),
+                );
+            }
 
-            let vm_outputs = {
-                let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
-                fail_point!("executor::vm_execute_block", |_| {
-                    Err(Error::from(anyhow::anyhow!(
-                        "Injected error in vm_execute_block"
-                    )))
-                });
-                V::execute_block(transactions.clone(), &state_view).map_err(anyhow::Error::from)?
+            let parent_state_root = parent_block_executed_trees.state_root();
+            let vm_outputs = match self
+                .result_cache
+                .get(block_id, parent_state_root, &transactions)
+            {
+                Some(vm_outputs) => {
+                    DIEM_EXECUTOR_RESULT_CACHE.with_label_values(&["hit"]).inc();
+                    // The VM reads every account it writes to, which the state view records with
+                    // its proof for the state tree update. Read them the same way.
+                    Self::read_written_accounts(&state_view, &vm_outputs)?;
+                    vm_outputs
+                }
+                None => {
+                    DIEM_EXECUTOR_RESULT_CACHE.with_label_values(&["miss"]).inc();
+                    let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
+                    fail_point!("executor::vm_execute_block", |_| {
+                        Err(Error::from(anyhow::anyhow!(
+                            "Injected error in vm_execute_block"
+                        )))
+                    });
+                    let vm_outputs = V::execute_block(transactions.clone(), &state_view)
+                        .map_err(anyhow::Error::from)?;
+                    self.result_cache.insert(
+                        block_id,
+                        parent_state_root,
+                        &transactions,
+                        vm_outputs.clone(),
+                    );
+                    vm_outputs
+                }
             };
 
             let status: Vec<_> = vm_outputs
@@ -891,6 +1010,7 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
                 txns_to_keep.push(TransactionToCommit::new(
                     txn.clone(),
                     txn_data.account_blobs().clone(),
+                    txn_data.write_set().clone(),
                     txn_data.events().to_vec(),
                     txn_data.gas_used(),
                     recorded_status.clone(),
@@ -974,6 +1094,7 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
             block.output().executed_trees().state_tree().prune()
         }
 
+        self.result_cache.prune(&block_ids);
         self.cache.prune(
             ledger_info_with_sigs.ledger_info(),
             committed_txns.clone(),
@@ -1014,6 +1135,7 @@ pub fn process_write_set(
                     }
                     Transaction::UserTransaction(txn) => match txn.payload() {
                         TransactionPayload::Module(_)
+                        | TransactionPayload::ModuleBundle(_)
                         | TransactionPayload::Script(_)
                         | TransactionPayload::ScriptFunction(_) => {
                             bail!("Write set should be a subset of read set.")
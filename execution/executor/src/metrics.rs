@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_metrics::{register_histogram, register_int_counter, Histogram, IntCounter};
+use diem_metrics::{
+    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
+    IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 pub static DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
@@ -97,3 +100,35 @@ pub static DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SAVE_TX_LATENCY: Lazy<Histogra
     )
         .unwrap()
 });
+
+pub static DIEM_EXECUTOR_PRE_EXECUTED_TRANSACTIONS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "diem_executor_pre_executed_transactions",
+        // metric description
+        "The number of mempool transactions pre-executed against the latest committed state"
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_PRE_EXECUTION_CACHE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_executor_pre_execution_cache",
+        // metric description
+        "Lookups of block transactions in the pre-execution cache, by result (hit or miss)",
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_RESULT_CACHE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_executor_result_cache",
+        // metric description
+        "Lookups of executed blocks in the result cache, by result (hit or miss)",
+        &["result"]
+    )
+    .unwrap()
+});
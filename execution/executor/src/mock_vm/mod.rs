@@ -379,5 +379,8 @@ fn decode_transaction(txn: &SignedTransaction) -> MockVMTransaction {
         TransactionPayload::Module(_) => {
             unimplemented!("MockVM does not support Module transaction payload.")
         }
+        TransactionPayload::ModuleBundle(_) => {
+            unimplemented!("MockVM does not support ModuleBundle transaction payload.")
+        }
     }
 }
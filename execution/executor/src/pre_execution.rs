@@ -0,0 +1,173 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Speculative pre-execution of mempool transactions.
+//!
+//! A [`PreExecutor`] executes transactions waiting in mempool against the latest committed state
+//! and records the account states each of them read, with their proofs, in a
+//! [`PreExecutionCache`] keyed by (transaction hash, state root). When a block containing those
+//! transactions arrives while that state is still the latest committed one, the executor seeds
+//! its state view with the recorded accounts instead of reading and verifying them from storage
+//! on the critical path.
+//!
+//! The outputs of pre-execution are not reused: every block starts with a `BlockMetadata`
+//! transaction updating the on-chain time, which every user transaction reads.
+
+use crate::metrics::{DIEM_EXECUTOR_PRE_EXECUTED_TRANSACTIONS, DIEM_EXECUTOR_PRE_EXECUTION_CACHE};
+use anyhow::Result;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_state_view::StateViewId;
+use diem_types::{
+    account_address::{AccountAddress, HashAccountAddress},
+    account_state_blob::AccountStateBlob,
+    transaction::{SignedTransaction, Transaction},
+};
+use diem_vm::VMExecutor;
+use scratchpad::SparseMerkleTree;
+use std::{collections::HashMap, convert::TryFrom, marker::PhantomData, sync::Arc};
+use storage_interface::{
+    state_view::{AccountWithProof, VerifiedStateView},
+    DbReader,
+};
+
+/// Account states read by pre-executed transactions, shared between the [`PreExecutor`] and the
+/// executor.
+pub struct PreExecutionCache {
+    /// Maximum number of transactions kept.
+    capacity: usize,
+    entries: Mutex<HashMap<(HashValue, HashValue), HashMap<AccountAddress, AccountWithProof>>>,
+}
+
+impl PreExecutionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn contains(&self, txn_hash: HashValue, state_root: HashValue) -> bool {
+        self.entries.lock().contains_key(&(txn_hash, state_root))
+    }
+
+    fn insert(
+        &self,
+        txn_hash: HashValue,
+        state_root: HashValue,
+        accounts: HashMap<AccountAddress, AccountWithProof>,
+    ) {
+        let mut entries = self.entries.lock();
+        if entries.len() < self.capacity {
+            entries.insert((txn_hash, state_root), accounts);
+        }
+    }
+
+    /// Drops the entries pre-executed against any other state than `state_root`.
+    fn prune(&self, state_root: HashValue) {
+        self.entries
+            .lock()
+            .retain(|(_, entry_state_root), _| *entry_state_root == state_root);
+    }
+
+    /// Returns the accounts read by the user transactions in `transactions` that were
+    /// pre-executed against `state_root`.
+    pub fn accounts_for(
+        &self,
+        transactions: &[Transaction],
+        state_root: HashValue,
+    ) -> HashMap<AccountAddress, AccountWithProof> {
+        let entries = self.entries.lock();
+        let mut accounts = HashMap::new();
+        for txn in transactions {
+            if let Transaction::UserTransaction(_) = txn {
+                match entries.get(&(txn.hash(), state_root)) {
+                    Some(txn_accounts) => {
+                        DIEM_EXECUTOR_PRE_EXECUTION_CACHE
+                            .with_label_values(&["hit"])
+                            .inc();
+                        accounts.extend(txn_accounts.clone());
+                    }
+                    None => DIEM_EXECUTOR_PRE_EXECUTION_CACHE
+                        .with_label_values(&["miss"])
+                        .inc(),
+                }
+            }
+        }
+        accounts
+    }
+}
+
+/// Executes transactions against the latest committed state to fill a [`PreExecutionCache`].
+pub struct PreExecutor<V> {
+    db: Arc<dyn DbReader>,
+    cache: Arc<PreExecutionCache>,
+    phantom: PhantomData<V>,
+}
+
+impl<V> PreExecutor<V>
+where
+    V: VMExecutor,
+{
+    pub fn new(db: Arc<dyn DbReader>, cache: Arc<PreExecutionCache>) -> Self {
+        Self {
+            db,
+            cache,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Pre-executes the transactions not pre-executed against the latest committed state yet,
+    /// each one on its own, and returns how many were.
+    pub fn pre_execute(&self, transactions: Vec<SignedTransaction>) -> Result<usize> {
+        let (version, state_root) = self.db.get_latest_state_root()?;
+        self.cache.prune(state_root);
+        let smt = SparseMerkleTree::new(state_root);
+
+        let mut num_pre_executed = 0;
+        for txn in transactions {
+            let txn = Transaction::UserTransaction(txn);
+            let txn_hash = txn.hash();
+            if self.cache.contains(txn_hash, state_root) {
+                continue;
+            }
+
+            let state_view = VerifiedStateView::new(
+                StateViewId::Miscellaneous,
+                Arc::clone(&self.db),
+                Some(version),
+                state_root,
+                &smt,
+            );
+            if let Err(err) = V::execute_block(vec![txn], &state_view) {
+                debug!("Failed to pre-execute transaction {}: {:?}", txn_hash, err);
+                continue;
+            }
+
+            let (account_to_state, mut account_to_proof) = state_view.into();
+            let mut accounts = HashMap::new();
+            for (address, account_state) in account_to_state {
+                let proof = match account_to_proof.remove(&address.hash()) {
+                    Some(proof) => proof,
+                    None => continue,
+                };
+                // Accounts that do not exist are read as empty account states.
+                let exists = proof
+                    .leaf()
+                    .map_or(false, |leaf| leaf.key() == address.hash());
+                let blob = if exists {
+                    Some(AccountStateBlob::try_from(&account_state)?)
+                } else {
+                    None
+                };
+                accounts.insert(address, (blob, proof));
+            }
+            self.cache.insert(txn_hash, state_root, accounts);
+            num_pre_executed += 1;
+        }
+
+        DIEM_EXECUTOR_PRE_EXECUTED_TRANSACTIONS.inc_by(num_pre_executed as u64);
+        Ok(num_pre_executed)
+    }
+}
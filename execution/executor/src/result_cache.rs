@@ -0,0 +1,99 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Results of executing blocks, kept so a block executed again is not re-executed by the VM.
+//!
+//! Consensus executes a block again when it retries, and when it restarts mid-epoch after the
+//! executor was reset. The VM outputs of a block, i.e. its write sets and events, only depend on
+//! its transactions and on the state they are executed against, so a [`ResultCache`] keys them by
+//! (block id, parent state root): outputs are only reused when the block is executed on top of
+//! the same state as when they were computed. The transactions they were computed for are kept
+//! too, and must match as well.
+
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_types::transaction::{Transaction, TransactionOutput};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Number of blocks whose results are kept by default.
+pub const DEFAULT_RESULT_CACHE_CAPACITY: usize = 100;
+
+struct CachedResult {
+    txn_hashes: Vec<HashValue>,
+    vm_outputs: Vec<TransactionOutput>,
+}
+
+/// VM outputs of the blocks executed last, keyed by (block id, parent state root).
+pub(crate) struct ResultCache {
+    /// Maximum number of blocks kept. The results of the oldest blocks are dropped first.
+    capacity: usize,
+    entries: HashMap<(HashValue, HashValue), CachedResult>,
+    /// Keys of `entries`, oldest first.
+    order: VecDeque<(HashValue, HashValue)>,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the VM outputs of `transactions`, if they were executed in block `block_id` on
+    /// top of `parent_state_root`.
+    pub fn get(
+        &self,
+        block_id: HashValue,
+        parent_state_root: HashValue,
+        transactions: &[Transaction],
+    ) -> Option<Vec<TransactionOutput>> {
+        let result = self.entries.get(&(block_id, parent_state_root))?;
+        if !itertools::equal(
+            result.txn_hashes.iter().copied(),
+            transactions.iter().map(CryptoHash::hash),
+        ) {
+            return None;
+        }
+        Some(result.vm_outputs.clone())
+    }
+
+    pub fn insert(
+        &mut self,
+        block_id: HashValue,
+        parent_state_root: HashValue,
+        transactions: &[Transaction],
+        vm_outputs: Vec<TransactionOutput>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (block_id, parent_state_root);
+        let result = CachedResult {
+            txn_hashes: transactions.iter().map(CryptoHash::hash).collect(),
+            vm_outputs,
+        };
+        if self.entries.insert(key, result).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Drops the results of `block_ids`, once they are committed and won't be executed again.
+    pub fn prune(&mut self, block_ids: &[HashValue]) {
+        let block_ids: HashSet<_> = block_ids.iter().collect();
+        let entries = &mut self.entries;
+        self.order.retain(|key| {
+            let committed = block_ids.contains(&key.0);
+            if committed {
+                entries.remove(key);
+            }
+            !committed
+        });
+    }
+}
@@ -11,6 +11,7 @@ use diem_types::{
     epoch_state::EpochState,
     proof::accumulator::InMemoryAccumulator,
     transaction::{TransactionStatus, Version},
+    write_set::WriteSet,
 };
 use executor_types::{ExecutedTrees, StateComputeResult};
 use std::{collections::HashMap, sync::Arc};
@@ -25,6 +26,9 @@ pub struct TransactionData {
     /// new blob.
     account_blobs: HashMap<AccountAddress, AccountStateBlob>,
 
+    /// The write set generated by the VM, kept so it can be persisted alongside the transaction.
+    write_set: WriteSet,
+
     /// The list of events emitted during this transaction.
     events: Vec<ContractEvent>,
 
@@ -47,6 +51,7 @@ pub struct TransactionData {
 impl TransactionData {
     pub fn new(
         account_blobs: HashMap<AccountAddress, AccountStateBlob>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         status: TransactionStatus,
         state_root_hash: HashValue,
@@ -56,6 +61,7 @@ impl TransactionData {
     ) -> Self {
         TransactionData {
             account_blobs,
+            write_set,
             events,
             status,
             state_root_hash,
@@ -69,6 +75,10 @@ impl TransactionData {
         &self.account_blobs
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -0,0 +1,103 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Indexer following the committed transactions of a node through its JSON RPC endpoint and
+//! denormalizing their transactions, events and account changes into a SQLite database, so block
+//! explorers can query them by sender, event key, account or resource.
+//!
+//! The database records a checkpoint, the version of the next transaction to index, which moves
+//! in the same database transaction as the rows of each batch indexed. An indexer stopped at any
+//! point resumes from its checkpoint without missing or duplicating transactions. See the README
+//! for the schema.
+
+mod store;
+pub use store::Store;
+
+use anyhow::Result;
+use diem_client::Client;
+use diem_logger::prelude::*;
+use std::{convert::TryFrom, time::Duration};
+
+const DEFAULT_BATCH_SIZE: u64 = 500;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct Indexer {
+    client: Client,
+    store: Store,
+    batch_size: u64,
+    poll_interval: Duration,
+}
+
+impl Indexer {
+    pub fn new(client: Client, store: Store) -> Self {
+        Self {
+            client,
+            store,
+            batch_size: DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Number of transactions requested at once, at most the page size limit of the node.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// How long to wait before asking for new transactions again once the indexer has caught up,
+    /// or after a failed request.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the version of the next transaction to index.
+    pub fn checkpoint(&self) -> Result<u64> {
+        self.store.next_version()
+    }
+
+    /// Indexes the batch of committed transactions following the checkpoint and returns the
+    /// number of transactions indexed, zero if the indexer has caught up with the ledger.
+    pub async fn index_next_batch(&mut self) -> Result<usize> {
+        let start_version = self.store.next_version()?;
+        let transactions = self
+            .client
+            .get_transactions(start_version, self.batch_size, false)
+            .await?
+            .into_inner();
+        if transactions.is_empty() {
+            return Ok(0);
+        }
+        let outputs = self
+            .client
+            .get_transaction_outputs(start_version, u64::try_from(transactions.len())?)
+            .await?
+            .into_inner();
+        self.store.index(&transactions, &outputs)?;
+        Ok(transactions.len())
+    }
+
+    /// Indexes committed transactions forever, retrying failed requests after the poll interval.
+    /// Only errors of the database stop the indexer.
+    pub async fn run(&mut self) -> Result<()> {
+        loop {
+            match self.index_next_batch().await {
+                Ok(0) => tokio::time::sleep(self.poll_interval).await,
+                Ok(num_indexed) => {
+                    debug!(
+                        "indexed {} transactions, next version {}",
+                        num_indexed,
+                        self.checkpoint()?
+                    );
+                }
+                Err(error) => match error.downcast::<diem_client::Error>() {
+                    Ok(error) => {
+                        warn!("failed to fetch transactions: {}", error);
+                        tokio::time::sleep(self.poll_interval).await;
+                    }
+                    Err(error) => return Err(error),
+                },
+            }
+        }
+    }
+}
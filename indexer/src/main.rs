@@ -0,0 +1,46 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use diem_client::Client;
+use diem_indexer::{Indexer, Store};
+use std::{path::PathBuf, time::Duration};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Indexes the transactions of a node into a SQLite database")]
+struct Opt {
+    /// Full URL address of the node's JSON RPC endpoint
+    #[structopt(short = "u", long)]
+    url: String,
+    /// Path of the SQLite database, created if it doesn't exist
+    #[structopt(short = "d", long, parse(from_os_str))]
+    db: PathBuf,
+    /// Version to start indexing from when the database is empty
+    #[structopt(long)]
+    start_version: Option<u64>,
+    /// Number of transactions requested at once
+    #[structopt(long, default_value = "500")]
+    batch_size: u64,
+    /// Milliseconds to wait before polling again once caught up with the ledger
+    #[structopt(long, default_value = "1000")]
+    poll_interval_ms: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let opt = Opt::from_args();
+    diem_logger::Logger::new().init();
+
+    let mut store = Store::open(&opt.db)?;
+    if let Some(start_version) = opt.start_version {
+        // Restarts resume from the checkpoint instead
+        if store.next_version()? == 0 {
+            store.start_at(start_version)?;
+        }
+    }
+    let mut indexer = Indexer::new(Client::new(opt.url), store)
+        .with_batch_size(opt.batch_size)
+        .with_poll_interval(Duration::from_millis(opt.poll_interval_ms));
+    indexer.run().await
+}
@@ -0,0 +1,47 @@
+-- Copyright (c) The Diem Core Contributors
+-- SPDX-License-Identifier: Apache-2.0
+
+-- Schema of the indexer database, see the README for a description of the columns.
+
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    next_version INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    version INTEGER PRIMARY KEY,
+    hash TEXT NOT NULL,
+    type TEXT NOT NULL,
+    sender TEXT,
+    sequence_number INTEGER,
+    script TEXT,
+    timestamp_usecs INTEGER,
+    vm_status TEXT NOT NULL,
+    gas_used INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS transactions_by_sender ON transactions (sender, sequence_number);
+
+CREATE TABLE IF NOT EXISTS events (
+    key TEXT NOT NULL,
+    sequence_number INTEGER NOT NULL,
+    transaction_version INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (key, sequence_number)
+);
+
+CREATE INDEX IF NOT EXISTS events_by_version ON events (transaction_version);
+
+CREATE TABLE IF NOT EXISTS account_changes (
+    transaction_version INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    path BLOB NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value BLOB,
+    PRIMARY KEY (transaction_version, address, path)
+);
+
+CREATE INDEX IF NOT EXISTS account_changes_by_address ON account_changes (address, name, transaction_version);
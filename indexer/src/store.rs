@@ -0,0 +1,305 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err, Result};
+use diem_client::views::{
+    EventView, TransactionDataView, TransactionOutputView, TransactionView, WriteSetChangeView,
+};
+use diem_types::access_path::Path;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+const SCHEMA: &str = include_str!("schema.sql");
+
+/// The indexer database, holding the denormalized transactions, events and account changes of
+/// the versions before its checkpoint.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and its tables if they don't exist yet.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Returns the checkpoint of the database, i.e. the version of the next transaction to index.
+    pub fn next_version(&self) -> Result<u64> {
+        let next_version: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT next_version FROM checkpoint WHERE id = 0",
+                params![],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(next_version.map_or(Ok(0), u64::try_from)?)
+    }
+
+    /// Sets the checkpoint of an empty database, to index the ledger from `version` on.
+    pub fn start_at(&mut self, version: u64) -> Result<()> {
+        ensure!(
+            self.next_version()? == 0,
+            "cannot move the checkpoint of a database already indexed"
+        );
+        set_next_version(&self.conn, version)
+    }
+
+    /// Indexes `transactions` and their `outputs`, the transactions committed right after the
+    /// checkpoint, and moves the checkpoint past them, all or nothing.
+    pub fn index(
+        &mut self,
+        transactions: &[TransactionView],
+        outputs: &[TransactionOutputView],
+    ) -> Result<()> {
+        ensure!(
+            transactions.len() == outputs.len(),
+            "got {} outputs for {} transactions",
+            outputs.len(),
+            transactions.len()
+        );
+        let mut next_version = self.next_version()?;
+        let db_txn = self.conn.transaction()?;
+        for (transaction, output) in transactions.iter().zip(outputs) {
+            ensure!(
+                transaction.version == next_version && output.version == next_version,
+                "expected transaction {}, got transaction {} and output {}",
+                next_version,
+                transaction.version,
+                output.version
+            );
+            insert_transaction(&db_txn, transaction)?;
+            for event in &output.events {
+                insert_event(&db_txn, event)?;
+            }
+            for change in &output.write_set {
+                insert_account_change(&db_txn, next_version, change)?;
+            }
+            next_version += 1;
+        }
+        set_next_version(&db_txn, next_version)?;
+        db_txn.commit()?;
+        Ok(())
+    }
+}
+
+fn set_next_version(conn: &Connection, next_version: u64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO checkpoint (id, next_version) VALUES (0, ?1)
+         ON CONFLICT (id) DO UPDATE SET next_version = excluded.next_version",
+        params![int(next_version)?],
+    )?;
+    Ok(())
+}
+
+fn insert_transaction(db_txn: &Transaction, transaction: &TransactionView) -> Result<()> {
+    let (sender, sequence_number, script, timestamp_usecs) = match &transaction.transaction {
+        TransactionDataView::UserTransaction {
+            sender,
+            sequence_number,
+            script,
+            ..
+        } => (
+            Some(sender.to_hex()),
+            Some(int(*sequence_number)?),
+            Some(script.r#type.clone()),
+            None,
+        ),
+        TransactionDataView::BlockMetadata { timestamp_usecs } => {
+            (None, None, None, Some(int(*timestamp_usecs)?))
+        }
+        TransactionDataView::WriteSet {} | TransactionDataView::UnknownTransaction => {
+            (None, None, None, None)
+        }
+    };
+    let (r#type, data) = tagged(&transaction.transaction)?;
+    let (vm_status, _) = tagged(&transaction.vm_status)?;
+    db_txn.execute(
+        "INSERT INTO transactions (version, hash, type, sender, sequence_number, script,
+             timestamp_usecs, vm_status, gas_used, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            int(transaction.version)?,
+            transaction.hash.to_hex(),
+            r#type,
+            sender,
+            sequence_number,
+            script,
+            timestamp_usecs,
+            vm_status,
+            int(transaction.gas_used)?,
+            data,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_event(db_txn: &Transaction, event: &EventView) -> Result<()> {
+    let (r#type, data) = tagged(&event.data)?;
+    db_txn.execute(
+        "INSERT INTO events (key, sequence_number, transaction_version, type, data)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            event.key.to_string(),
+            int(event.sequence_number)?,
+            int(event.transaction_version)?,
+            r#type,
+            data,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_account_change(
+    db_txn: &Transaction,
+    version: u64,
+    change: &WriteSetChangeView,
+) -> Result<()> {
+    let (kind, name) = match bcs::from_bytes::<Path>(&change.path) {
+        Ok(Path::Resource(struct_tag)) => ("resource", struct_tag.to_string()),
+        Ok(Path::Code(module_id)) => ("module", module_id.to_string()),
+        Err(_) => ("unknown", String::new()),
+    };
+    db_txn.execute(
+        "INSERT INTO account_changes (transaction_version, address, path, kind, name, value)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            int(version)?,
+            change.address.to_hex(),
+            change.path.as_ref(),
+            kind,
+            name,
+            change.value.as_deref(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Returns the `type` tag of a view serialized as an internally tagged enum, and the JSON
+/// serialization of the whole view.
+fn tagged<T: Serialize>(view: &T) -> Result<(String, String)> {
+    let value = serde_json::to_value(view)?;
+    let r#type = value
+        .get("type")
+        .and_then(|r#type| r#type.as_str())
+        .ok_or_else(|| format_err!("view without a type tag: {}", value))?
+        .to_string();
+    Ok((r#type, value.to_string()))
+}
+
+/// SQLite integers are signed.
+fn int(value: u64) -> Result<i64> {
+    Ok(i64::try_from(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_client::views::{BytesView, EventDataView, VMStatusView};
+    use diem_crypto::HashValue;
+    use diem_types::{account_config::diem_root_address, event::EventKey};
+
+    fn transaction(version: u64) -> TransactionView {
+        TransactionView {
+            version,
+            transaction: TransactionDataView::BlockMetadata {
+                timestamp_usecs: version * 1000,
+            },
+            hash: HashValue::zero(),
+            bytes: BytesView::new(vec![]),
+            events: vec![],
+            vm_status: VMStatusView::Executed,
+            gas_used: 0,
+        }
+    }
+
+    fn output(version: u64) -> TransactionOutputView {
+        TransactionOutputView {
+            version,
+            write_set: vec![WriteSetChangeView {
+                address: diem_root_address(),
+                path: BytesView::new(vec![0xff]),
+                value: None,
+            }],
+            events: vec![EventView {
+                key: EventKey::new_from_address(&diem_root_address(), version),
+                sequence_number: 0,
+                transaction_version: version,
+                data: EventDataView::NewBlock {
+                    round: version,
+                    proposer: diem_root_address(),
+                    proposed_time: version * 1000,
+                },
+            }],
+            gas_used: 0,
+            vm_status: VMStatusView::Executed,
+        }
+    }
+
+    fn count(store: &Store, table: &str) -> i64 {
+        store
+            .conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {}", table),
+                params![],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_index() {
+        let mut store = Store::open_in_memory().unwrap();
+        assert_eq!(store.next_version().unwrap(), 0);
+
+        store
+            .index(&[transaction(0), transaction(1)], &[output(0), output(1)])
+            .unwrap();
+        assert_eq!(store.next_version().unwrap(), 2);
+        assert_eq!(count(&store, "transactions"), 2);
+        assert_eq!(count(&store, "events"), 2);
+        assert_eq!(count(&store, "account_changes"), 2);
+
+        let (r#type, timestamp_usecs): (String, i64) = store
+            .conn
+            .query_row(
+                "SELECT type, timestamp_usecs FROM transactions WHERE version = 1",
+                params![],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(r#type, "blockmetadata");
+        assert_eq!(timestamp_usecs, 1000);
+    }
+
+    #[test]
+    fn test_index_rejects_gaps() {
+        let mut store = Store::open_in_memory().unwrap();
+        store.index(&[transaction(1)], &[output(1)]).unwrap_err();
+        store.index(&[transaction(0)], &[output(1)]).unwrap_err();
+        store.index(&[transaction(0)], &[]).unwrap_err();
+
+        // Failed batches leave nothing behind
+        assert_eq!(store.next_version().unwrap(), 0);
+        assert_eq!(count(&store, "transactions"), 0);
+    }
+
+    #[test]
+    fn test_start_at() {
+        let mut store = Store::open_in_memory().unwrap();
+        store.start_at(5).unwrap();
+        store.index(&[transaction(5)], &[output(5)]).unwrap();
+        assert_eq!(store.next_version().unwrap(), 6);
+        store.start_at(0).unwrap_err();
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Identification and rate limiting of the consumers of the JSON RPC endpoint.
+//!
+//! Consumers identify themselves with the API key they send in the `X-Diem-Api-Key` header.
+//! Each one is limited in the requests it sends per second and in the credits those requests
+//! spend per second, queries of ranges spending one credit per item requested. Requests without
+//! an API key are served as the anonymous consumer, if the node operator allows them.
+//!
+//! On permissioned networks, consumers may also be restricted to submitting the transactions of
+//! some senders, so an API key only lets its holder transact on behalf of their own accounts.
+
+use crate::counters;
+use diem_config::config::{ApiKeyConfig, ApiKeysConfig};
+use diem_infallible::Mutex;
+use diem_json_rpc_types::{errors::JsonRpcError, request::MethodRequest};
+use diem_rate_limiter::rate_limit::Bucket;
+use diem_types::account_address::AccountAddress;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
+
+pub const X_DIEM_API_KEY: &str = "X-Diem-Api-Key";
+
+// Counter labels for API consumer metrics
+const LABEL_ALLOWED: &str = "allowed";
+const LABEL_THROTTLED: &str = "throttled";
+const LABEL_REQUEST: &str = "request";
+const LABEL_RESPONSE: &str = "response";
+
+/// The consumers of the JSON RPC endpoint, keyed by API key.
+pub(crate) struct ApiKeys {
+    anonymous: Option<Consumer>,
+    consumers: HashMap<String, Consumer>,
+}
+
+impl ApiKeys {
+    pub fn new(config: &ApiKeysConfig) -> Self {
+        Self {
+            anonymous: config.anonymous.as_ref().map(Consumer::new),
+            consumers: config
+                .keys
+                .iter()
+                .map(|(api_key, consumer)| (api_key.clone(), Consumer::new(consumer)))
+                .collect(),
+        }
+    }
+
+    /// Identifies the consumer sending `api_key` and takes `num_requests` requests from its
+    /// request rate.
+    pub fn admit(
+        &self,
+        api_key: Option<&str>,
+        num_requests: usize,
+    ) -> Result<&Consumer, JsonRpcError> {
+        let consumer = match api_key {
+            Some(api_key) => self.consumers.get(api_key),
+            None => self.anonymous.as_ref(),
+        }
+        .ok_or_else(JsonRpcError::invalid_api_key)?;
+        consumer.acquire_requests(num_requests)?;
+        Ok(consumer)
+    }
+}
+
+/// A consumer of the JSON RPC endpoint with the token buckets of its rate limits, if it has any.
+pub(crate) struct Consumer {
+    name: String,
+    requests: Option<Mutex<Bucket>>,
+    credits: Option<Mutex<Bucket>>,
+    /// Senders of the transactions the consumer may submit, any if `None`.
+    allowed_senders: Option<HashSet<AccountAddress>>,
+}
+
+impl Consumer {
+    fn new(config: &ApiKeyConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            requests: bucket("json-rpc-requests", &config.name, config.requests_per_sec),
+            credits: bucket("json-rpc-credits", &config.name, config.credits_per_sec),
+            allowed_senders: config
+                .allowed_senders
+                .as_ref()
+                .map(|senders| senders.iter().copied().collect()),
+        }
+    }
+
+    fn acquire_requests(&self, num_requests: usize) -> Result<(), JsonRpcError> {
+        let allowed = acquire(&self.requests, num_requests);
+        counters::API_CONSUMER_REQUESTS
+            .with_label_values(&[&self.name, result_label(allowed)])
+            .inc_by(num_requests as u64);
+        if allowed {
+            Ok(())
+        } else {
+            Err(JsonRpcError::rate_limited("requests"))
+        }
+    }
+
+    /// Takes `credits` from the consumer's credit rate.
+    pub fn acquire_credits(&self, credits: u64) -> Result<(), JsonRpcError> {
+        let allowed = acquire(
+            &self.credits,
+            usize::try_from(credits).unwrap_or(usize::MAX),
+        );
+        counters::API_CONSUMER_CREDITS
+            .with_label_values(&[&self.name, result_label(allowed)])
+            .inc_by(credits);
+        if allowed {
+            Ok(())
+        } else {
+            Err(JsonRpcError::rate_limited("credits"))
+        }
+    }
+
+    /// Checks that the consumer may send `request`: transactions it submits, and sequence numbers
+    /// it allocates, must be of a sender it's allowed.
+    pub fn authorize(&self, request: &MethodRequest) -> Result<(), JsonRpcError> {
+        let allowed_senders = match &self.allowed_senders {
+            Some(allowed_senders) => allowed_senders,
+            None => return Ok(()),
+        };
+        let sender = match request {
+            MethodRequest::Submit(params) => params.data.sender(),
+            MethodRequest::AllocateSequenceNumber(params) => params.account,
+            _ => return Ok(()),
+        };
+        if allowed_senders.contains(&sender) {
+            Ok(())
+        } else {
+            counters::API_CONSUMER_REJECTED_SUBMISSIONS
+                .with_label_values(&[&self.name])
+                .inc();
+            Err(JsonRpcError::sender_not_allowed(sender))
+        }
+    }
+
+    /// Records the size of a request served to the consumer and of its response.
+    pub fn record_bytes(&self, request_bytes: u64, response_bytes: u64) {
+        counters::API_CONSUMER_BYTES
+            .with_label_values(&[&self.name, LABEL_REQUEST])
+            .inc_by(request_bytes);
+        counters::API_CONSUMER_BYTES
+            .with_label_values(&[&self.name, LABEL_RESPONSE])
+            .inc_by(response_bytes);
+    }
+}
+
+/// A full bucket holding a second worth of tokens, or none if `rate` is unlimited.
+fn bucket(label: &str, name: &str, rate: Option<usize>) -> Option<Mutex<Bucket>> {
+    rate.map(|rate| {
+        Mutex::new(Bucket::new(
+            label.to_string(),
+            name.to_string(),
+            name.to_string(),
+            rate,
+            rate,
+            rate,
+            None,
+        ))
+    })
+}
+
+fn acquire(bucket: &Option<Mutex<Bucket>>, tokens: usize) -> bool {
+    bucket.as_ref().map_or(true, |bucket| {
+        bucket.lock().acquire_all_tokens(tokens).is_ok()
+    })
+}
+
+fn result_label(allowed: bool) -> &'static str {
+    if allowed {
+        LABEL_ALLOWED
+    } else {
+        LABEL_THROTTLED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_json_rpc_types::{
+        compat::TransactionEncoding,
+        errors::ServerCode,
+        request::{AllocateSequenceNumberParams, GetMetadataParams, SubmitParams},
+    };
+    use diem_types::test_helpers::transaction_test_helpers::get_test_signed_txn;
+    use std::collections::BTreeMap;
+
+    fn consumer(name: &str, requests_per_sec: Option<usize>) -> ApiKeyConfig {
+        ApiKeyConfig {
+            name: name.to_string(),
+            requests_per_sec,
+            credits_per_sec: Some(10),
+            allowed_senders: None,
+        }
+    }
+
+    #[test]
+    fn test_admit() {
+        let mut keys = BTreeMap::new();
+        keys.insert("key".to_string(), consumer("tier1", Some(2)));
+        let api_keys = ApiKeys::new(&ApiKeysConfig {
+            anonymous: Some(consumer("anonymous", None)),
+            keys,
+        });
+
+        assert_eq!(api_keys.admit(Some("key"), 2).unwrap().name, "tier1");
+        let err = api_keys.admit(Some("key"), 1).err().unwrap();
+        assert_eq!(err.code, ServerCode::RateLimited as i16);
+        let err = api_keys.admit(Some("unknown"), 1).err().unwrap();
+        assert_eq!(err.code, ServerCode::InvalidApiKey as i16);
+
+        let anonymous = api_keys.admit(None, 100).unwrap();
+        anonymous.acquire_credits(10).unwrap();
+        anonymous.acquire_credits(1).unwrap_err();
+    }
+
+    #[test]
+    fn test_anonymous_requests_rejected() {
+        let api_keys = ApiKeys::new(&ApiKeysConfig {
+            anonymous: None,
+            keys: BTreeMap::new(),
+        });
+        let err = api_keys.admit(None, 1).err().unwrap();
+        assert_eq!(err.code, ServerCode::InvalidApiKey as i16);
+    }
+
+    #[test]
+    fn test_authorize() {
+        let allowed_sender = AccountAddress::random();
+        let mut keys = BTreeMap::new();
+        keys.insert(
+            "key".to_string(),
+            ApiKeyConfig {
+                allowed_senders: Some(vec![allowed_sender]),
+                ..consumer("org", None)
+            },
+        );
+        let api_keys = ApiKeys::new(&ApiKeysConfig {
+            anonymous: Some(consumer("anonymous", None)),
+            keys,
+        });
+        let submit = |sender| {
+            let private_key = Ed25519PrivateKey::generate_for_testing();
+            let public_key = private_key.public_key();
+            MethodRequest::Submit(SubmitParams {
+                data: get_test_signed_txn(sender, 0, &private_key, public_key, None),
+                encoding: TransactionEncoding::Bcs,
+                callback_url: None,
+            })
+        };
+
+        let consumer = api_keys.admit(Some("key"), 1).unwrap();
+        consumer.authorize(&submit(allowed_sender)).unwrap();
+        let err = consumer
+            .authorize(&submit(AccountAddress::random()))
+            .unwrap_err();
+        assert_eq!(err.code, ServerCode::SenderNotAllowed as i16);
+        let allocate = |account| {
+            MethodRequest::AllocateSequenceNumber(AllocateSequenceNumberParams { account })
+        };
+        consumer.authorize(&allocate(allowed_sender)).unwrap();
+        consumer
+            .authorize(&allocate(AccountAddress::random()))
+            .unwrap_err();
+        consumer
+            .authorize(&MethodRequest::GetMetadata(GetMetadataParams {
+                version: None,
+            }))
+            .unwrap();
+
+        // consumers without allowed senders submit for any sender
+        let anonymous = api_keys.admit(None, 1).unwrap();
+        anonymous
+            .authorize(&submit(AccountAddress::random()))
+            .unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of the responses to clients accepting it.
+//!
+//! Clients list the encodings they accept in the `Accept-Encoding` header. Responses from the
+//! configured size on are compressed with zstd when accepted, otherwise with gzip, and carry the
+//! encoding in the `Content-Encoding` header. Responses to clients accepting neither, and smaller
+//! responses, are sent as is.
+
+use crate::counters;
+use diem_config::config::ResponseCompressionConfig;
+use diem_logger::warn;
+use flate2::{write::GzEncoder, Compression};
+use hyper::{body::HttpBody, Body};
+use std::io::Write;
+use warp::{
+    http::header::{self, HeaderValue},
+    reply::Response,
+};
+
+/// Encodings responses may be compressed with, by order of preference.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Encoding {
+    Zstd,
+    Gzip,
+}
+
+impl Encoding {
+    const PREFERENCE: [Encoding; 2] = [Encoding::Zstd, Encoding::Gzip];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// The preferred encoding among those `accept_encoding`, the value of an `Accept-Encoding`
+    /// header, accepts. Encodings with a zero quality value are refused; other quality values
+    /// don't change the preference.
+    pub fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+        let accepted: Vec<&str> = accept_encoding
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let coding = parts.next()?;
+                let refused = parts.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .map_or(false, |q| q <= 0.0)
+                });
+                (!refused).then(|| coding)
+            })
+            .collect();
+        Self::PREFERENCE.iter().copied().find(|encoding| {
+            accepted
+                .iter()
+                .any(|coding| coding.eq_ignore_ascii_case(encoding.as_str()))
+        })
+    }
+}
+
+pub(crate) struct ResponseCompression {
+    config: ResponseCompressionConfig,
+}
+
+impl ResponseCompression {
+    pub fn new(config: &ResponseCompressionConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// Compresses `response` if the client accepts an encoding, per `accept_encoding`, and the
+    /// body is large enough. The response is returned as is otherwise, or if compression fails.
+    pub async fn compress(&self, response: Response, accept_encoding: Option<&str>) -> Response {
+        let min_response_bytes = match self.config.min_response_bytes {
+            Some(min_response_bytes) => min_response_bytes,
+            None => return response,
+        };
+        let (mut parts, body) = response.into_parts();
+        // the response varies with the encodings accepted, whether this one is compressed or not
+        parts
+            .headers
+            .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+        let encoding = accept_encoding.and_then(Encoding::negotiate);
+        let size = body.size_hint().exact();
+        let encoding = match encoding {
+            Some(encoding) if size.map_or(true, |size| size >= min_response_bytes as u64) => {
+                encoding
+            }
+            _ => return Response::from_parts(parts, body),
+        };
+
+        let bytes = match hyper::body::to_bytes(body).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!("Failed to read response body: {}", err);
+                return Response::from_parts(parts, Body::empty());
+            }
+        };
+        if bytes.len() < min_response_bytes {
+            return Response::from_parts(parts, bytes.into());
+        }
+        match self.encode(encoding, &bytes) {
+            Ok(compressed) => {
+                counters::RESPONSE_COMPRESSION_BYTES
+                    .with_label_values(&[encoding.as_str(), "uncompressed"])
+                    .inc_by(bytes.len() as u64);
+                counters::RESPONSE_COMPRESSION_BYTES
+                    .with_label_values(&[encoding.as_str(), "compressed"])
+                    .inc_by(compressed.len() as u64);
+                parts.headers.insert(
+                    header::CONTENT_ENCODING,
+                    HeaderValue::from_static(encoding.as_str()),
+                );
+                parts.headers.remove(header::CONTENT_LENGTH);
+                Response::from_parts(parts, compressed.into())
+            }
+            Err(err) => {
+                warn!(
+                    "Failed to compress response with {}: {}",
+                    encoding.as_str(),
+                    err
+                );
+                Response::from_parts(parts, bytes.into())
+            }
+        }
+    }
+
+    fn encode(&self, encoding: Encoding, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match encoding {
+            Encoding::Zstd => zstd::stream::encode_all(bytes, self.config.zstd_level),
+            Encoding::Gzip => {
+                let mut encoder =
+                    GzEncoder::new(Vec::new(), Compression::new(self.config.gzip_level));
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn compression(min_response_bytes: Option<usize>) -> ResponseCompression {
+        ResponseCompression::new(&ResponseCompressionConfig {
+            min_response_bytes,
+            ..ResponseCompressionConfig::default()
+        })
+    }
+
+    fn compress(
+        compression: &ResponseCompression,
+        body: &[u8],
+        accept_encoding: Option<&str>,
+    ) -> (Option<String>, Vec<u8>) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let response = Response::new(body.to_vec().into());
+            let response = compression.compress(response, accept_encoding).await;
+            let encoding = response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|value| value.to_str().unwrap().to_string());
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            (encoding, body.to_vec())
+        })
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Encoding::negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(
+            Encoding::negotiate("gzip, deflate, zstd"),
+            Some(Encoding::Zstd)
+        );
+        assert_eq!(Encoding::negotiate("GZIP;q=0.5, br"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("zstd;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(Encoding::negotiate("zstd; q=0.0"), None);
+        assert_eq!(Encoding::negotiate("identity, br"), None);
+        assert_eq!(Encoding::negotiate(""), None);
+    }
+
+    #[test]
+    fn test_compress() {
+        let body = br#"{"jsonrpc":"2.0","result":[]}"#.repeat(100);
+        let compression = compression(Some(1024));
+
+        let (encoding, compressed) = compress(&compression, &body, Some("gzip"));
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        let mut decompressed = vec![];
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+
+        let (encoding, compressed) = compress(&compression, &body, Some("gzip, zstd"));
+        assert_eq!(encoding.as_deref(), Some("zstd"));
+        assert_eq!(
+            zstd::stream::decode_all(compressed.as_slice()).unwrap(),
+            body
+        );
+
+        // clients accepting no encoding get the response as is
+        assert_eq!(compress(&compression, &body, None), (None, body.clone()));
+        assert_eq!(
+            compress(&compression, &body, Some("br")),
+            (None, body.clone())
+        );
+    }
+
+    #[test]
+    fn test_compress_threshold() {
+        let body = b"{}".repeat(100);
+        assert_eq!(
+            compress(&compression(Some(1024)), &body, Some("gzip")),
+            (None, body.clone())
+        );
+        assert_eq!(
+            compress(&compression(None), &body, Some("gzip")),
+            (None, body.clone())
+        );
+        assert_eq!(
+            compress(&compression(Some(0)), &body, Some("gzip"))
+                .0
+                .as_deref(),
+            Some("gzip")
+        );
+    }
+}
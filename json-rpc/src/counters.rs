@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_metrics::{register_histogram_vec, register_int_counter_vec, HistogramVec, IntCounterVec};
+use diem_metrics::{
+    register_histogram, register_histogram_vec, register_int_counter_vec, Histogram, HistogramVec,
+    IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 /// Cumulative number of rpc requests that the JSON RPC service receives
@@ -23,6 +26,17 @@ pub static RPC_REQUEST_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of requests in the batches that the JSON RPC service receives, to tune the batch size
+/// limit
+pub static BATCH_SIZE: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "diem_client_service_batch_size",
+        "Number of requests in the batches that JSON RPC client service receives",
+        vec![1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0]
+    )
+    .unwrap()
+});
+
 /// Cumulative number of valid requests that the JSON RPC client service receives
 pub static REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -82,3 +96,124 @@ pub static METHOD_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Cumulative number of transactions submitted, by the encoding they were submitted in
+pub static SUBMITTED_TRANSACTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_submitted_transactions_count",
+        "Cumulative number of transactions submitted to JSON RPC client service",
+        &[
+            "encoding", // bcs / legacy_signed_transaction / legacy_submit_request
+            "result",   // result of submission: "success", "fail"
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of requests of each API consumer, allowed or throttled by its rate limit
+pub static API_CONSUMER_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_api_consumer_requests_count",
+        "Cumulative number of requests of each API consumer",
+        &[
+            "consumer", // name of the API consumer, "anonymous" for requests without an API key
+            "result",   // allowed / throttled
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of credits spent by each API consumer, allowed or throttled by its rate limit
+pub static API_CONSUMER_CREDITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_api_consumer_credits_count",
+        "Cumulative number of credits spent by each API consumer",
+        &[
+            "consumer", // name of the API consumer, "anonymous" for requests without an API key
+            "result",   // allowed / throttled
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of bytes of the requests served to each API consumer and of their responses
+pub static API_CONSUMER_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_api_consumer_bytes",
+        "Cumulative number of bytes of the requests served to each API consumer",
+        &[
+            "consumer",  // name of the API consumer, "anonymous" for requests without an API key
+            "direction", // request / response
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of submissions of each API consumer rejected for their sender
+pub static API_CONSUMER_REJECTED_SUBMISSIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_api_consumer_rejected_submissions_count",
+        "Cumulative number of submissions of each API consumer rejected for their sender",
+        &["consumer"]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of credits spent on the costly queries of client addresses, by query class
+pub static QUERY_BUDGET_CREDITS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_query_budget_credits_count",
+        "Cumulative number of credits spent on the costly queries of client addresses",
+        &[
+            "class",  // range_scan / event_scan
+            "result", // allowed / queued / throttled
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of bytes of the compressed responses, before and after compression
+pub static RESPONSE_COMPRESSION_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_response_compression_bytes",
+        "Cumulative number of bytes of the compressed responses, before and after compression",
+        &[
+            "encoding", // gzip / zstd
+            "stage",    // uncompressed / compressed
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of notifications posted to webhooks
+pub static WEBHOOK_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_webhook_notifications_count",
+        "Cumulative number of notifications posted to webhooks",
+        &[
+            "event",  // accepted / committed / expired
+            "result", // success / fail
+        ]
+    )
+    .unwrap()
+});
+
+/// Cumulative number of resource changes posted to the callback URLs of resource watches
+pub static RESOURCE_WATCH_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_resource_watch_notifications_count",
+        "Cumulative number of resource changes posted to the callback URLs of resource watches",
+        &["result"] // success / fail
+    )
+    .unwrap()
+});
+
+/// Counters(queued,dequeued,dropped) of the commit events pending for resource watches
+pub static PENDING_RESOURCE_WATCH_COMMIT_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_pending_resource_watch_commit_events",
+        "Counters(queued,dequeued,dropped) of the commit events pending for resource watches",
+        &["state"]
+    )
+    .unwrap()
+});
@@ -3,9 +3,11 @@
 
 use crate::{
     errors::JsonRpcError,
+    module_abi::module_abi,
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
-        MetadataView, StateProofView, TransactionListView, TransactionView,
+        AccountResourceView, AccountStateWithProofView, AccountView, BytesView, CurrencyInfoView,
+        EpochInfoView, EventCursor, EventPageView, EventStatsView, EventView, EventWithProofView,
+        MetadataView, ModuleAbiView, ModuleView, StateProofView, TransactionListView, TransactionOutputView, TransactionView,
         TransactionsWithProofsView, TowerStateResourceView, OracleUpgradeStateView, WaypointView
     },
 };
@@ -13,7 +15,15 @@ use anyhow::{format_err, Result};
 // use diem_client::views::WaypointView;
 // use diem_client::views::TowerStateResourceView;
 use diem_crypto::HashValue;
-use diem_types::{account_address::AccountAddress, account_config::{diem_root_address, resources::dual_attestation::Limit, AccountResource}, account_state::AccountState, chain_id::ChainId, event::EventKey, ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use diem_json_rpc_types::request::EventFilter;
+use diem_types::{account_address::AccountAddress, account_config::{diem_root_address, resources::dual_attestation::Limit, AccountResource, ReceivedPaymentEvent, SentPaymentEvent}, account_state::AccountState, chain_id::ChainId, contract_event::ContractEvent, event::EventKey, ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use move_binary_format::file_format::CompiledModule;
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+    move_resource::MoveStructType,
+    parser::parse_type_tag,
+};
 use std::{
     cmp::min,
     convert::{TryFrom, TryInto},
@@ -108,6 +118,26 @@ pub fn get_transactions(
     Ok(TransactionListView::try_from(txs)?)
 }
 
+//////// 0L ////////
+/// Returns the outputs of transactions by range
+pub fn get_transaction_outputs(
+    db: &dyn DbReader,
+    ledger_version: u64,
+    start_version: u64,
+    limit: u64,
+) -> Result<Vec<TransactionOutputView>, JsonRpcError> {
+    if start_version > ledger_version || limit == 0 {
+        return Ok(vec![]);
+    }
+    let outputs = db.get_transaction_outputs(start_version, limit, ledger_version)?;
+    let views = outputs
+        .into_iter()
+        .zip(start_version..)
+        .map(|(output, version)| TransactionOutputView::try_from((version, output)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(views)
+}
+
 /// Returns transactions by range with proofs
 pub fn get_transactions_with_proofs(
     db: &dyn DbReader,
@@ -191,15 +221,82 @@ pub fn get_account_transactions(
     Ok(all_txs)
 }
 
-/// Returns events by given access path
+/// `EventFilter` with its type tag parsed, matched against the events read from the DB
+struct EventMatcher {
+    type_tag: Option<TypeTag>,
+    filter: EventFilter,
+}
+
+impl EventMatcher {
+    fn new(filter: EventFilter) -> Result<Self, JsonRpcError> {
+        let type_tag = filter
+            .type_tag
+            .as_deref()
+            .map(parse_type_tag)
+            .transpose()
+            .map_err(|e| JsonRpcError::invalid_param(&format!("filter.type_tag: {}", e)))?;
+        Ok(Self { type_tag, filter })
+    }
+
+    fn matches(&self, event: &ContractEvent) -> Result<bool> {
+        if let Some(type_tag) = &self.type_tag {
+            if event.type_tag() != type_tag {
+                return Ok(false);
+            }
+        }
+        let EventFilter {
+            sender,
+            min_amount,
+            max_amount,
+            ..
+        } = &self.filter;
+        if sender.is_none() && min_amount.is_none() && max_amount.is_none() {
+            return Ok(true);
+        }
+
+        let (event_sender, amount) =
+            if event.type_tag() == &TypeTag::Struct(SentPaymentEvent::struct_tag()) {
+                let sent_event = SentPaymentEvent::try_from(event)?;
+                (event.key().get_creator_address(), sent_event.amount())
+            } else if event.type_tag() == &TypeTag::Struct(ReceivedPaymentEvent::struct_tag()) {
+                let received_event = ReceivedPaymentEvent::try_from(event)?;
+                (received_event.sender(), received_event.amount())
+            } else {
+                return Ok(false);
+            };
+        Ok(sender.map_or(true, |sender| sender == event_sender)
+            && min_amount.map_or(true, |min_amount| amount >= min_amount)
+            && max_amount.map_or(true, |max_amount| amount <= max_amount))
+    }
+
+    /// Keeps the events matching the filter, in order
+    fn retain<T>(&self, events: Vec<T>, event: impl Fn(&T) -> &ContractEvent) -> Result<Vec<T>> {
+        let mut matching = vec![];
+        for item in events {
+            if self.matches(event(&item))? {
+                matching.push(item);
+            }
+        }
+        Ok(matching)
+    }
+}
+
+/// Returns events by given access path. With a `filter`, the page of `limit` events read from
+/// `start` is narrowed down to the matching events, so the next page still starts at
+/// `start + limit`.
 pub fn get_events(
     db: &dyn DbReader,
     ledger_version: u64,
     event_key: EventKey,
     start: u64,
     limit: u64,
+    filter: Option<EventFilter>,
 ) -> Result<Vec<EventView>, JsonRpcError> {
-    let events_raw = db.get_events(&event_key, start, Order::Ascending, limit)?;
+    let matcher = filter.map(EventMatcher::new).transpose()?;
+    let mut events_raw = db.get_events(&event_key, start, Order::Ascending, limit)?;
+    if let Some(matcher) = &matcher {
+        events_raw = matcher.retain(events_raw, |(_, event)| event)?;
+    }
 
     let events = events_raw
         .into_iter()
@@ -210,21 +307,119 @@ pub fn get_events(
     Ok(events)
 }
 
-/// Returns events by given access path along with their proofs
+/// Returns a page of the events emitted under `event_key`, continuing from `cursor` or from the
+/// first event. Pages continued from a cursor are read at the cursor's ledger version, so that
+/// events appended meanwhile don't shift them, and only scan the events the cursor knows remain.
+/// Once those are returned, the next page is read at `ledger_version` and resumes with the events
+/// emitted since.
+pub fn get_events_page(
+    db: &dyn DbReader,
+    ledger_version: u64,
+    event_key: EventKey,
+    limit: u64,
+    cursor: Option<EventCursor>,
+    filter: Option<EventFilter>,
+) -> Result<EventPageView, JsonRpcError> {
+    if let Some(cursor) = &cursor {
+        if cursor.key != event_key {
+            return Err(JsonRpcError::invalid_param(&format!(
+                "cursor: issued for event key {}",
+                cursor.key
+            )));
+        }
+    }
+    let matcher = filter.map(EventMatcher::new).transpose()?;
+    let (ledger_version, start, last_sequence_number) = match cursor {
+        Some(cursor) if cursor.has_more() => (
+            cursor.ledger_version,
+            cursor.next_sequence_number,
+            cursor.last_sequence_number,
+        ),
+        _ => {
+            let stats = db.get_event_stats(&event_key, ledger_version)?;
+            let start = match cursor {
+                Some(cursor) => cursor.next_sequence_number,
+                None => stats.map_or(0, |stats| stats.first_sequence_number),
+            };
+            (
+                ledger_version,
+                start,
+                stats.map(|stats| stats.last_sequence_number),
+            )
+        }
+    };
+
+    // Events up to `last_sequence_number` were all emitted as of `ledger_version`, so the scan
+    // needs no filtering by version
+    let remaining =
+        last_sequence_number.map_or(0, |last| last.saturating_add(1).saturating_sub(start));
+    let count = min(limit, remaining);
+    let mut events_raw = if count > 0 {
+        db.get_events(&event_key, start, Order::Ascending, count)?
+    } else {
+        vec![]
+    };
+    if let Some(matcher) = &matcher {
+        events_raw = matcher.retain(events_raw, |(_, event)| event)?;
+    }
+    let events = events_raw
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<EventView>>>()?;
+
+    let next = EventCursor {
+        key: event_key,
+        next_sequence_number: start.saturating_add(count),
+        ledger_version,
+        last_sequence_number,
+    };
+    Ok(EventPageView {
+        events,
+        ledger_version,
+        has_more: next.has_more(),
+        next,
+    })
+}
+
+/// Returns the number of events emitted under `event_key` and the range they span, as of
+/// `ledger_version`
+pub fn get_event_stats(
+    db: &dyn DbReader,
+    ledger_version: u64,
+    event_key: EventKey,
+) -> Result<EventStatsView, JsonRpcError> {
+    let stats = db.get_event_stats(&event_key, ledger_version)?;
+    Ok(EventStatsView {
+        key: event_key,
+        count: stats.map_or(0, |stats| stats.count),
+        first_sequence_number: stats.map(|stats| stats.first_sequence_number),
+        first_transaction_version: stats.map(|stats| stats.first_version),
+        last_sequence_number: stats.map(|stats| stats.last_sequence_number),
+        last_transaction_version: stats.map(|stats| stats.last_version),
+    })
+}
+
+/// Returns events by given access path along with their proofs, narrowed down by `filter` as in
+/// `get_events`
 pub fn get_events_with_proofs(
     db: &dyn DbReader,
     ledger_version: u64,
     event_key: EventKey,
     start: u64,
     limit: u64,
+    filter: Option<EventFilter>,
 ) -> Result<Vec<EventWithProofView>, JsonRpcError> {
-    let events_with_proofs = db.get_events_with_proofs(
+    let matcher = filter.map(EventMatcher::new).transpose()?;
+    let mut events_with_proofs = db.get_events_with_proofs(
         &event_key,
         start,
         Order::Ascending,
         limit,
         Some(ledger_version),
     )?;
+    if let Some(matcher) = &matcher {
+        events_with_proofs = matcher.retain(events_with_proofs, |event| &event.event)?;
+    }
 
     let views = events_with_proofs
         .iter()
@@ -266,6 +461,29 @@ pub fn get_state_proof(
     StateProofView::try_from((ledger_info.clone(), proofs.0, proofs.1)).map_err(Into::into)
 }
 
+//////// 0L ////////
+/// Returns the validator set of an epoch and the reconfigurations bounding it, as of
+/// `ledger_version`
+pub fn get_epoch_info(
+    db: &dyn DbReader,
+    ledger_version: u64,
+    epoch: u64,
+) -> Result<EpochInfoView, JsonRpcError> {
+    let mut epoch_info = db.get_epoch_info(epoch)?;
+    // storage may have moved past the ledger info this request is served at
+    if let Some(end_ledger_info) = &epoch_info.end_ledger_info {
+        if end_ledger_info.ledger_info().version() > ledger_version {
+            epoch_info.end_ledger_info = None;
+        }
+    }
+    EpochInfoView::try_from((
+        epoch_info.epoch_state,
+        epoch_info.start_ledger_info,
+        epoch_info.end_ledger_info,
+    ))
+    .map_err(Into::into)
+}
+
 /// Returns the account state to the client, alongside a proof relative to the version and
 /// ledger_version specified by the client. If version or ledger_version are not specified,
 /// the latest known versions will be used.
@@ -317,6 +535,65 @@ pub fn get_oracle_upgrade_state(
     }
 }
 
+/// Get a single resource of an account, along with the account state it was read from and the
+/// proof of that state against `ledger_version`
+pub fn get_account_resource(
+    db: &dyn DbReader,
+    ledger_version: u64,
+    account: AccountAddress,
+    path: &[u8],
+    version: u64,
+) -> Result<AccountResourceView, JsonRpcError> {
+    let account_state_with_proof =
+        db.get_account_state_with_proof(account, version, ledger_version)?;
+    let blob = match &account_state_with_proof.blob {
+        Some(account_blob) => AccountState::try_from(account_blob)?.get(path).cloned(),
+        None => None,
+    };
+    Ok(AccountResourceView {
+        version,
+        blob: blob.map(BytesView::new),
+        account_state_with_proof: AccountStateWithProofView::try_from(account_state_with_proof)?,
+    })
+}
+
+/// Returns the bytecode of the module `name` published under `account`, as of `version`. `None`
+/// if no such module is published.
+pub fn get_module(
+    db: &dyn DbReader,
+    account: AccountAddress,
+    name: &str,
+    version: u64,
+) -> Result<Option<ModuleView>, JsonRpcError> {
+    let identifier = Identifier::new(name)
+        .map_err(|_| JsonRpcError::invalid_param(&format!("name: {:?}", name)))?;
+    let path = ModuleId::new(account, identifier).access_vector();
+    let (bytecode, _, _) = db.get_account_resource_by_version(account, &path, version)?;
+    Ok(bytecode.map(|bytecode| ModuleView {
+        address: account,
+        name: name.to_string(),
+        version,
+        bytecode: BytesView::new(bytecode),
+    }))
+}
+
+/// Returns the ABI of the module `name` published under `account`, as of `version`, derived from
+/// its bytecode. `None` if no such module is published.
+pub fn get_module_abi(
+    db: &dyn DbReader,
+    account: AccountAddress,
+    name: &str,
+    version: u64,
+) -> Result<Option<ModuleAbiView>, JsonRpcError> {
+    let module = match get_module(db, account, name, version)? {
+        Some(module) => module,
+        None => return Ok(None),
+    };
+    let compiled = CompiledModule::deserialize(module.bytecode.inner())
+        .map_err(|e| format_err!("invalid bytecode of module {}::{}: {:?}", account, name, e))?;
+    Ok(Some(module_abi(&compiled, version)))
+}
+
 /// Get waypoint
 pub fn get_waypoint(
     ledger_info: &LedgerInfoWithSignatures,
@@ -1,8 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{methods, runtime, tests};
+use crate::{
+    api_keys::ApiKeys, compression::ResponseCompression, methods, query_budgets::QueryBudgets,
+    resource_watches::ResourceWatches, runtime, tests, webhooks::Webhooks,
+};
 use diem_config::config;
+use diem_mempool::MempoolClientRequest;
 use diem_proptest_helpers::ValueGenerator;
 use diem_types::account_state_blob::AccountStateWithProof;
 use futures::{channel::mpsc::channel, StreamExt};
@@ -109,6 +113,7 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         genesis: std::collections::HashMap::new(),
         all_accounts: std::collections::HashMap::new(),
         all_txns: vec![],
+        write_sets: vec![],
         events: vec![],
         account_state_with_proof: vec![account_state_with_proof],
         timestamps: vec![1598223353000000],
@@ -120,6 +125,12 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         diem_types::chain_id::ChainId::test(),
         config::DEFAULT_BATCH_SIZE_LIMIT,
         config::DEFAULT_PAGE_SIZE_LIMIT,
+        config::DEFAULT_MIN_VERSION_WAIT_MS,
+        None,
+        Arc::new(Webhooks::new(&config::WebhooksConfig::default())),
+        Arc::new(ResourceWatches::new(
+            &config::ResourceWatchesConfig::default(),
+        )),
     );
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -127,7 +138,15 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         .unwrap();
 
     rt.spawn(async move {
-        if let Some((_, cb)) = mp_events.next().await {
+        if let Some(request) = mp_events.next().await {
+            let cb = match request {
+                MempoolClientRequest::SubmitTransaction(_, cb)
+                | MempoolClientRequest::ValidateTransaction(_, cb) => cb,
+                MempoolClientRequest::AllocateSequenceNumber(_, cb) => {
+                    cb.send(Ok(None)).unwrap();
+                    return;
+                }
+            };
             cb.send(Ok((
                 diem_types::mempool_status::MempoolStatus::new(
                     diem_types::mempool_status::MempoolStatusCode::Accepted,
@@ -138,9 +157,25 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         }
     });
     let body = rt.block_on(async {
-        let reply = runtime::rpc_endpoint(json_request, service, None)
-            .await
-            .unwrap();
+        let reply = runtime::rpc_endpoint(
+            json_request,
+            service,
+            Arc::new(ApiKeys::new(&config::ApiKeysConfig::default())),
+            Arc::new(QueryBudgets::new(
+                &config::QueryBudgetsConfig::default(),
+                config::DEFAULT_PAGE_SIZE_LIMIT,
+            )),
+            Arc::new(ResponseCompression::new(
+                &config::ResponseCompressionConfig::default(),
+            )),
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
 
         let resp = reply.into_response();
         let (_, body) = resp.into_parts();
@@ -0,0 +1,248 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health of the node as a whole, served at `/node/health` for load balancers and uptime monitors.
+//!
+//! Storage is read directly. The other subsystems run in the same process and are observed through
+//! the metrics they publish; the ones not running on the node, e.g. consensus on a full node, are
+//! left out of the document.
+
+use anyhow::Result;
+use diem_infallible::Mutex;
+use diem_metrics::get_metric_values;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use storage_interface::DbReader;
+
+/// Ages of the latest commit making storage degraded, then unhealthy
+const STORAGE_DEGRADED_COMMIT_AGE: Duration = Duration::from_secs(60);
+const STORAGE_UNHEALTHY_COMMIT_AGE: Duration = Duration::from_secs(300);
+/// Numbers of versions state sync is behind its peers making it degraded, then unhealthy
+const STATE_SYNC_DEGRADED_LAG: u64 = 1_000;
+const STATE_SYNC_UNHEALTHY_LAG: u64 = 100_000;
+/// Fill ratio of mempool making it degraded
+const MEMPOOL_DEGRADED_FILL_RATIO: f64 = 0.9;
+/// Times without a new consensus round making consensus degraded, then unhealthy
+const CONSENSUS_DEGRADED_ROUND_AGE: Duration = Duration::from_secs(60);
+const CONSENSUS_UNHEALTHY_ROUND_AGE: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    Degraded,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    fn from_thresholds<T: PartialOrd>(value: T, degraded: T, unhealthy: T) -> Self {
+        if value >= unhealthy {
+            HealthStatus::Unhealthy
+        } else if value >= degraded {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+/// The health of each subsystem, and of the node overall: the worst of them
+#[derive(Debug, Serialize)]
+pub struct NodeHealth {
+    pub status: HealthStatus,
+    pub storage: StorageHealth,
+    pub state_sync: Option<StateSyncHealth>,
+    pub mempool: Option<MempoolHealth>,
+    pub consensus: Option<ConsensusHealth>,
+    pub peers: Option<PeersHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageHealth {
+    pub status: HealthStatus,
+    pub latest_version: u64,
+    pub secs_since_commit: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StateSyncHealth {
+    pub status: HealthStatus,
+    pub synced_version: u64,
+    /// Highest version advertised by peers
+    pub highest_version: u64,
+    pub lag: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MempoolHealth {
+    pub status: HealthStatus,
+    pub size: u64,
+    pub capacity: u64,
+    pub fill_ratio: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsensusHealth {
+    pub status: HealthStatus,
+    pub round: u64,
+    pub secs_since_new_round: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeersHealth {
+    pub status: HealthStatus,
+    /// Number of connections on each network
+    pub connections: BTreeMap<String, u64>,
+    pub total: u64,
+}
+
+/// Checks the health of the node, remembering what's needed to tell whether consensus progresses
+pub struct HealthMonitor {
+    db: Arc<dyn DbReader>,
+    /// Latest consensus round observed, and when it was first observed
+    latest_round: Mutex<Option<(u64, SystemTime)>>,
+}
+
+impl HealthMonitor {
+    pub fn new(db: Arc<dyn DbReader>) -> Self {
+        Self {
+            db,
+            latest_round: Mutex::new(None),
+        }
+    }
+
+    pub fn check(&self, now: SystemTime) -> Result<NodeHealth> {
+        let storage = self.storage_health(now)?;
+        let state_sync = state_sync_health();
+        let mempool = mempool_health();
+        let consensus = self.consensus_health(now);
+        let peers = peers_health();
+
+        let status = std::iter::once(storage.status)
+            .chain(state_sync.as_ref().map(|health| health.status))
+            .chain(mempool.as_ref().map(|health| health.status))
+            .chain(consensus.as_ref().map(|health| health.status))
+            .chain(peers.as_ref().map(|health| health.status))
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+        Ok(NodeHealth {
+            status,
+            storage,
+            state_sync,
+            mempool,
+            consensus,
+            peers,
+        })
+    }
+
+    fn storage_health(&self, now: SystemTime) -> Result<StorageHealth> {
+        let ledger_info = self.db.get_latest_ledger_info()?;
+        let commit_time =
+            UNIX_EPOCH + Duration::from_micros(ledger_info.ledger_info().timestamp_usecs());
+        let commit_age = now.duration_since(commit_time).unwrap_or_default();
+        Ok(StorageHealth {
+            status: HealthStatus::from_thresholds(
+                commit_age,
+                STORAGE_DEGRADED_COMMIT_AGE,
+                STORAGE_UNHEALTHY_COMMIT_AGE,
+            ),
+            latest_version: ledger_info.ledger_info().version(),
+            secs_since_commit: commit_age.as_secs(),
+        })
+    }
+
+    fn consensus_health(&self, now: SystemTime) -> Option<ConsensusHealth> {
+        let round = metric_value("diem_consensus_current_round", &[])? as u64;
+        let mut latest_round = self.latest_round.lock();
+        let new_round_time = match *latest_round {
+            Some((latest, time)) if latest == round => time,
+            _ => {
+                *latest_round = Some((round, now));
+                now
+            }
+        };
+        let round_age = now.duration_since(new_round_time).unwrap_or_default();
+        Some(ConsensusHealth {
+            status: HealthStatus::from_thresholds(
+                round_age,
+                CONSENSUS_DEGRADED_ROUND_AGE,
+                CONSENSUS_UNHEALTHY_ROUND_AGE,
+            ),
+            round,
+            secs_since_new_round: round_age.as_secs(),
+        })
+    }
+}
+
+fn state_sync_health() -> Option<StateSyncHealth> {
+    let synced_version = metric_value("diem_state_sync_version", &[("type", "synced")])? as u64;
+    let highest_version =
+        metric_value("diem_state_sync_version", &[("type", "highest")]).unwrap_or_default() as u64;
+    let lag = highest_version.saturating_sub(synced_version);
+    Some(StateSyncHealth {
+        status: HealthStatus::from_thresholds(
+            lag,
+            STATE_SYNC_DEGRADED_LAG,
+            STATE_SYNC_UNHEALTHY_LAG,
+        ),
+        synced_version,
+        highest_version,
+        lag,
+    })
+}
+
+fn mempool_health() -> Option<MempoolHealth> {
+    let capacity = metric_value("diem_core_mempool_capacity", &[])? as u64;
+    let size = metric_value("diem_core_mempool_index_size", &[("index", "system_ttl")])
+        .unwrap_or_default() as u64;
+    let fill_ratio = size as f64 / capacity.max(1) as f64;
+    Some(MempoolHealth {
+        status: HealthStatus::from_thresholds(
+            fill_ratio,
+            MEMPOOL_DEGRADED_FILL_RATIO,
+            f64::INFINITY,
+        ),
+        size,
+        capacity,
+        fill_ratio,
+    })
+}
+
+fn peers_health() -> Option<PeersHealth> {
+    let values = get_metric_values("diem_connections");
+    if values.is_empty() {
+        return None;
+    }
+    let mut connections = BTreeMap::new();
+    for (labels, value) in values {
+        if let Some(network_id) = labels.get("network_id") {
+            *connections.entry(network_id.clone()).or_insert(0) += value as u64;
+        }
+    }
+    let total = connections.values().sum();
+    Some(PeersHealth {
+        status: if total == 0 {
+            HealthStatus::Unhealthy
+        } else {
+            HealthStatus::Healthy
+        },
+        connections,
+        total,
+    })
+}
+
+/// Returns the value of the metric `name` with the given labels, if it's registered
+fn metric_value(name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+    get_metric_values(name)
+        .into_iter()
+        .find(|(metric_labels, _)| {
+            labels
+                .iter()
+                .all(|(name, value)| metric_labels.get(*name).map(String::as_str) == Some(*value))
+        })
+        .map(|(_, value)| value)
+}
@@ -9,17 +9,32 @@
 //! Protocol specification: https://www.jsonrpc.org/specification
 //!
 //! Module organization:
+//! ├── compression.rs    # compression of the responses to clients accepting it
+//! ├── health.rs         # aggregated health of the node
 //! ├── methods.rs        # contains all available JSON RPC method handlers
+//! ├── module_abi.rs     # ABI of published modules, derived from their bytecode
+//! ├── query_budgets.rs  # budgets of the costly queries of each client address
+//! ├── resource_watches.rs # changes of account resources, posted to the clients watching them
 //! ├── runtime.rs        # implementation of JSON RPC protocol over HTTP
+//! ├── script_schema.rs  # schemas of the transaction scripts known to the node, from their ABIs
 //! ├── tests.rs          # tests
+//! ├── webhooks.rs       # notifications of the outcome of submitted transactions
 
 #[macro_use]
 mod util;
 
+mod api_keys;
+mod compression;
 mod counters;
 pub mod data;
+mod health;
 mod methods;
+mod module_abi;
+mod query_budgets;
+mod resource_watches;
 mod runtime;
+mod script_schema;
+mod webhooks;
 
 pub use diem_json_rpc_types::{errors, response, views};
 
@@ -3,35 +3,52 @@
 
 //! Module contains RPC method handlers for Full Node JSON-RPC interface
 use crate::{
-    data,
+    counters, data,
     errors::JsonRpcError,
+    resource_watches::ResourceWatches,
+    script_schema,
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
-        MetadataView, TowerStateResourceView, OracleUpgradeStateView, StateProofView,
-        TransactionListView, TransactionView, TransactionsWithProofsView, WaypointView
+        AccountResourceView, AccountStateWithProofView, AccountView, CurrencyInfoView,
+        EpochInfoView, EventPageView, EventStatsView, EventView, EventWithProofView, MetadataView,
+        ModuleAbiView, ModuleView, TowerStateResourceView, OracleUpgradeStateView,
+        ResourceWatchView, ScriptSchemaView, StateProofView,
+        TransactionListView, TransactionOutputView, TransactionView, TransactionsWithProofsView,
+        WaypointView
     },
+    webhooks::Webhooks,
 };
 use anyhow::Result;
 use diem_config::config::RoleType;
+use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_json_rpc_types::request::{
-    GetAccountParams, GetAccountStateWithProofParams, GetAccountTransactionParams,
-    GetAccountTransactionsParams, GetCurrenciesParams, GetEventsParams, GetEventsWithProofsParams,
-    GetMetadataParams, GetTowerStateParams, GetNetworkStatusParams, GetStateProofParams,
-    GetTransactionsParams, GetTransactionsWithProofsParams, MethodRequest,
-    SubmitParams,
+    AllocateSequenceNumberParams, GetAccountParams, GetAccountResourceParams, GetAccountStateWithProofParams,
+    GetAccountTransactionParams, GetAccountTransactionsParams, GetCurrenciesParams,
+    GetEpochInfoParams, GetEventStatsParams, GetEventsPageParams, GetEventsParams,
+    GetEventsWithProofsParams,
+    GetMetadataParams, GetModuleParams, GetTowerStateParams, GetNetworkStatusParams,
+    GetScriptSchemasParams, GetStateProofParams,
+    GetTransactionOutputsParams, GetTransactionsParams, GetTransactionsWithProofsParams,
+    MethodRequest,
+    SubmitParams, UnwatchResourcesParams, ValidateTransactionParams, WatchResourcesParams,
 };
-use diem_mempool::{MempoolClientSender, SubmissionStatus};
+use diem_mempool::{MempoolClientRequest, MempoolClientSender, SubmissionStatus};
 use diem_types::{
-    chain_id::ChainId,
-    ledger_info::LedgerInfoWithSignatures, mempool_status::MempoolStatusCode,
-    transaction::SignedTransaction,
+    account_address::AccountAddress, chain_id::ChainId, ledger_info::LedgerInfoWithSignatures,
+    mempool_status::MempoolStatusCode, transaction::SignedTransaction,
 };
 use fail::fail_point;
 use futures::{channel::oneshot, SinkExt};
 use serde_json::Value;
-use std::{borrow::Borrow, sync::Arc};
+use std::{
+    borrow::Borrow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use storage_interface::DbReader;
 
+/// How often storage is re-checked while a read waits for its `min_version`
+const MIN_VERSION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 #[derive(Clone)]
 pub(crate) struct JsonRpcService {
     db: Arc<dyn DbReader>,
@@ -40,6 +57,10 @@ pub(crate) struct JsonRpcService {
     chain_id: ChainId,
     batch_size_limit: u16,
     page_size_limit: u16,
+    min_version_wait_ms: u64,
+    response_signing_key: Option<Arc<Ed25519PrivateKey>>,
+    webhooks: Arc<Webhooks>,
+    resource_watches: Arc<ResourceWatches>,
 }
 
 impl JsonRpcService {
@@ -50,6 +71,10 @@ impl JsonRpcService {
         chain_id: ChainId,
         batch_size_limit: u16,
         page_size_limit: u16,
+        min_version_wait_ms: u64,
+        response_signing_key: Option<Ed25519PrivateKey>,
+        webhooks: Arc<Webhooks>,
+        resource_watches: Arc<ResourceWatches>,
     ) -> Self {
         Self {
             db,
@@ -58,6 +83,10 @@ impl JsonRpcService {
             chain_id,
             batch_size_limit,
             page_size_limit,
+            min_version_wait_ms,
+            response_signing_key: response_signing_key.map(Arc::new),
+            webhooks,
+            resource_watches,
         }
     }
 
@@ -69,10 +98,45 @@ impl JsonRpcService {
 
         self.mempool_sender
             .clone()
-            .send((transaction, req_sender))
+            .send(MempoolClientRequest::SubmitTransaction(
+                transaction,
+                req_sender,
+            ))
             .await?;
 
-        callback.await?
+        Ok(callback.await??)
+    }
+
+    /// Returns the status mempool would accept `transaction` with, without submitting it
+    pub async fn mempool_validation_request(
+        &self,
+        transaction: SignedTransaction,
+    ) -> Result<SubmissionStatus> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mempool_sender
+            .clone()
+            .send(MempoolClientRequest::ValidateTransaction(
+                transaction,
+                req_sender,
+            ))
+            .await?;
+
+        Ok(callback.await??)
+    }
+
+    /// Allocates the next sequence number of `sender`, if mempool allocates sequence numbers to it
+    pub async fn mempool_allocation_request(&self, sender: AccountAddress) -> Result<Option<u64>> {
+        let (req_sender, callback) = oneshot::channel();
+
+        self.mempool_sender
+            .clone()
+            .send(MempoolClientRequest::AllocateSequenceNumber(
+                sender, req_sender,
+            ))
+            .await?;
+
+        Ok(callback.await??)
     }
 
     pub fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
@@ -85,12 +149,51 @@ impl JsonRpcService {
         self.db.get_latest_ledger_info()
     }
 
+    /// Waits up to `min_version_wait_ms` for storage to commit `min_version`, returning the
+    /// first ledger info at or above it. Fails with a retriable stale ledger version error if
+    /// the node does not catch up in time.
+    pub async fn wait_for_ledger_version(
+        &self,
+        min_version: u64,
+    ) -> Result<LedgerInfoWithSignatures, JsonRpcError> {
+        let deadline = Instant::now() + Duration::from_millis(self.min_version_wait_ms);
+        loop {
+            let ledger_info = self.get_latest_ledger_info()?;
+            let version = ledger_info.ledger_info().version();
+            if version >= min_version {
+                return Ok(ledger_info);
+            }
+            if Instant::now() >= deadline {
+                return Err(JsonRpcError::stale_ledger_version(min_version, version));
+            }
+            tokio::time::sleep(MIN_VERSION_POLL_INTERVAL).await;
+        }
+    }
 
     pub fn chain_id(&self) -> ChainId {
         self.chain_id
     }
 
+    /// Key signing the responses to requests carrying a client nonce, if any
+    pub fn response_signing_key(&self) -> Option<&Ed25519PrivateKey> {
+        self.response_signing_key.as_deref()
+    }
+
+    pub fn webhooks(&self) -> &Webhooks {
+        &self.webhooks
+    }
+
+    pub fn resource_watches(&self) -> &ResourceWatches {
+        &self.resource_watches
+    }
+
     pub fn validate_batch_size_limit(&self, size: usize) -> Result<(), JsonRpcError> {
+        // an empty batch is an invalid request, answered with a single error
+        if size == 0 {
+            return Err(JsonRpcError::invalid_request_with_msg(
+                "batch is empty".to_string(),
+            ));
+        }
         self.validate_size_limit("batch size", self.batch_size_limit, size)
     }
 
@@ -193,20 +296,94 @@ impl<'a> Handler<'a> {
             MethodRequest::GetWaypointView() => {
                 serde_json::to_value(self.get_waypoint().await?)?
             }
+            MethodRequest::GetAccountResource(params) => {
+                serde_json::to_value(self.get_account_resource(params).await?)?
+            }
+            MethodRequest::GetTransactionOutputs(params) => {
+                serde_json::to_value(self.get_transaction_outputs(params).await?)?
+            }
+            MethodRequest::GetEpochInfo(params) => {
+                serde_json::to_value(self.get_epoch_info(params).await?)?
+            }
+            MethodRequest::GetEventStats(params) => {
+                serde_json::to_value(self.get_event_stats(params).await?)?
+            }
+            MethodRequest::ValidateTransaction(params) => {
+                self.validate_transaction(params).await?.into()
+            }
+            MethodRequest::AllocateSequenceNumber(params) => {
+                self.allocate_sequence_number(params).await?.into()
+            }
+            MethodRequest::GetModule(params) => {
+                serde_json::to_value(self.get_module(params).await?)?
+            }
+            MethodRequest::GetModuleAbi(params) => {
+                serde_json::to_value(self.get_module_abi(params).await?)?
+            }
+            MethodRequest::GetEventsPage(params) => {
+                serde_json::to_value(self.get_events_page(params).await?)?
+            }
+            MethodRequest::GetScriptSchemas(params) => {
+                serde_json::to_value(self.get_script_schemas(params).await?)?
+            }
+            MethodRequest::WatchResources(params) => {
+                serde_json::to_value(self.watch_resources(params).await?)?
+            }
+            MethodRequest::UnwatchResources(params) => {
+                serde_json::to_value(self.unwatch_resources(params).await?)?
+            }
         };
         Ok(response)
     }
 
     async fn submit(&self, params: SubmitParams) -> Result<(), JsonRpcError> {
-        let (mempool_status, vm_status_opt) = self.service.mempool_request(params.data).await?;
-
-        if let Some(vm_status) = vm_status_opt {
-            Err(JsonRpcError::vm_status(vm_status))
-        } else if mempool_status.code == MempoolStatusCode::Accepted {
-            Ok(())
+        let encoding = params.encoding;
+        let webhooks = self.service.webhooks();
+        if let Some(callback_url) = &params.callback_url {
+            webhooks.validate_callback_url(callback_url)?;
+        }
+        let txn = if webhooks.enabled() {
+            Some(params.data.clone())
         } else {
-            Err(JsonRpcError::mempool_error(mempool_status)?)
+            None
+        };
+        let status = self.service.mempool_request(params.data).await?;
+
+        let result = submission_result(status);
+        counters::SUBMITTED_TRANSACTIONS
+            .with_label_values(&[
+                encoding.as_str(),
+                if result.is_ok() { "success" } else { "fail" },
+            ])
+            .inc();
+        if let (Ok(()), Some(txn)) = (&result, txn) {
+            webhooks.on_accepted(&txn, params.callback_url);
         }
+        result
+    }
+
+    /// Runs the checks of `submit` on a transaction without submitting it, returning the error
+    /// `submit` would fail with, if any
+    async fn validate_transaction(
+        &self,
+        params: ValidateTransactionParams,
+    ) -> Result<(), JsonRpcError> {
+        let status = self.service.mempool_validation_request(params.data).await?;
+        submission_result(status)
+    }
+
+    /// Allocates the next sequence number of an account, past its on-chain sequence number, its
+    /// transactions pending in mempool and the numbers allocated to it before.
+    /// Consumers without `allowed_senders`, e.g. the default anonymous one, may allocate numbers
+    /// of any account registered for allocation, delaying its transactions
+    async fn allocate_sequence_number(
+        &self,
+        params: AllocateSequenceNumberParams,
+    ) -> Result<u64, JsonRpcError> {
+        self.service
+            .mempool_allocation_request(params.account)
+            .await?
+            .ok_or_else(|| JsonRpcError::sequence_number_allocation_not_enabled(params.account))
     }
 
     /// Returns the blockchain metadata for a specified version. If no version is specified, default to
@@ -237,6 +414,7 @@ impl<'a> Handler<'a> {
             start_version,
             limit,
             include_events,
+            ..
         } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
@@ -258,6 +436,7 @@ impl<'a> Handler<'a> {
             start_version,
             limit,
             include_events,
+            ..
         } = params;
 
         // Notice limit is a u16 normally, but some APIs require u64 below
@@ -280,6 +459,7 @@ impl<'a> Handler<'a> {
             account,
             sequence_number,
             include_events,
+            ..
         } = params;
         data::get_account_transaction(
             self.service.db.borrow(),
@@ -300,6 +480,7 @@ impl<'a> Handler<'a> {
             start,
             limit,
             include_events,
+            ..
         } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
@@ -315,10 +496,23 @@ impl<'a> Handler<'a> {
 
     /// Returns events by given access path
     async fn get_events(&self, params: GetEventsParams) -> Result<Vec<EventView>, JsonRpcError> {
-        let GetEventsParams { key, start, limit } = params;
+        let GetEventsParams {
+            key,
+            start,
+            limit,
+            filter,
+            ..
+        } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
-        data::get_events(self.service.db.borrow(), self.version(), key, start, limit)
+        data::get_events(
+            self.service.db.borrow(),
+            self.version(),
+            key,
+            start,
+            limit,
+            filter,
+        )
     }
 
     /// Returns events by given access path along with their proofs
@@ -326,10 +520,23 @@ impl<'a> Handler<'a> {
         &self,
         params: GetEventsWithProofsParams,
     ) -> Result<Vec<EventWithProofView>, JsonRpcError> {
-        let GetEventsWithProofsParams { key, start, limit } = params;
+        let GetEventsWithProofsParams {
+            key,
+            start,
+            limit,
+            filter,
+            ..
+        } = params;
 
         self.service.validate_page_size_limit(limit as usize)?;
-        data::get_events_with_proofs(self.service.db.borrow(), self.version(), key, start, limit)
+        data::get_events_with_proofs(
+            self.service.db.borrow(),
+            self.version(),
+            key,
+            start,
+            limit,
+            filter,
+        )
     }
 
     /// Returns meta information about supported currencies
@@ -395,4 +602,160 @@ impl<'a> Handler<'a> {
     ) -> Result<WaypointView, JsonRpcError> {
         data::get_waypoint(self.ledger_info)
     }
-}
\ No newline at end of file
+
+    /// Returns a single resource of an account at the requested version (the latest known
+    /// version by default), with the proven account state it was read from.
+    async fn get_account_resource(
+        &self,
+        params: GetAccountResourceParams,
+    ) -> Result<AccountResourceView, JsonRpcError> {
+        let version = self.version_param(params.version, "version")?;
+        data::get_account_resource(
+            self.service.db.borrow(),
+            self.version(),
+            params.account,
+            params.path.inner(),
+            version,
+        )
+    }
+
+    /// Returns the write sets, events, gas used and status of a range of committed transactions,
+    /// so indexers can mirror state changes without re-executing them.
+    async fn get_transaction_outputs(
+        &self,
+        params: GetTransactionOutputsParams,
+    ) -> Result<Vec<TransactionOutputView>, JsonRpcError> {
+        let GetTransactionOutputsParams {
+            start_version,
+            limit,
+            ..
+        } = params;
+
+        self.service.validate_page_size_limit(limit as usize)?;
+        data::get_transaction_outputs(
+            self.service.db.borrow(),
+            self.version(),
+            start_version,
+            limit,
+        )
+    }
+
+    /// Returns the validator set of an epoch, the versions of the reconfigurations that started
+    /// and ended it, and the validators' signatures on the one that ended it.
+    async fn get_epoch_info(
+        &self,
+        params: GetEpochInfoParams,
+    ) -> Result<EpochInfoView, JsonRpcError> {
+        let latest_epoch = self.ledger_info.ledger_info().next_block_epoch();
+        if params.epoch == 0 || params.epoch > latest_epoch {
+            return Err(JsonRpcError::invalid_param(&format!(
+                "epoch should be between 1 and the latest epoch {}",
+                latest_epoch
+            )));
+        }
+        data::get_epoch_info(self.service.db.borrow(), self.version(), params.epoch)
+    }
+
+    /// Returns a page of the events emitted under an event key, with the cursor to continue from
+    async fn get_events_page(
+        &self,
+        params: GetEventsPageParams,
+    ) -> Result<EventPageView, JsonRpcError> {
+        let GetEventsPageParams {
+            key,
+            limit,
+            cursor,
+            filter,
+            ..
+        } = params;
+
+        self.service.validate_page_size_limit(limit as usize)?;
+        data::get_events_page(
+            self.service.db.borrow(),
+            self.version(),
+            key,
+            limit,
+            cursor,
+            filter,
+        )
+    }
+
+    /// Returns the number of events emitted under an event key and the range they span
+    async fn get_event_stats(
+        &self,
+        params: GetEventStatsParams,
+    ) -> Result<EventStatsView, JsonRpcError> {
+        data::get_event_stats(self.service.db.borrow(), self.version(), params.key)
+    }
+
+    /// Returns the bytecode of a module published on chain, at a given version or the latest one
+    async fn get_module(
+        &self,
+        params: GetModuleParams,
+    ) -> Result<Option<ModuleView>, JsonRpcError> {
+        let version = self.version_param(params.version, "version")?;
+        data::get_module(
+            self.service.db.borrow(),
+            params.account,
+            &params.name,
+            version,
+        )
+    }
+
+    /// Returns the ABI of a module published on chain, derived from its bytecode, at a given
+    /// version or the latest one
+    async fn get_module_abi(
+        &self,
+        params: GetModuleParams,
+    ) -> Result<Option<ModuleAbiView>, JsonRpcError> {
+        let version = self.version_param(params.version, "version")?;
+        data::get_module_abi(
+            self.service.db.borrow(),
+            params.account,
+            &params.name,
+            version,
+        )
+    }
+
+    /// Returns the schemas of the arguments of the transaction scripts known to the node
+    async fn get_script_schemas(
+        &self,
+        _params: GetScriptSchemasParams,
+    ) -> Result<&'static [ScriptSchemaView], JsonRpcError> {
+        Ok(script_schema::script_schemas())
+    }
+
+    /// Registers a watch of account resources, whose changes are posted to the callback URL at
+    /// every commit changing them
+    async fn watch_resources(
+        &self,
+        params: WatchResourcesParams,
+    ) -> Result<ResourceWatchView, JsonRpcError> {
+        self.service.resource_watches().watch(
+            self.service.db.borrow(),
+            params.callback_url,
+            params.resources,
+            self.version(),
+        )
+    }
+
+    /// Removes a watch registered by `watch_resources`. Returns whether it was registered.
+    async fn unwatch_resources(
+        &self,
+        params: UnwatchResourcesParams,
+    ) -> Result<bool, JsonRpcError> {
+        Ok(self.service.resource_watches().unwatch(params.id))
+    }
+}
+/// Returns the result of a submission, from the status mempool accepted or rejected it with
+fn submission_result(
+    (mempool_status, vm_status_opt): SubmissionStatus,
+) -> Result<(), JsonRpcError> {
+    if let Some(vm_status) = vm_status_opt {
+        Err(JsonRpcError::vm_status(vm_status))
+    } else if mempool_status.code == MempoolStatusCode::Accepted {
+        Ok(())
+    } else {
+        Err(JsonRpcError::mempool_error(mempool_status)?)
+    }
+}
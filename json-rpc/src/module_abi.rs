@@ -0,0 +1,137 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derives the ABI of a published module from its bytecode, for `get_module_abi`
+
+use crate::views::{ModuleAbiView, MoveFieldAbiView, MoveFunctionAbiView, MoveStructAbiView};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Ability, AbilitySet, CompiledModule, StructFieldInformation, Visibility},
+    normalized::{Field, Function, Type},
+};
+use move_core_types::identifier::Identifier;
+
+pub fn module_abi(module: &CompiledModule, version: u64) -> ModuleAbiView {
+    let structs = module
+        .struct_defs()
+        .iter()
+        .map(|def| {
+            let handle = module.struct_handle_at(def.struct_handle);
+            let fields = match &def.field_information {
+                StructFieldInformation::Native => vec![],
+                StructFieldInformation::Declared(fields) => fields
+                    .iter()
+                    .map(|field| field_abi(Field::new(module, field)))
+                    .collect(),
+            };
+            MoveStructAbiView {
+                name: module.identifier_at(handle.name).to_string(),
+                abilities: abilities(handle.abilities),
+                type_parameters: handle
+                    .type_parameters
+                    .iter()
+                    .copied()
+                    .map(abilities)
+                    .collect(),
+                is_native: matches!(def.field_information, StructFieldInformation::Native),
+                fields,
+            }
+        })
+        .collect();
+    let functions = module
+        .function_defs()
+        .iter()
+        .filter(|def| def.visibility != Visibility::Private)
+        .map(|def| function_abi(Function::new(module, def)))
+        .collect();
+
+    ModuleAbiView {
+        address: *module.address(),
+        name: module.name().to_string(),
+        version,
+        friends: module
+            .immediate_friends()
+            .iter()
+            .map(|friend| {
+                format!(
+                    "0x{}::{}",
+                    friend.address().short_str_lossless(),
+                    friend.name()
+                )
+            })
+            .collect(),
+        structs,
+        functions,
+    }
+}
+
+fn field_abi(field: Field) -> MoveFieldAbiView {
+    MoveFieldAbiView {
+        name: field.name.to_string(),
+        type_: type_abi(&field.type_),
+    }
+}
+
+fn function_abi((name, function): (Identifier, Function)) -> MoveFunctionAbiView {
+    let visibility = match function.visibility {
+        Visibility::Public => "public",
+        Visibility::Script => "script",
+        Visibility::Friend => "friend",
+        Visibility::Private => "private",
+    };
+    MoveFunctionAbiView {
+        name: name.to_string(),
+        visibility: visibility.to_string(),
+        type_parameters: function
+            .type_parameters
+            .into_iter()
+            .map(abilities)
+            .collect(),
+        parameters: function.parameters.iter().map(type_abi).collect(),
+        return_: function.return_.iter().map(type_abi).collect(),
+    }
+}
+
+/// Spells `type_` as in Move source
+fn type_abi(type_: &Type) -> String {
+    match type_ {
+        Type::Bool => "bool".to_string(),
+        Type::U8 => "u8".to_string(),
+        Type::U64 => "u64".to_string(),
+        Type::U128 => "u128".to_string(),
+        Type::Address => "address".to_string(),
+        Type::Signer => "signer".to_string(),
+        Type::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            let mut type_ = format!("0x{}::{}::{}", address.short_str_lossless(), module, name);
+            if !type_arguments.is_empty() {
+                let type_arguments: Vec<_> = type_arguments.iter().map(type_abi).collect();
+                type_ = format!("{}<{}>", type_, type_arguments.join(", "));
+            }
+            type_
+        }
+        Type::Vector(type_) => format!("vector<{}>", type_abi(type_)),
+        Type::TypeParameter(index) => format!("T{}", index),
+        Type::Reference(type_) => format!("&{}", type_abi(type_)),
+        Type::MutableReference(type_) => format!("&mut {}", type_abi(type_)),
+    }
+}
+
+fn abilities(abilities: AbilitySet) -> Vec<String> {
+    abilities
+        .into_iter()
+        .map(|ability| {
+            match ability {
+                Ability::Copy => "copy",
+                Ability::Drop => "drop",
+                Ability::Store => "store",
+                Ability::Key => "key",
+            }
+            .to_string()
+        })
+        .collect()
+}
@@ -0,0 +1,187 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Budgets of the costly queries of each client address.
+//!
+//! Queries of ranges of transactions and of events spend credits from budgets kept per client
+//! address and per query class, one credit per item requested, while other requests are never
+//! held back. A query over its client's budget is queued until enough credits come back, for a
+//! bounded time, so a client scanning the whole history is slowed down to its budget rather than
+//! starving the clients reading single accounts and transactions. Clients behind the same address,
+//! e.g. behind a proxy, share their budgets.
+
+use crate::counters;
+use diem_config::config::QueryBudgetsConfig;
+use diem_json_rpc_types::{
+    errors::JsonRpcError,
+    request::{MethodRequest, QueryClass},
+};
+use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+// Counter labels for query budget metrics
+const LABEL_ALLOWED: &str = "allowed";
+const LABEL_QUEUED: &str = "queued";
+const LABEL_THROTTLED: &str = "throttled";
+
+/// The budgets of each client address, for the query classes that have one.
+pub(crate) struct QueryBudgets {
+    budgets: HashMap<QueryClass, Budget>,
+    max_queue_wait: Duration,
+}
+
+struct Budget {
+    limiter: TokenBucketRateLimiter<IpAddr>,
+    /// Credits a client may spend at once
+    size: usize,
+}
+
+impl QueryBudgets {
+    /// Creates budgets holding at least a page of `page_size_limit` items, so any query that fits
+    /// in a page can be served.
+    pub fn new(config: &QueryBudgetsConfig, page_size_limit: u16) -> Self {
+        let budgets = vec![
+            (QueryClass::RangeScan, config.range_scan_credits_per_sec),
+            (QueryClass::EventScan, config.event_scan_credits_per_sec),
+        ]
+        .into_iter()
+        .filter_map(|(class, rate)| {
+            let rate = rate?.max(1);
+            let size = rate.max(page_size_limit as usize);
+            let limiter = TokenBucketRateLimiter::new(
+                "json-rpc-query-budget",
+                class.as_str().to_string(),
+                100,
+                size,
+                rate,
+                None,
+            );
+            Some((class, Budget { limiter, size }))
+        })
+        .collect();
+        Self {
+            budgets,
+            max_queue_wait: Duration::from_millis(config.max_queue_wait_ms),
+        }
+    }
+
+    /// Takes the credits of `request` from the budget of its class for `client`, waiting for them
+    /// to come back if needed. Fails if they don't within the maximum queue wait.
+    pub async fn acquire(
+        &self,
+        client: Option<IpAddr>,
+        request: &MethodRequest,
+    ) -> Result<(), JsonRpcError> {
+        let class = request.query_class();
+        let (budget, client) = match (self.budgets.get(&class), client) {
+            (Some(budget), Some(client)) => (budget, client),
+            _ => return Ok(()),
+        };
+        // Queries over the page size limit are rejected when they are handled.
+        let credits = (request.credits() as usize).min(budget.size);
+        let bucket = budget.limiter.bucket(client);
+        let deadline = Instant::now() + self.max_queue_wait;
+        let mut result_label = LABEL_ALLOWED;
+        loop {
+            let ready_at = match bucket.lock().acquire_all_tokens(credits) {
+                Ok(()) => break,
+                Err(ready_at) => ready_at,
+            };
+            match ready_at {
+                Some(ready_at) if ready_at <= deadline => {
+                    result_label = LABEL_QUEUED;
+                    tokio::time::sleep_until(tokio::time::Instant::from_std(ready_at)).await;
+                }
+                _ => {
+                    record_credits(class, LABEL_THROTTLED, credits);
+                    return Err(JsonRpcError::rate_limited(class.as_str()));
+                }
+            }
+        }
+        record_credits(class, result_label, credits);
+        Ok(())
+    }
+}
+
+fn record_credits(class: QueryClass, result_label: &str, credits: usize) {
+    counters::QUERY_BUDGET_CREDITS
+        .with_label_values(&[class.as_str(), result_label])
+        .inc_by(credits as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_json_rpc_types::{errors::ServerCode, Method};
+    use std::net::Ipv4Addr;
+
+    fn get_transactions(limit: u64) -> MethodRequest {
+        MethodRequest::from_value(
+            Method::GetTransactions,
+            serde_json::json!([0, limit, false]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_acquire() {
+        let budgets = QueryBudgets::new(
+            &QueryBudgetsConfig {
+                range_scan_credits_per_sec: Some(10),
+                event_scan_credits_per_sec: None,
+                max_queue_wait_ms: 0,
+            },
+            10,
+        );
+        let client = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        let other_client = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            budgets
+                .acquire(client, &get_transactions(10))
+                .await
+                .unwrap();
+            let err = budgets
+                .acquire(client, &get_transactions(1))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, ServerCode::RateLimited as i16);
+
+            // Budgets are per client, and only for the costly queries
+            budgets
+                .acquire(other_client, &get_transactions(10))
+                .await
+                .unwrap();
+            let get_metadata =
+                MethodRequest::from_value(Method::GetMetadata, serde_json::Value::Null).unwrap();
+            budgets.acquire(client, &get_metadata).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_acquire_queued() {
+        let budgets = QueryBudgets::new(
+            &QueryBudgetsConfig {
+                range_scan_credits_per_sec: Some(10),
+                event_scan_credits_per_sec: None,
+                max_queue_wait_ms: 2_000,
+            },
+            10,
+        );
+        let client = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            budgets
+                .acquire(client, &get_transactions(10))
+                .await
+                .unwrap();
+            let start = Instant::now();
+            budgets.acquire(client, &get_transactions(5)).await.unwrap();
+            assert!(start.elapsed() > Duration::from_millis(500));
+        });
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Watches of account resources registered by clients, so wallets learn of the changes of the
+//! resources they show instead of polling whole accounts every block.
+//!
+//! On every commit event, the watched resources are read at the committed version and compared
+//! with the values last posted. The changes of each watch are posted to its callback URL in a
+//! single request, with the values before and after decoded. Watches are kept in memory only, so
+//! they are lost when the node restarts and clients must register them again.
+
+use crate::{
+    counters,
+    errors::JsonRpcError,
+    views::{
+        BytesView, ResourceChangeView, ResourceChangesView, ResourceValueView, ResourceWatchView,
+    },
+};
+use anyhow::Result;
+use diem_config::config::ResourceWatchesConfig;
+use diem_infallible::Mutex;
+use diem_json_rpc_types::request::WatchedResource;
+use diem_logger::warn;
+use diem_types::{
+    access_path::{AccessPath, Path},
+    account_address::AccountAddress,
+    transaction::Version,
+    vm_status::StatusCode,
+};
+use futures::{Stream, StreamExt};
+use move_binary_format::errors::{Location, PartialVMError, PartialVMResult, VMResult};
+use move_core_types::language_storage::{ModuleId, StructTag};
+use move_vm_runtime::data_cache::MoveStorage;
+use resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue, MoveValueAnnotator};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use storage_interface::DbReader;
+use subscription_service::CommitEvent;
+
+/// Time a callback URL has to respond to a notification
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A resource of an account, and its access path
+type ResourceKey = (AccountAddress, Vec<u8>);
+
+struct Watch {
+    callback_url: String,
+    /// Version the values below were read at
+    version: Version,
+    /// Value last posted of every watched resource, `None` if it doesn't exist
+    values: BTreeMap<ResourceKey, Option<Vec<u8>>>,
+}
+
+pub(crate) struct ResourceWatches {
+    config: ResourceWatchesConfig,
+    client: reqwest::Client,
+    /// Registered watches, by ID
+    watches: Mutex<HashMap<u64, Watch>>,
+    next_id: Mutex<u64>,
+}
+
+impl ResourceWatches {
+    pub fn new(config: &ResourceWatchesConfig) -> Self {
+        Self {
+            config: config.clone(),
+            client: reqwest::Client::builder()
+                .timeout(NOTIFICATION_TIMEOUT)
+                .build()
+                .expect("[json-rpc] failed to create resource watches client"),
+            watches: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(0),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Registers a watch of `resources`, whose values are read at `version`
+    pub fn watch(
+        &self,
+        db: &dyn DbReader,
+        callback_url: String,
+        resources: Vec<WatchedResource>,
+        version: Version,
+    ) -> Result<ResourceWatchView, JsonRpcError> {
+        if !self.enabled() {
+            return Err(JsonRpcError::invalid_request_with_msg(
+                "resource watches are not enabled on this node".to_string(),
+            ));
+        }
+        reqwest::Url::parse(&callback_url)
+            .map_err(|e| JsonRpcError::invalid_param(&format!("callback_url: {}", e)))?;
+        if resources.is_empty() || resources.len() > self.config.max_resources_per_watch {
+            return Err(JsonRpcError::invalid_param(&format!(
+                "resources: between 1 and {} resources may be watched",
+                self.config.max_resources_per_watch
+            )));
+        }
+        let mut values = BTreeMap::new();
+        for resource in resources {
+            let path = resource.path.into_inner().into_vec();
+            if !matches!(bcs::from_bytes::<Path>(&path), Ok(Path::Resource(_))) {
+                return Err(JsonRpcError::invalid_param(&format!(
+                    "resources: {} is not the access path of a resource",
+                    hex::encode(&path)
+                )));
+            }
+            let (value, _, _) =
+                db.get_account_resource_by_version(resource.account, &path, version)?;
+            values.insert((resource.account, path), value);
+        }
+
+        let mut watches = self.watches.lock();
+        if watches.len() >= self.config.max_watches {
+            return Err(JsonRpcError::invalid_request_with_msg(
+                "too many resource watches registered on this node".to_string(),
+            ));
+        }
+        let id = {
+            let mut next_id = self.next_id.lock();
+            *next_id += 1;
+            *next_id
+        };
+        watches.insert(
+            id,
+            Watch {
+                callback_url,
+                version,
+                values,
+            },
+        );
+        Ok(ResourceWatchView { id, version })
+    }
+
+    /// Removes the watch `id`. Returns whether it was registered.
+    pub fn unwatch(&self, id: u64) -> bool {
+        self.watches.lock().remove(&id).is_some()
+    }
+
+    /// Posts the changes of the watched resources at every commit, until the runtime shuts down
+    pub async fn run(
+        self: Arc<Self>,
+        db: Arc<dyn DbReader>,
+        mut commit_events: impl Stream<Item = CommitEvent> + Unpin,
+    ) {
+        while let Some(event) = commit_events.next().await {
+            match self.changes(db.as_ref(), event.version) {
+                Ok(changes) => {
+                    for (callback_url, changes) in changes {
+                        self.notify(callback_url, changes);
+                    }
+                }
+                Err(e) => warn!(
+                    version = event.version,
+                    error = ?e,
+                    "Failed to read the watched resources."
+                ),
+            }
+        }
+    }
+
+    /// Reads the watched resources at `version`, and returns the changes of every watch since
+    /// the values last posted, along with the URL to post them to. A commit event dropped while
+    /// this falls behind only merges its changes into those of the next one.
+    pub fn changes(
+        &self,
+        db: &dyn DbReader,
+        version: Version,
+    ) -> Result<Vec<(String, ResourceChangesView)>> {
+        // Storage is read without holding the lock, so registrations aren't blocked meanwhile.
+        // Resources watched more than once are read once.
+        let keys = self
+            .watches
+            .lock()
+            .values()
+            .filter(|watch| watch.version < version)
+            .flat_map(|watch| watch.values.keys().cloned())
+            .collect::<Vec<_>>();
+        let mut current = BTreeMap::new();
+        for key in keys {
+            if !current.contains_key(&key) {
+                let (value, _, _) = db.get_account_resource_by_version(key.0, &key.1, version)?;
+                current.insert(key, value);
+            }
+        }
+
+        // (watch ID, callback URL, [(resource, before, after)])
+        let mut raw_changes = vec![];
+        for (id, watch) in self.watches.lock().iter_mut() {
+            if watch.version >= version {
+                continue;
+            }
+            watch.version = version;
+            let mut watch_changes = vec![];
+            for (key, before) in watch.values.iter_mut() {
+                match current.get(key) {
+                    Some(after) if after != before => {
+                        let before = std::mem::replace(before, after.clone());
+                        watch_changes.push((key.clone(), before, after.clone()));
+                    }
+                    // unchanged, or registered after storage was read
+                    _ => {}
+                }
+            }
+            if !watch_changes.is_empty() {
+                raw_changes.push((*id, watch.callback_url.clone(), watch_changes));
+            }
+        }
+        if raw_changes.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Resources are decoded outside of the lock too, with the modules as of `version`
+        let storage = DbMoveStorage { db, version };
+        let annotator = MoveValueAnnotator::new(&storage);
+        let value = |path: &[u8], bytes: Option<Vec<u8>>| {
+            bytes.map(|bytes| resource_value(&annotator, path, bytes))
+        };
+        Ok(raw_changes
+            .into_iter()
+            .map(|(watch_id, callback_url, watch_changes)| {
+                let changes = watch_changes
+                    .into_iter()
+                    .map(|((account, path), before, after)| ResourceChangeView {
+                        account,
+                        before: value(&path, before),
+                        after: value(&path, after),
+                        path: BytesView::new(path),
+                    })
+                    .collect();
+                (
+                    callback_url,
+                    ResourceChangesView {
+                        watch_id,
+                        version,
+                        changes,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    #[cfg(test)]
+    pub fn num_watches(&self) -> usize {
+        self.watches.lock().len()
+    }
+
+    fn notify(&self, callback_url: String, changes: ResourceChangesView) {
+        let request = self.client.post(&callback_url).json(&changes);
+        tokio::spawn(async move {
+            let result = match request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+            {
+                Ok(_) => "success",
+                Err(e) => {
+                    warn!(url = callback_url, error = ?e, "Failed to post resource changes.");
+                    "fail"
+                }
+            };
+            counters::RESOURCE_WATCH_NOTIFICATIONS
+                .with_label_values(&[result])
+                .inc();
+        });
+    }
+}
+
+/// Reads the modules resources are decoded with from storage, at the version of the commit
+struct DbMoveStorage<'a> {
+    db: &'a dyn DbReader,
+    version: Version,
+}
+
+impl MoveStorage for DbMoveStorage<'_> {
+    fn get_module(&self, module_id: &ModuleId) -> VMResult<Option<Vec<u8>>> {
+        let path = AccessPath::code_access_path(module_id.clone()).path;
+        self.db
+            .get_account_resource_by_version(*module_id.address(), &path, self.version)
+            .map(|(value, _, _)| value)
+            .map_err(|_| PartialVMError::new(StatusCode::STORAGE_ERROR).finish(Location::Undefined))
+    }
+
+    fn get_resource(
+        &self,
+        address: &AccountAddress,
+        tag: &StructTag,
+    ) -> PartialVMResult<Option<Vec<u8>>> {
+        let path = AccessPath::resource_access_vec(tag.clone());
+        self.db
+            .get_account_resource_by_version(*address, &path, self.version)
+            .map(|(value, _, _)| value)
+            .map_err(|_| PartialVMError::new(StatusCode::STORAGE_ERROR))
+    }
+}
+
+fn resource_value(
+    annotator: &MoveValueAnnotator,
+    path: &[u8],
+    bytes: Vec<u8>,
+) -> ResourceValueView {
+    let decoded = match bcs::from_bytes::<Path>(path) {
+        Ok(Path::Resource(tag)) => match annotator.view_resource(&tag, &bytes) {
+            Ok(resource) => Some(struct_json(&resource)),
+            Err(e) => {
+                warn!(resource = tag.to_string(), error = ?e, "Failed to decode resource.");
+                None
+            }
+        },
+        _ => None,
+    };
+    ResourceValueView {
+        bytes: BytesView::new(bytes),
+        decoded,
+    }
+}
+
+/// Spells a decoded resource in JSON as `get_script_schemas` has clients spell arguments
+fn struct_json(value: &AnnotatedMoveStruct) -> Value {
+    let fields = value
+        .value
+        .iter()
+        .map(|(name, value)| (name.to_string(), value_json(value)))
+        .collect::<Map<_, _>>();
+    Value::Object(fields)
+}
+
+fn value_json(value: &AnnotatedMoveValue) -> Value {
+    match value {
+        AnnotatedMoveValue::U8(value) => json!(value),
+        AnnotatedMoveValue::U64(value) => json!(value),
+        AnnotatedMoveValue::U128(value) => json!(value.to_string()),
+        AnnotatedMoveValue::Bool(value) => json!(value),
+        AnnotatedMoveValue::Address(address) => json!(address),
+        AnnotatedMoveValue::Vector(_, values) => {
+            Value::Array(values.iter().map(value_json).collect())
+        }
+        AnnotatedMoveValue::Bytes(bytes) => json!(hex::encode(bytes)),
+        AnnotatedMoveValue::Struct(value) => struct_json(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_types::account_config::CORE_CODE_ADDRESS;
+    use move_binary_format::file_format::AbilitySet;
+    use move_core_types::{identifier::Identifier, language_storage::TypeTag};
+
+    #[test]
+    fn test_value_json() {
+        let value = AnnotatedMoveStruct {
+            abilities: AbilitySet::EMPTY,
+            type_: StructTag {
+                address: CORE_CODE_ADDRESS,
+                module: Identifier::new("M").unwrap(),
+                name: Identifier::new("S").unwrap(),
+                type_params: vec![],
+            },
+            value: vec![
+                (
+                    Identifier::new("balance").unwrap(),
+                    AnnotatedMoveValue::U128(u128::MAX),
+                ),
+                (
+                    Identifier::new("owners").unwrap(),
+                    AnnotatedMoveValue::Vector(
+                        TypeTag::Address,
+                        vec![AnnotatedMoveValue::Address(CORE_CODE_ADDRESS)],
+                    ),
+                ),
+                (
+                    Identifier::new("metadata").unwrap(),
+                    AnnotatedMoveValue::Bytes(vec![0xca, 0xfe]),
+                ),
+            ],
+        };
+        assert_eq!(
+            struct_json(&value),
+            json!({
+                "balance": "340282366920938463463374607431768211455",
+                "owners": ["00000000000000000000000000000001"],
+                "metadata": "cafe",
+            })
+        );
+    }
+}
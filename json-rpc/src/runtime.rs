@@ -2,28 +2,47 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    api_keys::{ApiKeys, Consumer, X_DIEM_API_KEY},
+    compression::ResponseCompression,
     counters,
     errors::is_internal_error,
+    health::{HealthMonitor, HealthStatus},
     methods::{Handler, JsonRpcService},
-    response::{JsonRpcResponse, X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID, X_DIEM_VERSION_ID},
+    query_budgets::QueryBudgets,
+    resource_watches::ResourceWatches,
+    response::{
+        JsonRpcResponse, DIEM_CLIENT_NONCE, X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID,
+        X_DIEM_VERSION_ID,
+    },
     util::{sdk_info_from_user_agent, SdkInfo},
+    webhooks::Webhooks,
 };
 use anyhow::{ensure, Result};
-use diem_config::config::{NodeConfig, RoleType};
+use diem_config::{
+    config::{
+        ApiKeysConfig, NodeConfig, QueryBudgetsConfig, ResourceWatchesConfig,
+        ResponseCompressionConfig, RoleType, WebhooksConfig,
+    },
+    keys::ConfigKey,
+};
+use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_json_rpc_types::Method;
-use diem_logger::{debug, Schema};
+use diem_logger::{debug, warn, Schema};
 use diem_mempool::MempoolClientSender;
+use diem_transaction_builder::error_explain::{self, CustomErrorMapping};
 use diem_types::{chain_id::ChainId, ledger_info::LedgerInfoWithSignatures};
 use futures::future::{join_all, Either};
+use hyper::body::HttpBody;
 use rand::{rngs::OsRng, RngCore};
 use serde_json::Value;
 use std::{
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     ops::Sub,
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use storage_interface::DbReader;
+use subscription_service::CommitEventBus;
 use tokio::runtime::{Builder, Runtime};
 use warp::{
     http::header,
@@ -37,6 +56,10 @@ const LABEL_SUCCESS: &str = "success";
 const LABEL_BATCH: &str = "batch";
 const LABEL_SINGLE: &str = "single";
 
+/// Commit events queued for the resource watches at most. When they fall further behind, the
+/// changes of the dropped events are posted along with those of the next one.
+const COMMIT_EVENTS_CAPACITY: usize = 100;
+
 #[derive(Schema)]
 struct HttpRequestLog<'a> {
     #[schema(display)]
@@ -98,9 +121,17 @@ pub fn bootstrap(
     address: SocketAddr,
     batch_size_limit: u16,
     page_size_limit: u16,
+    min_version_wait_ms: u64,
     content_len_limit: usize,
+    api_keys: &ApiKeysConfig,
+    query_budgets: &QueryBudgetsConfig,
+    response_compression: &ResponseCompressionConfig,
+    response_signing_key: Option<Ed25519PrivateKey>,
     tls_cert_path: &Option<String>,
     tls_key_path: &Option<String>,
+    webhooks: &WebhooksConfig,
+    resource_watches: &ResourceWatchesConfig,
+    commit_event_bus: &CommitEventBus,
     diem_db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
     role: RoleType,
@@ -112,6 +143,19 @@ pub fn bootstrap(
         .build()
         .expect("[json-rpc] failed to create runtime");
 
+    let webhooks = Arc::new(Webhooks::new(webhooks));
+    if webhooks.enabled() {
+        runtime.spawn(webhooks.clone().run(diem_db.clone()));
+    }
+    let resource_watches = Arc::new(ResourceWatches::new(resource_watches));
+    if resource_watches.enabled() {
+        let commit_events = commit_event_bus.subscribe(
+            "json-rpc resource watches",
+            COMMIT_EVENTS_CAPACITY,
+            Some(&counters::PENDING_RESOURCE_WATCH_COMMIT_EVENTS),
+        );
+        runtime.spawn(resource_watches.clone().run(diem_db.clone(), commit_events));
+    }
     let service = JsonRpcService::new(
         diem_db.clone(),
         mp_sender,
@@ -119,7 +163,15 @@ pub fn bootstrap(
         chain_id,
         batch_size_limit,
         page_size_limit,
+        min_version_wait_ms,
+        response_signing_key,
+        webhooks,
+        resource_watches,
     );
+    let api_keys = Arc::new(ApiKeys::new(api_keys));
+    let query_budgets = Arc::new(QueryBudgets::new(query_budgets, page_size_limit));
+    let response_compression = Arc::new(ResponseCompression::new(response_compression));
+    let health_monitor = Arc::new(HealthMonitor::new(diem_db.clone()));
 
     let base_route = warp::any()
         .and(warp::post())
@@ -127,7 +179,14 @@ pub fn bootstrap(
         .and(warp::body::content_length_limit(content_len_limit as u64))
         .and(warp::body::json())
         .and(warp::any().map(move || service.clone()))
+        .and(warp::any().map(move || api_keys.clone()))
+        .and(warp::any().map(move || query_budgets.clone()))
+        .and(warp::any().map(move || response_compression.clone()))
+        .and(warp::addr::remote())
+        .and(warp::filters::header::optional::<String>(X_DIEM_API_KEY))
+        .and(warp::filters::header::optional::<u64>("content-length"))
         .and(warp::filters::header::optional::<String>("user-agent"))
+        .and(warp::filters::header::optional::<String>("accept-encoding"))
         .and_then(rpc_endpoint)
         .with(warp::log::custom(|info| {
             debug!(HttpRequestLog {
@@ -147,12 +206,12 @@ pub fn bootstrap(
         // CORS is required for full node server to accept requests from different domain web pages.
         // It needs to be configured for the json-rpc request accepting method and headers.
         // Technically it's fine for any headers, but for simplicity we only set must have header
-        // content-type.
+        // content-type, and the API key identifying the consumer.
         .with(
             warp::cors()
                 .allow_any_origin()
                 .allow_methods(vec!["POST"])
-                .allow_headers(vec![header::CONTENT_TYPE]),
+                .allow_headers(vec![header::CONTENT_TYPE.as_str(), X_DIEM_API_KEY]),
         );
 
     // For now we still allow user to use "/", but user should start to move to "/v1" soon
@@ -169,7 +228,15 @@ pub fn bootstrap(
         .and(warp::any().map(SystemTime::now))
         .and_then(health_check);
 
-    let full_route = health_route.or(route_v1.or(route_root));
+    let node_health_route = warp::path!("node" / "health")
+        .and(warp::path::end())
+        .and(warp::any().map(move || health_monitor.clone()))
+        .and(warp::any().map(SystemTime::now))
+        .and_then(node_health);
+
+    let full_route = health_route
+        .or(node_health_route)
+        .or(route_v1.or(route_root));
 
     // Ensure that we actually bind to the socket first before spawning the
     // server tasks. This helps in tests to prevent races where a client attempts
@@ -199,14 +266,34 @@ pub fn bootstrap_from_config(
     chain_id: ChainId,
     diem_db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
+    commit_event_bus: &CommitEventBus,
 ) -> Runtime {
+    if let Some(path) = &config.json_rpc.abort_codes_path {
+        if let Err(e) =
+            CustomErrorMapping::load(path).and_then(error_explain::register_custom_explanations)
+        {
+            warn!(error = ?e, "Failed to register custom abort codes.");
+        }
+    }
     bootstrap(
         config.json_rpc.address,
         config.json_rpc.batch_size_limit,
         config.json_rpc.page_size_limit,
+        config.json_rpc.min_version_wait_ms,
         config.json_rpc.content_length_limit,
+        &config.json_rpc.api_keys,
+        &config.json_rpc.query_budgets,
+        &config.json_rpc.response_compression,
+        config
+            .json_rpc
+            .response_signing_key
+            .as_ref()
+            .map(ConfigKey::private_key),
         &config.json_rpc.tls_cert_path,
         &config.json_rpc.tls_key_path,
+        &config.json_rpc.webhooks,
+        &config.json_rpc.resource_watches,
+        commit_event_bus,
         diem_db,
         mp_sender,
         config.base.role,
@@ -228,9 +315,41 @@ async fn health_check(
         check_latest_ledger_info_timestamp(duration, timestamp, now)
             .map_err(|_| reject::custom(HealthCheckError))?;
     }
+    let corrupted_version_ranges = db
+        .get_corrupted_version_ranges()
+        .map_err(|_| reject::custom(HealthCheckError))?;
+    if !corrupted_version_ranges.is_empty() {
+        let ranges = corrupted_version_ranges
+            .iter()
+            .map(|range| format!("{}..{}", range.start, range.end))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Ok(Box::new(warp::reply::with_status(
+            format!("diem-node:storage corrupted at versions {}", ranges),
+            warp::http::StatusCode::SERVICE_UNAVAILABLE,
+        )));
+    }
     Ok(Box::new("diem-node:ok"))
 }
 
+/// Serves the health of the node as JSON, with a 503 status when it's unhealthy
+async fn node_health(
+    monitor: Arc<HealthMonitor>,
+    now: SystemTime,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let health = monitor
+        .check(now)
+        .map_err(|_| reject::custom(HealthCheckError))?;
+    let status = match health.status {
+        HealthStatus::Unhealthy => warp::http::StatusCode::SERVICE_UNAVAILABLE,
+        HealthStatus::Healthy | HealthStatus::Degraded => warp::http::StatusCode::OK,
+    };
+    Ok(Box::new(warp::reply::with_status(
+        warp::reply::json(&health),
+        status,
+    )))
+}
+
 pub fn check_latest_ledger_info_timestamp(
     duration_sec: u64,
     timestamp_usecs: u64,
@@ -249,7 +368,14 @@ pub fn check_latest_ledger_info_timestamp(
 pub(crate) async fn rpc_endpoint(
     data: Value,
     service: JsonRpcService,
+    api_keys: Arc<ApiKeys>,
+    query_budgets: Arc<QueryBudgets>,
+    response_compression: Arc<ResponseCompression>,
+    remote_addr: Option<SocketAddr>,
+    api_key: Option<String>,
+    content_length: Option<u64>,
     user_agent: Option<String>,
+    accept_encoding: Option<String>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     let label = match data {
         Value::Array(_) => LABEL_BATCH,
@@ -259,7 +385,19 @@ pub(crate) async fn rpc_endpoint(
     let timer = counters::RPC_REQUEST_LATENCY
         .with_label_values(&[label])
         .start_timer();
-    let ret = rpc_endpoint_without_metrics(data, service, user_agent.as_deref()).await;
+    let ret = rpc_endpoint_without_metrics(
+        data,
+        service,
+        &api_keys,
+        &query_budgets,
+        &response_compression,
+        remote_addr.map(|addr| addr.ip()),
+        api_key.as_deref(),
+        content_length,
+        user_agent.as_deref(),
+        accept_encoding.as_deref(),
+    )
+    .await;
     timer.stop_and_record();
     ret
 }
@@ -267,7 +405,14 @@ pub(crate) async fn rpc_endpoint(
 async fn rpc_endpoint_without_metrics(
     data: Value,
     service: JsonRpcService,
+    api_keys: &ApiKeys,
+    query_budgets: &QueryBudgets,
+    response_compression: &ResponseCompression,
+    client: Option<IpAddr>,
+    api_key: Option<&str>,
+    content_length: Option<u64>,
     user_agent: Option<&str>,
+    accept_encoding: Option<&str>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     // take snapshot of latest version of DB to be used across all requests, especially for batched requests
     let ledger_info = service
@@ -284,42 +429,91 @@ async fn rpc_endpoint_without_metrics(
     let latest_ledger_version = ledger_info.ledger_info().version();
     let latest_ledger_timestamp_usecs = ledger_info.ledger_info().timestamp_usecs();
     let sdk_info = sdk_info_from_user_agent(user_agent);
+    let (label, num_requests) = match &data {
+        Value::Array(requests) => (LABEL_BATCH, requests.len()),
+        _ => (LABEL_SINGLE, 1),
+    };
 
-    let resp = if let Value::Array(requests) = data {
-        match service.validate_batch_size_limit(requests.len()) {
-            Ok(_) => {
-                // batch API call
-                let futures = requests.into_iter().map(|req| {
-                    rpc_request_handler(req, &service, &ledger_info, LABEL_BATCH, sdk_info)
-                });
-                let responses = join_all(futures).await;
-                for resp in &responses {
-                    log_response!(&trace_id, &resp, true);
+    let admission = api_keys.admit(api_key, num_requests);
+    let resp = match admission {
+        Ok(consumer) => {
+            if let Value::Array(requests) = data {
+                counters::BATCH_SIZE.observe(requests.len() as f64);
+                match service.validate_batch_size_limit(requests.len()) {
+                    Ok(_) => {
+                        // batch API call
+                        let futures = requests.into_iter().map(|req| {
+                            rpc_request_handler(
+                                req,
+                                &service,
+                                &ledger_info,
+                                consumer,
+                                query_budgets,
+                                client,
+                                LABEL_BATCH,
+                                sdk_info,
+                            )
+                        });
+                        let responses = join_all(futures).await;
+                        for resp in &responses {
+                            log_response!(&trace_id, &resp, true);
+                        }
+                        warp::reply::json(&responses)
+                    }
+                    Err(err) => {
+                        let mut response = JsonRpcResponse::new(
+                            chain_id,
+                            latest_ledger_version,
+                            latest_ledger_timestamp_usecs,
+                        );
+                        response.error = Some(err);
+                        bump_counters(&response, LABEL_BATCH, None, sdk_info);
+                        log_response!(&trace_id, &response, true);
+
+                        warp::reply::json(&response)
+                    }
                 }
-                warp::reply::json(&responses)
-            }
-            Err(err) => {
-                let mut response = JsonRpcResponse::new(
-                    chain_id,
-                    latest_ledger_version,
-                    latest_ledger_timestamp_usecs,
-                );
-                response.error = Some(err);
-                bump_counters(&response, LABEL_BATCH, None, sdk_info);
-                log_response!(&trace_id, &response, true);
-
-                warp::reply::json(&response)
+            } else {
+                // single API call
+                let resp = rpc_request_handler(
+                    data,
+                    &service,
+                    &ledger_info,
+                    consumer,
+                    query_budgets,
+                    client,
+                    LABEL_SINGLE,
+                    sdk_info,
+                )
+                .await;
+                log_response!(&trace_id, &resp, false);
+
+                warp::reply::json(&resp)
             }
         }
-    } else {
-        // single API call
-        let resp = rpc_request_handler(data, &service, &ledger_info, LABEL_SINGLE, sdk_info).await;
-        log_response!(&trace_id, &resp, false);
-
-        warp::reply::json(&resp)
+        Err(ref err) => {
+            // the consumer is unknown or over its request rate, nothing is served
+            let mut response = JsonRpcResponse::new(
+                chain_id,
+                latest_ledger_version,
+                latest_ledger_timestamp_usecs,
+            );
+            response.error = Some(err.clone());
+            bump_counters(&response, label, None, sdk_info);
+            log_response!(&trace_id, &response, label == LABEL_BATCH);
+
+            warp::reply::json(&response)
+        }
     };
 
-    let mut http_response = resp.into_response();
+    // compressed before recording the response bytes, so consumers are accounted the bytes sent
+    let mut http_response = response_compression
+        .compress(resp.into_response(), accept_encoding)
+        .await;
+    if let Ok(consumer) = admission {
+        let response_bytes = http_response.body().size_hint().exact().unwrap_or(0);
+        consumer.record_bytes(content_length.unwrap_or(0), response_bytes);
+    }
     let headers = http_response.headers_mut();
 
     headers.insert(
@@ -342,17 +536,23 @@ async fn rpc_request_handler(
     request: Value,
     service: &JsonRpcService,
     ledger_info: &LedgerInfoWithSignatures,
+    consumer: &Consumer,
+    query_budgets: &QueryBudgets,
+    client: Option<IpAddr>,
     request_type_label: &str,
     sdk_info: SdkInfo,
 ) -> JsonRpcResponse {
-    let handler = Handler::new(&service, &ledger_info);
-
     let mut response = JsonRpcResponse::new(
         service.chain_id(),
         ledger_info.ledger_info().version(),
         ledger_info.ledger_info().timestamp_usecs(),
     );
     let method: Option<Method>;
+    // requests carrying a client nonce get signed responses when the node has a signing key
+    let request_to_sign = match service.response_signing_key() {
+        Some(_) if request.get(DIEM_CLIENT_NONCE).is_some() => Some(request.clone()),
+        _ => None,
+    };
 
     match diem_json_rpc_types::request::JsonRpcRequest::from_value(request) {
         Ok(request) => {
@@ -361,7 +561,39 @@ async fn rpc_request_handler(
                 .with_label_values(&[request_type_label, request.method_request.method().as_str()])
                 .start_timer();
             response.id = Some(serde_json::to_value(&request.id).unwrap());
-            match handler.handle(request.method_request).await {
+            // reads asking for a version the snapshot hasn't reached wait for storage to catch up
+            let credits = consumer
+                .authorize(&request.method_request)
+                .and_then(|_| consumer.acquire_credits(request.method_request.credits()));
+            // costly queries also wait for the budget of their client
+            let credits = match credits {
+                Ok(_) => query_budgets.acquire(client, &request.method_request).await,
+                Err(e) => Err(e),
+            };
+            let result = match (credits, request.method_request.min_version()) {
+                // consumers over their credit rate or query budget, or not allowed the request, are
+                // not served
+                (Err(e), _) => Err(e),
+                (Ok(_), Some(min_version)) if min_version > ledger_info.ledger_info().version() => {
+                    match service.wait_for_ledger_version(min_version).await {
+                        Ok(ledger_info) => {
+                            response.diem_ledger_version = ledger_info.ledger_info().version();
+                            response.diem_ledger_timestampusec =
+                                ledger_info.ledger_info().timestamp_usecs();
+                            Handler::new(&service, &ledger_info)
+                                .handle(request.method_request)
+                                .await
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                _ => {
+                    Handler::new(&service, &ledger_info)
+                        .handle(request.method_request)
+                        .await
+                }
+            };
+            match result {
                 Ok(ret) => response.result = Some(ret),
                 Err(e) => response.error = Some(e),
             }
@@ -374,6 +606,10 @@ async fn rpc_request_handler(
         }
     }
 
+    if let (Some(signing_key), Some(request)) = (service.response_signing_key(), request_to_sign) {
+        response.sign(&request, signing_key);
+    }
+
     bump_counters(&response, request_type_label, method, sdk_info);
 
     response
@@ -403,6 +639,7 @@ fn bump_counters(
                 -32601 => "method_not_found",
                 -32602 => "invalid_params",
                 -32604 => "invalid_format",
+                -32013 => "stale_ledger_version",
                 _ => "unexpected_code",
             };
             counters::INVALID_REQUESTS
@@ -0,0 +1,131 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derives the schemas of the transaction scripts known to the node from their ABIs, for
+//! `get_script_schemas`
+
+use crate::views::{ScriptArgumentSchemaView, ScriptSchemaView};
+use diem_framework_releases::{
+    current_script_abis, legacy::transaction_scripts::LegacyStdlibScript,
+};
+use diem_types::{account_address::AccountAddress, transaction::ScriptABI};
+use move_core_types::language_storage::TypeTag;
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+static SCRIPT_SCHEMAS: Lazy<Vec<ScriptSchemaView>> = Lazy::new(|| {
+    let legacy_abis = LegacyStdlibScript::all()
+        .into_iter()
+        .map(|script| ScriptABI::TransactionScript(script.abi()));
+    legacy_abis
+        .chain(current_script_abis().iter().cloned())
+        .map(|abi| script_schema(&abi))
+        .collect()
+});
+
+/// The schemas of the legacy transaction scripts, then of the script functions of the current
+/// release
+pub fn script_schemas() -> &'static [ScriptSchemaView] {
+    &SCRIPT_SCHEMAS
+}
+
+fn script_schema(abi: &ScriptABI) -> ScriptSchemaView {
+    let module = match abi {
+        ScriptABI::TransactionScript(_) => None,
+        ScriptABI::ScriptFunction(abi) => Some(format!(
+            "0x{}::{}",
+            abi.module_name().address().short_str_lossless(),
+            abi.module_name().name()
+        )),
+    };
+    ScriptSchemaView {
+        name: abi.name().to_string(),
+        module,
+        doc: abi.doc().to_string(),
+        type_arguments: abi
+            .ty_args()
+            .iter()
+            .map(|ty_arg| ty_arg.name().to_string())
+            .collect(),
+        arguments: abi
+            .args()
+            .iter()
+            .map(|arg| ScriptArgumentSchemaView {
+                name: arg.name().to_string(),
+                type_: type_name(arg.type_tag()),
+                schema: argument_schema(arg.type_tag()),
+            })
+            .collect(),
+    }
+}
+
+/// Spells `type_tag` as in Move source
+fn type_name(type_tag: &TypeTag) -> String {
+    match type_tag {
+        TypeTag::Bool => "bool".to_string(),
+        TypeTag::U8 => "u8".to_string(),
+        TypeTag::U64 => "u64".to_string(),
+        TypeTag::U128 => "u128".to_string(),
+        TypeTag::Address => "address".to_string(),
+        TypeTag::Signer => "signer".to_string(),
+        TypeTag::Vector(type_tag) => format!("vector<{}>", type_name(type_tag)),
+        TypeTag::Struct(struct_tag) => struct_tag.to_string(),
+    }
+}
+
+/// The JSON schema of the arguments of type `type_tag`, as JSON-RPC clients spell them: numbers
+/// for integers up to `u64`, decimal strings for `u128`, and hex strings for addresses and bytes
+fn argument_schema(type_tag: &TypeTag) -> Value {
+    match type_tag {
+        TypeTag::Bool => json!({"type": "boolean"}),
+        TypeTag::U8 => json!({"type": "integer", "minimum": 0, "maximum": u8::MAX}),
+        TypeTag::U64 => json!({"type": "integer", "minimum": 0, "maximum": u64::MAX}),
+        TypeTag::U128 => json!({"type": "string", "pattern": "^[0-9]{1,39}$"}),
+        TypeTag::Address => json!({
+            "type": "string",
+            "pattern": format!("^[0-9a-fA-F]{{{}}}$", AccountAddress::LENGTH * 2),
+        }),
+        TypeTag::Vector(type_tag) if **type_tag == TypeTag::U8 => json!({
+            "type": "string",
+            "contentEncoding": "base16",
+            "pattern": "^([0-9a-fA-F]{2})*$",
+        }),
+        TypeTag::Vector(type_tag) => json!({
+            "type": "array",
+            "items": argument_schema(type_tag),
+        }),
+        // not valid as script arguments
+        TypeTag::Signer | TypeTag::Struct(_) => json!(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argument_schema() {
+        assert_eq!(
+            argument_schema(&TypeTag::U64),
+            json!({"type": "integer", "minimum": 0, "maximum": 18446744073709551615u64})
+        );
+        assert_eq!(
+            argument_schema(&TypeTag::Address),
+            json!({"type": "string", "pattern": "^[0-9a-fA-F]{32}$"})
+        );
+        let vector_of_bytes = TypeTag::Vector(Box::new(TypeTag::Vector(Box::new(TypeTag::U8))));
+        assert_eq!(type_name(&vector_of_bytes), "vector<vector<u8>>");
+        assert_eq!(
+            argument_schema(&vector_of_bytes),
+            json!({
+                "type": "array",
+                "items": {
+                    "type": "string",
+                    "contentEncoding": "base16",
+                    "pattern": "^([0-9a-fA-F]{2})*$",
+                },
+            })
+        );
+        assert_eq!(argument_schema(&TypeTag::Signer), json!(false));
+    }
+}
@@ -2,34 +2,53 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::ServerCode,
+    data,
+    errors::{InvalidRequestCode, ServerCode},
+    health::{HealthMonitor, HealthStatus},
+    resource_watches::ResourceWatches,
     runtime::check_latest_ledger_info_timestamp,
     tests::utils::{
         create_database_client_and_runtime, create_db_and_runtime, mock_db, test_bootstrap,
         MockDiemDB,
     },
     util::{sdk_info_from_user_agent, SdkInfo, SdkLang, SdkVersion},
-    views::VMStatusView,
+    views::{BytesView, EventPageView, VMStatusView},
+    webhooks::Webhooks,
+};
+use diem_client::{
+    views::TransactionDataView, BlockingClient, ErrorKind, EventFilter, MethodRequest,
+};
+use diem_config::{
+    config::{ResourceWatchesConfig, WebhooksConfig, DEFAULT_CONTENT_LENGTH_LIMIT},
+    utils,
 };
-use diem_client::{views::TransactionDataView, BlockingClient, MethodRequest};
-use diem_config::{config::DEFAULT_CONTENT_LENGTH_LIMIT, utils};
 use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
+use diem_json_rpc_types::request::WatchedResource;
+use diem_mempool::MempoolClientRequest;
 use diem_metrics::get_all_metrics;
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
-    account_config::AccountResource,
+    account_config::{AccountResource, FreezingBit, SentPaymentEvent, CORE_CODE_ADDRESS, XUS_NAME},
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     chain_id::ChainId,
+    contract_event::ContractEvent,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
-    test_helpers::transaction_test_helpers::get_test_signed_txn,
+    test_helpers::transaction_test_helpers::{get_test_signed_transaction, get_test_signed_txn},
     transaction::{Transaction, TransactionInfo, TransactionPayload},
     vm_status::StatusCode,
+    write_set::WriteOp,
 };
 use futures::{channel::mpsc::channel, StreamExt};
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+    move_resource::{MoveResource, MoveStructType},
+};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{
     cmp::{max, min},
@@ -762,6 +781,22 @@ fn test_json_rpc_protocol_invalid_requests() {
                 "diem_ledger_version": version
             }),
         ),
+        (
+            "get_epoch_info: epoch has not started",
+            json!({"jsonrpc": "2.0", "method": "get_epoch_info", "params": [1], "id": 1}),
+            json!({
+                "error": {
+                    "code": -32602,
+                    "message": "Invalid param epoch should be between 1 and the latest epoch 0",
+                    "data": null
+                },
+                "id": 1,
+                "jsonrpc": "2.0",
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_timestampusec": timestamp,
+                "diem_ledger_version": version
+            }),
+        ),
         (
             "get_account_state_with_proof: invalid account address",
             json!({"jsonrpc": "2.0", "method": "get_account_state_with_proof", "params": ["invalid", 1, 1], "id": 1}),
@@ -953,6 +988,8 @@ fn test_metrics() {
         // rpc request latency
         "diem_client_service_rpc_request_latency_seconds{type=single}",
         "diem_client_service_rpc_request_latency_seconds{type=batch}",
+        // batch size
+        "diem_client_service_batch_size{}",
         // method request count
         "diem_client_service_requests_count{method=get_currencies,result=success,sdk_lang=python,sdk_ver=2.11.15,type=single}",
         // method latency
@@ -984,7 +1021,12 @@ fn test_transaction_submission() {
     // future that mocks shared mempool execution
     runtime.spawn(async move {
         let validator = MockVMValidator;
-        while let Some((txn, cb)) = mp_events.next().await {
+        while let Some(request) = mp_events.next().await {
+            let (txn, cb) = match request {
+                MempoolClientRequest::SubmitTransaction(txn, cb) => (txn, cb),
+                MempoolClientRequest::ValidateTransaction(..)
+                | MempoolClientRequest::AllocateSequenceNumber(..) => unreachable!(),
+            };
             let vm_status = validator.validate_transaction(txn).unwrap().status();
             let result = if vm_status.is_some() {
                 (MempoolStatus::new(MempoolStatusCode::VmError), vm_status)
@@ -1017,6 +1059,104 @@ fn test_transaction_submission() {
     assert_eq!(status_code, StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST);
 }
 
+#[test]
+fn test_validate_transaction() {
+    let (mp_sender, mut mp_events) = channel(1);
+    let mock_db = mock_db();
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let runtime = test_bootstrap(address.parse().unwrap(), Arc::new(mock_db), mp_sender);
+    let client = BlockingClient::new(format!("http://127.0.0.1:{}/v1", port));
+
+    // future that mocks shared mempool, which would be full if the transactions were submitted
+    runtime.spawn(async move {
+        let validator = MockVMValidator;
+        while let Some(request) = mp_events.next().await {
+            match request {
+                MempoolClientRequest::SubmitTransaction(_, cb) => {
+                    let status = MempoolStatus::new(MempoolStatusCode::MempoolIsFull);
+                    cb.send(Ok((status, None))).unwrap();
+                }
+                MempoolClientRequest::ValidateTransaction(txn, cb) => {
+                    let vm_status = validator.validate_transaction(txn).unwrap().status();
+                    let result = if vm_status.is_some() {
+                        (MempoolStatus::new(MempoolStatusCode::VmError), vm_status)
+                    } else {
+                        (MempoolStatus::new(MempoolStatusCode::Accepted), None)
+                    };
+                    cb.send(Ok(result)).unwrap();
+                }
+                MempoolClientRequest::AllocateSequenceNumber(..) => unreachable!(),
+            }
+        }
+    });
+
+    let txn_validation = move |sender| {
+        let privkey = Ed25519PrivateKey::generate_for_testing();
+        let txn = get_test_signed_txn(sender, 0, &privkey, privkey.public_key(), None);
+        client.validate_transaction(&txn)
+    };
+
+    let sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    txn_validation(sender).unwrap();
+
+    // errors are the ones a submission fails with
+    let sender = AccountAddress::new([0; AccountAddress::LENGTH]);
+    let error = txn_validation(sender).unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, ServerCode::VmValidationError as i16);
+    let status_code: StatusCode = error.as_status_code().unwrap();
+    assert_eq!(status_code, StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST);
+}
+
+#[test]
+fn test_allocate_sequence_number() {
+    let (mp_sender, mut mp_events) = channel(1);
+    let mock_db = mock_db();
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let runtime = test_bootstrap(address.parse().unwrap(), Arc::new(mock_db), mp_sender);
+    let client = BlockingClient::new(format!("http://127.0.0.1:{}/v1", port));
+
+    // future that mocks shared mempool, allocating sequence numbers to a single sender
+    let allocation_sender = AccountAddress::new([9; AccountAddress::LENGTH]);
+    runtime.spawn(async move {
+        let mut next_sequence_number = 0;
+        while let Some(request) = mp_events.next().await {
+            match request {
+                MempoolClientRequest::AllocateSequenceNumber(sender, cb) => {
+                    let sequence_number = if sender == allocation_sender {
+                        next_sequence_number += 1;
+                        Some(next_sequence_number - 1)
+                    } else {
+                        None
+                    };
+                    cb.send(Ok(sequence_number)).unwrap();
+                }
+                MempoolClientRequest::SubmitTransaction(..)
+                | MempoolClientRequest::ValidateTransaction(..) => unreachable!(),
+            }
+        }
+    });
+
+    for expected in 0..3 {
+        let sequence_number = client
+            .allocate_sequence_number(allocation_sender)
+            .unwrap()
+            .into_inner();
+        assert_eq!(sequence_number, expected);
+    }
+
+    let error = client
+        .allocate_sequence_number(AccountAddress::new([0; AccountAddress::LENGTH]))
+        .unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(
+        error.code,
+        ServerCode::SequenceNumberAllocationNotEnabled as i16
+    );
+}
+
 #[test]
 fn test_get_account() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1104,6 +1244,31 @@ fn test_get_metadata() {
     assert_eq!(metadata.timestamp, mock_db.timestamps[1]);
 }
 
+#[test]
+fn test_min_version() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+    let version = mock_db.version;
+    let account = get_first_account_from_mock_db(&mock_db);
+
+    // min_version already committed is served from the snapshot
+    let request = json!({"jsonrpc": "2.0", "method": "get_account", "params": [account, null, version], "id": 1});
+    let resp = client.post(&url).json(&request).send().unwrap();
+    let resp_json: serde_json::Value = resp.json().unwrap();
+    assert!(resp_json.get("result").is_some(), "{}", resp_json);
+
+    // min_version never reached by the mock db times out with a retriable error
+    let request = json!({"jsonrpc": "2.0", "method": "get_account", "params": [account, null, version + 1], "id": 1});
+    let resp = client.post(&url).json(&request).send().unwrap();
+    let resp_json: serde_json::Value = resp.json().unwrap();
+    assert_eq!(
+        resp_json["error"]["code"],
+        json!(ServerCode::StaleLedgerVersion as i16),
+        "{}",
+        resp_json
+    );
+}
+
 #[test]
 fn test_limit_batch_size() {
     let (_, client, _runtime) = create_database_client_and_runtime();
@@ -1121,6 +1286,21 @@ fn test_limit_batch_size() {
     assert_eq!(format!("{:?}", error), expected)
 }
 
+#[test]
+fn test_empty_batch() {
+    let (_mock_db, _runtime, url, _) = create_db_and_runtime();
+    let client = reqwest::blocking::Client::new();
+    let resp = client.post(&url).json(&json!([])).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp_json: serde_json::Value = resp.json().unwrap();
+    assert_eq!(
+        resp_json["error"],
+        json!({"code": -32600, "message": "Invalid Request: batch is empty", "data": null}),
+        "{}",
+        resp_json
+    );
+}
+
 #[test]
 fn test_get_events_page_limit() {
     let (_, client, _runtime) = create_database_client_and_runtime();
@@ -1178,6 +1358,197 @@ fn test_get_events() {
     );
 }
 
+#[test]
+fn test_get_events_page() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let key = *mock_db.events[0].1.key();
+    let expected: Vec<_> = mock_db
+        .events
+        .iter()
+        .filter(|(_, event)| *event.key() == key)
+        .map(|(_, event)| event.sequence_number())
+        .collect();
+
+    let mut sequence_numbers = vec![];
+    let mut cursor = None;
+    loop {
+        let page = client.get_events_page(key, 2, cursor).unwrap().into_inner();
+        assert!(page.events.len() <= 2);
+        sequence_numbers.extend(page.events.iter().map(|event| event.sequence_number));
+        cursor = Some(page.next);
+        if !page.has_more {
+            break;
+        }
+    }
+    assert_eq!(sequence_numbers, expected);
+
+    // the last cursor resumes after the events returned
+    let page = client.get_events_page(key, 2, cursor).unwrap().into_inner();
+    assert!(page.events.is_empty());
+    assert!(!page.has_more);
+
+    // a cursor is only valid for the key it was issued for
+    let other_key = EventKey::new_from_address(&AccountAddress::random(), 0);
+    let error = client.get_events_page(other_key, 2, cursor).unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+}
+
+#[test]
+fn test_get_events_page_pinned_to_version() {
+    let key = EventKey::new_from_address(&AccountAddress::random(), 0);
+    let mut mock_db = mock_db();
+    for (seq, version) in [1, 1, 1, 2, 2].iter().enumerate() {
+        let event = ContractEvent::new(key, seq as u64, TypeTag::Bool, vec![]);
+        mock_db.events.push((*version, event));
+    }
+    let sequence_numbers = |page: &EventPageView| -> Vec<u64> {
+        page.events
+            .iter()
+            .map(|event| event.sequence_number)
+            .collect()
+    };
+
+    let page = data::get_events_page(&mock_db, 1, key, 2, None, None).unwrap();
+    assert_eq!(sequence_numbers(&page), vec![0, 1]);
+    assert!(page.has_more);
+
+    // events committed since don't shift the pages continued from the cursor
+    let page = data::get_events_page(&mock_db, 2, key, 2, Some(page.next), None).unwrap();
+    assert_eq!(sequence_numbers(&page), vec![2]);
+    assert_eq!(page.ledger_version, 1);
+    assert!(!page.has_more);
+
+    // once the pages of the version are returned, the cursor resumes with the new events
+    let page = data::get_events_page(&mock_db, 2, key, 2, Some(page.next), None).unwrap();
+    assert_eq!(sequence_numbers(&page), vec![3, 4]);
+    assert_eq!(page.ledger_version, 2);
+    assert!(!page.has_more);
+}
+
+#[test]
+fn test_get_filtered_events() {
+    let sender = AccountAddress::random();
+    let key = EventKey::new_from_address(&sender, 0);
+    let mut mock_db = mock_db();
+    for (seq, amount) in [10, 100, 1000].iter().enumerate() {
+        let payment = SentPaymentEvent::new(
+            *amount,
+            Identifier::new(XUS_NAME).unwrap(),
+            AccountAddress::random(),
+            vec![],
+        );
+        let event = ContractEvent::new(
+            key,
+            seq as u64,
+            TypeTag::Struct(SentPaymentEvent::struct_tag()),
+            bcs::to_bytes(&payment).unwrap(),
+        );
+        mock_db.events.push((1, event));
+    }
+    mock_db
+        .events
+        .push((1, ContractEvent::new(key, 3, TypeTag::Bool, vec![1])));
+
+    let (mp_sender, _mp_events) = channel(1);
+    let port = utils::get_available_port();
+    let address = format!("0.0.0.0:{}", port);
+    let _runtime = test_bootstrap(address.parse().unwrap(), Arc::new(mock_db), mp_sender);
+    let client = BlockingClient::new(format!("http://127.0.0.1:{}/v1", port));
+    let sequence_numbers = |filter: EventFilter| -> Vec<u64> {
+        client
+            .get_filtered_events(key, 0, 10, filter)
+            .unwrap()
+            .into_inner()
+            .iter()
+            .map(|event| event.sequence_number)
+            .collect()
+    };
+
+    assert_eq!(sequence_numbers(EventFilter::default()), vec![0, 1, 2, 3]);
+    assert_eq!(
+        sequence_numbers(EventFilter {
+            type_tag: Some(SentPaymentEvent::struct_tag().to_string()),
+            ..EventFilter::default()
+        }),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        sequence_numbers(EventFilter {
+            min_amount: Some(100),
+            ..EventFilter::default()
+        }),
+        vec![1, 2]
+    );
+    assert_eq!(
+        sequence_numbers(EventFilter {
+            sender: Some(sender),
+            max_amount: Some(100),
+            ..EventFilter::default()
+        }),
+        vec![0, 1]
+    );
+    assert!(sequence_numbers(EventFilter {
+        sender: Some(AccountAddress::random()),
+        ..EventFilter::default()
+    })
+    .is_empty());
+
+    let error = client
+        .get_filtered_events(
+            key,
+            0,
+            10,
+            EventFilter {
+                type_tag: Some("0x1::".to_owned()),
+                ..EventFilter::default()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::JsonRpcError);
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+}
+
+#[test]
+fn test_get_event_stats() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let (first_event_version, first_event) = mock_db.events[0].clone();
+    let (last_event_version, last_event) = mock_db
+        .events
+        .iter()
+        .filter(|(_, event)| event.key() == first_event.key())
+        .last()
+        .cloned()
+        .unwrap();
+
+    let stats = client
+        .get_event_stats(*first_event.key())
+        .unwrap()
+        .into_inner();
+    assert_eq!(stats.key, *first_event.key());
+    assert_eq!(stats.count, last_event.sequence_number() + 1);
+    assert_eq!(
+        stats.first_sequence_number,
+        Some(first_event.sequence_number())
+    );
+    assert_eq!(stats.first_transaction_version, Some(first_event_version));
+    assert_eq!(
+        stats.last_sequence_number,
+        Some(last_event.sequence_number())
+    );
+    assert_eq!(stats.last_transaction_version, Some(last_event_version));
+
+    // a key without events
+    let key = EventKey::new_from_address(&AccountAddress::random(), 0);
+    let stats = client.get_event_stats(key).unwrap().into_inner();
+    assert_eq!(stats.count, 0);
+    assert!(stats.first_sequence_number.is_none());
+    assert!(stats.last_transaction_version.is_none());
+}
+
 #[test]
 fn test_get_transactions() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1389,6 +1760,183 @@ fn test_get_account_state_with_proof() {
     assert_eq!(txn_info_with_proof, *expected_txn_info_with_proof);
 }
 
+#[test]
+fn test_get_account_resource() {
+    use move_core_types::move_resource::MoveResource;
+
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let (account, blob) = mock_db.all_accounts.iter().next().unwrap();
+    let path = AccountResource::resource_path();
+    let expected = AccountState::try_from(blob).unwrap().get(&path).cloned();
+    assert!(expected.is_some());
+
+    let resource = client
+        .get_account_resource(*account, &path, None)
+        .unwrap()
+        .into_inner();
+    assert_eq!(resource.version, mock_db.version);
+    assert_eq!(
+        resource.blob.map(|blob| blob.into_inner().to_vec()),
+        expected
+    );
+
+    // the account state the resource was read from, with its proof
+    let account_state_with_proof =
+        AccountStateWithProof::try_from(&resource.account_state_with_proof).unwrap();
+    assert_eq!(account_state_with_proof.blob.as_ref(), Some(blob));
+    assert_eq!(
+        account_state_with_proof.proof,
+        get_first_state_proof_from_mock_db(&mock_db).proof
+    );
+
+    // a path the account does not hold
+    let resource = client
+        .get_account_resource(*account, &[0xff], Some(0))
+        .unwrap()
+        .into_inner();
+    assert_eq!(resource.version, 0);
+    assert!(resource.blob.is_none());
+}
+
+#[test]
+fn test_get_module() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let module = client
+        .get_module(CORE_CODE_ADDRESS, "DiemAccount", None)
+        .unwrap()
+        .into_inner()
+        .unwrap();
+    assert_eq!(module.address, CORE_CODE_ADDRESS);
+    assert_eq!(module.name, "DiemAccount");
+    assert_eq!(module.version, mock_db.version);
+    assert!(!module.bytecode.is_empty());
+
+    let abi = client
+        .get_module_abi(CORE_CODE_ADDRESS, "DiemAccount", None)
+        .unwrap()
+        .into_inner()
+        .unwrap();
+    assert_eq!(abi.name, "DiemAccount");
+    let account = abi
+        .structs
+        .iter()
+        .find(|s| s.name == "DiemAccount")
+        .unwrap();
+    assert_eq!(account.abilities, vec!["key".to_string()]);
+    assert!(!account.fields.is_empty());
+    assert!(!abi.functions.is_empty());
+    assert!(abi.functions.iter().all(|f| f.visibility != "private"));
+
+    // a module that isn't published
+    let module = client
+        .get_module(CORE_CODE_ADDRESS, "NotPublished", Some(0))
+        .unwrap()
+        .into_inner();
+    assert!(module.is_none());
+    let abi = client
+        .get_module_abi(CORE_CODE_ADDRESS, "NotPublished", Some(0))
+        .unwrap()
+        .into_inner();
+    assert!(abi.is_none());
+
+    // a name that isn't an identifier
+    let error = client
+        .get_module(CORE_CODE_ADDRESS, "Not::A::Name", None)
+        .unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+}
+
+#[test]
+fn test_get_script_schemas() {
+    let (_mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let schemas = client.get_script_schemas().unwrap().into_inner();
+    // the legacy transaction scripts and the script functions of the current release
+    assert!(schemas.iter().any(|s| s.module.is_none()));
+    assert!(schemas.iter().any(|s| s.module.is_some()));
+    assert!(schemas.iter().all(|s| !s.name.is_empty()));
+
+    let p2p = schemas
+        .iter()
+        .find(|s| {
+            s.module.as_deref() == Some("0x1::PaymentScripts")
+                && s.name == "peer_to_peer_with_metadata"
+        })
+        .unwrap();
+    assert_eq!(p2p.type_arguments.len(), 1);
+    // the sender's signer isn't an argument
+    let arguments: Vec<_> = p2p
+        .arguments
+        .iter()
+        .map(|arg| (arg.name.as_str(), arg.type_.as_str()))
+        .collect();
+    assert_eq!(
+        arguments,
+        vec![
+            ("payee", "address"),
+            ("amount", "u64"),
+            ("metadata", "vector<u8>"),
+            ("metadata_signature", "vector<u8>"),
+        ]
+    );
+    assert_eq!(
+        p2p.arguments[1].schema,
+        json!({"type": "integer", "minimum": 0, "maximum": u64::MAX})
+    );
+}
+
+#[test]
+fn test_get_transaction_outputs() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let version = mock_db.get_latest_version().unwrap();
+    let limit = min(version + 1, 100);
+    let outputs = client
+        .get_transaction_outputs(0, limit)
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        outputs.len() as u64,
+        min(limit, mock_db.all_txns.len() as u64)
+    );
+
+    for (i, view) in outputs.iter().enumerate() {
+        assert_eq!(view.version, i as u64);
+        let (_, status) = &mock_db.all_txns[i];
+        assert_eq!(VMStatusView::from(status), view.vm_status);
+
+        let write_set = &mock_db.write_sets[i];
+        assert_eq!(view.write_set.len(), write_set.iter().count());
+        for (change, (access_path, op)) in view.write_set.iter().zip(write_set.iter()) {
+            assert_eq!(change.address, access_path.address);
+            assert_eq!(change.path.inner(), access_path.path.as_slice());
+            match op {
+                WriteOp::Value(value) => {
+                    assert_eq!(change.value.as_ref().unwrap().inner(), value.as_slice())
+                }
+                WriteOp::Deletion => assert!(change.value.is_none()),
+            }
+        }
+
+        let expected_events = mock_db
+            .events
+            .iter()
+            .filter(|(v, _)| *v == view.version)
+            .count();
+        assert_eq!(view.events.len(), expected_events);
+    }
+
+    // past the latest version
+    let outputs = client
+        .get_transaction_outputs(version + 1, 10)
+        .unwrap()
+        .into_inner();
+    assert!(outputs.is_empty());
+}
+
 #[test]
 fn test_get_state_proof() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1429,6 +1977,188 @@ fn test_health_check() {
     assert_eq!(resp.status(), 200);
 }
 
+#[test]
+fn test_node_health() {
+    let (mock_db, _runtime, url, _) = create_db_and_runtime();
+    let ledger_info = mock_db.get_latest_ledger_info().unwrap();
+
+    let client = reqwest::blocking::Client::new();
+    let resp = client.get(&format!("{}/node/health", url)).send().unwrap();
+    let status = resp.status();
+    let health: serde_json::Value = resp.json().unwrap();
+    assert_eq!(
+        health["storage"]["latest_version"],
+        json!(ledger_info.ledger_info().version())
+    );
+    if health["status"] == json!("unhealthy") {
+        assert_eq!(status, 503);
+    } else {
+        assert_eq!(status, 200);
+    }
+
+    // Storage degrades as its latest commit ages
+    let monitor = HealthMonitor::new(Arc::new(mock_db));
+    let commit_time =
+        UNIX_EPOCH + Duration::from_micros(ledger_info.ledger_info().timestamp_usecs());
+    let storage_status = |secs_since_commit| {
+        monitor
+            .check(commit_time + Duration::from_secs(secs_since_commit))
+            .unwrap()
+            .storage
+            .status
+    };
+    assert_eq!(storage_status(0), HealthStatus::Healthy);
+    assert_eq!(storage_status(120), HealthStatus::Degraded);
+    assert_eq!(storage_status(600), HealthStatus::Unhealthy);
+}
+
+#[test]
+fn test_webhooks() {
+    let mock_db = mock_db();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _guard = runtime.enter();
+
+    Webhooks::new(&WebhooksConfig::default())
+        .validate_callback_url("http://localhost/")
+        .unwrap_err();
+    let webhooks = Webhooks::new(&WebhooksConfig {
+        allow_client_callbacks: true,
+        ..WebhooksConfig::default()
+    });
+    webhooks.validate_callback_url("localhost").unwrap_err();
+    // Nothing listens there: failed deliveries are only logged
+    let callback_url = "http://localhost:1/";
+    webhooks.validate_callback_url(callback_url).unwrap();
+
+    let committed_txn = mock_db
+        .all_txns
+        .iter()
+        .find_map(|(txn, _)| txn.as_signed_user_txn().ok().cloned())
+        .unwrap();
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let pending_txn = |expiration_timestamp_secs| {
+        get_test_signed_transaction(
+            AccountAddress::random(),
+            0,
+            &private_key,
+            private_key.public_key(),
+            None,
+            expiration_timestamp_secs,
+            0,
+            XUS_NAME.to_owned(),
+            None,
+        )
+    };
+    webhooks.on_accepted(&committed_txn, Some(callback_url.to_owned()));
+    webhooks.on_accepted(&pending_txn(0), Some(callback_url.to_owned()));
+    webhooks.on_accepted(&pending_txn(u64::MAX), Some(callback_url.to_owned()));
+    // Without any webhook to notify, the transaction isn't watched
+    webhooks.on_accepted(&pending_txn(u64::MAX), None);
+    assert_eq!(webhooks.num_watched(), 3);
+
+    // The committed and expired transactions are notified, the other one awaits its outcome
+    webhooks.poll(&mock_db).unwrap();
+    assert_eq!(webhooks.num_watched(), 1);
+}
+
+#[test]
+fn test_resource_watches() {
+    let mock_db = mock_db();
+    let (account, blob) = mock_db
+        .all_accounts
+        .iter()
+        .find(|(account, _)| !mock_db.genesis.contains_key(account))
+        .unwrap();
+    let path = FreezingBit::resource_path();
+    let (frozen, _, _) = mock_db
+        .get_account_resource_by_version(*account, &path, mock_db.version)
+        .unwrap();
+    assert!(frozen.is_some());
+    let resource = |path: Vec<u8>| WatchedResource {
+        account: *account,
+        path: BytesView::new(path),
+    };
+    let callback_url = "http://localhost:1/".to_string();
+
+    ResourceWatches::new(&ResourceWatchesConfig::default())
+        .watch(
+            &mock_db,
+            callback_url.clone(),
+            vec![resource(path.clone())],
+            mock_db.version,
+        )
+        .unwrap_err();
+    let watches = ResourceWatches::new(&ResourceWatchesConfig {
+        enabled: true,
+        ..ResourceWatchesConfig::default()
+    });
+    // Only resources may be watched, not modules
+    let module_path = AccessPath::code_access_path(ModuleId::new(
+        CORE_CODE_ADDRESS,
+        Identifier::new("DiemAccount").unwrap(),
+    ))
+    .path;
+    watches
+        .watch(
+            &mock_db,
+            callback_url.clone(),
+            vec![resource(module_path)],
+            mock_db.version,
+        )
+        .unwrap_err();
+    watches
+        .watch(&mock_db, callback_url.clone(), vec![], mock_db.version)
+        .unwrap_err();
+    let watch = watches
+        .watch(
+            &mock_db,
+            callback_url.clone(),
+            vec![resource(path.clone())],
+            mock_db.version,
+        )
+        .unwrap();
+    assert_eq!(watch.version, mock_db.version);
+
+    // Nothing changed
+    assert!(watches
+        .changes(&mock_db, mock_db.version + 1)
+        .unwrap()
+        .is_empty());
+
+    // The resource is removed from the account
+    let mut changed_db = mock_db.clone();
+    let mut state = AccountState::try_from(blob).unwrap();
+    state.remove(&path);
+    changed_db
+        .all_accounts
+        .insert(*account, AccountStateBlob::try_from(&state).unwrap());
+    let changes = watches.changes(&changed_db, mock_db.version + 2).unwrap();
+    assert_eq!(changes.len(), 1);
+    let (url, changes) = &changes[0];
+    assert_eq!(url, &callback_url);
+    assert_eq!(changes.watch_id, watch.id);
+    assert_eq!(changes.version, mock_db.version + 2);
+    assert_eq!(changes.changes.len(), 1);
+    let change = &changes.changes[0];
+    assert_eq!(change.account, *account);
+    assert_eq!(change.path, BytesView::new(path.clone()));
+    assert_eq!(
+        change.before.as_ref().unwrap().bytes,
+        BytesView::new(frozen.unwrap())
+    );
+    assert_eq!(change.after, None);
+
+    // The change is only posted once
+    assert!(watches
+        .changes(&changed_db, mock_db.version + 3)
+        .unwrap()
+        .is_empty());
+
+    assert!(watches.unwatch(watch.id));
+    assert!(!watches.unwatch(watch.id));
+    assert_eq!(watches.num_watches(), 0);
+}
+
 #[test]
 fn test_sdk_info_from_user_agent() {
     // Invalid user agents:
@@ -4,12 +4,14 @@
 use anyhow::{format_err, Error, Result};
 use diem_config::{
     config::{
-        RoleType, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+        ApiKeysConfig, QueryBudgetsConfig, ResourceWatchesConfig, ResponseCompressionConfig,
+        RoleType, WebhooksConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
+        DEFAULT_MIN_VERSION_WAIT_MS, DEFAULT_PAGE_SIZE_LIMIT,
     },
     utils,
 };
 use diem_crypto::HashValue;
-use diem_mempool::{MempoolClientSender, SubmissionStatus};
+use diem_mempool::{MempoolClientRequest, MempoolClientSender};
 
 use diem_types::{
     account_address::AccountAddress,
@@ -26,10 +28,11 @@ use diem_types::{
         TransactionAccumulatorProof, TransactionInfoWithProof, TransactionListProof,
     },
     transaction::{
-        SignedTransaction, Transaction, TransactionInfo, TransactionListWithProof,
-        TransactionWithProof, Version,
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionStatus, TransactionWithProof, Version,
     },
     vm_status::KeptVMStatus,
+    write_set::WriteSet,
 };
 use diemdb::test_helper::arb_blocks_to_commit;
 
@@ -37,10 +40,7 @@ use crate::tests::genesis::generate_genesis_state;
 use diem_client::BlockingClient;
 use diem_proptest_helpers::ValueGenerator;
 use diem_types::account_config::FreezingBit;
-use futures::channel::{
-    mpsc::{channel, Receiver},
-    oneshot,
-};
+use futures::channel::mpsc::{channel, Receiver};
 use move_core_types::{
     language_storage::TypeTag,
     move_resource::MoveResource,
@@ -54,7 +54,8 @@ use std::{
     net::SocketAddr,
     sync::Arc,
 };
-use storage_interface::{DbReader, Order, StartupInfo, TreeState};
+use storage_interface::{DbReader, EventStats, Order, StartupInfo, TreeState};
+use subscription_service::CommitEventBus;
 use tokio::runtime::Runtime;
 
 /// Creates JSON RPC server for a Validator node
@@ -69,9 +70,17 @@ pub fn test_bootstrap(
         address,
         DEFAULT_BATCH_SIZE_LIMIT,
         DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_MIN_VERSION_WAIT_MS,
         DEFAULT_CONTENT_LENGTH_LIMIT,
+        &ApiKeysConfig::default(),
+        &QueryBudgetsConfig::default(),
+        &ResponseCompressionConfig::default(),
+        None,
         &None,
         &None,
+        &WebhooksConfig::default(),
+        &ResourceWatchesConfig::default(),
+        &CommitEventBus::new(),
         diem_db,
         mp_sender,
         RoleType::Validator,
@@ -87,6 +96,7 @@ pub struct MockDiemDB {
     pub genesis: HashMap<AccountAddress, AccountStateBlob>,
     pub all_accounts: HashMap<AccountAddress, AccountStateBlob>,
     pub all_txns: Vec<(Transaction, KeptVMStatus)>,
+    pub write_sets: Vec<WriteSet>,
     pub events: Vec<(u64, ContractEvent)>,
     pub account_state_with_proof: Vec<AccountStateWithProof>,
     pub timestamps: Vec<u64>,
@@ -229,6 +239,45 @@ impl DbReader for MockDiemDB {
         })
     }
 
+    fn get_transaction_outputs(
+        &self,
+        start_version: u64,
+        limit: u64,
+        ledger_version: u64,
+    ) -> Result<Vec<TransactionOutput>, Error> {
+        // ensure inputs are validated before we enter mock DB
+        assert!(
+            start_version <= ledger_version,
+            "start_version: {}, ledger_version: {}",
+            start_version,
+            ledger_version
+        );
+        assert!(limit > 0, "limit: {}", limit);
+        let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+        Ok(self
+            .all_txns
+            .iter()
+            .zip(self.write_sets.iter())
+            .enumerate()
+            .skip(start_version as usize)
+            .take(limit as usize)
+            .map(|(version, ((_, status), write_set))| {
+                let events = self
+                    .events
+                    .iter()
+                    .filter(|(v, _)| *v == version as u64)
+                    .map(|(_, e)| e.clone())
+                    .collect();
+                TransactionOutput::new(
+                    write_set.clone(),
+                    events,
+                    0,
+                    TransactionStatus::Keep(status.clone()),
+                )
+            })
+            .collect())
+    }
+
     fn get_events(
         &self,
         key: &EventKey,
@@ -249,6 +298,26 @@ impl DbReader for MockDiemDB {
         Ok(events)
     }
 
+    fn get_event_stats(&self, key: &EventKey, ledger_version: u64) -> Result<Option<EventStats>> {
+        let mut events = self
+            .events
+            .iter()
+            .filter(|(version, e)| e.key() == key && *version <= ledger_version);
+        let first = match events.next() {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        let (first_version, first_event) = first;
+        let (last_version, last_event) = events.last().unwrap_or(first);
+        Ok(Some(EventStats {
+            count: last_event.sequence_number() + 1,
+            first_sequence_number: first_event.sequence_number(),
+            first_version: *first_version,
+            last_sequence_number: last_event.sequence_number(),
+            last_version: *last_version,
+        }))
+    }
+
     fn get_events_with_proofs(
         &self,
         _key: &EventKey,
@@ -290,15 +359,19 @@ impl DbReader for MockDiemDB {
 
     fn get_account_state_with_proof(
         &self,
-        _address: AccountAddress,
+        address: AccountAddress,
         _version: Version,
         _ledger_version: Version,
     ) -> Result<AccountStateWithProof> {
-        Ok(self
+        let account_state_with_proof = self
             .account_state_with_proof
             .get(0)
-            .ok_or_else(|| format_err!("could not find account state"))?
-            .clone())
+            .ok_or_else(|| format_err!("could not find account state"))?;
+        Ok(AccountStateWithProof::new(
+            account_state_with_proof.version,
+            self.get_latest_account_state(address)?,
+            account_state_with_proof.proof.clone(),
+        ))
     }
 
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
@@ -361,6 +434,7 @@ pub fn mock_db() -> MockDiemDB {
     let mut version = 1;
     let mut all_accounts = HashMap::new();
     let mut all_txns = vec![];
+    let mut write_sets = vec![];
     let mut events = vec![];
     let mut timestamps = vec![0_u64];
 
@@ -399,12 +473,16 @@ pub fn mock_db() -> MockDiemDB {
                 txn_to_commit.status().clone(),
             )
         }));
+        write_sets.extend(
+            txns_to_commit
+                .iter()
+                .map(|txn_to_commit| txn_to_commit.write_set().clone()),
+        );
     }
 
-    if account_state_with_proof.blob.is_none() {
-        let (_, blob) = all_accounts.iter().next().unwrap();
-        account_state_with_proof.blob = Some(blob.clone());
-    }
+    // The mock serves the state of the requested account with the generated proof.
+    let (_, blob) = all_accounts.iter().next().unwrap();
+    account_state_with_proof.blob = Some(blob.clone());
 
     let account_state_with_proof = vec![account_state_with_proof];
 
@@ -425,6 +503,7 @@ pub fn mock_db() -> MockDiemDB {
         genesis,
         all_accounts,
         all_txns,
+        write_sets,
         events,
         account_state_with_proof,
         timestamps,
@@ -442,15 +521,7 @@ pub fn create_database_client_and_runtime() -> (MockDiemDB, BlockingClient, Runt
 }
 
 #[allow(unused)]
-pub fn create_db_and_runtime() -> (
-    MockDiemDB,
-    Runtime,
-    String,
-    Receiver<(
-        SignedTransaction,
-        oneshot::Sender<anyhow::Result<SubmissionStatus>>,
-    )>,
-) {
+pub fn create_db_and_runtime() -> (MockDiemDB, Runtime, String, Receiver<MempoolClientRequest>) {
     let mock_db = mock_db();
 
     let host = "127.0.0.1";
@@ -0,0 +1,242 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notifications of the outcome of the transactions submitted through this node, posted to the
+//! configured sinks and to the callback URL a transaction was submitted with.
+//!
+//! A transaction is notified as accepted once mempool accepts it, then storage is polled for its
+//! outcome: it's committed once found at its sender's sequence number, and expired once the ledger
+//! time passes its expiration without it being committed. Watched transactions are kept in memory
+//! only, so transactions awaiting their outcome when the node restarts are never notified again.
+
+use crate::{counters, errors::JsonRpcError, views::VMStatusView};
+use anyhow::Result;
+use diem_config::config::WebhooksConfig;
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::Mutex;
+use diem_logger::warn;
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, Transaction, Version},
+};
+use serde::Serialize;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use storage_interface::DbReader;
+
+/// Time a webhook has to respond to a notification
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Accepted,
+    Committed,
+    Expired,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Accepted => "accepted",
+            WebhookEvent::Committed => "committed",
+            WebhookEvent::Expired => "expired",
+        }
+    }
+}
+
+/// Body of the requests posted to webhooks
+#[derive(Clone, Debug, Serialize)]
+pub struct WebhookNotification {
+    pub event: WebhookEvent,
+    pub hash: HashValue,
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    /// Version of a committed transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+    /// Status of a committed transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_status: Option<VMStatusView>,
+}
+
+/// An accepted transaction awaiting its outcome
+struct WatchedTransaction {
+    sender: AccountAddress,
+    sequence_number: u64,
+    expiration_timestamp_secs: u64,
+    urls: Vec<String>,
+}
+
+pub(crate) struct Webhooks {
+    config: WebhooksConfig,
+    client: reqwest::Client,
+    /// Watched transactions, by hash
+    watched: Mutex<HashMap<HashValue, WatchedTransaction>>,
+}
+
+impl Webhooks {
+    pub fn new(config: &WebhooksConfig) -> Self {
+        Self {
+            config: config.clone(),
+            client: reqwest::Client::builder()
+                .timeout(NOTIFICATION_TIMEOUT)
+                .build()
+                .expect("[json-rpc] failed to create webhooks client"),
+            watched: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled()
+    }
+
+    /// Checks the `callback_url` a transaction is submitted with
+    pub fn validate_callback_url(&self, callback_url: &str) -> Result<(), JsonRpcError> {
+        if !self.config.allow_client_callbacks {
+            return Err(JsonRpcError::invalid_param(
+                "callback_url: client callbacks are not allowed by this node",
+            ));
+        }
+        reqwest::Url::parse(callback_url)
+            .map_err(|e| JsonRpcError::invalid_param(&format!("callback_url: {}", e)))?;
+        Ok(())
+    }
+
+    /// Notifies the webhooks of `txn` that mempool accepted it, and watches it for its outcome
+    pub fn on_accepted(&self, txn: &SignedTransaction, callback_url: Option<String>) {
+        let urls = self
+            .config
+            .sinks
+            .iter()
+            .cloned()
+            .chain(callback_url)
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            return;
+        }
+
+        let hash = Transaction::UserTransaction(txn.clone()).hash();
+        self.notify(
+            &urls,
+            WebhookNotification {
+                event: WebhookEvent::Accepted,
+                hash,
+                sender: txn.sender(),
+                sequence_number: txn.sequence_number(),
+                version: None,
+                vm_status: None,
+            },
+        );
+
+        let mut watched = self.watched.lock();
+        if watched.len() >= self.config.max_watched_transactions {
+            warn!(
+                hash = hash.to_hex(),
+                "Too many transactions awaiting their outcome, not watching the transaction."
+            );
+            return;
+        }
+        watched.insert(
+            hash,
+            WatchedTransaction {
+                sender: txn.sender(),
+                sequence_number: txn.sequence_number(),
+                expiration_timestamp_secs: txn.expiration_timestamp_secs(),
+                urls,
+            },
+        );
+    }
+
+    /// Polls storage for the outcome of the watched transactions, until the runtime shuts down
+    pub async fn run(self: Arc<Self>, db: Arc<dyn DbReader>) {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.config.poll_interval_ms));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll(db.as_ref()) {
+                warn!(error = ?e, "Failed to check the outcome of watched transactions.");
+            }
+        }
+    }
+
+    /// Notifies the watched transactions committed or expired as of the latest ledger info
+    pub fn poll(&self, db: &dyn DbReader) -> Result<()> {
+        let ledger_info = db.get_latest_ledger_info()?;
+        let ledger_version = ledger_info.ledger_info().version();
+        let ledger_timestamp_secs = ledger_info.ledger_info().timestamp_usecs() / 1_000_000;
+
+        // Storage is read without holding the lock, so submissions aren't blocked meanwhile
+        let watched = self
+            .watched
+            .lock()
+            .iter()
+            .map(|(hash, txn)| {
+                (
+                    *hash,
+                    txn.sender,
+                    txn.sequence_number,
+                    txn.expiration_timestamp_secs,
+                )
+            })
+            .collect::<Vec<_>>();
+        for (hash, sender, sequence_number, expiration_timestamp_secs) in watched {
+            let committed = db
+                .get_txn_by_account(sender, sequence_number, ledger_version, false)?
+                .filter(|txn| txn.transaction.hash() == hash);
+            let notification = WebhookNotification {
+                event: WebhookEvent::Committed,
+                hash,
+                sender,
+                sequence_number,
+                version: None,
+                vm_status: None,
+            };
+            let notification = match committed {
+                Some(txn) => WebhookNotification {
+                    version: Some(txn.version),
+                    vm_status: Some(VMStatusView::from(txn.proof.transaction_info().status())),
+                    ..notification
+                },
+                // Blocks are timestamped in increasing order, so no later block can commit it
+                None if ledger_timestamp_secs >= expiration_timestamp_secs => WebhookNotification {
+                    event: WebhookEvent::Expired,
+                    ..notification
+                },
+                None => continue,
+            };
+            if let Some(txn) = self.watched.lock().remove(&hash) {
+                self.notify(&txn.urls, notification);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub fn num_watched(&self) -> usize {
+        self.watched.lock().len()
+    }
+
+    fn notify(&self, urls: &[String], notification: WebhookNotification) {
+        for url in urls {
+            let request = self.client.post(url).json(&notification);
+            let event = notification.event;
+            let url = url.clone();
+            tokio::spawn(async move {
+                let result = match request
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                {
+                    Ok(_) => "success",
+                    Err(e) => {
+                        warn!(url = url, error = ?e, "Failed to notify webhook.");
+                        "fail"
+                    }
+                };
+                counters::WEBHOOK_NOTIFICATIONS
+                    .with_label_values(&[event.as_str(), result])
+                    .inc();
+            });
+        }
+    }
+}
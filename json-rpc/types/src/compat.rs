@@ -0,0 +1,197 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decoding of the transactions submitted by SDKs of the previous generation, so existing wallets
+//! keep working through the transition window.
+//!
+//! Those SDKs wrap the BCS `SignedTransaction` in the protobuf messages of the admission control
+//! API they were written against:
+//!
+//! ```protobuf
+//! message SignedTransaction { bytes txn_bytes = 5; }
+//! message SubmitTransactionRequest { SignedTransaction transaction = 1; }
+//! ```
+//!
+//! The wrapped bytes are what the sender signed, so transactions are translated by unwrapping
+//! them: re-encoding any part of them, e.g. their script payload, would invalidate the signature.
+
+use anyhow::{format_err, Result};
+use diem_types::transaction::SignedTransaction;
+
+/// Field number of `txn_bytes` in the legacy `SignedTransaction` message.
+const SIGNED_TRANSACTION_TXN_BYTES_FIELD: u64 = 5;
+/// Field number of `transaction` in the legacy `SubmitTransactionRequest` message.
+const SUBMIT_REQUEST_TRANSACTION_FIELD: u64 = 1;
+
+/// The encoding a transaction was submitted in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransactionEncoding {
+    /// A BCS `SignedTransaction`, the current encoding.
+    Bcs,
+    /// A BCS `SignedTransaction` wrapped in a legacy protobuf `SignedTransaction`.
+    LegacySignedTransaction,
+    /// A BCS `SignedTransaction` wrapped in a legacy protobuf `SubmitTransactionRequest`.
+    LegacySubmitRequest,
+}
+
+impl TransactionEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionEncoding::Bcs => "bcs",
+            TransactionEncoding::LegacySignedTransaction => "legacy_signed_transaction",
+            TransactionEncoding::LegacySubmitRequest => "legacy_submit_request",
+        }
+    }
+}
+
+/// Decodes a submitted transaction, in the current encoding or a legacy one.
+pub fn decode_signed_transaction(bytes: &[u8]) -> Result<(SignedTransaction, TransactionEncoding)> {
+    if let Ok(txn) = bcs::from_bytes(bytes) {
+        return Ok((txn, TransactionEncoding::Bcs));
+    }
+
+    let (txn_bytes, encoding) = match proto_field(bytes, SUBMIT_REQUEST_TRANSACTION_FIELD)
+        .and_then(|signed_txn| proto_field(signed_txn, SIGNED_TRANSACTION_TXN_BYTES_FIELD))
+    {
+        Some(txn_bytes) => (txn_bytes, TransactionEncoding::LegacySubmitRequest),
+        None => (
+            proto_field(bytes, SIGNED_TRANSACTION_TXN_BYTES_FIELD)
+                .ok_or_else(|| format_err!("Neither BCS nor a legacy transaction encoding"))?,
+            TransactionEncoding::LegacySignedTransaction,
+        ),
+    };
+    Ok((bcs::from_bytes(txn_bytes)?, encoding))
+}
+
+/// Returns the last value of the length-delimited field `field_number` of the protobuf `message`,
+/// or `None` if it's absent or the message is malformed.
+fn proto_field(message: &[u8], field_number: u64) -> Option<&[u8]> {
+    let mut rest = message;
+    let mut value = None;
+    while !rest.is_empty() {
+        let key = read_varint(&mut rest)?;
+        match key & 0x7 {
+            // varint
+            0 => {
+                read_varint(&mut rest)?;
+            }
+            // 64-bit
+            1 => rest = rest.get(8..)?,
+            // length-delimited
+            2 => {
+                let len = read_varint(&mut rest)? as usize;
+                if len > rest.len() {
+                    return None;
+                }
+                let (field, tail) = rest.split_at(len);
+                if key >> 3 == field_number {
+                    value = Some(field);
+                }
+                rest = tail;
+            }
+            // 32-bit
+            5 => rest = rest.get(4..)?,
+            _ => return None,
+        }
+    }
+    value
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::request::SubmitParams;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_types::{
+        account_address::AccountAddress,
+        test_helpers::transaction_test_helpers::get_test_signed_txn,
+    };
+
+    fn length_delimited(field_number: u64, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![(field_number << 3 | 2) as u8];
+        let mut len = value.len();
+        while len >= 0x80 {
+            bytes.push((len as u8 & 0x7f) | 0x80);
+            len >>= 7;
+        }
+        bytes.push(len as u8);
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    fn signed_txn() -> SignedTransaction {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = private_key.public_key();
+        get_test_signed_txn(AccountAddress::random(), 0, &private_key, public_key, None)
+    }
+
+    #[test]
+    fn decode_bcs() {
+        let txn = signed_txn();
+        let bytes = bcs::to_bytes(&txn).unwrap();
+        assert_eq!(
+            decode_signed_transaction(&bytes).unwrap(),
+            (txn, TransactionEncoding::Bcs)
+        );
+    }
+
+    #[test]
+    fn decode_legacy() {
+        let txn = signed_txn();
+        let txn_bytes = bcs::to_bytes(&txn).unwrap();
+        let signed_txn = length_delimited(SIGNED_TRANSACTION_TXN_BYTES_FIELD, &txn_bytes);
+        assert_eq!(
+            decode_signed_transaction(&signed_txn).unwrap(),
+            (txn.clone(), TransactionEncoding::LegacySignedTransaction)
+        );
+
+        // Unknown fields are skipped
+        let mut submit_request = vec![0x10, 0x96, 0x01];
+        submit_request.extend(length_delimited(
+            SUBMIT_REQUEST_TRANSACTION_FIELD,
+            &signed_txn,
+        ));
+        assert_eq!(
+            decode_signed_transaction(&submit_request).unwrap(),
+            (txn, TransactionEncoding::LegacySubmitRequest)
+        );
+
+        // Truncated, or without a transaction
+        decode_signed_transaction(&signed_txn[..signed_txn.len() - 1]).unwrap_err();
+        decode_signed_transaction(&length_delimited(2, &txn_bytes)).unwrap_err();
+    }
+
+    #[test]
+    fn submit_params() {
+        let txn = signed_txn();
+        let signed_txn = length_delimited(
+            SIGNED_TRANSACTION_TXN_BYTES_FIELD,
+            &bcs::to_bytes(&txn).unwrap(),
+        );
+        let value = serde_json::json!([hex::encode(&signed_txn)]);
+        let params = serde_json::from_value::<SubmitParams>(value).unwrap();
+        assert_eq!(params.data, txn);
+        assert_eq!(
+            params.encoding,
+            TransactionEncoding::LegacySignedTransaction
+        );
+
+        // Always serialized in the current encoding
+        let value = serde_json::to_value(&params).unwrap();
+        let params = serde_json::from_value::<SubmitParams>(value).unwrap();
+        assert_eq!(params.encoding, TransactionEncoding::Bcs);
+    }
+}
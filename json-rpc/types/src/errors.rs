@@ -3,6 +3,7 @@
 
 use anyhow::Result;
 use diem_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     vm_status::{StatusCode, StatusType},
 };
@@ -51,6 +52,27 @@ pub enum ServerCode {
     MempoolInvalidUpdate = -32010,
     MempoolVmError = -32011,
     MempoolUnknownError = -32012,
+
+    // Node has not synced up to the `min_version` requested by a read, retriable
+    StaleLedgerVersion = -32013,
+
+    // Mempool error - transaction runs a script the node operator has filtered out
+    MempoolRejectedByFilter = -32014,
+
+    // API consumer sent more requests or spent more credits than its rate limits allow, retriable
+    RateLimited = -32015,
+
+    // Request carries an unknown API key, or none while anonymous requests are not served
+    InvalidApiKey = -32016,
+
+    // API consumer submitted a transaction of a sender its API key doesn't allow
+    SenderNotAllowed = -32017,
+
+    // Mempool error - transaction expires sooner or later than the node accepts
+    MempoolExpirationOutOfBounds = -32018,
+
+    // Sequence numbers are not allocated to the sender by the node
+    SequenceNumberAllocationNotEnabled = -32019,
 }
 
 /// JSON RPC server error codes for invalid request
@@ -181,6 +203,55 @@ impl JsonRpcError {
         }
     }
 
+    pub fn stale_ledger_version(min_version: u64, latest_version: u64) -> Self {
+        Self {
+            code: ServerCode::StaleLedgerVersion as i16,
+            message: format!(
+                "Server error: ledger version {} is behind requested min_version {}",
+                latest_version, min_version
+            ),
+            data: None,
+        }
+    }
+
+    pub fn rate_limited(limit: &str) -> Self {
+        Self {
+            code: ServerCode::RateLimited as i16,
+            message: format!("Server error: {} rate limit exceeded, retry later", limit),
+            data: None,
+        }
+    }
+
+    pub fn invalid_api_key() -> Self {
+        Self {
+            code: ServerCode::InvalidApiKey as i16,
+            message: "Server error: missing or unknown API key".to_string(),
+            data: None,
+        }
+    }
+
+    pub fn sender_not_allowed(sender: AccountAddress) -> Self {
+        Self {
+            code: ServerCode::SenderNotAllowed as i16,
+            message: format!(
+                "Server error: API key not allowed to submit transactions of sender {}",
+                sender
+            ),
+            data: None,
+        }
+    }
+
+    pub fn sequence_number_allocation_not_enabled(sender: AccountAddress) -> Self {
+        Self {
+            code: ServerCode::SequenceNumberAllocationNotEnabled as i16,
+            message: format!(
+                "Server error: sequence numbers are not allocated to sender {}",
+                sender
+            ),
+            data: None,
+        }
+    }
+
     pub fn mempool_error(error: MempoolStatus) -> Result<Self> {
         let code = match error.code {
             MempoolStatusCode::InvalidSeqNumber => ServerCode::MempoolInvalidSeqNumber,
@@ -189,6 +260,8 @@ impl JsonRpcError {
             MempoolStatusCode::InvalidUpdate => ServerCode::MempoolInvalidUpdate,
             MempoolStatusCode::VmError => ServerCode::MempoolVmError,
             MempoolStatusCode::UnknownStatus => ServerCode::MempoolUnknownError,
+            MempoolStatusCode::RejectedByFilter => ServerCode::MempoolRejectedByFilter,
+            MempoolStatusCode::ExpirationOutOfBounds => ServerCode::MempoolExpirationOutOfBounds,
             MempoolStatusCode::Accepted => {
                 return Err(anyhow::format_err!(
                     "[JSON RPC] cannot create mempool error for mempool accepted status"
@@ -237,6 +310,7 @@ impl JsonRpcError {
 mod tests {
     use crate::errors::{is_internal_error, JsonRpcError, ServerCode, INTERNAL_ERRORS};
     use diem_types::{
+        account_address::AccountAddress,
         mempool_status::{MempoolStatus, MempoolStatusCode},
         vm_status::StatusCode,
     };
@@ -302,6 +376,14 @@ mod tests {
             MempoolStatusCode::UnknownStatus,
             ServerCode::MempoolUnknownError,
         );
+        assert_map_code(
+            MempoolStatusCode::RejectedByFilter,
+            ServerCode::MempoolRejectedByFilter,
+        );
+        assert_map_code(
+            MempoolStatusCode::ExpirationOutOfBounds,
+            ServerCode::MempoolExpirationOutOfBounds,
+        );
     }
 
     #[test]
@@ -315,6 +397,28 @@ mod tests {
         assert!(!is_internal_error(&(ServerCode::VmValidationError as i16)));
     }
 
+    #[test]
+    fn test_stale_ledger_version() {
+        let err = JsonRpcError::stale_ledger_version(10, 5);
+        assert_eq!(err.code, ServerCode::StaleLedgerVersion as i16);
+        assert!(!is_internal_error(&err.code));
+    }
+
+    #[test]
+    fn test_api_key_errors() {
+        let err = JsonRpcError::rate_limited("requests");
+        assert_eq!(err.code, ServerCode::RateLimited as i16);
+        assert!(!is_internal_error(&err.code));
+
+        let err = JsonRpcError::invalid_api_key();
+        assert_eq!(err.code, ServerCode::InvalidApiKey as i16);
+        assert!(!is_internal_error(&err.code));
+
+        let err = JsonRpcError::sender_not_allowed(AccountAddress::ZERO);
+        assert_eq!(err.code, ServerCode::SenderNotAllowed as i16);
+        assert!(!is_internal_error(&err.code));
+    }
+
     fn assert_map_code(from: MempoolStatusCode, to: ServerCode) {
         let err = JsonRpcError::mempool_error(MempoolStatus {
             code: from,
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod compat;
 pub mod errors;
 pub mod request;
 pub mod response;
@@ -49,6 +50,18 @@ pub enum Method {
     GetTowerStateView,
     GetOracleUpgradeStateView,
     GetWaypointView,
+    GetAccountResource,
+    GetTransactionOutputs,
+    GetEpochInfo,
+    GetEventStats,
+    ValidateTransaction,
+    AllocateSequenceNumber,
+    GetModule,
+    GetModuleAbi,
+    GetEventsPage,
+    GetScriptSchemas,
+    WatchResources,
+    UnwatchResources,
 }
 
 impl Method {
@@ -73,6 +86,18 @@ impl Method {
             
             Method::GetOracleUpgradeStateView => "get_oracle_upgrade_state_view",
             Method::GetWaypointView => "get_waypoint_view", 
+            Method::GetAccountResource => "get_account_resource",
+            Method::GetTransactionOutputs => "get_transaction_outputs",
+            Method::GetEpochInfo => "get_epoch_info",
+            Method::GetEventStats => "get_event_stats",
+            Method::ValidateTransaction => "validate_transaction",
+            Method::AllocateSequenceNumber => "allocate_sequence_number",
+            Method::GetModule => "get_module",
+            Method::GetModuleAbi => "get_module_abi",
+            Method::GetEventsPage => "get_events_page",
+            Method::GetScriptSchemas => "get_script_schemas",
+            Method::WatchResources => "watch_resources",
+            Method::UnwatchResources => "unwatch_resources",
         }
     }
 }
@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{Id, JsonRpcVersion, Method};
-use crate::{errors::JsonRpcError, views::BytesView};
+use crate::{
+    compat::{self, TransactionEncoding},
+    errors::JsonRpcError,
+    views::{BytesView, EventCursor},
+};
 use diem_types::{
     account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
 };
@@ -59,6 +63,27 @@ impl JsonRpcRequest {
     }
 }
 
+/// Cost classes of requests, which node operators may budget separately
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum QueryClass {
+    /// Submissions and reads of a single item, e.g. an account or a transaction
+    Light,
+    /// Queries of ranges of transactions
+    RangeScan,
+    /// Queries of the events of an event stream
+    EventScan,
+}
+
+impl QueryClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryClass::Light => "light",
+            QueryClass::RangeScan => "range_scan",
+            QueryClass::EventScan => "event_scan",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
@@ -85,6 +110,18 @@ pub enum MethodRequest {
     GetTowerStateView(GetTowerStateParams),
     GetOracleUpgradeStateView(),
     GetWaypointView(),
+    GetAccountResource(GetAccountResourceParams),
+    GetTransactionOutputs(GetTransactionOutputsParams),
+    GetEpochInfo(GetEpochInfoParams),
+    GetEventStats(GetEventStatsParams),
+    ValidateTransaction(ValidateTransactionParams),
+    AllocateSequenceNumber(AllocateSequenceNumberParams),
+    GetModule(GetModuleParams),
+    GetModuleAbi(GetModuleParams),
+    GetEventsPage(GetEventsPageParams),
+    GetScriptSchemas(GetScriptSchemasParams),
+    WatchResources(WatchResourcesParams),
+    UnwatchResources(UnwatchResourcesParams),
 }
 
 impl MethodRequest {
@@ -130,6 +167,33 @@ impl MethodRequest {
             Method::GetWaypointView => {
                 MethodRequest::GetWaypointView()
             }
+
+            Method::GetAccountResource => {
+                MethodRequest::GetAccountResource(serde_json::from_value(value)?)
+            }
+            Method::GetTransactionOutputs => {
+                MethodRequest::GetTransactionOutputs(serde_json::from_value(value)?)
+            }
+            Method::GetEpochInfo => MethodRequest::GetEpochInfo(serde_json::from_value(value)?),
+            Method::GetEventStats => MethodRequest::GetEventStats(serde_json::from_value(value)?),
+            Method::ValidateTransaction => {
+                MethodRequest::ValidateTransaction(serde_json::from_value(value)?)
+            }
+            Method::AllocateSequenceNumber => {
+                MethodRequest::AllocateSequenceNumber(serde_json::from_value(value)?)
+            }
+            Method::GetModule => MethodRequest::GetModule(serde_json::from_value(value)?),
+            Method::GetModuleAbi => MethodRequest::GetModuleAbi(serde_json::from_value(value)?),
+            Method::GetEventsPage => MethodRequest::GetEventsPage(serde_json::from_value(value)?),
+            Method::GetScriptSchemas => {
+                MethodRequest::GetScriptSchemas(serde_json::from_value(value)?)
+            }
+            Method::WatchResources => {
+                MethodRequest::WatchResources(serde_json::from_value(value)?)
+            }
+            Method::UnwatchResources => {
+                MethodRequest::UnwatchResources(serde_json::from_value(value)?)
+            }
             
 
 
@@ -138,6 +202,62 @@ impl MethodRequest {
         Ok(method_request)
     }
 
+    /// Returns the `min_version` a read request asks the node to have caught up to, if any
+    pub fn min_version(&self) -> Option<u64> {
+        match self {
+            MethodRequest::GetAccount(params) => params.min_version,
+            MethodRequest::GetTransactions(params) => params.min_version,
+            MethodRequest::GetAccountTransaction(params) => params.min_version,
+            MethodRequest::GetAccountTransactions(params) => params.min_version,
+            MethodRequest::GetEvents(params) => params.min_version,
+            MethodRequest::GetAccountStateWithProof(params) => params.min_version,
+            MethodRequest::GetTransactionsWithProofs(params) => params.min_version,
+            MethodRequest::GetEventsWithProofs(params) => params.min_version,
+            MethodRequest::GetAccountResource(params) => params.min_version,
+            MethodRequest::GetTransactionOutputs(params) => params.min_version,
+            MethodRequest::GetEventStats(params) => params.min_version,
+            MethodRequest::GetModule(params) => params.min_version,
+            MethodRequest::GetModuleAbi(params) => params.min_version,
+            // Pages continued from a cursor are read at the cursor's version, which the node must
+            // have synced to
+            MethodRequest::GetEventsPage(params) => params
+                .min_version
+                .max(params.cursor.map(|cursor| cursor.ledger_version)),
+            _ => None,
+        }
+    }
+
+    /// Returns the credits an API consumer spends on the request: the number of items requested
+    /// by queries of ranges, one for any other request
+    pub fn credits(&self) -> u64 {
+        let items = match self {
+            MethodRequest::GetTransactions(params) => params.limit,
+            MethodRequest::GetAccountTransactions(params) => params.limit,
+            MethodRequest::GetEvents(params) => params.limit,
+            MethodRequest::GetTransactionsWithProofs(params) => params.limit,
+            MethodRequest::GetEventsWithProofs(params) => params.limit,
+            MethodRequest::GetTransactionOutputs(params) => params.limit,
+            MethodRequest::GetEventsPage(params) => params.limit,
+            _ => 1,
+        };
+        items.max(1)
+    }
+
+    /// Returns how costly the request is for the node to serve
+    pub fn query_class(&self) -> QueryClass {
+        match self {
+            MethodRequest::GetTransactions(_)
+            | MethodRequest::GetAccountTransactions(_)
+            | MethodRequest::GetTransactionsWithProofs(_)
+            | MethodRequest::GetTransactionOutputs(_) => QueryClass::RangeScan,
+            MethodRequest::GetEvents(_)
+            | MethodRequest::GetEventsWithProofs(_)
+            | MethodRequest::GetEventStats(_)
+            | MethodRequest::GetEventsPage(_) => QueryClass::EventScan,
+            _ => QueryClass::Light,
+        }
+    }
+
     pub fn method(&self) -> Method {
         match self {
             MethodRequest::Submit(_) => Method::Submit,
@@ -157,15 +277,85 @@ impl MethodRequest {
             MethodRequest::GetTowerStateView(_) =>  Method::GetTowerStateView, 
             MethodRequest::GetOracleUpgradeStateView() =>  Method::GetOracleUpgradeStateView,
             MethodRequest::GetWaypointView() => Method::GetWaypointView,
+            MethodRequest::GetAccountResource(_) => Method::GetAccountResource,
+            MethodRequest::GetTransactionOutputs(_) => Method::GetTransactionOutputs,
+            MethodRequest::GetEpochInfo(_) => Method::GetEpochInfo,
+            MethodRequest::GetEventStats(_) => Method::GetEventStats,
+            MethodRequest::ValidateTransaction(_) => Method::ValidateTransaction,
+            MethodRequest::AllocateSequenceNumber(_) => Method::AllocateSequenceNumber,
+            MethodRequest::GetModule(_) => Method::GetModule,
+            MethodRequest::GetModuleAbi(_) => Method::GetModuleAbi,
+            MethodRequest::GetEventsPage(_) => Method::GetEventsPage,
+            MethodRequest::GetScriptSchemas(_) => Method::GetScriptSchemas,
+            MethodRequest::WatchResources(_) => Method::WatchResources,
+            MethodRequest::UnwatchResources(_) => Method::UnwatchResources,
           }
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct SubmitParams {
     #[serde(serialize_with = "serialize_signed_transaction")]
-    #[serde(deserialize_with = "deserialize_signed_transaction")]
     pub data: SignedTransaction,
+    /// The encoding `data` was submitted in, always serialized in the current one
+    #[serde(skip_serializing)]
+    pub encoding: TransactionEncoding,
+    /// URL notified when the transaction is accepted into mempool, committed or expired, if the
+    /// node allows client callbacks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for SubmitParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Params {
+            #[serde(deserialize_with = "deserialize_signed_transaction")]
+            data: (SignedTransaction, TransactionEncoding),
+            #[serde(default)]
+            callback_url: Option<String>,
+        }
+
+        let Params {
+            data: (data, encoding),
+            callback_url,
+        } = Params::deserialize(deserializer)?;
+        Ok(SubmitParams {
+            data,
+            encoding,
+            callback_url,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ValidateTransactionParams {
+    #[serde(serialize_with = "serialize_signed_transaction")]
+    pub data: SignedTransaction,
+    /// The encoding `data` was submitted in, always serialized in the current one
+    #[serde(skip_serializing)]
+    pub encoding: TransactionEncoding,
+}
+
+impl<'de> Deserialize<'de> for ValidateTransactionParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Params {
+            #[serde(deserialize_with = "deserialize_signed_transaction")]
+            data: (SignedTransaction, TransactionEncoding),
+        }
+
+        let Params {
+            data: (data, encoding),
+        } = Params::deserialize(deserializer)?;
+        Ok(ValidateTransactionParams { data, encoding })
+    }
 }
 
 fn serialize_signed_transaction<S>(
@@ -179,14 +369,16 @@ where
     BytesView::new(bcs::to_bytes(txn).map_err(S::Error::custom)?).serialize(serializer)
 }
 
-fn deserialize_signed_transaction<'de, D>(deserializer: D) -> Result<SignedTransaction, D::Error>
+fn deserialize_signed_transaction<'de, D>(
+    deserializer: D,
+) -> Result<(SignedTransaction, TransactionEncoding), D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     use serde::de::Error;
     let bytes = BytesView::deserialize(deserializer)
         .map_err(|_| D::Error::custom("expected hex-encoded SignedTransaction"))?;
-    bcs::from_bytes(bytes.inner())
+    compat::decode_signed_transaction(bytes.inner())
         .map_err(|_| D::Error::custom("expected hex-encoded SignedTransaction"))
 }
 
@@ -222,6 +414,9 @@ pub struct GetAccountParams {
     pub account: AccountAddress,
     #[serde(default)]
     pub version: Option<u64>,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -229,6 +424,9 @@ pub struct GetTransactionsParams {
     pub start_version: u64,
     pub limit: u64,
     pub include_events: bool,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -236,6 +434,9 @@ pub struct GetAccountTransactionParams {
     pub account: AccountAddress,
     pub sequence_number: u64,
     pub include_events: bool,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -244,6 +445,9 @@ pub struct GetAccountTransactionsParams {
     pub start: u64,
     pub limit: u64,
     pub include_events: bool,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -251,6 +455,27 @@ pub struct GetEventsParams {
     pub key: EventKey,
     pub start: u64,
     pub limit: u64,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+    /// Only the events of the page matching the filter are returned
+    #[serde(default)]
+    pub filter: Option<EventFilter>,
+}
+
+/// Conditions on the events returned by `get_events` and `get_events_with_proofs`, all of which
+/// must hold. `sender`, `min_amount` and `max_amount` only match payment events.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventFilter {
+    /// Move type of the event, e.g. `0x1::DiemAccount::SentPaymentEvent`
+    pub type_tag: Option<String>,
+    /// Sender of the payment
+    pub sender: Option<AccountAddress>,
+    /// Minimum amount of the payment, inclusive
+    pub min_amount: Option<u64>,
+    /// Maximum amount of the payment, inclusive
+    pub max_amount: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -281,6 +506,20 @@ impl<'de> Deserialize<'de> for GetNetworkStatusParams {
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct GetScriptSchemasParams;
+
+impl<'de> Deserialize<'de> for GetScriptSchemasParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_option(NoParamsVisitor("get_script_schemas params"))
+            .map(|_| GetScriptSchemasParams)
+    }
+}
+
 /// A de::Visitor implementation for jsonrpc param structs without any parameters
 struct NoParamsVisitor(&'static str);
 impl<'de> de::Visitor<'de> for NoParamsVisitor {
@@ -338,6 +577,9 @@ pub struct GetAccountStateWithProofParams {
     pub version: Option<u64>,
     #[serde(default)]
     pub ledger_version: Option<u64>,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -345,6 +587,9 @@ pub struct GetTransactionsWithProofsParams {
     pub start_version: u64,
     pub limit: u64,
     pub include_events: bool,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -352,6 +597,12 @@ pub struct GetEventsWithProofsParams {
     pub key: EventKey,
     pub start: u64,
     pub limit: u64,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+    /// Only the events of the page matching the filter are returned
+    #[serde(default)]
+    pub filter: Option<EventFilter>,
 }
 
 //////// 0L ////////
@@ -360,6 +611,93 @@ pub struct GetTowerStateParams {
     pub account: AccountAddress,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetAccountResourceParams {
+    pub account: AccountAddress,
+    /// Access path of the resource within the account state, e.g. a struct tag's access vector
+    pub path: BytesView,
+    #[serde(default)]
+    pub version: Option<u64>,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetTransactionOutputsParams {
+    pub start_version: u64,
+    pub limit: u64,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetEpochInfoParams {
+    pub epoch: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetEventStatsParams {
+    pub key: EventKey,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AllocateSequenceNumberParams {
+    pub account: AccountAddress,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetModuleParams {
+    pub account: AccountAddress,
+    /// Name of the module, e.g. `DiemAccount`
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<u64>,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetEventsPageParams {
+    pub key: EventKey,
+    pub limit: u64,
+    /// Where the previous page ended, `None` to start from the first event
+    #[serde(default)]
+    pub cursor: Option<EventCursor>,
+    /// Minimum ledger version the node must have synced to before serving the read
+    #[serde(default)]
+    pub min_version: Option<u64>,
+    /// Only the events of the page matching the filter are returned
+    #[serde(default)]
+    pub filter: Option<EventFilter>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WatchResourcesParams {
+    /// URL the changes of the resources are posted to
+    pub callback_url: String,
+    pub resources: Vec<WatchedResource>,
+}
+
+/// A resource of an account, as given to `watch_resources`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WatchedResource {
+    pub account: AccountAddress,
+    /// Access path of the resource within the account state, i.e. its struct tag's access vector
+    pub path: BytesView,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnwatchResourcesParams {
+    /// ID `watch_resources` returned
+    pub id: u64,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -378,6 +716,158 @@ mod test {
         serde_json::from_value::<GetTowerStateParams>(value).unwrap();
     }
 
+    #[test]
+    fn get_account_resource() {
+        let account = "1668f6be25668c1a17cd8caf6b8d2f25";
+
+        // json object
+        let value = serde_json::json!({"account": account, "path": "01"});
+        let params = serde_json::from_value::<GetAccountResourceParams>(value).unwrap();
+        assert_eq!(params.path.inner(), &[1]);
+        assert_eq!(params.version, None);
+
+        // json list
+        let value = serde_json::json!([account, "01", 10]);
+        let params = serde_json::from_value::<GetAccountResourceParams>(value).unwrap();
+        assert_eq!(params.version, Some(10));
+
+        // path is required
+        let value = serde_json::json!([account]);
+        serde_json::from_value::<GetAccountResourceParams>(value).unwrap_err();
+    }
+
+    #[test]
+    fn get_module() {
+        let account = "00000000000000000000000000000001";
+
+        // json object
+        let value = serde_json::json!({"account": account, "name": "DiemAccount"});
+        let params = serde_json::from_value::<GetModuleParams>(value).unwrap();
+        assert_eq!(params.name, "DiemAccount");
+        assert_eq!(params.version, None);
+
+        // json list
+        let value = serde_json::json!([account, "DiemAccount", 10, 12]);
+        let params = serde_json::from_value::<GetModuleParams>(value).unwrap();
+        assert_eq!(params.version, Some(10));
+        assert_eq!(params.min_version, Some(12));
+
+        // name is required
+        let value = serde_json::json!([account]);
+        serde_json::from_value::<GetModuleParams>(value).unwrap_err();
+    }
+
+    #[test]
+    fn get_transaction_outputs() {
+        // json object
+        let value = serde_json::json!({"start_version": 10, "limit": 2});
+        let params = serde_json::from_value::<GetTransactionOutputsParams>(value).unwrap();
+        assert_eq!(params.start_version, 10);
+        assert_eq!(params.limit, 2);
+
+        // json list
+        let value = serde_json::json!([10, 2, 12]);
+        let params = serde_json::from_value::<GetTransactionOutputsParams>(value).unwrap();
+        assert_eq!(params.min_version, Some(12));
+
+        // limit is required
+        let value = serde_json::json!([10]);
+        serde_json::from_value::<GetTransactionOutputsParams>(value).unwrap_err();
+    }
+
+    #[test]
+    fn get_events_page() {
+        let key = "13000000000000000000000000000000000000000a550c18";
+
+        // Array starting from the first event
+        let value = serde_json::json!([key, 10]);
+        let params = serde_json::from_value::<GetEventsPageParams>(value).unwrap();
+        assert!(params.cursor.is_none());
+
+        // Array continuing from a cursor, whose version the node must have synced to
+        let cursor = EventCursor {
+            key: serde_json::from_value(serde_json::json!(key)).unwrap(),
+            next_sequence_number: 10,
+            ledger_version: 20,
+            last_sequence_number: Some(30),
+        };
+        let value = serde_json::json!([key, 10, cursor, 12]);
+        let request = MethodRequest::from_value(Method::GetEventsPage, value).unwrap();
+        assert_eq!(request.min_version(), Some(20));
+        let value = serde_json::json!([key, 10, cursor, 25]);
+        let request = MethodRequest::from_value(Method::GetEventsPage, value).unwrap();
+        assert_eq!(request.min_version(), Some(25));
+
+        // Array with an invalid cursor
+        let value = serde_json::json!([key, 10, "00"]);
+        serde_json::from_value::<GetEventsPageParams>(value).unwrap_err();
+    }
+
+    #[test]
+    fn watch_resources() {
+        let account = "00000000000000000000000000000001";
+        let path = "01";
+
+        // json object
+        let value = serde_json::json!({
+            "callback_url": "http://localhost/",
+            "resources": [{"account": account, "path": path}],
+        });
+        let params = serde_json::from_value::<WatchResourcesParams>(value).unwrap();
+        assert_eq!(params.resources[0].path.inner(), &[1]);
+
+        // json list
+        let value = serde_json::json!(["http://localhost/", [[account, path]]]);
+        let params = serde_json::from_value::<WatchResourcesParams>(value).unwrap();
+        assert_eq!(params.callback_url, "http://localhost/");
+        assert_eq!(params.resources.len(), 1);
+
+        // resources are required
+        let value = serde_json::json!(["http://localhost/"]);
+        serde_json::from_value::<WatchResourcesParams>(value).unwrap_err();
+
+        let value = serde_json::json!([3]);
+        let request = MethodRequest::from_value(Method::UnwatchResources, value).unwrap();
+        assert_eq!(request.method(), Method::UnwatchResources);
+    }
+
+    #[test]
+    fn credits() {
+        let request =
+            MethodRequest::from_value(Method::GetTransactions, serde_json::json!([10, 100, false]))
+                .unwrap();
+        assert_eq!(request.credits(), 100);
+
+        // Empty ranges still spend a credit
+        let request =
+            MethodRequest::from_value(Method::GetTransactionOutputs, serde_json::json!([10, 0]))
+                .unwrap();
+        assert_eq!(request.credits(), 1);
+
+        let request =
+            MethodRequest::from_value(Method::GetMetadata, serde_json::Value::Null).unwrap();
+        assert_eq!(request.credits(), 1);
+    }
+
+    #[test]
+    fn query_class() {
+        let request =
+            MethodRequest::from_value(Method::GetTransactions, serde_json::json!([10, 100, false]))
+                .unwrap();
+        assert_eq!(request.query_class(), QueryClass::RangeScan);
+
+        let request = MethodRequest::from_value(
+            Method::GetEvents,
+            serde_json::json!(["00000000000000000000000000000000000000000a550c18", 0, 10]),
+        )
+        .unwrap();
+        assert_eq!(request.query_class(), QueryClass::EventScan);
+
+        let request =
+            MethodRequest::from_value(Method::GetMetadata, serde_json::Value::Null).unwrap();
+        assert_eq!(request.query_class(), QueryClass::Light);
+    }
+
     #[test]
     fn metadata() {
         // Too many array params
@@ -433,8 +923,13 @@ mod test {
         let value = serde_json::json!([account, "foo"]);
         serde_json::from_value::<GetAccountParams>(value).unwrap_err();
 
-        // Array with too many params
+        // Array with min_version param
         let value = serde_json::json!([account, 10, 1]);
+        let params = serde_json::from_value::<GetAccountParams>(value).unwrap();
+        assert_eq!(params.min_version, Some(1));
+
+        // Array with too many params
+        let value = serde_json::json!([account, 10, 1, 2]);
         serde_json::from_value::<GetAccountParams>(value).unwrap_err();
 
         // Empty array without required params should fail
@@ -633,6 +1128,26 @@ mod test {
         let value = serde_json::json!([key, 10, 11]);
         serde_json::from_value::<GetEventsParams>(value).unwrap();
 
+        // Array with min_version param
+        let value = serde_json::json!([key, 10, 11, 12]);
+        let params = serde_json::from_value::<GetEventsParams>(value).unwrap();
+        assert_eq!(params.min_version, Some(12));
+
+        // Array with filter param
+        let value = serde_json::json!([key, 10, 11, null, {"min_amount": 5}]);
+        let params = serde_json::from_value::<GetEventsParams>(value).unwrap();
+        assert_eq!(
+            params.filter,
+            Some(EventFilter {
+                min_amount: Some(5),
+                ..EventFilter::default()
+            })
+        );
+
+        // Filter with unknown condition
+        let value = serde_json::json!([key, 10, 11, null, {"receiver": "foo"}]);
+        serde_json::from_value::<GetEventsParams>(value).unwrap_err();
+
         // Array with too many params
         let value = serde_json::json!([key, 10, 11, false]);
         serde_json::from_value::<GetEventsParams>(value).unwrap_err();
@@ -722,6 +1237,25 @@ mod test {
         serde_json::from_value::<JsonRpcRequest>(value).unwrap();
     }
 
+    #[test]
+    fn get_script_schemas() {
+        let value = serde_json::json!(["PaymentScripts"]);
+        serde_json::from_value::<GetScriptSchemasParams>(value).unwrap_err();
+
+        let value = serde_json::json!([]);
+        serde_json::from_value::<GetScriptSchemasParams>(value).unwrap();
+
+        let value = serde_json::Value::Null;
+        serde_json::from_value::<GetScriptSchemasParams>(value).unwrap();
+
+        let value = serde_json::json! {{
+            "jsonrpc": "2.0",
+            "method": Method::GetScriptSchemas,
+            "id": 1,
+        }};
+        serde_json::from_value::<JsonRpcRequest>(value).unwrap();
+    }
+
     #[test]
     fn get_state_proof() {
         // Array with all params
@@ -870,6 +1404,14 @@ mod test {
         let value = serde_json::json!([key, 10, 11]);
         serde_json::from_value::<GetEventsWithProofsParams>(value).unwrap();
 
+        // Array with filter param
+        let value = serde_json::json!([key, 10, 11, null, {"type_tag": "0x1::M::E"}]);
+        let params = serde_json::from_value::<GetEventsWithProofsParams>(value).unwrap();
+        assert_eq!(
+            params.filter.unwrap().type_tag.as_deref(),
+            Some("0x1::M::E")
+        );
+
         // Array with too many params
         let value = serde_json::json!([key, 10, 11, false]);
         serde_json::from_value::<GetEventsWithProofsParams>(value).unwrap_err();
@@ -2,18 +2,35 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::errors::JsonRpcError;
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    traits::{Signature, SigningKey},
+};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
 
 // http response header names
 pub const X_DIEM_CHAIN_ID: &str = "X-Diem-Chain-Id";
 pub const X_DIEM_VERSION_ID: &str = "X-Diem-Ledger-Version";
 pub const X_DIEM_TIMESTAMP_USEC_ID: &str = "X-Diem-Ledger-TimestampUsec";
 
+/// Request member carrying the nonce a client wants the node to sign its response with
+pub const DIEM_CLIENT_NONCE: &str = "diem_client_nonce";
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct JsonRpcResponse {
     pub diem_chain_id: u8,
     pub diem_ledger_version: u64,
     pub diem_ledger_timestampusec: u64,
+    /// Client nonce of the request, echoed when the node signs the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diem_client_nonce: Option<String>,
+    /// Hex encoded signature of the [`SignedResponseScope`] by the node's response signing key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diem_response_signature: Option<String>,
 
     pub jsonrpc: String,
 
@@ -34,18 +51,112 @@ impl JsonRpcResponse {
             diem_chain_id: chain_id.id(),
             diem_ledger_version,
             diem_ledger_timestampusec,
+            diem_client_nonce: None,
+            diem_response_signature: None,
             jsonrpc: "2.0".to_string(),
             id: None,
             result: None,
             error: None,
         }
     }
+
+    /// Echoes the client nonce of `request`, the JSON RPC request object this response answers,
+    /// and signs the response scope with `signing_key`. Responses to requests without a client
+    /// nonce are left unsigned.
+    pub fn sign(&mut self, request: &Value, signing_key: &Ed25519PrivateKey) {
+        if let Some(scope) = SignedResponseScope::new(request, self) {
+            let signature = signing_key.sign(&scope);
+            self.diem_client_nonce = Some(scope.client_nonce);
+            self.diem_response_signature = Some(hex::encode(signature.to_bytes()));
+        }
+    }
+
+    /// Checks that the response echoes the client nonce of `request`, the JSON RPC request
+    /// object sent, and is signed by the node's response signing key `public_key`.
+    pub fn verify_signature(&self, request: &Value, public_key: &Ed25519PublicKey) -> Result<()> {
+        let scope = SignedResponseScope::new(request, self)
+            .ok_or_else(|| format_err!("request without a client nonce"))?;
+        ensure!(
+            self.diem_client_nonce.as_ref() == Some(&scope.client_nonce),
+            "response doesn't echo the client nonce {}",
+            scope.client_nonce
+        );
+        let signature = self
+            .diem_response_signature
+            .as_ref()
+            .ok_or_else(|| format_err!("response isn't signed"))?;
+        let signature = Ed25519Signature::try_from(hex::decode(signature)?.as_slice())?;
+        signature.verify(&scope, public_key)
+    }
+}
+
+/// What a node signs in its response to a request carrying a client nonce: the nonce, the
+/// request, the ledger info the request was served at and the response itself. A response can't
+/// be replayed in answer to another request, or passed off as read at another ledger version.
+#[derive(Clone, Debug, Deserialize, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct SignedResponseScope {
+    pub client_nonce: String,
+    pub chain_id: u8,
+    pub ledger_version: u64,
+    pub ledger_timestampusec: u64,
+    /// Method and params of the request, as canonical JSON
+    pub request: String,
+    /// Result and error of the response, as canonical JSON
+    pub response: String,
+}
+
+impl SignedResponseScope {
+    /// Returns the scope of `response` to the JSON RPC request object `request`, or `None` if the
+    /// request carries no client nonce.
+    pub fn new(request: &Value, response: &JsonRpcResponse) -> Option<Self> {
+        let client_nonce = request.get(DIEM_CLIENT_NONCE)?.as_str()?.to_string();
+        let request = serde_json::json!({
+            "method": request.get("method"),
+            "params": request.get("params"),
+        });
+        let result = serde_json::json!({
+            "result": response.result,
+            "error": response.error,
+        });
+        Some(Self {
+            client_nonce,
+            chain_id: response.diem_chain_id,
+            ledger_version: response.diem_ledger_version,
+            ledger_timestampusec: response.diem_ledger_timestampusec,
+            request: canonical_json(&request),
+            response: canonical_json(&result),
+        })
+    }
+}
+
+/// Serializes `value` with the members of its objects sorted by name, so the node and the client
+/// sign and verify the same bytes whatever order they keep members in.
+fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(members) => {
+                let mut members: Vec<_> = members.iter().collect();
+                members.sort_by(|(a, _), (b, _)| a.cmp(b));
+                Value::Object(
+                    members
+                        .into_iter()
+                        .map(|(name, value)| (name.clone(), sorted(value)))
+                        .collect(),
+                )
+            }
+            Value::Array(values) => Value::Array(values.iter().map(sorted).collect()),
+            value => value.clone(),
+        }
+    }
+    sorted(value).to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::response::JsonRpcResponse;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
     use diem_types::chain_id::ChainId;
+    use serde_json::json;
 
     #[test]
     fn test_new() {
@@ -58,4 +169,51 @@ mod tests {
         assert!(resp.result.is_none());
         assert!(resp.error.is_none());
     }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signing_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = signing_key.public_key();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "get_account",
+            "params": ["00000000000000000000000000000dd", null],
+            "id": 1,
+            "diem_client_nonce": "abc",
+        });
+        let mut resp = JsonRpcResponse::new(ChainId::test(), 1, 2);
+        resp.result = Some(json!({"sequence_number": 3, "address": "dd"}));
+        resp.sign(&request, &signing_key);
+        assert_eq!(resp.diem_client_nonce.as_deref(), Some("abc"));
+        resp.verify_signature(&request, &public_key).unwrap();
+
+        // The client reads members back in whatever order
+        let mut parsed: JsonRpcResponse =
+            serde_json::from_str(&serde_json::to_string(&resp).unwrap()).unwrap();
+        parsed.result = Some(json!({"address": "dd", "sequence_number": 3}));
+        parsed.verify_signature(&request, &public_key).unwrap();
+
+        // Replayed in answer to another request
+        let mut other_request = request.clone();
+        other_request["params"] = json!(["00000000000000000000000000000ee", null]);
+        resp.verify_signature(&other_request, &public_key)
+            .unwrap_err();
+        other_request = request.clone();
+        other_request["diem_client_nonce"] = json!("def");
+        resp.verify_signature(&other_request, &public_key)
+            .unwrap_err();
+
+        // Passed off as read at another version
+        let mut stale = resp.clone();
+        stale.diem_ledger_version = 0;
+        stale.verify_signature(&request, &public_key).unwrap_err();
+
+        // Requests without a client nonce get unsigned responses
+        let mut unsigned = JsonRpcResponse::new(ChainId::test(), 1, 2);
+        let mut request = request;
+        request.as_object_mut().unwrap().remove("diem_client_nonce");
+        unsigned.sign(&request, &signing_key);
+        assert!(unsigned.diem_client_nonce.is_none());
+        assert!(unsigned.diem_response_signature.is_none());
+    }
 }
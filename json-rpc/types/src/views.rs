@@ -10,16 +10,17 @@ use diem_types::{account_config::{
         CurrencyInfoResource, DesignatedDealerPreburns, DiemIdDomainEvent, FreezingBit, MintEvent,
         NewBlockEvent, NewEpochEvent, PreburnEvent, ReceivedMintEvent, ReceivedPaymentEvent,
         SentPaymentEvent, ToXDXExchangeRateUpdateEvent,
-    }, account_state::AccountState, account_state_blob::{AccountStateBlob, AccountStateWithProof}, contract_event::{ContractEvent, EventWithProof}, diem_id_identifier::DiemIdVaspDomainIdentifier, epoch_change::EpochChangeProof, event::EventKey, ledger_info::LedgerInfoWithSignatures, ol_oracle_upgrade_state::{OracleResource, UpgradeOracle}, proof::{
+    }, account_state::AccountState, account_state_blob::{AccountStateBlob, AccountStateWithProof}, contract_event::{ContractEvent, EventWithProof}, diem_id_identifier::DiemIdVaspDomainIdentifier, epoch_change::EpochChangeProof, epoch_state::EpochState, event::EventKey, ledger_info::LedgerInfoWithSignatures, ol_oracle_upgrade_state::{OracleResource, UpgradeOracle}, proof::{
         AccountStateProof, AccumulatorConsistencyProof, SparseMerkleProof,
         TransactionAccumulatorProof, TransactionInfoWithProof, TransactionListProof,
     }, transaction::{
         Script, ScriptFunction, Transaction, TransactionArgument, TransactionInfo,
-        TransactionListWithProof, TransactionPayload,
-    }, vm_status::KeptVMStatus, waypoint::Waypoint};
+        TransactionListWithProof, TransactionOutput, TransactionPayload, TransactionStatus,
+    }, vm_status::KeptVMStatus, waypoint::Waypoint, write_set::WriteOp};
 use hex::FromHex;
 use move_core_types::{
     account_address::AccountAddress,
+    errmap::ErrorContext,
     identifier::Identifier,
     language_storage::{StructTag, TypeTag},
     move_resource::MoveStructType,
@@ -604,6 +605,17 @@ pub struct MoveAbortExplanationView {
     pub reason_description: String,
 }
 
+impl From<ErrorContext> for MoveAbortExplanationView {
+    fn from(context: ErrorContext) -> Self {
+        Self {
+            category: context.category.code_name,
+            category_description: context.category.code_description,
+            reason: context.reason.code_name,
+            reason_description: context.reason.code_description,
+        }
+    }
+}
+
 impl std::fmt::Display for MoveAbortExplanationView {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Error Category: {}", self.category)?;
@@ -641,6 +653,24 @@ impl VMStatusView {
     pub fn is_executed(&self) -> bool {
         matches!(self, Self::Executed)
     }
+
+    /// Explains a Move abort the server left unexplained, e.g. one with an abort code registered
+    /// as a custom error in this process only.
+    pub fn explain_abort(&mut self) {
+        if let VMStatusView::MoveAbort {
+            location,
+            abort_code,
+            explanation,
+        } = self
+        {
+            if explanation.is_none() {
+                *explanation = error_explain::parse_module_id(location)
+                    .ok()
+                    .and_then(|module_id| error_explain::get_explanation(&module_id, *abort_code))
+                    .map(MoveAbortExplanationView::from);
+            }
+        }
+    }
 }
 
 impl std::fmt::Display for VMStatusView {
@@ -652,20 +682,21 @@ impl std::fmt::Display for VMStatusView {
                 location,
                 abort_code,
                 explanation,
-            } => {
-                write!(f, "Move Abort: {} at {}", abort_code, location)?;
-                if let Some(explanation) = explanation {
-                    write!(f, "\nExplanation:\n{}", explanation)?
-                }
-                Ok(())
-            }
+            } => match explanation {
+                Some(explanation) => write!(
+                    f,
+                    "Move Abort: {} in {} (abort code {})\nExplanation:\n{}",
+                    explanation.reason, location, abort_code, explanation
+                ),
+                None => write!(f, "Move Abort: {} at {}", abort_code, location),
+            },
             VMStatusView::ExecutionFailure {
                 location,
                 function_index,
                 code_offset,
             } => write!(
                 f,
-                "Execution failure: {} {} {}",
+                "Execution failure at {}: function index {}, code offset {}",
                 location, function_index, code_offset
             ),
             VMStatusView::MiscellaneousError => write!(f, "Miscellaneous Error"),
@@ -684,14 +715,8 @@ impl From<&KeptVMStatus> for VMStatusView {
             KeptVMStatus::OutOfGas => VMStatusView::OutOfGas,
             KeptVMStatus::MoveAbort(loc, abort_code) => {
                 let explanation = if let AbortLocation::Module(module_id) = loc {
-                    error_explain::get_explanation(module_id, *abort_code).map(|context| {
-                        MoveAbortExplanationView {
-                            category: context.category.code_name,
-                            category_description: context.category.code_description,
-                            reason: context.reason.code_name,
-                            reason_description: context.reason.code_description,
-                        }
-                    })
+                    error_explain::get_explanation(module_id, *abort_code)
+                        .map(MoveAbortExplanationView::from)
                 } else {
                     None
                 };
@@ -1389,7 +1414,7 @@ impl TryFrom<&AccountStateProofView> for AccountStateProof {
 
 #[cfg(test)]
 mod tests {
-    use crate::views::{AmountView, EventDataView, PreburnWithMetadataView};
+    use crate::views::{AmountView, EventCursor, EventDataView, PreburnWithMetadataView};
     use diem_types::{contract_event::ContractEvent, event::EventKey};
     use move_core_types::language_storage::TypeTag;
     use serde_json::json;
@@ -1431,6 +1456,33 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_event_cursor_roundtrip() {
+        let cursor = EventCursor {
+            key: EventKey::from_str("0000000000000000000000000000000000000000000000dd").unwrap(),
+            next_sequence_number: 10,
+            ledger_version: 1000,
+            last_sequence_number: Some(42),
+        };
+        let value = serde_json::to_value(&cursor).unwrap();
+        assert!(value.is_string());
+        let bytes = hex::decode(value.as_str().unwrap()).unwrap();
+        assert_eq!(
+            serde_json::from_value::<EventCursor>(value).unwrap(),
+            cursor
+        );
+        assert!(cursor.has_more());
+
+        // unknown formats and truncated cursors are rejected
+        let decode =
+            |bytes: &[u8]| serde_json::from_value::<EventCursor>(json!(hex::encode(bytes)));
+        let mut unknown_format = bytes.clone();
+        unknown_format[0] = 1;
+        assert!(decode(&unknown_format).is_err());
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(&[]).is_err());
+    }
 }
 
 //////// 0L ////////
@@ -1520,4 +1572,374 @@ impl TryFrom<Waypoint> for WaypointView {
             waypoint: w,
         })
     }
-}
\ No newline at end of file
+}
+//////// 0L ////////
+/// A single resource read out of an account's state, along with the account state it was read
+/// from.
+///
+/// The state tree commits to an account's whole blob, so no proof can be given for the resource
+/// alone: clients verify `account_state_with_proof` against a ledger info, then check that the
+/// account state holds `blob` under the requested path.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountResourceView {
+    pub version: u64,
+    pub blob: Option<BytesView>,
+    pub account_state_with_proof: AccountStateWithProofView,
+}
+
+/// A single change in a transaction's write set. `value` is `None` when the resource or module at
+/// `path` was deleted.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WriteSetChangeView {
+    pub address: AccountAddress,
+    pub path: BytesView,
+    pub value: Option<BytesView>,
+}
+
+/// The effects of a committed transaction: what it wrote, what it emitted and how it ended.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TransactionOutputView {
+    pub version: u64,
+    pub write_set: Vec<WriteSetChangeView>,
+    pub events: Vec<EventView>,
+    pub gas_used: u64,
+    pub vm_status: VMStatusView,
+}
+
+impl TryFrom<(u64, TransactionOutput)> for TransactionOutputView {
+    type Error = Error;
+
+    fn try_from((version, output): (u64, TransactionOutput)) -> Result<Self> {
+        let vm_status = match output.status() {
+            TransactionStatus::Keep(status) => VMStatusView::from(status),
+            status => bail!("transaction {} was not kept: {:?}", version, status),
+        };
+        let write_set = output
+            .write_set()
+            .iter()
+            .map(|(access_path, op)| WriteSetChangeView {
+                address: access_path.address,
+                path: BytesView::new(access_path.path.clone()),
+                value: match op {
+                    WriteOp::Value(value) => Some(BytesView::new(value.clone())),
+                    WriteOp::Deletion => None,
+                },
+            })
+            .collect();
+        let events = output
+            .events()
+            .iter()
+            .cloned()
+            .map(|event| EventView::try_from((version, event)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(TransactionOutputView {
+            version,
+            write_set,
+            events,
+            gas_used: output.gas_used(),
+            vm_status,
+        })
+    }
+}
+
+/// A member of an epoch's validator set.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EpochValidatorView {
+    pub address: AccountAddress,
+    pub consensus_public_key: BytesView,
+    pub voting_power: u64,
+}
+
+/// A validator's signature on the ledger info that ended an epoch.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ValidatorSignatureView {
+    pub address: AccountAddress,
+    pub signature: BytesView,
+}
+
+/// The validator set of an epoch and the reconfigurations that started and ended it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EpochInfoView {
+    pub epoch: u64,
+    /// Version of the reconfiguration that started the epoch
+    pub start_version: u64,
+    /// Version of the reconfiguration that ended the epoch, `None` while the epoch is ongoing
+    pub end_version: Option<u64>,
+    pub validators: Vec<EpochValidatorView>,
+    pub quorum_voting_power: u64,
+    /// Signatures of the epoch's validators on the ledger info that ended it
+    pub signatures: Vec<ValidatorSignatureView>,
+    /// BCS-serialized `LedgerInfoWithSignatures` that ended the epoch, for clients verifying the
+    /// signatures themselves
+    pub end_ledger_info_with_signatures: Option<BytesView>,
+}
+
+impl
+    TryFrom<(
+        EpochState,
+        LedgerInfoWithSignatures,
+        Option<LedgerInfoWithSignatures>,
+    )> for EpochInfoView
+{
+    type Error = Error;
+
+    fn try_from(
+        (epoch_state, start_ledger_info, end_ledger_info): (
+            EpochState,
+            LedgerInfoWithSignatures,
+            Option<LedgerInfoWithSignatures>,
+        ),
+    ) -> Result<Self> {
+        let verifier = &epoch_state.verifier;
+        let validators = verifier
+            .get_ordered_account_addresses_iter()
+            .map(|address| {
+                let public_key = verifier
+                    .get_public_key(&address)
+                    .ok_or_else(|| format_err!("no public key for validator {}", address))?;
+                Ok(EpochValidatorView {
+                    address,
+                    consensus_public_key: BytesView::new(public_key.to_bytes().to_vec()),
+                    voting_power: verifier.get_voting_power(&address).unwrap_or_default(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let signatures = end_ledger_info
+            .iter()
+            .flat_map(|ledger_info| ledger_info.signatures())
+            .map(|(address, signature)| ValidatorSignatureView {
+                address: *address,
+                signature: BytesView::new(signature.to_bytes().to_vec()),
+            })
+            .collect();
+
+        Ok(EpochInfoView {
+            epoch: epoch_state.epoch,
+            start_version: start_ledger_info.ledger_info().version(),
+            end_version: end_ledger_info
+                .as_ref()
+                .map(|ledger_info| ledger_info.ledger_info().version()),
+            validators,
+            quorum_voting_power: verifier.quorum_voting_power(),
+            signatures,
+            end_ledger_info_with_signatures: end_ledger_info
+                .as_ref()
+                .map(bcs::to_bytes)
+                .transpose()?
+                .map(BytesView::new),
+        })
+    }
+}
+
+/// Aggregate statistics of the events emitted under an event key.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EventStatsView {
+    pub key: EventKey,
+    /// Number of events emitted under the key
+    pub count: u64,
+    /// Sequence number and transaction version of the oldest event the node holds, the first one
+    /// emitted unless the node was bootstrapped from a state snapshot. `None` without events.
+    pub first_sequence_number: Option<u64>,
+    pub first_transaction_version: Option<u64>,
+    /// Sequence number and transaction version of the latest event, `None` without events.
+    pub last_sequence_number: Option<u64>,
+    pub last_transaction_version: Option<u64>,
+}
+
+/// A page of the events emitted under an event key, returned by `get_events_page`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct EventPageView {
+    pub events: Vec<EventView>,
+    /// Ledger version the page was read at. Pages continued from `next` are read at the same
+    /// version until the events it held are all returned.
+    pub ledger_version: u64,
+    /// Whether events emitted as of `ledger_version` follow the page. Once they don't, `next`
+    /// resumes from the events emitted since.
+    pub has_more: bool,
+    pub next: EventCursor,
+}
+
+/// Position in the events of a key, handed out by `get_events_page` to continue from. Clients
+/// treat it as an opaque string: the hex encoding of a format byte followed by the BCS bytes of
+/// the cursor.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(into = "BytesView", try_from = "BytesView")]
+pub struct EventCursor {
+    pub key: EventKey,
+    /// Sequence number of the next event to return
+    pub next_sequence_number: u64,
+    /// Ledger version the pages are read at
+    pub ledger_version: u64,
+    /// Sequence number of the latest event emitted under `key` as of `ledger_version`, `None`
+    /// without events. Bounds the scans of the continued pages.
+    pub last_sequence_number: Option<u64>,
+}
+
+impl EventCursor {
+    const FORMAT: u8 = 0;
+
+    /// Whether events emitted as of `ledger_version` remain from `next_sequence_number`
+    pub fn has_more(&self) -> bool {
+        self.last_sequence_number
+            .map_or(false, |last| self.next_sequence_number <= last)
+    }
+}
+
+impl From<EventCursor> for BytesView {
+    fn from(cursor: EventCursor) -> Self {
+        let fields = (
+            cursor.key,
+            cursor.next_sequence_number,
+            cursor.ledger_version,
+            cursor.last_sequence_number,
+        );
+        let mut bytes = vec![EventCursor::FORMAT];
+        bytes.extend(bcs::to_bytes(&fields).expect("Cursor fields serialize"));
+        bytes.into()
+    }
+}
+
+impl TryFrom<BytesView> for EventCursor {
+    type Error = Error;
+
+    fn try_from(bytes: BytesView) -> Result<Self> {
+        let (format, fields) = bytes
+            .split_first()
+            .ok_or_else(|| format_err!("Empty event cursor"))?;
+        ensure!(
+            *format == Self::FORMAT,
+            "Unknown event cursor format {}",
+            format
+        );
+        let (key, next_sequence_number, ledger_version, last_sequence_number) =
+            bcs::from_bytes(fields)?;
+        Ok(Self {
+            key,
+            next_sequence_number,
+            ledger_version,
+            last_sequence_number,
+        })
+    }
+}
+
+/// The bytecode of a module published on chain, as of `version`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ModuleView {
+    pub address: AccountAddress,
+    pub name: String,
+    pub version: u64,
+    /// The BCS serialized `CompiledModule`
+    pub bytecode: BytesView,
+}
+
+/// The interface of a module published on chain, as of `version`, derived from its bytecode.
+/// Types are spelled as in Move source, e.g. `vector<u8>`, `&signer` or
+/// `0x1::Diem::Diem<T0>`, type parameters as `T0`, `T1`...
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ModuleAbiView {
+    pub address: AccountAddress,
+    pub name: String,
+    pub version: u64,
+    /// Modules allowed to call the `friend` functions, e.g. `0x1::DiemAccount`
+    pub friends: Vec<String>,
+    pub structs: Vec<MoveStructAbiView>,
+    /// Functions callable from outside the module, `script` ones being the entry points of
+    /// transactions
+    pub functions: Vec<MoveFunctionAbiView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MoveStructAbiView {
+    pub name: String,
+    /// Any of `copy`, `drop`, `store` and `key`
+    pub abilities: Vec<String>,
+    /// Abilities each type parameter is constrained to
+    pub type_parameters: Vec<Vec<String>>,
+    /// Whether the struct is native, and so has no fields
+    pub is_native: bool,
+    pub fields: Vec<MoveFieldAbiView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MoveFieldAbiView {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MoveFunctionAbiView {
+    pub name: String,
+    /// One of `public`, `script` and `friend`
+    pub visibility: String,
+    /// Abilities each type parameter is constrained to
+    pub type_parameters: Vec<Vec<String>>,
+    pub parameters: Vec<String>,
+    #[serde(rename = "return")]
+    pub return_: Vec<String>,
+}
+
+/// How to call a transaction script known to the node, from the ABIs of its Diem framework
+/// release.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScriptSchemaView {
+    pub name: String,
+    /// The module of a script function, e.g. `0x1::PaymentScripts`, none for a legacy
+    /// transaction script
+    pub module: Option<String>,
+    pub doc: String,
+    /// The names of the type arguments, each given as a Move type tag such as `0x1::GAS::GAS`
+    pub type_arguments: Vec<String>,
+    /// The arguments the sender provides, in order
+    pub arguments: Vec<ScriptArgumentSchemaView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScriptArgumentSchemaView {
+    pub name: String,
+    /// The Move type of the argument, e.g. `vector<u8>`
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// JSON schema of the value of the argument
+    pub schema: serde_json::Value,
+}
+
+/// A watch registered by `watch_resources`
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResourceWatchView {
+    /// ID to pass to `unwatch_resources`
+    pub id: u64,
+    /// The version the watched resources were read at. Changes are posted from the next commit.
+    pub version: u64,
+}
+
+/// Body of the requests posted to the callback URL of a watch, once per commit changing any of
+/// its resources
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResourceChangesView {
+    pub watch_id: u64,
+    /// Version of the last transaction of the commit
+    pub version: u64,
+    pub changes: Vec<ResourceChangeView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResourceChangeView {
+    pub account: AccountAddress,
+    pub path: BytesView,
+    /// The value last posted, or read when the watch was registered. None if the resource
+    /// didn't exist.
+    pub before: Option<ResourceValueView>,
+    /// None if the resource was removed
+    pub after: Option<ResourceValueView>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ResourceValueView {
+    /// The BCS encoded resource
+    pub bytes: BytesView,
+    /// The fields of the resource by name, none if its type couldn't be resolved. Integers up to
+    /// `u64` are numbers, `u128` values decimal strings, addresses and bytes hex strings.
+    pub decoded: Option<serde_json::Value>,
+}
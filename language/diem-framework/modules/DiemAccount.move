@@ -1619,6 +1619,30 @@ module DiemAccount {
       onboarding_gas_transfer<GAS>(owner_sig, oper, BOOTSTRAP_COIN_VALUE);
     }
 
+    //////// 0L ////////
+    // Used by genesis builders to seed accounts with an initial balance.
+    // Creates a user account at `new_account` if none exists and deposits
+    // `value` freshly minted coins into it.
+    public fun genesis_fund_account(
+      vm: &signer,
+      new_account: address,
+      new_account_authkey_prefix: vector<u8>,
+      value: u64,
+    ) acquires DiemAccount, Balance, AccountOperationsCapability, CumulativeDeposits {
+      CoreAddresses::assert_vm(vm);
+      if (!exists_at(new_account)) {
+        let new_signer = create_signer(new_account);
+        Roles::new_user_role_with_proof(&new_signer);
+        Event::publish_generator(&new_signer);
+        add_currencies_for_account<GAS>(&new_signer, false);
+        make_account(new_signer, new_account_authkey_prefix);
+      };
+      if (value > 0) {
+        let coins = Diem::mint<GAS>(vm, value);
+        vm_deposit_with_metadata<GAS>(vm, new_account, coins, b"genesis balance", b"");
+      };
+    }
+
     /// Rotate the authentication key for the account under cap.account_address
     public fun rotate_authentication_key(
         cap: &KeyRotationCapability,
@@ -2575,6 +2599,113 @@ module DiemAccount {
         with Errors::INVALID_ARGUMENT;
     }
 
+    /// The prologue for script transactions whose gas is paid by a fee payer rather than by
+    /// their sender
+    fun fee_payer_script_prologue<Token: store>(
+        sender: signer,
+        txn_sequence_number: u64,
+        txn_sender_public_key: vector<u8>,
+        fee_payer_address: address,
+        fee_payer_public_key_hash: vector<u8>,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        txn_expiration_time: u64,
+        chain_id: u8,
+        script_hash: vector<u8>,
+    ) acquires DiemAccount, Balance {
+        assert(
+            DiemTransactionPublishingOption::is_script_allowed(&sender, &script_hash),
+            Errors::invalid_state(PROLOGUE_ESCRIPT_NOT_ALLOWED),
+        );
+
+        // Check that the fee payer has an account which isn't frozen.
+        assert(exists_at(fee_payer_address), Errors::invalid_argument(PROLOGUE_EACCOUNT_DNE));
+        assert(
+            !AccountFreezing::account_is_frozen(fee_payer_address),
+            Errors::invalid_state(PROLOGUE_EACCOUNT_FROZEN)
+        );
+
+        // Check that the provided public key hash of the fee payer is equal to the
+        // authentication key stored on-chain.
+        let fee_payer_account = borrow_global<DiemAccount>(fee_payer_address);
+        assert(
+            fee_payer_public_key_hash == *&fee_payer_account.authentication_key,
+            Errors::invalid_argument(PROLOGUE_EINVALID_ACCOUNT_AUTH_KEY),
+        );
+
+        // Check that the fee payer can cover the maximum transaction fee, as [PCA5] to [PCA8]
+        // do for the sender of other transactions.
+        assert(
+            (txn_gas_price as u128) * (txn_max_gas_units as u128) <= MAX_U64,
+            Errors::invalid_argument(PROLOGUE_ECANT_PAY_GAS_DEPOSIT),
+        );
+        let max_transaction_fee = txn_gas_price * txn_max_gas_units;
+        if (max_transaction_fee > 0) {
+            assert(
+                TransactionFee::is_coin_initialized<Token>(),
+                Errors::invalid_argument(PROLOGUE_EBAD_TRANSACTION_FEE_CURRENCY)
+            );
+            assert(
+                exists<Balance<Token>>(fee_payer_address),
+                Errors::invalid_argument(PROLOGUE_ECANT_PAY_GAS_DEPOSIT)
+            );
+            assert(
+                balance<Token>(fee_payer_address) >= max_transaction_fee,
+                Errors::invalid_argument(PROLOGUE_ECANT_PAY_GAS_DEPOSIT)
+            );
+        };
+
+        // The sender doesn't pay for gas, so it needs no balance.
+        prologue_common<Token>(
+            &sender,
+            txn_sequence_number,
+            txn_sender_public_key,
+            0,
+            0,
+            txn_expiration_time,
+            chain_id,
+        )
+    }
+
+    spec fee_payer_script_prologue {
+        include FeePayerScriptPrologueAbortsIf<Token>{
+            txn_expiration_time_seconds: txn_expiration_time,
+        };
+        ensures prologue_guarantees(sender);
+    }
+
+    spec schema FeePayerScriptPrologueAbortsIf<Token> {
+        sender: signer;
+        txn_sequence_number: u64;
+        txn_sender_public_key: vector<u8>;
+        fee_payer_address: address;
+        fee_payer_public_key_hash: vector<u8>;
+        txn_gas_price: u64;
+        txn_max_gas_units: u64;
+        chain_id: u8;
+        txn_expiration_time_seconds: u64;
+        script_hash: vector<u8>;
+        let transaction_sender = Signer::spec_address_of(sender);
+        let max_transaction_fee = txn_gas_price * txn_max_gas_units;
+        include PrologueCommonAbortsIf<Token> {
+            transaction_sender,
+            txn_public_key: txn_sender_public_key,
+            max_transaction_fee: 0,
+        };
+        /// Aborts only in Genesis. Does not need to be handled.
+        include DiemTransactionPublishingOption::AbortsIfNoTransactionPublishingOption;
+        aborts_if !DiemTransactionPublishingOption::spec_is_script_allowed(sender, script_hash) with Errors::INVALID_STATE;
+        aborts_if !exists_at(fee_payer_address) with Errors::INVALID_ARGUMENT;
+        aborts_if AccountFreezing::spec_account_is_frozen(fee_payer_address) with Errors::INVALID_STATE;
+        aborts_if fee_payer_public_key_hash != global<DiemAccount>(fee_payer_address).authentication_key
+            with Errors::INVALID_ARGUMENT;
+        aborts_if max_transaction_fee > MAX_U64 with Errors::INVALID_ARGUMENT;
+        aborts_if max_transaction_fee > 0 && !TransactionFee::is_coin_initialized<Token>() with Errors::INVALID_ARGUMENT;
+        aborts_if max_transaction_fee > 0 && !exists<Balance<Token>>(fee_payer_address) with Errors::INVALID_ARGUMENT;
+        aborts_if max_transaction_fee > 0 && balance<Token>(fee_payer_address) < max_transaction_fee
+            with Errors::INVALID_ARGUMENT;
+    }
+
     /// The common prologue is invoked at the beginning of every transaction
     /// The main properties that it verifies:
     /// - The account's auth key matches the transaction's public key
@@ -2779,20 +2910,65 @@ module DiemAccount {
 
         if (transaction_fee_amount > 0) {
             // [Invariant Use]: Balance for `Token` verified to exist for non-zero transaction fee amounts by [PCA7].
-            let sender_balance = borrow_global_mut<Balance<Token>>(sender);
-            let coin = &mut sender_balance.coin;
+            pay_transaction_fee<Token>(sender, transaction_fee_amount)
+        }
+    }
 
-            // [EA4; Condition]: Abort if this withdrawal would make the `sender_account`'s balance go negative
-            assert(
-                transaction_fee_amount <= Diem::value(coin),
-                Errors::limit_exceeded(PROLOGUE_ECANT_PAY_GAS_DEPOSIT)
-            );
+    /// Epilogue for fee payer transactions. Bumps the sequence number of the sender like
+    /// `epilogue`, but collects the gas from `fee_payer_address`.
+    fun fee_payer_epilogue<Token: store>(
+        account: signer,
+        fee_payer_address: address,
+        txn_sequence_number: u64,
+        txn_gas_price: u64,
+        txn_max_gas_units: u64,
+        gas_units_remaining: u64
+    ) acquires DiemAccount, Balance {
+        // The sender pays no gas.
+        epilogue_common<Token>(
+            &account,
+            txn_sequence_number,
+            0,
+            txn_max_gas_units,
+            gas_units_remaining,
+        );
 
-            // NB: `withdraw_from_balance` is not used as limits do not apply to this transaction fee
-            TransactionFee::pay_fee(Diem::withdraw(coin, transaction_fee_amount))
+        // [EA1] was checked by `epilogue_common`.
+        let gas_used = txn_max_gas_units - gas_units_remaining;
+
+        // [EA2; Invariant]: Already checked by `fee_payer_script_prologue`.
+        assert(
+            (txn_gas_price as u128) * (gas_used as u128) <= MAX_U64,
+            Errors::limit_exceeded(EGAS)
+        );
+        let transaction_fee_amount = txn_gas_price * gas_used;
+
+        if (transaction_fee_amount > 0) {
+            // [Invariant Use]: Balance for `Token` verified to exist for non-zero transaction fee
+            // amounts by `fee_payer_script_prologue`.
+            pay_transaction_fee<Token>(fee_payer_address, transaction_fee_amount)
         }
     }
 
+    /// Withdraws `transaction_fee_amount` from the balance of `payer` and pays it as a
+    /// transaction fee.
+    fun pay_transaction_fee<Token: store>(
+        payer: address,
+        transaction_fee_amount: u64,
+    ) acquires Balance {
+        let payer_balance = borrow_global_mut<Balance<Token>>(payer);
+        let coin = &mut payer_balance.coin;
+
+        // [EA4; Condition]: Abort if this withdrawal would make the payer's balance go negative
+        assert(
+            transaction_fee_amount <= Diem::value(coin),
+            Errors::limit_exceeded(PROLOGUE_ECANT_PAY_GAS_DEPOSIT)
+        );
+
+        // NB: `withdraw_from_balance` is not used as limits do not apply to this transaction fee
+        TransactionFee::pay_fee(Diem::withdraw(coin, transaction_fee_amount))
+    }
+
     /// Epilogue for WriteSet trasnaction
     fun writeset_epilogue(
         dr_account: signer,
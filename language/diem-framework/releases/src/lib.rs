@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, Result};
-use diem_types::{transaction::ScriptFunction, vm_status::{StatusCode, VMStatus}};
+use diem_types::{transaction::{ScriptABI, ScriptFunction}, vm_status::{StatusCode, VMStatus}};
 use include_dir::{include_dir, Dir};
 use move_binary_format::file_format::CompiledModule;
 use once_cell::sync::Lazy;
@@ -83,6 +83,31 @@ pub fn load_error_descriptions_from_release(release_name: &str) -> Result<Vec<u8
     }
 }
 
+/// Load the ABIs of the script functions from the specified release, sorted by module and name.
+pub fn load_script_abis_from_release(release_name: &str) -> Result<Vec<ScriptABI>> {
+    let mut abis_path = PathBuf::from(release_name);
+    abis_path.push("script_abis");
+
+    match RELEASES_DIR.get_dir(&abis_path) {
+        Some(abis_dir) => {
+            // one directory per module
+            let mut abis = abis_dir
+                .dirs()
+                .iter()
+                .flat_map(|dir| dir.files())
+                .filter(|file| matches!(file.path().extension(), Some(ext) if ext == "abi"))
+                .collect::<Vec<_>>();
+
+            abis.sort_by_key(|file| file.path());
+
+            abis.into_iter()
+                .map(|file| Ok(bcs::from_bytes::<ScriptABI>(file.contents())?))
+                .collect()
+        }
+        None => bail!("release {} not found", release_name),
+    }
+}
+
 static CURRENT_MODULE_BLOBS: Lazy<Vec<Vec<u8>>> =
     Lazy::new(|| load_modules_from_release("current").unwrap());
 
@@ -113,6 +138,13 @@ pub fn current_error_descriptions() -> &'static [u8] {
     &CURRENT_ERROR_DESCRIPTIONS
 }
 
+static CURRENT_SCRIPT_ABIS: Lazy<Vec<ScriptABI>> =
+    Lazy::new(|| load_script_abis_from_release("current").unwrap());
+
+pub fn current_script_abis() -> &'static [ScriptABI] {
+    &CURRENT_SCRIPT_ABIS
+}
+
 pub fn name_for_script(bytes: &[u8]) -> Result<String> {
     if let Ok(script) = legacy::transaction_scripts::LegacyStdlibScript::try_from(bytes) {
         Ok(format!("{}", script))
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_types::transaction::ScriptABI;
 use move_binary_format::CompiledModule;
 
 #[test]
@@ -41,4 +42,21 @@ fn can_deserialize_and_verify_modules_from_all_versions() {
     }
 }
 
-// TODO: tests to ensure script abis and error_descriptions can be correctly read
+#[test]
+fn can_deserialize_script_abis_from_all_versions() {
+    for ver in crate::list_all_releases().unwrap() {
+        let abis = crate::load_script_abis_from_release(&ver).unwrap();
+        assert!(
+            !abis.is_empty(),
+            "no script abis found under version {}",
+            ver
+        );
+        assert!(
+            abis.iter().all(ScriptABI::is_script_fun_abi),
+            "transaction script abi found under version {}",
+            ver
+        );
+    }
+}
+
+// TODO: tests to ensure error_descriptions can be correctly read
@@ -1,16 +1,18 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::DiemValidatorInterface;
-use anyhow::Result;
+use crate::{CommittedOutput, DiemValidatorInterface};
+use anyhow::{ensure, format_err, Result};
 use diem_client::BlockingClient;
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
     contract_event::EventWithProof,
     event::EventKey,
     transaction::{Transaction, Version},
+    write_set::{WriteOp, WriteSetMut},
 };
 use std::convert::TryFrom;
 
@@ -77,6 +79,62 @@ impl DiemValidatorInterface for JsonRpcDebuggerInterface {
         Ok(output)
     }
 
+    fn get_committed_outputs(&self, start: Version, limit: u64) -> Result<Vec<CommittedOutput>> {
+        let txns = match self
+            .client
+            .get_transactions_with_proofs(start, limit, true)?
+            .into_inner()
+        {
+            Some(txns) => txns.try_into_txn_list_with_proof(start)?,
+            None => return Ok(vec![]),
+        };
+        let events = txns
+            .events
+            .ok_or_else(|| format_err!("Transactions returned without their events"))?;
+        let outputs = self
+            .client
+            .get_transaction_outputs(start, limit)?
+            .into_inner();
+        ensure!(
+            txns.proof.transaction_infos.len() == outputs.len(),
+            "Got {} outputs for {} transactions",
+            outputs.len(),
+            txns.proof.transaction_infos.len()
+        );
+
+        let mut result = vec![];
+        for ((info, events), output) in txns
+            .proof
+            .transaction_infos
+            .into_iter()
+            .zip(events)
+            .zip(outputs)
+        {
+            let write_set = WriteSetMut::new(
+                output
+                    .write_set
+                    .into_iter()
+                    .map(|change| {
+                        let access_path =
+                            AccessPath::new(change.address, change.path.into_inner().into());
+                        let op = match change.value {
+                            Some(value) => WriteOp::Value(value.into_inner().into()),
+                            None => WriteOp::Deletion,
+                        };
+                        (access_path, op)
+                    })
+                    .collect(),
+            )
+            .freeze()?;
+            result.push(CommittedOutput {
+                info,
+                write_set,
+                events,
+            });
+        }
+        Ok(result)
+    }
+
     fn get_latest_version(&self) -> Result<Version> {
         let metadata = self.client.get_metadata()?.into_inner();
 
@@ -14,13 +14,22 @@ use diem_types::{
     account_address::AccountAddress,
     account_config,
     account_state::AccountState,
-    contract_event::EventWithProof,
+    contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
     on_chain_config::ValidatorSet,
-    transaction::{Transaction, Version},
+    transaction::{Transaction, TransactionInfo, Version},
+    write_set::WriteSet,
 };
 use move_binary_format::file_format::CompiledModule;
 
+/// What the ledger recorded for a committed transaction.
+#[derive(Clone, Debug)]
+pub struct CommittedOutput {
+    pub info: TransactionInfo,
+    pub write_set: WriteSet,
+    pub events: Vec<ContractEvent>,
+}
+
 pub trait DiemValidatorInterface: Sync {
     fn get_account_state_by_version(
         &self,
@@ -30,6 +39,7 @@ pub trait DiemValidatorInterface: Sync {
     fn get_events(&self, key: &EventKey, start_seq: u64, limit: u64)
         -> Result<Vec<EventWithProof>>;
     fn get_committed_transactions(&self, start: Version, limit: u64) -> Result<Vec<Transaction>>;
+    fn get_committed_outputs(&self, start: Version, limit: u64) -> Result<Vec<CommittedOutput>>;
     fn get_latest_version(&self) -> Result<Version>;
     fn get_version_by_account_sequence(
         &self,
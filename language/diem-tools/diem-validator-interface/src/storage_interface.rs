@@ -1,8 +1,8 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::DiemValidatorInterface;
-use anyhow::{anyhow, Result};
+use crate::{CommittedOutput, DiemValidatorInterface};
+use anyhow::{anyhow, ensure, Result};
 use diem_config::config::RocksdbConfig;
 use diem_types::{
     account_address::AccountAddress,
@@ -57,6 +57,36 @@ impl DiemValidatorInterface for DBDebuggerInterface {
             .transactions)
     }
 
+    fn get_committed_outputs(&self, start: Version, limit: u64) -> Result<Vec<CommittedOutput>> {
+        let ledger_version = self.get_latest_version()?;
+        let infos = self
+            .0
+            .get_transactions(start, limit, ledger_version, false)?
+            .proof
+            .transaction_infos;
+        let outputs = self
+            .0
+            .get_transaction_outputs(start, limit, ledger_version)?;
+        ensure!(
+            infos.len() == outputs.len(),
+            "Got {} outputs for {} transactions",
+            outputs.len(),
+            infos.len()
+        );
+        Ok(infos
+            .into_iter()
+            .zip(outputs)
+            .map(|(info, output)| {
+                let (write_set, events) = output.into();
+                CommittedOutput {
+                    info,
+                    write_set,
+                    events,
+                }
+            })
+            .collect())
+    }
+
     fn get_latest_version(&self) -> Result<Version> {
         let (version, _) = self
             .0
@@ -0,0 +1,112 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use diem_types::{
+    access_path::{self, AccessPath},
+    account_address::AccountAddress,
+    account_state::AccountState,
+    transaction::{ChangeSet, Version},
+    write_set::{WriteOp, WriteSetMut},
+};
+use resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue, MoveValueAnnotator};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// The full state of an account at a version, as annotated JSON. The raw bytes are kept alongside
+/// the annotations, so the state can be re-created exactly on another network.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AccountExport {
+    pub address: AccountAddress,
+    pub version: Version,
+    pub entries: Vec<ExportedEntry>,
+}
+
+/// A resource or module stored under an exported account.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ExportedEntry {
+    /// The struct tag of a resource, or the module id of a module.
+    pub name: String,
+    /// The fields of a resource, `None` for modules.
+    pub value: Option<Value>,
+    /// Hex of the BCS serialized `access_path::Path`.
+    pub path: String,
+    /// Hex of the stored bytes.
+    pub bytes: String,
+}
+
+impl AccountExport {
+    /// Exports `state`, the state of the account at `address` at `version`, annotating resources
+    /// with `annotator`.
+    pub fn new(
+        address: AccountAddress,
+        version: Version,
+        state: &AccountState,
+        annotator: &MoveValueAnnotator,
+    ) -> Result<Self> {
+        let mut entries = vec![];
+        for (path, bytes) in state.iter() {
+            let (name, value) = match bcs::from_bytes::<access_path::Path>(path)? {
+                access_path::Path::Resource(struct_tag) => {
+                    let value =
+                        annotated_struct_to_json(&annotator.view_resource(&struct_tag, bytes)?);
+                    (struct_tag.to_string(), Some(value))
+                }
+                access_path::Path::Code(module_id) => (module_id.to_string(), None),
+            };
+            entries.push(ExportedEntry {
+                name,
+                value,
+                path: hex::encode(path),
+                bytes: hex::encode(bytes),
+            });
+        }
+        Ok(Self {
+            address,
+            version,
+            entries,
+        })
+    }
+
+    /// Returns the change set writing every exported entry back under the account, to be applied
+    /// by a write set transaction signed by the Diem root. Entries the account holds on the target
+    /// network but not in the export are left as they are.
+    pub fn change_set(&self) -> Result<ChangeSet> {
+        let mut write_set = WriteSetMut::new(vec![]);
+        for entry in &self.entries {
+            write_set.push((
+                AccessPath::new(self.address, hex::decode(&entry.path)?),
+                WriteOp::Value(hex::decode(&entry.bytes)?),
+            ));
+        }
+        Ok(ChangeSet::new(write_set.freeze()?, vec![]))
+    }
+}
+
+/// Converts an annotated struct to a JSON object of its fields.
+pub fn annotated_struct_to_json(annotated: &AnnotatedMoveStruct) -> Value {
+    Value::Object(
+        annotated
+            .value
+            .iter()
+            .map(|(field, value)| (field.to_string(), annotated_value_to_json(value)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+/// Converts an annotated value to JSON. `u128`s are represented as strings, so they survive JSON
+/// parsers limited to 64-bit numbers, and byte vectors as hex strings.
+pub fn annotated_value_to_json(annotated: &AnnotatedMoveValue) -> Value {
+    match annotated {
+        AnnotatedMoveValue::U8(v) => json!(v),
+        AnnotatedMoveValue::U64(v) => json!(v),
+        AnnotatedMoveValue::U128(v) => json!(v.to_string()),
+        AnnotatedMoveValue::Bool(v) => json!(v),
+        AnnotatedMoveValue::Address(v) => json!(v.to_string()),
+        AnnotatedMoveValue::Vector(_, values) => {
+            Value::Array(values.iter().map(annotated_value_to_json).collect())
+        }
+        AnnotatedMoveValue::Bytes(v) => json!(hex::encode(v)),
+        AnnotatedMoveValue::Struct(s) => annotated_struct_to_json(s),
+    }
+}
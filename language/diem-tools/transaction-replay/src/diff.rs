@@ -0,0 +1,172 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_crypto::{
+    hash::{CryptoHash, EventAccumulatorHasher},
+    HashValue,
+};
+use diem_types::{
+    access_path::AccessPath,
+    contract_event::ContractEvent,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{TransactionOutput, TransactionStatus},
+    vm_status::KeptVMStatus,
+    write_set::WriteOp,
+};
+use diem_validator_interface::CommittedOutput;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// A difference between the replayed output of a transaction and what the ledger recorded for it.
+#[derive(Debug)]
+pub enum Divergence {
+    Status {
+        committed: KeptVMStatus,
+        replayed: TransactionStatus,
+    },
+    GasUsed {
+        committed: u64,
+        replayed: u64,
+    },
+    /// A write to `access_path`, missing on one side if `None`.
+    Write {
+        access_path: AccessPath,
+        committed: Option<WriteOp>,
+        replayed: Option<WriteOp>,
+    },
+    /// The event at `index`, missing on one side if `None`.
+    Event {
+        index: usize,
+        committed: Option<ContractEvent>,
+        replayed: Option<ContractEvent>,
+    },
+    /// The replayed events don't hash to the event root of the committed transaction info.
+    EventRootHash {
+        committed: HashValue,
+        replayed: HashValue,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Status {
+                committed,
+                replayed,
+            } => write!(
+                f,
+                "status: committed {:?}, replayed {:?}",
+                committed, replayed
+            ),
+            Divergence::GasUsed {
+                committed,
+                replayed,
+            } => write!(
+                f,
+                "gas used: committed {}, replayed {}",
+                committed, replayed
+            ),
+            Divergence::Write {
+                access_path,
+                committed,
+                replayed,
+            } => write!(
+                f,
+                "write to {:?}: committed {:?}, replayed {:?}",
+                access_path, committed, replayed
+            ),
+            Divergence::Event {
+                index,
+                committed,
+                replayed,
+            } => write!(
+                f,
+                "event {}: committed {:?}, replayed {:?}",
+                index, committed, replayed
+            ),
+            Divergence::EventRootHash {
+                committed,
+                replayed,
+            } => write!(
+                f,
+                "event root hash: committed {}, replayed {}",
+                committed, replayed
+            ),
+        }
+    }
+}
+
+/// Compares the replayed `output` of a transaction with the `committed` one. The state root isn't
+/// compared: replaying from the same state, it only differs if the write sets do.
+pub fn diff_output(committed: &CommittedOutput, output: &TransactionOutput) -> Vec<Divergence> {
+    let mut divergences = vec![];
+
+    match output.status() {
+        TransactionStatus::Keep(status) if status == committed.info.status() => (),
+        replayed => divergences.push(Divergence::Status {
+            committed: committed.info.status().clone(),
+            replayed: replayed.clone(),
+        }),
+    }
+    if output.gas_used() != committed.info.gas_used() {
+        divergences.push(Divergence::GasUsed {
+            committed: committed.info.gas_used(),
+            replayed: output.gas_used(),
+        });
+    }
+
+    let committed_writes = committed
+        .write_set
+        .iter()
+        .cloned()
+        .collect::<BTreeMap<_, _>>();
+    let replayed_writes = output
+        .write_set()
+        .iter()
+        .cloned()
+        .collect::<BTreeMap<_, _>>();
+    let access_paths = committed_writes
+        .keys()
+        .chain(replayed_writes.keys())
+        .collect::<BTreeSet<_>>();
+    for access_path in access_paths {
+        let committed_write = committed_writes.get(access_path);
+        let replayed_write = replayed_writes.get(access_path);
+        if committed_write != replayed_write {
+            divergences.push(Divergence::Write {
+                access_path: access_path.clone(),
+                committed: committed_write.cloned(),
+                replayed: replayed_write.cloned(),
+            });
+        }
+    }
+
+    for index in 0..committed.events.len().max(output.events().len()) {
+        let committed_event = committed.events.get(index);
+        let replayed_event = output.events().get(index);
+        if committed_event != replayed_event {
+            divergences.push(Divergence::Event {
+                index,
+                committed: committed_event.cloned(),
+                replayed: replayed_event.cloned(),
+            });
+        }
+    }
+    let event_hashes = output
+        .events()
+        .iter()
+        .map(CryptoHash::hash)
+        .collect::<Vec<_>>();
+    let event_root_hash =
+        InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes).root_hash();
+    if event_root_hash != committed.info.event_root_hash() {
+        divergences.push(Divergence::EventRootHash {
+            committed: committed.info.event_root_hash(),
+            replayed: event_root_hash,
+        });
+    }
+
+    divergences
+}
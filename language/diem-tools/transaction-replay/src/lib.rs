@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::{anyhow, bail, format_err, Result};
+use anyhow::{anyhow, bail, ensure, format_err, Result};
 use diem_types::{
     access_path,
     account_address::AccountAddress,
@@ -9,7 +9,9 @@ use diem_types::{
     account_state::AccountState,
     contract_event::{ContractEvent, EventWithProof},
     event::EventKey,
-    transaction::{ChangeSet, Transaction, TransactionOutput, Version, WriteSetPayload},
+    transaction::{
+        ChangeSet, DryRunWriteSet, Transaction, TransactionOutput, Version, WriteSetPayload,
+    },
     write_set::WriteOp,
 };
 use diem_validator_interface::{
@@ -26,6 +28,13 @@ use move_vm_types::gas_schedule::GasStatus;
 use resource_viewer::{AnnotatedAccountStateBlob, AnnotatedMoveStruct, MoveValueAnnotator};
 use std::path::{Path, PathBuf};
 
+mod account_export;
+mod diff;
+pub use account_export::{
+    annotated_struct_to_json, annotated_value_to_json, AccountExport, ExportedEntry,
+};
+pub use diff::{diff_output, Divergence};
+
 #[cfg(test)]
 mod unit_tests;
 
@@ -87,6 +96,37 @@ impl DiemDebugger {
         Ok(ret)
     }
 
+    /// Replays the `limit` transactions from `begin` against the state before them and compares
+    /// their outputs with what the ledger recorded, stopping at the first version that diverges.
+    pub fn diff_past_transactions(
+        &self,
+        mut begin: Version,
+        limit: u64,
+    ) -> Result<Option<(Version, Vec<Divergence>)>> {
+        let mut txns = self.debugger.get_committed_transactions(begin, limit)?;
+        let mut committed = self.debugger.get_committed_outputs(begin, limit)?;
+        if txns.len() != committed.len() {
+            bail!(
+                "Got {} outputs for {} transactions",
+                committed.len(),
+                txns.len()
+            );
+        }
+        while !txns.is_empty() {
+            let outputs = self.execute_transactions_by_epoch(begin, txns.clone(), false)?;
+            for (version, (committed, output)) in (begin..).zip(committed.iter().zip(&outputs)) {
+                let divergences = diff_output(committed, output);
+                if !divergences.is_empty() {
+                    return Ok(Some((version, divergences)));
+                }
+            }
+            begin += outputs.len() as u64;
+            txns = txns.split_off(outputs.len());
+            committed = committed.split_off(outputs.len());
+        }
+        Ok(None)
+    }
+
     pub fn execute_transactions_by_epoch(
         &self,
         begin: Version,
@@ -150,6 +190,34 @@ impl DiemDebugger {
         Ok(output)
     }
 
+    /// Dry-runs `payload` as if signed by the Diem root against the state at `version`, so it can
+    /// be submitted. The modules it publishes must declare the ids they're published under.
+    pub fn dry_run_writeset(
+        &self,
+        version: Version,
+        payload: WriteSetPayload,
+    ) -> Result<DryRunWriteSet> {
+        if let WriteSetPayload::Direct(change_set) = &payload {
+            for (access_path, op) in change_set.write_set() {
+                if let (access_path::Path::Code(module_id), WriteOp::Value(bytes)) =
+                    (access_path.get_path(), op)
+                {
+                    let module = CompiledModule::deserialize(bytes).map_err(|e| {
+                        anyhow!("Failure deserializing module {}: {:?}", module_id, e)
+                    })?;
+                    ensure!(
+                        module.self_id() == module_id,
+                        "Module {} is published as {}",
+                        module.self_id(),
+                        module_id
+                    );
+                }
+            }
+        }
+        let output = self.execute_writeset_at_version(version, &payload, false)?;
+        DryRunWriteSet::new(payload, output)
+    }
+
     fn save_write_sets(&self, o: &TransactionOutput) -> Result<()> {
         let state_view = OnDiskStateView::create(&self.build_dir, &self.storage_dir)?;
         for (ap, op) in o.write_set() {
@@ -268,6 +336,21 @@ impl DiemDebugger {
         )
     }
 
+    /// Exports the full state of `account` at `version` as annotated JSON, see `AccountExport`.
+    pub fn export_account_state_at_version(
+        &self,
+        account: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountExport>> {
+        let state_view = DebuggerStateView::new(&*self.debugger, version);
+        let remote_storage = RemoteStorage::new(&state_view);
+        let annotator = MoveValueAnnotator::new(&remote_storage);
+        self.debugger
+            .get_account_state_by_version(account, version)?
+            .map(|account_state| AccountExport::new(account, version, &account_state, &annotator))
+            .transpose()
+    }
+
     pub fn annotate_key_accounts_at_version(
         &self,
         version: Version,
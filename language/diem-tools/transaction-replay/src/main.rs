@@ -33,6 +33,10 @@ enum Command {
     /// Replay transactions starting from version `start` to `start + limit`.
     #[structopt(name = "replay-transactions")]
     ReplayTransactions { start: Version, limit: u64 },
+    /// Replay transactions from version `start` to `start + limit` and compare their outputs with
+    /// the committed ones, reporting the first version that diverges.
+    #[structopt(name = "diff-transactions")]
+    DiffTransactions { start: Version, limit: u64 },
     /// Replay the last `txns` committed transactions.
     #[structopt(name = "replay-recent-transactions")]
     ReplayRecentTransactions { txns: u64 },
@@ -58,6 +62,14 @@ enum Command {
         account: AccountAddress,
         version: Option<Version>,
     },
+    /// Export the resources and modules stored under `account` at `version` as annotated JSON,
+    /// which `diem-writeset-generator import-account` turns into a write set re-creating them.
+    #[structopt(name = "export-account")]
+    ExportAccount {
+        #[structopt(parse(try_from_str))]
+        account: AccountAddress,
+        version: Option<Version>,
+    },
     /// Annotate the resources stored under `diem_root`, `treasury_compliance` and all validator addresses.
     #[structopt(name = "annotate-key-accounts")]
     AnnotateKeyAccounts { version: Version },
@@ -107,6 +119,21 @@ fn main() -> Result<()> {
                 debugger.execute_past_transactions(start, limit, opt.save_write_sets)
             );
         }
+        Command::DiffTransactions { start, limit } => {
+            match debugger.diff_past_transactions(start, limit)? {
+                Some((version, divergences)) => {
+                    println!("Replay diverges at version {}:", version);
+                    for divergence in divergences {
+                        println!("  {}", divergence);
+                    }
+                }
+                None => println!(
+                    "Replay matches the committed outputs from version {} to {}",
+                    start,
+                    start + limit
+                ),
+            }
+        }
         Command::ReplayRecentTransactions { txns } => {
             let latest_version = debugger
                 .get_latest_version()
@@ -166,6 +193,19 @@ fn main() -> Result<()> {
                     .expect("Account not found")
             )
         }
+        Command::ExportAccount {
+            account,
+            version: version_opt,
+        } => {
+            let version = match version_opt {
+                Some(v) => v,
+                None => debugger.get_latest_version()?,
+            };
+            let export = debugger
+                .export_account_state_at_version(account, version)?
+                .expect("Account not found");
+            println!("{}", serde_json::to_string_pretty(&export)?)
+        }
         Command::AnnotateEvents { key, start, limit } => {
             debugger.pretty_print_events(
                 &EventKey::from_bytes(hex::decode(key.as_str())?)?,
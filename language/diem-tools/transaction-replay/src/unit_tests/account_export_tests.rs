@@ -0,0 +1,46 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{unit_tests::TestInterface, AccountExport, DiemDebugger};
+use diem_types::{
+    account_config::{diem_root_address, AccountResource},
+    account_state::AccountState,
+    write_set::WriteOp,
+};
+use move_core_types::move_resource::MoveStructType;
+use std::convert::TryFrom;
+
+#[test]
+fn test_export_account() {
+    let interface = TestInterface::genesis();
+    let state = interface.state_db[&(0, diem_root_address())].clone();
+    let debugger = DiemDebugger::new(Box::new(interface));
+
+    let export = debugger
+        .export_account_state_at_version(diem_root_address(), 0)
+        .unwrap()
+        .unwrap();
+    let account_resource = export
+        .entries
+        .iter()
+        .find(|entry| entry.name == AccountResource::struct_tag().to_string())
+        .unwrap();
+    assert_eq!(
+        account_resource.value.as_ref().unwrap()["sequence_number"],
+        serde_json::json!(0)
+    );
+
+    // The exported JSON re-creates the account state as it was
+    let export: AccountExport =
+        serde_json::from_str(&serde_json::to_string(&export).unwrap()).unwrap();
+    let change_set = export.change_set().unwrap();
+    let state = AccountState::try_from(&state).unwrap();
+    assert_eq!(change_set.write_set().iter().count(), state.iter().count());
+    for (access_path, op) in change_set.write_set().iter() {
+        assert_eq!(access_path.address, diem_root_address());
+        match op {
+            WriteOp::Value(value) => assert_eq!(state.get(&access_path.path), Some(value)),
+            WriteOp::Deletion => panic!("Unexpected deletion"),
+        }
+    }
+}
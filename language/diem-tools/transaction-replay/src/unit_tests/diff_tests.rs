@@ -0,0 +1,56 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{diff_output, Divergence};
+use diem_crypto::{hash::EventAccumulatorHasher, HashValue};
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{TransactionInfo, TransactionOutput, TransactionStatus},
+    vm_status::KeptVMStatus,
+    write_set::{WriteOp, WriteSetMut},
+};
+use diem_validator_interface::CommittedOutput;
+
+fn output(value: u8, gas_used: u64) -> TransactionOutput {
+    let write_set = WriteSetMut::new(vec![(
+        AccessPath::new(AccountAddress::ZERO, vec![0]),
+        WriteOp::Value(vec![value]),
+    )])
+    .freeze()
+    .unwrap();
+    TransactionOutput::new(
+        write_set,
+        vec![],
+        gas_used,
+        TransactionStatus::Keep(KeptVMStatus::Executed),
+    )
+}
+
+#[test]
+fn test_diff_output() {
+    let committed = CommittedOutput {
+        info: TransactionInfo::new(
+            HashValue::zero(),
+            HashValue::zero(),
+            InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&[]).root_hash(),
+            5,
+            KeptVMStatus::Executed,
+        ),
+        write_set: output(1, 5).write_set().clone(),
+        events: vec![],
+    };
+    assert!(diff_output(&committed, &output(1, 5)).is_empty());
+
+    let divergences = diff_output(&committed, &output(2, 6));
+    assert_eq!(divergences.len(), 2);
+    assert!(matches!(
+        divergences[0],
+        Divergence::GasUsed {
+            committed: 5,
+            replayed: 6
+        }
+    ));
+    assert!(matches!(divergences[1], Divergence::Write { .. }));
+}
@@ -0,0 +1,30 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{unit_tests::TestInterface, DiemDebugger};
+use diem_types::{chain_id::ChainId, transaction::WriteSetBuilder};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+#[test]
+fn test_dry_run_misplaced_module() {
+    let debugger = DiemDebugger::new(Box::new(TestInterface::genesis()));
+    let module = debugger
+        .get_diem_framework_modules_at_version(0, false)
+        .unwrap()
+        .remove(0);
+    let mut bytes = vec![];
+    module.serialize(&mut bytes).unwrap();
+
+    // Published under an id it doesn't declare, the module is rejected before being executed
+    let module_id = ModuleId::new(
+        *module.self_id().address(),
+        Identifier::new("Misplaced").unwrap(),
+    );
+    let payload = WriteSetBuilder::new(ChainId::test())
+        .publish_module(module_id, bytes)
+        .unwrap()
+        .build()
+        .unwrap();
+    let error = debugger.dry_run_writeset(0, payload).unwrap_err();
+    assert!(error.to_string().contains("is published as"));
+}
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod account_export_tests;
 mod bisection_tests;
+mod diff_tests;
+mod dry_run_tests;
 
 use crate::DiemValidatorInterface;
 use anyhow::{bail, Result};
@@ -14,6 +17,7 @@ use diem_types::{
     transaction::{Transaction, Version, WriteSetPayload},
     write_set::WriteOp,
 };
+use diem_validator_interface::CommittedOutput;
 use std::{collections::HashMap, convert::TryFrom};
 use vm_genesis::{generate_genesis_change_set_for_testing, GenesisOptions};
 
@@ -93,6 +97,10 @@ impl DiemValidatorInterface for TestInterface {
         Ok(result)
     }
 
+    fn get_committed_outputs(&self, _start: Version, _limit: u64) -> Result<Vec<CommittedOutput>> {
+        unimplemented!()
+    }
+
     fn get_latest_version(&self) -> Result<Version> {
         Ok(self.latest_version)
     }
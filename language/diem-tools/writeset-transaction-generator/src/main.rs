@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{bail, format_err, Result};
+use diem_transaction_replay::AccountExport;
 use diem_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
-    transaction::{Transaction, TransactionPayload},
+    transaction::{Transaction, TransactionPayload, WriteSetPayload},
 };
 
 use diem_writeset_generator::{
@@ -83,6 +84,10 @@ enum Command {
     /// Block the execution of any transaction in the network
     #[structopt(name = "halt-network")]
     HaltNetwork,
+    /// Re-create the state of an account exported by `diem-transaction-replay export-account`,
+    /// e.g. to copy a mainnet account to a test network
+    #[structopt(name = "import-account")]
+    ImportAccount { export_file: PathBuf },
     /// Build a custom file in templates into admin script
     #[structopt(name = "build-custom-script")]
     BuildCustomScript {
@@ -212,6 +217,10 @@ fn main() -> Result<()> {
 
         //////// end 0L ////////
         Command::HaltNetwork => encode_halt_network_payload(),
+        Command::ImportAccount { export_file } => {
+            let export: AccountExport = serde_json::from_slice(&std::fs::read(export_file)?)?;
+            WriteSetPayload::Direct(export.change_set()?)
+        }
         Command::BuildCustomScript {
             script_name,
             args,
@@ -24,8 +24,9 @@ use diem_types::{
     block_metadata::BlockMetadata,
     on_chain_config::DIEM_VERSION_3,
     transaction::{
-        ChangeSet, Module, SignatureCheckedTransaction, Transaction, TransactionArgument,
-        TransactionOutput, TransactionPayload, TransactionStatus, WriteSetPayload,
+        ChangeSet, Module, ModuleBundle, SignatureCheckedTransaction, Transaction,
+        TransactionArgument, TransactionOutput, TransactionPayload, TransactionStatus,
+        WriteSetPayload,
     },
     vm_status::{KeptVMStatus, StatusCode, VMStatus},
     write_set::{WriteSet, WriteSetMut},
@@ -225,7 +226,9 @@ impl DiemVM {
                         log_context,
                     )
                 }
-                TransactionPayload::Module(_) | TransactionPayload::WriteSet(_) => {
+                TransactionPayload::Module(_)
+                | TransactionPayload::ModuleBundle(_)
+                | TransactionPayload::WriteSet(_) => {
                     return Err(VMStatus::Error(StatusCode::UNREACHABLE));
                 }
             }
@@ -288,6 +291,57 @@ impl DiemVM {
         )
     }
 
+    fn execute_module_bundle<S: MoveStorage>(
+        &self,
+        mut session: Session<S>,
+        gas_status: &mut GasStatus,
+        txn_data: &TransactionMetadata,
+        modules: &ModuleBundle,
+        account_currency_symbol: &IdentStr,
+        log_context: &impl LogContext,
+    ) -> Result<(VMStatus, TransactionOutput), VMStatus> {
+        fail_point!("move_adapter::execute_module_bundle", |_| {
+            Err(VMStatus::Error(
+                StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR,
+            ))
+        });
+
+        // Publish the modules, all or none of them
+        let module_address = if self.0.publishing_option(log_context)?.is_open_module() {
+            txn_data.sender()
+        } else {
+            account_config::CORE_CODE_ADDRESS
+        };
+
+        gas_status
+            .charge_intrinsic_gas(txn_data.transaction_size())
+            .map_err(|e| e.into_vm_status())?;
+        let result = session
+            .publish_module_bundle(
+                modules.clone().into_inner(),
+                module_address,
+                gas_status,
+                log_context,
+            )
+            .map_err(|e| e.into_vm_status())
+            .and_then(|()| charge_global_write_gas_usage(gas_status, &session, &txn_data.sender()))
+            .and_then(|()| {
+                self.success_transaction_cleanup(
+                    session,
+                    gas_status,
+                    txn_data,
+                    account_currency_symbol,
+                    log_context,
+                )
+            });
+        if result.is_err() {
+            // The modules of the bundle verified so far were loaded into the code cache, but
+            // won't be published.
+            self.0.mark_loader_cache_as_invalid();
+        }
+        result
+    }
+
     fn execute_user_transaction<S: MoveStorage>(
         &self,
         storage: &S,
@@ -342,6 +396,14 @@ impl DiemVM {
                 &account_currency_symbol,
                 log_context,
             ),
+            TransactionPayload::ModuleBundle(modules) => self.execute_module_bundle(
+                session,
+                &mut gas_status,
+                &txn_data,
+                modules,
+                &account_currency_symbol,
+                log_context,
+            ),
             TransactionPayload::WriteSet(_) => {
                 return discard_error_vm_status(VMStatus::Error(StatusCode::UNREACHABLE))
             }
@@ -587,6 +649,7 @@ impl DiemVM {
             match txn.payload() {
                 TransactionPayload::WriteSet(writeset_payload) => writeset_payload,
                 TransactionPayload::Module(_)
+                | TransactionPayload::ModuleBundle(_)
                 | TransactionPayload::Script(_)
                 | TransactionPayload::ScriptFunction(_) => {
                     log_context.alert();
@@ -10,7 +10,10 @@ use diem_state_view::StateView;
 use diem_types::{
     account_address::AccountAddress,
     account_config::{self, CurrencyInfoResource, RoleId},
-    on_chain_config::{DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3},
+    on_chain_config::{
+        DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+        DIEM_VERSION_5, DIEM_VERSION_6,
+    },
     transaction::{
         GovernanceRole, SignatureCheckedTransaction, SignedTransaction, TransactionPayload,
         VMValidatorResult,
@@ -49,6 +52,12 @@ impl DiemVMValidator {
             publishing_option,
         ))
     }
+
+    /// The VM config, holding the gas schedule, transactions are validated against. `None` if it
+    /// wasn't found on chain.
+    pub fn vm_config(&self) -> Option<&VMConfig> {
+        self.0.get_vm_config()
+    }
 }
 
 // VMValidator external API
@@ -133,6 +142,28 @@ pub(crate) fn validate_signature_checked_transaction<S: MoveStorage>(
         return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
     }
 
+    // Signatures of the schemes beyond Ed25519 are accepted once every validator runs a release
+    // verifying them.
+    if transaction.authenticator().has_generic_signatures()
+        && vm.get_diem_version()? < DIEM_VERSION_5
+    {
+        return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
+    }
+
+    if transaction.fee_payer().is_some() {
+        // Fee payer transactions are not allowed under this version
+        if vm.get_diem_version()? < DIEM_VERSION_6 {
+            return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
+        }
+        // Only the script prologue checks that the fee payer can pay for the gas.
+        if !matches!(
+            transaction.payload(),
+            TransactionPayload::Script(_) | TransactionPayload::ScriptFunction(_)
+        ) {
+            return Err(VMStatus::Error(StatusCode::MALFORMED));
+        }
+    }
+
     if transaction.contains_duplicate_signers() {
         return Err(VMStatus::Error(StatusCode::SIGNERS_CONTAIN_DUPLICATES));
     }
@@ -172,6 +203,18 @@ pub(crate) fn validate_signature_checked_transaction<S: MoveStorage>(
             vm.check_gas(&txn_data, log_context)?;
             vm.run_module_prologue(&mut session, &txn_data, &currency_code, log_context)
         }
+        TransactionPayload::ModuleBundle(modules) => {
+            // gate the behavior until the Diem version is ready
+            if vm.get_diem_version()? < DIEM_VERSION_4 {
+                return Err(VMStatus::Error(StatusCode::FEATURE_UNDER_GATING));
+            }
+            if modules.is_empty() {
+                return Err(VMStatus::Error(StatusCode::MALFORMED));
+            }
+            // NOTE: Module and ModuleBundle shares the same prologue
+            vm.check_gas(&txn_data, log_context)?;
+            vm.run_module_prologue(&mut session, &txn_data, &currency_code, log_context)
+        }
         TransactionPayload::WriteSet(_cs) => {
             vm.run_writeset_prologue(&mut session, &txn_data, log_context)
         }
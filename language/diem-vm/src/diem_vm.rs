@@ -19,6 +19,7 @@ use diem_types::{
     event::EventKey, 
     on_chain_config::{
         ConfigStorage, DiemVersion, OnChainConfig, VMConfig, VMPublishingOption, DIEM_VERSION_3,
+        DIEM_VERSION_6,
     }, 
     transaction::{TransactionOutput, TransactionStatus}, 
     ol_upgrade_payload::UpgradePayloadResource, 
@@ -118,6 +119,10 @@ impl DiemVMImpl {
             })
     }
 
+    pub(crate) fn get_vm_config(&self) -> Option<&VMConfig> {
+        self.on_chain_config.as_ref()
+    }
+
     pub fn get_diem_version(&self) -> Result<DiemVersion, VMStatus> {
         self.version.clone().ok_or_else(|| {
             CRITICAL_ERRORS.inc();
@@ -209,8 +214,9 @@ impl DiemVMImpl {
         Ok(())
     }
 
-    /// Run the prologue of a transaction by calling into either `SCRIPT_PROLOGUE_NAME` function
-    /// or `MULTI_AGENT_SCRIPT_PROLOGUE_NAME` function stored in the `ACCOUNT_MODULE` on chain.
+    /// Run the prologue of a transaction by calling into either `SCRIPT_PROLOGUE_NAME` function,
+    /// `MULTI_AGENT_SCRIPT_PROLOGUE_NAME` function or `FEE_PAYER_SCRIPT_PROLOGUE_NAME` function
+    /// stored in the `ACCOUNT_MODULE` on chain.
     pub(crate) fn run_script_prologue<S: MoveStorage>(
         &self,
         session: &mut Session<S>,
@@ -234,7 +240,30 @@ impl DiemVMImpl {
                 MoveValue::vector_u8(HashValue::sha3_256_of(&preimage.to_vec()).to_vec())
             })
             .collect();
-        let args = if self.get_diem_version()? >= DIEM_VERSION_3 && txn_data.is_multi_agent() {
+        let fee_payer = match (
+            txn_data.fee_payer(),
+            &txn_data.fee_payer_authentication_key_preimage,
+        ) {
+            (Some(address), Some(preimage)) if self.get_diem_version()? >= DIEM_VERSION_6 => {
+                Some((address, HashValue::sha3_256_of(preimage).to_vec()))
+            }
+            _ => None,
+        };
+        let is_fee_payer = fee_payer.is_some();
+        let args = if let Some((fee_payer_address, fee_payer_public_key_hash)) = fee_payer {
+            vec![
+                MoveValue::Signer(txn_data.sender),
+                MoveValue::U64(txn_sequence_number),
+                MoveValue::vector_u8(txn_public_key),
+                MoveValue::Address(fee_payer_address),
+                MoveValue::vector_u8(fee_payer_public_key_hash),
+                MoveValue::U64(txn_gas_price),
+                MoveValue::U64(txn_max_gas_units),
+                MoveValue::U64(txn_expiration_timestamp_secs),
+                MoveValue::U8(chain_id.id()),
+                MoveValue::vector_u8(txn_data.script_hash.clone()),
+            ]
+        } else if self.get_diem_version()? >= DIEM_VERSION_3 && txn_data.is_multi_agent() {
             vec![
                 MoveValue::Signer(txn_data.sender),
                 MoveValue::U64(txn_sequence_number),
@@ -258,12 +287,13 @@ impl DiemVMImpl {
                 MoveValue::vector_u8(txn_data.script_hash.clone()),
             ]
         };
-        let prologue_function_name =
-            if self.get_diem_version()? >= DIEM_VERSION_3 && txn_data.is_multi_agent() {
-                &MULTI_AGENT_SCRIPT_PROLOGUE_NAME
-            } else {
-                &SCRIPT_PROLOGUE_NAME
-            };
+        let prologue_function_name = if is_fee_payer {
+            &FEE_PAYER_SCRIPT_PROLOGUE_NAME
+        } else if self.get_diem_version()? >= DIEM_VERSION_3 && txn_data.is_multi_agent() {
+            &MULTI_AGENT_SCRIPT_PROLOGUE_NAME
+        } else {
+            &SCRIPT_PROLOGUE_NAME
+        };
         session
             .execute_function(
                 &account_config::ACCOUNT_MODULE,
@@ -318,8 +348,9 @@ impl DiemVMImpl {
             .or_else(|err| convert_prologue_error(err, log_context))
     }
 
-    /// Run the epilogue of a transaction by calling into `EPILOGUE_NAME` function stored
-    /// in the `ACCOUNT_MODULE` on chain.
+    /// Run the epilogue of a transaction by calling into `USER_EPILOGUE_NAME` function, or
+    /// `FEE_PAYER_EPILOGUE_NAME` function for fee payer transactions, stored in the
+    /// `ACCOUNT_MODULE` on chain.
     pub(crate) fn run_success_epilogue<S: MoveStorage>(
         &self,
         session: &mut Session<S>,
@@ -336,22 +367,13 @@ impl DiemVMImpl {
 
         let gas_currency_ty =
             account_config::type_tag_for_currency_code(account_currency_symbol.to_owned());
-        let txn_sequence_number = txn_data.sequence_number();
-        let txn_gas_price = txn_data.gas_unit_price().get();
-        let txn_max_gas_units = txn_data.max_gas_amount().get();
-        let gas_remaining = gas_status.remaining_gas().get();
+        let (epilogue_function_name, args) = user_epilogue_args(txn_data, gas_status);
         session
             .execute_function(
                 &account_config::ACCOUNT_MODULE,
-                &USER_EPILOGUE_NAME,
+                epilogue_function_name,
                 vec![gas_currency_ty],
-                serialize_values(&vec![
-                    MoveValue::Signer(txn_data.sender),
-                    MoveValue::U64(txn_sequence_number),
-                    MoveValue::U64(txn_gas_price),
-                    MoveValue::U64(txn_max_gas_units),
-                    MoveValue::U64(gas_remaining),
-                ]),
+                serialize_values(&args),
                 gas_status,
                 log_context,
             )
@@ -360,8 +382,9 @@ impl DiemVMImpl {
             .or_else(|err| convert_epilogue_error(err, log_context))
     }
 
-    /// Run the failure epilogue of a transaction by calling into `USER_EPILOGUE_NAME` function
-    /// stored in the `ACCOUNT_MODULE` on chain.
+    /// Run the failure epilogue of a transaction by calling into `USER_EPILOGUE_NAME` function,
+    /// or `FEE_PAYER_EPILOGUE_NAME` function for fee payer transactions, stored in the
+    /// `ACCOUNT_MODULE` on chain.
     pub(crate) fn run_failure_epilogue<S: MoveStorage>(
         &self,
         session: &mut Session<S>,
@@ -372,29 +395,20 @@ impl DiemVMImpl {
     ) -> Result<(), VMStatus> {
         let gas_currency_ty =
             account_config::type_tag_for_currency_code(account_currency_symbol.to_owned());
-        let txn_sequence_number = txn_data.sequence_number();
-        let txn_gas_price = txn_data.gas_unit_price().get();
-        let txn_max_gas_units = txn_data.max_gas_amount().get();
-        let gas_remaining = gas_status.remaining_gas().get();
+        let (epilogue_function_name, args) = user_epilogue_args(txn_data, gas_status);
         session
             .execute_function(
                 &account_config::ACCOUNT_MODULE,
-                &USER_EPILOGUE_NAME,
+                epilogue_function_name,
                 vec![gas_currency_ty],
-                serialize_values(&vec![
-                    MoveValue::Signer(txn_data.sender),
-                    MoveValue::U64(txn_sequence_number),
-                    MoveValue::U64(txn_gas_price),
-                    MoveValue::U64(txn_max_gas_units),
-                    MoveValue::U64(gas_remaining),
-                ]),
+                serialize_values(&args),
                 gas_status,
                 log_context,
             )
             .map(|_return_vals| ())
             .map_err(|err| expect_no_verification_errors(err, log_context))
             .or_else(|e| {
-                expect_only_successful_execution(e, USER_EPILOGUE_NAME.as_str(), log_context)
+                expect_only_successful_execution(e, epilogue_function_name.as_str(), log_context)
             })
     }
 
@@ -463,9 +477,17 @@ impl DiemVMImpl {
     }
 
     pub fn new_session<'r, R: MoveStorage>(&self, r: &'r R) -> Session<'r, '_, R> {
+        // Drop the modules of a module bundle that failed to publish, if any.
+        self.move_vm.flush_loader_cache_if_invalidated();
         self.move_vm.new_session(r)
     }
 
+    /// Flags the code cache for flushing before the next session, see
+    /// `Session::publish_module_bundle`.
+    pub(crate) fn mark_loader_cache_as_invalid(&self) {
+        self.move_vm.mark_loader_cache_as_invalid()
+    }
+
     //////// 0L ////////    
     // Note: currently the upgrade needs two blocks to happen: 
     // In the first block, consensus is reached and recorded; 
@@ -568,6 +590,40 @@ impl DiemVMImpl {
       }
 }
 
+/// Returns the epilogue to run at the end of the user transaction `txn_data`, and its arguments.
+fn user_epilogue_args(
+    txn_data: &TransactionMetadata,
+    gas_status: &GasStatus,
+) -> (&'static IdentStr, Vec<MoveValue>) {
+    let txn_sequence_number = txn_data.sequence_number();
+    let txn_gas_price = txn_data.gas_unit_price().get();
+    let txn_max_gas_units = txn_data.max_gas_amount().get();
+    let gas_remaining = gas_status.remaining_gas().get();
+    match txn_data.fee_payer() {
+        Some(fee_payer_address) => (
+            FEE_PAYER_EPILOGUE_NAME,
+            vec![
+                MoveValue::Signer(txn_data.sender),
+                MoveValue::Address(fee_payer_address),
+                MoveValue::U64(txn_sequence_number),
+                MoveValue::U64(txn_gas_price),
+                MoveValue::U64(txn_max_gas_units),
+                MoveValue::U64(gas_remaining),
+            ],
+        ),
+        None => (
+            USER_EPILOGUE_NAME,
+            vec![
+                MoveValue::Signer(txn_data.sender),
+                MoveValue::U64(txn_sequence_number),
+                MoveValue::U64(txn_gas_price),
+                MoveValue::U64(txn_max_gas_units),
+                MoveValue::U64(gas_remaining),
+            ],
+        ),
+    }
+}
+
 fn get_upgrade_payload<S: MoveStorage>(
     remote_cache: &S,
 ) -> Result<UpgradePayloadResource, VMStatus> {
@@ -45,8 +45,10 @@ pub static UPGRADE_RECONFIG: Lazy<Identifier> =
 // Names for special functions and structs
 pub const SCRIPT_PROLOGUE_NAME: &IdentStr = ident_str!("script_prologue");
 pub const MULTI_AGENT_SCRIPT_PROLOGUE_NAME: &IdentStr = ident_str!("multi_agent_script_prologue");
+pub const FEE_PAYER_SCRIPT_PROLOGUE_NAME: &IdentStr = ident_str!("fee_payer_script_prologue");
 pub const MODULE_PROLOGUE_NAME: &IdentStr = ident_str!("module_prologue");
 pub const WRITESET_PROLOGUE_NAME: &IdentStr = ident_str!("writeset_prologue");
 pub const WRITESET_EPILOGUE_NAME: &IdentStr = ident_str!("writeset_epilogue");
 pub const USER_EPILOGUE_NAME: &IdentStr = ident_str!("epilogue");
+pub const FEE_PAYER_EPILOGUE_NAME: &IdentStr = ident_str!("fee_payer_epilogue");
 pub const BLOCK_PROLOGUE: &IdentStr = ident_str!("block_prologue");
@@ -19,6 +19,8 @@ pub struct TransactionMetadata {
     pub authentication_key_preimage: Vec<u8>,
     pub secondary_signers: Vec<AccountAddress>,
     pub secondary_authentication_key_preimages: Vec<Vec<u8>>,
+    pub fee_payer: Option<AccountAddress>,
+    pub fee_payer_authentication_key_preimage: Option<Vec<u8>>,
     pub sequence_number: u64,
     pub max_gas_amount: GasUnits<GasCarrier>,
     pub gas_unit_price: GasPrice<GasCarrier>,
@@ -44,6 +46,11 @@ impl TransactionMetadata {
                 .iter()
                 .map(|account_auth| account_auth.authentication_key_preimage().into_vec())
                 .collect(),
+            fee_payer: txn.authenticator().fee_payer_address(),
+            fee_payer_authentication_key_preimage: txn
+                .authenticator()
+                .fee_payer_signer()
+                .map(|account_auth| account_auth.authentication_key_preimage().into_vec()),
             sequence_number: txn.sequence_number(),
             max_gas_amount: GasUnits::new(txn.max_gas_amount()),
             gas_unit_price: GasPrice::new(txn.gas_unit_price()),
@@ -54,6 +61,7 @@ impl TransactionMetadata {
                 TransactionPayload::Script(s) => HashValue::sha3_256_of(s.code()).to_vec(),
                 TransactionPayload::ScriptFunction(_) => vec![],
                 TransactionPayload::Module(_) => vec![],
+                TransactionPayload::ModuleBundle(_) => vec![],
                 TransactionPayload::WriteSet(_) => vec![],
             },
         }
@@ -98,6 +106,10 @@ impl TransactionMetadata {
     pub fn is_multi_agent(&self) -> bool {
         !self.secondary_signers.is_empty()
     }
+
+    pub fn fee_payer(&self) -> Option<AccountAddress> {
+        self.fee_payer
+    }
 }
 
 impl Default for TransactionMetadata {
@@ -110,6 +122,8 @@ impl Default for TransactionMetadata {
             authentication_key_preimage: AuthenticationKeyPreimage::ed25519(&public_key).into_vec(),
             secondary_signers: vec![],
             secondary_authentication_key_preimages: vec![],
+            fee_payer: None,
+            fee_payer_authentication_key_preimage: None,
             sequence_number: 0,
             max_gas_amount: GasUnits::new(100_000_000),
             gas_unit_price: GasPrice::new(0),
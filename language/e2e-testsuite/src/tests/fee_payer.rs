@@ -0,0 +1,293 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests for fee payer transactions, whose gas is paid by a fee payer rather than by their sender.
+//!
+//! The released genesis predates the fee payer prologue and epilogue, so most of these tests run
+//! against a genesis built from the framework sources; `fee_payer_current_release` upgrades the
+//! released genesis to the compiled release instead.
+
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use diem_framework_releases::legacy::transaction_scripts::LegacyStdlibScript;
+use diem_types::{
+    account_config,
+    on_chain_config::DIEM_VERSION_6,
+    transaction::{Module, Script, TransactionArgument, TransactionStatus},
+    vm_status::{KeptVMStatus, StatusCode},
+};
+use language_e2e_tests::{
+    account::{self, Account},
+    assert_prologue_parity,
+    executor::FakeExecutor,
+    transaction_status_eq, utils,
+};
+
+fn peer_to_peer_script(receiver: &Account, transfer_amount: u64) -> Script {
+    Script::new(
+        LegacyStdlibScript::PeerToPeerWithMetadata
+            .compiled_bytes()
+            .into_vec(),
+        vec![account_config::xus_tag()],
+        vec![
+            TransactionArgument::Address(*receiver.address()),
+            TransactionArgument::U64(transfer_amount),
+            TransactionArgument::U8Vector(vec![]),
+            TransactionArgument::U8Vector(vec![]),
+        ],
+    )
+}
+
+#[test]
+fn fee_payer_p2p() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    // The sender transfers its whole balance, and has nothing left for the gas.
+    let sender = executor.create_raw_account_data(1_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .sign_fee_payer(fee_payer.account());
+    assert_eq!(executor.verify_transaction(txn.clone()).status(), None);
+
+    let output = executor.execute_transaction(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    assert!(output.gas_used() > 0);
+    executor.apply_write_set(output.write_set());
+
+    let updated_sender = executor
+        .read_account_resource(sender.account())
+        .expect("sender must exist");
+    let updated_sender_balance = executor
+        .read_balance_resource(sender.account(), account::xus_currency_code())
+        .expect("sender balance must exist");
+    let updated_fee_payer = executor
+        .read_account_resource(fee_payer.account())
+        .expect("fee payer must exist");
+    let updated_fee_payer_balance = executor
+        .read_balance_resource(fee_payer.account(), account::xus_currency_code())
+        .expect("fee payer balance must exist");
+    assert_eq!(0, updated_sender_balance.coin());
+    assert_eq!(11, updated_sender.sequence_number());
+    assert_eq!(
+        1_000_000 - output.gas_used(),
+        updated_fee_payer_balance.coin()
+    );
+    assert_eq!(10, updated_fee_payer.sequence_number());
+}
+
+#[test]
+fn fee_payer_failed_script_charges_fee_payer() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    let sender = executor.create_raw_account_data(1_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    // The transfer aborts, as it's above the balance of the sender.
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 2_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .sign_fee_payer(fee_payer.account());
+    let output = executor.execute_transaction(txn);
+    assert!(matches!(
+        output.status(),
+        TransactionStatus::Keep(KeptVMStatus::MoveAbort(_, _))
+    ));
+    executor.apply_write_set(output.write_set());
+
+    let updated_sender = executor
+        .read_account_resource(sender.account())
+        .expect("sender must exist");
+    let updated_sender_balance = executor
+        .read_balance_resource(sender.account(), account::xus_currency_code())
+        .expect("sender balance must exist");
+    let updated_fee_payer_balance = executor
+        .read_balance_resource(fee_payer.account(), account::xus_currency_code())
+        .expect("fee payer balance must exist");
+    assert_eq!(1_000, updated_sender_balance.coin());
+    assert_eq!(11, updated_sender.sequence_number());
+    assert_eq!(
+        1_000_000 - output.gas_used(),
+        updated_fee_payer_balance.coin()
+    );
+}
+
+#[test]
+fn fee_payer_invalid_auth_key() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    let sender = executor.create_raw_account_data(1_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    // The fee payer signature is valid, but for a key other than the one of the fee payer.
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let other_fee_payer = Account::with_keypair(private_key.clone(), private_key.public_key());
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .raw()
+        .sign_fee_payer(
+            &sender.account().privkey,
+            *fee_payer.address(),
+            &private_key,
+        )
+        .unwrap()
+        .into_inner();
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::INVALID_AUTH_KEY
+    );
+
+    // Nor can the transaction be sponsored by an account which doesn't exist.
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .sign_fee_payer(&other_fee_payer);
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::SENDING_ACCOUNT_DOES_NOT_EXIST
+    );
+}
+
+#[test]
+fn fee_payer_insufficient_balance() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    // The sender could pay for the gas, but the fee payer can't.
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .sign_fee_payer(fee_payer.account());
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE
+    );
+}
+
+#[test]
+fn fee_payer_module_rejected() {
+    let mut executor = FakeExecutor::from_fresh_genesis();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&fee_payer);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .module(Module::new(vec![]))
+        .sequence_number(10)
+        .sign_fee_payer(fee_payer.account());
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::MALFORMED
+    );
+}
+
+#[test]
+fn fee_payer_gating() {
+    // DiemVersion 6 isn't enabled by the released framework.
+    let (mut executor, _, _, _) = utils::start_with_released_df();
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .sign_fee_payer(fee_payer.account());
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::FEATURE_UNDER_GATING
+    );
+}
+
+#[test]
+fn fee_payer_current_release() {
+    let (mut executor, dr_account, _, _) = utils::start_with_released_df();
+    let mut dr_seqno = 1;
+    utils::upgrade_df(
+        &mut executor,
+        &dr_account,
+        &mut dr_seqno,
+        Some(DIEM_VERSION_6.major),
+    );
+    let sender = executor.create_raw_account_data(1_000, 10);
+    let receiver = executor.create_raw_account_data(100_000, 10);
+    let fee_payer = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+    executor.add_account_data(&fee_payer);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .script(peer_to_peer_script(receiver.account(), 1_000))
+        .sequence_number(10)
+        .gas_unit_price(1)
+        .sign_fee_payer(fee_payer.account());
+    assert_eq!(executor.verify_transaction(txn.clone()).status(), None);
+
+    let output = executor.execute_transaction(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    executor.apply_write_set(output.write_set());
+
+    let updated_sender = executor
+        .read_account_resource(sender.account())
+        .expect("sender must exist");
+    let updated_fee_payer_balance = executor
+        .read_balance_resource(fee_payer.account(), account::xus_currency_code())
+        .expect("fee payer balance must exist");
+    assert_eq!(11, updated_sender.sequence_number());
+    assert_eq!(
+        1_000_000 - output.gas_used(),
+        updated_fee_payer_balance.coin()
+    );
+}
@@ -17,6 +17,7 @@ mod data_store;
 mod emergency_admin_script;
 mod execution_strategies;
 mod failed_transaction_tests;
+mod fee_payer;
 mod genesis;
 mod genesis_initializations;
 mod mint;
@@ -1,10 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use compiler::Compiler;
 use diem_types::{
+    access_path::Path,
     account_config::{self},
     on_chain_config::VMPublishingOption,
-    transaction::TransactionStatus,
+    transaction::{ModuleBundle, TransactionOutput, TransactionStatus},
     vm_status::{KeptVMStatus, StatusCode},
 };
 use language_e2e_tests::{
@@ -452,3 +454,137 @@ pub fn test_publishing_allow_modules() {
         &TransactionStatus::Keep(KeptVMStatus::Executed)
     );
 }
+
+fn published_code_count(output: &TransactionOutput) -> usize {
+    output
+        .write_set()
+        .iter()
+        .filter(|(access_path, _)| matches!(access_path.get_path(), Path::Code(_)))
+        .count()
+}
+
+#[test]
+pub fn test_publish_module_bundle() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let (module_a, blob_a) = compile_module_with_address(
+        sender.address(),
+        "file_name",
+        "
+        module A {
+            public f() { return; }
+        }
+        ",
+    );
+    let program_b = format!(
+        "
+        module B {{
+            import 0x{}.A;
+            public g() {{ A.f(); return; }}
+        }}
+        ",
+        sender.address(),
+    );
+    let blob_b = Compiler {
+        address: *sender.address(),
+        skip_stdlib_deps: false,
+        extra_deps: vec![module_a],
+    }
+    .into_module_blob("file_name", &program_b)
+    .unwrap();
+
+    // B depends on A, which is published before it in the same transaction.
+    let txn = sender
+        .account()
+        .transaction()
+        .module_bundle(ModuleBundle::new(vec![blob_a.code().to_vec(), blob_b]))
+        .sequence_number(10)
+        .sign();
+    assert_eq!(executor.verify_transaction(txn.clone()).status(), None);
+    let output = executor.execute_transaction(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    assert_eq!(published_code_count(&output), 2);
+}
+
+#[test]
+pub fn test_publish_module_bundle_atomic() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let (_, blob_a) = compile_module_with_address(
+        sender.address(),
+        "file_name",
+        "
+        module A {
+            public f() { return; }
+        }
+        ",
+    );
+    let (missing, _) = compile_module_with_address(
+        sender.address(),
+        "file_name",
+        "
+        module Missing {
+            public f() { return; }
+        }
+        ",
+    );
+    let program_b = format!(
+        "
+        module B {{
+            import 0x{}.Missing;
+            public g() {{ Missing.f(); return; }}
+        }}
+        ",
+        sender.address(),
+    );
+    let blob_b = Compiler {
+        address: *sender.address(),
+        skip_stdlib_deps: false,
+        extra_deps: vec![missing],
+    }
+    .into_module_blob("file_name", &program_b)
+    .unwrap();
+
+    // B fails to link, so A is not published either.
+    let txn = sender
+        .account()
+        .transaction()
+        .module_bundle(ModuleBundle::new(vec![blob_a.code().to_vec(), blob_b]))
+        .sequence_number(10)
+        .sign();
+    let output = executor.execute_transaction(txn);
+    assert_ne!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    assert_eq!(published_code_count(&output), 0);
+}
+
+#[test]
+pub fn test_publish_empty_module_bundle() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+
+    let sender = executor.create_raw_account_data(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let txn = sender
+        .account()
+        .transaction()
+        .module_bundle(ModuleBundle::new(vec![]))
+        .sequence_number(10)
+        .sign();
+    assert_prologue_parity!(
+        executor.verify_transaction(txn.clone()).status(),
+        executor.execute_transaction(txn).status(),
+        StatusCode::MALFORMED
+    );
+}
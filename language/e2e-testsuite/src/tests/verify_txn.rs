@@ -5,6 +5,7 @@ use compiler::Compiler;
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     multi_ed25519::{MultiEd25519PrivateKey, MultiEd25519PublicKey},
+    secp256k1::Secp256k1PrivateKey,
     PrivateKey, SigningKey, Uniform,
 };
 use diem_framework_releases::legacy::transaction_scripts::LegacyStdlibScript;
@@ -15,10 +16,10 @@ use diem_types::{
     account_address::AccountAddress,
     account_config,
     chain_id::ChainId,
-    on_chain_config::VMPublishingOption,
+    on_chain_config::{VMPublishingOption, DIEM_VERSION_5},
     test_helpers::transaction_test_helpers,
     transaction::{
-        authenticator::{AccountAuthenticator, AuthenticationKey, MAX_NUM_OF_SIGS},
+        authenticator::{AccountAuthenticator, AuthenticationKey, Scheme, MAX_NUM_OF_SIGS},
         RawTransactionWithData, Script, SignedTransaction, TransactionArgument, TransactionStatus,
         WriteSetPayload,
     },
@@ -28,14 +29,15 @@ use language_e2e_tests::{
     account::Account,
     assert_prologue_disparity, assert_prologue_parity,
     common_transactions::{
-        multi_agent_mint_script, multi_agent_swap_script, raw_multi_agent_swap_txn, rotate_key_txn,
+        multi_agent_mint_script, multi_agent_swap_script, raw_multi_agent_swap_txn,
+        raw_rotate_key_txn, rotate_key_txn,
     },
     compile::compile_module_with_address,
     current_function_name,
     executor::FakeExecutor,
     gas_costs,
     keygen::KeyGen,
-    test_with_different_versions, transaction_status_eq,
+    test_with_different_versions, transaction_status_eq, utils,
     versioning::CURRENT_RELEASE_VERSIONS,
 };
 use move_binary_format::file_format::CompiledModule;
@@ -78,6 +80,107 @@ fn verify_signature() {
     }
 }
 
+#[test]
+fn verify_secp256k1_signature_gating() {
+    let (mut executor, dr_account, _, _) = utils::start_with_released_df();
+    let mut dr_seqno = 1;
+    let sender = executor.create_raw_account_data(900_000, 0);
+    executor.add_account_data(&sender);
+    let private_key = Secp256k1PrivateKey::generate_for_testing();
+    let program = encode_peer_to_peer_with_metadata_script(
+        account_config::xus_tag(),
+        *sender.address(),
+        100,
+        vec![],
+        vec![],
+    );
+    let signed_txn = sender
+        .account()
+        .transaction()
+        .script(program)
+        .sequence_number(0)
+        .raw()
+        .sign_with(&private_key)
+        .unwrap()
+        .into_inner();
+
+    assert_prologue_parity!(
+        executor.verify_transaction(signed_txn.clone()).status(),
+        executor.execute_transaction(signed_txn.clone()).status(),
+        StatusCode::FEATURE_UNDER_GATING
+    );
+
+    // enable the feature
+    utils::upgrade_df(
+        &mut executor,
+        &dr_account,
+        &mut dr_seqno,
+        Some(DIEM_VERSION_5.major),
+    );
+
+    // The signature is now verified, and the transaction only fails on the authentication key
+    // of the sender, derived from another key.
+    assert_prologue_parity!(
+        executor.verify_transaction(signed_txn.clone()).status(),
+        executor.execute_transaction(signed_txn).status(),
+        StatusCode::INVALID_AUTH_KEY
+    );
+}
+
+#[test]
+fn verify_secp256k1_signature() {
+    let (mut executor, dr_account, _, _) = utils::start_with_released_df();
+    let mut dr_seqno = 1;
+    utils::upgrade_df(
+        &mut executor,
+        &dr_account,
+        &mut dr_seqno,
+        Some(DIEM_VERSION_5.major),
+    );
+    let sender = executor.create_raw_account_data(900_000, 0);
+    executor.add_account_data(&sender);
+
+    // Rotate the sender's authentication key to one derived from a secp256k1 key.
+    let private_key = Secp256k1PrivateKey::generate_for_testing();
+    let new_auth_key = AuthenticationKey::secp256k1(&private_key.public_key());
+    let output =
+        executor.execute_transaction(rotate_key_txn(sender.account(), new_auth_key.to_vec(), 0));
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed),
+    );
+    executor.apply_write_set(output.write_set());
+
+    // A transaction signed with the secp256k1 key is now kept and executed.
+    let signed_txn = raw_rotate_key_txn(sender.account(), new_auth_key.to_vec(), 1)
+        .sign_with(&private_key)
+        .unwrap()
+        .into_inner();
+    assert!(matches!(
+        signed_txn.authenticator().sender(),
+        AccountAuthenticator::Generic {
+            scheme: Scheme::Secp256k1,
+            ..
+        }
+    ));
+    assert_eq!(
+        executor.verify_transaction(signed_txn.clone()).status(),
+        None
+    );
+    let output = executor.execute_transaction(signed_txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed),
+    );
+    executor.apply_write_set(output.write_set());
+
+    let updated_sender = executor
+        .read_account_resource(sender.account())
+        .expect("sender must exist");
+    assert_eq!(updated_sender.sequence_number(), 2);
+    assert_eq!(updated_sender.authentication_key(), new_auth_key.to_vec());
+}
+
 #[test]
 fn verify_multi_agent() {
     let mut executor = FakeExecutor::from_genesis_file();
@@ -17,7 +17,7 @@ mod interpreter;
 mod loader;
 pub mod logging;
 pub mod move_vm;
-mod native_functions;
+pub mod native_functions;
 mod runtime;
 pub mod session;
 #[macro_use]
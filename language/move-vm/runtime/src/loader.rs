@@ -3,7 +3,7 @@
 
 use crate::{
     logging::{expect_no_verification_errors, LogContext},
-    native_functions::NativeFunction,
+    native_functions::{NativeFunction, NativeFunctionRegistry},
 };
 use bytecode_verifier::{self, cyclic_dependencies, dependencies, script_signature};
 use diem_crypto::HashValue;
@@ -107,14 +107,16 @@ pub struct ModuleCache {
     modules: BinaryCache<ModuleId, Module>,
     structs: Vec<Arc<StructType>>,
     functions: Vec<Arc<Function>>,
+    natives: Arc<NativeFunctionRegistry>,
 }
 
 impl ModuleCache {
-    fn new() -> Self {
+    fn new(natives: Arc<NativeFunctionRegistry>) -> Self {
         Self {
             modules: BinaryCache::new(),
             structs: vec![],
             functions: vec![],
+            natives,
         }
     }
 
@@ -188,7 +190,7 @@ impl ModuleCache {
             })?;
         for (idx, func) in module.function_defs().iter().enumerate() {
             let findex = FunctionDefinitionIndex(idx as TableIndex);
-            let function = Function::new(findex, func, module);
+            let function = Function::new(findex, func, module, &self.natives);
             self.functions.push(Arc::new(function));
         }
         Ok(())
@@ -419,14 +421,40 @@ pub(crate) struct Loader {
     scripts: RwLock<ScriptCache>,
     module_cache: RwLock<ModuleCache>,
     type_cache: RwLock<TypeCache>,
+    natives: Arc<NativeFunctionRegistry>,
+    // Whether the caches may hold modules that are not in storage, see `mark_as_invalid()`.
+    invalidated: RwLock<bool>,
 }
 
 impl Loader {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(natives: NativeFunctionRegistry) -> Self {
+        let natives = Arc::new(natives);
         Self {
             scripts: RwLock::new(ScriptCache::new()),
-            module_cache: RwLock::new(ModuleCache::new()),
+            module_cache: RwLock::new(ModuleCache::new(Arc::clone(&natives))),
             type_cache: RwLock::new(TypeCache::new()),
+            natives,
+            invalidated: RwLock::new(false),
+        }
+    }
+
+    // Flags the caches as possibly holding modules that never made it to storage, e.g. the modules
+    // of a bundle whose publishing failed, which were loaded to verify the modules depending on
+    // them.
+    pub(crate) fn mark_as_invalid(&self) {
+        *self.invalidated.write() = true;
+    }
+
+    // Empties the caches if they were flagged by `mark_as_invalid()`, so that modules get loaded
+    // from storage again. Must not be called while a session is alive, as the types of its values
+    // refer to the cached structs.
+    pub(crate) fn flush_if_invalidated(&self) {
+        let mut invalidated = self.invalidated.write();
+        if *invalidated {
+            *self.scripts.write() = ScriptCache::new();
+            *self.module_cache.write() = ModuleCache::new(Arc::clone(&self.natives));
+            *self.type_cache.write() = TypeCache::new();
+            *invalidated = false;
         }
     }
 
@@ -668,7 +696,7 @@ impl Loader {
         log_context: &impl LogContext,
     ) -> VMResult<()> {
         bytecode_verifier::verify_module(&module)?;
-        Self::check_natives(&module)?;
+        self.check_natives(&module)?;
 
         let deps = module.immediate_dependencies();
         let loaded_imm_deps = if verify_no_missing_modules {
@@ -713,8 +741,11 @@ impl Loader {
     }
 
     // All native functions must be known to the loader
-    fn check_natives(module: &CompiledModule) -> VMResult<()> {
-        fn check_natives_impl(module: &CompiledModule) -> PartialVMResult<()> {
+    fn check_natives(&self, module: &CompiledModule) -> VMResult<()> {
+        fn check_natives_impl(
+            natives: &NativeFunctionRegistry,
+            module: &CompiledModule,
+        ) -> PartialVMResult<()> {
             for (idx, native_function) in module
                 .function_defs()
                 .iter()
@@ -724,6 +755,7 @@ impl Loader {
                 let fh = module.function_handle_at(native_function.function);
                 let mh = module.module_handle_at(fh.module);
                 NativeFunction::resolve(
+                    natives,
                     module.address_identifier_at(mh.address),
                     module.identifier_at(mh.name).as_str(),
                     module.identifier_at(fh.name).as_str(),
@@ -749,7 +781,8 @@ impl Loader {
             }
             Ok(())
         }
-        check_natives_impl(module).map_err(|e| e.finish(Location::Module(module.self_id())))
+        check_natives_impl(&self.natives, module)
+            .map_err(|e| e.finish(Location::Module(module.self_id())))
     }
 
     //
@@ -1541,12 +1574,14 @@ impl Function {
         index: FunctionDefinitionIndex,
         def: &FunctionDefinition,
         module: &CompiledModule,
+        natives: &NativeFunctionRegistry,
     ) -> Self {
         let handle = module.function_handle_at(def.function);
         let name = module.identifier_at(handle.name).to_owned();
         let module_id = module.self_id();
         let native = if def.is_native() {
             NativeFunction::resolve(
+                natives,
                 module_id.address(),
                 module_id.name().as_str(),
                 name.as_str(),
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{data_cache::MoveStorage, runtime::VMRuntime, session::Session};
+use crate::{
+    data_cache::MoveStorage, native_functions::NativeFunctionRegistry, runtime::VMRuntime,
+    session::Session,
+};
 
 pub struct MoveVM {
     runtime: VMRuntime,
@@ -9,8 +12,14 @@ pub struct MoveVM {
 
 impl MoveVM {
     pub fn new() -> Self {
+        Self::new_with_natives(NativeFunctionRegistry::default())
+    }
+
+    /// Create a MoveVM that, in addition to the built-in natives, links the natives registered
+    /// in `natives` (subject to the registry's address allowlist).
+    pub fn new_with_natives(natives: NativeFunctionRegistry) -> Self {
         Self {
-            runtime: VMRuntime::new(),
+            runtime: VMRuntime::new(natives),
         }
     }
 
@@ -31,4 +40,17 @@ impl MoveVM {
     pub fn new_session<'r, S: MoveStorage>(&self, remote: &'r S) -> Session<'r, '_, S> {
         self.runtime.new_session(remote)
     }
+
+    /// Flags the code cache as possibly holding modules that are not in storage, after discarding
+    /// a Session that published a module bundle. The cache is flushed by the next call to
+    /// `flush_loader_cache_if_invalidated`.
+    pub fn mark_loader_cache_as_invalid(&self) {
+        self.runtime.loader().mark_as_invalid()
+    }
+
+    /// Flushes the code cache if it was flagged by `mark_loader_cache_as_invalid`. Must only be
+    /// called while no Session is alive.
+    pub fn flush_loader_cache_if_invalidated(&self) {
+        self.runtime.loader().flush_if_invalidated()
+    }
 }
@@ -18,7 +18,10 @@ use move_vm_types::{
     natives::function::{NativeContext, NativeResult},
     values::Value,
 };
-use std::{collections::VecDeque, fmt::Write};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Write},
+};
 
 // The set of native functions the VM supports.
 // The functions can line in any crate linked in but the VM declares them here.
@@ -57,10 +60,13 @@ pub(crate) enum NativeFunction {
     HashKeccak256,
     EthSignatureRecover,
     EthSignatureVerify,
+    // natives registered by the embedder through a `NativeFunctionRegistry`
+    External(ExternalNative),
 }
 
 impl NativeFunction {
     pub(crate) fn resolve(
+        natives: &NativeFunctionRegistry,
         module_address: &AccountAddress,
         module_name: &str,
         function_name: &str,
@@ -99,7 +105,8 @@ impl NativeFunction {
             (&CORE_CODE_ADDRESS, "EthSignature", "recover") => EthSignatureRecover,
             (&CORE_CODE_ADDRESS, "EthSignature", "verify") => EthSignatureVerify,
 
-            _ => return None,
+            // built-in natives always win, so a registry can never shadow them
+            _ => return natives.resolve(module_address, module_name, function_name),
         })
     }
 
@@ -141,6 +148,7 @@ impl NativeFunction {
             Self::HashKeccak256 => ol_hash::native_keccak_256(ctx, t, v),
             Self::EthSignatureRecover => ol_eth_signature::native_eth_signature_recover(ctx, t, v),
             Self::EthSignatureVerify => ol_eth_signature::native_eth_signature_verify(ctx, t, v),
+            Self::External(native) => (native.0)(ctx.cost_table(), t, v),
         };
         debug_assert!(match &result {
             Err(e) => e.major_status().status_type() == StatusType::InvariantViolation,
@@ -150,6 +158,82 @@ impl NativeFunction {
     }
 }
 
+/// Signature of a native function supplied by the embedder.
+///
+/// Unlike the built-in natives, which are generic over `NativeContext`, external natives
+/// only get access to the `CostTable`, which is all a self-contained native (e.g. a
+/// cryptographic check) should need to charge for its execution.
+pub type ExternalNativeFunction =
+    fn(&CostTable, Vec<Type>, VecDeque<Value>) -> PartialVMResult<NativeResult>;
+
+#[derive(Clone, Copy)]
+pub(crate) struct ExternalNative(ExternalNativeFunction);
+
+impl fmt::Debug for ExternalNative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExternalNative({:p})", self.0 as *const ())
+    }
+}
+
+/// Native functions registered by the embedder at VM construction, in addition to the set
+/// the VM declares itself.
+///
+/// A registered native only resolves if its module address has been allowed with
+/// `allow_address`. This lets an embedder register every native it knows about and decide
+/// per network (e.g. from the node config) which of them are actually linked. Built-in
+/// natives always take precedence over registered ones.
+#[derive(Clone, Debug, Default)]
+pub struct NativeFunctionRegistry {
+    allowed_addresses: BTreeSet<AccountAddress>,
+    functions: BTreeMap<(AccountAddress, String, String), ExternalNative>,
+}
+
+impl NativeFunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow natives registered under `address` to be linked by the loader.
+    pub fn allow_address(&mut self, address: AccountAddress) -> &mut Self {
+        self.allowed_addresses.insert(address);
+        self
+    }
+
+    /// Register `function` as the implementation of the native
+    /// `address::module_name::function_name`, replacing any previous registration.
+    pub fn register(
+        &mut self,
+        address: AccountAddress,
+        module_name: &str,
+        function_name: &str,
+        function: ExternalNativeFunction,
+    ) -> &mut Self {
+        self.functions.insert(
+            (address, module_name.to_string(), function_name.to_string()),
+            ExternalNative(function),
+        );
+        self
+    }
+
+    fn resolve(
+        &self,
+        module_address: &AccountAddress,
+        module_name: &str,
+        function_name: &str,
+    ) -> Option<NativeFunction> {
+        if !self.allowed_addresses.contains(module_address) {
+            return None;
+        }
+        self.functions
+            .get(&(
+                *module_address,
+                module_name.to_string(),
+                function_name.to_string(),
+            ))
+            .map(|native| NativeFunction::External(*native))
+    }
+}
+
 pub(crate) struct FunctionContext<'a, L: LogContext> {
     interpreter: &'a mut Interpreter<L>,
     data_store: &'a mut dyn DataStore,
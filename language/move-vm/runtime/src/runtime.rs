@@ -6,6 +6,7 @@ use crate::{
     interpreter::Interpreter,
     loader::Loader,
     logging::LogContext,
+    native_functions::NativeFunctionRegistry,
     session::Session,
 };
 use diem_logger::prelude::*;
@@ -26,6 +27,7 @@ use move_core_types::{
 use move_vm_types::{
     data_store::DataStore, gas_schedule::GasStatus, loaded_data::runtime_types::Type, values::Value,
 };
+use std::collections::BTreeSet;
 
 /// An instantiation of the MoveVM.
 pub(crate) struct VMRuntime {
@@ -41,12 +43,16 @@ fn is_signer_reference(s: &Type) -> bool {
 }
 
 impl VMRuntime {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(natives: NativeFunctionRegistry) -> Self {
         VMRuntime {
-            loader: Loader::new(),
+            loader: Loader::new(natives),
         }
     }
 
+    pub(crate) fn loader(&self) -> &Loader {
+        &self.loader
+    }
+
     pub fn new_session<'r, S: MoveStorage>(&self, remote: &'r S) -> Session<'r, '_, S> {
         Session {
             runtime: self,
@@ -63,9 +69,48 @@ impl VMRuntime {
         _gas_status: &mut GasStatus,
         log_context: &impl LogContext,
     ) -> VMResult<()> {
+        let compiled_module = self.deserialize_module(&module, sender, log_context)?;
+        self.publish_compiled_module(compiled_module, module, data_store, log_context)
+    }
+
+    // See Session::publish_module_bundle for what contracts to follow.
+    pub(crate) fn publish_module_bundle(
+        &self,
+        modules: Vec<Vec<u8>>,
+        sender: AccountAddress,
+        data_store: &mut impl DataStore,
+        _gas_status: &mut GasStatus,
+        log_context: &impl LogContext,
+    ) -> VMResult<()> {
+        // Each module is published to the data cache before the next one is verified, so a module
+        // can depend on the ones before it in the bundle. A module republished by the bundle is
+        // still in the code cache in its old version though, so the modules after it can only
+        // use what the old version offers.
+        let mut module_ids = BTreeSet::new();
+        for module in modules {
+            let compiled_module = self.deserialize_module(&module, sender, log_context)?;
+            if !module_ids.insert(compiled_module.self_id()) {
+                return Err(PartialVMError::new(StatusCode::DUPLICATE_MODULE_NAME)
+                    .with_message(format!(
+                        "{:?} is published twice in the bundle",
+                        compiled_module.self_id()
+                    ))
+                    .finish(Location::Undefined));
+            }
+            self.publish_compiled_module(compiled_module, module, data_store, log_context)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_module(
+        &self,
+        module: &[u8],
+        sender: AccountAddress,
+        log_context: &impl LogContext,
+    ) -> VMResult<CompiledModule> {
         // deserialize the module. Perform bounds check. After this indexes can be
         // used with the `[]` operator
-        let compiled_module = match CompiledModule::deserialize(&module) {
+        let compiled_module = match CompiledModule::deserialize(module) {
             Ok(module) => module,
             Err(err) => {
                 warn!(*log_context, "[VM] module deserialization failed {:?}", err);
@@ -84,7 +129,16 @@ impl VMRuntime {
             )
             .finish(Location::Undefined));
         }
+        Ok(compiled_module)
+    }
 
+    fn publish_compiled_module(
+        &self,
+        compiled_module: CompiledModule,
+        module: Vec<u8>,
+        data_store: &mut impl DataStore,
+        log_context: &impl LogContext,
+    ) -> VMResult<()> {
         let module_id = compiled_module.self_id();
 
         // For now, we assume that all modules can be republished, as long as the new module is
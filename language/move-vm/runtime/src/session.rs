@@ -185,6 +185,32 @@ impl<'r, 'l, S: MoveStorage> Session<'r, 'l, S> {
         )
     }
 
+    /// Publish the given modules, in order, as a bundle.
+    ///
+    /// Each module may only depend on modules already published, either in storage or earlier in
+    /// the bundle. The Move VM MUST return a user error if any module fails to publish, for the
+    /// reasons listed in `publish_module`, or if a module appears twice in the bundle.
+    ///
+    /// The modules of the bundle get loaded into the code cache when the modules depending on them
+    /// are verified. If the effects of the Session are not committed, e.g. because a later module
+    /// failed to publish, the caller must flag the code cache with
+    /// `MoveVM::mark_loader_cache_as_invalid` and flush it before running other Sessions.
+    pub fn publish_module_bundle(
+        &mut self,
+        modules: Vec<Vec<u8>>,
+        sender: AccountAddress,
+        gas_status: &mut GasStatus,
+        log_context: &impl LogContext,
+    ) -> VMResult<()> {
+        self.runtime.publish_module_bundle(
+            modules,
+            sender,
+            &mut self.data_cache,
+            gas_status,
+            log_context,
+        )
+    }
+
     pub fn num_mutated_accounts(&self, sender: &AccountAddress) -> u64 {
         self.data_cache.num_mutated_accounts(sender)
     }
@@ -0,0 +1,54 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::native_functions::{NativeFunction, NativeFunctionRegistry};
+use move_binary_format::errors::PartialVMResult;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{CostTable, InternalGasUnits},
+    language_storage::CORE_CODE_ADDRESS,
+};
+use move_vm_types::{
+    loaded_data::runtime_types::Type, natives::function::NativeResult, values::Value,
+};
+use std::collections::VecDeque;
+
+fn native_abort(
+    _cost_table: &CostTable,
+    _ty_args: Vec<Type>,
+    _args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    Ok(NativeResult::err(InternalGasUnits::new(0), 42))
+}
+
+#[test]
+fn registered_native_requires_allowed_address() {
+    let address = AccountAddress::new([1; AccountAddress::LENGTH]);
+    let mut natives = NativeFunctionRegistry::new();
+    natives.register(address, "Abort", "get", native_abort);
+
+    assert!(NativeFunction::resolve(&natives, &address, "Abort", "get").is_none());
+
+    natives.allow_address(address);
+    assert!(matches!(
+        NativeFunction::resolve(&natives, &address, "Abort", "get"),
+        Some(NativeFunction::External(_))
+    ));
+    assert!(NativeFunction::resolve(&natives, &address, "Abort", "other").is_none());
+}
+
+#[test]
+fn registered_native_cannot_shadow_builtin() {
+    let mut natives = NativeFunctionRegistry::new();
+    natives.allow_address(CORE_CODE_ADDRESS).register(
+        CORE_CODE_ADDRESS,
+        "Hash",
+        "sha2_256",
+        native_abort,
+    );
+
+    assert!(matches!(
+        NativeFunction::resolve(&natives, &CORE_CODE_ADDRESS, "Hash", "sha2_256"),
+        Some(NativeFunction::HashSha2_256)
+    ));
+}
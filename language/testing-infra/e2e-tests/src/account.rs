@@ -16,8 +16,8 @@ use diem_types::{
     chain_id::ChainId,
     event::EventHandle,
     transaction::{
-        authenticator::AuthenticationKey, Module, RawTransaction, Script, ScriptFunction,
-        SignedTransaction, TransactionPayload, WriteSetPayload,
+        authenticator::AuthenticationKey, Module, ModuleBundle, RawTransaction, Script,
+        ScriptFunction, SignedTransaction, TransactionPayload, WriteSetPayload,
     },
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
@@ -266,6 +266,11 @@ impl TransactionBuilder {
         self
     }
 
+    pub fn module_bundle(mut self, modules: ModuleBundle) -> Self {
+        self.program = Some(TransactionPayload::ModuleBundle(modules));
+        self
+    }
+
     pub fn write_set(mut self, w: WriteSetPayload) -> Self {
         self.program = Some(TransactionPayload::WriteSet(w));
         self
@@ -352,6 +357,27 @@ impl TransactionBuilder {
         .unwrap()
         .into_inner()
     }
+
+    pub fn sign_fee_payer(self, fee_payer: &Account) -> SignedTransaction {
+        RawTransaction::new(
+            *self.sender.address(),
+            self.sequence_number.expect("sequence number not set"),
+            self.program.expect("transaction payload not set"),
+            self.max_gas_amount.unwrap_or(gas_costs::TXN_RESERVED),
+            self.gas_unit_price.unwrap_or(0),
+            self.gas_currency_code
+                .unwrap_or_else(|| XUS_NAME.to_owned()),
+            self.ttl.unwrap_or(DEFAULT_EXPIRATION_TIME),
+            ChainId::test(),
+        )
+        .sign_fee_payer(
+            &self.sender.privkey,
+            *fee_payer.address(),
+            &fee_payer.privkey,
+        )
+        .unwrap()
+        .into_inner()
+    }
 }
 
 //---------------------------------------------------------------------------
@@ -29,7 +29,7 @@ use diem_types::{
     },
 };
 use diem_vm::{convert_changeset_and_events, data_cache::StateViewCache};
-use move_binary_format::CompiledModule;
+use move_binary_format::{access::ModuleAccess, CompiledModule};
 use move_core_types::{
     account_address::AccountAddress,
     identifier::Identifier,
@@ -81,6 +81,13 @@ pub type OperatorAssignment = (
 // Defines a validator operator and maps that to a validator (config)
 pub type OperatorRegistration = (Ed25519PublicKey, Name, ScriptFunction, AccountAddress);
 
+//////// 0L ////////
+// An account to create at genesis and the amount of GAS to mint into it
+pub type InitialBalance = (AuthenticationKey, u64);
+
+//////// 0L ////////
+const GENESIS_FUND_ACCOUNT_FUNCTION: &str = "genesis_fund_account";
+
 pub fn encode_genesis_transaction(
     diem_root_key: Option<&Ed25519PublicKey>, //////// 0L ////////
     treasury_compliance_key: Option<&Ed25519PublicKey>, //////// 0L ////////
@@ -94,6 +101,7 @@ pub fn encode_genesis_transaction(
         treasury_compliance_key,
         operator_assignments,
         operator_registrations,
+        &[],
         current_module_blobs(), // Must use compiled stdlib,
         //////// 0L ////////
         vm_publishing_option.unwrap_or_else(|| VMPublishingOption::open()), // :)
@@ -106,6 +114,7 @@ pub fn encode_genesis_change_set(
     treasury_compliance_key: Option<&Ed25519PublicKey>, //////// 0L ////////
     operator_assignments: &[OperatorAssignment],
     operator_registrations: &[OperatorRegistration],
+    initial_balances: &[InitialBalance], //////// 0L ////////
     stdlib_modules: &[Vec<u8>],
     vm_publishing_option: VMPublishingOption,
     chain_id: ChainId,
@@ -159,6 +168,8 @@ pub fn encode_genesis_change_set(
     distribute_genesis_subsidy(&mut session, &log_context);
 
     fund_operators(&mut session, &log_context, &operator_assignments);
+
+    fund_initial_balances(&mut session, &log_context, initial_balances);
     //////// 0L end ////////
 
     reconfigure(&mut session, &log_context);
@@ -1043,6 +1054,7 @@ pub fn generate_test_genesis(
             .iter()
             .map(|v| v.operator_registration())
             .collect::<Vec<_>>(),
+        &[],
         stdlib_modules,
         vm_publishing_option,
         ChainId::test(),
@@ -1099,6 +1111,46 @@ fn fund_operators(
     }
 }
 
+//////// 0L /////////
+fn fund_initial_balances(
+    session: &mut Session<StateViewCache>,
+    log_context: &impl LogContext,
+    initial_balances: &[InitialBalance],
+) {
+    let diem_root_address = account_config::diem_root_address();
+    for (auth_key, amount) in initial_balances {
+        exec_function(
+            session,
+            log_context,
+            "DiemAccount",
+            GENESIS_FUND_ACCOUNT_FUNCTION,
+            vec![],
+            serialize_values(&vec![
+                MoveValue::Signer(diem_root_address),
+                MoveValue::Address(auth_key.derived_address()),
+                MoveValue::vector_u8(auth_key.prefix().to_vec()),
+                MoveValue::U64(*amount),
+            ]),
+        );
+    }
+}
+
+//////// 0L /////////
+/// Whether `stdlib_modules` can seed initial balances, i.e. include
+/// `DiemAccount::genesis_fund_account`. Older framework releases do not.
+pub fn supports_initial_balances(stdlib_modules: &[Vec<u8>]) -> bool {
+    stdlib_modules.iter().any(|blob| {
+        CompiledModule::deserialize(blob)
+            .map(|module| {
+                module.self_id().name().as_str() == "DiemAccount"
+                    && module.function_defs().iter().any(|def| {
+                        let handle = module.function_handle_at(def.function);
+                        module.identifier_at(handle.name).as_str() == GENESIS_FUND_ACCOUNT_FUNCTION
+                    })
+            })
+            .unwrap_or(false)
+    })
+}
 
 //////// 0L ////////
 fn initialize_testnet(session: &mut Session<StateViewCache>, log_context: &impl LogContext) {
@@ -0,0 +1,371 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Drives shared mempool with synthetic transaction workloads: clients submit txns of many
+//! senders concurrently, while consensus pulls and commits blocks and the txns are broadcast to
+//! peers. Reports throughput, contention on the core mempool lock and broadcast efficiency.
+
+use anyhow::{bail, format_err, Error, Result};
+use diem_config::config::NodeConfig;
+use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use diem_mempool::bench::{BenchMempool, BroadcastStats};
+use diem_types::{
+    account_config::XUS_NAME,
+    chain_id::ChainId,
+    transaction::{authenticator::AuthenticationKey, RawTransaction, Script, SignedTransaction},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Pause of consensus when mempool has no block for it
+const CONSENSUS_IDLE_INTERVAL: Duration = Duration::from_millis(1);
+/// Interval between two probes of the core mempool lock
+const LOCK_PROBE_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Distribution of the gas prices of generated txns.
+#[derive(Clone, Copy, Debug)]
+pub enum GasPrices {
+    /// Every txn pays the same price
+    Fixed(u64),
+    /// Prices drawn uniformly from `[min, max]`
+    Uniform { min: u64, max: u64 },
+    /// `high_percent` percent of txns pay `high`, the others `low`
+    Bimodal {
+        low: u64,
+        high: u64,
+        high_percent: u8,
+    },
+}
+
+impl GasPrices {
+    fn sample(&self, rng: &mut StdRng) -> u64 {
+        match *self {
+            GasPrices::Fixed(price) => price,
+            GasPrices::Uniform { min, max } => rng.gen_range(min..=max),
+            GasPrices::Bimodal {
+                low,
+                high,
+                high_percent,
+            } => {
+                if rng.gen_bool(f64::from(high_percent) / 100.0) {
+                    high
+                } else {
+                    low
+                }
+            }
+        }
+    }
+}
+
+/// Parses `fixed:PRICE`, `uniform:MIN:MAX` or `bimodal:LOW:HIGH:HIGH_PERCENT`.
+impl FromStr for GasPrices {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let kind = parts.next().unwrap_or_default();
+        let params = parts
+            .map(u64::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format_err!("invalid gas prices {}: {}", s, e))?;
+        let gas_prices = match (kind, params.as_slice()) {
+            ("fixed", [price]) => GasPrices::Fixed(*price),
+            ("uniform", [min, max]) if min <= max => GasPrices::Uniform {
+                min: *min,
+                max: *max,
+            },
+            ("bimodal", [low, high, high_percent]) if *high_percent <= 100 => GasPrices::Bimodal {
+                low: *low,
+                high: *high,
+                high_percent: *high_percent as u8,
+            },
+            _ => bail!(
+                "invalid gas prices {}, expected fixed:PRICE, uniform:MIN:MAX or \
+                 bimodal:LOW:HIGH:HIGH_PERCENT",
+                s
+            ),
+        };
+        Ok(gas_prices)
+    }
+}
+
+/// Synthetic workload run against shared mempool.
+#[derive(Clone, Debug)]
+pub struct Workload {
+    pub num_senders: usize,
+    /// Txns of each sender, with consecutive sequence numbers from 0
+    pub txns_per_sender: u64,
+    pub gas_prices: GasPrices,
+    /// Threads submitting txns, each for its own share of the senders
+    pub num_submitters: usize,
+    /// Txns submitted per request
+    pub submit_batch_size: usize,
+    /// Txns pulled per block by consensus
+    pub block_size: u64,
+    pub num_peers: usize,
+    /// Peers announce the digest protocol, and are sent digests before transactions
+    pub digest_broadcasts: bool,
+}
+
+/// Outcome of a workload run.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub submitted: usize,
+    pub accepted: usize,
+    pub committed: usize,
+    /// Time from the first submission until consensus committed everything it could
+    pub elapsed: Duration,
+    /// Waits to acquire the core mempool lock, probed while the workload ran, sorted
+    pub lock_waits: Vec<Duration>,
+    pub broadcasts: BroadcastStats,
+    pub num_peers: usize,
+}
+
+impl Report {
+    fn lock_wait_percentile(&self, percentile: usize) -> Duration {
+        if self.lock_waits.is_empty() {
+            return Duration::default();
+        }
+        self.lock_waits[(self.lock_waits.len() - 1) * percentile / 100]
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.elapsed.as_secs_f64();
+        let ratio = |part: u64, total: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                100.0 * part as f64 / total as f64
+            }
+        };
+        writeln!(
+            f,
+            "submitted {} txns, accepted {}, committed {} in {:.3}s",
+            self.submitted, self.accepted, self.committed, secs
+        )?;
+        writeln!(
+            f,
+            "throughput: {:.0} accepted txns/s, {:.0} committed txns/s",
+            self.accepted as f64 / secs,
+            self.committed as f64 / secs
+        )?;
+        writeln!(
+            f,
+            "mempool lock wait over {} probes: p50 {:?}, p99 {:?}, max {:?}",
+            self.lock_waits.len(),
+            self.lock_wait_percentile(50),
+            self.lock_wait_percentile(99),
+            self.lock_wait_percentile(100)
+        )?;
+        write!(
+            f,
+            "broadcasts to {} peers: {} messages, {} txns and {} digests sent, {} txns delivered \
+             ({:.1}% of txns sent, {:.1}% of accepted txns to every peer)",
+            self.num_peers,
+            self.broadcasts.messages,
+            self.broadcasts.txns_sent,
+            self.broadcasts.digests_sent,
+            self.broadcasts.txns_delivered,
+            ratio(self.broadcasts.txns_delivered, self.broadcasts.txns_sent),
+            ratio(
+                self.broadcasts.txns_delivered,
+                (self.accepted * self.num_peers) as u64
+            )
+        )
+    }
+}
+
+/// Generates the txns of `workload`, as the batches each submitter sends in turn.
+fn generate_txns(workload: &Workload) -> Vec<Vec<Vec<SignedTransaction>>> {
+    let mut rng = StdRng::from_seed([0u8; 32]);
+    let num_submitters = workload.num_submitters.max(1);
+    let mut submitters = vec![vec![]; num_submitters];
+    for sender_idx in 0..workload.num_senders {
+        let private_key = Ed25519PrivateKey::generate(&mut rng);
+        let public_key = private_key.public_key();
+        let sender = AuthenticationKey::ed25519(&public_key).derived_address();
+        let submitter_txns: &mut Vec<_> = &mut submitters[sender_idx % num_submitters];
+        for sequence_number in 0..workload.txns_per_sender {
+            let raw_txn = RawTransaction::new_script(
+                sender,
+                sequence_number,
+                Script::new(vec![], vec![], vec![]),
+                1_000_000,
+                workload.gas_prices.sample(&mut rng),
+                XUS_NAME.to_owned(),
+                u64::max_value(),
+                ChainId::test(),
+            );
+            submitter_txns.push(
+                raw_txn
+                    .sign(&private_key, public_key.clone())
+                    .expect("Failed to sign raw transaction.")
+                    .into_inner(),
+            );
+        }
+    }
+    submitters
+        .into_iter()
+        .map(|txns| {
+            txns.chunks(workload.submit_batch_size.max(1))
+                .map(|batch| batch.to_vec())
+                .collect()
+        })
+        .collect()
+}
+
+/// Runs `workload` against a shared mempool configured by `config`.
+pub fn run(config: &NodeConfig, workload: &Workload) -> Report {
+    let submitters_txns = generate_txns(workload);
+    let submitted = submitters_txns.iter().flatten().map(Vec::len).sum();
+    let bench = Arc::new(BenchMempool::new(
+        config,
+        workload.num_peers,
+        workload.digest_broadcasts,
+    ));
+    let submitting = Arc::new(AtomicBool::new(true));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let start = Instant::now();
+    let submitters: Vec<_> = submitters_txns
+        .into_iter()
+        .map(|batches| {
+            let bench = bench.clone();
+            thread::spawn(move || {
+                batches
+                    .into_iter()
+                    .map(|batch| bench.submit(batch))
+                    .sum::<usize>()
+            })
+        })
+        .collect();
+    let consensus = {
+        let bench = bench.clone();
+        let submitting = submitting.clone();
+        let block_size = workload.block_size;
+        thread::spawn(move || {
+            let mut committed = 0;
+            loop {
+                // Read before pulling the block, so the last submissions make it in.
+                let done = !submitting.load(Ordering::SeqCst);
+                let block_len = bench.commit_block(block_size);
+                committed += block_len;
+                if block_len == 0 {
+                    if done {
+                        return committed;
+                    }
+                    thread::sleep(CONSENSUS_IDLE_INTERVAL);
+                }
+            }
+        })
+    };
+    let broadcaster = {
+        let bench = bench.clone();
+        let running = running.clone();
+        let tick_interval = Duration::from_millis(config.mempool.shared_mempool.tick_interval_ms);
+        thread::spawn(move || {
+            let mut stats = BroadcastStats::default();
+            while running.load(Ordering::SeqCst) {
+                stats += bench.broadcast();
+                thread::sleep(tick_interval);
+            }
+            stats
+        })
+    };
+    let lock_prober = {
+        let bench = bench.clone();
+        let running = running.clone();
+        thread::spawn(move || {
+            let mut lock_waits = vec![];
+            while running.load(Ordering::SeqCst) {
+                lock_waits.push(bench.lock_wait());
+                thread::sleep(LOCK_PROBE_INTERVAL);
+            }
+            lock_waits
+        })
+    };
+
+    let accepted = submitters
+        .into_iter()
+        .map(|submitter| submitter.join().expect("submitter panicked"))
+        .sum();
+    submitting.store(false, Ordering::SeqCst);
+    let committed = consensus.join().expect("consensus panicked");
+    let elapsed = start.elapsed();
+    running.store(false, Ordering::SeqCst);
+    let broadcasts = broadcaster.join().expect("broadcaster panicked");
+    let mut lock_waits = lock_prober.join().expect("lock prober panicked");
+    lock_waits.sort();
+
+    Report {
+        submitted,
+        accepted,
+        committed,
+        elapsed,
+        lock_waits,
+        broadcasts,
+        num_peers: workload.num_peers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gas_prices_from_str() {
+        assert!(matches!(
+            "fixed:5".parse::<GasPrices>(),
+            Ok(GasPrices::Fixed(5))
+        ));
+        assert!(matches!(
+            "uniform:1:10".parse::<GasPrices>(),
+            Ok(GasPrices::Uniform { min: 1, max: 10 })
+        ));
+        assert!(matches!(
+            "bimodal:1:100:10".parse::<GasPrices>(),
+            Ok(GasPrices::Bimodal {
+                low: 1,
+                high: 100,
+                high_percent: 10
+            })
+        ));
+        assert!("uniform:10:1".parse::<GasPrices>().is_err());
+        assert!("bimodal:1:100:101".parse::<GasPrices>().is_err());
+        assert!("normal:1".parse::<GasPrices>().is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut config = NodeConfig::default();
+        config.mempool.capacity = 1_000;
+        config.mempool.capacity_per_user = 10;
+        config.mempool.overflow_capacity_per_user = 10;
+        let workload = Workload {
+            num_senders: 20,
+            txns_per_sender: 5,
+            gas_prices: GasPrices::Uniform { min: 1, max: 100 },
+            num_submitters: 2,
+            submit_batch_size: 10,
+            block_size: 50,
+            num_peers: 3,
+            digest_broadcasts: true,
+        };
+
+        let report = run(&config, &workload);
+        assert_eq!(report.submitted, 100);
+        assert_eq!(report.accepted, 100);
+        assert_eq!(report.committed, 100);
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_config::config::NodeConfig;
+use mempool_bench::{GasPrices, Workload};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    #[structopt(long, default_value = "1000")]
+    num_senders: usize,
+
+    #[structopt(long, default_value = "10")]
+    txns_per_sender: u64,
+
+    /// fixed:PRICE, uniform:MIN:MAX or bimodal:LOW:HIGH:HIGH_PERCENT
+    #[structopt(long, default_value = "uniform:1:1000")]
+    gas_prices: GasPrices,
+
+    #[structopt(long, default_value = "4")]
+    num_submitters: usize,
+
+    #[structopt(long, default_value = "100")]
+    submit_batch_size: usize,
+
+    #[structopt(long, default_value = "500")]
+    block_size: u64,
+
+    #[structopt(long, default_value = "10")]
+    num_peers: usize,
+
+    #[structopt(long)]
+    digest_broadcasts: bool,
+
+    #[structopt(long, default_value = "100000")]
+    capacity: usize,
+
+    #[structopt(long, default_value = "100")]
+    capacity_per_user: usize,
+
+    #[structopt(long, default_value = "100")]
+    overflow_capacity_per_user: usize,
+
+    #[structopt(long)]
+    sender_fair_ordering: bool,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+
+    diem_logger::Logger::new().init();
+
+    let mut config = NodeConfig::default();
+    config.mempool.capacity = opt.capacity;
+    config.mempool.capacity_per_user = opt.capacity_per_user;
+    config.mempool.overflow_capacity_per_user = opt.overflow_capacity_per_user;
+    config.mempool.sender_fair_ordering = opt.sender_fair_ordering;
+
+    let report = mempool_bench::run(
+        &config,
+        &Workload {
+            num_senders: opt.num_senders,
+            txns_per_sender: opt.txns_per_sender,
+            gas_prices: opt.gas_prices,
+            num_submitters: opt.num_submitters,
+            submit_batch_size: opt.submit_batch_size,
+            block_size: opt.block_size,
+            num_peers: opt.num_peers,
+            digest_broadcasts: opt.digest_broadcasts,
+        },
+    );
+    println!("{}", report);
+}
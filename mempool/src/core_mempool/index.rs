@@ -7,14 +7,19 @@ use crate::{
     counters,
     logging::{LogEntry, LogSchema},
 };
+use diem_infallible::RwLock;
 use diem_logger::prelude::*;
-use diem_types::{account_address::AccountAddress, transaction::GovernanceRole};
+use diem_types::{
+    account_address::AccountAddress,
+    transaction::{GovernanceRole, SignedTransaction},
+};
 use rand::seq::SliceRandom;
 use std::{
     cmp::Ordering,
-    collections::{btree_set::Iter, BTreeMap, BTreeSet, HashMap},
+    collections::{btree_set::Iter, BTreeMap, BTreeSet, HashMap, VecDeque},
     iter::Rev,
     ops::Bound,
+    sync::Arc,
     time::Duration,
 };
 
@@ -194,72 +199,127 @@ impl Ord for TTLOrderingKey {
 /// We only add a transaction to the index if it has a chance to be included in the next consensus
 /// block (which means its status is != NotReady or its sequential to another "ready" transaction).
 ///
-/// It's represented as Map <timeline_id, (Address, sequence_number)>, where timeline_id is auto
-/// increment unique id of "ready" transaction in local Mempool. (Address, sequence_number) is a
-/// logical reference to transaction content in main storage.
+/// It's represented as Map <timeline_id, transaction>, where timeline_id is auto increment unique
+/// id of "ready" transaction in local Mempool. Unlike the other indexes, it keeps a copy of the
+/// transactions, so broadcasts read it through a `TimelineReader` without taking the Mempool lock,
+/// and concurrently with each other.
 pub struct TimelineIndex {
     timeline_id: u64,
-    timeline: BTreeMap<u64, (AccountAddress, u64)>,
+    timeline: Arc<RwLock<BTreeMap<u64, SignedTransaction>>>,
+    sender_fair_ordering: bool,
 }
 
 impl TimelineIndex {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(sender_fair_ordering: bool) -> Self {
         Self {
             timeline_id: 1,
-            timeline: BTreeMap::new(),
+            timeline: Arc::new(RwLock::new(BTreeMap::new())),
+            sender_fair_ordering,
+        }
+    }
+
+    pub(crate) fn reader(&self) -> TimelineReader {
+        TimelineReader {
+            timeline: self.timeline.clone(),
+            sender_fair_ordering: self.sender_fair_ordering,
         }
     }
 
-    /// Read all transactions from the timeline since <timeline_id>.
+    pub(crate) fn insert(&mut self, txn: &mut MempoolTransaction) {
+        self.timeline
+            .write()
+            .insert(self.timeline_id, txn.txn.clone());
+        txn.timeline_state = TimelineState::Ready(self.timeline_id);
+        self.timeline_id += 1;
+    }
+
+    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
+        if let TimelineState::Ready(timeline_id) = txn.timeline_state {
+            self.timeline.write().remove(&timeline_id);
+        }
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.timeline.read().len()
+    }
+}
+
+/// Shared read access to a `TimelineIndex`, holding its lock only for the duration of a read.
+#[derive(Clone)]
+pub struct TimelineReader {
+    timeline: Arc<RwLock<BTreeMap<u64, SignedTransaction>>>,
+    sender_fair_ordering: bool,
+}
+
+impl TimelineReader {
+    /// Read `count` transactions from the timeline since `timeline_id` (exclusive).
+    /// Returns block of transactions and new last_timeline_id.
+    /// With sender fair ordering, the senders of the block are interleaved: the receiving peer
+    /// admits them in that order, so when its mempool fills up, one sender isn't admitted at the
+    /// expense of all others.
     pub(crate) fn read_timeline(
-        &mut self,
+        &self,
         timeline_id: u64,
         count: usize,
-    ) -> Vec<(AccountAddress, u64)> {
-        let mut batch = vec![];
-        for (_id, &(address, sequence_number)) in self
+    ) -> (Vec<SignedTransaction>, u64) {
+        let mut last_timeline_id = timeline_id;
+        let batch: Vec<_> = self
             .timeline
+            .read()
             .range((Bound::Excluded(timeline_id), Bound::Unbounded))
-        {
-            batch.push((address, sequence_number));
-            if batch.len() == count {
-                break;
-            }
+            .take(count)
+            .map(|(id, txn)| {
+                last_timeline_id = *id;
+                txn.clone()
+            })
+            .collect();
+        if self.sender_fair_ordering {
+            (
+                interleave_senders(batch, SignedTransaction::sender),
+                last_timeline_id,
+            )
+        } else {
+            (batch, last_timeline_id)
         }
-        batch
     }
 
     /// Read transactions from the timeline from `start_id` (exclusive) to `end_id` (inclusive).
-    pub(crate) fn timeline_range(
-        &mut self,
-        start_id: u64,
-        end_id: u64,
-    ) -> Vec<(AccountAddress, u64)> {
+    pub(crate) fn timeline_range(&self, start_id: u64, end_id: u64) -> Vec<SignedTransaction> {
         self.timeline
+            .read()
             .range((Bound::Excluded(start_id), Bound::Included(end_id)))
-            .map(|(_idx, txn)| txn)
-            .cloned()
+            .map(|(_id, txn)| txn.clone())
             .collect()
     }
+}
 
-    pub(crate) fn insert(&mut self, txn: &mut MempoolTransaction) {
-        self.timeline.insert(
-            self.timeline_id,
-            (txn.get_sender(), txn.get_sequence_number()),
-        );
-        txn.timeline_state = TimelineState::Ready(self.timeline_id);
-        self.timeline_id += 1;
+/// Reorders `items` round-robin across their senders: the first item of each sender, in order of
+/// first appearance, then the second one of each, and so on. The items of a sender keep their
+/// relative order, so their sequence numbers stay in order.
+pub(crate) fn interleave_senders<T>(
+    items: Vec<T>,
+    sender: impl Fn(&T) -> AccountAddress,
+) -> Vec<T> {
+    let num_items = items.len();
+    let mut queues: Vec<VecDeque<T>> = vec![];
+    let mut sender_queues = HashMap::new();
+    for item in items {
+        let queue = *sender_queues.entry(sender(&item)).or_insert_with(|| {
+            queues.push(VecDeque::new());
+            queues.len() - 1
+        });
+        queues[queue].push_back(item);
     }
 
-    pub(crate) fn remove(&mut self, txn: &MempoolTransaction) {
-        if let TimelineState::Ready(timeline_id) = txn.timeline_state {
-            self.timeline.remove(&timeline_id);
+    let mut interleaved = Vec::with_capacity(num_items);
+    while interleaved.len() < num_items {
+        for queue in &mut queues {
+            if let Some(item) = queue.pop_front() {
+                interleaved.push(item);
+            }
         }
     }
-
-    pub(crate) fn size(&self) -> usize {
-        self.timeline.len()
-    }
+    interleaved
 }
 
 /// ParkingLotIndex keeps track of "not_ready" transactions, e.g., transactions that
@@ -5,15 +5,17 @@
 //! agreed upon.
 use crate::{
     core_mempool::{
-        index::TxnPointer,
+        index::{interleave_senders, TimelineReader, TxnPointer},
         transaction::{MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
         ttl_cache::TtlCache,
     },
     counters,
     logging::{LogEntry, LogSchema, TxnsLog},
+    shared_mempool::types::SnapshotTransaction,
 };
 use diem_config::config::NodeConfig;
+use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
@@ -22,7 +24,7 @@ use diem_types::{
 };
 use std::{
     cmp::max,
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     time::{Duration, SystemTime},
 };
 
@@ -36,6 +38,15 @@ pub struct Mempool {
     // takes to pick it up by consensus.
     pub(crate) metrics_cache: TtlCache<(AccountAddress, u64), SystemTime>,
     pub system_transaction_timeout: Duration,
+    sender_fair_ordering: bool,
+    // Bounds of the time left until the client-specified expiration of accepted transactions,
+    // see `MempoolConfig::min_expiration_window_secs`. 0 disables a bound.
+    min_expiration_window_secs: u64,
+    max_expiration_window_secs: u64,
+    // Numbers allocated to each sender that haven't reached mempool yet, with the time of their
+    // allocation, see `allocate_sequence_number`.
+    sequence_number_allocations: HashMap<AccountAddress, BTreeMap<u64, SystemTime>>,
+    pub(crate) sequence_number_allocation_ttl: Duration,
 }
 
 impl Mempool {
@@ -47,6 +58,13 @@ impl Mempool {
             system_transaction_timeout: Duration::from_secs(
                 config.mempool.system_transaction_timeout_secs,
             ),
+            sender_fair_ordering: config.mempool.sender_fair_ordering,
+            min_expiration_window_secs: config.mempool.min_expiration_window_secs,
+            max_expiration_window_secs: config.mempool.max_expiration_window_secs,
+            sequence_number_allocations: HashMap::new(),
+            sequence_number_allocation_ttl: Duration::from_secs(
+                config.mempool.sequence_number_allocation_ttl_secs,
+            ),
         }
     }
 
@@ -102,7 +120,11 @@ impl Mempool {
     }
 
     /// Used to add a transaction to the Mempool.
-    /// Performs basic validation: checks account's sequence number.
+    /// Performs basic validation: checks account's sequence number and expiration time.
+    /// A txn with `client_submitted` set can wait in the account's overflow queue instead of being
+    /// rejected when the account is at capacity, see `MempoolConfig::overflow_capacity_per_user`,
+    /// and wins conflicts under `ConflictResolution::ClientWins`.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn add_txn(
         &mut self,
         txn: SignedTransaction,
@@ -111,10 +133,12 @@ impl Mempool {
         db_sequence_number: u64,
         timeline_state: TimelineState,
         governance_role: GovernanceRole,
+        client_submitted: bool,
     ) -> MempoolStatus {
         trace!(
             LogSchema::new(LogEntry::AddTxn)
-                .txns(TxnsLog::new_txn(txn.sender(), txn.sequence_number())),
+                .txns(TxnsLog::new_txn(txn.sender(), txn.sequence_number()))
+                .trace_id(txn.trace_id()),
             committed_seq_number = db_sequence_number
         );
         let cached_value = self.sequence_number_cache.get(&txn.sender());
@@ -131,6 +155,9 @@ impl Mempool {
                 sequence_number,
             ));
         }
+        if let Some(status) = self.check_expiration_window(&txn) {
+            return status;
+        }
 
         let expiration_time =
             diem_infallible::duration_since_epoch() + self.system_transaction_timeout;
@@ -146,11 +173,74 @@ impl Mempool {
             ranking_score,
             timeline_state,
             governance_role,
+            client_submitted,
         );
 
         self.transactions.insert(txn_info, sequence_number)
     }
 
+    /// Returns the status `add_txn` would add `txn` with, without adding it.
+    pub(crate) fn check_txn(
+        &self,
+        txn: SignedTransaction,
+        gas_amount: u64,
+        ranking_score: u64,
+        db_sequence_number: u64,
+        governance_role: GovernanceRole,
+        client_submitted: bool,
+    ) -> MempoolStatus {
+        let sequence_number = self
+            .sequence_number_cache
+            .get(&txn.sender())
+            .map_or(db_sequence_number, |value| max(*value, db_sequence_number));
+        if txn.sequence_number() < sequence_number {
+            return MempoolStatus::new(MempoolStatusCode::InvalidSeqNumber).with_message(format!(
+                "transaction sequence number is {}, current sequence number is  {}",
+                txn.sequence_number(),
+                sequence_number,
+            ));
+        }
+        if let Some(status) = self.check_expiration_window(&txn) {
+            return status;
+        }
+
+        let txn_info = MempoolTransaction::new(
+            txn,
+            diem_infallible::duration_since_epoch() + self.system_transaction_timeout,
+            gas_amount,
+            ranking_score,
+            TimelineState::NotReady,
+            governance_role,
+            client_submitted,
+        );
+        self.transactions.check_insert(&txn_info, sequence_number)
+    }
+
+    /// Returns the status rejecting `txn` if its expiration time is closer or further away than
+    /// the node accepts.
+    fn check_expiration_window(&self, txn: &SignedTransaction) -> Option<MempoolStatus> {
+        let now_secs = diem_infallible::duration_since_epoch().as_secs();
+        let expiration_secs = txn.expiration_timestamp_secs();
+        let too_soon = self.min_expiration_window_secs > 0
+            && expiration_secs < now_secs.saturating_add(self.min_expiration_window_secs);
+        let too_late = self.max_expiration_window_secs > 0
+            && expiration_secs > now_secs.saturating_add(self.max_expiration_window_secs);
+        if !too_soon && !too_late {
+            return None;
+        }
+        Some(
+            MempoolStatus::new(MempoolStatusCode::ExpirationOutOfBounds).with_message(format!(
+                "transaction expires in {}s, expected between {}s and {}s",
+                expiration_secs as i128 - now_secs as i128,
+                self.min_expiration_window_secs,
+                match self.max_expiration_window_secs {
+                    0 => "unbounded".to_string(),
+                    max => max.to_string(),
+                },
+            )),
+        )
+    }
+
     /// Fetches next block of transactions for consensus.
     /// `batch_size` - size of requested block.
     /// `seen_txns` - transactions that were sent to Consensus but were not committed yet,
@@ -171,6 +261,13 @@ impl Mempool {
         let mut skipped = HashSet::new();
         let seen_size = seen.len();
         let mut txn_walked = 0usize;
+        // with sender fair ordering, every eligible transaction is collected before the senders
+        // are interleaved, otherwise the highest ranked ones would already fill the block
+        let max_txns = if self.sender_fair_ordering {
+            u64::MAX
+        } else {
+            batch_size
+        };
         // iterate over the queue of transactions based on gas price
         'main: for txn in self.transactions.iter_queue() {
             txn_walked += 1;
@@ -186,7 +283,7 @@ impl Mempool {
                 let ptr = TxnPointer::from(txn);
                 seen.insert(ptr);
                 result.push(ptr);
-                if (result.len() as u64) == batch_size {
+                if (result.len() as u64) == max_txns {
                     break;
                 }
 
@@ -196,7 +293,7 @@ impl Mempool {
                 while skipped.contains(&skipped_txn) {
                     seen.insert(skipped_txn);
                     result.push(skipped_txn);
-                    if (result.len() as u64) == batch_size {
+                    if (result.len() as u64) == max_txns {
                         break 'main;
                     }
                     skipped_txn = (txn.address, skipped_txn.1 + 1);
@@ -205,15 +302,27 @@ impl Mempool {
                 skipped.insert(TxnPointer::from(txn));
             }
         }
+        if self.sender_fair_ordering {
+            result = interleave_senders(result, |(address, _)| *address);
+            result.truncate(batch_size as usize);
+        }
         let result_size = result.len();
         // convert transaction pointers to real values
         let mut block_log = TxnsLog::new();
         let block: Vec<_> = result
             .into_iter()
-            .filter_map(|(address, seq)| {
-                block_log.add(address, seq);
-                self.transactions.get(&address, seq)
-            })
+            .filter_map(
+                |(address, seq)| match self.transactions.get_with_metadata(&address, seq) {
+                    Some(txn) => {
+                        block_log.add_traced(address, seq, txn.trace_id);
+                        Some(txn.txn.clone())
+                    }
+                    None => {
+                        block_log.add(address, seq);
+                        None
+                    }
+                },
+            )
             .collect();
 
         debug!(
@@ -234,6 +343,57 @@ impl Mempool {
         block
     }
 
+    /// Allocates the next sequence number of `sender`, whose on-chain sequence number is
+    /// `account_sequence_number`. Numbers are allocated past the sender's pending txns and the
+    /// numbers allocated before, so concurrent submitters of one account don't collide. A number
+    /// that hasn't reached mempool `sequence_number_allocation_ttl` after its allocation is
+    /// allocated again, so an unsubmitted number doesn't leave a gap blocking the sender's txns.
+    ///
+    /// Callers must check that the requester may submit for `sender`: anyone allocating numbers
+    /// of a sender delays its txns by the TTL. The JSON-RPC API only checks this for API keys
+    /// with `allowed_senders`, which the anonymous consumer doesn't have by default.
+    pub(crate) fn allocate_sequence_number(
+        &mut self,
+        sender: AccountAddress,
+        account_sequence_number: u64,
+    ) -> u64 {
+        let now = SystemTime::now();
+        let committed_sequence_number = max(
+            account_sequence_number,
+            self.sequence_number_cache
+                .get(&sender)
+                .copied()
+                .unwrap_or_default(),
+        );
+        let transactions = &self.transactions;
+        let allocations = self.sequence_number_allocations.entry(sender).or_default();
+        // numbers committed or submitted since aren't outstanding anymore
+        allocations.retain(|&sequence_number, _| {
+            sequence_number >= committed_sequence_number
+                && !transactions.contains(&sender, sequence_number)
+        });
+
+        let ttl = self.sequence_number_allocation_ttl;
+        let expired = allocations
+            .iter()
+            .find(|(_, &allocated)| allocated + ttl <= now)
+            .map(|(&sequence_number, _)| sequence_number);
+        let sequence_number = expired.unwrap_or_else(|| {
+            [
+                transactions.get_next_sequence_number(&sender),
+                allocations
+                    .keys()
+                    .next_back()
+                    .map(|sequence_number| sequence_number + 1),
+            ]
+            .iter()
+            .flatten()
+            .fold(committed_sequence_number, |a, &b| max(a, b))
+        });
+        allocations.insert(sequence_number, now);
+        sequence_number
+    }
+
     /// Periodic core mempool garbage collection.
     /// Removes all expired transactions and clears expired entries in metrics
     /// cache and sequence number cache.
@@ -250,19 +410,69 @@ impl Mempool {
             .gc_by_expiration_time(block_time, &self.metrics_cache);
     }
 
+    /// Share of the mempool capacity in use, between 0 and 1.
+    pub(crate) fn fill_ratio(&self) -> f64 {
+        self.transactions.fill_ratio()
+    }
+
+    /// Returns all transactions in mempool, those in the overflow queue included.
+    pub(crate) fn get_all_txns(&self) -> Vec<SignedTransaction> {
+        self.transactions.get_all()
+    }
+
+    /// Returns all transactions in mempool with their metadata, to be exported in a snapshot.
+    pub(crate) fn export_txns(&self) -> Vec<SnapshotTransaction> {
+        self.transactions
+            .get_all_with_metadata()
+            .into_iter()
+            .map(|txn| SnapshotTransaction {
+                expiration_time_secs: txn.expiration_time.as_secs(),
+                gas_amount: txn.gas_amount,
+                ranking_score: txn.ranking_score,
+                txn: txn.txn,
+            })
+            .collect()
+    }
+
+    /// Updates the ranking score of a transaction, e.g. after the gas schedule changed.
+    pub(crate) fn update_ranking_score(
+        &mut self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+        ranking_score: u64,
+    ) {
+        self.transactions
+            .update_ranking_score(sender, sequence_number, ranking_score);
+    }
+
     /// Read `count` transactions from timeline since `timeline_id`.
     /// Returns block of transactions and new last_timeline_id.
     pub(crate) fn read_timeline(
-        &mut self,
+        &self,
         timeline_id: u64,
         count: usize,
     ) -> (Vec<SignedTransaction>, u64) {
-        self.transactions.read_timeline(timeline_id, count)
+        self.timeline_reader().read_timeline(timeline_id, count)
+    }
+
+    /// Returns a handle reading the timeline without locking Mempool, for broadcasts.
+    pub(crate) fn timeline_reader(&self) -> TimelineReader {
+        self.transactions.timeline_reader()
+    }
+
+    /// Returns the digests in `digests` of transactions that are not in mempool.
+    pub(crate) fn missing_digests(&self, digests: &[HashValue]) -> Vec<HashValue> {
+        digests
+            .iter()
+            .filter(|digest| !self.transactions.contains_digest(digest))
+            .cloned()
+            .collect()
     }
 
-    /// Read transactions from timeline from `start_id` (exclusive) to `end_id` (inclusive).
-    pub(crate) fn timeline_range(&mut self, start_id: u64, end_id: u64) -> Vec<SignedTransaction> {
-        self.transactions.timeline_range(start_id, end_id)
+    /// Returns the number of parked txns made ready by txns added since the last call, which
+    /// filled the sequence number gaps before them.
+    pub(crate) fn take_unparked_txns(&mut self) -> usize {
+        self.transactions.take_unparked_txns()
     }
 
     pub fn gen_snapshot(&self) -> TxnsLog {
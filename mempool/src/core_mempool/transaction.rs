@@ -1,9 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_crypto::HashValue;
 use diem_types::{
     account_address::AccountAddress,
-    transaction::{GovernanceRole, SignedTransaction},
+    transaction::{GovernanceRole, SignedTransaction, TraceId},
 };
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -17,6 +18,13 @@ pub struct MempoolTransaction {
     pub ranking_score: u64,
     pub timeline_state: TimelineState,
     pub governance_role: GovernanceRole,
+    // Whether the transaction was submitted to this node by a client, rather than received from
+    // a peer.
+    pub client_submitted: bool,
+    // Digest announced to peers in place of the full transaction, see `transaction_digest`.
+    pub digest: HashValue,
+    // ID the transaction is logged with, from its submission to its commit.
+    pub trace_id: TraceId,
 }
 
 impl MempoolTransaction {
@@ -27,7 +35,10 @@ impl MempoolTransaction {
         ranking_score: u64,
         timeline_state: TimelineState,
         governance_role: GovernanceRole,
+        client_submitted: bool,
     ) -> Self {
+        let digest = transaction_digest(&txn);
+        let trace_id = txn.trace_id();
         Self {
             txn,
             expiration_time,
@@ -35,6 +46,9 @@ impl MempoolTransaction {
             ranking_score,
             timeline_state,
             governance_role,
+            client_submitted,
+            digest,
+            trace_id,
         }
     }
     pub(crate) fn get_sequence_number(&self) -> u64 {
@@ -48,6 +62,12 @@ impl MempoolTransaction {
     }
 }
 
+/// Identifies a transaction in mempool digest announcements: the SHA3-256 hash of its BCS bytes,
+/// signature included, so differently signed versions of a transaction get different digests.
+pub fn transaction_digest(txn: &SignedTransaction) -> HashValue {
+    HashValue::sha3_256_of(&bcs::to_bytes(txn).expect("failed BCS serialization of transaction"))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Hash, Serialize)]
 pub enum TimelineState {
     // The transaction is ready for broadcast.
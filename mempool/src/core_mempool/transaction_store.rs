@@ -5,7 +5,7 @@ use crate::{
     core_mempool::{
         index::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
-            TimelineIndex,
+            TimelineIndex, TimelineReader,
         },
         transaction::{MempoolTransaction, TimelineState},
         ttl_cache::TtlCache,
@@ -13,7 +13,8 @@ use crate::{
     counters,
     logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
 };
-use diem_config::config::MempoolConfig;
+use diem_config::config::{ConflictResolution, MempoolConfig};
+use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
     account_address::AccountAddress,
@@ -21,7 +22,7 @@ use diem_types::{
     transaction::SignedTransaction,
 };
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Bound,
     time::{Duration, SystemTime},
 };
@@ -42,14 +43,26 @@ pub struct TransactionStore {
     timeline_index: TimelineIndex,
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
+    // digest of every stored txn, used to answer peers' digest announcements
+    digest_index: HashSet<HashValue>,
+    // client-submitted txns that can't be stored yet, because their account is at
+    // `capacity_per_user` or the txns before them aren't stored either; held outside of all
+    // indexes until earlier txns of the account commit
+    overflow: HashMap<AccountAddress, AccountTransactions>,
+    // parked txns made ready by `insert` filling the gap before them, since the last
+    // `take_unparked_txns`
+    unparked_txns: usize,
 
     // configuration
     capacity: usize,
     capacity_per_user: usize,
+    overflow_capacity_per_user: usize,
+    conflict_resolution: ConflictResolution,
 }
 
 impl TransactionStore {
     pub(crate) fn new(config: &MempoolConfig) -> Self {
+        counters::CORE_MEMPOOL_CAPACITY.set(config.capacity as i64);
         Self {
             // main DS
             transactions: HashMap::new(),
@@ -60,12 +73,17 @@ impl TransactionStore {
                 Duration::from_secs(t.txn.expiration_timestamp_secs())
             })),
             priority_index: PriorityIndex::new(),
-            timeline_index: TimelineIndex::new(),
+            timeline_index: TimelineIndex::new(config.sender_fair_ordering),
             parking_lot_index: ParkingLotIndex::new(),
+            digest_index: HashSet::new(),
+            overflow: HashMap::new(),
+            unparked_txns: 0,
 
             // configuration
             capacity: config.capacity,
             capacity_per_user: config.capacity_per_user,
+            overflow_capacity_per_user: config.overflow_capacity_per_user,
+            conflict_resolution: config.conflict_resolution,
         }
     }
 
@@ -75,17 +93,28 @@ impl TransactionStore {
         address: &AccountAddress,
         sequence_number: u64,
     ) -> Option<SignedTransaction> {
-        if let Some(txn) = self
-            .transactions
+        self.get_with_metadata(address, sequence_number)
+            .map(|txn| txn.txn.clone())
+    }
+
+    pub(crate) fn get_with_metadata(
+        &self,
+        address: &AccountAddress,
+        sequence_number: u64,
+    ) -> Option<&MempoolTransaction> {
+        self.transactions
             .get(&address)
             .and_then(|txns| txns.get(&sequence_number))
-        {
-            return Some(txn.txn.clone());
-        }
-        None
+    }
+
+    /// Returns true if a transaction with the given digest is stored.
+    pub(crate) fn contains_digest(&self, digest: &HashValue) -> bool {
+        self.digest_index.contains(digest)
     }
 
     /// Insert transaction into TransactionStore. Performs validation checks and updates indexes.
+    /// A client-submitted txn of an account at `capacity_per_user`, or one that doesn't follow on
+    /// from the account's stored txns, is held in the overflow queue instead, if enabled.
     pub(crate) fn insert(
         &mut self,
         txn: MempoolTransaction,
@@ -96,28 +125,27 @@ impl TransactionStore {
 
         // check if transaction is already present in Mempool
         // e.g. given request is update
-        // we allow increase in gas price to speed up process.
         // ignores the case transaction hash is same for retrying submit transaction.
-        if let Some(txns) = self.transactions.get_mut(&address) {
-            if let Some(current_version) = txns.get_mut(&sequence_number) {
-                if current_version.txn == txn.txn {
-                    return MempoolStatus::new(MempoolStatusCode::Accepted);
-                }
-                if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
-                    && current_version.txn.payload() == txn.txn.payload()
-                    && current_version.txn.expiration_timestamp_secs()
-                        == txn.txn.expiration_timestamp_secs()
-                    && current_version.get_gas_price() < txn.get_gas_price()
-                {
-                    if let Some(txn) = txns.remove(&txn.get_sequence_number()) {
-                        self.index_remove(&txn);
-                    }
-                } else {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        format!("Failed to update gas price to {}", txn.get_gas_price()),
-                    );
-                }
+        if let Some(current_version) = self.get_with_metadata(&address, sequence_number) {
+            if current_version.txn == txn.txn {
+                return MempoolStatus::new(MempoolStatusCode::Accepted);
             }
+            let resolution = self.resolve_conflict(current_version, &txn);
+            counters::CORE_MEMPOOL_CONFLICT_COUNT
+                .with_label_values(&[
+                    self.conflict_resolution.as_str(),
+                    if resolution.is_ok() {
+                        counters::CONFLICT_REPLACED_LABEL
+                    } else {
+                        counters::CONFLICT_KEPT_LABEL
+                    },
+                ])
+                .inc();
+            if let Err(status) = resolution {
+                return status;
+            }
+            self.replace(txn, current_sequence_number);
+            return MempoolStatus::new(MempoolStatusCode::Accepted);
         }
 
         if self.check_is_full_after_eviction(&txn, current_sequence_number) {
@@ -133,15 +161,25 @@ impl TransactionStore {
             .or_insert_with(AccountTransactions::new);
 
         self.clean_committed_transactions(&address, current_sequence_number);
+        self.promote_overflow(&address, current_sequence_number);
 
         if let Some(txns) = self.transactions.get_mut(&address) {
             // capacity check
-            if txns.len() >= self.capacity_per_user {
+            let txns_len = txns.len();
+            // overflowed txns are only let in once the txns before them are
+            let has_gap = sequence_number > current_sequence_number
+                && !txns.contains_key(&(sequence_number - 1));
+            if txn.client_submitted
+                && self.overflow_capacity_per_user > 0
+                && (txns_len >= self.capacity_per_user || has_gap)
+            {
+                return self.insert_overflow(txn, current_sequence_number);
+            }
+            if txns_len >= self.capacity_per_user {
                 return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
                     format!(
                         "txns length: {} capacity per user: {}",
-                        txns.len(),
-                        self.capacity_per_user,
+                        txns_len, self.capacity_per_user,
                     ),
                 );
             }
@@ -149,13 +187,216 @@ impl TransactionStore {
             // insert into storage and other indexes
             self.system_ttl_index.insert(&txn);
             self.expiration_time_index.insert(&txn);
+            self.digest_index.insert(txn.digest);
             txns.insert(sequence_number, txn);
             self.track_indices();
         }
-        self.process_ready_transactions(&address, current_sequence_number);
+        self.unparked_txns += self.process_ready_transactions(&address, current_sequence_number);
+        MempoolStatus::new(MempoolStatusCode::Accepted)
+    }
+
+    /// Returns the number of parked txns `insert` made ready since the last call, and resets it.
+    pub(crate) fn take_unparked_txns(&mut self) -> usize {
+        std::mem::take(&mut self.unparked_txns)
+    }
+
+    /// Checks whether `insert` would accept `txn`, without inserting it. A full mempool is assumed
+    /// to evict a parked txn if it has any, and the overflowed txns of the account `insert` would
+    /// promote first are not accounted for.
+    pub(crate) fn check_insert(
+        &self,
+        txn: &MempoolTransaction,
+        current_sequence_number: u64,
+    ) -> MempoolStatus {
+        let address = txn.get_sender();
+        let sequence_number = txn.get_sequence_number();
+        let txns = self.transactions.get(&address);
+
+        if let Some(current_version) = txns.and_then(|txns| txns.get(&sequence_number)) {
+            if current_version.txn == txn.txn {
+                return MempoolStatus::new(MempoolStatusCode::Accepted);
+            }
+            // a replacement takes no more room
+            return match self.resolve_conflict(current_version, txn) {
+                Ok(()) => MempoolStatus::new(MempoolStatusCode::Accepted),
+                Err(status) => status,
+            };
+        }
+
+        let evictable =
+            self.check_txn_ready(txn, current_sequence_number) && self.parking_lot_index.size() > 0;
+        if self.system_ttl_index.size() >= self.capacity + evictable as usize {
+            return MempoolStatus::new(MempoolStatusCode::MempoolIsFull).with_message(format!(
+                "mempool size: {}, capacity: {}",
+                self.system_ttl_index.size(),
+                self.capacity,
+            ));
+        }
+
+        // committed txns of the account are cleaned up on insertion
+        let txns_len = txns.map_or(0, |txns| txns.range(current_sequence_number..).count());
+        let has_gap = sequence_number > current_sequence_number
+            && !txns.map_or(false, |txns| txns.contains_key(&(sequence_number - 1)));
+        if txn.client_submitted
+            && self.overflow_capacity_per_user > 0
+            && (txns_len >= self.capacity_per_user || has_gap)
+        {
+            let overflow = self.overflow.get(&address);
+            let overflow_len = overflow.map_or(0, |overflow| {
+                overflow.range(current_sequence_number..).count()
+            });
+            if !overflow.map_or(false, |overflow| overflow.contains_key(&sequence_number))
+                && overflow_len >= self.overflow_capacity_per_user
+            {
+                return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
+                    format!(
+                        "overflow length: {} overflow capacity per user: {}",
+                        overflow_len, self.overflow_capacity_per_user,
+                    ),
+                );
+            }
+            return MempoolStatus::new(MempoolStatusCode::Accepted);
+        }
+        if txns_len >= self.capacity_per_user {
+            return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
+                format!(
+                    "txns length: {} capacity per user: {}",
+                    txns_len, self.capacity_per_user,
+                ),
+            );
+        }
         MempoolStatus::new(MempoolStatusCode::Accepted)
     }
 
+    /// Decides whether `txn` replaces `current_version`, the different txn stored with the same
+    /// sender and sequence number, returning the status rejecting `txn` if it doesn't. A txn only
+    /// raising the gas price of the stored one always replaces it, so senders can speed up their
+    /// txns; other conflicts are resolved by the configured policy.
+    fn resolve_conflict(
+        &self,
+        current_version: &MempoolTransaction,
+        txn: &MempoolTransaction,
+    ) -> Result<(), MempoolStatus> {
+        let gas_price_update = current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
+            && current_version.txn.payload() == txn.txn.payload()
+            && current_version.txn.expiration_timestamp_secs()
+                == txn.txn.expiration_timestamp_secs()
+            && current_version.get_gas_price() < txn.get_gas_price();
+        if gas_price_update {
+            return Ok(());
+        }
+        match self.conflict_resolution {
+            ConflictResolution::FirstWins => {}
+            ConflictResolution::GasPriceWins => {
+                if current_version.get_gas_price() < txn.get_gas_price() {
+                    return Ok(());
+                }
+            }
+            ConflictResolution::ClientWins => {
+                if txn.client_submitted && !current_version.client_submitted {
+                    return Ok(());
+                }
+            }
+        }
+        Err(
+            MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(format!(
+                "Failed to update gas price to {}, conflicting txn kept under {} resolution",
+                txn.get_gas_price(),
+                self.conflict_resolution.as_str(),
+            )),
+        )
+    }
+
+    /// Swaps `txn` in for the stored txn with the same sender and sequence number. The swap
+    /// bypasses the capacity checks, as it takes no more room, so it can't fail halfway and lose
+    /// the stored txn.
+    fn replace(&mut self, txn: MempoolTransaction, current_sequence_number: u64) {
+        let address = txn.get_sender();
+        let sequence_number = txn.get_sequence_number();
+        let txns = match self.transactions.get_mut(&address) {
+            Some(txns) => txns,
+            None => return,
+        };
+        if let Some(current_version) = txns.remove(&sequence_number) {
+            self.index_remove(&current_version);
+        }
+        self.system_ttl_index.insert(&txn);
+        self.expiration_time_index.insert(&txn);
+        self.digest_index.insert(txn.digest);
+        if let Some(txns) = self.transactions.get_mut(&address) {
+            txns.insert(sequence_number, txn);
+        }
+        self.track_indices();
+        self.unparked_txns += self.process_ready_transactions(&address, current_sequence_number);
+    }
+
+    /// Holds `txn` in its account's overflow queue, replacing an overflowed txn with the same
+    /// sequence number.
+    fn insert_overflow(
+        &mut self,
+        txn: MempoolTransaction,
+        current_sequence_number: u64,
+    ) -> MempoolStatus {
+        let overflow = self
+            .overflow
+            .entry(txn.get_sender())
+            .or_insert_with(AccountTransactions::new);
+        *overflow = overflow.split_off(&current_sequence_number);
+        let sequence_number = txn.get_sequence_number();
+        if !overflow.contains_key(&sequence_number)
+            && overflow.len() >= self.overflow_capacity_per_user
+        {
+            return MempoolStatus::new(MempoolStatusCode::TooManyTransactions).with_message(
+                format!(
+                    "overflow length: {} overflow capacity per user: {}",
+                    overflow.len(),
+                    self.overflow_capacity_per_user,
+                ),
+            );
+        }
+        overflow.insert(sequence_number, txn);
+        self.track_indices();
+        MempoolStatus::new(MempoolStatusCode::Accepted)
+    }
+
+    /// Moves an account's overflowed txns into the store in sequence number order, for as long as
+    /// they follow on from the account's stored txns, the account is below `capacity_per_user`
+    /// and mempool is below `capacity`.
+    fn promote_overflow(&mut self, address: &AccountAddress, current_sequence_number: u64) {
+        let overflow = match self.overflow.get_mut(address) {
+            Some(overflow) => overflow,
+            None => return,
+        };
+        *overflow = overflow.split_off(&current_sequence_number);
+        let txns = self
+            .transactions
+            .entry(*address)
+            .or_insert_with(AccountTransactions::new);
+        while txns.len() < self.capacity_per_user && self.system_ttl_index.size() < self.capacity {
+            let sequence_number = match overflow.keys().next() {
+                Some(sequence_number) => *sequence_number,
+                None => break,
+            };
+            if sequence_number != current_sequence_number
+                && !txns.contains_key(&(sequence_number - 1))
+            {
+                break;
+            }
+            if let Some(txn) = overflow.remove(&sequence_number) {
+                if !txns.contains_key(&sequence_number) {
+                    self.system_ttl_index.insert(&txn);
+                    self.expiration_time_index.insert(&txn);
+                    self.digest_index.insert(txn.digest);
+                    txns.insert(sequence_number, txn);
+                }
+            }
+        }
+        if overflow.is_empty() {
+            self.overflow.remove(address);
+        }
+        self.track_indices();
+    }
+
     fn track_indices(&self) {
         counters::core_mempool_index_size(
             counters::SYSTEM_TTL_INDEX_LABEL,
@@ -177,6 +418,10 @@ impl TransactionStore {
             counters::TIMELINE_INDEX_LABEL,
             self.timeline_index.size(),
         );
+        counters::core_mempool_index_size(
+            counters::OVERFLOW_INDEX_LABEL,
+            self.overflow.values().map(AccountTransactions::len).sum(),
+        );
     }
 
     /// Checks if Mempool is full.
@@ -197,12 +442,12 @@ impl TransactionStore {
                     .get_mut(&address)
                     .and_then(|txns| txns.remove(&sequence_number))
                 {
-                    debug!(
-                        LogSchema::new(LogEntry::MempoolFullEvictedTxn).txns(TxnsLog::new_txn(
+                    debug!(LogSchema::new(LogEntry::MempoolFullEvictedTxn)
+                        .txns(TxnsLog::new_txn(
                             txn.get_sender(),
                             txn.get_sequence_number()
                         ))
-                    );
+                        .trace_id(txn.trace_id));
                     self.index_remove(&txn);
                 }
             }
@@ -216,7 +461,7 @@ impl TransactionStore {
     /// (this handles both cases where, (1) txn is first possible txn for an account and (2) the
     /// previous txn is committed).
     /// 2. The txn before this is ready for broadcast but not yet committed.
-    fn check_txn_ready(&mut self, txn: &MempoolTransaction, curr_sequence_number: u64) -> bool {
+    fn check_txn_ready(&self, txn: &MempoolTransaction, curr_sequence_number: u64) -> bool {
         let tx_sequence_number = txn.get_sequence_number();
         if tx_sequence_number == curr_sequence_number {
             return true;
@@ -241,14 +486,20 @@ impl TransactionStore {
     ///   should be included in both the PriorityIndex (ordering for Consensus) and
     ///   TimelineIndex (txns for SharedMempool).
     /// - Other txns are considered to be "non-ready" and should be added to ParkingLotIndex.
+    ///
+    /// Returns the number of txns moved out of the parking lot.
     fn process_ready_transactions(
         &mut self,
         address: &AccountAddress,
         current_sequence_number: u64,
-    ) {
+    ) -> usize {
+        let mut unparked_txns = 0;
         if let Some(txns) = self.transactions.get_mut(&address) {
             let mut sequence_number = current_sequence_number;
             while let Some(txn) = txns.get_mut(&sequence_number) {
+                if self.parking_lot_index.contains(address, &sequence_number) {
+                    unparked_txns += 1;
+                }
                 self.priority_index.insert(txn);
 
                 if txn.timeline_state == TimelineState::NotReady {
@@ -276,9 +527,11 @@ impl TransactionStore {
                 first_ready_seq_num = current_sequence_number,
                 last_ready_seq_num = sequence_number,
                 num_parked_txns = parking_lot_txns,
+                num_unparked_txns = unparked_txns,
             );
             self.track_indices();
         }
+        unparked_txns
     }
 
     fn clean_committed_transactions(&mut self, address: &AccountAddress, sequence_number: u64) {
@@ -294,7 +547,11 @@ impl TransactionStore {
 
             let mut rm_txns = TxnsLog::new();
             for transaction in txns_for_removal.values() {
-                rm_txns.add(transaction.get_sender(), transaction.get_sequence_number());
+                rm_txns.add_traced(
+                    transaction.get_sender(),
+                    transaction.get_sequence_number(),
+                    transaction.trace_id,
+                );
                 self.index_remove(transaction);
             }
             trace!(
@@ -315,15 +572,21 @@ impl TransactionStore {
         account_sequence_number: u64,
     ) {
         self.clean_committed_transactions(account, account_sequence_number);
+        self.promote_overflow(account, account_sequence_number);
         self.process_ready_transactions(account, account_sequence_number);
     }
 
     pub(crate) fn reject_transaction(&mut self, account: &AccountAddress, _sequence_number: u64) {
         info!("Rejecting transactions for account {}", account);
+        self.overflow.remove(account);
         if let Some(txns) = self.transactions.remove(&account) {
             let mut txns_log = TxnsLog::new();
             for transaction in txns.values() {
-                txns_log.add(transaction.get_sender(), transaction.get_sequence_number());
+                txns_log.add_traced(
+                    transaction.get_sender(),
+                    transaction.get_sequence_number(),
+                    transaction.trace_id,
+                );
                 self.index_remove(&transaction);
             }
             debug!(LogSchema::new(LogEntry::CleanRejectedTxn).txns(txns_log));
@@ -338,44 +601,12 @@ impl TransactionStore {
         self.priority_index.remove(&txn);
         self.timeline_index.remove(&txn);
         self.parking_lot_index.remove(&txn);
+        self.digest_index.remove(&txn.digest);
         self.track_indices();
     }
 
-    /// Read `count` transactions from timeline since `timeline_id`.
-    /// Returns block of transactions and new last_timeline_id.
-    pub(crate) fn read_timeline(
-        &mut self,
-        timeline_id: u64,
-        count: usize,
-    ) -> (Vec<SignedTransaction>, u64) {
-        let mut batch = vec![];
-        let mut last_timeline_id = timeline_id;
-        for (address, sequence_number) in self.timeline_index.read_timeline(timeline_id, count) {
-            if let Some(txn) = self
-                .transactions
-                .get_mut(&address)
-                .and_then(|txns| txns.get(&sequence_number))
-            {
-                batch.push(txn.txn.clone());
-                if let TimelineState::Ready(timeline_id) = txn.timeline_state {
-                    last_timeline_id = timeline_id;
-                }
-            }
-        }
-        (batch, last_timeline_id)
-    }
-
-    pub(crate) fn timeline_range(&mut self, start_id: u64, end_id: u64) -> Vec<SignedTransaction> {
-        self.timeline_index
-            .timeline_range(start_id, end_id)
-            .iter()
-            .filter_map(|(account, sequence_number)| {
-                self.transactions
-                    .get(account)
-                    .and_then(|txns| txns.get(sequence_number))
-                    .map(|txn| txn.txn.clone())
-            })
-            .collect()
+    pub(crate) fn timeline_reader(&self) -> TimelineReader {
+        self.timeline_index.reader()
     }
 
     /// Garbage collect old transactions.
@@ -419,6 +650,10 @@ impl TransactionStore {
         counters::CORE_MEMPOOL_GC_EVENT_COUNT
             .with_label_values(&[metric_label])
             .inc();
+        let _timer = counters::CORE_MEMPOOL_GC_SWEEP_DURATION
+            .with_label_values(&[metric_label])
+            .start_timer();
+        let mut evicted = 0;
 
         let mut gc_txns = index.gc(now);
         // sort the expired txns by order of sequence number per account
@@ -450,7 +685,7 @@ impl TransactionStore {
                     };
                     let account = txn.get_sender();
                     let sequence_number = txn.get_sequence_number();
-                    gc_txns_log.add_with_status(account, sequence_number, status);
+                    gc_txns_log.add_with_status(account, sequence_number, txn.trace_id, status);
                     if let Some(&creation_time) = metrics_cache.get(&(account, sequence_number)) {
                         if let Ok(time_delta) = SystemTime::now().duration_since(creation_time) {
                             counters::CORE_MEMPOOL_GC_LATENCY
@@ -461,14 +696,114 @@ impl TransactionStore {
 
                     // remove txn
                     self.index_remove(&txn);
+                    evicted += 1;
                 }
             }
         }
 
+        // overflowed txns are in no index, so check their expiration directly
+        for txns in self.overflow.values_mut() {
+            txns.retain(|_, txn| {
+                let expiration_time = if by_system_ttl {
+                    txn.expiration_time
+                } else {
+                    Duration::from_secs(txn.txn.expiration_timestamp_secs())
+                };
+                let expired = expiration_time < now;
+                if expired {
+                    gc_txns_log.add_traced(
+                        txn.get_sender(),
+                        txn.get_sequence_number(),
+                        txn.trace_id,
+                    );
+                    evicted += 1;
+                }
+                !expired
+            });
+        }
+        self.overflow.retain(|_, txns| !txns.is_empty());
+        counters::CORE_MEMPOOL_GC_EVICTED_COUNT
+            .with_label_values(&[metric_label])
+            .inc_by(evicted);
+
         debug!(LogSchema::event_log(LogEntry::GCRemoveTxns, log_event).txns(gc_txns_log));
         self.track_indices();
     }
 
+    /// Share of the mempool capacity in use, between 0 and 1.
+    pub(crate) fn fill_ratio(&self) -> f64 {
+        if self.capacity == 0 {
+            return 1.0;
+        }
+        (self.system_ttl_index.size() as f64 / self.capacity as f64).min(1.0)
+    }
+
+    /// Returns true if a txn of the account with the given sequence number is stored, in the
+    /// overflow queue included.
+    pub(crate) fn contains(&self, address: &AccountAddress, sequence_number: u64) -> bool {
+        [&self.transactions, &self.overflow].iter().any(|txns| {
+            txns.get(address)
+                .map_or(false, |txns| txns.contains_key(&sequence_number))
+        })
+    }
+
+    /// Returns the sequence number following the highest one of the account's stored txns, those
+    /// in the overflow queue included, if any are stored.
+    pub(crate) fn get_next_sequence_number(&self, address: &AccountAddress) -> Option<u64> {
+        [&self.transactions, &self.overflow]
+            .iter()
+            .filter_map(|txns| txns.get(address)?.keys().next_back())
+            .max()
+            .map(|sequence_number| sequence_number + 1)
+    }
+
+    /// Returns all stored txns, those in the overflow queue included.
+    pub(crate) fn get_all(&self) -> Vec<SignedTransaction> {
+        self.transactions
+            .values()
+            .chain(self.overflow.values())
+            .flat_map(|txns| txns.values().map(|txn| txn.txn.clone()))
+            .collect()
+    }
+
+    /// Returns all stored txns with their metadata, those in the overflow queue included.
+    pub(crate) fn get_all_with_metadata(&self) -> Vec<MempoolTransaction> {
+        self.transactions
+            .values()
+            .chain(self.overflow.values())
+            .flat_map(|txns| txns.values().cloned())
+            .collect()
+    }
+
+    /// Sets the ranking score of a stored txn, moving it in the priority queue if it's in it.
+    pub(crate) fn update_ranking_score(
+        &mut self,
+        address: &AccountAddress,
+        sequence_number: u64,
+        ranking_score: u64,
+    ) {
+        if let Some(txn) = self
+            .overflow
+            .get_mut(address)
+            .and_then(|txns| txns.get_mut(&sequence_number))
+        {
+            txn.ranking_score = ranking_score;
+        }
+        if let Some(txn) = self
+            .transactions
+            .get_mut(address)
+            .and_then(|txns| txns.get_mut(&sequence_number))
+        {
+            if self.priority_index.contains(txn) {
+                self.priority_index.remove(txn);
+                txn.ranking_score = ranking_score;
+                self.priority_index.insert(txn);
+            } else {
+                txn.ranking_score = ranking_score;
+            }
+        }
+    }
+
     pub(crate) fn iter_queue(&self) -> PriorityQueueIter {
         self.priority_index.iter()
     }
@@ -479,14 +814,14 @@ impl TransactionStore {
     ) -> TxnsLog {
         let mut txns_log = TxnsLog::new();
         for (account, txns) in self.transactions.iter() {
-            for (seq_num, _txn) in txns.iter() {
+            for (seq_num, txn) in txns.iter() {
                 let status = if self.parking_lot_index.contains(account, seq_num) {
                     "parked"
                 } else {
                     "ready"
                 };
                 let timestamp = metrics_cache.get(&(*account, *seq_num)).cloned();
-                txns_log.add_full_metadata(*account, *seq_num, status, timestamp);
+                txns_log.add_full_metadata(*account, *seq_num, txn.trace_id, status, timestamp);
             }
         }
         txns_log
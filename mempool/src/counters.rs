@@ -2,15 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_config::{config::PeerNetworkId, network_id::NetworkId};
+use diem_infallible::Mutex;
 use diem_metrics::{
     register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
     register_int_gauge, register_int_gauge_vec, DurationHistogram, HistogramTimer, HistogramVec,
     IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
-use diem_types::PeerId;
 use once_cell::sync::Lazy;
 use short_hex_str::AsShortHexStr;
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 // Core mempool index labels
 pub const PRIORITY_INDEX_LABEL: &str = "priority";
@@ -18,6 +18,7 @@ pub const EXPIRATION_TIME_INDEX_LABEL: &str = "expiration";
 pub const SYSTEM_TTL_INDEX_LABEL: &str = "system_ttl";
 pub const TIMELINE_INDEX_LABEL: &str = "timeline";
 pub const PARKING_LOT_INDEX_LABEL: &str = "parking_lot";
+pub const OVERFLOW_INDEX_LABEL: &str = "overflow";
 
 // Core mempool commit stages labels
 pub const GET_BLOCK_STAGE_LABEL: &str = "get_block";
@@ -45,6 +46,14 @@ pub const REQUEST_SUCCESS_LABEL: &str = "success";
 pub const FETCH_SEQ_NUM_LABEL: &str = "storage_fetch";
 pub const VM_VALIDATION_LABEL: &str = "vm_validation";
 
+// Gas schedule update result labels
+pub const REPRICED_LABEL: &str = "repriced";
+pub const REJECTED_LABEL: &str = "rejected";
+
+// Conflict resolution outcome labels
+pub const CONFLICT_REPLACED_LABEL: &str = "replaced";
+pub const CONFLICT_KEPT_LABEL: &str = "kept";
+
 // Txn process result labels
 pub const CLIENT_LABEL: &str = "client";
 pub const SUCCESS_LABEL: &str = "success";
@@ -66,11 +75,15 @@ pub const START_LABEL: &str = "start";
 // Mempool network msg failure type labels:
 pub const BROADCAST_TXNS: &str = "broadcast_txns";
 pub const ACK_TXNS: &str = "ack_txns";
+pub const BROADCAST_DIGESTS: &str = "broadcast_digests";
+pub const ACK_DIGESTS: &str = "ack_digests";
+pub const PROTOCOL_VERSION: &str = "protocol_version";
 
 // Broadcast/ACK type labels
 pub const EXPIRED_BROADCAST_LABEL: &str = "expired";
 pub const RETRY_BROADCAST_LABEL: &str = "retry";
 pub const BACKPRESSURE_BROADCAST_LABEL: &str = "backpressure";
+pub const DIGEST_BROADCAST_LABEL: &str = "digest";
 
 // ACK direction labels
 pub const RECEIVED_LABEL: &str = "received";
@@ -80,6 +93,217 @@ pub const SENT_LABEL: &str = "sent";
 pub const INVALID_REQUEST_ID: &str = "invalid_req_id";
 pub const UNKNOWN_PEER: &str = "unknown_peer";
 
+/// Max number of peers that get their own value for the peer label of per-peer metrics.
+/// All other peers are aggregated under `OTHER_PEERS_LABEL`, so that nodes with many peers
+/// don't blow up metric cardinality.
+pub const MAX_LABELED_PEERS: usize = 10;
+pub const OTHER_PEERS_LABEL: &str = "other";
+
+/// Per-peer metric vec, whose label values are removed once a peer stops being a top peer
+#[derive(Clone, Copy)]
+enum PeerMetric {
+    Histogram(&'static Lazy<HistogramVec>),
+    Counter(&'static Lazy<IntCounterVec>),
+}
+
+impl PartialEq for PeerMetric {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (PeerMetric::Histogram(a), PeerMetric::Histogram(b)) => std::ptr::eq(*a, *b),
+            (PeerMetric::Counter(a), PeerMetric::Counter(b)) => std::ptr::eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl PeerMetric {
+    fn remove_label_values(&self, labels: &[String]) {
+        let labels: Vec<_> = labels.iter().map(String::as_str).collect();
+        // An error only means the label values were never set
+        let _ = match self {
+            PeerMetric::Histogram(vec) => vec.remove_label_values(&labels),
+            PeerMetric::Counter(vec) => vec.remove_label_values(&labels),
+        };
+    }
+}
+
+/// Tracks the most active peers, which are the only ones broken down individually in metrics
+struct TopPeers {
+    capacity: usize,
+    activity: HashMap<PeerNetworkId, u64>,
+    top: Vec<PeerNetworkId>,
+    /// Label values of the per-peer metrics set for each top peer
+    peer_label_values: HashMap<PeerNetworkId, Vec<(PeerMetric, Vec<String>)>>,
+}
+
+impl TopPeers {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            activity: HashMap::new(),
+            top: Vec::new(),
+            peer_label_values: HashMap::new(),
+        }
+    }
+
+    /// Records activity for `peer` and returns whether it is currently one of the top peers
+    fn record(&mut self, peer: &PeerNetworkId) -> bool {
+        let count = {
+            let count = self.activity.entry(peer.clone()).or_insert(0);
+            *count += 1;
+            *count
+        };
+        if self.top.contains(peer) {
+            return true;
+        }
+        if self.top.len() < self.capacity {
+            self.top.push(peer.clone());
+            return true;
+        }
+
+        // Replace the least active top peer once `peer` has overtaken it
+        let activity = &self.activity;
+        let least_active = self
+            .top
+            .iter()
+            .enumerate()
+            .map(|(idx, top_peer)| (idx, activity.get(top_peer).copied().unwrap_or(0)))
+            .min_by_key(|(_, count)| *count);
+        match least_active {
+            Some((idx, min_count)) if count > min_count => {
+                let evicted = std::mem::replace(&mut self.top[idx], peer.clone());
+                self.remove_label_values(&evicted);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records activity for `peer` and returns its label values for `metric`: its network and
+    /// bounded peer label, followed by `extra_labels`
+    fn label_values(
+        &mut self,
+        peer: &PeerNetworkId,
+        metric: PeerMetric,
+        extra_labels: &[&str],
+    ) -> Vec<String> {
+        let is_top = self.record(peer);
+        let peer_label = if is_top {
+            peer.peer_id().short_str().to_string()
+        } else {
+            OTHER_PEERS_LABEL.to_string()
+        };
+        let mut labels = vec![peer.raw_network_id().as_str().to_string(), peer_label];
+        labels.extend(extra_labels.iter().map(|label| label.to_string()));
+
+        if is_top {
+            let peer_label_values = self.peer_label_values.entry(peer.clone()).or_default();
+            if !peer_label_values
+                .iter()
+                .any(|(set_metric, set_labels)| *set_metric == metric && *set_labels == labels)
+            {
+                peer_label_values.push((metric, labels.clone()));
+            }
+        }
+        labels
+    }
+
+    fn remove(&mut self, peer: &PeerNetworkId) {
+        self.activity.remove(peer);
+        self.top.retain(|top_peer| top_peer != peer);
+        self.remove_label_values(peer);
+    }
+
+    /// Removes the label values of `peer` from all per-peer metrics
+    fn remove_label_values(&mut self, peer: &PeerNetworkId) {
+        for (metric, labels) in self.peer_label_values.remove(peer).unwrap_or_default() {
+            metric.remove_label_values(&labels);
+        }
+    }
+}
+
+static TOP_PEERS: Lazy<Mutex<TopPeers>> =
+    Lazy::new(|| Mutex::new(TopPeers::new(MAX_LABELED_PEERS)));
+
+/// Calls `f` with the label values of `metric` for `peer`. The top peers stay locked meanwhile,
+/// so that label values can't be set again for a peer that was just evicted.
+fn with_peer_label_values<R>(
+    peer: &PeerNetworkId,
+    metric: PeerMetric,
+    extra_labels: &[&str],
+    f: impl FnOnce(&[&str]) -> R,
+) -> R {
+    let mut top_peers = TOP_PEERS.lock();
+    let labels = top_peers.label_values(peer, metric, extra_labels);
+    let labels: Vec<_> = labels.iter().map(String::as_str).collect();
+    f(&labels)
+}
+
+/// Drops a disconnected peer from the per-peer metrics breakdown
+pub fn remove_peer_label(peer: &PeerNetworkId) {
+    TOP_PEERS.lock().remove(peer);
+}
+
+/// Histogram with `network` and bounded per-peer labels, along with its per-network aggregate
+pub struct PeerHistogramVec {
+    peers: &'static Lazy<HistogramVec>,
+    networks: &'static Lazy<HistogramVec>,
+}
+
+impl PeerHistogramVec {
+    pub fn observe(&self, peer: &PeerNetworkId, value: f64) {
+        self.networks
+            .with_label_values(&[peer.raw_network_id().as_str()])
+            .observe(value);
+        with_peer_label_values(peer, PeerMetric::Histogram(self.peers), &[], |labels| {
+            self.peers.with_label_values(labels).observe(value)
+        });
+    }
+
+    pub fn start_timer(&self, peer: &PeerNetworkId) -> PeerHistogramTimer {
+        let network_timer = self
+            .networks
+            .with_label_values(&[peer.raw_network_id().as_str()])
+            .start_timer();
+        let peer_timer =
+            with_peer_label_values(peer, PeerMetric::Histogram(self.peers), &[], |labels| {
+                self.peers.with_label_values(labels).start_timer()
+            });
+        PeerHistogramTimer {
+            _peer: peer_timer,
+            _network: network_timer,
+        }
+    }
+}
+
+/// Timer observing into both a per-peer histogram and its per-network aggregate when dropped
+pub struct PeerHistogramTimer {
+    _peer: HistogramTimer,
+    _network: HistogramTimer,
+}
+
+/// Counter with `network` and bounded per-peer labels, followed by any extra labels, along with
+/// its per-network aggregate
+pub struct PeerCounterVec {
+    peers: &'static Lazy<IntCounterVec>,
+    networks: &'static Lazy<IntCounterVec>,
+}
+
+impl PeerCounterVec {
+    pub fn inc(&self, peer: &PeerNetworkId, extra_labels: &[&str]) {
+        let network = peer.raw_network_id();
+        let mut network_labels = vec![network.as_str()];
+        network_labels.extend_from_slice(extra_labels);
+        self.networks.with_label_values(&network_labels).inc();
+        with_peer_label_values(
+            peer,
+            PeerMetric::Counter(self.peers),
+            extra_labels,
+            |labels| self.peers.with_label_values(labels).inc(),
+        );
+    }
+}
+
 /// Counter tracking size of various indices in core mempool
 static CORE_MEMPOOL_INDEX_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -90,6 +314,15 @@ static CORE_MEMPOOL_INDEX_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Gauge of the number of transactions core mempool holds at most
+pub static CORE_MEMPOOL_CAPACITY: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_core_mempool_capacity",
+        "Number of transactions core mempool holds at most"
+    )
+    .unwrap()
+});
+
 pub fn core_mempool_index_size(label: &'static str, size: usize) {
     CORE_MEMPOOL_INDEX_SIZE
         .with_label_values(&[label])
@@ -105,6 +338,26 @@ pub static CORE_MEMPOOL_REMOVED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter tracking number of parked txns made ready by the txns before them arriving
+pub static CORE_MEMPOOL_UNPARKED_TXNS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_core_mempool_unparked_txns_count",
+        "Number of parked txns made ready by the txns before them arriving"
+    )
+    .unwrap()
+});
+
+/// Counter tracking conflicts between different txns with the same sender and sequence number,
+/// by resolution policy and whether the stored txn was replaced or kept
+pub static CORE_MEMPOOL_CONFLICT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_core_mempool_conflict_count",
+        "Number of txns conflicting with a stored txn with the same sender and sequence number",
+        &["policy", "result"]
+    )
+    .unwrap()
+});
+
 /// Counter tracking latency of txns reaching various stages in committing
 /// (e.g. time from txn entering core mempool to being pulled in consensus block)
 pub static CORE_MEMPOOL_TXN_COMMIT_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -126,6 +379,26 @@ pub static CORE_MEMPOOL_GC_EVENT_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
        .unwrap()
 });
 
+/// Counter for number of txns removed by garbage-collection, by GC type
+pub static CORE_MEMPOOL_GC_EVICTED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_core_mempool_gc_evicted_count",
+        "Number of txns removed from core mempool by garbage-collection",
+        &["type"]
+    )
+    .unwrap()
+});
+
+/// Histogram of how long a garbage-collection sweep of core mempool takes, by GC type
+pub static CORE_MEMPOOL_GC_SWEEP_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_core_mempool_gc_sweep_duration",
+        "Duration of a garbage-collection sweep of core mempool, in seconds",
+        &["type"]
+    )
+    .unwrap()
+});
+
 /// Counter tracking time for how long a transaction stayed in core-mempool before being garbage-collected
 pub static CORE_MEMPOOL_GC_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
@@ -195,7 +468,7 @@ pub fn shared_mempool_event_inc(event: &'static str) {
 }
 
 /// Counter for tracking e2e latency for mempool to process txn submission requests from clients and peers
-static PROCESS_TXN_SUBMISSION_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+static PROCESS_TXN_SUBMISSION_LATENCY_VEC: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "diem_shared_mempool_request_latency",
         "Latency of mempool processing txn submission requests",
@@ -204,10 +477,29 @@ static PROCESS_TXN_SUBMISSION_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
-pub fn process_txn_submit_latency_timer(network: &str, sender: &str) -> HistogramTimer {
-    PROCESS_TXN_SUBMISSION_LATENCY
-        .with_label_values(&[network, sender])
-        .start_timer()
+static PROCESS_TXN_SUBMISSION_LATENCY_NETWORK_VEC: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_shared_mempool_request_latency_by_network",
+        "Latency of mempool processing txn submission requests, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub static PROCESS_TXN_SUBMISSION_LATENCY: PeerHistogramVec = PeerHistogramVec {
+    peers: &PROCESS_TXN_SUBMISSION_LATENCY_VEC,
+    networks: &PROCESS_TXN_SUBMISSION_LATENCY_NETWORK_VEC,
+};
+
+pub fn process_client_txn_submit_latency_timer() -> PeerHistogramTimer {
+    PeerHistogramTimer {
+        _peer: PROCESS_TXN_SUBMISSION_LATENCY_VEC
+            .with_label_values(&[CLIENT_LABEL, CLIENT_LABEL])
+            .start_timer(),
+        _network: PROCESS_TXN_SUBMISSION_LATENCY_NETWORK_VEC
+            .with_label_values(&[CLIENT_LABEL])
+            .start_timer(),
+    }
 }
 
 /// Tracks latency of different stages of txn processing (e.g. vm validation, storage read)
@@ -221,7 +513,7 @@ pub static PROCESS_TXN_BREAKDOWN_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
 });
 
 /// Counter for tracking latency for mempool to broadcast to a peer
-pub static SHARED_MEMPOOL_BROADCAST_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+static SHARED_MEMPOOL_BROADCAST_LATENCY_VEC: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "diem_broadcast_latency",
         "Latency of mempool executing broadcast to another peer",
@@ -230,8 +522,22 @@ pub static SHARED_MEMPOOL_BROADCAST_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+static SHARED_MEMPOOL_BROADCAST_LATENCY_NETWORK_VEC: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_broadcast_latency_by_network",
+        "Latency of mempool executing broadcast to another peer, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub static SHARED_MEMPOOL_BROADCAST_LATENCY: PeerHistogramVec = PeerHistogramVec {
+    peers: &SHARED_MEMPOOL_BROADCAST_LATENCY_VEC,
+    networks: &SHARED_MEMPOOL_BROADCAST_LATENCY_NETWORK_VEC,
+};
+
 /// Counter for tracking roundtrip-time from sending a broadcast to receiving ACK for that broadcast
-pub static SHARED_MEMPOOL_BROADCAST_RTT: Lazy<HistogramVec> = Lazy::new(|| {
+static SHARED_MEMPOOL_BROADCAST_RTT_VEC: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "diem_shared_mempool_broadcast_roundtrip_latency",
         "Time elapsed between sending a broadcast and receiving an ACK for that broadcast",
@@ -240,44 +546,76 @@ pub static SHARED_MEMPOOL_BROADCAST_RTT: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
-/// Counter tracking number of mempool broadcasts that have not been ACK'ed for
+static SHARED_MEMPOOL_BROADCAST_RTT_NETWORK_VEC: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_shared_mempool_broadcast_roundtrip_latency_by_network",
+        "Time elapsed between sending a broadcast and receiving an ACK for that broadcast, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub static SHARED_MEMPOOL_BROADCAST_RTT: PeerHistogramVec = PeerHistogramVec {
+    peers: &SHARED_MEMPOOL_BROADCAST_RTT_VEC,
+    networks: &SHARED_MEMPOOL_BROADCAST_RTT_NETWORK_VEC,
+};
+
+/// Counter tracking number of mempool broadcasts that have not been ACK'ed for, per network
 static SHARED_MEMPOOL_PENDING_BROADCASTS_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_shared_mempool_pending_broadcasts_count",
         "Number of mempool broadcasts not ACK'ed for yet",
-        &["network", "recipient"]
+        &["network"]
     )
     .unwrap()
 });
 
-pub fn shared_mempool_pending_broadcasts(peer: &PeerNetworkId) -> IntGauge {
-    SHARED_MEMPOOL_PENDING_BROADCASTS_COUNT.with_label_values(&[
-        peer.raw_network_id().as_str(),
-        peer.peer_id().short_str().as_str(),
-    ])
+pub fn shared_mempool_pending_broadcasts(network_id: &NetworkId) -> IntGauge {
+    SHARED_MEMPOOL_PENDING_BROADCASTS_COUNT.with_label_values(&[network_id.as_str()])
 }
 
-static SHARED_MEMPOOL_TRANSACTIONS_PROCESSED: Lazy<IntCounterVec> = Lazy::new(|| {
+/// Counter for txns received and handled by shared mempool, by sender and by state of processing:
+/// "received", "success", or the status code of a failure
+static SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_shared_mempool_transactions_processed",
         "Number of transactions received and handled by shared mempool",
-        &[
-            "status", // state of transaction processing: "received", "success", status code from failed txn processing
-            "network", // state of transaction processing: "received", "success", status code from failed txn processing
-            "sender"   // sender of the txns
-        ]
+        &["network", "sender", "status"]
     )
     .unwrap()
 });
 
-pub fn shared_mempool_transactions_processed_inc(status: &str, network: &str, sender: &str) {
-    SHARED_MEMPOOL_TRANSACTIONS_PROCESSED
-        .with_label_values(&[status, network, sender])
-        .inc();
+static SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_shared_mempool_transactions_processed_by_network",
+        "Number of transactions received and handled by shared mempool, aggregated per network",
+        &["network", "status"]
+    )
+    .unwrap()
+});
+
+static SHARED_MEMPOOL_TRANSACTIONS_PROCESSED: PeerCounterVec = PeerCounterVec {
+    peers: &SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_VEC,
+    networks: &SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_NETWORK_VEC,
+};
+
+/// Counts a txn processed by shared mempool, sent by `sender`, or by a client if `None`
+pub fn shared_mempool_transactions_processed_inc(status: &str, sender: Option<&PeerNetworkId>) {
+    match sender {
+        Some(peer) => SHARED_MEMPOOL_TRANSACTIONS_PROCESSED.inc(peer, &[status]),
+        None => {
+            SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_VEC
+                .with_label_values(&[CLIENT_LABEL, CLIENT_LABEL, status])
+                .inc();
+            SHARED_MEMPOOL_TRANSACTIONS_PROCESSED_NETWORK_VEC
+                .with_label_values(&[CLIENT_LABEL, status])
+                .inc();
+        }
+    }
 }
 
 /// Counter for number of transactions in each mempool broadcast sent
-pub static SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+static SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE_VEC: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "diem_shared_mempool_transaction_broadcast",
         "Number of transactions in each mempool broadcast sent",
@@ -286,7 +624,21 @@ pub static SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE: Lazy<HistogramVec> = Lazy:
     .unwrap()
 });
 
-pub static SHARED_MEMPOOL_BROADCAST_TYPE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+static SHARED_MEMPOOL_BROADCAST_SIZE_NETWORK_VEC: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_shared_mempool_transaction_broadcast_by_network",
+        "Number of transactions in each mempool broadcast sent, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub static SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE: PeerHistogramVec = PeerHistogramVec {
+    peers: &SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE_VEC,
+    networks: &SHARED_MEMPOOL_BROADCAST_SIZE_NETWORK_VEC,
+};
+
+static SHARED_MEMPOOL_BROADCAST_TYPE_COUNT_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_shared_mempool_rebroadcast_count",
         "Number of various types of broadcasts executed by shared mempool",
@@ -295,7 +647,21 @@ pub static SHARED_MEMPOOL_BROADCAST_TYPE_COUNT: Lazy<IntCounterVec> = Lazy::new(
     .unwrap()
 });
 
-static SHARED_MEMPOOL_ACK_TYPE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+static SHARED_MEMPOOL_BROADCAST_TYPE_COUNT_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_shared_mempool_rebroadcast_count_by_network",
+        "Number of various types of broadcasts executed by shared mempool, aggregated per network",
+        &["network", "type"]
+    )
+    .unwrap()
+});
+
+pub static SHARED_MEMPOOL_BROADCAST_TYPE_COUNT: PeerCounterVec = PeerCounterVec {
+    peers: &SHARED_MEMPOOL_BROADCAST_TYPE_COUNT_VEC,
+    networks: &SHARED_MEMPOOL_BROADCAST_TYPE_COUNT_NETWORK_VEC,
+};
+
+static SHARED_MEMPOOL_ACK_TYPE_COUNT_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_shared_mempool_ack_count",
         "Number of various types of ACKs sent/received by shared mempool",
@@ -304,15 +670,22 @@ static SHARED_MEMPOOL_ACK_TYPE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+static SHARED_MEMPOOL_ACK_TYPE_COUNT_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_shared_mempool_ack_count_by_network",
+        "Number of various types of ACKs sent/received by shared mempool, aggregated per network",
+        &["network", "direction", "type"]
+    )
+    .unwrap()
+});
+
+static SHARED_MEMPOOL_ACK_TYPE_COUNT: PeerCounterVec = PeerCounterVec {
+    peers: &SHARED_MEMPOOL_ACK_TYPE_COUNT_VEC,
+    networks: &SHARED_MEMPOOL_ACK_TYPE_COUNT_NETWORK_VEC,
+};
+
 pub fn shared_mempool_ack_inc(peer: &PeerNetworkId, direction: &str, label: &'static str) {
-    SHARED_MEMPOOL_ACK_TYPE_COUNT
-        .with_label_values(&[
-            peer.raw_network_id().as_str(),
-            peer.peer_id().short_str().as_str(),
-            direction,
-            label,
-        ])
-        .inc();
+    SHARED_MEMPOOL_ACK_TYPE_COUNT.inc(peer, &[direction, label]);
 }
 
 static TASK_SPAWN_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -346,6 +719,16 @@ pub static VM_RECONFIG_UPDATE_FAIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter for txns re-validated after a reconfig changed the gas schedule, by result
+pub static GAS_SCHEDULE_UPDATE_TXNS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_mempool_gas_schedule_update_txns",
+        "Number of txns repriced or rejected after a reconfig changed the gas schedule",
+        &["result"]
+    )
+    .unwrap()
+});
+
 /// Counter for failed Diem network sends
 static NETWORK_SEND_FAIL: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -360,7 +743,7 @@ pub fn network_send_fail_inc(label: &'static str) {
     NETWORK_SEND_FAIL.with_label_values(&[label]).inc();
 }
 
-static UNEXPECTED_NETWORK_MSG_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+static UNEXPECTED_NETWORK_MSG_COUNT_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_mempool_unexpected_network_count",
         "Number of unexpected network msgs received",
@@ -369,10 +752,22 @@ static UNEXPECTED_NETWORK_MSG_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
-pub fn unexpected_msg_count_inc(network_id: &NetworkId, peer_id: &PeerId) {
-    UNEXPECTED_NETWORK_MSG_COUNT
-        .with_label_values(&[network_id.as_str(), peer_id.short_str().as_str()])
-        .inc();
+static UNEXPECTED_NETWORK_MSG_COUNT_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_mempool_unexpected_network_count_by_network",
+        "Number of unexpected network msgs received, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+static UNEXPECTED_NETWORK_MSG_COUNT: PeerCounterVec = PeerCounterVec {
+    peers: &UNEXPECTED_NETWORK_MSG_COUNT_VEC,
+    networks: &UNEXPECTED_NETWORK_MSG_COUNT_NETWORK_VEC,
+};
+
+pub fn unexpected_msg_count_inc(peer: &PeerNetworkId) {
+    UNEXPECTED_NETWORK_MSG_COUNT.inc(peer, &[]);
 }
 
 /// Counter for failed callback response to JSON RPC
@@ -386,7 +781,7 @@ pub static CLIENT_CALLBACK_FAIL: Lazy<IntCounter> = Lazy::new(|| {
 
 /// Counter for how many ACKs were received with an invalid request_id that this node's mempool
 /// did not send
-static INVALID_ACK_RECEIVED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+static INVALID_ACK_RECEIVED_COUNT_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_mempool_unrecognized_ack_received_count",
         "Number of ACK messages received with an invalid request_id that this node's mempool did not send",
@@ -395,14 +790,22 @@ static INVALID_ACK_RECEIVED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
         .unwrap()
 });
 
+static INVALID_ACK_RECEIVED_COUNT_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_mempool_unrecognized_ack_received_count_by_network",
+        "Number of ACK messages received with an invalid request_id that this node's mempool did not send, aggregated per network",
+        &["network", "type"]
+    )
+    .unwrap()
+});
+
+static INVALID_ACK_RECEIVED_COUNT: PeerCounterVec = PeerCounterVec {
+    peers: &INVALID_ACK_RECEIVED_COUNT_VEC,
+    networks: &INVALID_ACK_RECEIVED_COUNT_NETWORK_VEC,
+};
+
 pub fn invalid_ack_inc(peer: &PeerNetworkId, label: &'static str) {
-    INVALID_ACK_RECEIVED_COUNT
-        .with_label_values(&[
-            peer.raw_network_id().as_str(),
-            peer.peer_id().short_str().as_str(),
-            label,
-        ])
-        .inc();
+    INVALID_ACK_RECEIVED_COUNT.inc(peer, &[label]);
 }
 
 /// Counter for number of times a DB read resulted in error
@@ -514,7 +917,7 @@ pub static TASKS_PROCESS_CONSENSUS_REQUEST_EVENT: Lazy<IntCounter> = Lazy::new(|
         .unwrap()
 });
 
-pub static PEER_MANAGER_PEER_REQUESTED_BACKOFF: Lazy<IntCounterVec> = Lazy::new(|| {
+static PEER_MANAGER_PEER_REQUESTED_BACKOFF_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_mempool_peer_requested_backoff",
         "Number of backoff requests from peers",
@@ -522,3 +925,99 @@ pub static PEER_MANAGER_PEER_REQUESTED_BACKOFF: Lazy<IntCounterVec> = Lazy::new(
     )
         .unwrap()
 });
+
+static PEER_MANAGER_PEER_REQUESTED_BACKOFF_NETWORK_VEC: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_mempool_peer_requested_backoff_by_network",
+        "Number of backoff requests from peers, aggregated per network",
+        &["network"]
+    )
+    .unwrap()
+});
+
+pub static PEER_MANAGER_PEER_REQUESTED_BACKOFF: PeerCounterVec = PeerCounterVec {
+    peers: &PEER_MANAGER_PEER_REQUESTED_BACKOFF_VEC,
+    networks: &PEER_MANAGER_PEER_REQUESTED_BACKOFF_NETWORK_VEC,
+};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_top_peers_bounded() {
+        let mut top_peers = TopPeers::new(2);
+        let peer_a = PeerNetworkId::random();
+        let peer_b = PeerNetworkId::random();
+        let peer_c = PeerNetworkId::random();
+
+        assert!(top_peers.record(&peer_a));
+        assert!(top_peers.record(&peer_a));
+        assert!(top_peers.record(&peer_b));
+        // Capacity reached and peer_c is not more active than peer_b yet
+        assert!(!top_peers.record(&peer_c));
+        // peer_c overtakes peer_b
+        assert!(top_peers.record(&peer_c));
+        assert_eq!(top_peers.top.len(), 2);
+        assert!(!top_peers.top.contains(&peer_b));
+
+        top_peers.remove(&peer_a);
+        assert!(top_peers.record(&peer_b));
+    }
+
+    static TEST_PEER_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+        register_int_counter_vec!(
+            "diem_mempool_test_peer_count",
+            "Per-peer counter for tests",
+            &["network", "peer", "type"]
+        )
+        .unwrap()
+    });
+
+    fn inc(top_peers: &mut TopPeers, peer: &PeerNetworkId, label: &str) {
+        let labels = top_peers.label_values(peer, PeerMetric::Counter(&TEST_PEER_COUNT), &[label]);
+        let labels: Vec<_> = labels.iter().map(String::as_str).collect();
+        TEST_PEER_COUNT.with_label_values(&labels).inc();
+    }
+
+    /// Returns the `type` label values set for `peer_label`, with their counts
+    fn counts(peer_label: &str) -> Vec<(String, f64)> {
+        let mut counts: Vec<_> = diem_metrics::get_metric_values("diem_mempool_test_peer_count")
+            .into_iter()
+            .filter(|(labels, _)| labels["peer"] == peer_label)
+            .map(|(labels, value)| (labels["type"].clone(), value))
+            .collect();
+        counts.sort_by(|a, b| a.0.cmp(&b.0));
+        counts
+    }
+
+    #[test]
+    fn test_top_peers_label_values_removed() {
+        let mut top_peers = TopPeers::new(1);
+        let peer_a = PeerNetworkId::random();
+        let peer_b = PeerNetworkId::random();
+        let label_a = peer_a.peer_id().short_str().to_string();
+        let label_b = peer_b.peer_id().short_str().to_string();
+
+        inc(&mut top_peers, &peer_a, "x");
+        inc(&mut top_peers, &peer_a, "y");
+        inc(&mut top_peers, &peer_b, "x");
+        inc(&mut top_peers, &peer_b, "x");
+        assert_eq!(
+            counts(&label_a),
+            vec![("x".to_string(), 1.0), ("y".to_string(), 1.0)]
+        );
+        assert!(counts(&label_b).is_empty());
+
+        // peer_b overtakes peer_a, whose label values are removed on eviction
+        inc(&mut top_peers, &peer_b, "x");
+        assert!(counts(&label_a).is_empty());
+        assert_eq!(counts(&label_b), vec![("x".to_string(), 1.0)]);
+        assert!(top_peers.peer_label_values.get(&peer_a).is_none());
+
+        // Label values of a disconnected peer are removed too
+        top_peers.remove(&peer_b);
+        assert!(counts(&label_b).is_empty());
+        assert!(top_peers.peer_label_values.is_empty());
+    }
+}
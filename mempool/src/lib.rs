@@ -57,16 +57,21 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+#[cfg(feature = "chaos")]
+pub use shared_mempool::chaos::{ChaosConfig, ChaosHooks};
 pub use shared_mempool::{
     bootstrap, network,
+    script_filter::{ScriptFilter, ScriptFilterRules, SCRIPT_FILTER_FILE},
     types::{
-        gen_mempool_reconfig_subscription, CommitNotification, CommitResponse,
-        CommittedTransaction, ConsensusRequest, ConsensusResponse, MempoolClientSender,
-        SubmissionStatus, TransactionExclusion,
+        gen_mempool_commit_subscription, gen_mempool_reconfig_subscription, CommitResponse,
+        CommittedTransaction, ConsensusRequest, ConsensusResponse, MempoolClientRequest,
+        MempoolClientSender, MempoolError, MempoolPeerRequest, MempoolPeerSender, MempoolSnapshot,
+        MempoolSnapshotRequest, MempoolSnapshotSender, PeerBroadcastStatus, SnapshotTransaction,
+        SubmissionStatus, SubmissionStatusBundle, TransactionExclusion, MEMPOOL_SNAPSHOT_VERSION,
     },
 };
 #[cfg(any(test, feature = "fuzzing"))]
-pub use tests::{fuzzing, mocks};
+pub use tests::{bench, fuzzing, mocks};
 
 mod core_mempool;
 mod counters;
@@ -1,19 +1,27 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::shared_mempool::{
-    peer_manager::BatchId,
-    types::{CommitNotification, ConsensusRequest},
-};
+use crate::shared_mempool::{peer_manager::BatchId, types::ConsensusRequest};
 use anyhow::Error;
 use diem_config::{config::PeerNetworkId, network_id::NetworkId};
 use diem_logger::Schema;
-use diem_types::{account_address::AccountAddress, on_chain_config::OnChainConfigPayload};
+use diem_types::{
+    account_address::AccountAddress,
+    on_chain_config::OnChainConfigPayload,
+    transaction::{SignedTransaction, TraceId},
+};
 use serde::Serialize;
 use std::{fmt, time::SystemTime};
+use subscription_service::CommitEvent;
 
 pub struct TxnsLog {
-    txns: Vec<(AccountAddress, u64, Option<String>, Option<SystemTime>)>,
+    txns: Vec<(
+        AccountAddress,
+        u64,
+        Option<TraceId>,
+        Option<String>,
+        Option<SystemTime>,
+    )>,
 }
 
 impl TxnsLog {
@@ -23,28 +31,59 @@ impl TxnsLog {
 
     pub fn new_txn(account: AccountAddress, seq_num: u64) -> Self {
         Self {
-            txns: vec![(account, seq_num, None, None)],
+            txns: vec![(account, seq_num, None, None, None)],
+        }
+    }
+
+    /// Creates a log of the txns in `txns`, with their trace IDs.
+    pub fn new_traced(txns: &[SignedTransaction]) -> Self {
+        let mut txns_log = Self::new();
+        for txn in txns {
+            txns_log.add_traced(txn.sender(), txn.sequence_number(), txn.trace_id());
         }
+        txns_log
     }
 
     pub fn add(&mut self, account: AccountAddress, seq_num: u64) {
-        self.txns.push((account, seq_num, None, None));
+        self.txns.push((account, seq_num, None, None, None));
     }
 
-    pub fn add_with_status(&mut self, account: AccountAddress, seq_num: u64, status: &str) {
+    pub fn add_traced(&mut self, account: AccountAddress, seq_num: u64, trace_id: TraceId) {
         self.txns
-            .push((account, seq_num, Some(status.to_string()), None));
+            .push((account, seq_num, Some(trace_id), None, None));
+    }
+
+    pub fn add_with_status(
+        &mut self,
+        account: AccountAddress,
+        seq_num: u64,
+        trace_id: TraceId,
+        status: &str,
+    ) {
+        self.txns.push((
+            account,
+            seq_num,
+            Some(trace_id),
+            Some(status.to_string()),
+            None,
+        ));
     }
 
     pub fn add_full_metadata(
         &mut self,
         account: AccountAddress,
         seq_num: u64,
+        trace_id: TraceId,
         status: &str,
         timestamp: Option<SystemTime>,
     ) {
-        self.txns
-            .push((account, seq_num, Some(status.to_string()), timestamp));
+        self.txns.push((
+            account,
+            seq_num,
+            Some(trace_id),
+            Some(status.to_string()),
+            timestamp,
+        ));
     }
 }
 
@@ -52,8 +91,11 @@ impl fmt::Display for TxnsLog {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut txns = "".to_string();
 
-        for (account, seq_num, status, timestamp) in self.txns.iter() {
+        for (account, seq_num, trace_id, status, timestamp) in self.txns.iter() {
             let mut txn = format!("{}:{}", account, seq_num);
+            if let Some(trace_id) = trace_id {
+                txn += &format!(":{}", trace_id)
+            }
             if let Some(status) = status {
                 txn += &format!(":{}", status)
             }
@@ -81,11 +123,13 @@ pub struct LogSchema<'a> {
     reconfig_update: Option<OnChainConfigPayload>,
     #[schema(display)]
     txns: Option<TxnsLog>,
+    #[schema(display)]
+    trace_id: Option<TraceId>,
     account: Option<AccountAddress>,
     #[schema(display)]
     consensus_msg: Option<&'a ConsensusRequest>,
-    #[schema(display)]
-    state_sync_msg: Option<&'a CommitNotification>,
+    #[schema(debug)]
+    commit_event: Option<&'a CommitEvent>,
     network_level: Option<usize>,
     upstream_network: Option<&'a NetworkId>,
     #[schema(debug)]
@@ -112,8 +156,9 @@ impl<'a> LogSchema<'a> {
             reconfig_update: None,
             account: None,
             txns: None,
+            trace_id: None,
             consensus_msg: None,
-            state_sync_msg: None,
+            commit_event: None,
             network_level: None,
             upstream_network: None,
             batch_id: None,
@@ -149,6 +194,9 @@ pub enum LogEntry {
     UpstreamNetwork,
     UnexpectedNetworkMsg,
     MempoolSnapshot,
+    ProtocolVersion,
+    ScriptFilter,
+    PeerBroadcastControl,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -163,6 +211,7 @@ pub enum LogEvent {
     Received,
     Process,
     VMUpdateFail,
+    GasScheduleUpdate,
 
     CallbackFail,
     NetworkSendFail,
@@ -172,4 +221,14 @@ pub enum LogEvent {
     ClientExpiration,
 
     Success,
+    Update,
+
+    // mempool snapshot events
+    Export,
+    Import,
+
+    // peer broadcast control events
+    Pause,
+    Resume,
+    Flush,
 }
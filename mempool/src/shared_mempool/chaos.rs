@@ -0,0 +1,106 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Faults injected into shared mempool, to test that the broadcast/ACK protocol holds up on an
+//! unreliable network: ACKs dropped, broadcasts delayed, incoming batches duplicated and incoming
+//! messages reordered.
+//!
+//! Faults can only be injected in tests and in builds with the `chaos` feature. In other builds
+//! `ChaosHooks` is empty and shared mempool never calls into it.
+
+#[cfg(any(test, feature = "chaos"))]
+use crate::network::MempoolSyncMsg;
+#[cfg(any(test, feature = "chaos"))]
+use diem_config::network_id::NodeNetworkId;
+#[cfg(any(test, feature = "chaos"))]
+use diem_infallible::Mutex;
+#[cfg(any(test, feature = "chaos"))]
+use network::protocols::network::Event;
+#[cfg(any(test, feature = "chaos"))]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(any(test, feature = "chaos"))]
+use std::time::Duration;
+
+/// Faults to inject. All of them are off by default.
+#[cfg(any(test, feature = "chaos"))]
+#[derive(Clone, Debug, Default)]
+pub struct ChaosConfig {
+    /// Fraction of incoming broadcast ACKs that are dropped, between 0 and 1
+    pub drop_ack_rate: f64,
+    /// Delay added to every scheduled broadcast
+    pub broadcast_delay: Duration,
+    /// Fraction of incoming broadcast batches that are handled twice, between 0 and 1
+    pub duplicate_batch_rate: f64,
+    /// Fraction of incoming messages that are held back and handled after the next one, between
+    /// 0 and 1
+    pub reorder_rate: f64,
+    /// Seed the faults are drawn with, so a failing run can be replayed
+    pub seed: u64,
+}
+
+/// Handle to the faults injected into a shared mempool.
+#[derive(Default)]
+pub struct ChaosHooks {
+    #[cfg(any(test, feature = "chaos"))]
+    state: Mutex<ChaosState>,
+}
+
+#[cfg(any(test, feature = "chaos"))]
+#[derive(Default)]
+struct ChaosState {
+    config: ChaosConfig,
+    rng: Option<StdRng>,
+    /// Messages held back to be handled after the next incoming message
+    held: Vec<(NodeNetworkId, Event<MempoolSyncMsg>)>,
+}
+
+#[cfg(any(test, feature = "chaos"))]
+impl ChaosHooks {
+    /// Starts injecting the faults of `config`, replacing the ones injected so far.
+    pub fn set(&self, config: ChaosConfig) {
+        let mut state = self.state.lock();
+        state.rng = Some(StdRng::seed_from_u64(config.seed));
+        state.config = config;
+    }
+
+    /// Delay to add to the next scheduled broadcast.
+    pub(crate) fn broadcast_delay(&self) -> Duration {
+        self.state.lock().config.broadcast_delay
+    }
+
+    /// Applies the faults to an incoming network event, returning the events to handle in its
+    /// place, in order. Only messages are tampered with: peer events are handled as is.
+    pub(crate) fn intercept(
+        &self,
+        network_id: NodeNetworkId,
+        event: Event<MempoolSyncMsg>,
+    ) -> Vec<(NodeNetworkId, Event<MempoolSyncMsg>)> {
+        let (peer_id, msg) = match event {
+            Event::Message(peer_id, msg) => (peer_id, msg),
+            event => return vec![(network_id, event)],
+        };
+        let mut state = self.state.lock();
+        let ChaosState { config, rng, held } = &mut *state;
+        let rng = match rng {
+            Some(rng) => rng,
+            None => return vec![(network_id, Event::Message(peer_id, msg))],
+        };
+
+        let is_ack = matches!(msg, MempoolSyncMsg::BroadcastTransactionsResponse { .. });
+        if is_ack && rng.gen::<f64>() < config.drop_ack_rate {
+            return vec![];
+        }
+        let mut events = vec![];
+        let is_batch = matches!(msg, MempoolSyncMsg::BroadcastTransactionsRequest { .. });
+        if is_batch && rng.gen::<f64>() < config.duplicate_batch_rate {
+            events.push((network_id.clone(), Event::Message(peer_id, msg.clone())));
+        }
+        events.push((network_id, Event::Message(peer_id, msg)));
+        if held.is_empty() && rng.gen::<f64>() < config.reorder_rate {
+            *held = events;
+            return vec![];
+        }
+        events.append(held);
+        events
+    }
+}
@@ -10,23 +10,23 @@ use crate::{
     network::{MempoolNetworkEvents, MempoolSyncMsg},
     shared_mempool::{
         tasks,
-        types::{notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification},
+        types::{
+            notify_subscribers, MempoolClientRequest, MempoolPeerRequest, MempoolSnapshot,
+            MempoolSnapshotRequest, ScheduledBroadcast, SharedMempool, SharedMempoolNotification,
+            MEMPOOL_SNAPSHOT_VERSION,
+        },
     },
-    CommitNotification, ConsensusRequest, SubmissionStatus,
+    ConsensusRequest,
 };
 use ::network::protocols::network::Event;
-use anyhow::Result;
 use bounded_executor::BoundedExecutor;
 use channel::diem_channel;
 use diem_config::{config::PeerNetworkId, network_id::NodeNetworkId};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
-use diem_types::{
-    mempool_status::MempoolStatus, on_chain_config::OnChainConfigPayload,
-    transaction::SignedTransaction, vm_status::DiscardedVMStatus,
-};
+use diem_types::on_chain_config::OnChainConfigPayload;
 use futures::{
-    channel::{mpsc, oneshot},
+    channel::mpsc,
     stream::{select_all, FuturesUnordered},
     StreamExt,
 };
@@ -34,6 +34,7 @@ use std::{
     sync::Arc,
     time::{Duration, SystemTime},
 };
+use subscription_service::CommitNotification;
 use tokio::{runtime::Handle, time::interval};
 use tokio_stream::wrappers::IntervalStream;
 use vm_validator::vm_validator::TransactionValidation;
@@ -43,13 +44,12 @@ pub(crate) async fn coordinator<V>(
     mut smp: SharedMempool<V>,
     executor: Handle,
     network_events: Vec<(NodeNetworkId, MempoolNetworkEvents)>,
-    mut client_events: mpsc::Receiver<(
-        SignedTransaction,
-        oneshot::Sender<Result<SubmissionStatus>>,
-    )>,
+    mut client_events: mpsc::Receiver<MempoolClientRequest>,
     mut consensus_requests: mpsc::Receiver<ConsensusRequest>,
-    mut state_sync_requests: mpsc::Receiver<CommitNotification>,
+    mut commit_events: mpsc::Receiver<CommitNotification>,
     mut mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    mut snapshot_requests: mpsc::Receiver<MempoolSnapshotRequest>,
+    mut peer_requests: mpsc::Receiver<MempoolPeerRequest>,
 ) where
     V: TransactionValidation,
 {
@@ -66,16 +66,16 @@ pub(crate) async fn coordinator<V>(
 
     // Use a BoundedExecutor to restrict only `workers_available` concurrent
     // worker tasks that can process incoming transactions.
-    let workers_available = smp.config.shared_mempool_max_concurrent_inbound_syncs;
+    let workers_available = smp.shared_config.read().max_concurrent_inbound_syncs;
     let bounded_executor = BoundedExecutor::new(workers_available, executor.clone());
 
     loop {
         let _timer = counters::MAIN_LOOP.start_timer();
         ::futures::select! {
-            (msg, callback) = client_events.select_next_some() => {
-                debug!("handle_client_event");
+            request = client_events.select_next_some() => {
+                debug!("handle_client_request");
                 counters::COORDINATOR_HANDLE_CLIENT_EVENT.inc();
-                handle_client_event(&mut smp, &bounded_executor, msg, callback).await;
+                handle_client_request(&mut smp, &bounded_executor, request).await;
             },
             // 0L TODO: execute mempool tasks in a bounded execution with capacity.
             msg = consensus_requests.select_next_some() => {
@@ -87,22 +87,35 @@ pub(crate) async fn coordinator<V>(
               handle_consensus_request(&mut smp, &bounded_executor, msg).await;
                 // tasks::process_consensus_request(&smp.mempool, msg).await;
             }
-            msg = state_sync_requests.select_next_some() => {
-                debug!("state_sync_requests");
+            notification = commit_events.select_next_some() => {
+                debug!("commit_events");
                 counters::COORDINATOR_HANDLE_STATE_SYNC_EVENT.inc();
-                handle_state_sync_request(&mut smp, msg);
+                handle_commit_notification(&mut smp, notification);
             }
             config_update = mempool_reconfig_events.select_next_some() => {
                 debug!("handle_mempool_reconfig_event");
                 counters::COORDINATOR_HANDLE_MEMPOOL_RECONFIG_EVENT.inc();
                 handle_mempool_reconfig_event(&mut smp, &bounded_executor, config_update).await;
             },
+            request = snapshot_requests.select_next_some() => {
+                debug!("handle_snapshot_request");
+                handle_snapshot_request(&mut smp, &bounded_executor, request).await;
+            },
+            request = peer_requests.select_next_some() => {
+                debug!("handle_peer_request");
+                handle_peer_request(&smp, request);
+            },
             (peer, backoff) = scheduled_broadcasts.select_next_some() => {
                 tasks::execute_broadcast(peer, backoff, &mut smp, &mut scheduled_broadcasts, executor.clone());
             },
             (network_id, event) = events.select_next_some() => {
                 // dbg!("handle_event", &event.);
 
+                #[cfg(any(test, feature = "chaos"))]
+                for (network_id, event) in smp.chaos.intercept(network_id, event) {
+                    handle_event(&executor, &bounded_executor, &mut scheduled_broadcasts, &mut smp, network_id, event).await;
+                }
+                #[cfg(not(any(test, feature = "chaos")))]
                 handle_event(&executor, &bounded_executor, &mut scheduled_broadcasts, &mut smp, network_id, event).await;
             },
             complete => break,
@@ -114,11 +127,10 @@ pub(crate) async fn coordinator<V>(
     ));
 }
 
-async fn handle_client_event<V>(
+async fn handle_client_request<V>(
     smp: &mut SharedMempool<V>,
     bounded_executor: &BoundedExecutor,
-    msg: SignedTransaction,
-    callback: oneshot::Sender<anyhow::Result<(MempoolStatus, Option<DiscardedVMStatus>)>>,
+    request: MempoolClientRequest,
 ) where
     V: TransactionValidation,
 {
@@ -129,14 +141,38 @@ async fn handle_client_event<V>(
     // This timer measures how long it took for the task to go from scheduled to started.
     let task_start_timer =
         counters::task_spawn_latency_timer(counters::CLIENT_EVENT_LABEL, counters::START_LABEL);
-    bounded_executor
-        .spawn(tasks::process_client_transaction_submission(
-            smp.clone(),
-            msg,
-            callback,
-            task_start_timer,
-        ))
-        .await;
+    match request {
+        MempoolClientRequest::SubmitTransaction(txn, callback) => {
+            bounded_executor
+                .spawn(tasks::process_client_transaction_submission(
+                    smp.clone(),
+                    txn,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
+        MempoolClientRequest::ValidateTransaction(txn, callback) => {
+            bounded_executor
+                .spawn(tasks::process_client_transaction_validation(
+                    smp.clone(),
+                    txn,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
+        MempoolClientRequest::AllocateSequenceNumber(sender, callback) => {
+            bounded_executor
+                .spawn(tasks::process_sequence_number_allocation(
+                    smp.clone(),
+                    sender,
+                    callback,
+                    task_start_timer,
+                ))
+                .await;
+        }
+    }
 }
 
 //////// 0L ////////
@@ -160,13 +196,17 @@ async fn handle_consensus_request<V>(
         .await;
 }
 
-fn handle_state_sync_request<V>(smp: &mut SharedMempool<V>, msg: CommitNotification)
+fn handle_commit_notification<V>(smp: &mut SharedMempool<V>, notification: CommitNotification)
 where
     V: TransactionValidation,
 {
     let _timer =
         counters::task_spawn_latency_timer(counters::STATE_SYNC_EVENT_LABEL, counters::SPAWN_LABEL);
-    tokio::spawn(tasks::process_state_sync_request(smp.mempool.clone(), msg));
+    smp.validator.read().notify_commit();
+    tokio::spawn(tasks::process_commit_notification(
+        smp.mempool.clone(),
+        notification,
+    ));
 }
 
 async fn handle_mempool_reconfig_event<V>(
@@ -187,10 +227,76 @@ async fn handle_mempool_reconfig_event<V>(
         .spawn(tasks::process_config_update(
             config_update,
             smp.validator.clone(),
+            smp.mempool.clone(),
         ))
         .await;
 }
 
+async fn handle_snapshot_request<V>(
+    smp: &mut SharedMempool<V>,
+    bounded_executor: &BoundedExecutor,
+    request: MempoolSnapshotRequest,
+) where
+    V: TransactionValidation,
+{
+    match request {
+        MempoolSnapshotRequest::Export(callback) => {
+            let transactions = smp.mempool.lock().export_txns();
+            info!(
+                LogSchema::event_log(LogEntry::MempoolSnapshot, LogEvent::Export),
+                num_txns = transactions.len()
+            );
+            let snapshot = MempoolSnapshot {
+                version: MEMPOOL_SNAPSHOT_VERSION,
+                transactions,
+            };
+            if callback.send(snapshot).is_err() {
+                error!(LogSchema::event_log(
+                    LogEntry::MempoolSnapshot,
+                    LogEvent::CallbackFail
+                ));
+            }
+        }
+        MempoolSnapshotRequest::Import(snapshot, callback) => {
+            bounded_executor
+                .spawn(tasks::process_snapshot_import(
+                    smp.clone(),
+                    snapshot,
+                    callback,
+                ))
+                .await;
+        }
+    }
+}
+
+fn handle_peer_request<V>(smp: &SharedMempool<V>, request: MempoolPeerRequest)
+where
+    V: TransactionValidation,
+{
+    let peer_manager = &smp.peer_manager;
+    let (statuses, callback) = match request {
+        MempoolPeerRequest::List(callback) => (peer_manager.broadcast_statuses(None), callback),
+        MempoolPeerRequest::Pause(peer_id, callback) => {
+            peer_manager.pause_broadcasts(peer_id);
+            (peer_manager.broadcast_statuses(Some(peer_id)), callback)
+        }
+        MempoolPeerRequest::Resume(peer_id, callback) => {
+            peer_manager.resume_broadcasts(peer_id);
+            (peer_manager.broadcast_statuses(Some(peer_id)), callback)
+        }
+        MempoolPeerRequest::FlushRetries(peer_id, callback) => {
+            peer_manager.flush_retries(peer_id);
+            (peer_manager.broadcast_statuses(Some(peer_id)), callback)
+        }
+    };
+    if callback.send(statuses).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::PeerBroadcastControl,
+            LogEvent::CallbackFail
+        ));
+    }
+}
+
 async fn handle_event<V>(
     executor: &Handle,
     bounded_executor: &BoundedExecutor,
@@ -211,6 +317,7 @@ async fn handle_event<V>(
                 .peer(&peer)
                 .is_upstream_peer(is_upstream_peer));
             notify_subscribers(SharedMempoolNotification::PeerStateChange, &smp.subscribers);
+            tasks::announce_protocol_version(smp, &peer);
             if is_new_peer && is_upstream_peer {
                 tasks::execute_broadcast(peer, false, smp, scheduled_broadcasts, executor.clone());
             }
@@ -274,35 +381,80 @@ async fn handle_event<V>(
                         ack_timestamp,
                     );
                 }
+                MempoolSyncMsg::ProtocolVersion { version } => {
+                    let peer = PeerNetworkId(network_id, peer_id);
+                    debug!(
+                        LogSchema::new(LogEntry::ProtocolVersion).peer(&peer),
+                        version = version
+                    );
+                    smp.peer_manager.set_protocol_version(&peer, version);
+                }
+                MempoolSyncMsg::BroadcastDigestsRequest {
+                    request_id,
+                    digests,
+                } => {
+                    let peer = PeerNetworkId(network_id, peer_id);
+                    tasks::process_digest_broadcast(smp, request_id, digests, peer);
+                }
+                MempoolSyncMsg::BroadcastDigestsResponse {
+                    request_id,
+                    missing,
+                } => {
+                    let peer_manager = smp.peer_manager.clone();
+                    peer_manager.process_digest_response(
+                        PeerNetworkId(network_id, peer_id),
+                        request_id,
+                        missing,
+                        smp,
+                    );
+                }
             }
         }
         Event::RpcRequest(peer_id, _msg, _res_tx) => {
-            counters::unexpected_msg_count_inc(&network_id.network_id(), &peer_id);
+            let peer = PeerNetworkId(network_id, peer_id);
+            counters::unexpected_msg_count_inc(&peer);
             sample!(
                 SampleRate::Duration(Duration::from_secs(60)),
-                warn!(LogSchema::new(LogEntry::UnexpectedNetworkMsg)
-                    .peer(&PeerNetworkId(network_id, peer_id)))
+                warn!(LogSchema::new(LogEntry::UnexpectedNetworkMsg).peer(&peer))
             );
         }
     }
 }
 
-/// Garbage collect all expired transactions by SystemTTL.
-pub(crate) async fn gc_coordinator(mempool: Arc<Mutex<CoreMempool>>, gc_interval_ms: u64) {
+/// Garbage collect all expired transactions, by SystemTTL and by client-specified expiration
+/// time. The interval between sweeps shrinks from `max_interval` while mempool is empty to
+/// `min_interval` while it is full.
+pub(crate) async fn gc_coordinator(
+    mempool: Arc<Mutex<CoreMempool>>,
+    min_interval: Duration,
+    max_interval: Duration,
+) {
     info!(LogSchema::event_log(LogEntry::GCRuntime, LogEvent::Start));
-    let mut interval = IntervalStream::new(interval(Duration::from_millis(gc_interval_ms)));
-    while let Some(_interval) = interval.next().await {
+    loop {
+        let fill_ratio = {
+            let mut mempool = mempool.lock();
+            mempool.gc();
+            // expired txns are otherwise only removed when consensus pulls or commits a block,
+            // so they linger on quiet networks and on nodes that don't take part in consensus
+            mempool.gc_by_expiration_time(diem_infallible::duration_since_epoch());
+            mempool.fill_ratio()
+        };
         sample!(
             SampleRate::Duration(Duration::from_secs(60)),
             info!(LogSchema::event_log(LogEntry::GCRuntime, LogEvent::Live))
         );
-        mempool.lock().gc();
+        tokio::time::sleep(gc_interval(fill_ratio, min_interval, max_interval)).await;
     }
+}
 
-    error!(LogSchema::event_log(
-        LogEntry::GCRuntime,
-        LogEvent::Terminated
-    ));
+/// Interval until the next GC sweep of a mempool `fill_ratio` full.
+pub(crate) fn gc_interval(
+    fill_ratio: f64,
+    min_interval: Duration,
+    max_interval: Duration,
+) -> Duration {
+    let range = max_interval.checked_sub(min_interval).unwrap_or_default();
+    max_interval - range.mul_f64(fill_ratio.max(0.0).min(1.0))
 }
 
 /// Periodically logs a snapshot of transactions in core mempool.
@@ -315,3 +467,22 @@ pub(crate) async fn snapshot_job(mempool: Arc<Mutex<CoreMempool>>, snapshot_inte
         debug!(LogSchema::new(LogEntry::MempoolSnapshot).txns(snapshot));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_gc_interval() {
+        let min = Duration::from_millis(100);
+        let max = Duration::from_millis(1_000);
+        assert_eq!(gc_interval(0.0, min, max), max);
+        assert_eq!(gc_interval(0.5, min, max), Duration::from_millis(550));
+        assert_eq!(gc_interval(1.0, min, max), min);
+
+        // Equal bounds disable adaptation
+        assert_eq!(gc_interval(0.5, max, max), max);
+        // Misconfigured bounds keep the max interval
+        assert_eq!(gc_interval(0.5, max, min), min);
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub(crate) mod chaos;
 pub mod network;
 mod runtime;
 pub(crate) mod types;
@@ -9,4 +10,5 @@ pub use runtime::bootstrap;
 pub(crate) use runtime::start_shared_mempool;
 mod coordinator;
 pub(crate) mod peer_manager;
+pub(crate) mod script_filter;
 pub(crate) mod tasks;
@@ -5,6 +5,7 @@
 
 use crate::counters;
 use channel::message_queues::QueueStyle;
+use diem_crypto::HashValue;
 use diem_metrics::IntCounterVec;
 use diem_types::{transaction::SignedTransaction, PeerId};
 use fail::fail_point;
@@ -34,8 +35,31 @@ pub enum MempoolSyncMsg {
         /// A backpressure signal from the recipient when it is overwhelmed (e.g., mempool is full).
         backoff: bool,
     },
+    /// Announces the highest mempool protocol version the sender speaks. Sent to every new peer;
+    /// peers that never announce one are assumed to speak version 1.
+    ProtocolVersion { version: u32 },
+    /// Broadcast request issued by a sender to a peer speaking version 2 or later: only the
+    /// digests of the batch are sent, and the full transactions follow for those the receiver
+    /// is missing.
+    BroadcastDigestsRequest {
+        request_id: Vec<u8>,
+        digests: Vec<HashValue>,
+    },
+    /// Reply to a digest broadcast, listing the digests the receiver does not have. A receiver
+    /// missing none of them replies with `BroadcastTransactionsResponse` instead.
+    BroadcastDigestsResponse {
+        request_id: Vec<u8>,
+        missing: Vec<HashValue>,
+    },
 }
 
+/// Mempool protocol version spoken by this node. Version 2 adds digest broadcasts.
+pub const MEMPOOL_PROTOCOL_VERSION: u32 = 2;
+/// Version assumed for peers that have not announced one.
+pub const MEMPOOL_LEGACY_PROTOCOL_VERSION: u32 = 1;
+/// First version that understands `BroadcastDigestsRequest`.
+pub const MEMPOOL_DIGEST_PROTOCOL_VERSION: u32 = 2;
+
 /// Protocol id for mempool direct-send calls.
 pub const MEMPOOL_DIRECT_SEND_PROTOCOL: &[u8] = b"/diem/direct-send/0.1.0/mempool/0.1.0";
 
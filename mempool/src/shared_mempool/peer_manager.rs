@@ -2,32 +2,43 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    core_mempool::transaction_digest,
     counters,
-    logging::{LogEntry, LogEvent, LogSchema},
-    network::MempoolSyncMsg,
+    logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
+    network::{MempoolSyncMsg, MEMPOOL_DIGEST_PROTOCOL_VERSION, MEMPOOL_LEGACY_PROTOCOL_VERSION},
     shared_mempool::{
         tasks,
-        types::{notify_subscribers, SharedMempool, SharedMempoolNotification},
+        types::{
+            notify_subscribers, PeerBroadcastStatus, SharedMempool, SharedMempoolNotification,
+        },
     },
 };
-use diem_config::config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType};
-use diem_infallible::Mutex;
+use diem_config::{
+    config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType, SharedMempoolConfig},
+    network_id::NetworkId,
+};
+use diem_crypto::HashValue;
+use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
-use diem_types::transaction::SignedTransaction;
+use diem_types::{transaction::SignedTransaction, PeerId};
 use itertools::Itertools;
 use netcore::transport::ConnectionOrigin;
 use network::transport::ConnectionMetadata;
 use serde::{Deserialize, Serialize};
-use short_hex_str::AsShortHexStr;
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ops::Add,
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 use vm_validator::vm_validator::TransactionValidation;
 
 const PRIMARY_NETWORK_PREFERENCE: usize = 0;
+/// Weight given to the newest ACK when updating a peer's smoothed RTT and retry ratio.
+const ACK_SMOOTHING_FACTOR: f64 = 0.125;
+/// Smoothed retry ratio above which a peer is considered lossy and gets smaller batches.
+const LOSSY_PEER_RETRY_RATIO: f64 = 0.1;
 
 /// Peers that receive txns from this node.
 pub(crate) type PeerSyncStates = HashMap<PeerNetworkId, PeerSyncState>;
@@ -35,21 +46,24 @@ pub(crate) type PeerSyncStates = HashMap<PeerNetworkId, PeerSyncState>;
 /// State of last sync with peer:
 /// `timeline_id` is position in log of ready transactions
 /// `is_alive` - is connection healthy
+/// `protocol_version` - mempool protocol version announced by the peer
 #[derive(Clone, Debug)]
 pub(crate) struct PeerSyncState {
     pub timeline_id: u64,
     pub is_alive: bool,
     pub broadcast_info: BroadcastInfo,
     pub metadata: ConnectionMetadata,
+    pub protocol_version: u32,
 }
 
 impl PeerSyncState {
-    pub fn new(metadata: ConnectionMetadata) -> Self {
+    pub fn new(metadata: ConnectionMetadata, batch_size: usize) -> Self {
         PeerSyncState {
             timeline_id: 0,
             is_alive: true,
-            broadcast_info: BroadcastInfo::new(),
+            broadcast_info: BroadcastInfo::new(batch_size),
             metadata,
+            protocol_version: MEMPOOL_LEGACY_PROTOCOL_VERSION,
         }
     }
 }
@@ -58,8 +72,13 @@ pub(crate) struct PeerManager {
     /// Role of the current node
     role: RoleType,
     mempool_config: MempoolConfig,
+    /// Reloadable settings of `mempool_config.shared_mempool`, read from here instead
+    shared_config: Arc<RwLock<SharedMempoolConfig>>,
     peer_states: Mutex<PeerSyncStates>,
     prioritized_peers: Mutex<Vec<PeerNetworkId>>,
+    /// Peers the operator paused broadcasts to, kept apart from `peer_states` to outlive
+    /// reconnects.
+    paused_peers: Mutex<HashSet<PeerId>>,
 }
 
 /// Identifier for a broadcasted batch of txns.
@@ -89,28 +108,80 @@ pub struct BroadcastInfo {
     pub retry_batches: BTreeSet<BatchId>,
     // Whether broadcasting to this peer is in backoff mode, e.g. broadcasting at longer intervals.
     pub backoff_mode: bool,
+    // Number of txns read from the timeline for a fresh broadcast, adapted to this peer's ACKs.
+    pub batch_size: usize,
+    // Exponentially smoothed ACK round-trip time, `None` until the first ACK arrives.
+    pub smoothed_rtt: Option<Duration>,
+    // Exponentially smoothed fraction of ACKs that asked for a retry.
+    pub retry_ratio: f64,
 }
 
 impl BroadcastInfo {
-    fn new() -> Self {
+    fn new(batch_size: usize) -> Self {
         Self {
             sent_batches: BTreeMap::new(),
             retry_batches: BTreeSet::new(),
             backoff_mode: false,
+            batch_size,
+            smoothed_rtt: None,
+            retry_ratio: 0.0,
         }
     }
+
+    /// Folds an ACK into this peer's smoothed RTT and retry ratio, then resizes the next fresh
+    /// batch: halved for peers that are slow (RTT above half the ACK timeout) or lossy, grown by a
+    /// quarter for peers that answer well within the timeout.
+    fn record_ack(&mut self, rtt: Duration, retry: bool, config: &SharedMempoolConfig) {
+        let smoothed_rtt = match self.smoothed_rtt {
+            Some(prev) => {
+                prev.mul_f64(1.0 - ACK_SMOOTHING_FACTOR) + rtt.mul_f64(ACK_SMOOTHING_FACTOR)
+            }
+            None => rtt,
+        };
+        self.smoothed_rtt = Some(smoothed_rtt);
+        let retry_sample = if retry { 1.0 } else { 0.0 };
+        self.retry_ratio =
+            self.retry_ratio * (1.0 - ACK_SMOOTHING_FACTOR) + retry_sample * ACK_SMOOTHING_FACTOR;
+
+        let ack_timeout = Duration::from_millis(config.ack_timeout_ms);
+        if retry || self.retry_ratio > LOSSY_PEER_RETRY_RATIO || smoothed_rtt > ack_timeout / 2 {
+            self.set_batch_size(self.batch_size / 2, config);
+        } else if smoothed_rtt < ack_timeout / 4 {
+            self.set_batch_size(
+                self.batch_size + std::cmp::max(self.batch_size / 4, 1),
+                config,
+            );
+        }
+    }
+
+    /// A broadcast that expired without any ACK is treated like a slow ACK.
+    fn record_expired(&mut self, config: &SharedMempoolConfig) {
+        self.set_batch_size(self.batch_size / 2, config);
+    }
+
+    fn set_batch_size(&mut self, batch_size: usize, config: &SharedMempoolConfig) {
+        self.batch_size = batch_size
+            .min(config.max_batch_size)
+            .max(config.min_batch_size);
+    }
 }
 
 impl PeerManager {
-    pub fn new(role: RoleType, mempool_config: MempoolConfig) -> Self {
+    pub fn new(
+        role: RoleType,
+        mempool_config: MempoolConfig,
+        shared_config: Arc<RwLock<SharedMempoolConfig>>,
+    ) -> Self {
         // Primary network is always chosen at initialization.
         counters::upstream_network(PRIMARY_NETWORK_PREFERENCE);
         info!(LogSchema::new(LogEntry::UpstreamNetwork).network_level(PRIMARY_NETWORK_PREFERENCE));
         Self {
             role,
             mempool_config,
+            shared_config,
             peer_states: Mutex::new(PeerSyncStates::new()),
             prioritized_peers: Mutex::new(Vec::new()),
+            paused_peers: Mutex::new(HashSet::new()),
         }
     }
 
@@ -122,13 +193,18 @@ impl PeerManager {
             // If we have a new peer, let's insert new data, otherwise, let's just update the current state
             if is_new_peer {
                 counters::active_upstream_peers(&peer.raw_network_id()).inc();
-                peer_states.insert(peer, PeerSyncState::new(metadata));
+                peer_states.insert(
+                    peer,
+                    PeerSyncState::new(metadata, self.shared_config.read().batch_size),
+                );
             } else if let Some(peer_state) = peer_states.get_mut(&peer) {
                 if !peer_state.is_alive {
                     counters::active_upstream_peers(&peer.raw_network_id()).inc();
                 }
                 peer_state.is_alive = true;
                 peer_state.metadata = metadata;
+                // The new connection may be to a different build, wait for it to announce again.
+                peer_state.protocol_version = MEMPOOL_LEGACY_PROTOCOL_VERSION;
             }
         }
         drop(peer_states);
@@ -142,13 +218,24 @@ impl PeerManager {
     pub fn disable_peer(&self, peer: PeerNetworkId) {
         error!("shared mempool disable peer {:?}", &peer);
         // Remove all state on the peer, and start over
-        self.peer_states.lock().remove(&peer);
+        let mut peer_states = self.peer_states.lock();
+        peer_states.remove(&peer);
+        update_pending_broadcasts_gauge(&peer_states, &peer.raw_network_id());
+        drop(peer_states);
         counters::active_upstream_peers(&peer.raw_network_id()).dec();
+        counters::remove_peer_label(&peer);
 
         // Always update prioritized peers to be in line with peer states
         self.update_prioritized_peers();
     }
 
+    /// Records the mempool protocol version announced by `peer`.
+    pub fn set_protocol_version(&self, peer: &PeerNetworkId, version: u32) {
+        if let Some(state) = self.peer_states.lock().get_mut(peer) {
+            state.protocol_version = version;
+        }
+    }
+
     pub fn is_backoff_mode(&self, peer: &PeerNetworkId) -> bool {
         if let Some(state) = self.peer_states.lock().get(peer) {
             warn!("shared mempool is in backoff mode for peer: {:?} ", &peer);
@@ -159,6 +246,77 @@ impl PeerManager {
         }
     }
 
+    /// Stops broadcasting to `peer_id` on all networks until `resume_broadcasts` is called.
+    pub fn pause_broadcasts(&self, peer_id: PeerId) {
+        if self.paused_peers.lock().insert(peer_id) {
+            info!(
+                LogSchema::event_log(LogEntry::PeerBroadcastControl, LogEvent::Pause),
+                peer_id = peer_id.to_string(),
+                "Paused broadcasts to peer."
+            );
+        }
+    }
+
+    pub fn resume_broadcasts(&self, peer_id: PeerId) {
+        if self.paused_peers.lock().remove(&peer_id) {
+            info!(
+                LogSchema::event_log(LogEntry::PeerBroadcastControl, LogEvent::Resume),
+                peer_id = peer_id.to_string(),
+                "Resumed broadcasts to peer."
+            );
+        }
+    }
+
+    pub fn is_paused(&self, peer: &PeerNetworkId) -> bool {
+        self.paused_peers.lock().contains(&peer.peer_id())
+    }
+
+    /// Drops the sent and retry batches of `peer_id` on all networks, and takes it out of backoff
+    /// mode. The txns of dropped batches are not broadcast to the peer again, and its batch size
+    /// starts over from the configured one.
+    pub fn flush_retries(&self, peer_id: PeerId) {
+        let batch_size = self.shared_config.read().batch_size;
+        let mut peer_states = self.peer_states.lock();
+        let mut networks = vec![];
+        for (peer, state) in peer_states.iter_mut() {
+            if peer.peer_id() == peer_id {
+                info!(
+                    LogSchema::event_log(LogEntry::PeerBroadcastControl, LogEvent::Flush)
+                        .peer(peer),
+                    num_sent_batches = state.broadcast_info.sent_batches.len(),
+                    num_retry_batches = state.broadcast_info.retry_batches.len(),
+                    "Flushed broadcast retries of peer."
+                );
+                state.broadcast_info = BroadcastInfo::new(batch_size);
+                networks.push(peer.raw_network_id());
+            }
+        }
+        for network_id in networks {
+            update_pending_broadcasts_gauge(&peer_states, &network_id);
+        }
+    }
+
+    /// Returns the broadcast state of `peer_id` on all networks, or of all peers if `None`.
+    pub fn broadcast_statuses(&self, peer_id: Option<PeerId>) -> Vec<PeerBroadcastStatus> {
+        let paused_peers = self.paused_peers.lock().clone();
+        let peer_states = self.peer_states.lock();
+        peer_states
+            .iter()
+            .filter(|(peer, _)| peer_id.map_or(true, |peer_id| peer.peer_id() == peer_id))
+            .map(|(peer, state)| PeerBroadcastStatus {
+                peer_id: peer.peer_id(),
+                network_id: peer.raw_network_id(),
+                paused: paused_peers.contains(&peer.peer_id()),
+                is_alive: state.is_alive,
+                backoff_mode: state.broadcast_info.backoff_mode,
+                pending_broadcasts: state.broadcast_info.sent_batches.len(),
+                retry_broadcasts: state.broadcast_info.retry_batches.len(),
+                batch_size: state.broadcast_info.batch_size,
+            })
+            .sorted_by(|a, b| (&a.network_id, a.peer_id).cmp(&(&b.network_id, b.peer_id)))
+            .collect()
+    }
+
     pub fn execute_broadcast<V>(
         &self,
         peer: PeerNetworkId,
@@ -174,6 +332,11 @@ impl PeerManager {
         // Start timer for tracking broadcast latency.
         let start_time = Instant::now();
 
+        // Paused peers stay scheduled, so broadcasts pick up again once they are resumed.
+        if self.is_paused(&peer) {
+            return;
+        }
+
         let mut peer_states = self.peer_states.lock();
         let state = if let Some(state) = peer_states.get_mut(&peer) {
             state
@@ -212,7 +375,9 @@ impl PeerManager {
         let transactions: Vec<SignedTransaction>;
         let mut metric_label = None;
         {
-            let mut mempool = smp.mempool.lock();
+            // The timeline is read without locking mempool, so broadcasts to different peers
+            // don't wait for each other.
+            let timeline = &smp.timeline;
 
             // Sync peer's pending broadcasts with latest mempool state.
             // A pending broadcast might become empty if the corresponding txns were committed through
@@ -222,7 +387,7 @@ impl PeerManager {
                 .sent_batches
                 .clone()
                 .into_iter()
-                .filter(|(id, _batch)| !mempool.timeline_range(id.0, id.1).is_empty())
+                .filter(|(id, _batch)| !timeline.timeline_range(id.0, id.1).is_empty())
                 .collect::<BTreeMap<BatchId, SystemTime>>();
 
             // Check for batch to rebroadcast:
@@ -230,13 +395,12 @@ impl PeerManager {
             // 2. Batch that an earlier ACK marked as retriable
             let mut pending_broadcasts = 0;
             let mut expired = None;
+            let ack_timeout = Duration::from_millis(self.shared_config.read().ack_timeout_ms);
 
             // Find earliest batch in timeline index that expired.
             // Note that state.broadcast_info.sent_batches is ordered in decreasing order in the timeline index
             for (batch, sent_time) in state.broadcast_info.sent_batches.iter() {
-                let deadline = sent_time.add(Duration::from_millis(
-                    self.mempool_config.shared_mempool_ack_timeout_ms,
-                ));
+                let deadline = sent_time.add(ack_timeout);
                 if SystemTime::now().duration_since(deadline).is_ok() {
                     expired = Some(batch);
                 } else {
@@ -263,15 +427,13 @@ impl PeerManager {
                         Some(counters::RETRY_BROADCAST_LABEL)
                     };
 
-                    let txns = mempool.timeline_range(id.0, id.1);
+                    let txns = timeline.timeline_range(id.0, id.1);
                     (*id, txns)
                 }
                 None => {
                     // Fresh broadcast
-                    let (txns, new_timeline_id) = mempool.read_timeline(
-                        state.timeline_id,
-                        self.mempool_config.shared_mempool_batch_size,
-                    );
+                    let (txns, new_timeline_id) =
+                        timeline.read_timeline(state.timeline_id, state.broadcast_info.batch_size);
                     (BatchId(state.timeline_id, new_timeline_id), txns)
                 }
             };
@@ -291,14 +453,37 @@ impl PeerManager {
             .clone();
 
         let num_txns = transactions.len();
-        if let Err(e) = network_sender.send_to(
-            peer.peer_id(),
-            MempoolSyncMsg::BroadcastTransactionsRequest {
-                request_id: bcs::to_bytes(&batch_id).expect("failed BCS serialization of batch ID"),
-                transactions,
-            },
-        ) {
-            counters::network_send_fail_inc(counters::BROADCAST_TXNS);
+        trace!(
+            LogSchema::new(LogEntry::BroadcastTransaction)
+                .peer(&peer)
+                .batch_id(&batch_id)
+                .txns(TxnsLog::new_traced(&transactions)),
+            "broadcasting batch"
+        );
+        let request_id = bcs::to_bytes(&batch_id).expect("failed BCS serialization of batch ID");
+        // Peers that speak digests only get the full transactions they ask for, see
+        // `process_digest_response`.
+        let send_digests = state.protocol_version >= MEMPOOL_DIGEST_PROTOCOL_VERSION;
+        let (msg, failure_label) = if send_digests {
+            let digests = transactions.iter().map(transaction_digest).collect();
+            (
+                MempoolSyncMsg::BroadcastDigestsRequest {
+                    request_id,
+                    digests,
+                },
+                counters::BROADCAST_DIGESTS,
+            )
+        } else {
+            (
+                MempoolSyncMsg::BroadcastTransactionsRequest {
+                    request_id,
+                    transactions,
+                },
+                counters::BROADCAST_TXNS,
+            )
+        };
+        if let Err(e) = network_sender.send_to(peer.peer_id(), msg) {
+            counters::network_send_fail_inc(failure_label);
             error!(
                 LogSchema::event_log(LogEntry::BroadcastTransaction, LogEvent::NetworkSendFail)
                     .peer(&peer)
@@ -315,6 +500,11 @@ impl PeerManager {
             .sent_batches
             .insert(batch_id, SystemTime::now());
         state.broadcast_info.retry_batches.remove(&batch_id);
+        if metric_label == Some(counters::EXPIRED_BROADCAST_LABEL) {
+            state
+                .broadcast_info
+                .record_expired(&self.shared_config.read());
+        }
         notify_subscribers(SharedMempoolNotification::Broadcast, &smp.subscribers);
 
         let latency = start_time.elapsed();
@@ -324,32 +514,118 @@ impl PeerManager {
                 .batch_id(&batch_id)
                 .backpressure(scheduled_backoff)
         );
-        let peer_id = peer.peer_id().short_str();
-        let network_id = peer.raw_network_id();
-        counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE
-            .with_label_values(&[network_id.as_str(), peer_id.as_str()])
-            .observe(num_txns as f64);
-        counters::shared_mempool_pending_broadcasts(&peer)
-            .set(state.broadcast_info.sent_batches.len() as i64);
-        counters::SHARED_MEMPOOL_BROADCAST_LATENCY
-            .with_label_values(&[network_id.as_str(), peer_id.as_str()])
-            .observe(latency.as_secs_f64());
+        update_pending_broadcasts_gauge(&peer_states, &peer.raw_network_id());
+        if !send_digests {
+            counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE.observe(&peer, num_txns as f64);
+        }
+        counters::SHARED_MEMPOOL_BROADCAST_LATENCY.observe(&peer, latency.as_secs_f64());
         if let Some(label) = metric_label {
-            counters::SHARED_MEMPOOL_BROADCAST_TYPE_COUNT
-                .with_label_values(&[network_id.as_str(), peer_id.as_str(), label])
-                .inc();
+            counters::SHARED_MEMPOOL_BROADCAST_TYPE_COUNT.inc(&peer, &[label]);
         }
         if scheduled_backoff {
             counters::SHARED_MEMPOOL_BROADCAST_TYPE_COUNT
-                .with_label_values(&[
-                    network_id.as_str(),
-                    peer_id.as_str(),
-                    counters::BACKPRESSURE_BROADCAST_LABEL,
-                ])
-                .inc();
+                .inc(&peer, &[counters::BACKPRESSURE_BROADCAST_LABEL]);
+        }
+        if send_digests {
+            counters::SHARED_MEMPOOL_BROADCAST_TYPE_COUNT
+                .inc(&peer, &[counters::DIGEST_BROADCAST_LABEL]);
         }
     }
 
+    /// Executes a broadcast to every peer ahead of its schedule, so txns that just joined the
+    /// timeline don't wait for the next tick. Peers in backoff mode are skipped, and the
+    /// scheduled broadcasts go on as before.
+    pub fn execute_broadcasts<V>(&self, smp: &mut SharedMempool<V>)
+    where
+        V: TransactionValidation,
+    {
+        let peers: Vec<_> = self.peer_states.lock().keys().cloned().collect();
+        for peer in peers {
+            self.execute_broadcast(peer, false, smp);
+        }
+    }
+
+    /// Node receives the digests `peer` is missing from a digest broadcast, and sends it the
+    /// corresponding transactions under the same request ID. The batch stays pending until the
+    /// peer ACKs those transactions.
+    pub fn process_digest_response<V>(
+        &self,
+        peer: PeerNetworkId,
+        request_id_bytes: Vec<u8>,
+        missing: Vec<HashValue>,
+        smp: &mut SharedMempool<V>,
+    ) where
+        V: TransactionValidation,
+    {
+        let batch_id = if let Ok(id) = bcs::from_bytes::<BatchId>(&request_id_bytes) {
+            id
+        } else {
+            counters::invalid_ack_inc(&peer, counters::INVALID_REQUEST_ID);
+            return;
+        };
+
+        // The batch stays pending, to be broadcast again once the peer is resumed.
+        if self.is_paused(&peer) {
+            return;
+        }
+
+        match self.peer_states.lock().get(&peer) {
+            Some(state) if state.broadcast_info.sent_batches.contains_key(&batch_id) => {}
+            Some(_) => {
+                trace!(
+                    LogSchema::new(LogEntry::ReceiveACK)
+                        .peer(&peer)
+                        .batch_id(&batch_id),
+                    "batch ID does not exist or expired"
+                );
+                return;
+            }
+            None => {
+                counters::invalid_ack_inc(&peer, counters::UNKNOWN_PEER);
+                return;
+            }
+        }
+
+        // Only transactions of the announced batch are sent, whatever digests the peer asks for.
+        let missing: HashSet<HashValue> = missing.into_iter().collect();
+        let transactions: Vec<_> = smp
+            .timeline
+            .timeline_range(batch_id.0, batch_id.1)
+            .into_iter()
+            .filter(|txn| missing.contains(&transaction_digest(txn)))
+            .collect();
+        if transactions.is_empty() {
+            // The missing transactions have left mempool since the announcement, so the peer
+            // already has everything that is left of the batch.
+            self.process_broadcast_ack(peer, request_id_bytes, false, false, SystemTime::now());
+            return;
+        }
+
+        let mut network_sender = smp
+            .network_senders
+            .get_mut(&peer.network_id())
+            .expect("[shared mempool] missing network sender")
+            .clone();
+        let num_txns = transactions.len();
+        if let Err(e) = network_sender.send_to(
+            peer.peer_id(),
+            MempoolSyncMsg::BroadcastTransactionsRequest {
+                request_id: request_id_bytes,
+                transactions,
+            },
+        ) {
+            counters::network_send_fail_inc(counters::BROADCAST_TXNS);
+            error!(
+                LogSchema::event_log(LogEntry::BroadcastTransaction, LogEvent::NetworkSendFail)
+                    .peer(&peer)
+                    .error(&e.into())
+            );
+            return;
+        }
+        notify_subscribers(SharedMempoolNotification::Broadcast, &smp.subscribers);
+        counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE.observe(&peer, num_txns as f64);
+    }
+
     fn update_prioritized_peers(&self) {
         // Only do this if it's not a validator
         if self.role.is_validator() {
@@ -408,13 +684,11 @@ impl PeerManager {
                 .duration_since(sent_timestamp)
                 .expect("failed to calculate mempool broadcast RTT");
 
-            let network_id = peer.raw_network_id();
-            let peer_id = peer.peer_id().short_str();
-            counters::SHARED_MEMPOOL_BROADCAST_RTT
-                .with_label_values(&[network_id.as_str(), peer_id.as_str()])
-                .observe(rtt.as_secs_f64());
-
-            counters::shared_mempool_pending_broadcasts(&peer).dec();
+            counters::SHARED_MEMPOOL_BROADCAST_RTT.observe(&peer, rtt.as_secs_f64());
+            counters::shared_mempool_pending_broadcasts(&peer.raw_network_id()).dec();
+            sync_state
+                .broadcast_info
+                .record_ack(rtt, retry, &self.shared_config.read());
         } else {
             trace!(
                 LogSchema::new(LogEntry::ReceiveACK)
@@ -442,10 +716,7 @@ impl PeerManager {
         // as a backoff broadcast.
         // This ensures backpressure request from remote peer is honored at least once.
         if backoff {
-            counters::PEER_MANAGER_PEER_REQUESTED_BACKOFF.with_label_values(&[
-                &peer.raw_network_id().to_string(),
-                &peer.peer_id().to_string(),
-            ]).inc();
+            counters::PEER_MANAGER_PEER_REQUESTED_BACKOFF.inc(&peer, &[]);
             error!("Peer requested backoff: {:?}", &peer);
             sync_state.broadcast_info.backoff_mode = true;
         }
@@ -475,6 +746,17 @@ impl PeerManager {
     }
 }
 
+/// Sets the pending broadcasts gauge of `network_id` to the number of un-ACK'ed broadcasts
+/// across all of its peers
+fn update_pending_broadcasts_gauge(peer_states: &PeerSyncStates, network_id: &NetworkId) {
+    let pending_broadcasts: usize = peer_states
+        .iter()
+        .filter(|(peer, _)| &peer.raw_network_id() == network_id)
+        .map(|(_, state)| state.broadcast_info.sent_batches.len())
+        .sum();
+    counters::shared_mempool_pending_broadcasts(network_id).set(pending_broadcasts as i64);
+}
+
 /// Provides ordering for prioritized peers
 fn compare_prioritized_peers(
     peer_a: &(PeerNetworkId, PeerRole),
@@ -507,7 +789,6 @@ fn compare_prioritized_peers(
 mod test {
     use super::*;
     use diem_config::network_id::{NetworkId, NodeNetworkId};
-    use diem_types::PeerId;
 
     fn peer_network_id(peer_id: PeerId, network: NetworkId) -> PeerNetworkId {
         PeerNetworkId(NodeNetworkId::new(network, 0), peer_id)
@@ -555,4 +836,94 @@ mod test {
         // Same the only equal case
         assert_eq!(Ordering::Equal, compare_prioritized_peers(&val_1, &val_1));
     }
+
+    #[test]
+    fn check_adaptive_batch_size() {
+        let config = SharedMempoolConfig {
+            ack_timeout_ms: 1_000,
+            batch_size: 100,
+            min_batch_size: 10,
+            max_batch_size: 200,
+            ..SharedMempoolConfig::default()
+        };
+        let fast = Duration::from_millis(50);
+        let slow = Duration::from_millis(900);
+
+        // Fast, reliable peer grows up to the max.
+        let mut info = BroadcastInfo::new(config.batch_size);
+        info.record_ack(fast, false, &config);
+        assert_eq!(info.batch_size, 125);
+        for _ in 0..10 {
+            info.record_ack(fast, false, &config);
+        }
+        assert_eq!(info.batch_size, 200);
+
+        // Retry ACKs shrink the batch immediately and keep it from growing until the retry
+        // ratio decays.
+        info.record_ack(fast, true, &config);
+        assert_eq!(info.batch_size, 100);
+        info.record_ack(fast, false, &config);
+        assert_eq!(info.batch_size, 50);
+
+        // Slow peer shrinks down to the min, as do expired broadcasts.
+        let mut info = BroadcastInfo::new(config.batch_size);
+        info.record_ack(slow, false, &config);
+        assert_eq!(info.batch_size, 50);
+        info.record_expired(&config);
+        info.record_expired(&config);
+        info.record_expired(&config);
+        assert_eq!(info.batch_size, 10);
+    }
+
+    #[test]
+    fn check_peer_broadcast_controls() {
+        let peer_manager = PeerManager::new(
+            RoleType::Validator,
+            MempoolConfig::default(),
+            Arc::new(RwLock::new(SharedMempoolConfig::default())),
+        );
+        let peer_id = PeerId::random();
+        let peer = peer_network_id(peer_id, NetworkId::Validator);
+        peer_manager.add_peer(peer.clone(), ConnectionMetadata::mock(peer_id));
+        {
+            let mut peer_states = peer_manager.peer_states.lock();
+            let broadcast_info = &mut peer_states.get_mut(&peer).unwrap().broadcast_info;
+            broadcast_info
+                .sent_batches
+                .insert(BatchId(5, 10), SystemTime::now());
+            broadcast_info.retry_batches.insert(BatchId(0, 5));
+            broadcast_info.backoff_mode = true;
+        }
+
+        peer_manager.pause_broadcasts(peer_id);
+        assert!(peer_manager.is_paused(&peer));
+        let statuses = peer_manager.broadcast_statuses(None);
+        assert_eq!(statuses.len(), 1);
+        assert!(statuses[0].paused);
+        assert!(statuses[0].backoff_mode);
+        assert_eq!(statuses[0].pending_broadcasts, 1);
+        assert_eq!(statuses[0].retry_broadcasts, 1);
+
+        peer_manager.flush_retries(peer_id);
+        let statuses = peer_manager.broadcast_statuses(Some(peer_id));
+        assert_eq!(statuses.len(), 1);
+        assert!(!statuses[0].backoff_mode);
+        assert_eq!(statuses[0].pending_broadcasts, 0);
+        assert_eq!(statuses[0].retry_broadcasts, 0);
+
+        // The pause outlives a reconnect, and applies to peers that are not connected.
+        peer_manager.disable_peer(peer.clone());
+        peer_manager.add_peer(peer.clone(), ConnectionMetadata::mock(peer_id));
+        assert!(peer_manager.is_paused(&peer));
+        let other_peer_id = PeerId::random();
+        peer_manager.pause_broadcasts(other_peer_id);
+        assert!(peer_manager
+            .broadcast_statuses(Some(other_peer_id))
+            .is_empty());
+        assert!(peer_manager.is_paused(&peer_network_id(other_peer_id, NetworkId::Public)));
+
+        peer_manager.resume_broadcasts(peer_id);
+        assert!(!peer_manager.is_paused(&peer));
+        assert!(!peer_manager.broadcast_statuses(None)[0].paused);
+    }
 }
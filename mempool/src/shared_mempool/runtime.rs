@@ -5,23 +5,28 @@ use crate::{
     core_mempool::CoreMempool,
     network::{MempoolNetworkEvents, MempoolNetworkSender},
     shared_mempool::{
+        chaos::ChaosHooks,
         coordinator::{coordinator, gc_coordinator, snapshot_job},
         peer_manager::PeerManager,
-        types::{SharedMempool, SharedMempoolNotification},
+        script_filter::ScriptFilter,
+        types::{
+            MempoolClientRequest, MempoolPeerRequest, MempoolSnapshotRequest, SharedMempool,
+            SharedMempoolNotification,
+        },
     },
-    CommitNotification, ConsensusRequest, SubmissionStatus,
+    ConsensusRequest,
 };
-use anyhow::Result;
 use channel::diem_channel;
-use diem_config::{config::NodeConfig, network_id::NodeNetworkId};
-use diem_infallible::{Mutex, RwLock};
-use diem_types::{on_chain_config::OnChainConfigPayload, transaction::SignedTransaction};
-use futures::channel::{
-    mpsc::{self, Receiver, UnboundedSender},
-    oneshot,
+use diem_config::{
+    config::{NodeConfig, SharedMempoolConfig},
+    network_id::NodeNetworkId,
 };
-use std::{collections::HashMap, sync::Arc};
+use diem_infallible::{Mutex, RwLock};
+use diem_types::on_chain_config::OnChainConfigPayload;
+use futures::channel::mpsc::{self, Receiver, UnboundedSender};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use storage_interface::DbReader;
+use subscription_service::CommitNotification;
 use tokio::runtime::{Builder, Handle, Runtime};
 use vm_validator::vm_validator::{TransactionValidation, VMValidator};
 
@@ -37,17 +42,26 @@ pub(crate) fn start_shared_mempool<V>(
     // First element in tuple is the network ID.
     // See `NodeConfig::is_upstream_peer` for the definition of network ID.
     mempool_network_handles: Vec<(NodeNetworkId, MempoolNetworkSender, MempoolNetworkEvents)>,
-    client_events: mpsc::Receiver<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>,
+    client_events: mpsc::Receiver<MempoolClientRequest>,
     consensus_requests: mpsc::Receiver<ConsensusRequest>,
-    state_sync_requests: mpsc::Receiver<CommitNotification>,
+    commit_events: mpsc::Receiver<CommitNotification>,
     mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    snapshot_requests: mpsc::Receiver<MempoolSnapshotRequest>,
+    peer_requests: mpsc::Receiver<MempoolPeerRequest>,
     db: Arc<dyn DbReader>,
     validator: Arc<RwLock<V>>,
+    script_filter: Arc<ScriptFilter>,
+    shared_config: Arc<RwLock<SharedMempoolConfig>>,
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    chaos: Arc<ChaosHooks>,
 ) where
     V: TransactionValidation + 'static,
 {
-    let peer_manager = Arc::new(PeerManager::new(config.base.role, config.mempool.clone()));
+    let peer_manager = Arc::new(PeerManager::new(
+        config.base.role,
+        config.mempool.clone(),
+        shared_config.clone(),
+    ));
 
     let mut all_network_events = vec![];
     let mut network_senders = HashMap::new();
@@ -57,13 +71,17 @@ pub(crate) fn start_shared_mempool<V>(
     }
 
     let smp = SharedMempool {
+        timeline: mempool.lock().timeline_reader(),
         mempool: mempool.clone(),
         config: config.mempool.clone(),
+        shared_config,
         network_senders,
         db,
         validator,
+        script_filter,
         peer_manager,
         subscribers,
+        chaos,
     };
 
     executor.spawn(coordinator(
@@ -72,13 +90,16 @@ pub(crate) fn start_shared_mempool<V>(
         all_network_events,
         client_events,
         consensus_requests,
-        state_sync_requests,
+        commit_events,
         mempool_reconfig_events,
+        snapshot_requests,
+        peer_requests,
     ));
 
     executor.spawn(gc_coordinator(
         mempool.clone(),
-        config.mempool.system_transaction_gc_interval_ms,
+        Duration::from_millis(config.mempool.system_transaction_gc_min_interval_ms),
+        Duration::from_millis(config.mempool.system_transaction_gc_interval_ms),
     ));
 
     executor.spawn(snapshot_job(
@@ -93,10 +114,14 @@ pub fn bootstrap(
     // The first element in the tuple is the ID of the network that this network is a handle to.
     // See `NodeConfig::is_upstream_peer` for the definition of network ID.
     mempool_network_handles: Vec<(NodeNetworkId, MempoolNetworkSender, MempoolNetworkEvents)>,
-    client_events: Receiver<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>,
+    client_events: Receiver<MempoolClientRequest>,
     consensus_requests: Receiver<ConsensusRequest>,
-    state_sync_requests: Receiver<CommitNotification>,
+    commit_events: mpsc::Receiver<CommitNotification>,
     mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    snapshot_requests: Receiver<MempoolSnapshotRequest>,
+    peer_requests: Receiver<MempoolPeerRequest>,
+    script_filter: Arc<ScriptFilter>,
+    shared_config: Arc<RwLock<SharedMempoolConfig>>,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
@@ -112,11 +137,16 @@ pub fn bootstrap(
         mempool_network_handles,
         client_events,
         consensus_requests,
-        state_sync_requests,
+        commit_events,
         mempool_reconfig_events,
+        snapshot_requests,
+        peer_requests,
         db,
         vm_validator,
+        script_filter,
+        shared_config,
         vec![],
+        Arc::new(ChaosHooks::default()),
     );
     runtime
 }
@@ -0,0 +1,108 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Operator-controlled allowlist and denylist of transaction scripts, checked before a
+//! transaction is validated. Rules can be replaced at runtime, e.g. to cut off a spam campaign
+//! using a specific script, and are persisted so they survive a restart.
+
+use crate::logging::{LogEntry, LogEvent, LogSchema};
+use anyhow::{Context, Result};
+use diem_crypto::HashValue;
+use diem_infallible::RwLock;
+use diem_logger::prelude::*;
+use diem_types::transaction::{SignedTransaction, TransactionPayload};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// File in the node's data directory the rules are persisted to.
+pub const SCRIPT_FILTER_FILE: &str = "mempool_script_filter.json";
+
+/// Script hashes are the SHA3-256 of the script code, as reported in `script_hash` by JSON-RPC.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptFilterRules {
+    /// If not empty, only scripts with these hashes are accepted.
+    pub allow: BTreeSet<HashValue>,
+    /// Scripts with these hashes are rejected, even if allowed.
+    pub deny: BTreeSet<HashValue>,
+}
+
+impl ScriptFilterRules {
+    pub fn allows(&self, script_hash: &HashValue) -> bool {
+        !self.deny.contains(script_hash)
+            && (self.allow.is_empty() || self.allow.contains(script_hash))
+    }
+}
+
+/// The script filter shared by mempool and the operator endpoint. Only `Script` payloads are
+/// filtered; script functions, modules and write sets are always let through.
+#[derive(Debug, Default)]
+pub struct ScriptFilter {
+    /// Where the rules are persisted, if anywhere.
+    path: Option<PathBuf>,
+    rules: RwLock<ScriptFilterRules>,
+}
+
+impl ScriptFilter {
+    /// Loads the rules persisted in `data_dir`, starting with no rules if there are none yet.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(SCRIPT_FILTER_FILE);
+        let rules = if path.exists() {
+            let bytes = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+            serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            ScriptFilterRules::default()
+        };
+        Ok(Self {
+            path: Some(path),
+            rules: RwLock::new(rules),
+        })
+    }
+
+    pub fn rules(&self) -> ScriptFilterRules {
+        self.rules.read().clone()
+    }
+
+    /// Replaces the rules. The new rules are persisted before they take effect, so a failed
+    /// write leaves the current rules in place. `source` identifies who made the change in the
+    /// audit log.
+    pub fn update(&self, rules: ScriptFilterRules, source: &str) -> Result<()> {
+        let mut current = self.rules.write();
+        if let Some(path) = &self.path {
+            let tmp_path = path.with_extension("json.tmp");
+            fs::write(&tmp_path, serde_json::to_vec_pretty(&rules)?)
+                .with_context(|| format!("writing {}", tmp_path.display()))?;
+            fs::rename(&tmp_path, path).with_context(|| format!("writing {}", path.display()))?;
+        }
+        info!(
+            LogSchema::event_log(LogEntry::ScriptFilter, LogEvent::Update),
+            source = source,
+            allowed = ?rules.allow.difference(&current.allow).collect::<Vec<_>>(),
+            unallowed = ?current.allow.difference(&rules.allow).collect::<Vec<_>>(),
+            denied = ?rules.deny.difference(&current.deny).collect::<Vec<_>>(),
+            undenied = ?current.deny.difference(&rules.deny).collect::<Vec<_>>(),
+            "Updated mempool script filter."
+        );
+        *current = rules;
+        Ok(())
+    }
+
+    /// Returns the hash of the script `txn` runs if the rules reject it.
+    pub fn rejected_script(&self, txn: &SignedTransaction) -> Option<HashValue> {
+        match txn.payload() {
+            TransactionPayload::Script(script) => {
+                let script_hash = HashValue::sha3_256_of(script.code());
+                if self.rules.read().allows(&script_hash) {
+                    None
+                } else {
+                    Some(script_hash)
+                }
+            }
+            _ => None,
+        }
+    }
+}
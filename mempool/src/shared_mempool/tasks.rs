@@ -6,35 +6,35 @@
 use crate::{
     core_mempool::{CoreMempool, TimelineState, TxnPointer},
     counters,
-    logging::{LogEntry, LogEvent, LogSchema},
-    network::MempoolSyncMsg,
+    logging::{LogEntry, LogEvent, LogSchema, TxnsLog},
+    network::{MempoolSyncMsg, MEMPOOL_PROTOCOL_VERSION},
     shared_mempool::types::{
-        notify_subscribers, ScheduledBroadcast, SharedMempool, SharedMempoolNotification,
-        SubmissionStatusBundle,
+        notify_subscribers, MempoolError, MempoolSnapshot, ScheduledBroadcast, SharedMempool,
+        SharedMempoolNotification, SubmissionStatusBundle, MEMPOOL_SNAPSHOT_VERSION,
     },
-    CommitNotification, CommitResponse, CommittedTransaction, ConsensusRequest, ConsensusResponse,
-    SubmissionStatus,
+    CommittedTransaction, ConsensusRequest, ConsensusResponse, SubmissionStatus,
 };
-use anyhow::Result;
 use diem_config::config::PeerNetworkId;
+use diem_crypto::HashValue;
 use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_metrics::HistogramTimer;
 use diem_types::{
+    account_address::AccountAddress,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     on_chain_config::OnChainConfigPayload,
-    transaction::SignedTransaction,
+    transaction::{SignedTransaction, VMValidatorResult},
     vm_status::DiscardedVMStatus,
 };
 use futures::{channel::oneshot, stream::FuturesUnordered};
 use rayon::prelude::*;
-use short_hex_str::AsShortHexStr;
 use std::{
     cmp,
     collections::HashSet,
     sync::Arc,
     time::{Duration, Instant},
 };
+use subscription_service::CommitNotification;
 use tokio::runtime::Handle;
 use vm_validator::vm_validator::{get_account_sequence_number, TransactionValidation};
 
@@ -56,11 +56,16 @@ pub(crate) fn execute_broadcast<V>(
     peer_manager.execute_broadcast(peer.clone(), backoff, smp);
     let schedule_backoff = peer_manager.is_backoff_mode(&peer);
 
-    let interval_ms = if schedule_backoff {
-        smp.config.shared_mempool_backoff_interval_ms
-    } else {
-        smp.config.shared_mempool_tick_interval_ms
+    let interval_ms = {
+        let shared_config = smp.shared_config.read();
+        if schedule_backoff {
+            shared_config.backoff_interval_ms
+        } else {
+            shared_config.tick_interval_ms
+        }
     };
+    #[cfg(any(test, feature = "chaos"))]
+    let interval_ms = interval_ms + smp.chaos.broadcast_delay().as_millis() as u64;
 
     scheduled_broadcasts.push(ScheduledBroadcast::new(
         Instant::now() + Duration::from_millis(interval_ms),
@@ -70,6 +75,30 @@ pub(crate) fn execute_broadcast<V>(
     ))
 }
 
+/// Announces this node's mempool protocol version to a newly connected `peer`.
+pub(crate) fn announce_protocol_version<V>(smp: &mut SharedMempool<V>, peer: &PeerNetworkId)
+where
+    V: TransactionValidation,
+{
+    let network_sender = smp
+        .network_senders
+        .get_mut(&peer.network_id())
+        .expect("[shared mempool] missing network sender");
+    if let Err(e) = network_sender.send_to(
+        peer.peer_id(),
+        MempoolSyncMsg::ProtocolVersion {
+            version: MEMPOOL_PROTOCOL_VERSION,
+        },
+    ) {
+        counters::network_send_fail_inc(counters::PROTOCOL_VERSION);
+        error!(
+            LogSchema::event_log(LogEntry::ProtocolVersion, LogEvent::NetworkSendFail)
+                .peer(peer)
+                .error(&e.into())
+        );
+    }
+}
+
 // =============================== //
 // Tasks processing txn submission //
 // =============================== //
@@ -78,7 +107,7 @@ pub(crate) fn execute_broadcast<V>(
 pub(crate) async fn process_client_transaction_submission<V>(
     smp: SharedMempool<V>,
     transaction: SignedTransaction,
-    callback: oneshot::Sender<Result<SubmissionStatus>>,
+    callback: oneshot::Sender<Result<SubmissionStatus, MempoolError>>,
     timer: HistogramTimer,
 ) where
     V: TransactionValidation,
@@ -88,10 +117,18 @@ pub(crate) async fn process_client_transaction_submission<V>(
     debug!("new transaction seq number: {:?}", &transaction.sequence_number());
 
     timer.stop_and_record();
-    let _timer =
-        counters::process_txn_submit_latency_timer(counters::CLIENT_LABEL, counters::CLIENT_LABEL);
+    debug!(
+        LogSchema::new(LogEntry::JsonRpc)
+            .txns(TxnsLog::new_txn(
+                transaction.sender(),
+                transaction.sequence_number()
+            ))
+            .trace_id(transaction.trace_id()),
+        "transaction submitted"
+    );
+    let _timer = counters::process_client_txn_submit_latency_timer();
     let statuses =
-        process_incoming_transactions(&smp, vec![transaction], TimelineState::NotReady).await;
+        process_incoming_transactions(&smp, vec![transaction], TimelineState::NotReady, true).await;
     log_txn_process_results(&statuses, None);
 
     if let Some(status) = statuses.get(0) {
@@ -105,6 +142,137 @@ pub(crate) async fn process_client_transaction_submission<V>(
     }
 }
 
+/// Processes a transaction submitted by a client for validation only: it goes through the checks
+/// of a submission, mempool's included, without being added to mempool.
+pub(crate) async fn process_client_transaction_validation<V>(
+    smp: SharedMempool<V>,
+    transaction: SignedTransaction,
+    callback: oneshot::Sender<Result<SubmissionStatus, MempoolError>>,
+    timer: HistogramTimer,
+) where
+    V: TransactionValidation,
+{
+    timer.stop_and_record();
+    let can_overflow = smp.config.overflow_capacity_per_user > 0;
+    let (statuses, transactions) =
+        validate_incoming_transactions(&smp, vec![transaction], can_overflow);
+    let status = match transactions.into_iter().next() {
+        Some((transaction, sequence_number, validation_result)) => {
+            let mempool_status = smp.mempool.lock().check_txn(
+                transaction.clone(),
+                transaction.max_gas_amount(),
+                validation_result.score(),
+                sequence_number,
+                validation_result.governance_role(),
+                true,
+            );
+            (mempool_status, None)
+        }
+        None => match statuses.into_iter().next() {
+            Some((_, status)) => status,
+            // the VM validator failed rather than rejecting the transaction
+            None => return,
+        },
+    };
+
+    if callback.send(Ok(status)).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::JsonRpc,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
+/// Allocates the next sequence number of `sender`, combining its on-chain sequence number with
+/// its pending txns and the numbers allocated to it before.
+pub(crate) async fn process_sequence_number_allocation<V>(
+    smp: SharedMempool<V>,
+    sender: AccountAddress,
+    callback: oneshot::Sender<Result<Option<u64>, MempoolError>>,
+    timer: HistogramTimer,
+) where
+    V: TransactionValidation,
+{
+    timer.stop_and_record();
+    let result = if smp
+        .config
+        .sequence_number_allocation_senders
+        .contains(&sender)
+    {
+        get_account_sequence_number(smp.db.as_ref(), sender)
+            .map_err(|e| {
+                error!(LogSchema::new(LogEntry::DBError).error(&e));
+                counters::DB_ERROR.inc();
+                MempoolError::StorageError(e)
+            })
+            .map(|account_sequence_number| {
+                Some(
+                    smp.mempool
+                        .lock()
+                        .allocate_sequence_number(sender, account_sequence_number),
+                )
+            })
+    } else {
+        Ok(None)
+    };
+
+    if callback.send(result).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::JsonRpc,
+            LogEvent::CallbackFail
+        ));
+        counters::CLIENT_CALLBACK_FAIL.inc();
+    }
+}
+
+/// Submits the transactions of a mempool snapshot exported by another node, as if sent by clients.
+pub(crate) async fn process_snapshot_import<V>(
+    smp: SharedMempool<V>,
+    snapshot: MempoolSnapshot,
+    callback: oneshot::Sender<Result<Vec<SubmissionStatusBundle>, MempoolError>>,
+) where
+    V: TransactionValidation,
+{
+    let statuses = import_snapshot(&smp, snapshot).await;
+    if let Ok(statuses) = &statuses {
+        log_txn_process_results(statuses, None);
+    }
+    if callback.send(statuses).is_err() {
+        error!(LogSchema::event_log(
+            LogEntry::MempoolSnapshot,
+            LogEvent::CallbackFail
+        ));
+    }
+}
+
+async fn import_snapshot<V>(
+    smp: &SharedMempool<V>,
+    snapshot: MempoolSnapshot,
+) -> Result<Vec<SubmissionStatusBundle>, MempoolError>
+where
+    V: TransactionValidation,
+{
+    if snapshot.version != MEMPOOL_SNAPSHOT_VERSION {
+        return Err(MempoolError::UnsupportedSnapshotVersion {
+            version: snapshot.version,
+            expected: MEMPOOL_SNAPSHOT_VERSION,
+        });
+    }
+    info!(
+        LogSchema::event_log(LogEntry::MempoolSnapshot, LogEvent::Import),
+        num_txns = snapshot.transactions.len()
+    );
+    let mut transactions = snapshot
+        .transactions
+        .into_iter()
+        .map(|snapshot_txn| snapshot_txn.txn)
+        .collect::<Vec<_>>();
+    // In the order the clients submitted them in, the lowest sequence numbers of each account first.
+    transactions.sort_by_key(|txn| (txn.sender(), txn.sequence_number()));
+    Ok(process_incoming_transactions(smp, transactions, TimelineState::NotReady, true).await)
+}
+
 /// Processes transactions from other nodes.
 pub(crate) async fn process_transaction_broadcast<V>(
     mut smp: SharedMempool<V>,
@@ -119,11 +287,9 @@ pub(crate) async fn process_transaction_broadcast<V>(
   warn!("process_transaction_broadcast from other node {:?}", &peer);
     counters::TASKS_PROCESS_TX_BROADCAST_EVENT.inc();
     timer.stop_and_record();
-    let _timer = counters::process_txn_submit_latency_timer(
-        peer.raw_network_id().as_str(),
-        peer.peer_id().short_str().as_str(),
-    );
-    let results = process_incoming_transactions(&smp, transactions.clone(), timeline_state).await;
+    let _timer = counters::PROCESS_TXN_SUBMISSION_LATENCY.start_timer(&peer);
+    let results =
+        process_incoming_transactions(&smp, transactions.clone(), timeline_state, false).await;
     log_txn_process_results(&results, Some(peer.clone()));
 
     let ack_response = gen_ack_response(request_id, results, &peer);
@@ -145,6 +311,55 @@ pub(crate) async fn process_transaction_broadcast<V>(
     notify_subscribers(SharedMempoolNotification::ACK, &smp.subscribers);
 }
 
+/// Answers a digest broadcast from `peer` with the digests missing from mempool, or with an ACK
+/// if none are.
+pub(crate) fn process_digest_broadcast<V>(
+    smp: &mut SharedMempool<V>,
+    request_id: Vec<u8>,
+    digests: Vec<HashValue>,
+    peer: PeerNetworkId,
+) where
+    V: TransactionValidation,
+{
+    let missing = smp.mempool.lock().missing_digests(&digests);
+    let is_ack = missing.is_empty();
+    let (response, failure_label) = if is_ack {
+        (
+            MempoolSyncMsg::BroadcastTransactionsResponse {
+                request_id,
+                retry: false,
+                backoff: false,
+            },
+            counters::ACK_TXNS,
+        )
+    } else {
+        (
+            MempoolSyncMsg::BroadcastDigestsResponse {
+                request_id,
+                missing,
+            },
+            counters::ACK_DIGESTS,
+        )
+    };
+
+    let network_sender = smp
+        .network_senders
+        .get_mut(&peer.network_id())
+        .expect("[shared mempool] missing network sender");
+    if let Err(e) = network_sender.send_to(peer.peer_id(), response) {
+        counters::network_send_fail_inc(failure_label);
+        error!(
+            LogSchema::event_log(LogEntry::BroadcastACK, LogEvent::NetworkSendFail)
+                .peer(&peer)
+                .error(&e.into())
+        );
+        return;
+    }
+    if is_ack {
+        notify_subscribers(SharedMempoolNotification::ACK, &smp.subscribers);
+    }
+}
+
 fn gen_ack_response(
     request_id: Vec<u8>,
     results: Vec<SubmissionStatusBundle>,
@@ -202,16 +417,108 @@ fn is_txn_retryable(result: SubmissionStatus) -> bool {
 
 /// Submits a list of SignedTransaction to the local mempool
 /// and returns a vector containing AdmissionControlStatus.
+/// Transactions submitted by a client may be held in the sender's overflow queue, if enabled.
 pub(crate) async fn process_incoming_transactions<V>(
     smp: &SharedMempool<V>,
     transactions: Vec<SignedTransaction>,
     timeline_state: TimelineState,
+    client_submitted: bool,
 ) -> Vec<SubmissionStatusBundle>
+where
+    V: TransactionValidation,
+{
+    let can_overflow = client_submitted && smp.config.overflow_capacity_per_user > 0;
+    let (mut statuses, transactions) =
+        validate_incoming_transactions(smp, transactions, can_overflow);
+    let unparked_txns = {
+        let mut mempool = smp.mempool.lock();
+        for (transaction, sequence_number, validation_result) in transactions {
+            let mempool_status = mempool.add_txn(
+                transaction.clone(),
+                transaction.max_gas_amount(),
+                validation_result.score(),
+                sequence_number,
+                timeline_state,
+                validation_result.governance_role(),
+                client_submitted,
+            );
+            statuses.push((transaction, (mempool_status, None)));
+        }
+        mempool.take_unparked_txns()
+    };
+    notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
+    if unparked_txns > 0 {
+        // Senders submitting out of order are waiting on the parked txns, broadcast them now
+        // rather than on the next tick.
+        counters::CORE_MEMPOOL_UNPARKED_TXNS.inc_by(unparked_txns as u64);
+        notify_subscribers(
+            SharedMempoolNotification::ParkedTransactionsReady,
+            &smp.subscribers,
+        );
+        let mut smp = smp.clone();
+        let peer_manager = smp.peer_manager.clone();
+        peer_manager.execute_broadcasts(&mut smp);
+    }
+    statuses
+}
+
+/// Runs the checks preceding the insertion of transactions into mempool: the script filter, the
+/// gas unit price floor, the sequence number and VM validation. Returns the statuses of the
+/// transactions failing them, and the others with the sequence number of their sender and their
+/// validation result.
+fn validate_incoming_transactions<V>(
+    smp: &SharedMempool<V>,
+    transactions: Vec<SignedTransaction>,
+    can_overflow: bool,
+) -> (
+    Vec<SubmissionStatusBundle>,
+    Vec<(SignedTransaction, u64, VMValidatorResult)>,
+)
 where
     V: TransactionValidation,
 {
     let mut statuses = vec![];
 
+    // Transactions running a script the operator filtered out are rejected before any work.
+    let transactions: Vec<_> = transactions
+        .into_iter()
+        .filter_map(|t| match smp.script_filter.rejected_script(&t) {
+            Some(script_hash) => {
+                let status = MempoolStatus::new(MempoolStatusCode::RejectedByFilter).with_message(
+                    format!("script {} is not accepted by this node", script_hash),
+                );
+                statuses.push((t, (status, None)));
+                None
+            }
+            None => Some(t),
+        })
+        .collect();
+
+    // So are transactions paying less than the operator's floor for their payload type, as
+    // validating them may be expensive, e.g. module publishes.
+    let transactions: Vec<_> = transactions
+        .into_iter()
+        .filter_map(|t| {
+            let min_gas_unit_price = smp.config.min_gas_unit_price.for_payload(t.payload());
+            if t.gas_unit_price() >= min_gas_unit_price {
+                return Some(t);
+            }
+            let status = MempoolStatus::new(MempoolStatusCode::VmError).with_message(format!(
+                "gas unit price {} is below the minimum of {} accepted by this node",
+                t.gas_unit_price(),
+                min_gas_unit_price
+            ));
+            statuses.push((
+                t,
+                (
+                    status,
+                    Some(DiscardedVMStatus::GAS_UNIT_PRICE_BELOW_MIN_BOUND),
+                ),
+            ));
+            None
+        })
+        .collect();
+
     let start_storage_read = Instant::now();
     // Track latency: fetching seq number
     let seq_numbers = transactions
@@ -236,7 +543,10 @@ where
         .enumerate()
         .filter_map(|(idx, t)| {
             if let Ok(sequence_number) = seq_numbers[idx] {
-                if t.sequence_number() == sequence_number {
+                // too new client txns can still wait in the sender's overflow queue
+                if t.sequence_number() == sequence_number
+                    || (can_overflow && t.sequence_number() > sequence_number)
+                {
                     return Some((t, sequence_number));
                 } else if t.sequence_number() > sequence_number{ // discard transactions that are too new.
                     statuses.push((
@@ -282,52 +592,37 @@ where
         .collect::<Vec<_>>();
     vm_validation_timer.stop_and_record();
 
+    let mut validated = vec![];
+    for ((transaction, sequence_number), validation_result) in
+        transactions.into_iter().zip(validation_results)
     {
-        let mut mempool = smp.mempool.lock();
-        for (idx, (transaction, sequence_number)) in transactions.into_iter().enumerate() {
-            if let Ok(validation_result) = &validation_results[idx] {
-                match validation_result.status() {
-                    None => {
-                        let gas_amount = transaction.max_gas_amount();
-                        let ranking_score = validation_result.score();
-                        let governance_role = validation_result.governance_role();
-                        let mempool_status = mempool.add_txn(
-                            transaction.clone(),
-                            gas_amount,
-                            ranking_score,
-                            sequence_number,
-                            timeline_state,
-                            governance_role,
-                        );
-                        statuses.push((transaction, (mempool_status, None)));
-                    }
-                    Some(validation_status) => {
-                        statuses.push((
-                            transaction.clone(),
-                            (
-                                MempoolStatus::new(MempoolStatusCode::VmError),
-                                Some(validation_status),
-                            ),
-                        ));
-                    }
+        if let Ok(validation_result) = validation_result {
+            // The VM rejects too new txns, but they are only admitted to the overflow queue
+            // and are let out of it in sequence number order.
+            let status = validation_result.status().filter(|status| {
+                !(can_overflow && *status == DiscardedVMStatus::SEQUENCE_NUMBER_TOO_NEW)
+            });
+            match status {
+                None => validated.push((transaction, sequence_number, validation_result)),
+                Some(validation_status) => {
+                    statuses.push((
+                        transaction,
+                        (
+                            MempoolStatus::new(MempoolStatusCode::VmError),
+                            Some(validation_status),
+                        ),
+                    ));
                 }
             }
         }
     }
-    notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
-    statuses
+    (statuses, validated)
 }
 
 fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<PeerNetworkId>) {
-    let (network, sender) = match sender {
-        Some(peer) => (
-            peer.raw_network_id().to_string(),
-            peer.peer_id().to_string(),
-        ),
-        None => (
-            counters::CLIENT_LABEL.to_string(),
-            counters::CLIENT_LABEL.to_string(),
-        ),
+    let sender_label = match &sender {
+        Some(peer) => peer.peer_id().to_string(),
+        None => counters::CLIENT_LABEL.to_string(),
     };
     for (txn, (mempool_status, maybe_vm_status)) in results.iter() {
         if let Some(vm_status) = maybe_vm_status {
@@ -335,25 +630,22 @@ fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<Pe
                 SecurityEvent::InvalidTransactionMempool,
                 failed_transaction = txn,
                 vm_status = vm_status,
-                sender = sender,
+                sender = sender_label,
             );
             counters::shared_mempool_transactions_processed_inc(
                 counters::VM_VALIDATION_LABEL,
-                &network,
-                &sender,
+                sender.as_ref(),
             );
             continue;
         }
         match mempool_status.code {
             MempoolStatusCode::Accepted => counters::shared_mempool_transactions_processed_inc(
                 counters::SUCCESS_LABEL,
-                &network,
-                &sender,
+                sender.as_ref(),
             ),
             _ => counters::shared_mempool_transactions_processed_inc(
                 &mempool_status.code.to_string(),
-                &network,
-                &sender,
+                sender.as_ref(),
             ),
         }
     }
@@ -363,20 +655,29 @@ fn log_txn_process_results(results: &[SubmissionStatusBundle], sender: Option<Pe
 // intra-node communication handlers //
 // ================================= //
 
-pub(crate) async fn process_state_sync_request(
+pub(crate) async fn process_commit_notification(
     mempool: Arc<Mutex<CoreMempool>>,
-    req: CommitNotification,
+    notification: CommitNotification,
 ) {
+    let CommitNotification { event, callback } = notification;
     let start_time = Instant::now();
     debug!(
-        LogSchema::event_log(LogEntry::StateSyncCommit, LogEvent::Received).state_sync_msg(&req)
+        LogSchema::event_log(LogEntry::StateSyncCommit, LogEvent::Received).commit_event(&event)
     );
     counters::mempool_service_transactions(
         counters::COMMIT_STATE_SYNC_LABEL,
-        req.transactions.len(),
+        event.user_transactions.len(),
     );
-    commit_txns(&mempool, req.transactions, req.block_timestamp_usecs).await;
-    let result = if req.callback.send(Ok(CommitResponse::success())).is_err() {
+    let transactions = event
+        .user_transactions
+        .into_iter()
+        .map(|(sender, sequence_number)| CommittedTransaction {
+            sender,
+            sequence_number,
+        })
+        .collect();
+    commit_txns(&mempool, transactions, event.block_timestamp_usecs).await;
+    let result = if callback.send(()).is_err() {
         error!(LogSchema::event_log(
             LogEntry::StateSyncCommit,
             LogEvent::CallbackFail
@@ -495,9 +796,11 @@ async fn remove_txns(
 }
 
 /// Processes on-chain reconfiguration notification.
+/// If the gas schedule changed, the txns in mempool are re-validated against it.
 pub(crate) async fn process_config_update<V>(
     config_update: OnChainConfigPayload,
     validator: Arc<RwLock<V>>,
+    mempool: Arc<Mutex<CoreMempool>>,
 ) where
     V: TransactionValidation,
 {
@@ -506,8 +809,69 @@ pub(crate) async fn process_config_update<V>(
             .reconfig_update(config_update.clone())
     );
 
-    if let Err(e) = validator.write().restart(config_update) {
-        counters::VM_RECONFIG_UPDATE_FAIL_COUNT.inc();
-        error!(LogSchema::event_log(LogEntry::ReconfigUpdate, LogEvent::VMUpdateFail).error(&e));
+    let gas_schedule_changed = {
+        let mut validator = validator.write();
+        let vm_config = validator.vm_config().cloned();
+        if let Err(e) = validator.restart(config_update) {
+            counters::VM_RECONFIG_UPDATE_FAIL_COUNT.inc();
+            error!(
+                LogSchema::event_log(LogEntry::ReconfigUpdate, LogEvent::VMUpdateFail).error(&e)
+            );
+            return;
+        }
+        validator.vm_config() != vm_config.as_ref()
+    };
+    if gas_schedule_changed {
+        reprice_transactions(&mempool, &validator);
+    }
+}
+
+/// Re-validates all txns in mempool, updating their ranking scores and removing those no longer
+/// valid, e.g. because their gas price is now below the minimum.
+fn reprice_transactions<V>(mempool: &Mutex<CoreMempool>, validator: &RwLock<V>)
+where
+    V: TransactionValidation,
+{
+    let transactions = mempool.lock().get_all_txns();
+    let validation_results = transactions
+        .par_iter()
+        .map(|t| validator.read().validate_transaction(t.clone()))
+        .collect::<Vec<_>>();
+
+    let mut mempool = mempool.lock();
+    let (mut repriced, mut rejected) = (0, 0);
+    for (transaction, validation_result) in transactions.iter().zip(validation_results) {
+        let validation_result = match validation_result {
+            Ok(validation_result) => validation_result,
+            Err(_) => continue,
+        };
+        let sender = transaction.sender();
+        let sequence_number = transaction.sequence_number();
+        match validation_result.status() {
+            None => {
+                mempool.update_ranking_score(&sender, sequence_number, validation_result.score());
+                repriced += 1;
+            }
+            // Txns waiting on earlier ones of their account are too new to be scored, and
+            // committed ones are cleaned up by the commit notification
+            Some(DiscardedVMStatus::SEQUENCE_NUMBER_TOO_NEW)
+            | Some(DiscardedVMStatus::SEQUENCE_NUMBER_TOO_OLD) => {}
+            Some(_) => {
+                mempool.remove_transaction(&sender, sequence_number, true);
+                rejected += 1;
+            }
+        }
     }
+    counters::GAS_SCHEDULE_UPDATE_TXNS
+        .with_label_values(&[counters::REPRICED_LABEL])
+        .inc_by(repriced);
+    counters::GAS_SCHEDULE_UPDATE_TXNS
+        .with_label_values(&[counters::REJECTED_LABEL])
+        .inc_by(rejected);
+    info!(
+        LogSchema::event_log(LogEntry::ReconfigUpdate, LogEvent::GasScheduleUpdate),
+        repriced = repriced,
+        rejected = rejected,
+        "repriced mempool txns for the new gas schedule"
+    );
 }
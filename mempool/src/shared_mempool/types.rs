@@ -4,14 +4,17 @@
 //! Objects used by/related to shared mempool
 
 use crate::{
-    core_mempool::CoreMempool,
-    shared_mempool::{network::MempoolNetworkSender, peer_manager::PeerManager},
+    core_mempool::{CoreMempool, TimelineReader},
+    shared_mempool::{
+        chaos::ChaosHooks, network::MempoolNetworkSender, peer_manager::PeerManager,
+        script_filter::ScriptFilter,
+    },
 };
 use anyhow::Result;
 use channel::diem_channel::Receiver;
 use diem_config::{
-    config::{MempoolConfig, PeerNetworkId},
-    network_id::NodeNetworkId,
+    config::{MempoolConfig, PeerNetworkId, SharedMempoolConfig},
+    network_id::{NetworkId, NodeNetworkId},
 };
 use diem_infallible::{Mutex, RwLock};
 use diem_types::{
@@ -20,15 +23,18 @@ use diem_types::{
     on_chain_config::{ConfigID, DiemVersion, OnChainConfig, OnChainConfigPayload, VMConfig},
     transaction::SignedTransaction,
     vm_status::DiscardedVMStatus,
+    PeerId,
 };
 use futures::{
     channel::{mpsc, mpsc::UnboundedSender, oneshot},
     future::Future,
     task::{Context, Poll},
 };
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt, pin::Pin, sync::Arc, task::Waker, time::Instant};
 use storage_interface::DbReader;
-use subscription_service::ReconfigSubscription;
+use subscription_service::{CommitEventBus, CommitNotification, ReconfigSubscription};
+use thiserror::Error;
 use tokio::runtime::Handle;
 use vm_validator::vm_validator::TransactionValidation;
 
@@ -39,12 +45,20 @@ where
     V: TransactionValidation + 'static,
 {
     pub mempool: Arc<Mutex<CoreMempool>>,
+    /// Timeline of `mempool`, read by broadcasts without locking it
+    pub timeline: TimelineReader,
     pub config: MempoolConfig,
+    /// Settings of `config.shared_mempool`, reloaded while the node runs. Read from here rather
+    /// than from `config`.
+    pub shared_config: Arc<RwLock<SharedMempoolConfig>>,
     pub network_senders: HashMap<NodeNetworkId, MempoolNetworkSender>,
     pub db: Arc<dyn DbReader>,
     pub validator: Arc<RwLock<V>>,
+    pub script_filter: Arc<ScriptFilter>,
     pub peer_manager: Arc<PeerManager>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    /// Faults injected to test broadcasts, see `chaos`
+    pub chaos: Arc<ChaosHooks>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -53,6 +67,8 @@ pub enum SharedMempoolNotification {
     NewTransactions,
     ACK,
     Broadcast,
+    /// Parked txns became ready once the txns before them arrived
+    ParkedTransactionsReady,
 }
 
 pub(crate) fn notify_subscribers(
@@ -163,29 +179,6 @@ pub enum ConsensusResponse {
     CommitResponse(),
 }
 
-/// Notification from state sync to mempool of commit event.
-/// This notifies mempool to remove committed txns.
-pub struct CommitNotification {
-    pub transactions: Vec<CommittedTransaction>,
-    /// Timestamp of committed block.
-    pub block_timestamp_usecs: u64,
-    pub callback: oneshot::Sender<Result<CommitResponse>>,
-}
-
-impl fmt::Display for CommitNotification {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut txns = "".to_string();
-        for txn in self.transactions.iter() {
-            txns += &format!("{} ", txn);
-        }
-        write!(
-            f,
-            "CommitNotification [block_timestamp_usecs: {}, txns: {}]",
-            self.block_timestamp_usecs, txns
-        )
-    }
-}
-
 #[derive(Debug)]
 pub struct CommitResponse {
     pub success: bool,
@@ -239,8 +232,110 @@ pub type SubmissionStatus = (MempoolStatus, Option<DiscardedVMStatus>);
 
 pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 
-pub type MempoolClientSender =
-    mpsc::Sender<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>;
+/// Failure of a request of a client to mempool. Rejected transactions aren't failures: they are
+/// reported in their `SubmissionStatus`.
+#[derive(Debug, Error)]
+pub enum MempoolError {
+    /// Reading the state of an account from storage failed.
+    #[error("Storage error: {0}")]
+    StorageError(anyhow::Error),
+    /// The snapshot was exported in another `MEMPOOL_SNAPSHOT_VERSION` format.
+    #[error("Unsupported mempool snapshot version {version}, expected {expected}")]
+    UnsupportedSnapshotVersion { version: u8, expected: u8 },
+}
+
+/// Request of a client, e.g. JSON-RPC, to mempool.
+pub enum MempoolClientRequest {
+    /// Submits a transaction to mempool.
+    SubmitTransaction(
+        SignedTransaction,
+        oneshot::Sender<Result<SubmissionStatus, MempoolError>>,
+    ),
+    /// Returns the status a transaction would be submitted with, without adding it to mempool.
+    ValidateTransaction(
+        SignedTransaction,
+        oneshot::Sender<Result<SubmissionStatus, MempoolError>>,
+    ),
+    /// Allocates the next sequence number of a sender, or none if the sender isn't one of
+    /// `MempoolConfig::sequence_number_allocation_senders`.
+    AllocateSequenceNumber(
+        AccountAddress,
+        oneshot::Sender<Result<Option<u64>, MempoolError>>,
+    ),
+}
+
+pub type MempoolClientSender = mpsc::Sender<MempoolClientRequest>;
+
+/// Version of the `MempoolSnapshot` format, to be bumped on any change to it.
+pub const MEMPOOL_SNAPSHOT_VERSION: u8 = 1;
+
+/// The pending transactions of a mempool in a stable serialized form (BCS), so operators can move
+/// them to another node, e.g. when migrating a node to new hardware.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MempoolSnapshot {
+    /// `MEMPOOL_SNAPSHOT_VERSION` of the node that exported the snapshot.
+    pub version: u8,
+    pub transactions: Vec<SnapshotTransaction>,
+}
+
+/// A transaction of a `MempoolSnapshot` with the metadata mempool held it with. The metadata is
+/// informational: imported transactions are validated again and get fresh metadata.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SnapshotTransaction {
+    pub txn: SignedTransaction,
+    /// System TTL expiration of the transaction on the exporting node, in seconds since the epoch.
+    pub expiration_time_secs: u64,
+    pub gas_amount: u64,
+    pub ranking_score: u64,
+}
+
+/// Request to export or import the transactions of mempool.
+pub enum MempoolSnapshotRequest {
+    /// Exports all transactions in mempool, those in overflow queues included.
+    Export(oneshot::Sender<MempoolSnapshot>),
+    /// Submits the transactions of a snapshot as if sent by clients.
+    Import(
+        MempoolSnapshot,
+        oneshot::Sender<Result<Vec<SubmissionStatusBundle>, MempoolError>>,
+    ),
+}
+
+pub type MempoolSnapshotSender = mpsc::Sender<MempoolSnapshotRequest>;
+
+/// The broadcast state of an upstream peer on one of the networks it is connected on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PeerBroadcastStatus {
+    pub peer_id: PeerId,
+    pub network_id: NetworkId,
+    /// Whether the operator paused broadcasts to this peer.
+    pub paused: bool,
+    pub is_alive: bool,
+    pub backoff_mode: bool,
+    /// Broadcasts sent that have not been ACK'ed yet.
+    pub pending_broadcasts: usize,
+    /// Broadcasts the peer asked to be resent.
+    pub retry_broadcasts: usize,
+    pub batch_size: usize,
+}
+
+/// Request of an operator to inspect or control broadcasts to upstream peers. Peers are identified
+/// by peer ID only, so a request applies to all networks the peer is connected on. Each request
+/// is answered with the broadcast state of the peers it applies to.
+pub enum MempoolPeerRequest {
+    /// Returns the broadcast state of all upstream peers.
+    List(oneshot::Sender<Vec<PeerBroadcastStatus>>),
+    /// Stops broadcasting to the peer until it is resumed, e.g. while the peer is under
+    /// maintenance. The pause is kept when the peer reconnects, and can be set before it connects,
+    /// but not across restarts of this node.
+    Pause(PeerId, oneshot::Sender<Vec<PeerBroadcastStatus>>),
+    /// Resumes broadcasting to a paused peer.
+    Resume(PeerId, oneshot::Sender<Vec<PeerBroadcastStatus>>),
+    /// Drops the broadcasts pending an ACK or a resend to the peer, and its backoff mode, so
+    /// broadcasts to it start over from where its timeline is at.
+    FlushRetries(PeerId, oneshot::Sender<Vec<PeerBroadcastStatus>>),
+}
+
+pub type MempoolPeerSender = mpsc::Sender<MempoolPeerRequest>;
 
 const MEMPOOL_SUBSCRIBED_CONFIGS: &[ConfigID] = &[DiemVersion::CONFIG_ID, VMConfig::CONFIG_ID];
 
@@ -248,3 +343,13 @@ pub fn gen_mempool_reconfig_subscription(
 ) -> (ReconfigSubscription, Receiver<(), OnChainConfigPayload>) {
     ReconfigSubscription::subscribe_all("mempool", MEMPOOL_SUBSCRIBED_CONFIGS.to_vec(), vec![])
 }
+
+/// Number of commit events queued for mempool. State sync waits for mempool to acknowledge each
+/// event, so events only pile up when mempool misses the `mempool_commit_timeout_ms` deadline.
+const MEMPOOL_COMMIT_EVENTS_CAPACITY: usize = 1_024;
+
+/// Subscribes mempool to commit events, so it can remove committed txns. Mempool acknowledges
+/// every event once the txns are removed.
+pub fn gen_mempool_commit_subscription(bus: &CommitEventBus) -> mpsc::Receiver<CommitNotification> {
+    bus.subscribe_acknowledged("mempool", MEMPOOL_COMMIT_EVENTS_CAPACITY)
+}
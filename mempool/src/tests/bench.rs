@@ -0,0 +1,262 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared mempool driven directly by benchmarks, with mocked storage, VM and peers.
+//! See the `mempool-bench` crate.
+
+use crate::{
+    core_mempool::{transaction_digest, CoreMempool, TimelineState},
+    network::{MempoolNetworkSender, MempoolSyncMsg, MEMPOOL_DIGEST_PROTOCOL_VERSION},
+    shared_mempool::{
+        chaos::ChaosHooks, peer_manager::PeerManager, script_filter::ScriptFilter, tasks,
+        types::SharedMempool,
+    },
+};
+use anyhow::Result;
+use channel::{diem_channel, message_queues::QueueStyle};
+use diem_config::{
+    config::{NodeConfig, PeerNetworkId},
+    network_id::{NetworkId, NodeNetworkId},
+};
+use diem_crypto::HashValue;
+use diem_infallible::{Mutex, RwLock};
+use diem_types::{
+    mempool_status::MempoolStatusCode,
+    on_chain_config::{OnChainConfigPayload, VMConfig},
+    transaction::{GovernanceRole, SignedTransaction, VMValidatorResult},
+    PeerId,
+};
+use futures::{executor::block_on, FutureExt, StreamExt};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::network::NewNetworkSender,
+    transport::ConnectionMetadata,
+    ProtocolId,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::AddAssign,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use storage_interface::mock::MockDbReader;
+use vm_validator::{
+    mocks::mock_vm_validator::MockVMValidator, vm_validator::TransactionValidation,
+};
+
+const NETWORK_QUEUE_SIZE: usize = 1_024;
+
+/// Accepts every transaction and ranks it by gas price, as the VM does, without the cost of
+/// running the VM.
+#[derive(Clone)]
+struct BenchVMValidator;
+
+impl TransactionValidation for BenchVMValidator {
+    type ValidationInstance = MockVMValidator;
+
+    fn validate_transaction(&self, txn: SignedTransaction) -> Result<VMValidatorResult> {
+        Ok(VMValidatorResult::new(
+            None,
+            txn.gas_unit_price(),
+            GovernanceRole::NonGovernanceRole,
+        ))
+    }
+
+    fn restart(&mut self, _config: OnChainConfigPayload) -> Result<()> {
+        Ok(())
+    }
+
+    fn vm_config(&self) -> Option<&VMConfig> {
+        None
+    }
+
+    fn notify_commit(&self) {}
+}
+
+/// Network messages exchanged with the peers of a `BenchMempool`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BroadcastStats {
+    /// Broadcast requests sent, of transactions or of digests
+    pub messages: u64,
+    /// Transactions sent in full
+    pub txns_sent: u64,
+    /// Digests sent in place of transactions
+    pub digests_sent: u64,
+    /// Transactions received by a peer for the first time
+    pub txns_delivered: u64,
+}
+
+impl AddAssign for BroadcastStats {
+    fn add_assign(&mut self, other: Self) {
+        self.messages += other.messages;
+        self.txns_sent += other.txns_sent;
+        self.digests_sent += other.digests_sent;
+        self.txns_delivered += other.txns_delivered;
+    }
+}
+
+/// Peers of a `BenchMempool`, answering the requests it sends them.
+struct BenchPeers {
+    network_reqs: diem_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    /// Digests of the transactions each peer received
+    received: HashMap<PeerId, HashSet<HashValue>>,
+}
+
+/// Shared mempool on a validator network of `num_peers` peers, whose tasks are run by the caller
+/// rather than by the coordinator.
+pub struct BenchMempool {
+    smp: SharedMempool<BenchVMValidator>,
+    network_id: NodeNetworkId,
+    peer_ids: Vec<PeerId>,
+    peers: Mutex<BenchPeers>,
+}
+
+impl BenchMempool {
+    /// Creates a shared mempool with `num_peers` peers. If `digest_broadcasts`, the peers announce
+    /// the digest protocol, so they are sent digests and then the transactions they are missing.
+    pub fn new(config: &NodeConfig, num_peers: usize, digest_broadcasts: bool) -> Self {
+        let (network_reqs_tx, network_reqs) =
+            diem_channel::new(QueueStyle::FIFO, NETWORK_QUEUE_SIZE, None);
+        let (connection_reqs_tx, _) = diem_channel::new(QueueStyle::FIFO, 8, None);
+        let network_id = NodeNetworkId::new(NetworkId::Validator, 0);
+        let network_sender = MempoolNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+
+        let mempool = CoreMempool::new(config);
+        let shared_config = Arc::new(RwLock::new(config.mempool.shared_mempool.clone()));
+        let smp = SharedMempool {
+            timeline: mempool.timeline_reader(),
+            mempool: Arc::new(Mutex::new(mempool)),
+            config: config.mempool.clone(),
+            shared_config: shared_config.clone(),
+            network_senders: vec![(network_id.clone(), network_sender)]
+                .into_iter()
+                .collect(),
+            db: Arc::new(MockDbReader),
+            validator: Arc::new(RwLock::new(BenchVMValidator)),
+            script_filter: Arc::new(ScriptFilter::default()),
+            peer_manager: Arc::new(PeerManager::new(
+                config.base.role,
+                config.mempool.clone(),
+                shared_config,
+            )),
+            subscribers: vec![],
+            chaos: Arc::new(ChaosHooks::default()),
+        };
+
+        let peer_ids: Vec<_> = (0..num_peers).map(|_| PeerId::random()).collect();
+        for peer_id in &peer_ids {
+            let peer = PeerNetworkId(network_id.clone(), *peer_id);
+            smp.peer_manager
+                .add_peer(peer.clone(), ConnectionMetadata::mock(*peer_id));
+            if digest_broadcasts {
+                smp.peer_manager
+                    .set_protocol_version(&peer, MEMPOOL_DIGEST_PROTOCOL_VERSION);
+            }
+        }
+
+        Self {
+            smp,
+            network_id,
+            peer_ids,
+            peers: Mutex::new(BenchPeers {
+                network_reqs,
+                received: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Submits `txns` as a client would, returning how many were accepted into mempool.
+    pub fn submit(&self, txns: Vec<SignedTransaction>) -> usize {
+        block_on(tasks::process_incoming_transactions(
+            &self.smp,
+            txns,
+            TimelineState::NotReady,
+            true,
+        ))
+        .iter()
+        .filter(|(_, (status, _))| status.code == MempoolStatusCode::Accepted)
+        .count()
+    }
+
+    /// Pulls a block of up to `block_size` txns, as consensus does, then commits it. Returns the
+    /// number of txns committed.
+    pub fn commit_block(&self, block_size: u64) -> usize {
+        let block = self
+            .smp
+            .mempool
+            .lock()
+            .get_block(block_size, HashSet::new());
+        let mut mempool = self.smp.mempool.lock();
+        for txn in &block {
+            mempool.remove_transaction(&txn.sender(), txn.sequence_number(), false);
+        }
+        block.len()
+    }
+
+    /// Executes a broadcast to every peer, and has the peers answer it: digests with the
+    /// transactions they are missing, and transactions with an ACK.
+    pub fn broadcast(&self) -> BroadcastStats {
+        let mut peers = self.peers.lock();
+        let mut smp = self.smp.clone();
+        let peer_manager = smp.peer_manager.clone();
+        for peer_id in &self.peer_ids {
+            let peer = PeerNetworkId(self.network_id.clone(), *peer_id);
+            peer_manager.execute_broadcast(peer, false, &mut smp);
+        }
+
+        let mut stats = BroadcastStats::default();
+        while let Some(Some(request)) = peers.network_reqs.next().now_or_never() {
+            let (peer_id, msg) = match request {
+                PeerManagerRequest::SendDirectSend(peer_id, msg) => (peer_id, msg),
+                _ => continue,
+            };
+            let peer = PeerNetworkId(self.network_id.clone(), peer_id);
+            let received = peers.received.entry(peer_id).or_default();
+            match bcs::from_bytes(&msg.mdata) {
+                Ok(MempoolSyncMsg::BroadcastDigestsRequest {
+                    request_id,
+                    digests,
+                }) => {
+                    stats.messages += 1;
+                    stats.digests_sent += digests.len() as u64;
+                    let missing = digests
+                        .into_iter()
+                        .filter(|digest| !received.contains(digest))
+                        .collect();
+                    peer_manager.process_digest_response(peer, request_id, missing, &mut smp);
+                }
+                Ok(MempoolSyncMsg::BroadcastTransactionsRequest {
+                    request_id,
+                    transactions,
+                }) => {
+                    stats.messages += 1;
+                    stats.txns_sent += transactions.len() as u64;
+                    for txn in &transactions {
+                        if received.insert(transaction_digest(txn)) {
+                            stats.txns_delivered += 1;
+                        }
+                    }
+                    peer_manager.process_broadcast_ack(
+                        peer,
+                        request_id,
+                        false,
+                        false,
+                        SystemTime::now(),
+                    );
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Returns how long it took to acquire the core mempool lock.
+    pub fn lock_wait(&self) -> Duration {
+        let start = Instant::now();
+        drop(self.smp.mempool.lock());
+        start.elapsed()
+    }
+}
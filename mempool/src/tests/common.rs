@@ -112,6 +112,7 @@ pub(crate) fn add_txns_to_mempool(
             0,
             TimelineState::NotReady,
             transaction.governance_role,
+            false,
         );
         transactions.push(txn);
     }
@@ -131,6 +132,7 @@ pub(crate) fn add_signed_txn(pool: &mut CoreMempool, transaction: SignedTransact
             0,
             TimelineState::NotReady,
             GovernanceRole::NonGovernanceRole,
+            false,
         )
         .code
     {
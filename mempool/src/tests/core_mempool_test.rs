@@ -2,14 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TtlCache},
+    core_mempool::{transaction_digest, CoreMempool, TimelineState, TtlCache},
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, exist_in_metrics_cache, setup_mempool,
         TestTransaction,
     },
 };
-use diem_config::config::NodeConfig;
-use diem_types::transaction::{GovernanceRole, SignedTransaction};
+use diem_config::config::{ConflictResolution, NodeConfig};
+use diem_crypto::HashValue;
+use diem_types::{
+    account_address::AccountAddress,
+    mempool_status::MempoolStatusCode,
+    transaction::{GovernanceRole, SignedTransaction},
+};
 use std::{
     collections::HashSet,
     time::{Duration, SystemTime},
@@ -65,6 +70,26 @@ fn test_transaction_ordering() {
     }
 }
 
+#[test]
+fn test_update_ranking_score() {
+    let (mut mempool, mut consensus) = setup_mempool();
+    let transactions = add_txns_to_mempool(
+        &mut mempool,
+        vec![TestTransaction::new(0, 0, 3), TestTransaction::new(1, 0, 5)],
+    );
+    assert_eq!(mempool.get_all_txns().len(), 2);
+
+    mempool.update_ranking_score(&TestTransaction::get_address(0), 0, 10);
+    assert_eq!(
+        consensus.get_block(&mut mempool, 1),
+        vec!(transactions[0].clone())
+    );
+    assert_eq!(
+        consensus.get_block(&mut mempool, 1),
+        vec!(transactions[1].clone())
+    );
+}
+
 #[test]
 fn test_ordering_of_governance_transactions() {
     let gov_roles = vec![
@@ -188,6 +213,74 @@ fn test_update_invalid_transaction_in_mempool() {
     assert_eq!(next_tnx[0].gas_unit_price(), 1);
 }
 
+#[test]
+fn test_conflict_resolution() {
+    let pool_with = |conflict_resolution| {
+        let mut config = NodeConfig::random();
+        config.mempool.conflict_resolution = conflict_resolution;
+        CoreMempool::new(&config)
+    };
+    let add = |pool: &mut CoreMempool, txn: &SignedTransaction, client_submitted| {
+        pool.add_txn(
+            txn.clone(),
+            0,
+            txn.gas_unit_price(),
+            0,
+            TimelineState::NotReady,
+            GovernanceRole::NonGovernanceRole,
+            client_submitted,
+        )
+        .code
+    };
+    let block = |pool: &mut CoreMempool| pool.get_block(10, HashSet::new());
+    // Conflicting txns, not mere gas price updates of each other
+    let cheap = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let expensive = TestTransaction::new(0, 0, 5).make_signed_transaction_with_max_gas_amount(200);
+
+    let mut pool = pool_with(ConflictResolution::FirstWins);
+    assert_eq!(add(&mut pool, &cheap, false), MempoolStatusCode::Accepted);
+    assert_eq!(
+        add(&mut pool, &expensive, true),
+        MempoolStatusCode::InvalidUpdate
+    );
+    assert_eq!(block(&mut pool), vec![cheap.clone()]);
+
+    let mut pool = pool_with(ConflictResolution::GasPriceWins);
+    assert_eq!(
+        add(&mut pool, &expensive, false),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(
+        add(&mut pool, &cheap, true),
+        MempoolStatusCode::InvalidUpdate
+    );
+    let mut pool = pool_with(ConflictResolution::GasPriceWins);
+    assert_eq!(add(&mut pool, &cheap, false), MempoolStatusCode::Accepted);
+    assert_eq!(
+        add(&mut pool, &expensive, false),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(block(&mut pool), vec![expensive.clone()]);
+
+    let mut pool = pool_with(ConflictResolution::ClientWins);
+    assert_eq!(
+        add(&mut pool, &expensive, false),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(add(&mut pool, &cheap, true), MempoolStatusCode::Accepted);
+    // A peer's txn doesn't replace a client's in turn.
+    assert_eq!(
+        add(&mut pool, &expensive, false),
+        MempoolStatusCode::InvalidUpdate
+    );
+    assert_eq!(
+        pool.check_txn(expensive, 0, 5, 0, GovernanceRole::NonGovernanceRole, false)
+            .code,
+        MempoolStatusCode::InvalidUpdate
+    );
+    assert_eq!(block(&mut pool), vec![cheap]);
+}
+
 #[test]
 fn test_remove_transaction() {
     let (mut pool, mut consensus) = setup_mempool();
@@ -318,6 +411,124 @@ fn test_timeline() {
     assert_eq!(0, pool.get_parking_lot_size());
 }
 
+#[test]
+fn test_unparked_txns() {
+    let mut pool = setup_mempool().0;
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(1, 0, 1),
+            TestTransaction::new(1, 2, 1),
+            TestTransaction::new(1, 3, 1),
+            TestTransaction::new(1, 5, 1),
+        ],
+    );
+    assert_eq!(pool.take_unparked_txns(), 0);
+
+    // Txn 1 fills the gap before txns 2 and 3, txn 5 stays parked.
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 1, 1)]);
+    assert_eq!(pool.take_unparked_txns(), 2);
+    assert_eq!(pool.take_unparked_txns(), 0);
+    assert_eq!(1, pool.get_parking_lot_size());
+
+    // Txns made ready by a commit aren't counted.
+    pool.remove_transaction(&TestTransaction::get_address(1), 4, false);
+    assert_eq!(0, pool.get_parking_lot_size());
+    assert_eq!(pool.take_unparked_txns(), 0);
+}
+
+#[test]
+fn test_timeline_reader() {
+    let mut pool = setup_mempool().0;
+    // The reader sees changes made after it was taken, without locking mempool
+    let timeline = pool.timeline_reader();
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(1, 0, 1),
+            TestTransaction::new(1, 1, 1),
+            TestTransaction::new(2, 0, 1),
+        ],
+    );
+    let (txns, last_timeline_id) = timeline.read_timeline(0, 2);
+    assert_eq!(txns.len(), 2);
+    assert_eq!(last_timeline_id, 2);
+    let (txns, last_timeline_id) = timeline.read_timeline(last_timeline_id, 2);
+    assert_eq!(txns.len(), 1);
+    assert_eq!(last_timeline_id, 3);
+    assert!(timeline.read_timeline(last_timeline_id, 2).0.is_empty());
+    assert_eq!(timeline.timeline_range(1, 3).len(), 2);
+
+    // Committed transactions leave the timeline
+    pool.remove_transaction(&TestTransaction::get_address(2), 0, false);
+    assert_eq!(timeline.timeline_range(0, 3).len(), 2);
+}
+
+#[test]
+fn test_sender_fair_ordering() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity_per_user = 10;
+    config.mempool.sender_fair_ordering = true;
+    let mut pool = CoreMempool::new(&config);
+    // Account 0 outbids the others with many transactions
+    add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 5),
+            TestTransaction::new(0, 1, 5),
+            TestTransaction::new(0, 2, 5),
+            TestTransaction::new(0, 3, 5),
+            TestTransaction::new(1, 0, 3),
+            TestTransaction::new(2, 0, 2),
+            TestTransaction::new(2, 1, 2),
+        ],
+    );
+    let view = |txns: Vec<SignedTransaction>| -> Vec<(AccountAddress, u64)> {
+        txns.iter()
+            .map(|txn| (txn.sender(), txn.sequence_number()))
+            .collect()
+    };
+    let account = TestTransaction::get_address;
+
+    // Senders take turns, in the order their first transactions rank
+    assert_eq!(
+        view(pool.get_block(4, HashSet::new())),
+        vec![
+            (account(0), 0),
+            (account(1), 0),
+            (account(2), 0),
+            (account(0), 1)
+        ]
+    );
+    assert_eq!(
+        view(pool.get_block(7, HashSet::new())),
+        vec![
+            (account(0), 0),
+            (account(1), 0),
+            (account(2), 0),
+            (account(0), 1),
+            (account(2), 1),
+            (account(0), 2),
+            (account(0), 3),
+        ]
+    );
+
+    // Broadcast batches interleave the senders they contain
+    let (timeline, last_timeline_id) = pool.read_timeline(0, 6);
+    assert_eq!(
+        view(timeline),
+        vec![
+            (account(0), 0),
+            (account(1), 0),
+            (account(2), 0),
+            (account(0), 1),
+            (account(0), 2),
+            (account(0), 3),
+        ]
+    );
+    assert_eq!(last_timeline_id, 6);
+}
+
 #[test]
 fn test_capacity() {
     let mut config = NodeConfig::random();
@@ -339,6 +550,117 @@ fn test_capacity() {
     assert!(add_txn(&mut pool, TestTransaction::new(1, 2, 1)).is_ok());
 }
 
+#[test]
+fn test_fill_ratio() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 4;
+    let mut pool = CoreMempool::new(&config);
+    assert_eq!(pool.fill_ratio(), 0.0);
+
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert_eq!(pool.fill_ratio(), 0.25);
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    assert_eq!(pool.fill_ratio(), 0.5);
+
+    pool.remove_transaction(&TestTransaction::get_address(0), 0, false);
+    assert_eq!(pool.fill_ratio(), 0.25);
+}
+
+#[test]
+fn test_overflow() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity_per_user = 1;
+    config.mempool.overflow_capacity_per_user = 2;
+    let mut pool = CoreMempool::new(&config);
+    let add_client_txn = |pool: &mut CoreMempool, sequence_number| {
+        let txn = TestTransaction::new(1, sequence_number, 1).make_signed_transaction();
+        pool.add_txn(
+            txn,
+            0,
+            1,
+            0,
+            TimelineState::NotReady,
+            GovernanceRole::NonGovernanceRole,
+            true,
+        )
+        .code
+    };
+    let block = |pool: &mut CoreMempool| -> Vec<u64> {
+        pool.get_block(10, HashSet::new())
+            .iter()
+            .map(SignedTransaction::sequence_number)
+            .collect()
+    };
+
+    // Txns over the per-account cap, or after a gap, wait in the overflow queue.
+    assert_eq!(add_client_txn(&mut pool, 0), MempoolStatusCode::Accepted);
+    assert_eq!(add_client_txn(&mut pool, 2), MempoolStatusCode::Accepted);
+    assert_eq!(add_client_txn(&mut pool, 1), MempoolStatusCode::Accepted);
+    assert_eq!(
+        add_client_txn(&mut pool, 3),
+        MempoolStatusCode::TooManyTransactions
+    );
+    // Txns not submitted by a client are still rejected at the cap.
+    assert!(add_txn(&mut pool, TestTransaction::new(1, 3, 1)).is_err());
+    assert_eq!(block(&mut pool), vec![0]);
+
+    // Overflowed txns are let in, in order, as earlier ones commit.
+    pool.remove_transaction(&TestTransaction::get_address(1), 0, false);
+    assert_eq!(block(&mut pool), vec![1]);
+    pool.remove_transaction(&TestTransaction::get_address(1), 1, false);
+    assert_eq!(block(&mut pool), vec![2]);
+}
+
+#[test]
+fn test_check_txn() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity = 3;
+    config.mempool.capacity_per_user = 2;
+    let mut pool = CoreMempool::new(&config);
+    let check = |pool: &CoreMempool, txn: TestTransaction| {
+        pool.check_txn(
+            txn.make_signed_transaction(),
+            0,
+            1,
+            0,
+            GovernanceRole::NonGovernanceRole,
+            false,
+        )
+        .code
+    };
+
+    // Checking a txn doesn't add it.
+    assert_eq!(
+        check(&pool, TestTransaction::new(1, 0, 1)),
+        MempoolStatusCode::Accepted
+    );
+    assert!(pool.get_block(10, HashSet::new()).is_empty());
+
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 1, 1)).unwrap();
+    // The same txn is accepted again, and a gas price update replaces it.
+    assert_eq!(
+        check(&pool, TestTransaction::new(1, 1, 1)),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(
+        check(&pool, TestTransaction::new(1, 1, 5)),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(
+        check(&pool, TestTransaction::new(1, 2, 1)),
+        MempoolStatusCode::TooManyTransactions
+    );
+
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    assert_eq!(
+        check(&pool, TestTransaction::new(2, 0, 1)),
+        MempoolStatusCode::MempoolIsFull
+    );
+    // Checks agree with insertions.
+    assert!(add_txn(&mut pool, TestTransaction::new(2, 0, 1)).is_err());
+}
+
 #[test]
 fn test_parking_lot_eviction() {
     let mut config = NodeConfig::random();
@@ -411,6 +733,7 @@ fn test_gc_ready_transaction() {
         0,
         TimelineState::NotReady,
         GovernanceRole::NonGovernanceRole,
+        false,
     );
 
     // Insert few transactions after it.
@@ -450,6 +773,7 @@ fn test_clean_stuck_transactions() {
         db_sequence_number,
         TimelineState::NotReady,
         GovernanceRole::NonGovernanceRole,
+        false,
     );
     let block = pool.get_block(10, HashSet::new());
     assert_eq!(block.len(), 1);
@@ -479,3 +803,134 @@ fn test_ttl_cache() {
         .unwrap());
     assert_eq!(cache.size(), 0);
 }
+
+#[test]
+fn test_missing_digests() {
+    let mut pool = setup_mempool().0;
+    let txn_a = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let txn_b = TestTransaction::new(1, 0, 1).make_signed_transaction();
+    add_signed_txn(&mut pool, txn_a.clone()).unwrap();
+    add_signed_txn(&mut pool, txn_b.clone()).unwrap();
+
+    let unknown = HashValue::random();
+    let digests = vec![
+        transaction_digest(&txn_a),
+        unknown,
+        transaction_digest(&txn_b),
+    ];
+    assert_eq!(pool.missing_digests(&digests), vec![unknown]);
+
+    // Replacing a txn with a higher gas price replaces its digest.
+    let txn_a_update = TestTransaction::new(0, 0, 5).make_signed_transaction();
+    add_signed_txn(&mut pool, txn_a_update.clone()).unwrap();
+    assert_eq!(
+        pool.missing_digests(&[
+            transaction_digest(&txn_a),
+            transaction_digest(&txn_a_update)
+        ]),
+        vec![transaction_digest(&txn_a)]
+    );
+
+    // Committed txns are no longer known.
+    pool.remove_transaction(&txn_b.sender(), 0, false);
+    assert_eq!(
+        pool.missing_digests(&[transaction_digest(&txn_b)]),
+        vec![transaction_digest(&txn_b)]
+    );
+}
+
+#[test]
+fn test_expiration_window() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity_per_user = 10;
+    config.mempool.min_expiration_window_secs = 10;
+    config.mempool.max_expiration_window_secs = 3_600;
+    let mut pool = CoreMempool::new(&config);
+    let now_secs = diem_infallible::duration_since_epoch().as_secs();
+    let add = |pool: &mut CoreMempool, sequence_number, expires_in_secs| {
+        let txn = TestTransaction::new(1, sequence_number, 1)
+            .make_signed_transaction_with_expiration_time(now_secs + expires_in_secs);
+        pool.add_txn(
+            txn,
+            0,
+            1,
+            0,
+            TimelineState::NotReady,
+            GovernanceRole::NonGovernanceRole,
+            false,
+        )
+        .code
+    };
+
+    assert_eq!(
+        add(&mut pool, 0, 1),
+        MempoolStatusCode::ExpirationOutOfBounds
+    );
+    assert_eq!(
+        add(&mut pool, 0, 2 * 3_600),
+        MempoolStatusCode::ExpirationOutOfBounds
+    );
+    assert_eq!(
+        pool.check_txn(
+            TestTransaction::new(1, 0, 1).make_signed_transaction(),
+            0,
+            1,
+            0,
+            GovernanceRole::NonGovernanceRole,
+            false,
+        )
+        .code,
+        MempoolStatusCode::ExpirationOutOfBounds
+    );
+    assert!(pool.get_block(10, HashSet::new()).is_empty());
+
+    assert_eq!(add(&mut pool, 0, 60), MempoolStatusCode::Accepted);
+    assert_eq!(add(&mut pool, 1, 3_000), MempoolStatusCode::Accepted);
+    assert_eq!(pool.get_block(10, HashSet::new()).len(), 2);
+}
+
+#[test]
+fn test_allocate_sequence_number() {
+    let mut config = NodeConfig::random();
+    config.mempool.capacity_per_user = 10;
+    let sender = TestTransaction::get_address(1);
+    config.mempool.sequence_number_allocation_senders = vec![sender];
+    let mut pool = CoreMempool::new(&config);
+
+    // numbers are allocated from the on-chain sequence number, once each
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 3);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 4);
+
+    // past the pending txns of the sender
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(1, 5, 1), TestTransaction::new(1, 6, 1)],
+    );
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 7);
+
+    // and past the txns committed since
+    assert_eq!(pool.allocate_sequence_number(sender, 10), 10);
+    assert_eq!(pool.allocate_sequence_number(sender, 10), 11);
+
+    // numbers that never reach mempool are allocated again once expired, filling the gap
+    let mut pool = CoreMempool::new(&config);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 3);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 4);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 5);
+    add_txns_to_mempool(
+        &mut pool,
+        vec![TestTransaction::new(1, 4, 1), TestTransaction::new(1, 5, 1)],
+    );
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 6);
+    pool.sequence_number_allocation_ttl = Duration::from_secs(0);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 3);
+    add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 3, 1)]);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 6);
+
+    // expired allocations are allocated again
+    config.mempool.sequence_number_allocation_ttl_secs = 0;
+    let mut pool = CoreMempool::new(&config);
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 3);
+    pool.gc();
+    assert_eq!(pool.allocate_sequence_number(sender, 3), 3);
+}
@@ -3,7 +3,10 @@
 
 use crate::{
     core_mempool::{CoreMempool, TimelineState},
-    shared_mempool::{peer_manager::PeerManager, tasks, types::SharedMempool},
+    shared_mempool::{
+        chaos::ChaosHooks, peer_manager::PeerManager, script_filter::ScriptFilter, tasks,
+        types::SharedMempool,
+    },
 };
 use diem_config::config::NodeConfig;
 use diem_infallible::{Mutex, RwLock};
@@ -35,17 +38,27 @@ pub fn test_mempool_process_incoming_transactions_impl(
     let config = NodeConfig::default();
     let mock_db = MockDbReader;
     let vm_validator = Arc::new(RwLock::new(MockVMValidator));
+    let mempool = CoreMempool::new(&config);
+    let shared_config = Arc::new(RwLock::new(config.mempool.shared_mempool.clone()));
     let smp = SharedMempool {
-        mempool: Arc::new(Mutex::new(CoreMempool::new(&config))),
+        timeline: mempool.timeline_reader(),
+        mempool: Arc::new(Mutex::new(mempool)),
         config: config.mempool.clone(),
+        shared_config: shared_config.clone(),
         network_senders: HashMap::new(),
         db: Arc::new(mock_db),
         validator: vm_validator,
-        peer_manager: Arc::new(PeerManager::new(config.base.role, config.mempool)),
+        script_filter: Arc::new(ScriptFilter::default()),
+        peer_manager: Arc::new(PeerManager::new(
+            config.base.role,
+            config.mempool,
+            shared_config,
+        )),
         subscribers: vec![],
+        chaos: Arc::new(ChaosHooks::default()),
     };
 
-    let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
+    let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state, false);
 }
 
 proptest! {
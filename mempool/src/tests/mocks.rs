@@ -3,14 +3,15 @@
 
 use crate::{
     core_mempool::{CoreMempool, TimelineState},
+    gen_mempool_commit_subscription,
     network::{MempoolNetworkEvents, MempoolNetworkSender},
-    shared_mempool::start_shared_mempool,
-    CommitNotification, ConsensusRequest, SubmissionStatus,
+    shared_mempool::{chaos::ChaosHooks, start_shared_mempool},
+    ConsensusRequest, MempoolClientSender, MempoolPeerSender, MempoolSnapshotSender, ScriptFilter,
 };
 use anyhow::{format_err, Result};
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{NetworkConfig, NodeConfig},
+    config::{MempoolConfig, NetworkConfig, NodeConfig},
     network_id::{NetworkId, NodeNetworkId},
 };
 use diem_infallible::{Mutex, RwLock};
@@ -18,30 +19,43 @@ use diem_types::{
     mempool_status::MempoolStatusCode,
     transaction::{GovernanceRole, SignedTransaction},
 };
-use futures::channel::{mpsc, oneshot};
+use futures::channel::mpsc;
 use network::{
     peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
     protocols::network::{NewNetworkEvents, NewNetworkSender},
 };
 use std::sync::Arc;
 use storage_interface::mock::MockDbReader;
+use subscription_service::CommitEventBus;
 use tokio::runtime::{Builder, Runtime};
 use vm_validator::mocks::mock_vm_validator::MockVMValidator;
 
 /// Mock of a running instance of shared mempool.
 pub struct MockSharedMempool {
     _runtime: Runtime,
-    pub ac_client: mpsc::Sender<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>,
+    pub ac_client: MempoolClientSender,
     pub mempool: Arc<Mutex<CoreMempool>>,
     pub consensus_sender: mpsc::Sender<ConsensusRequest>,
-    pub state_sync_sender: Option<mpsc::Sender<CommitNotification>>,
+    pub commit_event_bus: CommitEventBus,
+    pub script_filter: Arc<ScriptFilter>,
+    pub snapshot_sender: MempoolSnapshotSender,
+    pub peer_sender: MempoolPeerSender,
 }
 
 impl MockSharedMempool {
     /// Creates a mock of a running instance of shared mempool.
     /// Returns the runtime on which the shared mempool is running
     /// and the channel through which shared mempool receives client events.
-    pub fn new(state_sync: Option<mpsc::Receiver<CommitNotification>>) -> Self {
+    /// Commit events are received from `commit_event_bus`, or from a new bus if none is given.
+    pub fn new(commit_event_bus: Option<CommitEventBus>) -> Self {
+        Self::new_with_config(commit_event_bus, MempoolConfig::default())
+    }
+
+    /// Like `new`, with mempool configured by `mempool_config`.
+    pub fn new_with_config(
+        commit_event_bus: Option<CommitEventBus>,
+        mempool_config: MempoolConfig,
+    ) -> Self {
         let runtime = Builder::new_multi_thread()
             .thread_name("mock-shared-mem")
             .enable_all()
@@ -50,6 +64,7 @@ impl MockSharedMempool {
 
         let mut config = NodeConfig::random();
         config.validator_network = Some(NetworkConfig::network_with_id(NetworkId::Validator));
+        config.mempool = mempool_config;
 
         let mempool = Arc::new(Mutex::new(CoreMempool::new(&config)));
         let (network_reqs_tx, _network_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 8, None);
@@ -63,15 +78,13 @@ impl MockSharedMempool {
         let network_events = MempoolNetworkEvents::new(network_notifs_rx, conn_notifs_rx);
         let (ac_client, client_events) = mpsc::channel(1_024);
         let (consensus_sender, consensus_events) = mpsc::channel(1_024);
-        let (state_sync_sender, state_sync_events) = match state_sync {
-            None => {
-                let (sender, events) = mpsc::channel(1_024);
-                (Some(sender), events)
-            }
-            Some(state_sync) => (None, state_sync),
-        };
+        let (snapshot_sender, snapshot_requests) = mpsc::channel(1_024);
+        let (peer_sender, peer_requests) = mpsc::channel(1_024);
+        let commit_event_bus = commit_event_bus.unwrap_or_default();
+        let commit_events = gen_mempool_commit_subscription(&commit_event_bus);
         let (_reconfig_event_publisher, reconfig_event_subscriber) =
             diem_channel::new(QueueStyle::LIFO, 1, None);
+        let script_filter = Arc::new(ScriptFilter::default());
         let network_handles = vec![(
             NodeNetworkId::new(NetworkId::Validator, 0),
             network_sender,
@@ -85,11 +98,16 @@ impl MockSharedMempool {
             network_handles,
             client_events,
             consensus_events,
-            state_sync_events,
+            commit_events,
             reconfig_event_subscriber,
+            snapshot_requests,
+            peer_requests,
             Arc::new(MockDbReader),
             Arc::new(RwLock::new(MockVMValidator)),
+            script_filter.clone(),
+            Arc::new(RwLock::new(config.mempool.shared_mempool.clone())),
             vec![],
+            Arc::new(ChaosHooks::default()),
         );
 
         Self {
@@ -97,7 +115,10 @@ impl MockSharedMempool {
             ac_client,
             mempool,
             consensus_sender,
-            state_sync_sender,
+            commit_event_bus,
+            script_filter,
+            snapshot_sender,
+            peer_sender,
         }
     }
 
@@ -113,6 +134,7 @@ impl MockSharedMempool {
                         0,
                         TimelineState::NotReady,
                         GovernanceRole::NonGovernanceRole,
+                        false,
                     )
                     .code
                     != MempoolStatusCode::Accepted
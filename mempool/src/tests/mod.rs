@@ -12,6 +12,8 @@ mod node;
 #[cfg(test)]
 mod shared_mempool_test;
 
+#[cfg(any(feature = "fuzzing", test))]
+pub mod bench;
 pub mod fuzzing;
 #[cfg(any(feature = "fuzzing", test))]
 pub mod mocks;
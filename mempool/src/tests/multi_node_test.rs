@@ -3,7 +3,10 @@
 
 use crate::{
     network::MempoolSyncMsg,
-    shared_mempool::types::SharedMempoolNotification,
+    shared_mempool::{
+        chaos::ChaosConfig,
+        types::{PeerBroadcastStatus, SharedMempoolNotification},
+    },
     tests::{
         common::TestTransaction,
         node::{
@@ -20,7 +23,10 @@ use network::{
     ProtocolId,
 };
 use rand::{rngs::StdRng, SeedableRng};
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 /// A struct holding a list of overriding configurations for mempool
 #[derive(Clone, Copy)]
@@ -164,14 +170,17 @@ impl TestHarness {
     fn update_config(config: &mut NodeConfig, mempool_config: Option<MempoolOverrideConfig>) {
         if let Some(mempool_config) = mempool_config {
             if let Some(batch_size) = mempool_config.broadcast_batch_size {
-                config.mempool.shared_mempool_batch_size = batch_size;
+                config.mempool.shared_mempool.batch_size = batch_size;
             }
+            // Keep broadcast sizes fixed so tests can assert on exact batches.
+            config.mempool.shared_mempool.min_batch_size = config.mempool.shared_mempool.batch_size;
+            config.mempool.shared_mempool.max_batch_size = config.mempool.shared_mempool.batch_size;
             if let Some(mempool_size) = mempool_config.mempool_size {
                 config.mempool.capacity = mempool_size;
             }
 
             // Set the ack timeout duration to 0 to avoid sleeping to test rebroadcast scenario (broadcast must timeout for this).
-            config.mempool.shared_mempool_ack_timeout_ms =
+            config.mempool.shared_mempool.ack_timeout_ms =
                 mempool_config.ack_timeout_ms.unwrap_or(0);
 
             if let Some(max_broadcasts_per_peer) = mempool_config.max_broadcasts_per_peer {
@@ -179,11 +188,11 @@ impl TestHarness {
             }
 
             if let Some(backoff_interval_ms) = mempool_config.backoff_interval_ms {
-                config.mempool.shared_mempool_backoff_interval_ms = backoff_interval_ms;
+                config.mempool.shared_mempool.backoff_interval_ms = backoff_interval_ms;
             }
 
             if let Some(tick_interval_ms) = mempool_config.tick_interval_ms {
-                config.mempool.shared_mempool_tick_interval_ms = tick_interval_ms;
+                config.mempool.shared_mempool.tick_interval_ms = tick_interval_ms;
             }
         }
     }
@@ -452,6 +461,59 @@ impl TestHarness {
         }
     }
 
+    /// Starts injecting the faults of `config` into a node's shared mempool
+    fn set_chaos(&self, node_id: &NodeId, config: ChaosConfig) {
+        self.node(node_id).set_chaos(config)
+    }
+
+    /// Delivers the messages each node sends on its primary network, until it sends none within
+    /// `timeout`. Unlike `broadcast_txns`, makes no assumption on which messages are sent, and
+    /// ignores the nodes' events.
+    fn relay_messages(&mut self, timeout: Duration) {
+        let node_ids: Vec<_> = self.nodes.keys().copied().collect();
+        for node_id in node_ids.iter() {
+            let sender_peer_id = self.node(node_id).primary_peer_id();
+            while let Some(network_req) = self
+                .mut_node(node_id)
+                .try_get_next_network_req(true, timeout)
+            {
+                if let PeerManagerRequest::SendDirectSend(remote_peer_id, msg) = network_req {
+                    let receiver_id = *self.peer_to_node_id.get(&remote_peer_id).unwrap();
+                    let receiver = self.mut_node(&receiver_id);
+                    let is_primary = receiver.primary_peer_id() == remote_peer_id;
+                    receiver.send_network_req(
+                        is_primary,
+                        ProtocolId::MempoolDirectSend,
+                        PeerManagerNotification::RecvMessage(sender_peer_id, msg),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Broadcast state of `peer_id` on a node
+    fn broadcast_status(&mut self, node_id: &NodeId, peer_id: &NodeId) -> PeerBroadcastStatus {
+        let peer_id = self.node(peer_id).primary_peer_id();
+        self.mut_node(node_id)
+            .broadcast_statuses()
+            .into_iter()
+            .find(|status| status.peer_id == peer_id)
+            .unwrap()
+    }
+
+    /// Check if a transaction made it into a node's mempool
+    fn exist_in_mempool(&self, node_id: &NodeId, txn: &TestTransaction) -> bool {
+        let address = TestTransaction::get_address(txn.address);
+        self.node(node_id)
+            .mempool()
+            .get_all_txns()
+            .iter()
+            .any(|signed_txn| {
+                signed_txn.sender() == address
+                    && signed_txn.sequence_number() == txn.sequence_number
+            })
+    }
+
     /// Check if a transaction made it into the metrics cache
     fn exist_in_metrics_cache(&self, node_id: &NodeId, txn: &TestTransaction) -> bool {
         self.node(node_id)
@@ -896,3 +958,60 @@ fn test_max_broadcast_limit() {
         harness.assert_no_message_sent(&v_a, true);
     }
 }
+
+#[test]
+fn test_broadcast_under_chaos() {
+    let mut validator_mempool_config = MempoolOverrideConfig::new();
+    validator_mempool_config.broadcast_batch_size = Some(2);
+    validator_mempool_config.ack_timeout_ms = Some(100);
+    validator_mempool_config.tick_interval_ms = Some(10);
+    let (mut harness, validators) =
+        TestHarness::bootstrap_validator_network(2, Some(validator_mempool_config));
+    let (v_a, v_b) = (validators.get(0).unwrap(), validators.get(1).unwrap());
+
+    let chaos = ChaosConfig {
+        drop_ack_rate: 0.3,
+        broadcast_delay: Duration::from_millis(5),
+        duplicate_batch_rate: 0.3,
+        reorder_rate: 0.3,
+        seed: 0,
+    };
+    harness.set_chaos(v_a, chaos.clone());
+    harness.set_chaos(v_b, chaos);
+
+    // One txn per account, so every txn is ready as soon as it arrives
+    let txns: Vec<_> = (0..6)
+        .map(|address| TestTransaction::new(address, 0, 1))
+        .collect();
+    let num_batches = 3;
+    harness.add_txns(v_a, txns.clone());
+    harness.connect(v_b, v_a);
+
+    // Every accepted txn eventually reaches B, and A keeps track of no more broadcasts than it
+    // made batches, however many of them are resent
+    let mut delivered = false;
+    for _ in 0..500 {
+        harness.relay_messages(Duration::from_millis(20));
+        let status = harness.broadcast_status(v_a, v_b);
+        assert!(status.pending_broadcasts + status.retry_broadcasts <= num_batches);
+        if txns.iter().all(|txn| harness.exist_in_mempool(v_b, txn)) {
+            delivered = true;
+            break;
+        }
+    }
+    assert!(delivered, "Txns were lost under chaos");
+    assert!(txns.iter().all(|txn| harness.exist_in_mempool(v_a, txn)));
+
+    // Once every batch is ACK'ed, no broadcast is left pending or to resend
+    let mut settled = false;
+    for _ in 0..500 {
+        harness.relay_messages(Duration::from_millis(20));
+        let status = harness.broadcast_status(v_a, v_b);
+        assert!(status.pending_broadcasts + status.retry_broadcasts <= num_batches);
+        if status.pending_broadcasts == 0 && status.retry_broadcasts == 0 {
+            settled = true;
+            break;
+        }
+    }
+    assert!(settled, "Broadcasts were retried without end under chaos");
+}
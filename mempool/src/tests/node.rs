@@ -3,9 +3,16 @@
 
 use crate::{
     core_mempool::{CoreMempool, TimelineState},
+    gen_mempool_commit_subscription,
     network::{MempoolNetworkEvents, MempoolSyncMsg},
     shared_mempool::{
-        network::MempoolNetworkSender, start_shared_mempool, types::SharedMempoolNotification,
+        chaos::{ChaosConfig, ChaosHooks},
+        network::MempoolNetworkSender,
+        script_filter::ScriptFilter,
+        start_shared_mempool,
+        types::{
+            MempoolPeerRequest, MempoolPeerSender, PeerBroadcastStatus, SharedMempoolNotification,
+        },
     },
     tests::common::TestTransaction,
 };
@@ -18,8 +25,11 @@ use diem_infallible::{Mutex, MutexGuard, RwLock};
 use diem_types::{account_address::AccountAddress, transaction::GovernanceRole, PeerId};
 use enum_dispatch::enum_dispatch;
 use futures::{
-    channel::mpsc::{self, unbounded, UnboundedReceiver},
-    FutureExt, StreamExt,
+    channel::{
+        mpsc::{self, unbounded, UnboundedReceiver},
+        oneshot,
+    },
+    FutureExt, SinkExt, StreamExt,
 };
 use netcore::transport::ConnectionOrigin;
 use network::{
@@ -32,8 +42,9 @@ use network::{
     DisconnectReason, ProtocolId,
 };
 use rand::rngs::StdRng;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use storage_interface::mock::MockDbReader;
+use subscription_service::CommitEventBus;
 use tokio::runtime::{Builder, Runtime};
 use vm_validator::mocks::mock_vm_validator::MockVMValidator;
 
@@ -310,6 +321,10 @@ pub struct Node {
     runtime: Arc<Runtime>,
     /// Subscriber for mempool events
     subscriber: UnboundedReceiver<SharedMempoolNotification>,
+    /// Sender of requests inspecting broadcasts to upstream peers
+    peer_requests: MempoolPeerSender,
+    /// Faults injected into shared mempool
+    chaos: Arc<ChaosHooks>,
 }
 
 /// Reimplement `NodeInfoTrait` for simplicity
@@ -343,7 +358,8 @@ impl Node {
     /// Sets up a single node by starting up mempool and any network handles
     pub fn new(node: NodeInfo, config: NodeConfig) -> Node {
         let (network_interfaces, network_handles) = setup_node_network_interfaces(&node);
-        let (mempool, runtime, subscriber) = start_node_mempool(config, network_handles);
+        let (mempool, runtime, subscriber, peer_requests, chaos) =
+            start_node_mempool(config, network_handles);
 
         Node {
             node_info: node,
@@ -351,6 +367,8 @@ impl Node {
             network_interfaces,
             runtime: Arc::new(runtime),
             subscriber,
+            peer_requests,
+            chaos,
         }
     }
 
@@ -359,6 +377,21 @@ impl Node {
         self.mempool.lock()
     }
 
+    /// Starts injecting the faults of `config` into the node's shared mempool
+    pub fn set_chaos(&self, config: ChaosConfig) {
+        self.chaos.set(config)
+    }
+
+    /// Retrieves the broadcast state of all upstream peers
+    pub fn broadcast_statuses(&mut self) -> Vec<PeerBroadcastStatus> {
+        let (callback, statuses) = oneshot::channel();
+        let runtime = self.runtime.clone();
+        runtime
+            .block_on(self.peer_requests.send(MempoolPeerRequest::List(callback)))
+            .unwrap();
+        runtime.block_on(statuses).unwrap()
+    }
+
     /// Queues transactions for sending on a node.  Must use `broadcast_txns` to send to other nodes
     pub fn add_txns(&self, txns: Vec<TestTransaction>) {
         let mut mempool = self.mempool();
@@ -371,6 +404,7 @@ impl Node {
                 0,
                 TimelineState::NotReady,
                 GovernanceRole::NonGovernanceRole,
+                false,
             );
         }
     }
@@ -461,6 +495,17 @@ impl Node {
             .get_next_network_req(runtime)
     }
 
+    /// Retrieves the next network request `PeerManagerRequest`, if one is sent within `timeout`
+    pub fn try_get_next_network_req(
+        &mut self,
+        is_primary: bool,
+        timeout: Duration,
+    ) -> Option<PeerManagerRequest> {
+        let runtime = self.runtime.clone();
+        self.get_network_interface(is_primary)
+            .try_get_next_network_req(runtime, timeout)
+    }
+
     /// Send network request `PeerManagerNotification` from a remote peer to the local node
     pub fn send_network_req(
         &mut self,
@@ -492,8 +537,33 @@ pub struct NodeNetworkInterface {
 }
 
 impl NodeNetworkInterface {
+    /// Returns the next outgoing request, skipping protocol version announcements: they are never
+    /// delivered, so nodes under test keep broadcasting full transactions to each other.
     fn get_next_network_req(&mut self, runtime: Arc<Runtime>) -> PeerManagerRequest {
-        runtime.block_on(self.network_reqs_rx.next()).unwrap()
+        runtime.block_on(self.next_network_req())
+    }
+
+    /// Like `get_next_network_req`, but gives up if no request is sent within `timeout`.
+    fn try_get_next_network_req(
+        &mut self,
+        runtime: Arc<Runtime>,
+        timeout: Duration,
+    ) -> Option<PeerManagerRequest> {
+        runtime
+            .block_on(tokio::time::timeout(timeout, self.next_network_req()))
+            .ok()
+    }
+
+    async fn next_network_req(&mut self) -> PeerManagerRequest {
+        loop {
+            let request = self.network_reqs_rx.next().await.unwrap();
+            if let PeerManagerRequest::SendDirectSend(_, msg) = &request {
+                if let Ok(MempoolSyncMsg::ProtocolVersion { .. }) = bcs::from_bytes(&msg.mdata) {
+                    continue;
+                }
+            }
+            return request;
+        }
     }
 
     fn send_network_req(&mut self, protocol: ProtocolId, message: PeerManagerNotification) {
@@ -586,13 +656,18 @@ fn start_node_mempool(
     Arc<Mutex<CoreMempool>>,
     Runtime,
     UnboundedReceiver<SharedMempoolNotification>,
+    MempoolPeerSender,
+    Arc<ChaosHooks>,
 ) {
     let mempool = Arc::new(Mutex::new(CoreMempool::new(&config)));
     let (sender, subscriber) = unbounded();
     let (_ac_endpoint_sender, ac_endpoint_receiver) = mpsc::channel(1_024);
     let (_consensus_sender, consensus_events) = mpsc::channel(1_024);
-    let (_state_sync_sender, state_sync_events) = mpsc::channel(1_024);
+    let (_snapshot_sender, snapshot_requests) = mpsc::channel(1_024);
+    let (peer_sender, peer_requests) = mpsc::channel(1_024);
+    let commit_events = gen_mempool_commit_subscription(&CommitEventBus::new());
     let (_reconfig_events, reconfig_events_receiver) = diem_channel::new(QueueStyle::LIFO, 1, None);
+    let chaos = Arc::new(ChaosHooks::default());
 
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
@@ -606,12 +681,17 @@ fn start_node_mempool(
         network_handles,
         ac_endpoint_receiver,
         consensus_events,
-        state_sync_events,
+        commit_events,
         reconfig_events_receiver,
+        snapshot_requests,
+        peer_requests,
         Arc::new(MockDbReader),
         Arc::new(RwLock::new(MockVMValidator)),
+        Arc::new(ScriptFilter::default()),
+        Arc::new(RwLock::new(config.mempool.shared_mempool.clone())),
         vec![sender],
+        chaos.clone(),
     );
 
-    (mempool, runtime, subscriber)
+    (mempool, runtime, subscriber, peer_sender, chaos)
 }
@@ -4,13 +4,15 @@
 use crate::{
     mocks::MockSharedMempool,
     tests::common::{batch_add_signed_txn, TestTransaction},
-    CommitNotification, CommittedTransaction, ConsensusRequest,
-};
-use futures::{
-    channel::{mpsc, oneshot},
-    executor::block_on,
-    sink::SinkExt,
+    CommittedTransaction, ConsensusRequest, MempoolClientRequest, MempoolError, MempoolSnapshot,
+    MempoolSnapshotRequest, ScriptFilter, ScriptFilterRules, MEMPOOL_SNAPSHOT_VERSION,
 };
+use diem_config::config::{MempoolConfig, MinGasUnitPriceConfig};
+use diem_crypto::HashValue;
+use diem_temppath::TempPath;
+use diem_types::{mempool_status::MempoolStatusCode, vm_status::DiscardedVMStatus};
+use futures::{channel::oneshot, executor::block_on, sink::SinkExt};
+use subscription_service::CommitEvent;
 
 #[test]
 fn test_consensus_events_rejected_txns() {
@@ -53,9 +55,15 @@ fn test_consensus_events_rejected_txns() {
 }
 
 #[test]
-fn test_state_sync_events_committed_txns() {
-    let (mut state_sync_sender, state_sync_events) = mpsc::channel(1_024);
-    let smp = MockSharedMempool::new(Some(state_sync_events));
+fn test_commit_events_committed_txns() {
+    // Room for the two txns of account 0
+    let smp = MockSharedMempool::new_with_config(
+        None,
+        MempoolConfig {
+            capacity_per_user: 2,
+            ..MempoolConfig::default()
+        },
+    );
 
     // Add txns 1, 2, 3, 4
     // Txn 1: committed successfully
@@ -75,24 +83,181 @@ fn test_state_sync_events_committed_txns() {
         assert!(batch_add_signed_txn(&mut pool, txns).is_ok());
     }
 
-    let committed_txns = vec![CommittedTransaction {
-        sender: committed_txn.sender(),
-        sequence_number: committed_txn.sequence_number(),
-    }];
+    // Mempool acknowledges the commit event once it removed the committed txns
+    block_on(smp.commit_event_bus.publish(CommitEvent {
+        version: 1,
+        block_timestamp_usecs: 1,
+        transaction_hashes: vec![HashValue::random()],
+        user_transactions: vec![(committed_txn.sender(), committed_txn.sequence_number())],
+        new_epoch: None,
+    }))
+    .unwrap();
+
+    let timeline = smp.read_timeline(0, 10);
+    assert_eq!(timeline.len(), 1);
+    assert_eq!(timeline.get(0).unwrap(), &kept_txn);
+}
+
+#[test]
+fn test_script_filter_rejects_txns() {
+    let smp = MockSharedMempool::new(None);
+    let txn = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let script_hash = HashValue::sha3_256_of(&[]);
+    let mut rules = ScriptFilterRules::default();
+    rules.deny.insert(script_hash);
+    smp.script_filter.update(rules, "test").unwrap();
 
     let (callback, callback_rcv) = oneshot::channel();
-    let req = CommitNotification {
-        transactions: committed_txns,
-        block_timestamp_usecs: 1,
-        callback,
+    let mut ac_client = smp.ac_client.clone();
+    let (status, _) = block_on(async {
+        assert!(ac_client
+            .send(MempoolClientRequest::SubmitTransaction(txn, callback))
+            .await
+            .is_ok());
+        callback_rcv.await.unwrap().unwrap()
+    });
+    assert_eq!(status.code, MempoolStatusCode::RejectedByFilter);
+    assert!(smp.read_timeline(0, 10).is_empty());
+}
+
+#[test]
+fn test_min_gas_unit_price_rejects_txns() {
+    let smp = MockSharedMempool::new_with_config(
+        None,
+        MempoolConfig {
+            min_gas_unit_price: MinGasUnitPriceConfig {
+                script: 5,
+                ..MinGasUnitPriceConfig::default()
+            },
+            ..MempoolConfig::default()
+        },
+    );
+    let submit = |txn| {
+        let (callback, callback_rcv) = oneshot::channel();
+        let mut ac_client = smp.ac_client.clone();
+        block_on(async {
+            assert!(ac_client
+                .send(MempoolClientRequest::SubmitTransaction(txn, callback))
+                .await
+                .is_ok());
+            callback_rcv.await.unwrap().unwrap()
+        })
     };
-    block_on(async {
-        assert!(state_sync_sender.send(req).await.is_ok());
-        assert!(callback_rcv.await.is_ok());
+
+    let (status, vm_status) = submit(TestTransaction::new(0, 0, 4).make_signed_transaction());
+    assert_eq!(status.code, MempoolStatusCode::VmError);
+    assert_eq!(
+        vm_status,
+        Some(DiscardedVMStatus::GAS_UNIT_PRICE_BELOW_MIN_BOUND)
+    );
+    assert!(smp.read_timeline(0, 10).is_empty());
+
+    let (status, _) = submit(TestTransaction::new(0, 0, 5).make_signed_transaction());
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    assert_eq!(smp.read_timeline(0, 10).len(), 1);
+}
+
+#[test]
+fn test_validate_txn() {
+    let smp = MockSharedMempool::new(None);
+    let validate = |txn| {
+        let (callback, callback_rcv) = oneshot::channel();
+        let mut ac_client = smp.ac_client.clone();
+        block_on(async {
+            assert!(ac_client
+                .send(MempoolClientRequest::ValidateTransaction(txn, callback))
+                .await
+                .is_ok());
+            callback_rcv.await.unwrap().unwrap()
+        })
+    };
+
+    let txn = TestTransaction::new(0, 0, 1).make_signed_transaction();
+    let (status, vm_status) = validate(txn.clone());
+    assert_eq!(status.code, MempoolStatusCode::Accepted);
+    assert_eq!(vm_status, None);
+    assert!(smp.read_timeline(0, 10).is_empty());
+
+    // Validation reports the conflicts with the txns in mempool.
+    smp.add_txns(vec![txn]).unwrap();
+    let update = TestTransaction::new(0, 0, 1).make_signed_transaction_with_max_gas_amount(10);
+    let (status, _) = validate(update);
+    assert_eq!(status.code, MempoolStatusCode::InvalidUpdate);
+    assert_eq!(smp.read_timeline(0, 10).len(), 1);
+}
+
+#[test]
+fn test_script_filter_persisted() {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let script_hash = HashValue::random();
+    let mut rules = ScriptFilterRules::default();
+    rules.allow.insert(script_hash);
+    ScriptFilter::load(dir.path())
+        .unwrap()
+        .update(rules.clone(), "test")
+        .unwrap();
+
+    let reloaded = ScriptFilter::load(dir.path()).unwrap();
+    assert_eq!(reloaded.rules(), rules);
+    assert!(reloaded.rules().allows(&script_hash));
+    assert!(!reloaded.rules().allows(&HashValue::random()));
+}
+
+#[test]
+fn test_snapshot_export_import() {
+    let source = MockSharedMempool::new(None);
+    let txns = vec![
+        TestTransaction::new(0, 0, 1).make_signed_transaction(),
+        TestTransaction::new(1, 0, 2).make_signed_transaction(),
+    ];
+    source.add_txns(txns.clone()).unwrap();
+
+    let (callback, callback_rcv) = oneshot::channel();
+    let mut snapshot_sender = source.snapshot_sender.clone();
+    let snapshot = block_on(async {
+        assert!(snapshot_sender
+            .send(MempoolSnapshotRequest::Export(callback))
+            .await
+            .is_ok());
+        callback_rcv.await.unwrap()
     });
+    assert_eq!(snapshot.version, MEMPOOL_SNAPSHOT_VERSION);
+    assert_eq!(snapshot.transactions.len(), 2);
+    let bytes = bcs::to_bytes(&snapshot).unwrap();
+    let snapshot: MempoolSnapshot = bcs::from_bytes(&bytes).unwrap();
 
-    let mut pool = smp.mempool.lock();
-    let (timeline, _) = pool.read_timeline(0, 10);
-    assert_eq!(timeline.len(), 1);
-    assert_eq!(timeline.get(0).unwrap(), &kept_txn);
+    let import = |snapshot| {
+        let target = MockSharedMempool::new(None);
+        let (callback, callback_rcv) = oneshot::channel();
+        let mut snapshot_sender = target.snapshot_sender.clone();
+        let statuses = block_on(async {
+            assert!(snapshot_sender
+                .send(MempoolSnapshotRequest::Import(snapshot, callback))
+                .await
+                .is_ok());
+            callback_rcv.await.unwrap()
+        });
+        (target, statuses)
+    };
+
+    let (target, statuses) = import(snapshot.clone());
+    let statuses = statuses.unwrap();
+    assert_eq!(statuses.len(), 2);
+    for (_, (status, _)) in statuses {
+        assert_eq!(status.code, MempoolStatusCode::Accepted);
+    }
+    let mut timeline = target.read_timeline(0, 10);
+    timeline.sort_by_key(|txn| txn.sender());
+    let mut expected = txns;
+    expected.sort_by_key(|txn| txn.sender());
+    assert_eq!(timeline, expected);
+
+    // Snapshots of another format version are refused
+    let mut snapshot = snapshot;
+    snapshot.version += 1;
+    assert!(matches!(
+        import(snapshot).1,
+        Err(MempoolError::UnsupportedSnapshotVersion { .. })
+    ));
 }
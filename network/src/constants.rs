@@ -13,6 +13,8 @@ pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
 /// Limit on concurrent Inbound RPC requests before backpressure is applied
 pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+/// Limit on low priority messages queued to be written to a peer before new ones are dropped
+pub const MAX_PENDING_LOW_PRIORITY_WIRE_MESSAGES: usize = 256;
 
 // These are only used in tests
 // TODO: Fix this so the tests and the defaults in config are the same
@@ -24,6 +24,10 @@ pub const SENT_LABEL: &str = "sent";
 pub const SUCCEEDED_LABEL: &str = "succeeded";
 pub const FAILED_LABEL: &str = "failed";
 
+// Low priority message states
+pub const DEFERRED_LABEL: &str = "deferred";
+pub const DROPPED_LABEL: &str = "dropped";
+
 pub static DIEM_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_connections",
@@ -303,6 +307,27 @@ pub fn direct_send_bytes(
     ])
 }
 
+pub static DIEM_NETWORK_LOW_PRIORITY_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_low_priority_messages",
+        "Number of low priority messages deferred behind high priority ones or dropped",
+        &["role_type", "network_id", "peer_id", "state"]
+    )
+    .unwrap()
+});
+
+pub fn low_priority_messages(
+    network_context: &NetworkContext,
+    state_label: &'static str,
+) -> IntCounter {
+    DIEM_NETWORK_LOW_PRIORITY_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        state_label,
+    ])
+}
+
 /// Counters(queued,dequeued,dropped) related to inbound network notifications for RPCs and
 /// DirectSends.
 pub static PENDING_NETWORK_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -394,6 +419,15 @@ pub static PENDING_WIRE_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Counter of low priority messages pending in queue to be sent out on the wire.
+pub static PENDING_LOW_PRIORITY_WIRE_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_network_pending_low_priority_wire_messages",
+        "Number of pending low priority wire messages"
+    )
+    .unwrap()
+});
+
 /// Counter of pending requests in Direct Send
 pub static PENDING_DIRECT_SEND_REQUESTS: Lazy<IntGauge> = Lazy::new(|| {
     register_int_gauge!(
@@ -15,7 +15,8 @@
 //! [`PeerManager`]: crate::peer_manager::PeerManager
 
 use crate::{
-    counters::{self, RECEIVED_LABEL, SENT_LABEL},
+    constants::MAX_PENDING_LOW_PRIORITY_WIRE_MESSAGES,
+    counters::{self, DEFERRED_LABEL, DROPPED_LABEL, RECEIVED_LABEL, SENT_LABEL},
     logging::NetworkSchema,
     peer_manager::{PeerManagerError, TransportNotification},
     protocols::{
@@ -97,6 +98,28 @@ impl fmt::Display for DisconnectReason {
     }
 }
 
+/// The quality of service class of an outbound message. High priority messages are written to the
+/// wire before any pending low priority ones, so e.g. consensus votes aren't delayed by a flood of
+/// mempool broadcasts. Low priority messages are dropped once too many of them are pending.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QosClass {
+    High,
+    Low,
+}
+
+impl QosClass {
+    pub fn of(protocol_id: ProtocolId) -> Self {
+        match protocol_id {
+            ProtocolId::MempoolDirectSend => QosClass::Low,
+            ProtocolId::ConsensusRpc
+            | ProtocolId::ConsensusDirectSend
+            | ProtocolId::StateSyncDirectSend
+            | ProtocolId::DiscoveryDirectSend
+            | ProtocolId::HealthCheckerRpc => QosClass::High,
+        }
+    }
+}
+
 enum State {
     Connected,
     ShuttingDown(DisconnectReason),
@@ -219,17 +242,20 @@ where
             self.outbound_rate_limiter.clone(),
         );
 
-        // Start writer "process" as a separate task. We receive two handles to
+        // Start writer "process" as a separate task. We receive three handles to
         // communicate with the task:
         //   1. `write_reqs_tx`: Queue of pending NetworkMessages to write.
-        //   2. `close_tx`: Handle to close the task and underlying connection.
-        let (mut write_reqs_tx, writer_close_tx) = Self::start_writer_task(
-            &self.executor,
-            self.time_service.clone(),
-            self.connection_metadata.clone(),
-            self.network_context.clone(),
-            writer,
-        );
+        //   2. `low_priority_write_reqs_tx`: Queue of pending low priority
+        //      NetworkMessages, only written when the first queue is empty.
+        //   3. `close_tx`: Handle to close the task and underlying connection.
+        let (mut write_reqs_tx, mut low_priority_write_reqs_tx, writer_close_tx) =
+            Self::start_writer_task(
+                &self.executor,
+                self.time_service.clone(),
+                self.connection_metadata.clone(),
+                self.network_context.clone(),
+                writer,
+            );
 
         // Start main Peer event loop.
         let reason = loop {
@@ -241,7 +267,14 @@ where
                 // Handle a new outbound request from the PeerManager.
                 maybe_request = self.peer_reqs_rx.next() => {
                     match maybe_request {
-                        Some(request) => self.handle_outbound_request(request, &mut write_reqs_tx).await,
+                        Some(request) => {
+                            self.handle_outbound_request(
+                                request,
+                                &mut write_reqs_tx,
+                                &mut low_priority_write_reqs_tx,
+                            )
+                            .await
+                        }
                         // The PeerManager is requesting this connection to close
                         // by dropping the corresponding peer_reqs_tx handle.
                         None => self.shutdown(DisconnectReason::Requested),
@@ -293,10 +326,12 @@ where
     }
 
     // Start a new task on the given executor which is responsible for writing outbound messages on
-    // the wire. The function returns two channels which can be used to send intructions to the
+    // the wire. The function returns three channels which can be used to send intructions to the
     // task:
     // 1. The first channel is used to send outbound NetworkMessages to the task
-    // 2. The second channel is used to instruct the task to close the connection and terminate.
+    // 2. The second channel is used to send low priority outbound NetworkMessages, which are only
+    //    written when no message is pending in the first one
+    // 3. The third channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
     fn start_writer_task(
@@ -306,6 +341,10 @@ where
         network_context: Arc<NetworkContext>,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
     ) -> (
+        channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
         channel::Sender<(
             NetworkMessage,
             oneshot::Sender<Result<(), PeerManagerError>>,
@@ -320,32 +359,58 @@ where
             )>,
             _,
         ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
+        let (low_priority_write_reqs_tx, mut low_priority_write_reqs_rx): (
+            channel::Sender<(
+                NetworkMessage,
+                oneshot::Sender<Result<(), PeerManagerError>>,
+            )>,
+            _,
+        ) = channel::new(
+            MAX_PENDING_LOW_PRIORITY_WIRE_MESSAGES,
+            &counters::PENDING_LOW_PRIORITY_WIRE_MESSAGES,
+        );
         let (close_tx, close_rx) = oneshot::channel();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
+            // The next low priority message, taken off its queue to tell whether it's deferred by
+            // the high priority ones.
+            let mut deferred = None;
             loop {
-                futures::select! {
-                    (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(err) = writer
-                            .send(&message)
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
-                            warn!(
-                                NetworkSchema::new(&network_context)
-                                    .connection_metadata(&connection_metadata),
-                                error = %err,
-                                "{} Error in sending message to peer: {}, error: {}",
-                                network_context,
-                                remote_peer_id.short_str(),
-                                err
-                            );
+                if deferred.is_none() {
+                    deferred = low_priority_write_reqs_rx.next().now_or_never().flatten();
+                }
+                let (message, ack_ch) = match deferred.take() {
+                    Some(low_priority_request) => futures::select_biased! {
+                        _ = close_rx.select_next_some() => {
                             break;
-                        }
+                        },
+                        request = write_reqs_rx.select_next_some() => {
+                            counters::low_priority_messages(&network_context, DEFERRED_LABEL)
+                                .inc();
+                            deferred = Some(low_priority_request);
+                            request
+                        },
+                        default => low_priority_request,
                     },
-                    _ = close_rx.select_next_some() => {
-                        break;
-                    }
+                    None => futures::select_biased! {
+                        _ = close_rx.select_next_some() => {
+                            break;
+                        },
+                        request = write_reqs_rx.select_next_some() => request,
+                        request = low_priority_write_reqs_rx.select_next_some() => request,
+                    },
+                };
+                if let Err(err) = writer.send(&message).map_ok(|_| ack_ch.send(Ok(()))).await {
+                    warn!(
+                        NetworkSchema::new(&network_context)
+                            .connection_metadata(&connection_metadata),
+                        error = %err,
+                        "{} Error in sending message to peer: {}, error: {}",
+                        network_context,
+                        remote_peer_id.short_str(),
+                        err
+                    );
+                    break;
                 }
             }
             info!(
@@ -395,7 +460,7 @@ where
             }
         };
         executor.spawn(writer_task);
-        (write_reqs_tx, close_tx)
+        (write_reqs_tx, low_priority_write_reqs_tx, close_tx)
     }
 
     async fn handle_inbound_message(
@@ -517,6 +582,10 @@ where
             NetworkMessage,
             oneshot::Sender<Result<(), PeerManagerError>>,
         )>,
+        low_priority_write_reqs_tx: &mut channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
     ) {
         trace!(
             "Peer {} PeerRequest::{:?}",
@@ -525,7 +594,9 @@ where
         );
         match request {
             // To send an outbound DirectSendMsg, we just bump some counters and
-            // push it onto our outbound writer queue.
+            // push it onto our outbound writer queue. Low priority messages are
+            // dropped rather than waited for when their queue is full, so they
+            // don't hold up the requests behind them.
             PeerRequest::SendDirectSend(message) => {
                 let message_len = message.mdata.len();
                 let protocol_id = message.protocol_id;
@@ -536,12 +607,19 @@ where
                 });
                 let (ack_tx, _ack_rx) = oneshot::channel();
 
-                match write_reqs_tx.send((message, ack_tx)).await {
+                let result = match QosClass::of(protocol_id) {
+                    QosClass::High => write_reqs_tx.send((message, ack_tx)).await,
+                    QosClass::Low => low_priority_write_reqs_tx.try_send((message, ack_tx)),
+                };
+                match result {
                     Ok(_) => {
                         counters::direct_send_messages(&self.network_context, SENT_LABEL).inc();
                         counters::direct_send_bytes(&self.network_context, SENT_LABEL)
                             .inc_by(message_len as u64);
                     }
+                    Err(e) if e.is_full() => {
+                        counters::low_priority_messages(&self.network_context, DROPPED_LABEL).inc();
+                    }
                     Err(e) => {
                         warn!(
                             NetworkSchema::new(&self.network_context)
@@ -237,6 +237,60 @@ fn peer_send_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// Outbound consensus messages should be written before pending mempool messages.
+#[test]
+fn peer_send_message_priority() {
+    ::diem_logger::Logger::init_for_testing();
+    // The writer only runs once the peer has queued all the messages.
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let (peer, mut peer_handle, mut connection, _connection_notifs_rx, _peer_notifs_rx) =
+        build_test_peer(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+        );
+    let (mut client_sink, mut client_stream) = build_network_sink_stream(&mut connection);
+
+    let message = |protocol_id, data: &'static str| {
+        (
+            Message {
+                protocol_id,
+                mdata: Bytes::from(data),
+            },
+            NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id,
+                priority: 0,
+                raw_msg: Vec::from(data),
+            }),
+        )
+    };
+    let mempool_msgs = (0..5)
+        .map(|_| message(ProtocolId::MempoolDirectSend, "txns"))
+        .collect::<Vec<_>>();
+    let consensus_msg = message(ProtocolId::ConsensusDirectSend, "vote");
+
+    for (send_msg, _) in &mempool_msgs {
+        peer_handle.send_direct_send(send_msg.clone());
+    }
+    peer_handle.send_direct_send(consensus_msg.0.clone());
+
+    let client = async {
+        // Client should receive the consensus message first.
+        let msg = client_stream.next().await.unwrap().unwrap();
+        assert_eq!(msg, consensus_msg.1);
+        for (_, recv_msg) in &mempool_msgs {
+            let msg = client_stream.next().await.unwrap().unwrap();
+            assert_eq!(&msg, recv_msg);
+        }
+        // Client then closes the connection.
+        client_sink.close().await.unwrap();
+    };
+    rt.block_on(future::join(peer.start(), client));
+}
+
 // Reading an inbound DirectSendMsg off the wire should notify the PeerManager of
 // an inbound DirectSend.
 #[test]
@@ -51,6 +51,7 @@ pub fn commit_proof_tx(tx_params: &TxParams, block: VDFProof) -> Result<Transact
         key_pair: Some(tx_params.keypair.clone()),
         sequence_number,
         status: AccountStatus::Persisted,
+        watch_only: false,
     };
 
     let t = submit_tx(client, signed_tx, &mut signer_account_data)?;
@@ -199,6 +199,7 @@ fn stage(
                     key_pair: Some(tx_params.keypair.clone()),
                     sequence_number,
                     status: AccountStatus::Persisted,
+                    watch_only: false,
                 };
                 Ok((signer_account_data, txn))
             } else {
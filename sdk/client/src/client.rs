@@ -2,21 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{
+    new_request,
     request::{JsonRpcRequest, MethodRequest},
     response::{MethodResponse, Response},
     state::StateManager,
-    validate, validate_batch, BatchResponse, USER_AGENT,
+    validate, validate_batch, verify_response, BatchResponse, USER_AGENT,
 };
 use crate::{
     error::WaitForTransactionError,
+    event_stream::{EventCheckpoint, EventStream},
     move_deserialize::{self, Event},
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
-        MetadataView, StateProofView, TransactionView, TransactionsWithProofsView,
+        AccountResourceView, AccountStateWithProofView, AccountView, CurrencyInfoView,
+        EpochInfoView, EventCursor, EventPageView, EventStatsView, EventView, EventWithProofView,
+        MetadataView, ModuleAbiView, ModuleView, ResourceWatchView, ScriptSchemaView,
+        StateProofView, TransactionOutputView, TransactionView, TransactionsWithProofsView,
     },
-    Error, Result, Retry, State,
+    Error, EventFilter, Result, Retry, State, VersionPin,
 };
-use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_crypto::{ed25519::Ed25519PublicKey, hash::CryptoHash, HashValue};
 use diem_types::{
     account_address::AccountAddress,
     event::EventKey,
@@ -33,6 +37,7 @@ pub struct Client {
     inner: ReqwestClient,
     state: StateManager,
     retry: Retry,
+    response_key: Option<Ed25519PublicKey>,
 }
 
 impl Client {
@@ -51,9 +56,36 @@ impl Client {
             inner,
             state: StateManager::new(),
             retry,
+            response_key: None,
         }
     }
 
+    /// Fail responses whose ledger timestamp trails local time by more than `max_ledger_lag`
+    /// with a retriable error (see [`Error::stale_ledger_lag`]), so callers can tell that the
+    /// connected node is behind instead of silently showing outdated state.
+    pub fn with_max_ledger_lag(mut self, max_ledger_lag: Duration) -> Self {
+        self.state.set_max_ledger_lag(Some(max_ledger_lag));
+        self
+    }
+
+    /// Require the responses to reads to be signed by the node's response signing key
+    /// `response_key`, failing the others with an invalid proof error. Each read carries a fresh
+    /// client nonce which the node signs along with the request, the response and the ledger info
+    /// it read, so the responses of a semi-trusted node can't be forged or replayed by whoever
+    /// relays them. Transaction submissions are not signed.
+    pub fn with_response_key(mut self, response_key: Ed25519PublicKey) -> Self {
+        self.response_key = Some(response_key);
+        self
+    }
+
+    /// Pin the reads to `version_pin`, e.g. to the version of the last known state, so that a
+    /// sequence of reads observes a single ledger state. See [`VersionPin`] for the reads served
+    /// at exactly the pinned version. Batches only get the guarantees of [`VersionPin::AtLeast`].
+    pub fn with_version_pin(mut self, version_pin: VersionPin) -> Self {
+        self.state.set_version_pin(Some(version_pin));
+        self
+    }
+
     pub fn last_known_state(&self) -> Option<State> {
         self.state.last_known_state()
     }
@@ -145,14 +177,22 @@ impl Client {
     }
 
     pub async fn get_metadata(&self) -> Result<Response<MetadataView>> {
-        self.send(MethodRequest::get_metadata()).await
+        let request = match self.state.exact_version() {
+            Some(version) => MethodRequest::get_metadata_by_version(version),
+            None => MethodRequest::get_metadata(),
+        };
+        self.send(request).await
     }
 
     pub async fn get_account(
         &self,
         address: AccountAddress,
     ) -> Result<Response<Option<AccountView>>> {
-        self.send(MethodRequest::get_account(address)).await
+        let request = match self.state.exact_version() {
+            Some(version) => MethodRequest::get_account_by_version(address, version),
+            None => MethodRequest::get_account(address),
+        };
+        self.send(request).await
     }
 
     pub async fn get_account_by_version(
@@ -164,18 +204,132 @@ impl Client {
             .await
     }
 
+    //////// 0L ////////
+    /// Fetch a single resource stored under `path` in the account state of `address`, at
+    /// `version` or the latest known version, along with the account state it was read from and
+    /// its proof. `VerifyingClient::get_account_resource` checks them.
+    pub async fn get_account_resource(
+        &self,
+        address: AccountAddress,
+        path: &[u8],
+        version: Option<u64>,
+    ) -> Result<Response<AccountResourceView>> {
+        let version = version.or_else(|| self.state.exact_version());
+        self.send(MethodRequest::get_account_resource(address, path, version))
+            .await
+    }
+
+    /// Returns the write set, events, gas used and status of up to `limit` committed
+    /// transactions starting at `start_version`.
+    pub async fn get_transaction_outputs(
+        &self,
+        start_version: u64,
+        limit: u64,
+    ) -> Result<Response<Vec<TransactionOutputView>>> {
+        self.send(MethodRequest::get_transaction_outputs(start_version, limit))
+            .await
+    }
+
+    /// Returns the validator set of `epoch` and the reconfigurations that started and ended it.
+    pub async fn get_epoch_info(&self, epoch: u64) -> Result<Response<EpochInfoView>> {
+        self.send(MethodRequest::get_epoch_info(epoch)).await
+    }
+
+    /// Returns the number of events emitted under `key` and the range they span.
+    pub async fn get_event_stats(&self, key: EventKey) -> Result<Response<EventStatsView>> {
+        self.send(MethodRequest::get_event_stats(key)).await
+    }
+
+    /// Returns a page of at most `limit` events emitted under `key`, from `cursor` or from the
+    /// first event. Continue from the page's `next` cursor.
+    pub async fn get_events_page(
+        &self,
+        key: EventKey,
+        limit: u64,
+        cursor: Option<EventCursor>,
+    ) -> Result<Response<EventPageView>> {
+        self.send(MethodRequest::get_events_page(key, limit, cursor))
+            .await
+    }
+
+    /// Runs the checks of `submit` on `txn` without submitting it, failing with the error
+    /// `submit` would fail with.
+    pub async fn validate_transaction(&self, txn: &SignedTransaction) -> Result<Response<()>> {
+        self.send(MethodRequest::validate_transaction(txn).map_err(Error::request)?)
+            .await
+    }
+
+    /// Allocates the next sequence number of `address` on the node, which concurrent submitters
+    /// of the account take their sequence numbers from instead of `get_account`, so their
+    /// transactions don't collide. The node must allocate sequence numbers to the account.
+    pub async fn allocate_sequence_number(&self, address: AccountAddress) -> Result<Response<u64>> {
+        self.send(MethodRequest::allocate_sequence_number(address))
+            .await
+    }
+
+    /// Fetch the bytecode of the module `name` published under `address`, at `version` or the
+    /// latest known version. The returned value is not accompanied by a proof.
+    pub async fn get_module(
+        &self,
+        address: AccountAddress,
+        name: &str,
+        version: Option<u64>,
+    ) -> Result<Response<Option<ModuleView>>> {
+        let version = version.or_else(|| self.state.exact_version());
+        self.send(MethodRequest::get_module(address, name, version))
+            .await
+    }
+
+    /// Fetch the ABI of the module `name` published under `address`, derived from its bytecode,
+    /// at `version` or the latest known version.
+    pub async fn get_module_abi(
+        &self,
+        address: AccountAddress,
+        name: &str,
+        version: Option<u64>,
+    ) -> Result<Response<Option<ModuleAbiView>>> {
+        let version = version.or_else(|| self.state.exact_version());
+        self.send(MethodRequest::get_module_abi(address, name, version))
+            .await
+    }
+
+    /// Fetch the schemas of the arguments of the transaction scripts known to the node, to
+    /// build script payloads without hardcoding their arguments.
+    pub async fn get_script_schemas(&self) -> Result<Response<Vec<ScriptSchemaView>>> {
+        self.send(MethodRequest::get_script_schemas()).await
+    }
+
+    /// Watch the resources at the given access paths of the given accounts. The node posts
+    /// their values before and after to `callback_url` at every commit changing them, until
+    /// the watch is removed by `unwatch_resources` or the node restarts.
+    pub async fn watch_resources(
+        &self,
+        callback_url: &str,
+        resources: &[(AccountAddress, Vec<u8>)],
+    ) -> Result<Response<ResourceWatchView>> {
+        self.send(MethodRequest::watch_resources(callback_url, resources))
+            .await
+    }
+
+    /// Remove the watch `id`, returning whether it was registered.
+    pub async fn unwatch_resources(&self, id: u64) -> Result<Response<bool>> {
+        self.send(MethodRequest::unwatch_resources(id)).await
+    }
+
     pub async fn get_transactions(
         &self,
         start_seq: u64,
         limit: u64,
         include_events: bool,
     ) -> Result<Response<Vec<TransactionView>>> {
-        self.send(MethodRequest::get_transactions(
-            start_seq,
-            limit,
-            include_events,
-        ))
-        .await
+        let response: Response<Vec<TransactionView>> = self
+            .send(MethodRequest::get_transactions(
+                start_seq,
+                limit,
+                include_events,
+            ))
+            .await?;
+        response.and_then(|txns| Ok(self.state.truncate_to_pin(txns, |txn| txn.version)))
     }
 
     pub async fn get_account_transaction(
@@ -184,12 +338,19 @@ impl Client {
         seq: u64,
         include_events: bool,
     ) -> Result<Response<Option<TransactionView>>> {
-        self.send(MethodRequest::get_account_transaction(
-            address,
-            seq,
-            include_events,
-        ))
-        .await
+        let response: Response<Option<TransactionView>> = self
+            .send(MethodRequest::get_account_transaction(
+                address,
+                seq,
+                include_events,
+            ))
+            .await?;
+        response.and_then(|txn| {
+            let txns = self
+                .state
+                .truncate_to_pin(txn.into_iter().collect(), |txn| txn.version);
+            Ok(txns.into_iter().next())
+        })
     }
 
     pub async fn get_account_transactions(
@@ -199,13 +360,15 @@ impl Client {
         limit: u64,
         include_events: bool,
     ) -> Result<Response<Vec<TransactionView>>> {
-        self.send(MethodRequest::get_account_transactions(
-            address,
-            start_seq,
-            limit,
-            include_events,
-        ))
-        .await
+        let response: Response<Vec<TransactionView>> = self
+            .send(MethodRequest::get_account_transactions(
+                address,
+                start_seq,
+                limit,
+                include_events,
+            ))
+            .await?;
+        response.and_then(|txns| Ok(self.state.truncate_to_pin(txns, |txn| txn.version)))
     }
 
     pub async fn get_events(
@@ -214,8 +377,41 @@ impl Client {
         start_seq: u64,
         limit: u64,
     ) -> Result<Response<Vec<EventView>>> {
-        self.send(MethodRequest::get_events(key, start_seq, limit))
-            .await
+        let response: Response<Vec<EventView>> = self
+            .send(MethodRequest::get_events(key, start_seq, limit))
+            .await?;
+        response.and_then(|events| {
+            Ok(self
+                .state
+                .truncate_to_pin(events, |event| event.transaction_version))
+        })
+    }
+
+    /// Like `get_events`, but the node only returns the events of the page matching `filter`, so
+    /// a page may hold fewer than `limit` events even when more follow.
+    pub async fn get_filtered_events(
+        &self,
+        key: EventKey,
+        start_seq: u64,
+        limit: u64,
+        filter: EventFilter,
+    ) -> Result<Response<Vec<EventView>>> {
+        let response: Response<Vec<EventView>> = self
+            .send(MethodRequest::get_filtered_events(
+                key, start_seq, limit, filter,
+            ))
+            .await?;
+        response.and_then(|events| {
+            Ok(self
+                .state
+                .truncate_to_pin(events, |event| event.transaction_version))
+        })
+    }
+
+    /// Streams the events emitted to `checkpoint.key` from `checkpoint` on, historical events
+    /// first, then new events as they are committed. See [`EventStream`].
+    pub fn stream_events(&self, checkpoint: EventCheckpoint) -> EventStream {
+        EventStream::new(self.clone(), checkpoint)
     }
 
     pub async fn get_currencies(&self) -> Result<Response<Vec<CurrencyInfoView>>> {
@@ -241,10 +437,11 @@ impl Client {
         from_version: Option<u64>,
         to_version: Option<u64>,
     ) -> Result<Response<AccountStateWithProofView>> {
+        let exact_version = self.state.exact_version();
         self.send(MethodRequest::get_account_state_with_proof(
             address,
-            from_version,
-            to_version,
+            from_version.or(exact_version),
+            to_version.or(exact_version),
         ))
         .await
     }
@@ -273,6 +470,21 @@ impl Client {
             .await
     }
 
+    /// Like `get_events_with_proofs`, but the node only returns the events of the page matching
+    /// `filter`.
+    pub async fn get_filtered_events_with_proofs(
+        &self,
+        key: EventKey,
+        start_seq: u64,
+        limit: u64,
+        filter: EventFilter,
+    ) -> Result<Response<Vec<EventWithProofView>>> {
+        self.send(MethodRequest::get_filtered_events_with_proofs(
+            key, start_seq, limit, filter,
+        ))
+        .await
+    }
+
     /// Return the events of type `T` that have been emitted to `event_key` since `start_seq`, with a max of `limit`
     /// results
     /// Returns an empty vector if there are no such events
@@ -314,7 +526,11 @@ impl Client {
     //
 
     async fn send<T: DeserializeOwned>(&self, request: MethodRequest) -> Result<Response<T>> {
-        let request = JsonRpcRequest::new(request);
+        let request = new_request(
+            request,
+            self.response_key.as_ref(),
+            self.state.version_pin(),
+        );
 
         self.retry
             .retry_async(|| async { self.send_without_retry(&request, false).await })
@@ -328,6 +544,7 @@ impl Client {
     ) -> Result<Response<T>> {
         let resp: diem_json_rpc_types::response::JsonRpcResponse = self.send_impl(&request).await?;
 
+        verify_response(self.response_key.as_ref(), request, &resp)?;
         let (id, state, result) = validate(&self.state, &resp, ignore_stale)?;
 
         if request.id() != id {
@@ -342,12 +559,21 @@ impl Client {
         &self,
         requests: Vec<MethodRequest>,
     ) -> Result<Vec<Result<Response<MethodResponse>>>> {
-        let request: Vec<JsonRpcRequest> = requests.into_iter().map(JsonRpcRequest::new).collect();
+        let request: Vec<JsonRpcRequest> = requests
+            .into_iter()
+            .map(|request| {
+                new_request(
+                    request,
+                    self.response_key.as_ref(),
+                    self.state.version_pin(),
+                )
+            })
+            .collect();
         let resp: BatchResponse = self.send_impl(&request).await?;
 
         let resp = resp.success()?;
 
-        validate_batch(&self.state, &request, resp)
+        validate_batch(&self.state, self.response_key.as_ref(), &request, resp)
     }
 
     async fn send_impl<S: Serialize, T: DeserializeOwned>(&self, payload: &S) -> Result<T> {
@@ -6,6 +6,7 @@
 #![allow(dead_code)]
 
 use diem_json_rpc_types::errors::JsonRpcError;
+use std::time::Duration;
 
 pub type Result<T, E = Error> = ::std::result::Result<T, E>;
 
@@ -18,44 +19,79 @@ type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 #[derive(Debug)]
 struct Inner {
-    kind: Kind,
+    kind: ErrorKind,
     source: Option<BoxError>,
     json_rpc_error: Option<JsonRpcError>,
 }
 
-#[derive(Debug)]
-enum Kind {
+/// What went wrong with a request, see [`Error::kind`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The node answered with this HTTP status, other than 200
     HttpStatus(u16),
     Timeout,
+    /// The request could not be sent
     Request,
+    /// The node answered with a JSON-RPC error, see [`Error::json_rpc_error`]
     JsonRpcError,
+    /// The response doesn't match the request
     RpcResponse,
+    /// The node is on another chain
     ChainId,
+    /// The node is behind the state the client has already seen, or pinned its reads to
     StaleResponse,
+    /// The node's latest ledger timestamp trails local time by more than the configured threshold
+    StaleLedger {
+        lag: Duration,
+    },
+    /// The responses of a batch don't match its requests
     Batch,
     Decode,
     InvalidProof,
+    /// See [`Error::is_conflicting_ledger_info`]
+    ConflictingLedgerInfo,
     Unknown,
 }
 
 impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        self.inner.kind
+    }
+
     pub fn json_rpc_error(&self) -> Option<&JsonRpcError> {
         self.inner.json_rpc_error.as_ref()
     }
 
+    /// If the connected node's latest ledger timestamp trailed local time by more than the
+    /// client's configured threshold, returns how far behind it was.
+    pub fn stale_ledger_lag(&self) -> Option<Duration> {
+        match self.inner.kind {
+            ErrorKind::StaleLedger { lag } => Some(lag),
+            _ => None,
+        }
+    }
+
+    /// Whether nodes presented verified ledger infos certifying different transaction histories
+    /// at the same version, i.e. validators equivocated or the client's trusted state is
+    /// compromised. Such errors are unrecoverable: the `VerifyingClient` fails every request after.
+    pub fn is_conflicting_ledger_info(&self) -> bool {
+        matches!(self.inner.kind, ErrorKind::ConflictingLedgerInfo)
+    }
+
     pub fn is_retriable(&self) -> bool {
         match self.inner.kind {
             // internal server errors are retriable
-            Kind::HttpStatus(status) => (500..=599).contains(&status),
-            Kind::Timeout | Kind::StaleResponse => true,
-            Kind::RpcResponse
-            | Kind::Request
-            | Kind::JsonRpcError
-            | Kind::ChainId
-            | Kind::Batch
-            | Kind::Decode
-            | Kind::InvalidProof
-            | Kind::Unknown => false,
+            ErrorKind::HttpStatus(status) => (500..=599).contains(&status),
+            ErrorKind::Timeout | ErrorKind::StaleResponse | ErrorKind::StaleLedger { .. } => true,
+            ErrorKind::RpcResponse
+            | ErrorKind::Request
+            | ErrorKind::JsonRpcError
+            | ErrorKind::ChainId
+            | ErrorKind::Batch
+            | ErrorKind::Decode
+            | ErrorKind::InvalidProof
+            | ErrorKind::ConflictingLedgerInfo
+            | ErrorKind::Unknown => false,
         }
     }
 
@@ -63,7 +99,7 @@ impl Error {
     // Private Constructors
     //
 
-    fn new<E: Into<BoxError>>(kind: Kind, source: Option<E>) -> Self {
+    fn new<E: Into<BoxError>>(kind: ErrorKind, source: Option<E>) -> Self {
         Self {
             inner: Box::new(Inner {
                 kind,
@@ -79,55 +115,86 @@ impl Error {
     }
 
     pub(crate) fn status(status: u16) -> Self {
-        Self::new(Kind::HttpStatus(status), None::<Error>)
+        Self::new(ErrorKind::HttpStatus(status), None::<Error>)
     }
 
     pub(crate) fn timeout<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::Timeout, Some(e))
+        Self::new(ErrorKind::Timeout, Some(e))
     }
 
     pub(crate) fn json_rpc(json_rpc_error: JsonRpcError) -> Self {
-        Self::new(Kind::JsonRpcError, None::<Error>).with_json_rpc_error(json_rpc_error)
+        Self::new(ErrorKind::JsonRpcError, None::<Error>).with_json_rpc_error(json_rpc_error)
     }
 
     pub(crate) fn rpc_response<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::RpcResponse, Some(e))
+        Self::new(ErrorKind::RpcResponse, Some(e))
     }
 
     pub(crate) fn batch<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::Batch, Some(e))
+        Self::new(ErrorKind::Batch, Some(e))
     }
 
     pub(crate) fn decode<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::Decode, Some(e))
+        Self::new(ErrorKind::Decode, Some(e))
     }
 
     pub(crate) fn invalid_proof<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::InvalidProof, Some(e))
+        Self::new(ErrorKind::InvalidProof, Some(e))
     }
 
     pub(crate) fn unknown<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::Unknown, Some(e))
+        Self::new(ErrorKind::Unknown, Some(e))
     }
 
     pub(crate) fn request<E: Into<BoxError>>(e: E) -> Self {
-        Self::new(Kind::Request, Some(e))
+        Self::new(ErrorKind::Request, Some(e))
     }
 
     pub(crate) fn chain_id(expected: u8, recieved: u8) -> Self {
         Self::new(
-            Kind::ChainId,
+            ErrorKind::ChainId,
             Some(format!("expected: {} recieved: {}", expected, recieved)),
         )
     }
 
     pub(crate) fn stale(expected: &super::State, recieved: &super::State) -> Self {
         Self::new(
-            Kind::StaleResponse,
+            ErrorKind::StaleResponse,
             Some(format!("expected: {:?} recieved: {:?}", expected, recieved)),
         )
     }
 
+    pub(crate) fn conflicting_ledger_info(
+        version: u64,
+        trusted: &diem_crypto::HashValue,
+        received: &diem_crypto::HashValue,
+    ) -> Self {
+        Self::new(
+            ErrorKind::ConflictingLedgerInfo,
+            Some(format!(
+                "conflicting ledger infos at version {}: verified accumulator root {} before, received {}",
+                version, trusted, received
+            )),
+        )
+    }
+
+    pub(crate) fn behind_version_pin(pinned_version: u64, received: &super::State) -> Self {
+        Self::new(
+            ErrorKind::StaleResponse,
+            Some(format!(
+                "expected version at least {} received: {:?}",
+                pinned_version, received
+            )),
+        )
+    }
+
+    pub(crate) fn stale_ledger(lag: Duration) -> Self {
+        Self::new(
+            ErrorKind::StaleLedger { lag },
+            Some(format!("ledger is {:?} behind local time", lag)),
+        )
+    }
+
     cfg_async! {
         pub(crate) fn from_reqwest_error(e: reqwest::Error) -> Self {
             if e.is_timeout() {
@@ -0,0 +1,261 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{views::EventView, Client, Error, Result};
+use diem_types::event::EventKey;
+use std::{collections::VecDeque, fmt, str::FromStr, time::Duration};
+
+const DEFAULT_PAGE_SIZE: u64 = 100;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Position in the events of an event key to resume streaming from, i.e. the sequence number of
+/// the next event to return.
+///
+/// Checkpoints print as `<event key>:<sequence number>` tokens which parse back into checkpoints,
+/// so consumers can persist them along with the events they processed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EventCheckpoint {
+    pub key: EventKey,
+    pub next_sequence_number: u64,
+}
+
+impl EventCheckpoint {
+    pub fn new(key: EventKey, next_sequence_number: u64) -> Self {
+        Self {
+            key,
+            next_sequence_number,
+        }
+    }
+}
+
+impl fmt::Display for EventCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.key, self.next_sequence_number)
+    }
+}
+
+impl FromStr for EventCheckpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, next_sequence_number) = s
+            .split_once(':')
+            .ok_or_else(|| Error::decode(format!("invalid event checkpoint {}", s)))?;
+        Ok(Self::new(
+            key.parse().map_err(Error::decode)?,
+            next_sequence_number.parse().map_err(Error::decode)?,
+        ))
+    }
+}
+
+/// The events emitted to an event key from a checkpoint on: the historical events first, paged
+/// through from storage, then the new events as they are committed, polled for once the stream
+/// has caught up with the ledger.
+pub struct EventStream {
+    client: Client,
+    checkpoint: EventCheckpoint,
+    page_size: u64,
+    poll_interval: Duration,
+    fetched: VecDeque<EventView>,
+}
+
+impl EventStream {
+    pub fn new(client: Client, checkpoint: EventCheckpoint) -> Self {
+        Self {
+            client,
+            checkpoint,
+            page_size: DEFAULT_PAGE_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            fetched: VecDeque::new(),
+        }
+    }
+
+    /// Number of events requested at once, at most the page size limit of the node.
+    ///
+    /// A page size of 0 is raised to 1: empty pages would be taken for the stream having caught
+    /// up, and the stream would wait for new events forever.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// How long to wait before asking for new events again once the stream has caught up.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the checkpoint resuming the stream after the last event returned by
+    /// [`EventStream::next`].
+    pub fn checkpoint(&self) -> EventCheckpoint {
+        self.checkpoint
+    }
+
+    /// Returns the next event, waiting for it to be committed if the stream has caught up.
+    ///
+    /// Errors of the requests to the node are returned as they happen; calling `next` again
+    /// resumes the stream from the same event.
+    pub async fn next(&mut self) -> Result<EventView> {
+        loop {
+            if let Some(event) = self.pop_fetched() {
+                return Ok(event);
+            }
+
+            let (key, start, limit) = self.next_page_request();
+            let events = self
+                .client
+                .get_events(key, start, limit)
+                .await?
+                .into_inner();
+            if !self.push_page(start, events)? {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    /// The event key, first sequence number and number of events of the next page to request.
+    fn next_page_request(&self) -> (EventKey, u64, u64) {
+        (
+            self.checkpoint.key,
+            self.checkpoint.next_sequence_number,
+            self.page_size,
+        )
+    }
+
+    /// Takes the next fetched event, moving the checkpoint past it.
+    fn pop_fetched(&mut self) -> Option<EventView> {
+        let event = self.fetched.pop_front()?;
+        self.checkpoint.next_sequence_number = event.sequence_number + 1;
+        Some(event)
+    }
+
+    /// Checks and queues the page of events requested from `start`. Returns false if the page is
+    /// empty, i.e. the stream has caught up with the ledger.
+    fn push_page(&mut self, start: u64, events: Vec<EventView>) -> Result<bool> {
+        let key = self.checkpoint.key;
+        for (sequence_number, event) in (start..).zip(&events) {
+            if event.key != key || event.sequence_number != sequence_number {
+                return Err(Error::rpc_response(format!(
+                    "expected event {} of {}, got event {} of {}",
+                    sequence_number, key, event.sequence_number, event.key
+                )));
+            }
+        }
+        let caught_up = events.is_empty();
+        self.fetched.extend(events);
+        Ok(!caught_up)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, views::EventDataView};
+    use diem_types::account_address::AccountAddress;
+
+    fn event_key(salt: u64) -> EventKey {
+        EventKey::new_from_address(&AccountAddress::ZERO, salt)
+    }
+
+    fn event(key: EventKey, sequence_number: u64) -> EventView {
+        EventView {
+            key,
+            sequence_number,
+            transaction_version: sequence_number,
+            data: EventDataView::Unknown { bytes: None },
+        }
+    }
+
+    fn stream(page_size: u64) -> EventStream {
+        let checkpoint = EventCheckpoint::new(event_key(0), 0);
+        Client::new("http://localhost")
+            .stream_events(checkpoint)
+            .with_page_size(page_size)
+    }
+
+    /// Requests the next page from `events`, the events committed so far, as the node would
+    /// answer it. Returns false if the stream has caught up.
+    fn fetch_page(stream: &mut EventStream, events: &[EventView]) -> bool {
+        let (key, start, limit) = stream.next_page_request();
+        assert_eq!(key, stream.checkpoint().key);
+        let page = events
+            .iter()
+            .skip(start as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        stream.push_page(start, page).unwrap()
+    }
+
+    /// Returns the fetched events, checking the checkpoint moves past each of them.
+    fn pop_all(stream: &mut EventStream) -> Vec<u64> {
+        let mut sequence_numbers = vec![];
+        while let Some(event) = stream.pop_fetched() {
+            assert_eq!(
+                stream.checkpoint().next_sequence_number,
+                event.sequence_number + 1
+            );
+            sequence_numbers.push(event.sequence_number);
+        }
+        sequence_numbers
+    }
+
+    #[test]
+    fn test_paging() {
+        let mut stream = stream(2);
+        let key = stream.checkpoint().key;
+        let events: Vec<_> = (0..5).map(|i| event(key, i)).collect();
+
+        let mut pages = vec![];
+        while fetch_page(&mut stream, &events) {
+            pages.push(pop_all(&mut stream));
+        }
+        assert_eq!(pages, vec![vec![0, 1], vec![2, 3], vec![4]]);
+        assert_eq!(stream.checkpoint().next_sequence_number, 5);
+
+        // A page which doesn't start at the checkpoint, or of another key, is rejected
+        let error = stream.push_page(5, vec![event(key, 6)]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::RpcResponse);
+        let error = stream
+            .push_page(5, vec![event(event_key(1), 5)])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::RpcResponse);
+        assert!(stream.pop_fetched().is_none());
+    }
+
+    #[test]
+    fn test_polling_after_head() {
+        let mut stream = stream(10);
+        let key = stream.checkpoint().key;
+        let mut events: Vec<_> = (0..3).map(|i| event(key, i)).collect();
+
+        assert!(fetch_page(&mut stream, &events));
+        assert_eq!(pop_all(&mut stream), vec![0, 1, 2]);
+
+        // Caught up: the stream keeps polling from its checkpoint
+        for _ in 0..3 {
+            assert!(!fetch_page(&mut stream, &events));
+            assert!(stream.pop_fetched().is_none());
+            assert_eq!(stream.next_page_request().1, 3);
+        }
+
+        // Until new events are committed
+        events.extend((3..5).map(|i| event(key, i)));
+        assert!(fetch_page(&mut stream, &events));
+        assert_eq!(pop_all(&mut stream), vec![3, 4]);
+        assert!(!fetch_page(&mut stream, &events));
+    }
+
+    #[test]
+    fn test_invalid_page_size() {
+        let mut stream = stream(0);
+        let key = stream.checkpoint().key;
+        let events: Vec<_> = (0..2).map(|i| event(key, i)).collect();
+
+        assert_eq!(stream.next_page_request().2, 1);
+        assert!(fetch_page(&mut stream, &events));
+        assert_eq!(pop_all(&mut stream), vec![0]);
+        assert!(fetch_page(&mut stream, &events));
+        assert_eq!(pop_all(&mut stream), vec![1]);
+    }
+}
@@ -5,7 +5,7 @@
 mod macros;
 
 mod error;
-pub use error::{Error, Result, WaitForTransactionError};
+pub use error::{Error, ErrorKind, Result, WaitForTransactionError};
 
 cfg_blocking! {
     mod blocking;
@@ -16,6 +16,9 @@ cfg_async! {
     mod client;
     pub use client::Client;
 
+    mod event_stream;
+    pub use event_stream::{EventCheckpoint, EventStream};
+
     mod verifying_client;
     // WARNING: the VerifyingClient is currently experimental; it's not recommended
     // to use it until it stabilizes further
@@ -41,12 +44,12 @@ cfg_async_or_blocking! {
 }
 
 mod state;
-pub use state::State;
+pub use state::{State, VersionPin};
 
 mod retry;
 pub use retry::Retry;
 
-pub use diem_json_rpc_types::{errors, views};
+pub use diem_json_rpc_types::{errors, request::EventFilter, views};
 pub use diem_types::{account_address::AccountAddress, transaction::SignedTransaction};
 
 use serde::{Deserialize, Serialize};
@@ -86,9 +89,55 @@ pub enum Method {
     GetTowerStateView,         /////// 0L /////////
     GetOracleUpgradeStateView, /////// 0L /////////
     GetWaypointView, /////// 0L /////////
+    GetAccountResource,        /////// 0L /////////
+    GetTransactionOutputs,     /////// 0L /////////
+    GetEpochInfo,              /////// 0L /////////
+    GetEventStats,             /////// 0L /////////
+    ValidateTransaction,       /////// 0L /////////
+    AllocateSequenceNumber,    /////// 0L /////////
+    GetModule,                 /////// 0L /////////
+    GetModuleAbi,              /////// 0L /////////
+    GetEventsPage,             /////// 0L /////////
+    GetScriptSchemas,          /////// 0L /////////
+    WatchResources,            /////// 0L /////////
+    UnwatchResources,          /////// 0L /////////
 }
 
 cfg_async_or_blocking! {
+    use diem_crypto::{ed25519::Ed25519PublicKey, HashValue};
+
+    /// Wraps `request`, with a fresh client nonce when responses are verified against
+    /// `response_key`, and the version the reads are pinned to, if any.
+    fn new_request(
+        request: MethodRequest,
+        response_key: Option<&Ed25519PublicKey>,
+        version_pin: Option<VersionPin>,
+    ) -> JsonRpcRequest {
+        let mut request = JsonRpcRequest::new(request);
+        if let Some(version_pin) = version_pin {
+            request = request.with_min_version(version_pin.version());
+        }
+        match response_key {
+            Some(_) => request.with_client_nonce(HashValue::random().to_hex()),
+            None => request,
+        }
+    }
+
+    /// Checks that `resp` is signed by the node's `response_key` in answer to `request`, when the
+    /// request carries a client nonce.
+    fn verify_response(
+        response_key: Option<&Ed25519PublicKey>,
+        request: &JsonRpcRequest,
+        resp: &diem_json_rpc_types::response::JsonRpcResponse,
+    ) -> Result<()> {
+        if let (Some(public_key), Some(_)) = (response_key, request.client_nonce()) {
+            let request = serde_json::to_value(request).map_err(Error::request)?;
+            resp.verify_signature(&request, public_key)
+                .map_err(Error::invalid_proof)?;
+        }
+        Ok(())
+    }
+
     fn validate(
         state_manager: &state::StateManager,
         resp: &diem_json_rpc_types::response::JsonRpcResponse,
@@ -121,6 +170,7 @@ cfg_async_or_blocking! {
 
     fn validate_batch(
         state_manager: &state::StateManager,
+        response_key: Option<&Ed25519PublicKey>,
         requests: &[JsonRpcRequest],
         raw_responses: Vec<diem_json_rpc_types::response::JsonRpcResponse>,
     ) -> Result<Vec<Result<Response<MethodResponse>>>> {
@@ -129,22 +179,25 @@ cfg_async_or_blocking! {
             let id = get_id(&raw_response)?;
             let response = validate(state_manager, &raw_response, false);
 
-            responses.insert(id, response);
+            responses.insert(id, (raw_response, response));
         }
 
         let mut result = Vec::new();
 
         for request in requests {
-            let response = if let Some(response) = responses.remove(&request.id()) {
-                response
-            } else {
-                return Err(Error::batch(format!("{:?}", raw_responses)));
-            };
-
-            let response = response.and_then(|(_id, state, result)| {
-                MethodResponse::from_json(request.method(), result)
-                    .map(|result| Response::new(result, state))
-            });
+            let (raw_response, response) =
+                if let Some(response) = responses.remove(&request.id()) {
+                    response
+                } else {
+                    return Err(Error::batch(format!("{:?}", raw_responses)));
+                };
+
+            let response = verify_response(response_key, request, raw_response)
+                .and(response)
+                .and_then(|(_id, state, result)| {
+                    MethodResponse::from_json(request.method(), result)
+                        .map(|result| Response::new(result, state))
+                });
 
             result.push(response);
         }
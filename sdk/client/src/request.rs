@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{JsonRpcVersion, Method};
+use diem_json_rpc_types::{request::EventFilter, views::EventCursor};
 use diem_types::{
     account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
 };
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use std::sync::atomic::AtomicU64;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -35,6 +36,22 @@ pub enum MethodRequest {
     GetTowerStateView((AccountAddress,)), // this is the format for passing a list of params
     GetOracleUpgradeStateView(),
     GetWaypointView(),
+    GetAccountResource(AccountAddress, String, Option<u64>),
+    GetTransactionOutputs(u64, u64),
+    GetEpochInfo((u64,)),
+    GetEventStats((EventKey,)),
+    ValidateTransaction((String,)),
+    AllocateSequenceNumber((AccountAddress,)),
+    GetModule(AccountAddress, String, Option<u64>),
+    GetModuleAbi(AccountAddress, String, Option<u64>),
+    GetEventsPage(EventKey, u64, Option<EventCursor>),
+    GetScriptSchemas([(); 0]),
+    WatchResources(String, Vec<(AccountAddress, String)>),
+    UnwatchResources((u64,)),
+    // Sent as `get_events` and `get_events_with_proofs`. The filter follows the `min_version`
+    // param, so its slot is part of the params rather than appended by `with_min_version`.
+    GetFilteredEvents(EventKey, u64, u64, Option<u64>, EventFilter),
+    GetFilteredEventsWithProofs(EventKey, u64, u64, Option<u64>, EventFilter),
 }
 
 impl MethodRequest {
@@ -74,6 +91,66 @@ impl MethodRequest {
         Self::GetWaypointView()
     }
 
+    /////// 0L /////////
+    pub fn get_account_resource(
+        address: AccountAddress,
+        path: &[u8],
+        version: Option<u64>,
+    ) -> Self {
+        Self::GetAccountResource(address, hex::encode(path), version)
+    }
+
+    pub fn get_transaction_outputs(start_version: u64, limit: u64) -> Self {
+        Self::GetTransactionOutputs(start_version, limit)
+    }
+
+    pub fn get_epoch_info(epoch: u64) -> Self {
+        Self::GetEpochInfo((epoch,))
+    }
+
+    pub fn get_event_stats(key: EventKey) -> Self {
+        Self::GetEventStats((key,))
+    }
+
+    pub fn get_events_page(key: EventKey, limit: u64, cursor: Option<EventCursor>) -> Self {
+        Self::GetEventsPage(key, limit, cursor)
+    }
+
+    pub fn get_script_schemas() -> Self {
+        Self::GetScriptSchemas([])
+    }
+
+    pub fn watch_resources(callback_url: &str, resources: &[(AccountAddress, Vec<u8>)]) -> Self {
+        Self::WatchResources(
+            callback_url.to_string(),
+            resources
+                .iter()
+                .map(|(address, path)| (*address, hex::encode(path)))
+                .collect(),
+        )
+    }
+
+    pub fn unwatch_resources(id: u64) -> Self {
+        Self::UnwatchResources((id,))
+    }
+
+    pub fn validate_transaction(txn: &SignedTransaction) -> Result<Self, bcs::Error> {
+        let txn_payload = hex::encode(bcs::to_bytes(txn)?);
+        Ok(Self::ValidateTransaction((txn_payload,)))
+    }
+
+    pub fn allocate_sequence_number(address: AccountAddress) -> Self {
+        Self::AllocateSequenceNumber((address,))
+    }
+
+    pub fn get_module(address: AccountAddress, name: &str, version: Option<u64>) -> Self {
+        Self::GetModule(address, name.to_string(), version)
+    }
+
+    pub fn get_module_abi(address: AccountAddress, name: &str, version: Option<u64>) -> Self {
+        Self::GetModuleAbi(address, name.to_string(), version)
+    }
+
 
     pub fn get_transactions(start_seq: u64, limit: u64, include_events: bool) -> Self {
         Self::GetTransactions(start_seq, limit, include_events)
@@ -135,6 +212,51 @@ impl MethodRequest {
         Self::GetEventsWithProofs(key, start_seq, limit)
     }
 
+    /// Like `get_events`, but the node only returns the events of the page matching `filter`.
+    pub fn get_filtered_events(
+        key: EventKey,
+        start_seq: u64,
+        limit: u64,
+        filter: EventFilter,
+    ) -> Self {
+        Self::GetFilteredEvents(key, start_seq, limit, None, filter)
+    }
+
+    /// Like `get_events_with_proofs`, but the node only returns the events of the page matching
+    /// `filter`.
+    pub fn get_filtered_events_with_proofs(
+        key: EventKey,
+        start_seq: u64,
+        limit: u64,
+        filter: EventFilter,
+    ) -> Self {
+        Self::GetFilteredEventsWithProofs(key, start_seq, limit, None, filter)
+    }
+
+    /// Whether the method takes a trailing `min_version` parameter, i.e. whether the node waits to
+    /// have synced to a given version before serving it.
+    pub fn supports_min_version(&self) -> bool {
+        matches!(
+            self,
+            MethodRequest::GetAccount(..)
+                | MethodRequest::GetTransactions(..)
+                | MethodRequest::GetAccountTransaction(..)
+                | MethodRequest::GetAccountTransactions(..)
+                | MethodRequest::GetEvents(..)
+                | MethodRequest::GetAccountStateWithProof(..)
+                | MethodRequest::GetTransactionsWithProofs(..)
+                | MethodRequest::GetEventsWithProofs(..)
+                | MethodRequest::GetAccountResource(..)
+                | MethodRequest::GetTransactionOutputs(..)
+                | MethodRequest::GetEventStats(..)
+                | MethodRequest::GetModule(..)
+                | MethodRequest::GetModuleAbi(..)
+                | MethodRequest::GetEventsPage(..)
+                | MethodRequest::GetFilteredEvents(..)
+                | MethodRequest::GetFilteredEventsWithProofs(..)
+        )
+    }
+
     pub fn method(&self) -> Method {
         match self {
             MethodRequest::Submit(_) => Method::Submit,
@@ -154,17 +276,36 @@ impl MethodRequest {
             MethodRequest::GetTowerStateView(_) => Method::GetTowerStateView,
             MethodRequest::GetOracleUpgradeStateView() => Method::GetOracleUpgradeStateView,
             MethodRequest::GetWaypointView() => Method::GetWaypointView,
+            MethodRequest::GetAccountResource(_, _, _) => Method::GetAccountResource,
+            MethodRequest::GetTransactionOutputs(_, _) => Method::GetTransactionOutputs,
+            MethodRequest::GetEpochInfo(_) => Method::GetEpochInfo,
+            MethodRequest::GetEventStats(_) => Method::GetEventStats,
+            MethodRequest::ValidateTransaction(_) => Method::ValidateTransaction,
+            MethodRequest::AllocateSequenceNumber(_) => Method::AllocateSequenceNumber,
+            MethodRequest::GetModule(..) => Method::GetModule,
+            MethodRequest::GetModuleAbi(..) => Method::GetModuleAbi,
+            MethodRequest::GetEventsPage(..) => Method::GetEventsPage,
+            MethodRequest::GetScriptSchemas(_) => Method::GetScriptSchemas,
+            MethodRequest::WatchResources(..) => Method::WatchResources,
+            MethodRequest::UnwatchResources(_) => Method::UnwatchResources,
+            MethodRequest::GetFilteredEvents(..) => Method::GetEvents,
+            MethodRequest::GetFilteredEventsWithProofs(..) => Method::GetEventsWithProofs,
             
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize)]
 pub struct JsonRpcRequest {
     jsonrpc: JsonRpcVersion,
     #[serde(flatten)]
     method_request: MethodRequest,
     id: u64,
+    #[serde(default)]
+    diem_client_nonce: Option<String>,
+    // Sent as the trailing parameter of `method_request`, see `with_min_version`
+    #[serde(skip)]
+    min_version: Option<u64>,
 }
 
 impl JsonRpcRequest {
@@ -177,14 +318,68 @@ impl JsonRpcRequest {
             jsonrpc: JsonRpcVersion::V2,
             method_request,
             id,
+            diem_client_nonce: None,
+            min_version: None,
         }
     }
 
+    /// Asks the node to wait until it has synced to `min_version` before serving the request,
+    /// if its method supports it (see `MethodRequest::supports_min_version`).
+    pub fn with_min_version(mut self, min_version: u64) -> Self {
+        match &mut self.method_request {
+            MethodRequest::GetFilteredEvents(_, _, _, slot, _)
+            | MethodRequest::GetFilteredEventsWithProofs(_, _, _, slot, _) => {
+                *slot = Some(min_version)
+            }
+            request if request.supports_min_version() => self.min_version = Some(min_version),
+            _ => {}
+        }
+        self
+    }
+
+    /// Asks the node to sign its response, binding it to this request, `client_nonce` and the
+    /// ledger info it is served at. The nonce should not be reused.
+    pub fn with_client_nonce(mut self, client_nonce: String) -> Self {
+        self.diem_client_nonce = Some(client_nonce);
+        self
+    }
+
     pub fn id(&self) -> u64 {
         self.id
     }
 
+    pub fn client_nonce(&self) -> Option<&str> {
+        self.diem_client_nonce.as_deref()
+    }
+
     pub fn method(&self) -> Method {
         self.method_request.method()
     }
 }
+
+impl Serialize for JsonRpcRequest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let mut method_request =
+            serde_json::to_value(&self.method_request).map_err(S::Error::custom)?;
+        if let (Some(min_version), Some(params)) = (
+            self.min_version,
+            method_request
+                .get_mut("params")
+                .and_then(serde_json::Value::as_array_mut),
+        ) {
+            params.push(min_version.into());
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("jsonrpc", &self.jsonrpc)?;
+        map.serialize_entry("method", &self.method())?;
+        map.serialize_entry("params", &method_request["params"])?;
+        map.serialize_entry("id", &self.id)?;
+        if let Some(client_nonce) = &self.diem_client_nonce {
+            map.serialize_entry("diem_client_nonce", client_nonce)?;
+        }
+        map.end()
+    }
+}
@@ -4,9 +4,11 @@
 use super::Method;
 use crate::{
     views::{
-        AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, 
-        MetadataView, TowerStateResourceView, OracleUpgradeStateView,
-        StateProofView, TransactionView,
+        AccountResourceView, AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, 
+        MetadataView, ModuleAbiView, ModuleView, TowerStateResourceView, OracleUpgradeStateView,
+        EpochInfoView, EventPageView, EventStatsView, ResourceWatchView, ScriptSchemaView,
+        StateProofView,
+        TransactionOutputView, TransactionView,
     },
     Error, State,
 };
@@ -73,6 +75,18 @@ pub enum MethodResponse {
     GetTowerStateView(TowerStateResourceView),         //////// 0L ////////
     GetOracleUpgradeStateView(OracleUpgradeStateView), //////// 0L ////////
     GetWaypointView(WaypointView), //////// 0L ////////
+    GetAccountResource(AccountResourceView), //////// 0L ////////
+    GetTransactionOutputs(Vec<TransactionOutputView>), //////// 0L ////////
+    GetEpochInfo(EpochInfoView), //////// 0L ////////
+    GetEventStats(EventStatsView), //////// 0L ////////
+    ValidateTransaction, //////// 0L ////////
+    AllocateSequenceNumber(u64), //////// 0L ////////
+    GetModule(Option<ModuleView>), //////// 0L ////////
+    GetModuleAbi(Option<ModuleAbiView>), //////// 0L ////////
+    GetEventsPage(EventPageView), //////// 0L ////////
+    GetScriptSchemas(Vec<ScriptSchemaView>), //////// 0L ////////
+    WatchResources(ResourceWatchView), //////// 0L ////////
+    UnwatchResources(bool), //////// 0L ////////
 
 }
 
@@ -106,6 +120,30 @@ impl MethodResponse {
             Method::GetWaypointView => {
                 MethodResponse::GetWaypointView(serde_json::from_value(json)?)
             }            
+            Method::GetAccountResource => {
+                MethodResponse::GetAccountResource(serde_json::from_value(json)?)
+            }
+            Method::GetTransactionOutputs => {
+                MethodResponse::GetTransactionOutputs(serde_json::from_value(json)?)
+            }
+            Method::GetEpochInfo => MethodResponse::GetEpochInfo(serde_json::from_value(json)?),
+            Method::GetEventStats => MethodResponse::GetEventStats(serde_json::from_value(json)?),
+            Method::ValidateTransaction => MethodResponse::ValidateTransaction,
+            Method::AllocateSequenceNumber => {
+                MethodResponse::AllocateSequenceNumber(serde_json::from_value(json)?)
+            }
+            Method::GetModule => MethodResponse::GetModule(serde_json::from_value(json)?),
+            Method::GetModuleAbi => MethodResponse::GetModuleAbi(serde_json::from_value(json)?),
+            Method::GetEventsPage => MethodResponse::GetEventsPage(serde_json::from_value(json)?),
+            Method::GetScriptSchemas => {
+                MethodResponse::GetScriptSchemas(serde_json::from_value(json)?)
+            }
+            Method::WatchResources => {
+                MethodResponse::WatchResources(serde_json::from_value(json)?)
+            }
+            Method::UnwatchResources => {
+                MethodResponse::UnwatchResources(serde_json::from_value(json)?)
+            }
             //////// 0L end ////////
             Method::GetStateProof => MethodResponse::GetStateProof(serde_json::from_value(json)?),
             Method::GetAccountStateWithProof => {
@@ -144,6 +182,19 @@ impl MethodResponse {
             MethodResponse::GetOracleUpgradeStateView(_) => Method::GetOracleUpgradeStateView,
             
             MethodResponse::GetWaypointView(_) => Method::GetWaypointView,
+
+            MethodResponse::GetAccountResource(_) => Method::GetAccountResource,
+            MethodResponse::GetTransactionOutputs(_) => Method::GetTransactionOutputs,
+            MethodResponse::GetEpochInfo(_) => Method::GetEpochInfo,
+            MethodResponse::GetEventStats(_) => Method::GetEventStats,
+            MethodResponse::ValidateTransaction => Method::ValidateTransaction,
+            MethodResponse::AllocateSequenceNumber(_) => Method::AllocateSequenceNumber,
+            MethodResponse::GetModule(_) => Method::GetModule,
+            MethodResponse::GetModuleAbi(_) => Method::GetModuleAbi,
+            MethodResponse::GetEventsPage(_) => Method::GetEventsPage,
+            MethodResponse::GetScriptSchemas(_) => Method::GetScriptSchemas,
+            MethodResponse::WatchResources(_) => Method::WatchResources,
+            MethodResponse::UnwatchResources(_) => Method::UnwatchResources,
             //////// 0L end ////////
         }
     }
@@ -209,6 +260,86 @@ impl MethodResponse {
         }
     }
 
+    pub fn try_into_get_account_resource(self) -> Result<AccountResourceView, Error> {
+        match self {
+            MethodResponse::GetAccountResource(resource) => Ok(resource),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetAccountResource found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_transaction_outputs(self) -> Result<Vec<TransactionOutputView>, Error> {
+        match self {
+            MethodResponse::GetTransactionOutputs(outputs) => Ok(outputs),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetTransactionOutputs found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_epoch_info(self) -> Result<EpochInfoView, Error> {
+        match self {
+            MethodResponse::GetEpochInfo(epoch_info) => Ok(epoch_info),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetEpochInfo found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_event_stats(self) -> Result<EventStatsView, Error> {
+        match self {
+            MethodResponse::GetEventStats(event_stats) => Ok(event_stats),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetEventStats found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_events_page(self) -> Result<EventPageView, Error> {
+        match self {
+            MethodResponse::GetEventsPage(page) => Ok(page),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetEventsPage found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_script_schemas(self) -> Result<Vec<ScriptSchemaView>, Error> {
+        match self {
+            MethodResponse::GetScriptSchemas(schemas) => Ok(schemas),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetScriptSchemas found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_watch_resources(self) -> Result<ResourceWatchView, Error> {
+        match self {
+            MethodResponse::WatchResources(watch) => Ok(watch),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::WatchResources found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_unwatch_resources(self) -> Result<bool, Error> {
+        match self {
+            MethodResponse::UnwatchResources(removed) => Ok(removed),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::UnwatchResources found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
     pub fn try_into_get_network_status(self) -> Result<u64, Error> {
         match self {
             MethodResponse::GetNetworkStatus(status) => Ok(status),
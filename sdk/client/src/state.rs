@@ -20,17 +20,54 @@ impl State {
     }
 }
 
+/// A ledger version the reads of a client are pinned to, so that a sequence of reads (e.g. an
+/// account's balance, then its transactions and events) observes a single ledger state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionPin {
+    /// Reads are served by nodes synced to at least the version: nodes behind it wait to catch up
+    /// and responses from nodes still behind it fail with a retriable error. Reads may observe
+    /// later versions.
+    AtLeast(u64),
+    /// Like `AtLeast`, and in addition account states and metadata are read at the version, and
+    /// transactions and events committed after it are left out of the results. Other reads,
+    /// proofs included, are only pinned to at least the version.
+    Exactly(u64),
+}
+
+impl VersionPin {
+    pub fn version(&self) -> u64 {
+        match self {
+            VersionPin::AtLeast(version) | VersionPin::Exactly(version) => *version,
+        }
+    }
+
+    /// Returns the version reads are served at, if pinned to exactly one.
+    pub fn exact_version(&self) -> Option<u64> {
+        match self {
+            VersionPin::AtLeast(_) => None,
+            VersionPin::Exactly(version) => Some(*version),
+        }
+    }
+}
+
 cfg_async_or_blocking! {
     use crate::{Error, Result};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     #[derive(Debug)]
     pub(crate) struct StateManager {
         last_known_state: std::sync::Mutex<Option<State>>,
+        max_ledger_lag: Option<Duration>,
+        version_pin: Option<VersionPin>,
     }
 
     impl Clone for StateManager {
         fn clone(&self) -> Self {
-            Self::default()
+            Self {
+                max_ledger_lag: self.max_ledger_lag,
+                version_pin: self.version_pin,
+                ..Self::default()
+            }
         }
     }
 
@@ -38,6 +75,8 @@ cfg_async_or_blocking! {
         fn default() -> Self {
             Self {
                 last_known_state: std::sync::Mutex::new(None),
+                max_ledger_lag: None,
+                version_pin: None,
             }
         }
     }
@@ -47,6 +86,36 @@ cfg_async_or_blocking! {
             Self::default()
         }
 
+        pub(crate) fn set_max_ledger_lag(&mut self, max_ledger_lag: Option<Duration>) {
+            self.max_ledger_lag = max_ledger_lag;
+        }
+
+        pub(crate) fn set_version_pin(&mut self, version_pin: Option<VersionPin>) {
+            self.version_pin = version_pin;
+        }
+
+        pub(crate) fn version_pin(&self) -> Option<VersionPin> {
+            self.version_pin
+        }
+
+        /// Returns the version reads are served at, if pinned to exactly one.
+        pub(crate) fn exact_version(&self) -> Option<u64> {
+            self.version_pin.and_then(|version_pin| version_pin.exact_version())
+        }
+
+        /// Drops the items committed after the version reads are pinned to exactly, if any.
+        /// `version` returns the version an item was committed at.
+        pub(crate) fn truncate_to_pin<T>(
+            &self,
+            mut items: Vec<T>,
+            version: impl Fn(&T) -> u64,
+        ) -> Vec<T> {
+            if let Some(exact_version) = self.exact_version() {
+                items.retain(|item| version(item) <= exact_version);
+            }
+            items
+        }
+
         pub(crate) fn last_known_state(&self) -> Option<State> {
             self.last_known_state.lock().unwrap().clone()
         }
@@ -62,7 +131,163 @@ cfg_async_or_blocking! {
                 }
             }
             *state_writer = Some(resp_state.clone());
+
+            // Like a stale response, a node behind the pinned version may have caught up by the
+            // time the request is retried.
+            if let Some(version_pin) = self.version_pin {
+                if resp_state.version < version_pin.version() {
+                    return Err(Error::behind_version_pin(version_pin.version(), resp_state));
+                }
+            }
+
+            // The response is accepted as our latest known state either way, but if the ledger
+            // timestamp trails local time by more than the configured threshold the node is
+            // probably behind and callers should hear about it.
+            self.check_ledger_lag(resp_state.timestamp_usecs, unix_time())
+        }
+
+        /// Fails if the ledger timestamp trails `now`, the time since the Unix epoch, by more
+        /// than the max ledger lag.
+        fn check_ledger_lag(&self, timestamp_usecs: u64, now: Duration) -> Result<()> {
+            if let Some(max_ledger_lag) = self.max_ledger_lag {
+                let lag = ledger_lag(timestamp_usecs, now);
+                if lag > max_ledger_lag {
+                    return Err(Error::stale_ledger(lag));
+                }
+            }
             Ok(())
         }
     }
+
+    fn unix_time() -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+
+    /// How far the ledger timestamp trails `now`.
+    ///
+    /// A ledger timestamp ahead of `now` is clamped to a zero lag. Only clock skew between the
+    /// node and the local clock puts it there, and whichever clock is wrong, the node isn't
+    /// behind, so the response is not stale.
+    fn ledger_lag(timestamp_usecs: u64, now: Duration) -> Duration {
+        now.checked_sub(Duration::from_micros(timestamp_usecs))
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::error::ErrorKind;
+
+        fn state(version: u64, timestamp_usecs: u64) -> State {
+            State {
+                chain_id: 4,
+                version,
+                timestamp_usecs,
+            }
+        }
+
+        fn state_manager(max_ledger_lag: Option<Duration>) -> StateManager {
+            let mut state_manager = StateManager::new();
+            state_manager.set_max_ledger_lag(max_ledger_lag);
+            state_manager
+        }
+
+        #[test]
+        fn test_max_ledger_lag() {
+            let state_manager = state_manager(Some(Duration::from_secs(10)));
+
+            // A ledger an hour behind is stale, and the response is still the latest known state
+            let timestamp_usecs = (unix_time() - Duration::from_secs(3600)).as_micros() as u64;
+            let error = state_manager
+                .update_state(&state(1, timestamp_usecs))
+                .unwrap_err();
+            assert!(error.is_retriable());
+            assert!(error.stale_ledger_lag().unwrap() >= Duration::from_secs(3600));
+            assert_eq!(
+                state_manager.last_known_state(),
+                Some(state(1, timestamp_usecs))
+            );
+            assert!(state_manager
+                .update_state(&state(2, unix_time().as_micros() as u64))
+                .is_ok());
+
+            // Without a max ledger lag, any lag is fine
+            assert!(self::state_manager(None).update_state(&state(1, 0)).is_ok());
+        }
+
+        #[test]
+        fn test_max_ledger_lag_boundary() {
+            let state_manager = state_manager(Some(Duration::from_secs(10)));
+            let now = Duration::from_secs(1_000);
+            let timestamp_usecs = |lag: Duration| (now - lag).as_micros() as u64;
+
+            let lag = Duration::from_secs(10);
+            assert!(state_manager
+                .check_ledger_lag(timestamp_usecs(lag), now)
+                .is_ok());
+
+            let lag = Duration::from_secs(10) + Duration::from_micros(1);
+            let error = state_manager
+                .check_ledger_lag(timestamp_usecs(lag), now)
+                .unwrap_err();
+            assert!(matches!(error.kind(), ErrorKind::StaleLedger { .. }));
+            assert_eq!(error.stale_ledger_lag(), Some(lag));
+
+            // A ledger timestamp ahead of the local clock is no lag at all
+            let ahead = (now + Duration::from_secs(3600)).as_micros() as u64;
+            assert_eq!(ledger_lag(ahead, now), Duration::from_secs(0));
+            assert!(state_manager.check_ledger_lag(ahead, now).is_ok());
+        }
+
+        fn pinned_state_manager(version_pin: Option<VersionPin>) -> StateManager {
+            let mut state_manager = StateManager::new();
+            state_manager.set_version_pin(version_pin);
+            state_manager
+        }
+
+        #[test]
+        fn test_truncate_to_pin() {
+            let versions = vec![3, 5, 6, 8];
+
+            let state_manager = pinned_state_manager(Some(VersionPin::Exactly(5)));
+            assert_eq!(state_manager.exact_version(), Some(5));
+            assert_eq!(
+                state_manager.truncate_to_pin(versions.clone(), |version| *version),
+                vec![3, 5]
+            );
+
+            // Reads pinned to at least a version, or not pinned, aren't truncated
+            for &version_pin in &[Some(VersionPin::AtLeast(5)), None] {
+                let state_manager = pinned_state_manager(version_pin);
+                assert_eq!(state_manager.exact_version(), None);
+                assert_eq!(
+                    state_manager.truncate_to_pin(versions.clone(), |version| *version),
+                    versions
+                );
+            }
+        }
+
+        #[test]
+        fn test_behind_version_pin() {
+            for &version_pin in &[VersionPin::AtLeast(5), VersionPin::Exactly(5)] {
+                let state_manager = pinned_state_manager(Some(version_pin));
+
+                let error = state_manager.update_state(&state(4, 0)).unwrap_err();
+                assert_eq!(error.kind(), ErrorKind::StaleResponse);
+                assert!(error.is_retriable());
+                // The node may catch up by the time the request is retried
+                assert_eq!(state_manager.last_known_state(), Some(state(4, 0)));
+
+                assert!(state_manager.update_state(&state(5, 0)).is_ok());
+                assert!(state_manager.update_state(&state(6, 0)).is_ok());
+            }
+
+            // Unpinned, any version is fine
+            let state_manager = pinned_state_manager(None);
+            assert_eq!(state_manager.version_pin(), None);
+            assert!(state_manager.update_state(&state(0, 0)).is_ok());
+        }
+    }
 }
@@ -8,8 +8,10 @@ use crate::{
     response::{MethodResponse, Response},
     state::State,
 };
+use diem_crypto::HashValue;
 use diem_json_rpc_types::views::{
-    AccountView, CurrencyInfoView, EventView, TransactionListView, TransactionView,
+    AccountResourceView, AccountView, CurrencyInfoView, EventView, TransactionListView,
+    TransactionView,
 };
 use diem_types::{
     account_address::AccountAddress,
@@ -25,10 +27,14 @@ use diem_types::{
     trusted_state::TrustedState,
     waypoint::Waypoint,
 };
+use futures::channel::oneshot;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     convert::TryFrom,
     fmt::Debug,
+    panic::{self, AssertUnwindSafe},
     sync::{Arc, RwLock},
 };
 
@@ -51,6 +57,10 @@ type StateProof = (
 pub struct VerifyingClient<S> {
     inner: Client,
     trusted_state_store: Arc<RwLock<TrustedStateStore<S>>>,
+    /// Threads verifying proofs, rayon's global thread pool if `None`
+    verification_pool: Option<Arc<ThreadPool>>,
+    /// Whether responses are returned unverified, see `VerifyingClient::with_trusted_node`
+    trusted_node: bool,
 }
 
 impl<S: Storage> VerifyingClient<S> {
@@ -62,15 +72,34 @@ impl<S: Storage> VerifyingClient<S> {
         Ok(Self {
             inner,
             trusted_state_store: Arc::new(RwLock::new(trusted_state_store)),
+            verification_pool: None,
+            trusted_node: false,
         })
     }
 
-    pub fn new_with_state(inner: Client, trusted_state: TrustedState, storage: S) -> Self {
-        let trusted_state_store = TrustedStateStore::new_with_state(trusted_state, storage);
-        Self {
+    pub fn new_with_state(inner: Client, trusted_state: TrustedState, storage: S) -> Result<Self> {
+        let trusted_state_store = TrustedStateStore::new_with_state(trusted_state, storage)?;
+        Ok(Self {
             inner,
             trusted_state_store: Arc::new(RwLock::new(trusted_state_store)),
-        }
+            verification_pool: None,
+            trusted_node: false,
+        })
+    }
+
+    /// Verifies proofs on a dedicated pool of `num_threads` threads, instead of rayon's global
+    /// thread pool.
+    pub fn with_verification_threads(mut self, num_threads: usize) -> Result<Self> {
+        self.verification_pool = Some(Arc::new(verification_pool(num_threads)?));
+        Ok(self)
+    }
+
+    /// Returns the responses of the node without verifying them, for a node the client trusts,
+    /// e.g. one running on the same host. Requests are then sent as they are, without the
+    /// requests for proofs, and `batch` no longer ratchets the trusted state.
+    pub fn with_trusted_node(mut self) -> Self {
+        self.trusted_node = true;
+        self
     }
 
     /// Get a snapshot of our current trusted ledger [`Version`].
@@ -83,6 +112,18 @@ impl<S: Storage> VerifyingClient<S> {
         self.trusted_state_store.read().unwrap().waypoint()
     }
 
+    /// Returns a client querying the node behind `inner` that shares this client's trusted state
+    /// and the ledger infos it verified, so ledger infos of the two nodes that conflict are
+    /// detected.
+    pub fn with_inner(&self, inner: Client) -> Self {
+        Self {
+            inner,
+            trusted_state_store: self.trusted_state_store.clone(),
+            verification_pool: self.verification_pool.clone(),
+            trusted_node: self.trusted_node,
+        }
+    }
+
     /// Get a snapshot of our current [`TrustedState`].
     pub fn trusted_state(&self) -> TrustedState {
         self.trusted_state_store
@@ -105,37 +146,48 @@ impl<S: Storage> VerifyingClient<S> {
     /// `Ok(true)` if, after verification, we still need to sync more. Returns
     /// `Ok(false)` if we have finished syncing.
     pub async fn sync_one_step(&self) -> Result<bool> {
+        self.trusted_state_store.read().unwrap().check_conflict()?;
+
         let (state_proof_view, state) = self
             .inner
             .get_state_proof(self.version())
             .await?
             .into_parts();
-        let state_proof = StateProof::try_from(&state_proof_view).map_err(Error::decode)?;
 
-        // check the response metadata matches the state proof
-        verify_latest_li_matches_state(state_proof.0.ledger_info(), &state)?;
+        let trusted_state = self.trusted_state();
+        let (state_proof, new_state) =
+            verify_on_pool(self.verification_pool.as_deref(), move || {
+                let state_proof = StateProof::try_from(&state_proof_view).map_err(Error::decode)?;
+
+                // check the response metadata matches the state proof
+                verify_latest_li_matches_state(state_proof.0.ledger_info(), &state)?;
+
+                let new_state = verify_state_proof(&trusted_state, &state_proof)?;
+                Ok((state_proof, new_state))
+            })
+            .await?;
 
         // try to ratchet our trusted state using the state proof
-        self.verify_and_ratchet(&state_proof)?;
+        self.ratchet(state_proof.0.ledger_info(), new_state)?;
 
         Ok(state_proof.1.more)
     }
 
-    /// Verify and ratchet forward our trusted state using a state proof.
+    /// Verify and ratchet forward our trusted state using a state proof. Fails with an
+    /// unrecoverable error if the latest ledger info conflicts with one verified before for the
+    /// same version (see [`Error::is_conflicting_ledger_info`]).
     pub fn verify_and_ratchet(&self, state_proof: &StateProof) -> Result<()> {
-        let (latest_li, epoch_change_proof, _) = state_proof;
-
-        // TODO(philiphayes): stale error case
-        let trusted_state = self.trusted_state();
-        let change = trusted_state
-            .verify_and_ratchet(latest_li, epoch_change_proof)
-            .map_err(Error::invalid_proof)?;
+        let new_state = verify_state_proof(&self.trusted_state(), state_proof)?;
+        self.ratchet(state_proof.0.ledger_info(), new_state)
+    }
 
-        if let Some(new_state) = change.new_state() {
-            self.trusted_state_store
-                .write()
-                .unwrap()
-                .ratchet(new_state)?;
+    /// Records the latest ledger info of a verified state proof, and ratchets forward to the
+    /// trusted state the proof leads to, if any.
+    fn ratchet(&self, latest_li: &LedgerInfo, new_state: Option<TrustedState>) -> Result<()> {
+        let mut trusted_state_store = self.trusted_state_store.write().unwrap();
+        trusted_state_store.record_ledger_info(latest_li)?;
+        if let Some(new_state) = new_state {
+            trusted_state_store.ratchet(new_state)?;
         }
 
         // TODO(philiphayes): verify the accumulator consistency proof; no-one
@@ -189,6 +241,19 @@ impl<S: Storage> VerifyingClient<S> {
             .and_then(MethodResponse::try_into_get_events)
     }
 
+    /// Fetch the resource stored under `path` in the account state of `address`, at `version` or
+    /// the latest verified version, verified against the proven account state it was read from.
+    pub async fn get_account_resource(
+        &self,
+        address: AccountAddress,
+        path: &[u8],
+        version: Option<Version>,
+    ) -> Result<Response<AccountResourceView>> {
+        self.request(MethodRequest::get_account_resource(address, path, version))
+            .await?
+            .and_then(MethodResponse::try_into_get_account_resource)
+    }
+
     pub async fn get_currencies(&self) -> Result<Response<Vec<CurrencyInfoView>>> {
         self.request(MethodRequest::get_currencies())
             .await?
@@ -231,6 +296,12 @@ impl<S: Storage> VerifyingClient<S> {
         &self,
         requests: Vec<MethodRequest>,
     ) -> Result<Vec<Result<Response<MethodResponse>>>> {
+        if self.trusted_node {
+            return self.inner.batch(requests).await;
+        }
+
+        self.trusted_state_store.read().unwrap().check_conflict()?;
+
         // transform each request into verifying sub-request batches
         let batch = VerifyingBatch::from_batch(requests);
 
@@ -243,31 +314,97 @@ impl<S: Storage> VerifyingClient<S> {
         // actually send the batch
         let mut responses = self.inner.batch(requests).await?;
 
-        // extract and verify the state proof
+        // extract the state proof
         let (state_proof_response, state) = responses.pop().unwrap()?.into_parts();
         let state_proof_view = state_proof_response.try_into_get_state_proof()?;
-        let state_proof = StateProof::try_from(&state_proof_view).map_err(Error::decode)?;
 
-        // check the response metadata matches the state proof
-        verify_latest_li_matches_state(state_proof.0.ledger_info(), &state)?;
+        // Checking signatures and proofs is CPU bound, so it's left to the verification threads
+        // rather than to the async runtime.
+        let trusted_state = self.trusted_state();
+        let (state_proof, new_state, responses) =
+            verify_on_pool(self.verification_pool.as_deref(), move || {
+                let state_proof = StateProof::try_from(&state_proof_view).map_err(Error::decode)?;
+
+                // check the response metadata matches the state proof
+                verify_latest_li_matches_state(state_proof.0.ledger_info(), &state)?;
+
+                let new_state = verify_state_proof(&trusted_state, &state_proof)?;
+
+                // unflatten subresponses, verify, and collect into one response per request,
+                // unless we can't verify any proofs, see below
+                let responses = if state_proof.1.more {
+                    None
+                } else {
+                    Some(batch.validate_responses(start_version, &state, &state_proof, responses))
+                };
+                Ok((state_proof, new_state, responses))
+            })
+            .await?;
 
         // try to ratchet our trusted state using the state proof
-        self.verify_and_ratchet(&state_proof)?;
+        self.ratchet(state_proof.0.ledger_info(), new_state)?;
 
-        // remote says we're too far behind and need to sync. we have to throw
-        // out the batch since we can't verify any proofs
-        if state_proof.1.more {
+        match responses {
+            Some(responses) => responses,
+            // remote says we're too far behind and need to sync. we have to throw
+            // out the batch since we can't verify any proofs
+            //
             // TODO(philiphayes): what is the right behaviour here? it would obv.
             // be more convenient to just call `self.sync` here and then retry,
             // but maybe a client would like to control the syncs itself?
-            return Err(Error::unknown(
+            None => Err(Error::unknown(
                 "our client is too far behind, we need to sync",
-            ));
+            )),
         }
+    }
+}
+
+/// Builds a pool of `num_threads` threads verifying proofs.
+fn verification_pool(num_threads: usize) -> Result<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|index| format!("verifying-client-{}", index))
+        .build()
+        .map_err(Error::unknown)
+}
 
-        // unflatten subresponses, verify, and collect into one response per request
-        batch.validate_responses(start_version, &state, &state_proof, responses)
+/// Runs `verify` on `pool`, or on rayon's global thread pool if `None`, so that it doesn't block
+/// the async runtime. Parallel iterators in `verify` run on the same pool.
+async fn verify_on_pool<T, F>(pool: Option<&ThreadPool>, verify: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let task = move || {
+        // rayon aborts the process on panics in spawned tasks.
+        let result = panic::catch_unwind(AssertUnwindSafe(verify))
+            .unwrap_or_else(|_| Err(Error::unknown("proof verification panicked")));
+        // The receiver is only gone if the request was dropped.
+        let _ = sender.send(result);
+    };
+    match pool {
+        Some(pool) => pool.spawn(task),
+        None => rayon::spawn(task),
     }
+    receiver
+        .await
+        .map_err(|_| Error::unknown("proof verification was cancelled"))?
+}
+
+/// Verifies a state proof against `trusted_state`, returning the new trusted state it leads to,
+/// if any.
+fn verify_state_proof(
+    trusted_state: &TrustedState,
+    state_proof: &StateProof,
+) -> Result<Option<TrustedState>> {
+    let (latest_li, epoch_change_proof, _) = state_proof;
+
+    // TODO(philiphayes): stale error case
+    let change = trusted_state
+        .verify_and_ratchet(latest_li, epoch_change_proof)
+        .map_err(Error::invalid_proof)?;
+    Ok(change.new_state())
 }
 
 /// Check that certain metadata (version and timestamp) in a `LedgerInfo` matches
@@ -343,13 +480,21 @@ impl VerifyingBatch {
         }
 
         let mut responses_iter = responses.into_iter();
-
-        Ok(self
+        let subresponses: Vec<Vec<_>> = self
             .requests
             .iter()
             .map(|request| {
                 let n = request.subrequests.len();
-                let subresponses = responses_iter.by_ref().take(n).collect();
+                responses_iter.by_ref().take(n).collect()
+            })
+            .collect();
+
+        // Requests are verified in parallel, on the pool of the calling thread if it runs on one.
+        Ok(self
+            .requests
+            .par_iter()
+            .zip(subresponses)
+            .map(|(request, subresponses)| {
                 request.validate_subresponses(start_version, state, state_proof, subresponses)
             })
             .collect())
@@ -442,6 +587,9 @@ impl From<MethodRequest> for VerifyingRequest {
             }
             MethodRequest::GetCurrencies([]) => verifying_get_currencies(),
             MethodRequest::GetNetworkStatus([]) => verifying_get_network_status(),
+            MethodRequest::GetAccountResource(address, path, version) => {
+                verifying_get_account_resource(address, path, version)
+            }
             _ => todo!(),
         }
     }
@@ -687,6 +835,64 @@ fn verifying_get_currencies() -> VerifyingRequest {
     VerifyingRequest::new(request, subrequests, callback)
 }
 
+fn verifying_get_account_resource(
+    address: AccountAddress,
+    path: String,
+    version: Option<Version>,
+) -> VerifyingRequest {
+    let request = MethodRequest::GetAccountResource(address, path, version);
+    let subrequests = vec![request.clone()];
+    let callback: RequestCallback = |ctxt, subresponses| {
+        let resource = match subresponses {
+            [MethodResponse::GetAccountResource(ref resource)] => resource,
+            subresponses => {
+                return Err(Error::rpc_response(format!(
+                    "expected [GetAccountResource] subresponses, received: {:?}",
+                    subresponses,
+                )))
+            }
+        };
+
+        let (address, path, version) = match ctxt.request {
+            MethodRequest::GetAccountResource(address, path, version) => (*address, path, *version),
+            request => panic!("programmer error: unexpected request: {:?}", request),
+        };
+        let path = hex::decode(path).map_err(Error::decode)?;
+        let latest_li = ctxt.state_proof.0.ledger_info();
+        let version = version.unwrap_or_else(|| latest_li.version());
+
+        // The state tree commits to whole account states, so the resource is verified through
+        // the account state it was read from.
+        let account_state_with_proof =
+            AccountStateWithProof::try_from(&resource.account_state_with_proof)
+                .map_err(Error::decode)?;
+        account_state_with_proof
+            .verify(latest_li, version, address)
+            .map_err(Error::invalid_proof)?;
+
+        let value = match &account_state_with_proof.blob {
+            Some(blob) => AccountState::try_from(blob)
+                .map_err(Error::decode)?
+                .get(&path)
+                .cloned(),
+            None => None,
+        };
+        if resource.version != version
+            || resource.blob.as_ref().map(|blob| blob.inner()) != value.as_deref()
+        {
+            return Err(Error::invalid_proof(format!(
+                "resource {} of account {} at version {} does not match its proven account state",
+                hex::encode(&path),
+                address,
+                version,
+            )));
+        }
+
+        Ok(MethodResponse::GetAccountResource(resource.clone()))
+    };
+    VerifyingRequest::new(request, subrequests, callback)
+}
+
 fn verifying_get_network_status() -> VerifyingRequest {
     let request = MethodRequest::get_network_status();
     let subrequests = vec![MethodRequest::get_network_status()];
@@ -713,7 +919,8 @@ mod private {
 
 // TODO(philiphayes): unseal `Storage` trait once verifying client stabilizes.
 pub trait Storage: private::Sealed + Debug {
-    fn get(&self, key: &str) -> Result<Vec<u8>>;
+    /// Returns the value stored under `key`, `None` if nothing is stored under it.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
     fn set(&mut self, key: &str, value: Vec<u8>) -> Result<()>;
 }
 
@@ -731,11 +938,8 @@ impl InMemoryStorage {
 }
 
 impl Storage for InMemoryStorage {
-    fn get(&self, key: &str) -> Result<Vec<u8>> {
-        self.data
-            .get(key)
-            .map(Clone::clone)
-            .ok_or_else(|| Error::unknown("key not set"))
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.data.get(key).cloned())
     }
 
     fn set(&mut self, key: &str, value: Vec<u8>) -> Result<()> {
@@ -745,44 +949,135 @@ impl Storage for InMemoryStorage {
 }
 
 pub const TRUSTED_STATE_KEY: &str = "trusted_state";
+/// Prefix of the keys of the ledger info hashes, each stored under `{prefix}/{slot}`
+pub const LEDGER_INFO_HASHES_KEY: &str = "ledger_info_hashes";
+pub const LEDGER_INFO_CONFLICT_KEY: &str = "ledger_info_conflict";
+
+/// How many of the latest verified versions are remembered to detect conflicting ledger infos
+const MAX_LEDGER_INFO_HASHES: usize = 1_000;
+
+/// The transaction accumulator root hash of a verified ledger info, as stored in slot
+/// `index % MAX_LEDGER_INFO_HASHES`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct LedgerInfoHashEntry {
+    /// How many hashes were recorded before this one
+    index: u64,
+    version: Version,
+    hash: HashValue,
+}
+
+/// Transaction accumulator root hashes of the latest ledger infos verified, by version.
+///
+/// Each hash is stored on its own under one of `MAX_LEDGER_INFO_HASHES` keys, reused in turn, so
+/// recording a hash writes that hash only and overwrites the oldest one.
+#[derive(Debug, Default)]
+struct LedgerInfoHashes {
+    hashes: BTreeMap<Version, HashValue>,
+    /// Versions of `hashes`, oldest first
+    versions: VecDeque<Version>,
+    /// Index of the next hash recorded
+    next_index: u64,
+}
+
+impl LedgerInfoHashes {
+    fn load<S: Storage>(storage: &S) -> Result<Self> {
+        let mut entries = Vec::new();
+        for slot in 0..MAX_LEDGER_INFO_HASHES {
+            if let Some(entry) = get::<_, LedgerInfoHashEntry>(storage, &slot_key(slot))? {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|entry| entry.index);
+
+        let mut ledger_info_hashes = Self::default();
+        for entry in entries {
+            ledger_info_hashes.insert(entry.version, entry.hash);
+            ledger_info_hashes.next_index = entry.index + 1;
+        }
+        Ok(ledger_info_hashes)
+    }
+
+    fn get(&self, version: Version) -> Option<&HashValue> {
+        self.hashes.get(&version)
+    }
+
+    fn record<S: Storage>(
+        &mut self,
+        storage: &mut S,
+        version: Version,
+        hash: HashValue,
+    ) -> Result<()> {
+        let entry = LedgerInfoHashEntry {
+            index: self.next_index,
+            version,
+            hash,
+        };
+        let slot = (entry.index % MAX_LEDGER_INFO_HASHES as u64) as usize;
+        let entry_bytes = bcs::to_bytes(&entry).map_err(Error::decode)?;
+        storage.set(&slot_key(slot), entry_bytes)?;
+
+        self.insert(version, hash);
+        self.next_index += 1;
+        Ok(())
+    }
+
+    fn insert(&mut self, version: Version, hash: HashValue) {
+        self.hashes.insert(version, hash);
+        self.versions.push_back(version);
+        while self.versions.len() > MAX_LEDGER_INFO_HASHES {
+            if let Some(oldest) = self.versions.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+    }
+}
+
+fn slot_key(slot: usize) -> String {
+    format!("{}/{}", LEDGER_INFO_HASHES_KEY, slot)
+}
+
+/// Two verified ledger infos certifying different transaction histories at the same version
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct LedgerInfoConflict {
+    version: Version,
+    trusted: HashValue,
+    received: HashValue,
+}
 
 #[derive(Debug)]
 struct TrustedStateStore<S> {
     trusted_state: TrustedState,
+    /// Ledger infos of a version may legitimately differ in other fields than their transaction
+    /// accumulator root hash, e.g. the round of the blocks committed after a reconfiguration.
+    ledger_info_hashes: LedgerInfoHashes,
+    /// Once set, every request fails
+    conflict: Option<LedgerInfoConflict>,
     storage: S,
 }
 
 impl<S: Storage> TrustedStateStore<S> {
     fn new(storage: S) -> Result<Self> {
-        let trusted_state = storage
-            .get(TRUSTED_STATE_KEY)
-            .and_then(|bytes| bcs::from_bytes(&bytes).map_err(Error::decode))?;
-
-        Ok(Self {
-            trusted_state,
-            storage,
-        })
+        let trusted_state = get(&storage, TRUSTED_STATE_KEY)?
+            .ok_or_else(|| Error::unknown("trusted state not set"))?;
+        Self::with_storage(trusted_state, storage)
     }
 
-    fn new_with_state(trusted_state: TrustedState, storage: S) -> Self {
-        let maybe_stored_state: Result<TrustedState> = storage
-            .get(TRUSTED_STATE_KEY)
-            .and_then(|bytes| bcs::from_bytes(&bytes).map_err(Error::decode));
-
-        let trusted_state = if let Ok(stored_state) = maybe_stored_state {
-            if trusted_state.version() > stored_state.version() {
-                trusted_state
-            } else {
-                stored_state
-            }
-        } else {
-            trusted_state
+    fn new_with_state(trusted_state: TrustedState, storage: S) -> Result<Self> {
+        let trusted_state = match get::<_, TrustedState>(&storage, TRUSTED_STATE_KEY)? {
+            Some(stored_state) if stored_state.version() >= trusted_state.version() => stored_state,
+            _ => trusted_state,
         };
+        Self::with_storage(trusted_state, storage)
+    }
 
-        Self {
+    fn with_storage(trusted_state: TrustedState, storage: S) -> Result<Self> {
+        Ok(Self {
             trusted_state,
+            ledger_info_hashes: LedgerInfoHashes::load(&storage)?,
+            conflict: get::<_, Option<LedgerInfoConflict>>(&storage, LEDGER_INFO_CONFLICT_KEY)?
+                .flatten(),
             storage,
-        }
+        })
     }
 
     fn version(&self) -> Version {
@@ -797,6 +1092,50 @@ impl<S: Storage> TrustedStateStore<S> {
         &self.trusted_state
     }
 
+    fn check_conflict(&self) -> Result<()> {
+        match &self.conflict {
+            Some(conflict) => Err(Error::conflicting_ledger_info(
+                conflict.version,
+                &conflict.trusted,
+                &conflict.received,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Remembers the transaction accumulator root hash of a verified ledger info, failing for
+    /// good if it differs from the one of a ledger info verified before for the same version.
+    fn record_ledger_info(&mut self, ledger_info: &LedgerInfo) -> Result<()> {
+        self.check_conflict()?;
+
+        let version = ledger_info.version();
+        let received = ledger_info.transaction_accumulator_hash();
+        match self.ledger_info_hashes.get(version) {
+            Some(trusted) if *trusted == received => return Ok(()),
+            Some(trusted) => {
+                let conflict = LedgerInfoConflict {
+                    version,
+                    trusted: *trusted,
+                    received,
+                };
+                log::error!(
+                    "verified conflicting ledger infos at version {}: accumulator roots {} and {}",
+                    version,
+                    conflict.trusted,
+                    conflict.received,
+                );
+                let conflict_bytes = bcs::to_bytes(&Some(&conflict)).map_err(Error::decode)?;
+                self.conflict = Some(conflict);
+                self.storage.set(LEDGER_INFO_CONFLICT_KEY, conflict_bytes)?;
+                return self.check_conflict();
+            }
+            None => {}
+        }
+
+        self.ledger_info_hashes
+            .record(&mut self.storage, version, received)
+    }
+
     fn ratchet(&mut self, new_state: TrustedState) -> Result<()> {
         if new_state.version() > self.trusted_state.version() {
             self.trusted_state = new_state;
@@ -807,3 +1146,231 @@ impl<S: Storage> TrustedStateStore<S> {
         Ok(())
     }
 }
+
+/// Reads the value stored under `key`, `None` if nothing is stored yet.
+fn get<S: Storage, T: serde::de::DeserializeOwned>(storage: &S, key: &str) -> Result<Option<T>> {
+    storage
+        .get(key)?
+        .map(|bytes| bcs::from_bytes(&bytes).map_err(Error::decode))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use diem_types::block_info::BlockInfo;
+    use futures::executor::block_on;
+
+    fn state() -> State {
+        State {
+            chain_id: 4,
+            version: 1,
+            timestamp_usecs: 0,
+        }
+    }
+
+    fn state_proof() -> StateProof {
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        (
+            LedgerInfoWithSignatures::new(ledger_info, BTreeMap::new()),
+            EpochChangeProof::new(vec![], false),
+            AccumulatorConsistencyProof::new(vec![]),
+        )
+    }
+
+    /// Verifies the responses to `num_requests` `get_network_status` requests on `pool`
+    fn validate_network_statuses(
+        pool: Option<&ThreadPool>,
+        responses: Vec<MethodResponse>,
+        num_requests: usize,
+    ) -> Result<Vec<Result<Response<MethodResponse>>>> {
+        let batch =
+            VerifyingBatch::from_batch(vec![MethodRequest::get_network_status(); num_requests]);
+        let responses = responses
+            .into_iter()
+            .map(|response| Ok(Response::new(response, state())))
+            .collect();
+        block_on(verify_on_pool(pool, move || {
+            batch.validate_responses(0, &state(), &state_proof(), responses)
+        }))
+    }
+
+    #[test]
+    fn test_validate_responses_in_parallel() {
+        let pool = verification_pool(4).unwrap();
+        let responses = (0..100).map(MethodResponse::GetNetworkStatus).collect();
+
+        let verified = validate_network_statuses(Some(&pool), responses, 100).unwrap();
+        assert_eq!(verified.len(), 100);
+        for (expected, response) in (0..100).zip(verified) {
+            assert_eq!(
+                response.unwrap().into_inner(),
+                MethodResponse::GetNetworkStatus(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_responses_errors() {
+        // An unexpected subresponse only fails its own request
+        let mut responses: Vec<_> = (0..10).map(MethodResponse::GetNetworkStatus).collect();
+        responses[3] = MethodResponse::GetCurrencies(vec![]);
+        let verified = validate_network_statuses(None, responses, 10).unwrap();
+        for (index, response) in verified.into_iter().enumerate() {
+            assert_eq!(response.is_err(), index == 3);
+        }
+
+        // Missing subresponses fail the whole batch
+        let responses = (0..9).map(MethodResponse::GetNetworkStatus).collect();
+        let error = validate_network_statuses(None, responses, 10).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::RpcResponse);
+    }
+
+    #[test]
+    fn test_verify_on_pool() {
+        let pool = verification_pool(2).unwrap();
+        let thread_name = block_on(verify_on_pool(Some(&pool), || {
+            Ok(std::thread::current().name().map(str::to_owned))
+        }))
+        .unwrap();
+        assert!(thread_name.unwrap().starts_with("verifying-client-"));
+
+        // Errors are returned to the caller
+        let result: Result<()> = block_on(verify_on_pool(None, || {
+            Err(Error::invalid_proof("invalid"))
+        }));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidProof);
+
+        // A verification that panics fails instead of leaving the caller waiting, and the pool
+        // keeps working
+        let result: Result<()> = block_on(verify_on_pool(Some(&pool), || panic!("verifying")));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Unknown);
+        assert!(block_on(verify_on_pool(Some(&pool), || Ok(()))).is_ok());
+    }
+
+    /// A storage whose reads fail
+    #[derive(Debug)]
+    struct FailingStorage;
+
+    impl private::Sealed for FailingStorage {}
+
+    impl Storage for FailingStorage {
+        fn get(&self, _key: &str) -> Result<Option<Vec<u8>>> {
+            Err(Error::unknown("storage unavailable"))
+        }
+
+        fn set(&mut self, _key: &str, _value: Vec<u8>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn ledger_info(version: Version, accumulator_hash: HashValue) -> LedgerInfo {
+        LedgerInfo::new(
+            BlockInfo::new(0, 0, HashValue::zero(), accumulator_hash, version, 0, None),
+            HashValue::zero(),
+        )
+    }
+
+    fn trusted_state_store<S: Storage>(storage: S) -> Result<TrustedStateStore<S>> {
+        let waypoint = Waypoint::new_any(&ledger_info(0, HashValue::zero()));
+        TrustedStateStore::new_with_state(TrustedState::from(waypoint), storage)
+    }
+
+    #[test]
+    fn test_record_ledger_info_conflict() {
+        let mut store = trusted_state_store(InMemoryStorage::new()).unwrap();
+        let trusted = HashValue::random();
+        let received = HashValue::random();
+
+        store.record_ledger_info(&ledger_info(1, trusted)).unwrap();
+        store.record_ledger_info(&ledger_info(2, received)).unwrap();
+        // Ledger infos certifying the same history are fine
+        store.record_ledger_info(&ledger_info(1, trusted)).unwrap();
+
+        let error = store
+            .record_ledger_info(&ledger_info(1, received))
+            .unwrap_err();
+        assert!(error.is_conflicting_ledger_info());
+        assert!(!error.is_retriable());
+        assert_eq!(error.kind(), ErrorKind::ConflictingLedgerInfo);
+        let message = error.to_string();
+        assert!(message.contains(&trusted.to_string()));
+        assert!(message.contains(&received.to_string()));
+
+        // Every ledger info fails after a conflict, even after a restart
+        let error = store
+            .record_ledger_info(&ledger_info(3, HashValue::random()))
+            .unwrap_err();
+        assert!(error.is_conflicting_ledger_info());
+        let store = trusted_state_store(store.storage).unwrap();
+        assert!(store
+            .check_conflict()
+            .unwrap_err()
+            .is_conflicting_ledger_info());
+    }
+
+    #[test]
+    fn test_ledger_info_hashes_persisted() {
+        let mut store = trusted_state_store(InMemoryStorage::new()).unwrap();
+        let hashes: Vec<_> = (0..MAX_LEDGER_INFO_HASHES + 10)
+            .map(|_| HashValue::random())
+            .collect();
+        for (version, hash) in hashes.iter().enumerate() {
+            store
+                .record_ledger_info(&ledger_info(version as Version, *hash))
+                .unwrap();
+        }
+        // One key per remembered hash, the oldest ones being overwritten
+        assert_eq!(store.storage.data.len(), MAX_LEDGER_INFO_HASHES);
+
+        // The latest hashes are remembered after a restart
+        let mut store = trusted_state_store(store.storage).unwrap();
+        assert!(store.ledger_info_hashes.get(9).is_none());
+        assert_eq!(store.ledger_info_hashes.get(10), Some(&hashes[10]));
+
+        // and the hashes recorded after it keep replacing the oldest ones
+        let next_version = hashes.len() as Version;
+        store
+            .record_ledger_info(&ledger_info(next_version, HashValue::random()))
+            .unwrap();
+        let mut store = trusted_state_store(store.storage).unwrap();
+        assert!(store.ledger_info_hashes.get(10).is_none());
+        assert_eq!(store.ledger_info_hashes.get(11), Some(&hashes[11]));
+        assert!(store.ledger_info_hashes.get(next_version).is_some());
+
+        // Conflicts with the hashes recorded before a restart are detected
+        let error = store
+            .record_ledger_info(&ledger_info(11, HashValue::random()))
+            .unwrap_err();
+        assert!(error.is_conflicting_ledger_info());
+    }
+
+    #[test]
+    fn test_storage_errors() {
+        // Nothing stored yet
+        let store = trusted_state_store(InMemoryStorage::new()).unwrap();
+        assert!(store.check_conflict().is_ok());
+        assert_eq!(store.version(), 0);
+        assert_eq!(
+            TrustedStateStore::new(InMemoryStorage::new())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unknown
+        );
+
+        // Failing reads aren't mistaken for missing values
+        let error = trusted_state_store(FailingStorage).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unknown);
+        let error = TrustedStateStore::new(FailingStorage).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Unknown);
+
+        // Nor are values which don't decode
+        let mut storage = InMemoryStorage::new();
+        storage
+            .set(LEDGER_INFO_CONFLICT_KEY, vec![0xff; 3])
+            .unwrap();
+        let error = trusted_state_store(storage).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Decode);
+    }
+}
@@ -6,7 +6,9 @@ use crate::{
     types::{
         account_config::{gas_type_tag, xus_tag, GAS_NAME, XUS_NAME},
         chain_id::ChainId,
-        transaction::{authenticator::AuthenticationKey, RawTransaction, TransactionPayload},
+        transaction::{
+            authenticator::AuthenticationKey, DryRunWriteSet, RawTransaction, TransactionPayload,
+        },
     },
 };
 use serde::{Deserialize, Serialize};
@@ -15,6 +17,7 @@ use std::fmt;
 pub use diem_transaction_builder::stdlib;
 use diem_types::transaction::Script;
 
+#[derive(Clone, Debug)]
 pub struct TransactionBuilder {
     sender: Option<AccountAddress>,
     sequence_number: Option<u64>,
@@ -88,6 +91,15 @@ pub struct TransactionFactory {
 }
 
 impl TransactionFactory {
+    /// Create a factory whose chain id and Diem version are read from the connected node, so
+    /// transactions match the network without the caller having to configure them.
+    #[cfg(feature = "client")]
+    pub fn from_node(client: &crate::client::BlockingClient) -> crate::client::Result<Self> {
+        let metadata = client.get_metadata()?.into_inner();
+        Ok(Self::new(ChainId::new(metadata.chain_id))
+            .with_diem_version(metadata.diem_version.unwrap_or(0)))
+    }
+
     pub fn new(chain_id: ChainId) -> Self {
         Self {
             max_gas_amount: 1_000_000,
@@ -483,6 +495,12 @@ impl TransactionFactory {
         ))
     }
 
+    /// A write set transaction for an admin operation, to be signed by the Diem root. Write sets
+    /// built with a `WriteSetBuilder` are only taken once dry-run against the network's state.
+    pub fn admin_write_set(&self, write_set: DryRunWriteSet) -> TransactionBuilder {
+        self.payload(TransactionPayload::WriteSet(write_set.into_payload()))
+    }
+
     //
     // Internal Helpers
     //
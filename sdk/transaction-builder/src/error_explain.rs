@@ -9,12 +9,21 @@
 //! crates.io.
 //!
 //! Note that the ~13 KiB error descriptions will be inlined into the final binary.
+//!
+//! Abort codes the release error descriptions don't cover, e.g. bare codes raised by 0L modules
+//! or codes of modules published later, can be registered from a TOML file with
+//! [`register_custom_explanations`].
 
+use anyhow::{format_err, Context, Result};
 use move_core_types::{
-    errmap::{ErrorContext, ErrorMapping},
+    account_address::AccountAddress,
+    errmap::{ErrorContext, ErrorDescription, ErrorMapping},
+    identifier::Identifier,
     language_storage::ModuleId,
 };
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
 
 static RELEASE_ERRMAP_BYTES: &[u8] = include_bytes!("../release_errmap/error_description.errmap");
 
@@ -23,13 +32,104 @@ static RELEASE_ERRMAP: Lazy<ErrorMapping> = Lazy::new(|| {
         .expect("Failed to deserialize static error descriptions")
 });
 
+static CUSTOM_ERRMAP: OnceCell<CustomErrorMapping> = OnceCell::new();
+
 /// Given the module ID and the abort code raised from that module, returns the
 /// human-readable explanation of that abort if possible.
 pub fn get_explanation(module_id: &ModuleId, abort_code: u64) -> Option<ErrorContext> {
+    if let Some(reason) = CUSTOM_ERRMAP
+        .get()
+        .and_then(|custom| custom.get(module_id, abort_code))
+    {
+        return Some(ErrorContext {
+            category: ErrorDescription {
+                code_name: "UNCATEGORIZED".to_string(),
+                code_description: "The abort code was registered as a custom error".to_string(),
+            },
+            reason: reason.clone(),
+        });
+    }
     let errmap = &*RELEASE_ERRMAP;
     errmap.get_explanation(module_id, abort_code)
 }
 
+/// Registers `mapping` to be looked up by [`get_explanation`] before the release error
+/// descriptions. Custom mappings can only be registered once per process.
+pub fn register_custom_explanations(mapping: CustomErrorMapping) -> Result<()> {
+    CUSTOM_ERRMAP
+        .set(mapping)
+        .map_err(|_| format_err!("Custom abort code explanations are already registered"))
+}
+
+/// Named abort codes, keyed by module and by the full abort code as raised by the module.
+#[derive(Clone, Debug, Default)]
+pub struct CustomErrorMapping {
+    module_errors: BTreeMap<ModuleId, BTreeMap<u64, ErrorDescription>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomError {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+impl CustomErrorMapping {
+    /// Parses a mapping of the form:
+    ///
+    /// ```toml
+    /// ["0x1::DiemAccount"]
+    /// 120101 = { name = "ELIMIT_EXCEEDED", description = "The transfer exceeds the limit" }
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let modules: BTreeMap<String, BTreeMap<String, CustomError>> = toml::from_str(toml)?;
+        let mut module_errors = BTreeMap::new();
+        for (module, errors) in modules {
+            let module_id = parse_module_id(&module)?;
+            let mut codes = BTreeMap::new();
+            for (code, error) in errors {
+                let code = code
+                    .parse()
+                    .with_context(|| format!("Invalid abort code {} in {}", code, module))?;
+                codes.insert(
+                    code,
+                    ErrorDescription {
+                        code_name: error.name,
+                        code_description: error.description,
+                    },
+                );
+            }
+            module_errors.insert(module_id, codes);
+        }
+        Ok(Self { module_errors })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let toml =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::from_toml(&toml).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn get(&self, module_id: &ModuleId, abort_code: u64) -> Option<&ErrorDescription> {
+        self.module_errors.get(module_id)?.get(&abort_code)
+    }
+}
+
+/// Parses a module ID of the form `0x1::DiemAccount`, as printed in abort locations.
+pub fn parse_module_id(module: &str) -> Result<ModuleId> {
+    let (address, name) = module
+        .rsplit_once("::")
+        .ok_or_else(|| format_err!("Invalid module {}, expected <address>::<name>", module))?;
+    let address = if address.starts_with("0x") {
+        AccountAddress::from_hex_literal(address)
+    } else {
+        AccountAddress::from_hex(address)
+    }
+    .map_err(|e| format_err!("Invalid address in module {}: {}", module, e))?;
+    Ok(ModuleId::new(address, Identifier::new(name)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,4 +141,26 @@ mod tests {
         // We don't care about the result, just that the errmap deserializes without panicking.
         let _ = get_explanation(&module_id, 1234);
     }
+
+    #[test]
+    fn test_custom_error_mapping() {
+        let mapping = CustomErrorMapping::from_toml(
+            r#"
+            ["0x1::TowerState"]
+            130102 = { name = "ENOT_ENOUGH_PROOFS" }
+            "#,
+        )
+        .unwrap();
+        let module_id = parse_module_id("0x1::TowerState").unwrap();
+        assert_eq!(
+            mapping.get(&module_id, 130102).unwrap().code_name,
+            "ENOT_ENOUGH_PROOFS"
+        );
+        assert!(mapping.get(&module_id, 130103).is_none());
+
+        // Abort locations print the full address.
+        assert_eq!(parse_module_id(&module_id.to_string()).unwrap(), module_id);
+        assert_eq!(module_id.name(), ident_str!("TowerState"));
+        CustomErrorMapping::from_toml("[\"TowerState\"]").unwrap_err();
+    }
 }
@@ -320,6 +320,11 @@ impl NetworkServer {
         result.map_err(|err| err.1)
     }
 
+    /// Address of the downstream client, if there is one
+    pub fn remote_peer(&self) -> Option<SocketAddr> {
+        self.stream.as_ref().map(|stream| stream.remote)
+    }
+
     /// Shutdown the internal network stream
     pub fn shutdown(&mut self) -> Result<(), Error> {
         info!(SecureNetLogSchema::new(
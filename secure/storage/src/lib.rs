@@ -11,6 +11,7 @@ mod in_memory;
 mod kv_storage;
 mod namespaced;
 mod on_disk;
+mod operational_key;
 mod policy;
 mod storage;
 mod vault;
@@ -24,6 +25,7 @@ pub use crate::{
     kv_storage::{GetResponse, KVStorage},
     namespaced::Namespaced,
     on_disk::OnDiskStorage,
+    operational_key::{OperationalKey, RotationHook},
     policy::{Capability, Identity, Permission, Policy},
     storage::Storage,
     vault::VaultStorage,
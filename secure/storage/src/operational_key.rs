@@ -0,0 +1,97 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoStorage, Error, InMemoryStorage, Storage};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use diem_infallible::{Mutex, RwLock};
+use serde::Serialize;
+
+/// Called with the new public key of a rotated key.
+pub type RotationHook = Box<dyn Fn(&Ed25519PublicKey) + Send + Sync>;
+
+/// An Ed25519 key the node signs with, e.g. a consensus or account key, kept in secure storage
+/// instead of a raw key file. Signatures are made by the storage, so keys kept in Vault never
+/// leave it.
+///
+/// Keys may be rotated here or by another process sharing the storage, e.g. the key manager.
+/// Either way, the hooks registered with `on_rotation` are called once the rotation is seen.
+pub struct OperationalKey {
+    storage: RwLock<Storage>,
+    name: String,
+    /// Public key last seen, to tell when the key was rotated elsewhere
+    public_key: Mutex<Ed25519PublicKey>,
+    hooks: Mutex<Vec<RotationHook>>,
+}
+
+impl OperationalKey {
+    /// Opens the key stored at `name`, creating it if it doesn't exist yet.
+    pub fn new(mut storage: Storage, name: &str) -> Result<Self, Error> {
+        let public_key = match storage.get_public_key(name) {
+            Ok(response) => response.public_key,
+            Err(Error::KeyNotSet(_)) => storage.create_key(name)?,
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            storage: RwLock::new(storage),
+            name: name.to_string(),
+            public_key: Mutex::new(public_key),
+            hooks: Mutex::new(vec![]),
+        })
+    }
+
+    /// Keeps `private_key` in memory only, e.g. for keys written in a test config.
+    pub fn from_private_key(name: &str, private_key: Ed25519PrivateKey) -> Self {
+        let mut storage = Storage::from(InMemoryStorage::new());
+        storage
+            .import_private_key(name, private_key)
+            .expect("importing a key in memory can't fail");
+        Self::new(storage, name).expect("reading a key in memory can't fail")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Public key of the key as last seen, see `refresh`.
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        self.public_key.lock().clone()
+    }
+
+    /// Signs `message` with the current version of the key.
+    pub fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> Result<Ed25519Signature, Error> {
+        self.storage.read().sign(&self.name, message)
+    }
+
+    /// Registers a hook called with the new public key whenever the key is rotated.
+    pub fn on_rotation(&self, hook: impl Fn(&Ed25519PublicKey) + Send + Sync + 'static) {
+        self.hooks.lock().push(Box::new(hook));
+    }
+
+    /// Rotates the key, keeping its previous version, and calls the rotation hooks.
+    pub fn rotate(&self) -> Result<Ed25519PublicKey, Error> {
+        let public_key = self.storage.write().rotate_key(&self.name)?;
+        self.rotated(public_key.clone());
+        Ok(public_key)
+    }
+
+    /// Reads the public key from storage, and calls the rotation hooks if the key was rotated
+    /// since last seen. Returns whether it was.
+    pub fn refresh(&self) -> Result<bool, Error> {
+        let public_key = self.storage.read().get_public_key(&self.name)?.public_key;
+        if public_key == *self.public_key.lock() {
+            return Ok(false);
+        }
+        self.rotated(public_key);
+        Ok(true)
+    }
+
+    fn rotated(&self, public_key: Ed25519PublicKey) {
+        *self.public_key.lock() = public_key.clone();
+        for hook in self.hooks.lock().iter() {
+            hook(&public_key);
+        }
+    }
+}
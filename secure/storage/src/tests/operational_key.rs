@@ -0,0 +1,77 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoStorage, OnDiskStorage, OperationalKey, Storage};
+use diem_crypto::{
+    ed25519::Ed25519PrivateKey, test_utils::TestDiemCrypto, PrivateKey, Signature, Uniform,
+};
+use diem_temppath::TempPath;
+use std::sync::{Arc, Mutex};
+
+const KEY: &str = "operational_key";
+
+fn on_disk_storage(path: &TempPath) -> Storage {
+    Storage::from(OnDiskStorage::new(path.path().to_path_buf()))
+}
+
+#[test]
+fn test_sign_with_key_in_memory() {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let public_key = private_key.public_key();
+    let key = OperationalKey::from_private_key(KEY, private_key);
+    assert_eq!(key.name(), KEY);
+    assert_eq!(key.public_key(), public_key);
+
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = key.sign(&message).unwrap();
+    signature.verify(&message, &key.public_key()).unwrap();
+}
+
+#[test]
+fn test_key_created_if_missing() {
+    let path = TempPath::new();
+    path.create_as_file().unwrap();
+    let created = OperationalKey::new(on_disk_storage(&path), KEY)
+        .unwrap()
+        .public_key();
+    assert_eq!(
+        on_disk_storage(&path)
+            .get_public_key(KEY)
+            .unwrap()
+            .public_key,
+        created
+    );
+
+    // the key is persisted, and opened as is afterwards
+    let reopened = OperationalKey::new(on_disk_storage(&path), KEY).unwrap();
+    assert_eq!(reopened.public_key(), created);
+}
+
+#[test]
+fn test_rotation_hooks() {
+    let path = TempPath::new();
+    path.create_as_file().unwrap();
+    let key = OperationalKey::new(on_disk_storage(&path), KEY).unwrap();
+    let rotations = Arc::new(Mutex::new(vec![]));
+    let hook_rotations = rotations.clone();
+    key.on_rotation(move |public_key| hook_rotations.lock().unwrap().push(public_key.clone()));
+
+    let public_key = key.rotate().unwrap();
+    assert_eq!(key.public_key(), public_key);
+    assert_eq!(*rotations.lock().unwrap(), vec![public_key.clone()]);
+    assert!(!key.refresh().unwrap());
+
+    // rotated by another process sharing the storage, e.g. the key manager
+    let rotated_elsewhere = on_disk_storage(&path).rotate_key(KEY).unwrap();
+    assert!(key.refresh().unwrap());
+    assert_eq!(key.public_key(), rotated_elsewhere);
+    assert_eq!(
+        *rotations.lock().unwrap(),
+        vec![public_key, rotated_elsewhere.clone()]
+    );
+
+    // the new version signs from then on
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = key.sign(&message).unwrap();
+    signature.verify(&message, &rotated_elsewhere).unwrap();
+}
@@ -12,7 +12,7 @@ use executor_types::ChunkExecutor;
 use futures::channel::mpsc;
 use std::{boxed::Box, collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
-use subscription_service::ReconfigSubscription;
+use subscription_service::{CommitEventBus, ReconfigBus};
 use tokio::runtime::{Builder, Runtime};
 
 /// Creates and bootstraps new state syncs and creates clients for
@@ -25,12 +25,12 @@ pub struct StateSyncBootstrapper {
 impl StateSyncBootstrapper {
     pub fn bootstrap(
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
-        state_sync_to_mempool_sender: mpsc::Sender<diem_mempool::CommitNotification>,
+        commit_event_bus: CommitEventBus,
         storage: Arc<dyn DbReader>,
         executor: Box<dyn ChunkExecutor>,
         node_config: &NodeConfig,
         waypoint: Waypoint,
-        reconfig_event_subscriptions: Vec<ReconfigSubscription>,
+        reconfig_bus: ReconfigBus,
     ) -> Self {
         let runtime = Builder::new_multi_thread()
             .thread_name("state-sync")
@@ -38,11 +38,16 @@ impl StateSyncBootstrapper {
             .build()
             .expect("[State Sync] Failed to create runtime!");
 
-        let executor_proxy = ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
+        let executor_proxy = ExecutorProxy::new(
+            storage,
+            executor,
+            reconfig_bus,
+            node_config.state_sync.max_pending_chunks,
+        );
         Self::bootstrap_with_executor_proxy(
             runtime,
             network,
-            state_sync_to_mempool_sender,
+            commit_event_bus,
             node_config,
             waypoint,
             executor_proxy,
@@ -52,7 +57,7 @@ impl StateSyncBootstrapper {
     pub fn bootstrap_with_executor_proxy<E: ExecutorProxyTrait + 'static>(
         runtime: Runtime,
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
-        state_sync_to_mempool_sender: mpsc::Sender<diem_mempool::CommitNotification>,
+        commit_event_bus: CommitEventBus,
         node_config: &NodeConfig,
         waypoint: Waypoint,
         executor_proxy: E,
@@ -68,7 +73,7 @@ impl StateSyncBootstrapper {
 
         let coordinator = StateSyncCoordinator::new(
             coordinator_receiver,
-            state_sync_to_mempool_sender,
+            commit_event_bus,
             network_senders,
             node_config,
             waypoint,
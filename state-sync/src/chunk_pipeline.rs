@@ -0,0 +1,491 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The chunk pipeline applies chunks in three stages, each running on a thread of its own:
+//! proof verification, execution and commit. The stages are connected by bounded queues and
+//! pass chunks on in the order they were submitted, so that a chunk can be verified while the
+//! one before it is executed and the one before that is committed.
+
+use crate::{counters, error::Error, executor_proxy::ChunkOutcome};
+use diem_infallible::Mutex;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::TransactionListWithProof};
+use executor_types::{ChunkExecutor, ExecutedChunk};
+use futures::channel::mpsc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+/// A chunk submitted to the pipeline, along with the ledger infos it is verified against.
+struct SubmittedChunk {
+    txn_list_with_proof: TransactionListWithProof,
+    target_li: LedgerInfoWithSignatures,
+    intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+}
+
+/// What the stages of the pipeline hand each other: the index of the chunk (in submission order)
+/// and the chunk as processed so far, or the error that stopped it.
+type Staged<T> = (u64, Result<T, Error>);
+
+/// Bookkeeping shared by the stages of the pipeline.
+struct PipelineState {
+    // The index of the first chunk that failed. The chunks behind it are failed as well, rather
+    // than applied on top of it, until the pipeline is empty again.
+    failed_index: AtomicU64,
+    // The number of chunks submitted whose outcome is yet to be reported.
+    num_pending: AtomicUsize,
+    // The number of chunks that left the execution stage and are yet to be committed.
+    num_executed: AtomicUsize,
+}
+
+impl PipelineState {
+    fn is_behind_failed_chunk(&self, index: u64) -> bool {
+        index > self.failed_index.load(Ordering::SeqCst)
+    }
+
+    fn fail(&self, index: u64) {
+        self.failed_index.fetch_min(index, Ordering::SeqCst);
+    }
+
+    fn finish_chunk(&self) {
+        if self.num_pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // The pipeline is empty, so chunks submitted from now on don't follow a failed one.
+            self.failed_index.store(u64::MAX, Ordering::SeqCst);
+        }
+        counters::PENDING_CHUNKS.dec();
+    }
+}
+
+pub(crate) struct ChunkPipeline {
+    chunk_sender: SyncSender<Staged<SubmittedChunk>>,
+    next_index: u64,
+    state: Arc<PipelineState>,
+}
+
+impl ChunkPipeline {
+    /// Spawns the stages of a pipeline whose queues hold up to `max_pending_chunks` chunks each.
+    /// The outcome of every submitted chunk is reported on `outcome_sender`, in submission order.
+    pub(crate) fn new(
+        executor: Arc<Mutex<Box<dyn ChunkExecutor>>>,
+        max_pending_chunks: usize,
+        outcome_sender: mpsc::UnboundedSender<ChunkOutcome>,
+    ) -> Self {
+        let state = Arc::new(PipelineState {
+            failed_index: AtomicU64::new(u64::MAX),
+            num_pending: AtomicUsize::new(0),
+            num_executed: AtomicUsize::new(0),
+        });
+        let (chunk_sender, submitted_chunks) = sync_channel(max_pending_chunks);
+        let (verified_sender, verified_chunks) = sync_channel(max_pending_chunks);
+        let (executed_sender, executed_chunks) = sync_channel(max_pending_chunks);
+
+        let verify_state = Arc::clone(&state);
+        spawn_stage("state-sync-verify", move || {
+            verify_chunks(verify_state, submitted_chunks, verified_sender)
+        });
+        let execute_state = Arc::clone(&state);
+        spawn_stage("state-sync-execute", move || {
+            execute_chunks(execute_state, executor, verified_chunks, executed_sender)
+        });
+        let commit_state = Arc::clone(&state);
+        spawn_stage("state-sync-commit", move || {
+            commit_chunks(commit_state, executed_chunks, outcome_sender)
+        });
+
+        Self {
+            chunk_sender,
+            next_index: 0,
+            state,
+        }
+    }
+
+    /// Hands a chunk to the pipeline without waiting for it to be applied. Fails if the pipeline
+    /// already holds as many chunks as its queues can take.
+    pub(crate) fn submit(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let chunk = SubmittedChunk {
+            txn_list_with_proof,
+            target_li,
+            intermediate_end_of_epoch_li,
+        };
+        let index = self.next_index;
+        self.state.num_pending.fetch_add(1, Ordering::SeqCst);
+        counters::PENDING_CHUNKS.inc();
+        if let Err(error) = self.chunk_sender.try_send((index, Ok(chunk))) {
+            self.state.finish_chunk();
+            return Err(Error::UnexpectedError(match error {
+                TrySendError::Full(_) => "The chunk pipeline is full!".into(),
+                TrySendError::Disconnected(_) => "The chunk pipeline has stopped!".into(),
+            }));
+        }
+        self.next_index += 1;
+        Ok(())
+    }
+}
+
+fn spawn_stage<F: FnOnce() + Send + 'static>(name: &str, stage: F) {
+    thread::Builder::new()
+        .name(name.into())
+        .spawn(stage)
+        .expect("[state sync] Failed to spawn a chunk pipeline stage");
+}
+
+/// Verifies the proofs of the submitted chunks against their target ledger infos.
+fn verify_chunks(
+    state: Arc<PipelineState>,
+    submitted_chunks: Receiver<Staged<SubmittedChunk>>,
+    verified_sender: SyncSender<Staged<SubmittedChunk>>,
+) {
+    for (index, chunk) in submitted_chunks {
+        let chunk = chunk.and_then(|chunk| {
+            if state.is_behind_failed_chunk(index) {
+                return Err(Error::PrecedingChunkFailed);
+            }
+            chunk
+                .txn_list_with_proof
+                .verify(
+                    chunk.target_li.ledger_info(),
+                    chunk.txn_list_with_proof.first_transaction_version,
+                )
+                .map_err(|error| {
+                    Error::ProcessInvalidChunk(format!(
+                        "Chunk proof verification failed: {}",
+                        error
+                    ))
+                })?;
+            Ok(chunk)
+        });
+        if chunk.is_err() {
+            state.fail(index);
+        }
+        if verified_sender.send((index, chunk)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Executes the verified chunks on top of each other, ahead of their commit.
+fn execute_chunks(
+    state: Arc<PipelineState>,
+    executor: Arc<Mutex<Box<dyn ChunkExecutor>>>,
+    verified_chunks: Receiver<Staged<SubmittedChunk>>,
+    executed_sender: SyncSender<Staged<ExecutedChunk>>,
+) {
+    for (index, chunk) in verified_chunks {
+        let chunk = chunk.and_then(|chunk| {
+            if state.is_behind_failed_chunk(index) {
+                return Err(Error::PrecedingChunkFailed);
+            }
+            let mut executor = executor.lock();
+            // With no executed chunk waiting for its commit, storage may have moved on without
+            // the executor (e.g., consensus committed blocks), so start over from storage.
+            if state.num_executed.load(Ordering::SeqCst) == 0 {
+                executor.reset_executed_chunks().map_err(|error| {
+                    Error::UnexpectedError(format!("Failed to reset the executor: {}", error))
+                })?;
+            }
+            let _timer = counters::EXECUTE_CHUNK_DURATION.start_timer();
+            executor
+                .execute_verified_chunk(
+                    chunk.txn_list_with_proof,
+                    chunk.target_li,
+                    chunk.intermediate_end_of_epoch_li,
+                )
+                .map_err(|error| Error::UnexpectedError(format!("Execute chunk failed: {}", error)))
+        });
+        if chunk.is_err() {
+            state.fail(index);
+        }
+        state.num_executed.fetch_add(1, Ordering::SeqCst);
+        if executed_sender.send((index, chunk)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Commits the executed chunks and reports the outcome of every chunk.
+fn commit_chunks(
+    state: Arc<PipelineState>,
+    executed_chunks: Receiver<Staged<ExecutedChunk>>,
+    outcome_sender: mpsc::UnboundedSender<ChunkOutcome>,
+) {
+    for (index, chunk) in executed_chunks {
+        let outcome = chunk.and_then(|chunk| {
+            if state.is_behind_failed_chunk(index) {
+                return Err(Error::PrecedingChunkFailed);
+            }
+            let _timer = counters::COMMIT_CHUNK_DURATION.start_timer();
+            chunk
+                .commit()
+                .map_err(|error| Error::UnexpectedError(format!("Commit chunk failed: {}", error)))
+        });
+        if outcome.is_err() {
+            state.fail(index);
+        }
+        state.num_executed.fetch_sub(1, Ordering::SeqCst);
+        state.finish_chunk();
+        if outcome_sender.unbounded_send(outcome).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{bail, Result};
+    use diem_crypto::HashValue;
+    use diem_types::{
+        block_info::BlockInfo,
+        contract_event::ContractEvent,
+        ledger_info::LedgerInfo,
+        transaction::{TransactionToCommit, Version},
+    };
+    use executor_types::ExecutedTrees;
+    use futures::{executor::block_on, StreamExt};
+    use std::{
+        collections::BTreeMap,
+        sync::mpsc::{channel, Sender},
+    };
+    use storage_interface::DbWriter;
+
+    // Chunks are told apart by the version of their target ledger info.
+    #[derive(Debug, PartialEq)]
+    enum ExecutorCall {
+        Reset,
+        Execute(Version),
+    }
+
+    /// Commits chunks by reporting the version of their ledger info, once the gate (if any) lets
+    /// them through.
+    struct MockDb {
+        committed: Mutex<Sender<Version>>,
+        commit_gate: Option<Mutex<Receiver<()>>>,
+        failing_version: Option<Version>,
+    }
+
+    impl DbWriter for MockDb {
+        fn save_transactions(
+            &self,
+            _txns_to_commit: &[TransactionToCommit],
+            _first_version: Version,
+            ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+        ) -> Result<()> {
+            let version = ledger_info_with_sigs.unwrap().ledger_info().version();
+            if let Some(commit_gate) = &self.commit_gate {
+                commit_gate.lock().recv().unwrap();
+            }
+            if self.failing_version == Some(version) {
+                bail!("Failed to save chunk {}", version);
+            }
+            self.committed.lock().send(version).unwrap();
+            Ok(())
+        }
+    }
+
+    /// Executes chunks into empty chunks committing their target ledger info, and reports the
+    /// calls made to it.
+    struct MockChunkExecutor {
+        db: Arc<MockDb>,
+        calls: Sender<ExecutorCall>,
+        failing_version: Option<Version>,
+    }
+
+    impl ChunkExecutor for MockChunkExecutor {
+        fn execute_and_commit_chunk(
+            &mut self,
+            _txn_list_with_proof: TransactionListWithProof,
+            _verified_target_li: LedgerInfoWithSignatures,
+            _epoch_change_li: Option<LedgerInfoWithSignatures>,
+        ) -> Result<Vec<ContractEvent>> {
+            unimplemented!()
+        }
+
+        fn execute_verified_chunk(
+            &mut self,
+            _txn_list_with_proof: TransactionListWithProof,
+            verified_target_li: LedgerInfoWithSignatures,
+            _epoch_change_li: Option<LedgerInfoWithSignatures>,
+        ) -> Result<ExecutedChunk> {
+            let version = verified_target_li.ledger_info().version();
+            self.calls.send(ExecutorCall::Execute(version)).unwrap();
+            if self.failing_version == Some(version) {
+                bail!("Failed to execute chunk {}", version);
+            }
+            Ok(ExecutedChunk::new(
+                self.db.clone(),
+                version,
+                vec![],
+                Some(verified_target_li),
+                vec![],
+                ExecutedTrees::new_empty(),
+                Arc::new(Mutex::new(None)),
+            ))
+        }
+
+        fn reset_executed_chunks(&mut self) -> Result<()> {
+            self.calls.send(ExecutorCall::Reset).unwrap();
+            Ok(())
+        }
+    }
+
+    struct TestPipeline {
+        pipeline: ChunkPipeline,
+        outcomes: mpsc::UnboundedReceiver<ChunkOutcome>,
+        executor_calls: Receiver<ExecutorCall>,
+        committed: Receiver<Version>,
+    }
+
+    impl TestPipeline {
+        fn new(
+            failing_execution: Option<Version>,
+            failing_commit: Option<Version>,
+            commit_gate: Option<Receiver<()>>,
+        ) -> Self {
+            let (committed_sender, committed) = channel();
+            let (calls_sender, executor_calls) = channel();
+            let db = Arc::new(MockDb {
+                committed: Mutex::new(committed_sender),
+                commit_gate: commit_gate.map(Mutex::new),
+                failing_version: failing_commit,
+            });
+            let executor: Box<dyn ChunkExecutor> = Box::new(MockChunkExecutor {
+                db,
+                calls: calls_sender,
+                failing_version: failing_execution,
+            });
+            let (outcome_sender, outcomes) = mpsc::unbounded();
+            Self {
+                pipeline: ChunkPipeline::new(Arc::new(Mutex::new(executor)), 10, outcome_sender),
+                outcomes,
+                executor_calls,
+                committed,
+            }
+        }
+
+        fn submit(&mut self, version: Version) {
+            let target_li = LedgerInfoWithSignatures::new(
+                LedgerInfo::new(
+                    BlockInfo::new(1, 0, HashValue::zero(), HashValue::zero(), version, 0, None),
+                    HashValue::zero(),
+                ),
+                BTreeMap::new(),
+            );
+            self.pipeline
+                .submit(TransactionListWithProof::new_empty(), target_li, None)
+                .unwrap();
+        }
+
+        fn next_outcome(&mut self) -> ChunkOutcome {
+            block_on(self.outcomes.next()).unwrap()
+        }
+
+        fn executed_versions(&self) -> Vec<Version> {
+            self.executor_calls
+                .try_iter()
+                .filter_map(|call| match call {
+                    ExecutorCall::Execute(version) => Some(version),
+                    ExecutorCall::Reset => None,
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_chunks_committed_in_order() {
+        let mut pipeline = TestPipeline::new(None, None, None);
+        for version in 1..=5 {
+            pipeline.submit(version);
+        }
+        for _ in 1..=5 {
+            assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        }
+        assert_eq!(pipeline.executed_versions(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            pipeline.committed.try_iter().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_execution_failure_stops_later_chunks() {
+        let mut pipeline = TestPipeline::new(Some(2), None, None);
+        for version in 1..=4 {
+            pipeline.submit(version);
+        }
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert!(matches!(
+            pipeline.next_outcome(),
+            Err(Error::UnexpectedError(message)) if message.starts_with("Execute chunk failed")
+        ));
+        assert_eq!(pipeline.next_outcome(), Err(Error::PrecedingChunkFailed));
+        assert_eq!(pipeline.next_outcome(), Err(Error::PrecedingChunkFailed));
+        // The chunks behind the failed one are not even executed
+        assert_eq!(pipeline.executed_versions(), vec![1, 2]);
+        assert_eq!(pipeline.committed.try_iter().collect::<Vec<_>>(), vec![1]);
+
+        // Once the pipeline is empty, chunks are applied again
+        pipeline.submit(5);
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert_eq!(pipeline.committed.try_iter().collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn test_commit_failure_stops_later_chunks() {
+        let mut pipeline = TestPipeline::new(None, Some(2), None);
+        for version in 1..=3 {
+            pipeline.submit(version);
+        }
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert!(matches!(
+            pipeline.next_outcome(),
+            Err(Error::UnexpectedError(message)) if message.starts_with("Commit chunk failed")
+        ));
+        assert_eq!(pipeline.next_outcome(), Err(Error::PrecedingChunkFailed));
+        assert_eq!(pipeline.committed.try_iter().collect::<Vec<_>>(), vec![1]);
+
+        pipeline.submit(4);
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert_eq!(pipeline.committed.try_iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_executor_reset_with_no_chunk_pending_commit() {
+        let (commit_gate, commit_gate_receiver) = channel();
+        let mut pipeline = TestPipeline::new(None, None, Some(commit_gate_receiver));
+        let next_call = |pipeline: &TestPipeline| pipeline.executor_calls.recv().unwrap();
+
+        // Every chunk submitted to an empty pipeline starts over from storage
+        for version in 1..=2 {
+            pipeline.submit(version);
+            assert_eq!(next_call(&pipeline), ExecutorCall::Reset);
+            assert_eq!(next_call(&pipeline), ExecutorCall::Execute(version));
+            commit_gate.send(()).unwrap();
+            assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        }
+
+        // A chunk executed while the one before it awaits its commit is executed on top of it
+        pipeline.submit(3);
+        pipeline.submit(4);
+        assert_eq!(next_call(&pipeline), ExecutorCall::Reset);
+        assert_eq!(next_call(&pipeline), ExecutorCall::Execute(3));
+        assert_eq!(next_call(&pipeline), ExecutorCall::Execute(4));
+        commit_gate.send(()).unwrap();
+        commit_gate.send(()).unwrap();
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert_eq!(pipeline.next_outcome(), Ok(vec![]));
+        assert!(pipeline.executor_calls.try_recv().is_err());
+        assert_eq!(
+            pipeline.committed.try_iter().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     client::{CoordinatorMessage, SyncRequest},
     counters,
     error::Error,
-    executor_proxy::ExecutorProxyTrait,
+    executor_proxy::{ChunkOutcome, ExecutorProxyTrait},
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSyncEvents, StateSyncMessage, StateSyncSender},
     request_manager::RequestManager,
@@ -17,8 +17,9 @@ use diem_config::{
     config::{NodeConfig, PeerNetworkId, RoleType, StateSyncConfig},
     network_id::NodeNetworkId,
 };
+use diem_crypto::hash::CryptoHash;
 use diem_logger::prelude::*;
-use diem_mempool::{CommitResponse, CommittedTransaction};
+use diem_mempool::CommitResponse;
 use diem_types::{
     contract_event::ContractEvent,
     ledger_info::LedgerInfoWithSignatures,
@@ -35,9 +36,10 @@ use futures::{
 use network::{protocols::network::Event, transport::ConnectionMetadata};
 use std::{
     cmp,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     time::{Duration, SystemTime},
 };
+use subscription_service::{CommitEvent, CommitEventBus};
 use tokio::time::{interval, timeout};
 use tokio_stream::wrappers::IntervalStream;
 
@@ -50,6 +52,16 @@ struct PendingRequestInfo {
     chunk_limit: u64,
 }
 
+/// A chunk handed to the chunk pipeline that is yet to be committed.
+struct PendingChunk {
+    // The peer that sent the chunk
+    peer: PeerNetworkId,
+    transactions: Vec<Transaction>,
+    // The version and epoch the node is at once the chunk is committed
+    version: Version,
+    epoch: u64,
+}
+
 /// Coordination of the state sync process is driven by StateSyncCoordinator. The `start()`
 /// function runs an infinite event loop and triggers actions based on external and internal
 /// (local) requests. The coordinator works in two modes (depending on the role):
@@ -61,8 +73,8 @@ struct PendingRequestInfo {
 pub(crate) struct StateSyncCoordinator<T> {
     // used to process client requests
     client_events: mpsc::UnboundedReceiver<CoordinatorMessage>,
-    // used to send messages (e.g. notifications about newly committed txns) to mempool
-    state_sync_to_mempool_sender: mpsc::Sender<diem_mempool::CommitNotification>,
+    // used to publish newly committed txns to the rest of the node (e.g., mempool)
+    commit_event_bus: CommitEventBus,
     // Current state of the storage, which includes both the latest committed transaction and the
     // latest transaction covered by the LedgerInfo (see `SynchronizerState` documentation).
     // The state is updated via syncing with the local storage.
@@ -89,17 +101,30 @@ pub(crate) struct StateSyncCoordinator<T> {
     // queue of incoming long polling requests
     // peer will be notified about new chunk of transactions if it's available before expiry time
     subscriptions: HashMap<PeerNetworkId, PendingRequestInfo>,
+    // Chunks handed to the executor proxy's chunk pipeline that are yet to be committed, in the
+    // order they were handed over. The local state only covers committed chunks, so chunks are
+    // requested and validated against the version and epoch after the last pending chunk.
+    pending_chunks: VecDeque<PendingChunk>,
+    // Set once a pending chunk fails to apply. No chunks are accepted until the chunks behind it
+    // have failed as well, after which syncing resumes from the local state.
+    chunk_pipeline_failed: bool,
+    // Chunks received ahead of the chunk following the pending ones, in response to striped
+    // requests, keyed by the version they were requested at. Each is processed once the chunks
+    // before it have been.
+    striped_chunks: BTreeMap<u64, (PeerNetworkId, GetChunkResponse)>,
+    // Reports the outcome of each pending chunk, in order
+    chunk_outcomes: mpsc::UnboundedReceiver<ChunkOutcome>,
     executor_proxy: T,
 }
 
 impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
     pub fn new(
         client_events: mpsc::UnboundedReceiver<CoordinatorMessage>,
-        state_sync_to_mempool_sender: mpsc::Sender<diem_mempool::CommitNotification>,
+        commit_event_bus: CommitEventBus,
         network_senders: HashMap<NodeNetworkId, StateSyncSender>,
         node_config: &NodeConfig,
         waypoint: Waypoint,
-        executor_proxy: T,
+        mut executor_proxy: T,
         initial_state: SyncState,
     ) -> Result<Self, Error> {
         info!(LogSchema::event_log(LogEntry::Waypoint, LogEvent::Initialize).waypoint(waypoint));
@@ -122,10 +147,13 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             Duration::from_millis(node_config.state_sync.multicast_timeout_ms),
             network_senders,
         );
+        let chunk_outcomes = executor_proxy.take_chunk_outcomes().ok_or_else(|| {
+            Error::UnexpectedError("The chunk outcomes of the executor proxy were taken!".into())
+        })?;
 
         Ok(Self {
             client_events,
-            state_sync_to_mempool_sender,
+            commit_event_bus,
             local_state: initial_state,
             config: node_config.state_sync.clone(),
             role,
@@ -135,6 +163,10 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             sync_request: None,
             target_ledger_info: None,
             initialization_listener: None,
+            pending_chunks: VecDeque::new(),
+            chunk_pipeline_failed: false,
+            striped_chunks: BTreeMap::new(),
+            chunk_outcomes,
             executor_proxy,
         })
     }
@@ -231,6 +263,11 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
 
                     }
                 },
+                outcome = self.chunk_outcomes.select_next_some() => {
+                    if let Err(e) = self.process_chunk_outcome(outcome).await {
+                        error!(LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::PostCommitFail).error(&e));
+                    }
+                }
                 _ = interval.select_next_some() => {
                     // dbg!("statesync tick");
                     if let Err(e) = self.check_progress() {
@@ -247,6 +284,13 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         metadata: ConnectionMetadata,
     ) -> Result<(), Error> {
         let peer = PeerNetworkId(network_id, metadata.remote_peer_id);
+        if let Err(e) = self.request_manager.announce_protocol_version(&peer) {
+            error!(
+                LogSchema::event_log(LogEntry::ProtocolVersion, LogEvent::NetworkSendError)
+                    .peer(&peer)
+                    .error(&e)
+            );
+        }
         self.request_manager.enable_peer(peer, metadata)?;
         self.check_progress()
     }
@@ -327,6 +371,14 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                 // Process chunk response
                 self.process_chunk_response(&peer, *response).await
             }
+            StateSyncMessage::ProtocolVersion { version } => {
+                debug!(
+                    LogSchema::new(LogEntry::ProtocolVersion).peer(&peer),
+                    version = version
+                );
+                self.request_manager.set_protocol_version(&peer, version);
+                Ok(())
+            }
         }
     }
 
@@ -417,13 +469,17 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         // Save the new sync request
         self.sync_request = Some(request);
 
-        // Send a chunk request for the sync target
-        let known_version = self.local_state.synced_version();
-        self.send_chunk_request_with_target(
-            known_version,
-            self.local_state.trusted_epoch(),
-            self.create_sync_request_chunk_target(known_version)?,
-        )
+        // Send a chunk request for the sync target (unless it has to wait for pending chunks)
+        let (known_version, known_epoch) = self.known_version_and_epoch();
+        if !self.can_pipeline_next_chunk(known_epoch) {
+            return Ok(());
+        }
+        match self.create_next_chunk_target(known_version)? {
+            Some(chunk_target) => {
+                self.send_chunk_request_with_target(known_version, known_epoch, chunk_target)
+            }
+            None => Ok(()),
+        }
     }
 
     /// Notifies consensus of the given commit response.
@@ -462,12 +518,13 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         // We choose to re-sync the state with the storage as it's the simplest approach:
         // in case the performance implications of re-syncing upon every commit are high,
         // it's possible to manage some of the highest known versions in memory.
+        let old_epoch = self.local_state.trusted_epoch();
         self.sync_state_with_local_storage()?;
         self.update_sync_state_metrics_and_logs()?;
 
-        // Notify mempool of commit
+        // Publish the commit to the rest of the node
         let commit_response = match self
-            .notify_mempool_of_committed_transactions(committed_transactions)
+            .publish_commit_event(&committed_transactions, old_epoch)
             .await
         {
             Ok(()) => CommitResponse::success(),
@@ -561,60 +618,69 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         Ok(())
     }
 
-    /// Notifies mempool that transactions have been committed.
-    async fn notify_mempool_of_committed_transactions(
+    /// Publishes a commit event for `committed_transactions` to the subscribers of the commit
+    /// event bus (e.g., mempool, which removes the committed user transactions), and waits for
+    /// the subscribers that acknowledge commit events.
+    async fn publish_commit_event(
         &mut self,
-        committed_transactions: Vec<Transaction>,
+        committed_transactions: &[Transaction],
+        old_epoch: u64,
     ) -> Result<(), Error> {
-        // Get all user transactions from committed transactions
         let user_transactions = committed_transactions
             .iter()
             .filter_map(|transaction| match transaction {
-                Transaction::UserTransaction(signed_txn) => Some(CommittedTransaction {
-                    sender: signed_txn.sender(),
-                    sequence_number: signed_txn.sequence_number(),
-                }),
+                Transaction::UserTransaction(signed_txn) => {
+                    Some((signed_txn.sender(), signed_txn.sequence_number()))
+                }
                 _ => None,
             })
             .collect();
+        let new_epoch = self.local_state.trusted_epoch();
 
-        // Create commit notification of user transactions for mempool
-        let (callback_sender, callback_receiver) = oneshot::channel();
-        let req = diem_mempool::CommitNotification {
-            transactions: user_transactions,
+        let acknowledged = self.commit_event_bus.publish(CommitEvent {
+            version: self.local_state.synced_version(),
             block_timestamp_usecs: self
                 .local_state
                 .committed_ledger_info()
                 .ledger_info()
                 .timestamp_usecs(),
-            callback: callback_sender,
-        };
+            transaction_hashes: committed_transactions
+                .iter()
+                .map(|txn| txn.hash())
+                .collect(),
+            user_transactions,
+            new_epoch: if new_epoch > old_epoch {
+                Some(new_epoch)
+            } else {
+                None
+            },
+        });
 
-        // Notify mempool of committed transactions
-        if let Err(error) = self.state_sync_to_mempool_sender.try_send(req) {
-            counters::COMMIT_FLOW_FAIL
-                .with_label_values(&[counters::TO_MEMPOOL_LABEL])
-                .inc();
-            Err(Error::CallbackSendFailed(format!(
-                "Failed to notify mempool of committed transactions! Error: {:?}",
-                error
-            )))
-        } else if let Err(error) = timeout(
+        match timeout(
             Duration::from_millis(self.config.mempool_commit_timeout_ms),
-            callback_receiver,
+            acknowledged,
         )
         .await
         {
-          debug!("statesync commit to mempool failed with mempool_commit_timeout_ms expired");
-            counters::COMMIT_FLOW_FAIL
-                .with_label_values(&[counters::FROM_MEMPOOL_LABEL])
-                .inc();
-            Err(Error::CallbackSendFailed(format!(
-                "Did not receive ACK for commit notification from mempool! Error: {:?}",
-                error
-            )))
-        } else {
-            Ok(())
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(error)) => {
+                counters::COMMIT_FLOW_FAIL
+                    .with_label_values(&[counters::TO_MEMPOOL_LABEL])
+                    .inc();
+                Err(Error::CallbackSendFailed(format!(
+                    "Failed to notify commit event subscribers! Error: {:?}",
+                    error
+                )))
+            }
+            Err(error) => {
+                counters::COMMIT_FLOW_FAIL
+                    .with_label_values(&[counters::FROM_MEMPOOL_LABEL])
+                    .inc();
+                Err(Error::CallbackSendFailed(format!(
+                    "Did not receive ACK for commit event from subscribers! Error: {:?}",
+                    error
+                )))
+            }
         }
     }
 
@@ -995,10 +1061,17 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         // Process the chunk based on the response type
         let txn_list_with_proof = response.txn_list_with_proof.clone();
         let chunk_size = response.txn_list_with_proof.len() as u64;
-        let known_version = self.local_state.synced_version();
+        let (known_version, _) = self.known_version_and_epoch();
+        let advertised_version = match &response.response_li {
+            ResponseLedgerInfo::ProgressiveLedgerInfo {
+                highest_li: Some(highest_li),
+                ..
+            } => highest_li.ledger_info().version(),
+            response_li => response_li.version(),
+        };
         match response.response_li {
             ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_target_and_highest(txn_list_with_proof, li, None)
+                self.process_response_with_target_and_highest(peer, txn_list_with_proof, li, None)
             }
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
@@ -1006,6 +1079,7 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             } => {
                 let highest_li = highest_li.unwrap_or_else(|| target_li.clone());
                 self.process_response_with_target_and_highest(
+                    peer,
                     txn_list_with_proof,
                     target_li,
                     Some(highest_li),
@@ -1015,6 +1089,7 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                 waypoint_li,
                 end_of_epoch_li,
             } => self.process_response_with_waypoint_li(
+                peer,
                 txn_list_with_proof,
                 waypoint_li,
                 end_of_epoch_li,
@@ -1024,6 +1099,8 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             self.request_manager.process_invalid_chunk(&peer);
             Error::ProcessInvalidChunk(error.to_string())
         })?;
+        self.request_manager
+            .process_advertised_version(peer, advertised_version);
 
         // Update counters and logs with processed chunk information
         counters::STATE_SYNC_CHUNK_SIZE
@@ -1064,8 +1141,9 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         Ok(())
     }
 
-    /// * Verifies, processes and stores the chunk in the given response.
-    /// * Triggers post-commit actions based on new local state (after successfully processing a chunk).
+    /// * Verifies and processes the chunk in the given response.
+    /// * Hands the chunk to the chunk pipeline to be stored. Post-commit actions are triggered
+    /// once the pipeline reports that the chunk was committed (see `process_chunk_outcome`).
     async fn process_chunk_response(
         &mut self,
         peer: &PeerNetworkId,
@@ -1080,55 +1158,136 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             return Err(error);
         }
 
+        // Track how long the peer took to respond
+        if let Some(first_chunk_version) = response.txn_list_with_proof.first_transaction_version {
+            self.request_manager
+                .process_chunk_received(peer, first_chunk_version.saturating_sub(1));
+        }
+
+        // Chunks can't be applied on top of a failed chunk, so wait for the chunks behind it
+        // to be discarded.
+        if self.chunk_pipeline_failed {
+            return Err(Error::PrecedingChunkFailed);
+        }
+
+        // Keep chunks requested ahead of the next one until the chunks before them are processed.
+        if let Some(version) = self.striped_chunk_version(peer, &response) {
+            self.striped_chunks
+                .insert(version, (peer.clone(), response));
+            return Ok(());
+        }
+
         // Verify the chunk response is well formed before trying to process it.
         self.verify_chunk_response_is_valid(&peer, &response)?;
 
-        // Validate the response and store the chunk if possible.
+        // Validate the response and hand the chunk to the chunk pipeline if possible.
         // Any errors thrown here should be for detecting bad chunks.
-        match self.apply_chunk(peer, response.clone()) {
-            Ok(()) => {
+        if let Err(error) = self.apply_chunk(peer, response) {
+            self.log_apply_chunk_failure(peer, &error);
+            return Err(error);
+        }
+        self.process_striped_chunks();
+        Ok(())
+    }
+
+    /// Returns the version `response` was requested at if it answers a striped request, i.e. if
+    /// it starts after the chunk following the pending ones, at a version requested from `peer`.
+    fn striped_chunk_version(
+        &self,
+        peer: &PeerNetworkId,
+        response: &GetChunkResponse,
+    ) -> Option<u64> {
+        let version = response
+            .txn_list_with_proof
+            .first_transaction_version?
+            .checked_sub(1)?;
+        let (known_version, _) = self.known_version_and_epoch();
+        if version > known_version && self.request_manager.is_requested_from(peer, version) {
+            Some(version)
+        } else {
+            None
+        }
+    }
+
+    /// Processes the striped chunks following the pending ones, in order, and drops those the
+    /// node has synced past.
+    fn process_striped_chunks(&mut self) {
+        loop {
+            let (known_version, known_epoch) = self.known_version_and_epoch();
+            self.striped_chunks = self.striped_chunks.split_off(&known_version);
+            if !self.can_pipeline_next_chunk(known_epoch) {
+                return;
+            }
+            let (peer, response) = match self.striped_chunks.remove(&known_version) {
+                Some(striped_chunk) => striped_chunk,
+                None => return,
+            };
+            let result = self.verify_chunk_response_is_valid(&peer, &response);
+            if let Err(error) = result.and_then(|()| self.apply_chunk(&peer, response)) {
+                self.log_apply_chunk_failure(&peer, &error);
+                return;
+            }
+        }
+    }
+
+    /// Processes the outcome of the oldest pending chunk: triggers post-commit actions if the
+    /// chunk was committed, or flags the chunk pipeline as failed if it wasn't. Once a failed
+    /// pipeline has discarded all pending chunks, syncing resumes from the local state.
+    async fn process_chunk_outcome(&mut self, outcome: ChunkOutcome) -> Result<(), Error> {
+        let chunk = self.pending_chunks.pop_front().ok_or_else(|| {
+            Error::UnexpectedError("Received a chunk outcome, but no chunk is pending!".into())
+        })?;
+        let result = match outcome {
+            Ok(reconfiguration_events) => {
                 counters::APPLY_CHUNK_COUNT
                     .with_label_values(&[
-                        &peer.raw_network_id().to_string(),
-                        &peer.peer_id().to_string(),
+                        &chunk.peer.raw_network_id().to_string(),
+                        &chunk.peer.peer_id().to_string(),
                         counters::SUCCESS_LABEL,
                     ])
                     .inc();
+                self.process_commit_notification(
+                    chunk.transactions,
+                    None,
+                    reconfiguration_events,
+                    Some(&chunk.peer),
+                )
+                .await
             }
             Err(error) => {
-                error!(LogSchema::event_log(
-                    LogEntry::ProcessChunkResponse,
-                    LogEvent::ApplyChunkFail
-                )
-                .peer(peer)
-                .error(&error));
-                counters::APPLY_CHUNK_COUNT
-                    .with_label_values(&[
-                        &peer.raw_network_id().to_string(),
-                        &peer.peer_id().to_string(),
-                        counters::FAIL_LABEL,
-                    ])
-                    .inc();
-                return Err(error);
+                // Only the first failed chunk says anything about its sender: the chunks behind
+                // it are discarded regardless of their content.
+                if !self.chunk_pipeline_failed {
+                    self.chunk_pipeline_failed = true;
+                    self.request_manager.process_invalid_chunk(&chunk.peer);
+                    self.log_apply_chunk_failure(&chunk.peer, &error);
+                }
+                Ok(())
             }
+        };
+
+        if self.chunk_pipeline_failed && self.pending_chunks.is_empty() {
+            self.chunk_pipeline_failed = false;
+            self.sync_state_with_local_storage()?;
         }
+        self.process_striped_chunks();
+        self.request_next_chunk();
+        result
+    }
 
-        // Process the newly committed chunk
-        self.process_commit_notification(
-            response.txn_list_with_proof.transactions.clone(),
-            None,
-            vec![],
-            Some(peer),
-        )
-        .await
-        .map_err(|error| {
-            error!(
-                LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::PostCommitFail)
-                    .peer(peer)
-                    .error(&error)
-            );
-            error
-        })
+    fn log_apply_chunk_failure(&self, peer: &PeerNetworkId, error: &Error) {
+        error!(
+            LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::ApplyChunkFail)
+                .peer(peer)
+                .error(error)
+        );
+        counters::APPLY_CHUNK_COUNT
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                counters::FAIL_LABEL,
+            ])
+            .inc();
     }
 
     fn verify_chunk_response_is_valid(
@@ -1150,7 +1309,7 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
 
         // Verify the chunk is not empty and that it starts at the correct version
         if let Some(first_chunk_version) = response.txn_list_with_proof.first_transaction_version {
-            let known_version = self.local_state.synced_version();
+            let (known_version, _) = self.known_version_and_epoch();
             let expected_version = known_version
                 .checked_add(1)
                 .ok_or_else(|| Error::IntegerOverflow("Expected version has overflown!".into()))?;
@@ -1276,15 +1435,14 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         txn_list_with_proof: TransactionListWithProof,
         ledger_info: Option<LedgerInfoWithSignatures>,
     ) -> Result<(u64, u64), Error> {
-        let new_version = self
-            .local_state
-            .synced_version()
+        let (known_version, known_epoch) = self.known_version_and_epoch();
+        let new_version = known_version
             .checked_add(txn_list_with_proof.len() as u64)
             .ok_or_else(|| {
                 Error::IntegerOverflow("Potential state sync version has overflown".into())
             })?;
 
-        let mut new_epoch = self.local_state.trusted_epoch();
+        let mut new_epoch = known_epoch;
         if let Some(ledger_info) = ledger_info {
             if ledger_info.ledger_info().version() == new_version
                 && ledger_info.ledger_info().ends_epoch()
@@ -1334,55 +1492,140 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         TargetType::Waypoint(waypoint_version)
     }
 
+    /// Returns the version and epoch the node will be at once all pending chunks are committed.
+    fn known_version_and_epoch(&self) -> (u64, u64) {
+        match self.pending_chunks.back() {
+            Some(pending_chunk) => (pending_chunk.version, pending_chunk.epoch),
+            None => (
+                self.local_state.synced_version(),
+                self.local_state.trusted_epoch(),
+            ),
+        }
+    }
+
+    /// Returns true if the next chunk can be requested before the pending chunks are committed.
+    /// Chunks are not pipelined across epochs, as the next epoch's validator set is only
+    /// trusted once the epoch change is committed.
+    fn can_pipeline_next_chunk(&self, known_epoch: u64) -> bool {
+        !self.chunk_pipeline_failed
+            && (self.pending_chunks.len() as u64) < self.config.max_pending_chunks
+            && known_epoch == self.local_state.trusted_epoch()
+    }
+
+    /// Returns the chunk target for the chunk following `known_version`, if there is one.
+    fn create_next_chunk_target(&self, known_version: u64) -> Result<Option<TargetType>, Error> {
+        if !self.is_initialized() {
+            if known_version >= self.waypoint.version() && !self.pending_chunks.is_empty() {
+                // The pending chunks already reach the waypoint
+                Ok(None)
+            } else {
+                Ok(Some(self.create_waypoint_chunk_target()))
+            }
+        } else if let Some(sync_request) = &self.sync_request {
+            let target_version = sync_request.target.ledger_info().version();
+            if known_version >= target_version && !self.pending_chunks.is_empty() {
+                // The pending chunks already reach the sync request target
+                Ok(None)
+            } else {
+                self.create_sync_request_chunk_target(known_version).map(Some)
+            }
+        } else {
+            Ok(Some(self.create_highest_available_chunk_target(
+                self.target_ledger_info.clone(),
+            )))
+        }
+    }
+
+    /// Requests the chunk following the pending ones, if it can be pipelined and hasn't been
+    /// requested already, along with the striped chunks after it.
+    fn request_next_chunk(&mut self) {
+        let (known_version, known_epoch) = self.known_version_and_epoch();
+        if !self.can_pipeline_next_chunk(known_epoch) {
+            return;
+        }
+
+        if self
+            .request_manager
+            .get_last_request_time(known_version)
+            .is_none()
+        {
+            let result = self
+                .create_next_chunk_target(known_version)
+                .and_then(|chunk_target| match chunk_target {
+                    Some(chunk_target) => self.send_chunk_request_and_log_error(
+                        known_version,
+                        known_epoch,
+                        chunk_target,
+                        LogEntry::ProcessChunkResponse,
+                    ),
+                    None => Ok(()),
+                });
+            if let Err(error) = result {
+                debug!(LogSchema::new(LogEntry::SendChunkRequest)
+                    .version(known_version)
+                    .error(&error));
+            }
+        }
+        self.request_striped_chunks(known_version, known_epoch);
+    }
+
+    /// Requests the chunks after the one following `known_version` concurrently, when the sync
+    /// target is more than a chunk away. Each chunk is requested at the version the chunk before
+    /// it ends at if full, so that the requests are spread across peers and their responses can
+    /// be applied in turn. Requests that timed out are retried, against other peers if possible.
+    fn request_striped_chunks(&mut self, known_version: u64, known_epoch: u64) {
+        let chunk_target = match self.create_next_chunk_target(known_version) {
+            Ok(Some(chunk_target)) => chunk_target,
+            _ => return,
+        };
+        let target_version = match chunk_target.version() {
+            Some(target_version) => target_version,
+            None => return,
+        };
+
+        for stripe in 1..self.config.max_concurrent_chunk_requests {
+            let version = match stripe
+                .checked_mul(self.config.chunk_limit)
+                .and_then(|offset| known_version.checked_add(offset))
+            {
+                Some(version) if version < target_version => version,
+                _ => return,
+            };
+            if self.striped_chunks.contains_key(&version)
+                || !self
+                    .request_manager
+                    .has_request_timed_out(version)
+                    .unwrap_or(false)
+            {
+                continue;
+            }
+            if let Err(error) =
+                self.send_chunk_request_with_target(version, known_epoch, chunk_target.clone())
+            {
+                debug!(LogSchema::new(LogEntry::SendChunkRequest)
+                    .version(version)
+                    .error(&error));
+                return;
+            }
+        }
+    }
+
     /// Processing chunk responses that carry a LedgerInfo that should be verified using the
     /// current local trusted validator set.
     fn process_response_with_target_and_highest(
         &mut self,
+        peer: &PeerNetworkId,
         txn_list_with_proof: TransactionListWithProof,
         response_li: LedgerInfoWithSignatures,
         new_highest_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
-        // Optimistically calculate the new known version and epoch (assume the current chunk
-        // is applied successfully).
+        // Calculate the new known version and epoch (assuming the current chunk is applied
+        // successfully).
         let (known_version, known_epoch) = self.calculate_new_known_version_and_epoch(
             txn_list_with_proof.clone(),
             Some(response_li.clone()),
         )?;
 
-        // Send the next chunk request based on the sync mode (sync request or highest available).
-        if self.sync_request.is_some() {
-            match self.create_sync_request_chunk_target(known_version) {
-                Ok(chunk_target) => {
-                    // Send the chunk request and log any errors. If errors are logged
-                    // continue processing the chunk.
-                    let _ = self.send_chunk_request_and_log_error(
-                        known_version,
-                        known_epoch,
-                        chunk_target,
-                        LogEntry::ProcessChunkResponse,
-                    );
-                }
-                Err(error) => {
-                    error!(LogSchema::new(LogEntry::SendChunkRequest).error(&error));
-                }
-            }
-        } else {
-            let mut new_target_ledger_info = None;
-            if let Some(target_ledger_info) = self.target_ledger_info.clone() {
-                if known_version < target_ledger_info.ledger_info().version() {
-                    new_target_ledger_info = Some(target_ledger_info);
-                }
-            }
-            // Send the chunk request and log any errors. If errors are logged
-            // continue processing the chunk.
-            let _ = self.send_chunk_request_and_log_error(
-                known_version,
-                known_epoch,
-                self.create_highest_available_chunk_target(new_target_ledger_info),
-                LogEntry::ProcessChunkResponse,
-            );
-        }
-
         // Validate chunk ledger infos
         self.local_state.verify_ledger_info(&response_li)?;
         if let Some(new_highest_li) = new_highest_li.clone() {
@@ -1393,15 +1636,18 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
 
         // Validate and store the chunk
         self.log_highest_seen_version(new_highest_li.clone());
-        self.validate_and_store_chunk(txn_list_with_proof, response_li, None)?;
-
-        // Need to sync with local storage to update synced version
-        self.sync_state_with_local_storage()?;
-        let synced_version = self.local_state.synced_version();
+        self.validate_and_store_chunk(
+            peer,
+            txn_list_with_proof,
+            response_li,
+            None,
+            known_version,
+            known_epoch,
+        )?;
 
         // Check if we've synced beyond our current target ledger info
         if let Some(target_ledger_info) = &self.target_ledger_info {
-            if synced_version >= target_ledger_info.ledger_info().version() {
+            if known_version >= target_ledger_info.ledger_info().version() {
                 self.target_ledger_info = None;
             }
         }
@@ -1410,38 +1656,32 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         // is appropriate for us.
         if self.target_ledger_info.is_none() {
             if let Some(new_highest_li) = new_highest_li {
-                if synced_version < new_highest_li.ledger_info().version() {
+                if known_version < new_highest_li.ledger_info().version() {
                     self.target_ledger_info = Some(new_highest_li);
                 }
             }
         }
 
+        // Request the next chunk while this one is being applied
+        self.request_next_chunk();
+
         Ok(())
     }
 
     /// Processing chunk responses that carry a LedgerInfo corresponding to the waypoint.
     fn process_response_with_waypoint_li(
         &mut self,
+        peer: &PeerNetworkId,
         txn_list_with_proof: TransactionListWithProof,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
-        // Optimistically calculate the new known version and epoch (assume the current chunk
-        // is applied successfully).
+        // Calculate the new known version and epoch (assuming the current chunk is applied
+        // successfully).
         let (known_version, known_epoch) = self.calculate_new_known_version_and_epoch(
             txn_list_with_proof.clone(),
             end_of_epoch_li.clone(),
         )?;
-        if known_version < self.waypoint.version() {
-            // Send the chunk request and log any errors. If errors are logged
-            // continue processing the chunk.
-            let _ = self.send_chunk_request_and_log_error(
-                known_version,
-                known_epoch,
-                self.create_waypoint_chunk_target(),
-                LogEntry::ProcessChunkResponse,
-            );
-        }
 
         // Verify the end_of_epoch_li against local state and ensure the version
         // corresponds to the version at the end of the chunk.
@@ -1475,18 +1715,32 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                 Error::UnexpectedError(format!("Waypoint verification failed: {}", error))
             })?;
 
-        self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li_to_commit)?;
+        self.validate_and_store_chunk(
+            peer,
+            txn_list_with_proof,
+            waypoint_li,
+            end_of_epoch_li_to_commit,
+            known_version,
+            known_epoch,
+        )?;
         self.log_highest_seen_version(None);
 
+        // Request the next chunk while this one is being applied
+        self.request_next_chunk();
+
         Ok(())
     }
 
     // Assumes that the target LI has been already verified by the caller.
+    // `known_version` and `known_epoch` are the version and epoch once the chunk is committed.
     fn validate_and_store_chunk(
         &mut self,
+        peer: &PeerNetworkId,
         txn_list_with_proof: TransactionListWithProof,
         target: LedgerInfoWithSignatures,
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+        known_version: u64,
+        known_epoch: u64,
     ) -> Result<(), Error> {
         let target_epoch = target.ledger_info().epoch();
         let target_version = target.ledger_info().version();
@@ -1503,8 +1757,19 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             return Ok(());
         }
 
-        self.executor_proxy
-            .execute_chunk(txn_list_with_proof, target, intermediate_end_of_epoch_li)
+        let transactions = txn_list_with_proof.transactions.clone();
+        self.executor_proxy.submit_chunk(
+            txn_list_with_proof,
+            target,
+            intermediate_end_of_epoch_li,
+        )?;
+        self.pending_chunks.push_back(PendingChunk {
+            peer: peer.clone(),
+            transactions,
+            version: known_version,
+            epoch: known_epoch,
+        });
+        Ok(())
     }
 
     /// Returns true if consensus is currently executing and state sync should
@@ -1568,30 +1833,31 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             }
         }
 
+        // Wait for the pending chunks to be committed if the next one can't be pipelined.
+        let (known_version, known_epoch) = self.known_version_and_epoch();
+        if !self.can_pipeline_next_chunk(known_epoch) {
+            return Ok(());
+        }
+
         // If the coordinator didn't make progress by the expected time or did not
-        // send a request for the current local synced version, issue a new request.
-        let known_version = self.local_state.synced_version();
+        // send a request for the current known version, issue a new request.
         if self.request_manager.has_request_timed_out(known_version)? {
             counters::TIMEOUT.inc();
             warn!(LogSchema::new(LogEntry::Timeout).version(known_version));
 
-            let trusted_epoch = self.local_state.trusted_epoch();
-            let chunk_target = if !self.is_initialized() {
-                self.create_waypoint_chunk_target()
-            } else if self.sync_request.is_some() {
-                self.create_sync_request_chunk_target(known_version)?
-            } else {
-                self.create_highest_available_chunk_target(self.target_ledger_info.clone())
-            };
-            self.send_chunk_request_and_log_error(
-                known_version,
-                trusted_epoch,
-                chunk_target,
-                LogEntry::Timeout,
-            )
-        } else {
-            Ok(())
+            if let Some(chunk_target) = self.create_next_chunk_target(known_version)? {
+                self.send_chunk_request_and_log_error(
+                    known_version,
+                    known_epoch,
+                    chunk_target,
+                    LogEntry::Timeout,
+                )?;
+            }
         }
+
+        // Keep the chunks after the next one requested, retrying the requests that timed out
+        self.request_striped_chunks(known_version, known_epoch);
+        Ok(())
     }
 
     /// Sends a chunk request with a given `known_version`, `known_epoch` and `chunk_target`.
@@ -1768,6 +2034,7 @@ mod tests {
     };
     use diem_crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519Signature},
+        hash::CryptoHash,
         HashValue, PrivateKey, Uniform,
     };
     use diem_mempool::CommitResponse;
@@ -1784,10 +2051,11 @@ mod tests {
         waypoint::Waypoint,
         PeerId,
     };
-    use futures::{channel::oneshot, executor::block_on};
+    use futures::{channel::oneshot, executor::block_on, StreamExt};
     use netcore::transport::ConnectionOrigin;
     use network::transport::ConnectionMetadata;
     use std::{collections::BTreeMap, time::SystemTime};
+    use tokio::runtime::Runtime;
 
     #[test]
     fn test_process_sync_request() {
@@ -1918,37 +2186,59 @@ mod tests {
     fn test_process_commit_notification() {
         // Create a coordinator for a validator node
         let mut validator_coordinator = test_utils::create_validator_coordinator();
+        // Waiting for commit acknowledgements needs a tokio timer
+        let runtime = Runtime::new().unwrap();
 
         // Verify that a commit notification with no transactions doesn't error!
-        block_on(validator_coordinator.process_commit_notification(vec![], None, vec![], None))
+        runtime
+            .block_on(validator_coordinator.process_commit_notification(vec![], None, vec![], None))
             .unwrap();
 
         // Verify that consensus is sent a commit ack when everything works
         let (callback_sender, mut callback_receiver) =
             oneshot::channel::<Result<CommitResponse, Error>>();
-        block_on(validator_coordinator.process_commit_notification(
-            vec![],
-            Some(callback_sender),
-            vec![],
-            None,
-        ))
-        .unwrap();
+        runtime
+            .block_on(validator_coordinator.process_commit_notification(
+                vec![],
+                Some(callback_sender),
+                vec![],
+                None,
+            ))
+            .unwrap();
         let callback_result = callback_receiver.try_recv();
         if !matches!(callback_result, Ok(Some(Ok(..)))) {
             panic!("Expected an okay result but got: {:?}", callback_result);
         }
 
-        // TODO(joshlind): verify that mempool is sent the correct transactions!
+        // Verify that commit event subscribers are sent the committed transactions
+        let mut commit_events = validator_coordinator
+            .commit_event_bus
+            .subscribe("test", 1, None);
         let (callback_sender, _callback_receiver) =
             oneshot::channel::<Result<CommitResponse, Error>>();
-        let committed_transactions = vec![create_test_transaction()];
-        block_on(validator_coordinator.process_commit_notification(
-            committed_transactions,
-            Some(callback_sender),
-            vec![],
-            None,
-        ))
-        .unwrap();
+        let committed_transaction = create_test_transaction();
+        runtime
+            .block_on(validator_coordinator.process_commit_notification(
+                vec![committed_transaction.clone()],
+                Some(callback_sender),
+                vec![],
+                None,
+            ))
+            .unwrap();
+        let commit_event = block_on(commit_events.next()).unwrap();
+        assert_eq!(
+            commit_event.transaction_hashes,
+            vec![committed_transaction.hash()]
+        );
+        let signed_transaction = committed_transaction.as_signed_user_txn().unwrap();
+        assert_eq!(
+            commit_event.user_transactions,
+            vec![(
+                signed_transaction.sender(),
+                signed_transaction.sequence_number()
+            )]
+        );
+        assert_eq!(commit_event.new_epoch, None);
 
         // TODO(joshlind): check initialized is fired when unit tests support storage
         // modifications.
@@ -271,6 +271,24 @@ pub static EXECUTE_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Time it takes for state sync's chunk pipeline to commit an executed chunk
+pub static COMMIT_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "diem_state_sync_commit_chunk_duration_s",
+        "Histogram of time it takes for state sync's chunk pipeline to commit an executed chunk"
+    )
+    .unwrap()
+});
+
+/// Number of chunks handed to the chunk pipeline that are yet to be committed
+pub static PENDING_CHUNKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_pending_chunks",
+        "Number of chunks handed to the chunk pipeline that are yet to be committed"
+    )
+    .unwrap()
+});
+
 /// Number of times a long-poll subscription is successfully delivered
 pub static SUBSCRIPTION_DELIVERY_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
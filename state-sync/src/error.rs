@@ -33,6 +33,10 @@ pub enum Error {
     NoTransactionsCommitted,
     #[error("Received an old sync request for version {0}, but our known version is: {1}")]
     OldSyncRequestVersion(Version, Version),
+    #[error("Peer {0} speaks state sync protocol version {1}, but the message needs version {2}")]
+    PeerProtocolVersionTooOld(String, u32, u32),
+    #[error("A chunk ahead of this one failed to apply, so it was not applied either")]
+    PrecedingChunkFailed,
     #[error("Processed an invalid chunk! Failed to apply the chunk: {0}")]
     ProcessInvalidChunk(String),
     #[error(
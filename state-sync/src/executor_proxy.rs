@@ -2,11 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    chunk_pipeline::ChunkPipeline,
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
     shared_components::SyncState,
 };
+use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::{
     account_state::AccountState,
@@ -17,10 +19,15 @@ use diem_types::{
     transaction::TransactionListWithProof,
 };
 use executor_types::{ChunkExecutor, ExecutedTrees};
+use futures::channel::mpsc;
 use itertools::Itertools;
 use std::{collections::HashSet, convert::TryFrom, sync::Arc};
 use storage_interface::DbReader;
-use subscription_service::ReconfigSubscription;
+use subscription_service::ReconfigBus;
+
+/// The outcome of a chunk handed to the chunk pipeline: the reconfiguration events it emitted
+/// once committed, or the error that kept it from being committed.
+pub type ChunkOutcome = Result<Vec<ContractEvent>, Error>;
 
 /// Proxies interactions with execution and storage for state synchronization
 pub trait ExecutorProxyTrait: Send {
@@ -35,6 +42,19 @@ pub trait ExecutorProxyTrait: Send {
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error>;
 
+    /// Hands a batch of transactions to the chunk pipeline, which verifies, executes and commits
+    /// it after the batches handed to it before, without waiting for any of that to happen.
+    fn submit_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error>;
+
+    /// Takes the receiver on which the chunk pipeline reports the outcome of each submitted
+    /// batch, in submission order. Returns `None` if it was already taken.
+    fn take_chunk_outcomes(&mut self) -> Option<mpsc::UnboundedReceiver<ChunkOutcome>>;
+
     /// Gets chunk of transactions given the known version, target version and the max limit.
     fn get_chunk(
         &self,
@@ -59,29 +79,37 @@ pub trait ExecutorProxyTrait: Send {
 
 pub(crate) struct ExecutorProxy {
     storage: Arc<dyn DbReader>,
-    executor: Box<dyn ChunkExecutor>,
-    reconfig_subscriptions: Vec<ReconfigSubscription>,
-    on_chain_configs: OnChainConfigPayload,
+    executor: Arc<Mutex<Box<dyn ChunkExecutor>>>,
+    chunk_pipeline: ChunkPipeline,
+    chunk_outcomes: Option<mpsc::UnboundedReceiver<ChunkOutcome>>,
+    reconfig_bus: ReconfigBus,
 }
 
 impl ExecutorProxy {
     pub(crate) fn new(
         storage: Arc<dyn DbReader>,
         executor: Box<dyn ChunkExecutor>,
-        mut reconfig_subscriptions: Vec<ReconfigSubscription>,
+        reconfig_bus: ReconfigBus,
+        max_pending_chunks: u64,
     ) -> Self {
         let on_chain_configs = Self::fetch_all_configs(&*storage)
             .expect("[state sync] Failed initial read of on-chain configs");
-        for subscription in reconfig_subscriptions.iter_mut() {
-            subscription
-                .publish(on_chain_configs.clone())
-                .expect("[state sync] Failed to publish initial on-chain config");
+        for (_, result) in reconfig_bus.publish(on_chain_configs, &HashSet::new()) {
+            result.expect("[state sync] Failed to publish initial on-chain config");
         }
+        let executor = Arc::new(Mutex::new(executor));
+        let (outcome_sender, chunk_outcomes) = mpsc::unbounded();
+        let chunk_pipeline = ChunkPipeline::new(
+            Arc::clone(&executor),
+            max_pending_chunks as usize,
+            outcome_sender,
+        );
         Self {
             storage,
             executor,
-            reconfig_subscriptions,
-            on_chain_configs,
+            chunk_pipeline,
+            chunk_outcomes: Some(chunk_outcomes),
+            reconfig_bus,
         }
     }
 
@@ -172,6 +200,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
         let timer = counters::EXECUTE_CHUNK_DURATION.start_timer();
         let reconfig_events = self
             .executor
+            .lock()
             .execute_and_commit_chunk(
                 txn_list_with_proof,
                 verified_target_li,
@@ -193,6 +222,23 @@ impl ExecutorProxyTrait for ExecutorProxy {
         Ok(())
     }
 
+    fn submit_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        self.chunk_pipeline.submit(
+            txn_list_with_proof,
+            verified_target_li,
+            intermediate_end_of_epoch_li,
+        )
+    }
+
+    fn take_chunk_outcomes(&mut self) -> Option<mpsc::UnboundedReceiver<ChunkOutcome>> {
+        self.chunk_outcomes.take()
+    }
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -257,49 +303,27 @@ impl ExecutorProxyTrait for ExecutorProxy {
             .map(|event| *event.key())
             .collect::<HashSet<_>>();
 
-        // calculate deltas
+        // notify the subscribers to any changed config or emitted event
         let new_configs = Self::fetch_all_configs(&*self.storage)?;
-        let changed_configs = new_configs
-            .configs()
-            .iter()
-            .filter(|(id, cfg)| {
-                &self.on_chain_configs.configs().get(id).unwrap_or_else(|| {
-                    panic!("Missing on-chain config value in local copy: {}", id)
-                }) != cfg
-            })
-            .map(|(id, _)| *id)
-            .collect::<HashSet<_>>();
-
-        // notify subscribers
         let mut publish_success = true;
-        for subscription in self.reconfig_subscriptions.iter_mut() {
-            // publish updates if *any* of the subscribed configs changed
-            // or any of the subscribed events were emitted
-            let subscribed_items = subscription.subscribed_items();
-            if !changed_configs.is_disjoint(&subscribed_items.configs)
-                || !event_keys.is_disjoint(&subscribed_items.events)
-            {
-                if let Err(e) = subscription.publish(new_configs.clone()) {
-                    publish_success = false;
-                    error!(
-                        LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
-                            .subscription_name(subscription.name.clone())
-                            .error(&Error::UnexpectedError(e.to_string())),
-                        "Failed to publish reconfig notification to subscription {}",
-                        subscription.name
-                    );
-                } else {
-                    info!(
-                        LogSchema::event_log(LogEntry::Reconfig, LogEvent::Success)
-                            .subscription_name(subscription.name.clone()),
-                        "Successfully published reconfig notification to subscription {}",
-                        subscription.name
-                    );
-                }
+        for (name, result) in self.reconfig_bus.publish(new_configs, &event_keys) {
+            if let Err(e) = result {
+                publish_success = false;
+                error!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
+                        .subscription_name(name.clone())
+                        .error(&Error::UnexpectedError(e.to_string())),
+                    "Failed to publish reconfig notification to subscription {}", name
+                );
+            } else {
+                info!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::Success)
+                        .subscription_name(name.clone()),
+                    "Successfully published reconfig notification to subscription {}", name
+                );
             }
         }
 
-        self.on_chain_configs = new_configs;
         if publish_success {
             counters::RECONFIG_PUBLISH_COUNT
                 .with_label_values(&[counters::SUCCESS_LABEL])
@@ -317,6 +341,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
 mod tests {
     use super::*;
     use channel::diem_channel::Receiver;
+    use diem_config::config::StateSyncConfig;
     use diem_crypto::{ed25519::*, PrivateKey, Uniform};
     use diem_transaction_builder::stdlib::{
         encode_peer_to_peer_with_metadata_script,
@@ -638,7 +663,14 @@ mod tests {
         // Create executor proxy with given subscription
         let block_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![subscription]);
+        let reconfig_bus = ReconfigBus::new();
+        reconfig_bus.add(subscription);
+        let executor_proxy = ExecutorProxy::new(
+            db,
+            chunk_executor,
+            reconfig_bus,
+            StateSyncConfig::default().max_pending_chunks,
+        );
 
         // Verify initial reconfiguration notification is sent
         assert!(
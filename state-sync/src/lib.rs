@@ -8,6 +8,7 @@
 #![recursion_limit = "1024"]
 
 pub mod bootstrapper;
+mod chunk_pipeline;
 pub mod chunk_request;
 pub mod chunk_response;
 pub mod client;
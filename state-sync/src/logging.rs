@@ -90,6 +90,7 @@ pub enum LogEntry {
     Reconfig,
     NewPeer,
     NewPeerAlreadyExists,
+    ProtocolVersion,
     LostPeer,
     LostPeerNotKnown,
     Waypoint,
@@ -19,10 +19,37 @@ use serde::{Deserialize, Serialize};
 const STATE_SYNC_MAX_BUFFER_SIZE: usize = 1;
 
 /// State sync network messages
+///
+/// Variants are only ever appended, so that a node decodes every message of the protocol
+/// versions before its own. Messages of later versions fail to decode and are dropped by the
+/// network layer, which is why they are only sent to peers that announced such a version.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum StateSyncMessage {
     GetChunkRequest(Box<GetChunkRequest>),
     GetChunkResponse(Box<GetChunkResponse>),
+    /// Announces the highest state sync protocol version the sender speaks. Sent to every new
+    /// peer; peers that never announce one are assumed to speak version 1.
+    ProtocolVersion {
+        version: u32,
+    },
+}
+
+/// State sync protocol version spoken by this node. Version 2 adds protocol version
+/// announcements.
+pub const STATE_SYNC_PROTOCOL_VERSION: u32 = 2;
+/// Version assumed for peers that have not announced one.
+pub const STATE_SYNC_LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+impl StateSyncMessage {
+    /// Returns the first protocol version able to decode the message.
+    pub fn protocol_version(&self) -> u32 {
+        match self {
+            StateSyncMessage::GetChunkRequest(_) | StateSyncMessage::GetChunkResponse(_) => {
+                STATE_SYNC_LEGACY_PROTOCOL_VERSION
+            }
+            StateSyncMessage::ProtocolVersion { .. } => STATE_SYNC_PROTOCOL_VERSION,
+        }
+    }
 }
 
 /// The interface from Network to StateSync layer.
@@ -6,7 +6,10 @@ use crate::{
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
-    network::{StateSyncMessage, StateSyncSender},
+    network::{
+        StateSyncMessage, StateSyncSender, STATE_SYNC_LEGACY_PROTOCOL_VERSION,
+        STATE_SYNC_PROTOCOL_VERSION,
+    },
 };
 use diem_config::{
     config::{PeerNetworkId, PeerRole},
@@ -24,7 +27,7 @@ use std::{
     cmp::Ordering,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        BTreeMap, HashMap,
+        BTreeMap, HashMap, HashSet,
     },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -35,6 +38,9 @@ const MIN_SCORE: f64 = 1.0;
 const STARTING_SCORE: f64 = 50.0;
 const STARTING_SCORE_PREFERRED: f64 = 100.0;
 
+/// Weight of the latest sample in the moving average of peer latencies.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
 /// Basic metadata about the chunk request.
 #[derive(Clone, Debug)]
 pub struct ChunkRequestInfo {
@@ -44,6 +50,8 @@ pub struct ChunkRequestInfo {
     multicast_level: NetworkId,
     multicast_start_time: SystemTime,
     last_request_peers: Vec<PeerNetworkId>,
+    // Whether one of `last_request_peers` responded
+    responded: bool,
 }
 
 impl ChunkRequestInfo {
@@ -56,6 +64,7 @@ impl ChunkRequestInfo {
             multicast_level,
             multicast_start_time: now,
             last_request_peers: peers,
+            responded: false,
         }
     }
 }
@@ -85,6 +94,12 @@ pub struct RequestManager {
     // NetworkId.
     multicast_network_level: NetworkId,
     network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+    // Protocol versions announced by connected peers, upstream or not
+    protocol_versions: HashMap<PeerNetworkId, u32>,
+    // Highest version each upstream peer advertised in the ledger infos of its chunk responses
+    peer_versions: HashMap<PeerNetworkId, u64>,
+    // Moving average of the time each upstream peer took to respond to chunk requests
+    peer_latencies: HashMap<PeerNetworkId, Duration>,
 }
 
 impl RequestManager {
@@ -103,6 +118,9 @@ impl RequestManager {
             multicast_timeout,
             multicast_network_level,
             network_senders,
+            protocol_versions: HashMap::new(),
+            peer_versions: HashMap::new(),
+            peer_latencies: HashMap::new(),
         }
     }
 
@@ -157,6 +175,9 @@ impl RequestManager {
         .observe(0.0);
 
         info!(LogSchema::new(LogEntry::LostPeer).peer(&peer));
+        self.protocol_versions.remove(peer);
+        self.peer_versions.remove(peer);
+        self.peer_latencies.remove(peer);
 
         if self.peer_scores.contains_key(peer) {
             counters::ACTIVE_UPSTREAM_PEERS
@@ -213,10 +234,43 @@ impl RequestManager {
         }
     }
 
+    // Returns the weight of a peer when picking peers: its score, scaled down by its average
+    // latency (in seconds), so that of two peers with the same score the faster one is picked
+    // more often.
+    fn peer_weight(&self, peer: &PeerNetworkId, peer_score: f64) -> f64 {
+        match self.peer_latencies.get(peer) {
+            Some(latency) => peer_score / (1.0 + latency.as_secs_f64()),
+            None => peer_score,
+        }
+    }
+
+    // Returns the peers to avoid sending the chunk request with known_version = `version` to:
+    // peers that advertised they haven't synced beyond `version`, peers still expected to respond
+    // to other requests, and peers the request was last sent to (so retries go to alternates).
+    fn peers_to_avoid(&self, version: u64) -> HashSet<PeerNetworkId> {
+        let behind_peers = self
+            .peer_versions
+            .iter()
+            .filter(|(_, peer_version)| **peer_version <= version)
+            .map(|(peer, _)| peer.clone());
+        let busy_peers = self
+            .requests
+            .iter()
+            .filter(|(request_version, request)| {
+                **request_version == version
+                    || (!request.responded
+                        && !is_timeout(request.last_request_time, self.request_timeout))
+            })
+            .flat_map(|(_, request)| request.last_request_peers.iter().cloned());
+        behind_peers.chain(busy_peers).collect()
+    }
+
     // Calculates a weighted index for each peer per network. This is used to probabilistically
-    // select a peer (per network) to send a chunk request to.
+    // select a peer (per network) to send the chunk request with known_version = `version` to.
+    // Peers to avoid are left out, unless that would leave their network without peers.
     fn calculate_weighted_peers_per_network(
         &mut self,
+        version: u64,
     ) -> BTreeMap<NetworkId, (Vec<PeerNetworkId>, Option<WeightedIndex<f64>>)> {
         // Group peers by network level
         let peers_by_network_level = self
@@ -224,17 +278,29 @@ impl RequestManager {
             .iter()
             .map(|(peer, peer_score)| (peer.raw_network_id(), (peer, peer_score)))
             .into_group_map();
+        let peers_to_avoid = self.peers_to_avoid(version);
 
         // For each network, compute the weighted index
         peers_by_network_level
             .into_iter()
             .map(|(network_level, peers)| {
+                let preferred_peers: Vec<_> = peers
+                    .iter()
+                    .filter(|(peer, _)| !peers_to_avoid.contains(*peer))
+                    .cloned()
+                    .collect();
+                let peers = if preferred_peers.is_empty() {
+                    peers
+                } else {
+                    preferred_peers
+                };
+
                 let mut eligible_peers = vec![];
                 let weights: Vec<_> = peers
                     .iter()
                     .map(|(peer, peer_score)| {
                         eligible_peers.push((*peer).clone());
-                        *peer_score
+                        self.peer_weight(peer, **peer_score)
                     })
                     .collect();
                 let weighted_index = WeightedIndex::new(weights)
@@ -256,9 +322,11 @@ impl RequestManager {
     /// determined by the multicast network level. All networks with preference
     /// level <= multicast level are sampled. If there are no live peers in these networks,
     /// the multicast level is updated to the preference level of the first chosen network.
-    fn pick_peers(&mut self) -> Vec<PeerNetworkId> {
+    /// Within a network, peers are picked by score and latency, preferring peers that can serve
+    /// the chunk following `version` and aren't busy with other requests.
+    fn pick_peers(&mut self, version: u64) -> Vec<PeerNetworkId> {
         // Calculate a weighted peer selection map per network level
-        let weighted_peers_per_network = self.calculate_weighted_peers_per_network();
+        let weighted_peers_per_network = self.calculate_weighted_peers_per_network(version);
 
         let mut chosen_peers = vec![];
         let mut new_multicast_network_level = None;
@@ -286,7 +354,7 @@ impl RequestManager {
     pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<(), Error> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_request(req.clone());
 
-        let peers = self.pick_peers();
+        let peers = self.pick_peers(req.known_version);
         if peers.is_empty() {
             debug!("no statesync available peers");
             counters::STATE_SYNC_NO_AVAILABLE_PEERS.inc();
@@ -306,9 +374,8 @@ impl RequestManager {
         let mut failed_peer_sends = vec![];
 
         for peer in peers {
-            let mut sender = self.get_network_sender(&peer);
             let peer_id = peer.peer_id();
-            let send_result = sender.send_to(peer_id, msg.clone());
+            let send_result = self.send_to(&peer, msg.clone());
             let curr_log = log.clone().peer(&peer);
             let result_label = if let Err(e) = send_result {
                 failed_peer_sends.push(peer.clone());
@@ -354,6 +421,50 @@ impl RequestManager {
         peer: &PeerNetworkId,
         message: StateSyncMessage,
     ) -> Result<(), Error> {
+        self.send_to(peer, message)
+    }
+
+    /// Announces this node's state sync protocol version to a newly connected `peer`.
+    pub fn announce_protocol_version(&mut self, peer: &PeerNetworkId) -> Result<(), Error> {
+        let mut sender = self
+            .network_senders
+            .get(&peer.network_id())
+            .cloned()
+            .ok_or_else(|| {
+                Error::NetworkError(format!("Missing network sender for peer: {}", peer))
+            })?;
+        // Peers speaking the legacy version drop announcements they fail to decode.
+        sender.send_to(
+            peer.peer_id(),
+            StateSyncMessage::ProtocolVersion {
+                version: STATE_SYNC_PROTOCOL_VERSION,
+            },
+        )
+    }
+
+    pub fn set_protocol_version(&mut self, peer: &PeerNetworkId, version: u32) {
+        self.protocol_versions.insert(peer.clone(), version);
+    }
+
+    /// Returns the protocol version announced by `peer`, or the legacy version if it announced
+    /// none.
+    pub fn protocol_version(&self, peer: &PeerNetworkId) -> u32 {
+        self.protocol_versions
+            .get(peer)
+            .copied()
+            .unwrap_or(STATE_SYNC_LEGACY_PROTOCOL_VERSION)
+    }
+
+    /// Sends `message` to `peer`, unless `peer` speaks a protocol version too old to decode it.
+    fn send_to(&mut self, peer: &PeerNetworkId, message: StateSyncMessage) -> Result<(), Error> {
+        let peer_version = self.protocol_version(peer);
+        if message.protocol_version() > peer_version {
+            return Err(Error::PeerProtocolVersionTooOld(
+                peer.to_string(),
+                peer_version,
+                message.protocol_version(),
+            ));
+        }
         self.get_network_sender(peer)
             .send_to(peer.peer_id(), message)
     }
@@ -368,6 +479,7 @@ impl RequestManager {
             }
             prev_request.last_request_peers = peers;
             prev_request.last_request_time = now;
+            prev_request.responded = false;
             prev_request.clone()
         } else {
             let chunk_request_info =
@@ -393,6 +505,43 @@ impl RequestManager {
         self.update_score(peer, PeerScoreUpdateType::InvalidChunkRequest);
     }
 
+    /// Records that `peer` responded to the chunk request with known_version = `version`, and
+    /// updates its average latency if the request was sent to it.
+    pub fn process_chunk_received(&mut self, peer: &PeerNetworkId, version: u64) {
+        let request = match self.requests.get_mut(&version) {
+            Some(request) if request.last_request_peers.contains(peer) => request,
+            _ => return,
+        };
+        request.responded = true;
+        let latency = match SystemTime::now().duration_since(request.last_request_time) {
+            Ok(latency) => latency,
+            Err(_) => return,
+        };
+        let average_latency = match self.peer_latencies.get(peer) {
+            Some(average_latency) => {
+                average_latency.mul_f64(1.0 - LATENCY_SMOOTHING)
+                    + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        };
+        self.peer_latencies.insert(peer.clone(), average_latency);
+    }
+
+    /// Records that `peer` advertised it has synced up to `version` in a verified chunk response.
+    pub fn process_advertised_version(&mut self, peer: &PeerNetworkId, version: u64) {
+        if self.peer_scores.contains_key(peer) {
+            let peer_version = self.peer_versions.entry(peer.clone()).or_insert(0);
+            *peer_version = (*peer_version).max(version);
+        }
+    }
+
+    /// Returns true if the chunk request with known_version = `version` was last sent to `peer`.
+    pub fn is_requested_from(&self, peer: &PeerNetworkId, version: u64) -> bool {
+        self.requests
+            .get(&version)
+            .map_or(false, |req| req.last_request_peers.contains(peer))
+    }
+
     pub fn process_success_response(&mut self, peer: &PeerNetworkId) {
         // Update the multicast level if appropriate
         let peer_network_level = peer.raw_network_id();
@@ -633,6 +782,38 @@ mod tests {
         assert!(!request_manager.no_available_peers());
     }
 
+    #[test]
+    fn test_protocol_versions() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 1);
+        let validator_0 = validators[0].clone();
+
+        // Peers are assumed to speak the legacy version until they announce one
+        assert_eq!(
+            request_manager.protocol_version(&validator_0),
+            STATE_SYNC_LEGACY_PROTOCOL_VERSION
+        );
+        let announcement = StateSyncMessage::ProtocolVersion {
+            version: STATE_SYNC_PROTOCOL_VERSION,
+        };
+        let result = request_manager.send_to(&validator_0, announcement);
+        if !matches!(result, Err(Error::PeerProtocolVersionTooOld(..))) {
+            panic!("Expected a protocol version error but got: {:?}", result);
+        }
+
+        request_manager.set_protocol_version(&validator_0, STATE_SYNC_PROTOCOL_VERSION);
+        assert_eq!(
+            request_manager.protocol_version(&validator_0),
+            STATE_SYNC_PROTOCOL_VERSION
+        );
+
+        // Reconnecting peers announce their version again
+        request_manager.disable_peer(&validator_0).unwrap();
+        assert_eq!(
+            request_manager.protocol_version(&validator_0),
+            STATE_SYNC_LEGACY_PROTOCOL_VERSION
+        );
+    }
+
     #[test]
     fn test_score_chunk_success() {
         let num_validators = 4;
@@ -769,6 +950,68 @@ mod tests {
         verify_validator_picked_least_often(&mut request_manager, &validators, 0);
     }
 
+    #[test]
+    fn test_score_latency() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 4);
+
+        // Validator 0 takes 10 seconds to respond to each request
+        for version in 1..=NUM_CHUNKS_TO_PROCESS {
+            request_manager.add_request(version, vec![validators[0].clone()]);
+            let request = request_manager.requests.get_mut(&version).unwrap();
+            request.last_request_time -= Duration::from_secs(10);
+            request_manager.process_chunk_received(&validators[0], version);
+        }
+
+        // Verify validator 0 is chosen less often than the other validators
+        verify_validator_picked_least_often(&mut request_manager, &validators, 0);
+    }
+
+    #[test]
+    fn test_pick_peers_ahead_of_version() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 4);
+
+        // Validator 0 advertises version 100, the other validators version 50
+        request_manager.process_advertised_version(&validators[0], 100);
+        for validator in &validators[1..] {
+            request_manager.process_advertised_version(validator, 50);
+        }
+
+        // Verify only validator 0 is chosen for the chunk following version 50
+        for _ in 0..NUM_PICKS_TO_MAKE {
+            assert_eq!(request_manager.pick_peers(50), vec![validators[0].clone()]);
+        }
+
+        // Verify a validator is still chosen when none is known to be ahead
+        assert_eq!(request_manager.pick_peers(100).len(), 1);
+    }
+
+    #[test]
+    fn test_pick_peers_alternates() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 2);
+
+        let validator_0 = vec![validators[0].clone()];
+        let validator_1 = vec![validators[1].clone()];
+
+        // Verify retries of a request are sent to another validator
+        request_manager.add_request(1, validator_0.clone());
+        assert!(request_manager.is_requested_from(&validators[0], 1));
+        assert!(!request_manager.is_requested_from(&validators[1], 1));
+        for _ in 0..NUM_PICKS_TO_MAKE {
+            assert_eq!(request_manager.pick_peers(1), validator_1);
+        }
+
+        // Verify concurrent requests are sent to another validator until validator 0 responds
+        for _ in 0..NUM_PICKS_TO_MAKE {
+            assert_eq!(request_manager.pick_peers(251), validator_1);
+        }
+        request_manager.process_chunk_received(&validators[0], 1);
+        assert!((0..NUM_PICKS_TO_MAKE).any(|_| request_manager.pick_peers(251) == validator_0));
+
+        // Verify the request is retried with the same validator if no other is left
+        request_manager.disable_peer(&validators[1]).unwrap();
+        assert_eq!(request_manager.pick_peers(1), validator_0);
+    }
+
     #[test]
     fn test_score_preferred() {
         let num_validators = 4;
@@ -889,7 +1132,7 @@ mod tests {
         let mut pick_counts = HashMap::new();
 
         for _ in 0..number_of_picks_to_execute {
-            let picked_peers = request_manager.pick_peers();
+            let picked_peers = request_manager.pick_peers(0);
             assert_eq!(1, picked_peers.len()); // Ensure only one validator per multicast level
 
             let picked_peer = picked_peers[0].clone();
@@ -101,6 +101,7 @@ pub(crate) mod test_utils {
     };
     use std::collections::HashMap;
     use storage_interface::DbReaderWriter;
+    use subscription_service::{CommitEventBus, ReconfigBus};
 
     #[cfg(test)]
     pub(crate) fn create_coordinator_with_config_and_waypoint(
@@ -143,7 +144,12 @@ pub(crate) mod test_utils {
 
         // Create executor proxy
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![]);
+        let executor_proxy = ExecutorProxy::new(
+            db,
+            chunk_executor,
+            ReconfigBus::new(),
+            node_config.state_sync.max_pending_chunks,
+        );
 
         // Get initial state
         let initial_state = executor_proxy.get_local_storage_state().unwrap();
@@ -162,12 +168,11 @@ pub(crate) mod test_utils {
 
         // Create channel senders and receivers
         let (_coordinator_sender, coordinator_receiver) = mpsc::unbounded();
-        let (mempool_sender, _mempool_receiver) = mpsc::channel(1);
 
         // Return the new state sync coordinator
         StateSyncCoordinator::new(
             coordinator_receiver,
-            mempool_sender,
+            CommitEventBus::new(),
             network_senders,
             &node_config,
             waypoint,
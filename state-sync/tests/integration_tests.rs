@@ -846,6 +846,9 @@ fn check_chunk_request(message: Message, known_version: u64, target_version: Opt
         StateSyncMessage::GetChunkResponse(_) => {
             panic!("Received chunk response but expecting chunk request!");
         }
+        StateSyncMessage::ProtocolVersion { .. } => {
+            panic!("Received protocol version but expecting chunk request!");
+        }
     }
 }
 
@@ -874,6 +877,9 @@ fn check_chunk_response(
                 chunk_length
             )
         }
+        StateSyncMessage::ProtocolVersion { .. } => {
+            panic!("Received protocol version but expecting chunk response!");
+        }
     }
 }
 
@@ -39,7 +39,7 @@ use diem_types::{
     PeerId,
 };
 use executor_types::ExecutedTrees;
-use futures::{executor::block_on, future::FutureExt, StreamExt};
+use futures::{channel::mpsc, executor::block_on, future::FutureExt, StreamExt};
 use memsocket::MemoryListener;
 use netcore::transport::ConnectionOrigin;
 use network::{
@@ -62,8 +62,8 @@ use state_sync::{
     bootstrapper::StateSyncBootstrapper,
     client::StateSyncClient,
     error::Error,
-    executor_proxy::ExecutorProxyTrait,
-    network::{StateSyncEvents, StateSyncSender},
+    executor_proxy::{ChunkOutcome, ExecutorProxyTrait},
+    network::{StateSyncEvents, StateSyncMessage, StateSyncSender},
     shared_components::SyncState,
 };
 use std::{
@@ -72,6 +72,7 @@ use std::{
     ops::DerefMut,
     sync::Arc,
 };
+use subscription_service::CommitEventBus;
 use tokio::runtime::Runtime;
 use vm_genesis::GENESIS_KEYPAIR;
 
@@ -278,7 +279,12 @@ impl StateSyncEnvironment {
         multicast_timeout_ms: u64,
         mock_network: bool,
     ) {
-        let (config, network_id) = setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        let (mut config, network_id) =
+            setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        if mock_network {
+            // Tests on the mock network step through the messages of one chunk request at a time
+            config.state_sync.max_concurrent_chunk_requests = 1;
+        }
         let network_handles = self.setup_network_handles(index, &role, mock_network, network_id);
         let validators: Vec<ValidatorInfo> = self
             .peers
@@ -295,11 +301,11 @@ impl StateSyncEnvironment {
             peer.signer.clone(),
         )));
 
-        let (mempool_channel, mempool_requests) = futures::channel::mpsc::channel(1_024);
+        let commit_event_bus = CommitEventBus::new();
         let bootstrapper = StateSyncBootstrapper::bootstrap_with_executor_proxy(
             Runtime::new().unwrap(),
             network_handles,
-            mempool_channel,
+            commit_event_bus.clone(),
             &config,
             waypoint,
             MockExecutorProxy::new(handler, storage_proxy.clone()),
@@ -307,7 +313,7 @@ impl StateSyncEnvironment {
 
         peer.client = Some(bootstrapper.create_client(config.state_sync.client_commit_timeout_ms));
         peer.bootstrapper = Some(bootstrapper);
-        peer.mempool = Some(MockSharedMempool::new(Some(mempool_requests)));
+        peer.mempool = Some(MockSharedMempool::new(Some(commit_event_bus)));
         peer.storage_proxy = Some(storage_proxy);
     }
 
@@ -406,11 +412,41 @@ impl StateSyncEnvironment {
 
     /// Delivers next message from peer with index `sender` in this StateSyncEnvironment
     /// Returns the recipient of the msg
+    ///
+    /// Protocol version announcements sent on the way are delivered too, but skipped over.
     pub fn deliver_msg(&mut self, sender_peer_id: PeerId) -> (PeerId, Message) {
-        let network_reqs_rx = self.network_reqs_rxs.get_mut(&sender_peer_id).unwrap();
-        let network_req = block_on(network_reqs_rx.next()).unwrap();
+        loop {
+            let network_reqs_rx = self.network_reqs_rxs.get_mut(&sender_peer_id).unwrap();
+            let network_req = block_on(network_reqs_rx.next()).unwrap();
+
+            // await next message from node
+            let (receiver_id, msg) = self.forward_msg(sender_peer_id, network_req);
+            if !is_protocol_version_announcement(&msg) {
+                return (receiver_id, msg);
+            }
+        }
+    }
+
+    // checks that the `env_idx`th peer in this env sends no message to its `network_idx`th network
+    // other than protocol version announcements
+    pub fn assert_no_message_sent(&mut self, sender_peer_id: PeerId) {
+        loop {
+            let network_reqs_rx = self.network_reqs_rxs.get_mut(&sender_peer_id).unwrap();
+            match network_reqs_rx.select_next_some().now_or_never() {
+                None => return,
+                Some(network_req) => {
+                    let (_, msg) = self.forward_msg(sender_peer_id, network_req);
+                    assert!(is_protocol_version_announcement(&msg));
+                }
+            }
+        }
+    }
 
-        // await next message from node
+    fn forward_msg(
+        &mut self,
+        sender_peer_id: PeerId,
+        network_req: PeerManagerRequest,
+    ) -> (PeerId, Message) {
         if let PeerManagerRequest::SendDirectSend(receiver_id, msg) = network_req {
             let receiver_network_notif_tx = self.network_notifs_txs.get_mut(&receiver_id).unwrap();
             receiver_network_notif_tx
@@ -425,12 +461,6 @@ impl StateSyncEnvironment {
         }
     }
 
-    // checks that the `env_idx`th peer in this env sends no message to its `network_idx`th network
-    pub fn assert_no_message_sent(&mut self, sender_peer_id: PeerId) {
-        let network_reqs_rx = self.network_reqs_rxs.get_mut(&sender_peer_id).unwrap();
-        assert!(network_reqs_rx.select_next_some().now_or_never().is_none());
-    }
-
     pub fn clone_storage(&mut self, from_idx: usize, to_idx: usize) {
         let from_peer = self.peers[from_idx].borrow();
         let from_storage = from_peer.storage_proxy.as_ref().unwrap();
@@ -812,11 +842,19 @@ pub type MockRpcHandler = Box<
 pub struct MockExecutorProxy {
     handler: MockRpcHandler,
     storage: Arc<RwLock<MockStorage>>,
+    outcome_sender: mpsc::UnboundedSender<ChunkOutcome>,
+    chunk_outcomes: Option<mpsc::UnboundedReceiver<ChunkOutcome>>,
 }
 
 impl MockExecutorProxy {
     pub fn new(handler: MockRpcHandler, storage: Arc<RwLock<MockStorage>>) -> Self {
-        Self { handler, storage }
+        let (outcome_sender, chunk_outcomes) = mpsc::unbounded();
+        Self {
+            handler,
+            storage,
+            outcome_sender,
+            chunk_outcomes: Some(chunk_outcomes),
+        }
     }
 }
 
@@ -839,6 +877,28 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 
+    fn submit_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<(), Error> {
+        let outcome = self
+            .execute_chunk(
+                txn_list_with_proof,
+                ledger_info_with_sigs,
+                intermediate_end_of_epoch_li,
+            )
+            .map(|()| vec![]);
+        self.outcome_sender
+            .unbounded_send(outcome)
+            .map_err(|error| Error::UnexpectedError(error.to_string()))
+    }
+
+    fn take_chunk_outcomes(&mut self) -> Option<mpsc::UnboundedReceiver<ChunkOutcome>> {
+        self.chunk_outcomes.take()
+    }
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -885,3 +945,10 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 }
+
+fn is_protocol_version_announcement(msg: &Message) -> bool {
+    matches!(
+        bcs::from_bytes(&msg.mdata),
+        Ok(StateSyncMessage::ProtocolVersion { .. })
+    )
+}
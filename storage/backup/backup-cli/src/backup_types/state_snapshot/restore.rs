@@ -36,6 +36,12 @@ pub struct StateSnapshotRestoreOpt {
     pub manifest_handle: FileHandle,
     #[structopt(long = "state-into-version")]
     pub version: Version,
+    #[structopt(
+        long,
+        help = "Resume the restore of this snapshot if interrupted earlier. The DB must hold no \
+        other state."
+    )]
+    pub resume: bool,
 }
 
 pub struct StateSnapshotRestoreController {
@@ -48,6 +54,8 @@ pub struct StateSnapshotRestoreController {
     /// nothing will be done, otherwise, this has no effect.
     target_version: Version,
     epoch_history: Option<Arc<EpochHistory>>,
+    /// Whether a restore of the same snapshot interrupted earlier is resumed.
+    resume: bool,
 }
 
 impl StateSnapshotRestoreController {
@@ -64,6 +72,7 @@ impl StateSnapshotRestoreController {
             manifest_handle: opt.manifest_handle,
             target_version: global_opt.target_version,
             epoch_history,
+            resume: opt.resume,
         }
     }
 
@@ -108,9 +117,17 @@ impl StateSnapshotRestoreController {
             epoch_history.verify_ledger_info(&li)?;
         }
 
-        let mut receiver = self
-            .run_mode
-            .get_state_restore_receiver(self.version, manifest.root_hash)?;
+        let mut receiver = if self.resume {
+            self.run_mode
+                .get_resumable_state_restore_receiver(self.version, manifest.root_hash)?
+        } else {
+            self.run_mode
+                .get_state_restore_receiver(self.version, manifest.root_hash)?
+        };
+        let resume_point = receiver.previous_key_hash();
+        if let Some(key) = resume_point {
+            info!("Resuming the restore after account key {}.", key);
+        }
 
         let (ver_gauge, tgt_leaf_idx, leaf_idx) = if self.run_mode.is_verify() {
             (
@@ -128,16 +145,36 @@ impl StateSnapshotRestoreController {
 
         // FIXME update counters
         ver_gauge.set(self.version as i64);
-        tgt_leaf_idx.set(manifest.chunks.last().map_or(0, |c| c.last_idx as i64));
+        let target_leaf_idx = manifest.chunks.last().map_or(0, |c| c.last_idx);
+        tgt_leaf_idx.set(target_leaf_idx as i64);
+        let mut restored = false;
         for chunk in manifest.chunks {
-            let blobs = self.read_account_state_chunk(chunk.blobs).await?;
+            if resume_point.map_or(false, |key| chunk.last_key <= key) {
+                leaf_idx.set(chunk.last_idx as i64);
+                continue;
+            }
+            let blobs = self
+                .read_account_state_chunk(chunk.blobs)
+                .await?
+                .into_iter()
+                .filter(|(key, _blob)| resume_point.map_or(true, |resume_key| *key > resume_key))
+                .collect::<Vec<_>>();
             let proof = self.storage.load_bcs_file(&chunk.proof).await?;
 
             receiver.add_chunk(blobs, proof)?;
             leaf_idx.set(chunk.last_idx as i64);
+            restored = true;
+            info!(
+                "Restored accounts up to index {} of {}.",
+                chunk.last_idx, target_leaf_idx
+            );
         }
 
-        receiver.finish()?;
+        // The last account is only written when the restore finishes, so if it's restored already,
+        // the previous attempt did finish.
+        if restored || resume_point.is_none() {
+            receiver.finish()?;
+        }
         Ok(())
     }
 
@@ -4,13 +4,16 @@
 use crate::{
     backup_types::state_snapshot::{
         backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+        manifest::StateSnapshotBackup,
         restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
     },
     storage::{local_fs::LocalFs, BackupStorage},
     utils::{
         backup_service_client::BackupServiceClient,
-        test_utils::{start_local_backup_service, tmp_db_with_random_content},
-        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt, TrustedWaypointOpt,
+        storage_ext::BackupStorageExt,
+        test_utils::{start_local_backup_service, tmp_db_with_random_content, InterruptedStorage},
+        ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, GlobalRestoreOptions,
+        RocksdbOpt, TrustedWaypointOpt,
     },
 };
 use diem_config::config::RocksdbConfig;
@@ -59,6 +62,7 @@ fn end_to_end() {
             StateSnapshotRestoreOpt {
                 manifest_handle,
                 version: PRE_GENESIS_VERSION,
+                resume: false,
             },
             GlobalRestoreOpt {
                 dry_run: false,
@@ -94,3 +98,91 @@ fn end_to_end() {
 
     rt.shutdown_timeout(Duration::from_secs(1));
 }
+
+#[test]
+fn interrupted_and_resumed() {
+    let (_src_db_dir, src_db, _blocks) = tmp_db_with_random_content();
+    let tgt_db_dir = TempPath::new();
+    tgt_db_dir.create_as_dir().unwrap();
+    let backup_dir = TempPath::new();
+    backup_dir.create_as_dir().unwrap();
+    let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+    let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+    let version = latest_tree_state.num_transactions - 1;
+    let state_root_hash = latest_tree_state.account_state_root_hash;
+
+    let (rt, port) = start_local_backup_service(src_db);
+    let client = Arc::new(BackupServiceClient::new(format!(
+        "http://localhost:{}",
+        port
+    )));
+
+    let manifest_handle = rt
+        .block_on(
+            StateSnapshotBackupController::new(
+                StateSnapshotBackupOpt { version },
+                GlobalBackupOpt {
+                    max_chunk_size: 500,
+                },
+                client,
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+    let manifest: StateSnapshotBackup =
+        rt.block_on(store.load_json_file(&manifest_handle)).unwrap();
+    assert!(manifest.chunks.len() > 1);
+    // The restore is interrupted when reaching the middle of the snapshot.
+    let interrupted_store: Arc<dyn BackupStorage> = Arc::new(InterruptedStorage::new(
+        store,
+        manifest.chunks[manifest.chunks.len() / 2].blobs.clone(),
+    ));
+
+    // The DB is opened anew by each attempt, as it would be by a restarted restore.
+    let restore = |resume| {
+        let global_opt: GlobalRestoreOptions = GlobalRestoreOpt {
+            dry_run: false,
+            db_dir: Some(tgt_db_dir.path().to_path_buf()),
+            target_version: None, // max
+            trusted_waypoints: TrustedWaypointOpt::default(),
+            rocksdb_opt: RocksdbOpt::default(),
+            concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        }
+        .try_into()
+        .unwrap();
+        rt.block_on(
+            StateSnapshotRestoreController::new(
+                StateSnapshotRestoreOpt {
+                    manifest_handle: manifest_handle.clone(),
+                    version: PRE_GENESIS_VERSION,
+                    resume,
+                },
+                global_opt,
+                Arc::clone(&interrupted_store),
+                None, /* epoch_history */
+            )
+            .run(),
+        )
+    };
+    assert!(restore(false).is_err());
+    restore(true).unwrap();
+
+    let tgt_db = DiemDB::open(
+        &tgt_db_dir,
+        true, /* read_only */
+        None, /* pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        tgt_db
+            .get_latest_tree_state()
+            .unwrap()
+            .account_state_root_hash,
+        state_root_hash,
+    );
+
+    rt.shutdown_timeout(Duration::from_secs(1));
+}
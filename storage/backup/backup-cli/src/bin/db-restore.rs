@@ -8,7 +8,10 @@ use backup_cli::{
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
-    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    coordinators::{
+        bootstrap::{BootstrapCoordinator, BootstrapCoordinatorOpt},
+        restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    },
     storage::StorageOpt,
     utils::{GlobalRestoreOpt, GlobalRestoreOptions},
 };
@@ -52,6 +55,14 @@ enum RestoreType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    /// Bootstraps a new node from the latest state snapshot, leaving the later transactions to
+    /// state sync.
+    Bootstrap {
+        #[structopt(flatten)]
+        opt: BootstrapCoordinatorOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[tokio::main]
@@ -100,6 +111,11 @@ async fn main_impl() -> Result<()> {
                 .run()
                 .await?;
         }
+        RestoreType::Bootstrap { opt, storage } => {
+            BootstrapCoordinator::new(opt, global_opt, storage.init_storage().await?)
+                .run()
+                .await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,357 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fast bootstrap of a new node: instead of replaying the history, the latest state snapshot is
+//! restored, verified against the epoch history, along with the transaction backup it falls in.
+//! State sync then only has to fetch the transactions following the snapshot from the network.
+//!
+//! The bootstrap is resumable: if interrupted, running it again continues restoring the same
+//! snapshot from the last account restored.
+
+use crate::{
+    backup_types::{
+        epoch_ending::restore::EpochHistoryRestoreController,
+        state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        transaction::restore::TransactionRestoreBatchController,
+    },
+    metadata,
+    metadata::cache::MetadataCacheOpt,
+    metrics::restore::{
+        COORDINATOR_FAIL_TS, COORDINATOR_START_TS, COORDINATOR_SUCC_TS, COORDINATOR_TARGET_VERSION,
+    },
+    storage::BackupStorage,
+    utils::{unix_timestamp_sec, GlobalRestoreOptions, RestoreRunMode},
+};
+use anyhow::{bail, format_err, Result};
+use diem_logger::prelude::*;
+use std::sync::Arc;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct BootstrapCoordinatorOpt {
+    #[structopt(flatten)]
+    pub metadata_cache_opt: MetadataCacheOpt,
+}
+
+pub struct BootstrapCoordinator {
+    storage: Arc<dyn BackupStorage>,
+    global_opt: GlobalRestoreOptions,
+    metadata_cache_opt: MetadataCacheOpt,
+}
+
+impl BootstrapCoordinator {
+    pub fn new(
+        opt: BootstrapCoordinatorOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            storage,
+            global_opt,
+            metadata_cache_opt: opt.metadata_cache_opt,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!("Bootstrap coordinator started.");
+        COORDINATOR_START_TS.set(unix_timestamp_sec());
+
+        let ret = self.run_impl().await;
+
+        if let Err(e) = &ret {
+            error!(
+                error = ?e,
+                "Bootstrap coordinator failed."
+            );
+            COORDINATOR_FAIL_TS.set(unix_timestamp_sec());
+        } else {
+            info!("Bootstrap coordinator exiting with success.");
+            COORDINATOR_SUCC_TS.set(unix_timestamp_sec());
+        }
+
+        ret
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let metadata_view = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.global_opt.concurrent_downloads,
+        )
+        .await?;
+
+        let (restored_state_version, next_txn_version) = match self.global_opt.run_mode.as_ref() {
+            RestoreRunMode::Restore { restore_handler } => (
+                restore_handler.get_oldest_state_version()?,
+                restore_handler.get_next_expected_transaction_version()?,
+            ),
+            RestoreRunMode::Verify => {
+                info!("This is a dry run.");
+                (None, 0)
+            }
+        };
+
+        let snapshot = match restored_state_version {
+            Some(version) if next_txn_version > version => {
+                info!(
+                    "DB is already bootstrapped at version {}, state sync continues from there.",
+                    next_txn_version - 1,
+                );
+                return Ok(());
+            }
+            // A snapshot restore was interrupted, it's resumed rather than restarted.
+            Some(version) => metadata_view
+                .select_state_snapshot(version)?
+                .filter(|b| b.version == version)
+                .ok_or_else(|| {
+                    format_err!(
+                        "Resuming the restore of the state snapshot at version {}, \
+                        which is no longer in the backup storage.",
+                        version
+                    )
+                })?,
+            None => match metadata_view.select_state_snapshot(self.global_opt.target_version)? {
+                Some(b) => b,
+                None => bail!("No state snapshot found."),
+            },
+        };
+        COORDINATOR_TARGET_VERSION.set(snapshot.version as i64);
+        info!(
+            "Planned to bootstrap from the state snapshot at version {}.",
+            snapshot.version
+        );
+        let global_opt = GlobalRestoreOptions {
+            target_version: snapshot.version,
+            ..self.global_opt
+        };
+
+        info!("[1/3] Restoring the epoch history.");
+        let epoch_endings = metadata_view.select_epoch_ending_backups(snapshot.version)?;
+        let epoch_history = Arc::new(
+            EpochHistoryRestoreController::new(
+                epoch_endings
+                    .into_iter()
+                    .map(|backup| backup.manifest)
+                    .collect(),
+                global_opt.clone(),
+                Arc::clone(&self.storage),
+            )
+            .run()
+            .await?,
+        );
+
+        info!("[2/3] Restoring the state snapshot.");
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: snapshot.manifest,
+                version: snapshot.version,
+                resume: true,
+            },
+            global_opt.clone(),
+            Arc::clone(&self.storage),
+            Some(Arc::clone(&epoch_history)),
+        )
+        .run()
+        .await?;
+
+        info!("[3/3] Restoring the transactions up to the state snapshot.");
+        let txn_manifests = metadata_view
+            .select_transaction_backups(snapshot.version)?
+            .into_iter()
+            .filter(|b| b.last_version >= snapshot.version)
+            .map(|b| b.manifest)
+            .collect();
+        TransactionRestoreBatchController::new(
+            global_opt,
+            self.storage,
+            txn_manifests,
+            None, /* replay_from_version */
+            Some(epoch_history),
+        )
+        .run()
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backup_types::{
+            epoch_ending::backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+            state_snapshot::{
+                backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+                manifest::StateSnapshotBackup,
+            },
+            transaction::backup::{TransactionBackupController, TransactionBackupOpt},
+        },
+        storage::local_fs::LocalFs,
+        utils::{
+            backup_service_client::BackupServiceClient,
+            storage_ext::BackupStorageExt,
+            test_utils::{
+                start_local_backup_service, tmp_db_with_random_content, InterruptedStorage,
+            },
+            ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt,
+            TrustedWaypointOpt,
+        },
+    };
+    use diem_config::config::RocksdbConfig;
+    use diem_crypto::HashValue;
+    use diem_temppath::TempPath;
+    use diem_types::{
+        account_address::{AccountAddress, HashAccountAddress},
+        transaction::Version,
+    };
+    use diemdb::DiemDB;
+    use std::{collections::BTreeSet, convert::TryInto, path::Path};
+    use storage_interface::DbReader;
+    use tokio::time::Duration;
+
+    fn assert_bootstrapped(
+        db_dir: &Path,
+        version: Version,
+        state_root_hash: HashValue,
+        accounts: &BTreeSet<AccountAddress>,
+    ) {
+        let db = DiemDB::open(
+            db_dir,
+            true, /* read_only */
+            None, /* pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+        let tree_state = db.get_latest_tree_state().unwrap();
+        assert_eq!(tree_state.num_transactions, version + 1);
+        assert_eq!(tree_state.account_state_root_hash, state_root_hash);
+        // The restored state tree itself has the expected root hash.
+        for account in accounts {
+            let (blob, proof) = db
+                .get_account_state_with_proof_by_version(*account, version)
+                .unwrap();
+            assert!(blob.is_some());
+            proof
+                .verify(state_root_hash, account.hash(), blob.as_ref())
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_interrupted_and_rerun() {
+        let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+        let tgt_db_dir = TempPath::new();
+        tgt_db_dir.create_as_dir().unwrap();
+        let metadata_cache_dir = TempPath::new();
+        metadata_cache_dir.create_as_dir().unwrap();
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+        let latest_tree_state = src_db.get_latest_tree_state().unwrap();
+        let version = latest_tree_state.num_transactions - 1;
+        let state_root_hash = latest_tree_state.account_state_root_hash;
+        let accounts: BTreeSet<_> = blocks
+            .iter()
+            .flat_map(|(txns_to_commit, _)| txns_to_commit)
+            .flat_map(|txn_to_commit| txn_to_commit.account_states().keys().cloned())
+            .collect();
+        let latest_epoch = blocks.last().unwrap().1.ledger_info().next_block_epoch();
+
+        let (rt, port) = start_local_backup_service(src_db);
+        let client = Arc::new(BackupServiceClient::new(format!(
+            "http://localhost:{}",
+            port
+        )));
+        let global_backup_opt = GlobalBackupOpt {
+            max_chunk_size: 500,
+        };
+        rt.block_on(
+            EpochEndingBackupController::new(
+                EpochEndingBackupOpt {
+                    start_epoch: 0,
+                    end_epoch: latest_epoch,
+                },
+                global_backup_opt.clone(),
+                Arc::clone(&client),
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+        let state_manifest_handle = rt
+            .block_on(
+                StateSnapshotBackupController::new(
+                    StateSnapshotBackupOpt { version },
+                    global_backup_opt.clone(),
+                    Arc::clone(&client),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap();
+        rt.block_on(
+            TransactionBackupController::new(
+                TransactionBackupOpt {
+                    start_version: 0,
+                    num_transactions: version as usize + 1,
+                },
+                global_backup_opt,
+                client,
+                Arc::clone(&store),
+            )
+            .run(),
+        )
+        .unwrap();
+
+        let state_manifest: StateSnapshotBackup = rt
+            .block_on(store.load_json_file(&state_manifest_handle))
+            .unwrap();
+        assert!(state_manifest.chunks.len() > 1);
+        // The first bootstrap is interrupted when reaching the middle of the snapshot.
+        let interrupted_store: Arc<dyn BackupStorage> = Arc::new(InterruptedStorage::new(
+            store,
+            state_manifest.chunks[state_manifest.chunks.len() / 2]
+                .blobs
+                .clone(),
+        ));
+
+        // The DB is opened anew by each run, as it would be by a restarted bootstrap.
+        let bootstrap = || {
+            let global_opt: GlobalRestoreOptions = GlobalRestoreOpt {
+                dry_run: false,
+                db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                target_version: None, // max
+                trusted_waypoints: TrustedWaypointOpt::default(),
+                rocksdb_opt: RocksdbOpt::default(),
+                concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            }
+            .try_into()
+            .unwrap();
+            rt.block_on(
+                BootstrapCoordinator::new(
+                    BootstrapCoordinatorOpt {
+                        metadata_cache_opt: MetadataCacheOpt::new(Some(
+                            metadata_cache_dir.path().to_path_buf(),
+                        )),
+                    },
+                    global_opt,
+                    Arc::clone(&interrupted_store),
+                )
+                .run(),
+            )
+        };
+        assert!(bootstrap().is_err());
+
+        // The snapshot restore is resumed, then the transactions are restored.
+        bootstrap().unwrap();
+        assert_bootstrapped(tgt_db_dir.path(), version, state_root_hash, &accounts);
+
+        // Running it again on the bootstrapped DB leaves it as it is.
+        bootstrap().unwrap();
+        assert_bootstrapped(tgt_db_dir.path(), version, state_root_hash, &accounts);
+
+        rt.shutdown_timeout(Duration::from_secs(1));
+    }
+}
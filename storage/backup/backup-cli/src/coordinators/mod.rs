@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod backup;
+pub mod bootstrap;
 pub mod restore;
 pub mod verify;
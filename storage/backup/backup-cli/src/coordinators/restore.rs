@@ -129,6 +129,7 @@ impl RestoreCoordinator {
                 StateSnapshotRestoreOpt {
                     manifest_handle: backup.manifest,
                     version: backup.version,
+                    resume: false,
                 },
                 self.global_opt.clone(),
                 Arc::clone(&self.storage),
@@ -99,6 +99,7 @@ impl VerifyCoordinator {
                 StateSnapshotRestoreOpt {
                     manifest_handle: backup.manifest,
                     version: backup.version,
+                    resume: false,
                 },
                 global_opt.clone(),
                 Arc::clone(&self.storage),
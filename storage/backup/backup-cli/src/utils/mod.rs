@@ -138,6 +138,19 @@ impl RestoreRunMode {
             ),
         }
     }
+
+    pub fn get_resumable_state_restore_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<JellyfishMerkleRestore<AccountStateBlob>> {
+        match self {
+            Self::Restore { restore_handler } => {
+                restore_handler.get_resumable_state_restore_receiver(version, expected_root_hash)
+            }
+            Self::Verify => self.get_state_restore_receiver(version, expected_root_hash),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -1,6 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::storage::{
+    BackupHandle, BackupHandleRef, BackupStorage, FileHandle, FileHandleRef, ShellSafeName,
+    TextLine,
+};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
 use backup_service::start_backup_service;
 use diem_config::utils::get_available_port;
 use diem_proptest_helpers::ValueGenerator;
@@ -9,10 +15,16 @@ use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::Transaction
 use diemdb::{test_helper::arb_blocks_to_commit, DiemDB};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 use storage_interface::DbWriter;
-use tokio::runtime::Runtime;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    runtime::Runtime,
+};
 
 pub fn tmp_db_empty() -> (TempPath, Arc<DiemDB>) {
     let tmpdir = TempPath::new();
@@ -47,3 +59,54 @@ pub fn start_local_backup_service(db: Arc<DiemDB>) -> (Runtime, u16) {
     let rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
     (rt, port)
 }
+
+/// Backup storage failing the first read of `file_handle`, as if the restore reading it was
+/// interrupted there.
+pub struct InterruptedStorage {
+    inner: Arc<dyn BackupStorage>,
+    file_handle: FileHandle,
+    interrupted: AtomicBool,
+}
+
+impl InterruptedStorage {
+    pub fn new(inner: Arc<dyn BackupStorage>, file_handle: FileHandle) -> Self {
+        Self {
+            inner,
+            file_handle,
+            interrupted: AtomicBool::new(false),
+        }
+    }
+}
+
+#[async_trait]
+impl BackupStorage for InterruptedStorage {
+    async fn create_backup(&self, name: &ShellSafeName) -> Result<BackupHandle> {
+        self.inner.create_backup(name).await
+    }
+
+    async fn create_for_write(
+        &self,
+        backup_handle: &BackupHandleRef,
+        name: &ShellSafeName,
+    ) -> Result<(FileHandle, Box<dyn AsyncWrite + Send + Unpin>)> {
+        self.inner.create_for_write(backup_handle, name).await
+    }
+
+    async fn open_for_read(
+        &self,
+        file_handle: &FileHandleRef,
+    ) -> Result<Box<dyn AsyncRead + Send + Unpin>> {
+        if file_handle == self.file_handle && !self.interrupted.swap(true, Ordering::SeqCst) {
+            bail!("Interrupted before reading {}.", file_handle);
+        }
+        self.inner.open_for_read(file_handle).await
+    }
+
+    async fn save_metadata_line(&self, name: &ShellSafeName, content: &TextLine) -> Result<()> {
+        self.inner.save_metadata_line(name, content).await
+    }
+
+    async fn list_metadata_files(&self) -> Result<Vec<FileHandle>> {
+        self.inner.list_metadata_files().await
+    }
+}
@@ -105,7 +105,7 @@ impl BackupHandler {
         version: Version,
     ) -> Result<Box<dyn Iterator<Item = Result<(HashValue, AccountStateBlob)>> + Send + Sync>> {
         let iterator = JellyfishMerkleIterator::new(
-            Arc::clone(&self.state_store),
+            Arc::new(self.state_store.tree_reader(version)?),
             version,
             HashValue::zero(),
         )?
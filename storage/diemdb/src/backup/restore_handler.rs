@@ -8,7 +8,7 @@ use crate::{
 };
 use anyhow::{ensure, Result};
 use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
-use diem_jellyfish_merkle::restore::JellyfishMerkleRestore;
+use diem_jellyfish_merkle::{restore::JellyfishMerkleRestore, TreeReader};
 use diem_types::{
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
@@ -62,6 +62,26 @@ impl RestoreHandler {
         )
     }
 
+    /// Like `get_state_restore_receiver`, but resumes the restore of the same state snapshot if
+    /// one was interrupted, skipping the accounts already restored. The DB must hold no other
+    /// state.
+    pub fn get_resumable_state_restore_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<JellyfishMerkleRestore<AccountStateBlob>> {
+        JellyfishMerkleRestore::new(Arc::clone(&self.state_store), version, expected_root_hash)
+    }
+
+    /// Returns the version of the oldest state in the DB. In a DB restored from a state snapshot,
+    /// it's the version of the snapshot, whether its restore completed or not.
+    pub fn get_oldest_state_version(&self) -> Result<Option<Version>> {
+        Ok(self
+            .state_store
+            .get_rightmost_leaf()?
+            .map(|(node_key, _leaf)| node_key.version()))
+    }
+
     pub fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
         ensure!(!ledger_infos.is_empty(), "No LedgerInfos to save.");
 
@@ -5,7 +5,7 @@ use super::*;
 #[allow(unused_imports)]
 use crate::{
     schema::jellyfish_merkle_node::JellyfishMerkleNodeSchema,
-    test_helper::{arb_blocks_to_commit, arb_mock_genesis},
+    test_helper::{arb_blocks_to_commit, arb_blocks_to_commit_impl, arb_mock_genesis},
 };
 use diem_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
@@ -15,13 +15,14 @@ use diem_temppath::TempPath;
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_config::AccountResource,
+    account_state::AccountState,
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
     proof::SparseMerkleLeafNode,
     vm_status::{KeptVMStatus, StatusCode},
 };
 use proptest::prelude::*;
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::TryFrom};
 
 fn verify_epochs(db: &DiemDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures]) {
     const LIMIT: usize = 2;
@@ -56,6 +57,23 @@ fn verify_epochs(db: &DiemDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures
         .collect();
     assert_eq!(actual_epoch_change_lis, expected_epoch_change_lis);
 
+    for (i, li) in expected_epoch_change_lis.iter().enumerate() {
+        let epoch_info = db
+            .get_epoch_info(li.ledger_info().next_block_epoch())
+            .unwrap();
+        assert_eq!(epoch_info.start_ledger_info, *li);
+        assert_eq!(
+            Some(&epoch_info.epoch_state),
+            li.ledger_info().next_epoch_state()
+        );
+        assert_eq!(
+            epoch_info.end_ledger_info.as_ref(),
+            expected_epoch_change_lis.get(i + 1)
+        );
+    }
+    assert!(db.get_epoch_info(0).is_err());
+    assert!(db.get_epoch_info(latest_epoch + 1).is_err());
+
     let mut last_ver = 0;
     for li in ledger_infos_with_sigs {
         let this_ver = li.ledger_info().version();
@@ -374,6 +392,23 @@ fn verify_committed_transactions(
             account_state_with_proof
                 .verify(ledger_info, cur_ver, *addr)
                 .unwrap();
+
+            // Fetch and verify each resource along with the account state it was read from.
+            for (path, expected_value) in AccountState::try_from(expected_blob).unwrap().iter() {
+                let (value, blob, proof) = db
+                    .get_account_resource_by_version(*addr, path, cur_ver)
+                    .unwrap();
+                assert_eq!(value.as_ref(), Some(expected_value));
+                assert_eq!(blob.as_ref(), Some(expected_blob));
+                proof
+                    .verify(txn_info.state_root_hash(), addr.hash(), blob.as_ref())
+                    .unwrap();
+            }
+            let (value, blob, _) = db
+                .get_account_resource_by_version(*addr, &[0xff], cur_ver)
+                .unwrap();
+            assert!(value.is_none());
+            assert_eq!(blob.as_ref(), Some(expected_blob));
         }
 
         cur_ver += 1;
@@ -389,9 +424,122 @@ fn verify_committed_transactions(
     );
 }
 
+fn test_parallel_proofs_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let mut db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut num_txns = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            num_txns, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        num_txns += txns_to_commit.len() as u64;
+    }
+    let ledger_info = input.last().unwrap().1.ledger_info();
+    let ledger_version = ledger_info.version();
+    let txns_to_commit: Vec<_> = input.iter().flat_map(|(txns, _)| txns.clone()).collect();
+    let event_keys: Vec<_> = group_events_by_event_key(&txns_to_commit)
+        .into_iter()
+        .map(|(event_key, _)| event_key)
+        .collect();
+
+    let read_all = |db: &DiemDB| {
+        let txn_list_with_proof = db
+            .get_transactions(0, num_txns, ledger_version, true /* fetch_events */)
+            .unwrap();
+        let events_with_proofs: Vec<_> = event_keys
+            .iter()
+            .map(|event_key| {
+                db.get_events_with_proofs(
+                    event_key,
+                    0,
+                    Order::Ascending,
+                    MAX_LIMIT,
+                    Some(ledger_version),
+                )
+                .unwrap()
+            })
+            .collect();
+        (txn_list_with_proof, events_with_proofs)
+    };
+
+    // Proofs constructed in parallel are the ones constructed serially, in the same order.
+    let serial = read_all(&db);
+    db.enable_parallel_proofs(2).unwrap();
+    let parallel = read_all(&db);
+    assert_eq!(serial, parallel);
+    parallel.0.verify(ledger_info, Some(0)).unwrap();
+}
+
+fn test_archive_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let cold_storage_dir = TempPath::new();
+    let open_archive = || {
+        DiemDB::open_archive(
+            &tmp_dir,
+            cold_storage_dir.path(),
+            0, /* hot_storage_bytes */
+            RocksdbConfig::default(),
+        )
+        .unwrap()
+    };
+
+    let db = open_archive();
+    assert!(db.pruner.is_none());
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            &txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    drop(db);
+
+    // Reopened with the same tiers, the whole history reads back.
+    let db = open_archive();
+    let latest_ledger_info = input.last().unwrap().1.clone();
+    assert_eq!(
+        db.ledger_store.get_latest_ledger_info().unwrap(),
+        latest_ledger_info
+    );
+    cur_ver = 0;
+    for (txns_to_commit, _) in &input {
+        verify_committed_transactions(
+            &db,
+            &txns_to_commit,
+            cur_ver,
+            &latest_ledger_info,
+            false, /* is_latest */
+        );
+        cur_ver += txns_to_commit.len() as u64;
+    }
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
+    #[test]
+    fn test_parallel_proofs(
+        input in arb_blocks_to_commit_impl(
+            5,  /* num_accounts */
+            10, /* max_txn_per_block */
+            5,  /* max_blocks */
+        )
+    ) {
+        test_parallel_proofs_impl(input);
+    }
+
+    #[test]
+    fn test_archive(input in arb_blocks_to_commit()) {
+        test_archive_impl(input);
+    }
+
     #[test]
     fn test_save_blocks(input in arb_blocks_to_commit()) {
         test_save_blocks_impl(input);
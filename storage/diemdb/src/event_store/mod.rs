@@ -36,6 +36,7 @@ use std::{
     iter::Peekable,
     sync::Arc,
 };
+use storage_interface::EventStats;
 
 #[derive(Debug)]
 pub(crate) struct EventStore {
@@ -154,6 +155,46 @@ impl EventStore {
         ))
     }
 
+    /// Get the statistics of the events on `event_key` considering all transactions with versions
+    /// no greater than `ledger_version`. They're read off the indexes written as events are
+    /// committed: sequence numbers count the events of a key from 0, so the latest one gives the
+    /// number of events without scanning them.
+    pub fn get_event_stats(
+        &self,
+        event_key: &EventKey,
+        ledger_version: Version,
+    ) -> Result<Option<EventStats>> {
+        let mut iter = self
+            .db
+            .iter::<EventByVersionSchema>(ReadOptions::default())?;
+        iter.seek_for_prev(&(*event_key, ledger_version, u64::max_value()))?;
+        let (last_sequence_number, last_version) = match iter.next().transpose()? {
+            Some(((key, version, seq), _idx)) if key == *event_key => (seq, version),
+            _ => return Ok(None),
+        };
+
+        let mut iter = self.db.iter::<EventByKeySchema>(ReadOptions::default())?;
+        iter.seek(&(*event_key, 0))?;
+        let (first_sequence_number, first_version) = match iter.next().transpose()? {
+            Some(((key, seq), (version, _idx))) if key == *event_key => (seq, version),
+            _ => {
+                return Err(format_err!(
+                    "DB corrupt: event {} of seq num {} isn't indexed by key.",
+                    event_key,
+                    last_sequence_number
+                ))
+            }
+        };
+
+        Ok(Some(EventStats {
+            count: last_sequence_number + 1,
+            first_sequence_number,
+            first_version,
+            last_sequence_number,
+            last_version,
+        }))
+    }
+
     /// Get the next sequence number for specified event key.
     /// Returns 0 if there's no events already in the event stream.
     pub fn get_next_sequence_number(
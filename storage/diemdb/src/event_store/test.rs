@@ -251,10 +251,28 @@ fn test_index_get_impl(event_batches: Vec<Vec<ContractEvent>>) {
                     Some(last_seq_in_same_version as u64),
                     "latest_seq equals this at its version.",
                 );
+                assert_eq!(
+                    store.get_event_stats(&path, *ver).unwrap(),
+                    Some(EventStats {
+                        count: last_seq_in_same_version as u64 + 1,
+                        first_sequence_number: 0,
+                        first_version: events_and_versions[0].1,
+                        last_sequence_number: last_seq_in_same_version as u64,
+                        last_version: *ver,
+                    }),
+                );
 
                 prev_ver = *ver;
             }
 
+            let first_version = events_and_versions[0].1;
+            if first_version > 0 {
+                assert_eq!(
+                    store.get_event_stats(&path, first_version - 1).unwrap(),
+                    None
+                );
+            }
+
             // Fetch by key
             let events = events_and_versions
                 .into_iter()
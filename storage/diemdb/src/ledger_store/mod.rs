@@ -32,7 +32,7 @@ use diem_types::{
 use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaIterator, DB};
 use std::{ops::Deref, sync::Arc};
-use storage_interface::{StartupInfo, TreeState};
+use storage_interface::{EpochInfo, StartupInfo, TreeState};
 
 #[derive(Debug)]
 pub(crate) struct LedgerStore {
@@ -140,6 +140,39 @@ impl LedgerStore {
         })
     }
 
+    /// Gets the validator set of `epoch` along with the ledger infos at its boundaries.
+    pub fn get_epoch_info(&self, epoch: u64) -> Result<EpochInfo> {
+        ensure!(epoch > 0, "EpochInfo only queryable for epoch >= 1.");
+        let latest_epoch = self
+            .get_latest_ledger_info()?
+            .ledger_info()
+            .next_block_epoch();
+        ensure!(
+            epoch <= latest_epoch,
+            "Epoch {} hasn't started yet, latest epoch: {}",
+            epoch,
+            latest_epoch,
+        );
+
+        let start_ledger_info = self.get_latest_ledger_info_in_epoch(epoch - 1)?;
+        let epoch_state = start_ledger_info
+            .ledger_info()
+            .next_epoch_state()
+            .cloned()
+            .ok_or_else(|| format_err!("Last LedgerInfo in epoch must carry next_epoch_state."))?;
+        let end_ledger_info = if epoch < latest_epoch {
+            Some(self.get_latest_ledger_info_in_epoch(epoch)?)
+        } else {
+            None
+        };
+
+        Ok(EpochInfo {
+            epoch_state,
+            start_ledger_info,
+            end_ledger_info,
+        })
+    }
+
     fn get_epoch_state(&self, epoch: u64) -> Result<EpochState> {
         ensure!(epoch > 0, "EpochState only queryable for epoch >= 1.",);
 
@@ -226,6 +259,15 @@ impl LedgerStore {
         iter.next().transpose()
     }
 
+    /// Get the version of the oldest transaction info, the first version committed or restored.
+    pub fn get_first_transaction_info_version(&self) -> Result<Option<Version>> {
+        let mut iter = self
+            .db
+            .iter::<TransactionInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter.next().transpose()?.map(|(version, _)| version))
+    }
+
     /// Get latest transaction info together with its version. Note that during node syncing, this
     /// version can be greater than what's in the latest LedgerInfo.
     pub fn get_latest_transaction_info(&self) -> Result<(Version, TransactionInfo)> {
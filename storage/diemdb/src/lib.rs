@@ -25,9 +25,11 @@ mod event_store;
 mod ledger_counters;
 mod ledger_store;
 mod pruner;
+mod scrubber;
 mod state_store;
 mod system_store;
 mod transaction_store;
+mod tree_state_check;
 
 #[cfg(any(test, feature = "fuzzing"))]
 #[allow(dead_code)]
@@ -35,6 +37,7 @@ mod diemdb_test;
 
 #[cfg(feature = "fuzzing")]
 pub use diemdb_test::test_save_blocks_impl;
+pub use tree_state_check::{CorruptedNode, TreeStateCheck};
 
 use crate::{
     backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
@@ -47,11 +50,12 @@ use crate::{
         DIEM_STORAGE_API_LATENCY_SECONDS, DIEM_STORAGE_COMMITTED_TXNS,
         DIEM_STORAGE_LATEST_TXN_VERSION, DIEM_STORAGE_LEDGER_VERSION,
         DIEM_STORAGE_NEXT_BLOCK_EPOCH, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
-        DIEM_STORAGE_ROCKSDB_PROPERTIES,
+        DIEM_STORAGE_ROCKSDB_PROPERTIES, DIEM_STORAGE_SAVE_TRANSACTIONS_STAGE_SECONDS,
     },
     pruner::Pruner,
     schema::*,
-    state_store::StateStore,
+    scrubber::Scrubber,
+    state_store::{Checkpointer, StateStore},
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
@@ -71,22 +75,25 @@ use diem_types::{
         TransactionListProof,
     },
     transaction::{
-        TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
-        Version, PRE_GENESIS_VERSION,
+        TransactionInfo, TransactionListWithProof, TransactionOutput, TransactionStatus,
+        TransactionToCommit, TransactionWithProof, Version, PRE_GENESIS_VERSION,
     },
 };
 use itertools::{izip, zip_eq};
 use once_cell::sync::Lazy;
-use schemadb::{ColumnFamilyName, Options, DB, DEFAULT_CF_NAME};
+use rayon::prelude::*;
+use schemadb::{ColumnFamilyName, DBPath, Options, DB, DEFAULT_CF_NAME};
 use std::{
     collections::HashMap,
+    fs,
     iter::Iterator,
+    ops::Range,
     path::Path,
     sync::{mpsc, Arc, Mutex},
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, Order, StartupInfo, TreeState};
+use storage_interface::{DbReader, DbWriter, EpochInfo, EventStats, Order, StartupInfo, TreeState};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -94,6 +101,13 @@ const MAX_LIMIT: u64 = 1000;
 // or guarantee that there is always a recent enough waypoint and client knows to boot from there.
 const MAX_NUM_EPOCH_ENDING_LEDGER_INFO: usize = 100;
 
+/// Commits taking longer than this are logged with the time spent in each of their stages.
+const SLOW_COMMIT_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Reads of fewer items construct their proofs on the reading thread even if parallel proofs are
+/// enabled, as handing them to the proof pool would cost more than it saves.
+const MIN_PARALLEL_PROOF_ITEMS: usize = 16;
+
 static ROCKSDB_PROPERTY_MAP: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
     [
         (
@@ -209,6 +223,11 @@ pub struct DiemDB {
     system_store: SystemStore,
     rocksdb_property_reporter: RocksdbPropertyReporter,
     pruner: Option<Pruner>,
+    scrubber: Option<Scrubber>,
+    /// Writes the full state tree in the background, if state deltas are enabled.
+    checkpointer: Option<Checkpointer>,
+    /// Threads constructing the proofs of multi-item reads, if enabled.
+    proof_pool: Option<rayon::ThreadPool>,
 }
 
 impl DiemDB {
@@ -223,10 +242,12 @@ impl DiemDB {
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STATE_DELTA_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
+            WRITE_SET_CF_NAME,
         ]
     }
 
@@ -242,6 +263,9 @@ impl DiemDB {
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
             pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            scrubber: None,
+            checkpointer: None,
+            proof_pool: None,
         }
     }
 
@@ -256,10 +280,45 @@ impl DiemDB {
             "Do not set prune_window when opening readonly.",
         );
 
-        let path = db_root_path.as_ref().join("diemdb");
-        let instant = Instant::now();
+        let rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
+        Self::open_with_options(db_root_path, readonly, prune_window, rocksdb_opts)
+    }
 
+    /// Opens the DB of an archive node, never pruned. Once the SST files under `db_root_path`
+    /// take `hot_storage_bytes`, compaction writes the files of the oldest data, i.e. of the
+    /// last levels, under `cold_storage_path` instead. Reads find the files in either directory,
+    /// reads of recent data only touching the hot one. The DB must always be opened with the same
+    /// tiers, as files are looked up in the directory they were written to.
+    pub fn open_archive<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        cold_storage_path: &Path,
+        hot_storage_bytes: u64,
+        rocksdb_config: RocksdbConfig,
+    ) -> Result<Self> {
+        let path = db_root_path.as_ref().join("diemdb");
+        let cold_path = cold_storage_path.join("diemdb");
+        fs::create_dir_all(&cold_path)?;
         let mut rocksdb_opts = gen_rocksdb_options(&rocksdb_config);
+        rocksdb_opts.set_db_paths(&[
+            DBPath::new(&path, hot_storage_bytes)?,
+            DBPath::new(&cold_path, u64::max_value())?,
+        ]);
+        Self::open_with_options(
+            db_root_path,
+            false, /* readonly */
+            None,  /* prune_window */
+            rocksdb_opts,
+        )
+    }
+
+    fn open_with_options<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        readonly: bool,
+        prune_window: Option<u64>,
+        mut rocksdb_opts: Options,
+    ) -> Result<Self> {
+        let path = db_root_path.as_ref().join("diemdb");
+        let instant = Instant::now();
 
         let db = if readonly {
             DB::open_readonly(
@@ -475,9 +534,8 @@ impl DiemDB {
             }
         }
 
-        let mut events_with_proof = event_indices
-            .into_iter()
-            .map(|(seq, ver, idx)| {
+        let mut events_with_proof =
+            self.map_items_with_proofs(event_indices, |(seq, ver, idx)| {
                 let (event, event_proof) = self
                     .event_store
                     .get_event_with_proof_by_version_and_index(ver, idx)?;
@@ -492,8 +550,7 @@ impl DiemDB {
                     .get_transaction_info_with_proof(ver, ledger_version)?;
                 let proof = EventProof::new(txn_info_with_proof, event_proof);
                 Ok(EventWithProof::new(ver, idx, event, proof))
-            })
-            .collect::<Result<Vec<_>>>()?;
+            })?;
         if order == Order::Descending {
             events_with_proof.reverse();
         }
@@ -530,6 +587,7 @@ impl DiemDB {
         txns_to_commit: &[TransactionToCommit],
         first_version: u64,
         mut cs: &mut ChangeSet,
+        stages: &mut CommitStages,
     ) -> Result<HashValue> {
         let last_version = first_version + txns_to_commit.len() as u64 - 1;
 
@@ -538,25 +596,35 @@ impl DiemDB {
             .iter()
             .map(|txn_to_commit| txn_to_commit.account_states().clone())
             .collect::<Vec<_>>();
-        let state_root_hashes =
+        let state_root_hashes = stages.measure("state_tree", || {
             self.state_store
-                .put_account_state_sets(account_state_sets, first_version, &mut cs)?;
+                .put_account_state_sets(account_state_sets, first_version, &mut cs)
+        })?;
 
         // Event updates. Gather event accumulator root hashes.
-        let event_root_hashes = zip_eq(first_version..=last_version, txns_to_commit)
-            .map(|(ver, txn_to_commit)| {
-                self.event_store
-                    .put_events(ver, txn_to_commit.events(), &mut cs)
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        // Transaction updates. Gather transaction hashes.
-        zip_eq(first_version..=last_version, txns_to_commit).try_for_each(
-            |(ver, txn_to_commit)| {
-                self.transaction_store
-                    .put_transaction(ver, txn_to_commit.transaction(), &mut cs)
-            },
-        )?;
+        let event_root_hashes = stages.measure("events", || {
+            zip_eq(first_version..=last_version, txns_to_commit)
+                .map(|(ver, txn_to_commit)| {
+                    self.event_store
+                        .put_events(ver, txn_to_commit.events(), &mut cs)
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        // Transaction and write set updates. Gather transaction hashes.
+        stages.measure("transactions", || {
+            zip_eq(first_version..=last_version, txns_to_commit).try_for_each(
+                |(ver, txn_to_commit)| {
+                    self.transaction_store.put_transaction(
+                        ver,
+                        txn_to_commit.transaction(),
+                        &mut cs,
+                    )?;
+                    self.transaction_store
+                        .put_write_set(ver, txn_to_commit.write_set(), &mut cs)
+                },
+            )
+        })?;
 
         // Transaction accumulator updates. Get result root hash.
         let txn_infos = izip!(txns_to_commit, state_root_hashes, event_root_hashes)
@@ -572,9 +640,10 @@ impl DiemDB {
             .collect::<Result<Vec<_>>>()?;
         assert_eq!(txn_infos.len(), txns_to_commit.len());
 
-        let new_root_hash =
+        let new_root_hash = stages.measure("accumulator", || {
             self.ledger_store
-                .put_transaction_infos(first_version, &txn_infos, &mut cs)?;
+                .put_transaction_infos(first_version, &txn_infos, &mut cs)
+        })?;
 
         Ok(new_root_hash)
     }
@@ -582,17 +651,139 @@ impl DiemDB {
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support. Also committed are the
     /// LedgerCounters.
-    fn commit(&self, sealed_cs: SealedChangeSet) -> Result<()> {
-        self.db.write_schemas(sealed_cs.batch)?;
+    ///
+    /// The batch is written and the write-ahead log synced in two steps, which persists it just
+    /// like a synchronous write would, so that disk stalls show up apart from the write itself.
+    fn commit(&self, sealed_cs: SealedChangeSet, stages: &mut CommitStages) -> Result<()> {
+        stages.measure("rocksdb_write", || {
+            self.db.write_schemas_unsynced(sealed_cs.batch)
+        })?;
+        stages.measure("fsync", || self.db.sync_wal())?;
+
+        Ok(())
+    }
+
+    /// Starts checking committed data for corruption in the background, at most
+    /// `versions_per_sec` versions per second.
+    pub fn start_scrubber(&mut self, versions_per_sec: u64) {
+        self.scrubber = Some(Scrubber::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.ledger_store),
+            Arc::clone(&self.transaction_store),
+            Arc::clone(&self.event_store),
+            versions_per_sec,
+        ));
+    }
 
+    /// Makes versions write only the account states they change instead of the state tree nodes
+    /// they create, the full tree being written in the background once `checkpoint_interval`
+    /// versions went by since it last was. The DB stays readable with state deltas disabled again.
+    pub fn enable_state_deltas(&mut self, checkpoint_interval: u64) -> Result<()> {
+        ensure!(
+            checkpoint_interval > 0,
+            "State checkpoint interval must be positive."
+        );
+        self.state_store = Arc::new(StateStore::new_with_checkpoint_interval(
+            Arc::clone(&self.db),
+            Some(checkpoint_interval),
+        ));
+        self.checkpointer = Some(Checkpointer::new(Arc::clone(&self.state_store)));
         Ok(())
     }
 
+    /// Recomputes the frozen subtree hashes of the transaction accumulator and the state root
+    /// hash at `version`, by default the version of the latest ledger info, and compares them with
+    /// the stored ones `StartupInfo` is built from. If `repair`, corrupted accumulator nodes are
+    /// rewritten, provided the recomputed accumulator matches the latest ledger info.
+    pub fn check_tree_state(
+        &self,
+        version: Option<Version>,
+        repair: bool,
+    ) -> Result<TreeStateCheck> {
+        let version = match version {
+            Some(version) => version,
+            None => self
+                .ledger_store
+                .get_latest_ledger_info()?
+                .ledger_info()
+                .version(),
+        };
+        tree_state_check::check_tree_state(
+            &self.db,
+            &self.ledger_store,
+            &self.state_store,
+            version,
+            repair,
+        )
+    }
+
+    /// Constructs the per-item proofs of reads of many transactions or events in parallel, on a
+    /// pool of `num_threads` threads shared by all reads.
+    pub fn enable_parallel_proofs(&mut self, num_threads: usize) -> Result<()> {
+        ensure!(num_threads > 0, "Proof pool must have at least one thread.");
+        self.proof_pool = Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .thread_name(|index| format!("diemdb-proof-{}", index))
+                .build()?,
+        );
+        Ok(())
+    }
+
+    /// Maps each of `items` to its proven counterpart, on the proof pool if it is enabled and
+    /// there are enough items. The results are in the order of `items`.
+    fn map_items_with_proofs<T, R, F>(&self, items: Vec<T>, f: F) -> Result<Vec<R>>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> Result<R> + Send + Sync,
+    {
+        match &self.proof_pool {
+            Some(pool) if items.len() >= MIN_PARALLEL_PROOF_ITEMS => {
+                pool.install(|| items.into_par_iter().map(f).collect())
+            }
+            _ => items.into_iter().map(f).collect(),
+        }
+    }
+
     fn wake_pruner(&self, latest_version: Version) {
         if let Some(pruner) = self.pruner.as_ref() {
             pruner.wake(latest_version)
         }
     }
+
+    fn wake_checkpointer(&self, latest_version: Version) {
+        if let Some(checkpointer) = self.checkpointer.as_ref() {
+            checkpointer.wake(latest_version)
+        }
+    }
+
+    /// Keeps account state at `version` readable, regardless of the prune window, until
+    /// `unpin_version` is called for it as many times as it was pinned.
+    pub fn pin_version(&self, version: Version) {
+        if let Some(pruner) = self.pruner.as_ref() {
+            pruner.pin(version)
+        }
+    }
+
+    pub fn unpin_version(&self, version: Version) {
+        if let Some(pruner) = self.pruner.as_ref() {
+            pruner.unpin(version)
+        }
+    }
+
+    /// Returns the account state at `version` without its proof, for trusted readers that don't
+    /// verify it, saving the cost of building it.
+    pub fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        gauged_api("get_account_state_by_version", || {
+            self.state_store
+                .get_account_state_by_version(address, version)
+        })
+    }
 }
 
 impl DbReader for DiemDB {
@@ -667,21 +858,22 @@ impl DbReader for DiemDB {
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
 
-            let txns = (start_version..start_version + limit)
-                .map(|version| self.transaction_store.get_transaction(version))
-                .collect::<Result<Vec<_>>>()?;
-            let txn_infos = (start_version..start_version + limit)
-                .map(|version| self.ledger_store.get_transaction_info(version))
-                .collect::<Result<Vec<_>>>()?;
-            let events = if fetch_events {
-                Some(
-                    (start_version..start_version + limit)
-                        .map(|version| self.event_store.get_events_by_version(version))
-                        .collect::<Result<Vec<_>>>()?,
-                )
-            } else {
-                None
-            };
+            let items = self.map_items_with_proofs(
+                (start_version..start_version + limit).collect(),
+                |version| {
+                    let txn = self.transaction_store.get_transaction(version)?;
+                    let txn_info = self.ledger_store.get_transaction_info(version)?;
+                    let events = if fetch_events {
+                        self.event_store.get_events_by_version(version)?
+                    } else {
+                        Vec::new()
+                    };
+                    Ok((txn, (txn_info, events)))
+                },
+            )?;
+            let (txns, infos_and_events): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+            let (txn_infos, events): (Vec<_>, Vec<_>) = infos_and_events.into_iter().unzip();
+            let events = if fetch_events { Some(events) } else { None };
             let proof = TransactionListProof::new(
                 self.ledger_store.get_transaction_range_proof(
                     Some(start_version),
@@ -700,6 +892,35 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<TransactionOutput>> {
+        gauged_api("get_transaction_outputs", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            if start_version > ledger_version || limit == 0 {
+                return Ok(Vec::new());
+            }
+
+            let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+
+            (start_version..start_version + limit)
+                .map(|version| {
+                    let txn_info = self.ledger_store.get_transaction_info(version)?;
+                    Ok(TransactionOutput::new(
+                        self.transaction_store.get_write_set(version)?,
+                        self.event_store.get_events_by_version(version)?,
+                        txn_info.gas_used(),
+                        TransactionStatus::Keep(txn_info.status().clone()),
+                    ))
+                })
+                .collect()
+        })
+    }
+
     fn get_events(
         &self,
         event_key: &EventKey,
@@ -750,6 +971,20 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_epoch_info(&self, epoch: u64) -> Result<EpochInfo> {
+        gauged_api("get_epoch_info", || self.ledger_store.get_epoch_info(epoch))
+    }
+
+    fn get_event_stats(
+        &self,
+        event_key: &EventKey,
+        ledger_version: Version,
+    ) -> Result<Option<EventStats>> {
+        gauged_api("get_event_stats", || {
+            self.event_store.get_event_stats(event_key, ledger_version)
+        })
+    }
+
     fn get_state_proof_with_ledger_info(
         &self,
         known_version: u64,
@@ -840,6 +1075,14 @@ impl DbReader for DiemDB {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
 
+    fn get_corrupted_version_ranges(&self) -> Result<Vec<Range<Version>>> {
+        Ok(self
+            .scrubber
+            .as_ref()
+            .map(Scrubber::corrupted_version_ranges)
+            .unwrap_or_default())
+    }
+
     fn get_account_state_with_proof_by_version(
         &self,
         address: AccountAddress,
@@ -935,6 +1178,7 @@ impl DbWriter for DiemDB {
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()> {
         gauged_api("save_transactions", || {
+            let start = Instant::now();
             let num_txns = txns_to_commit.len() as u64;
             // ledger_info_with_sigs could be None if we are doing state synchronization. In this case
             // txns_to_commit should not be empty. Otherwise it is okay to commit empty blocks.
@@ -956,9 +1200,10 @@ impl DbWriter for DiemDB {
 
             // Gather db mutations to `batch`.
             let mut cs = ChangeSet::new();
+            let mut stages = CommitStages::default();
 
             let new_root_hash =
-                self.save_transactions_impl(txns_to_commit, first_version, &mut cs)?;
+                self.save_transactions_impl(txns_to_commit, first_version, &mut cs, &mut stages)?;
 
             // If expected ledger info is provided, verify result root hash and save the ledger info.
             if let Some(x) = ledger_info_with_sigs {
@@ -979,7 +1224,17 @@ impl DbWriter for DiemDB {
                 let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
                     .with_label_values(&["save_transactions_commit"])
                     .start_timer();
-                self.commit(sealed_cs)?;
+                self.commit(sealed_cs, &mut stages)?;
+            }
+
+            let latency = start.elapsed();
+            if latency > SLOW_COMMIT_THRESHOLD {
+                warn!(
+                    version_range = ?(first_version..first_version + num_txns),
+                    latency_ms = latency.as_millis() as u64,
+                    stages = ?stages,
+                    "Slow commit."
+                );
             }
 
             // Once everything is successfully persisted, update the latest in-memory ledger info.
@@ -991,7 +1246,8 @@ impl DbWriter for DiemDB {
             }
 
             // Only increment counter if commit succeeds and there are at least one transaction written
-            // to the storage. That's also when we'd inform the pruner thread to work.
+            // to the storage. That's also when we'd inform the pruner and checkpointer threads to
+            // work.
             if num_txns > 0 {
                 let last_version = first_version + num_txns - 1;
                 DIEM_STORAGE_COMMITTED_TXNS.inc_by(num_txns);
@@ -1000,7 +1256,9 @@ impl DbWriter for DiemDB {
                     .expect("Counters should be bumped with transactions being saved.")
                     .bump_op_counters();
 
+                self.state_store.set_committed_version(last_version);
                 self.wake_pruner(last_version);
+                self.wake_checkpointer(last_version);
             }
 
             Ok(())
@@ -1008,6 +1266,27 @@ impl DbWriter for DiemDB {
     }
 }
 
+/// Time spent in each stage of saving transactions, in the order the stages ran.
+#[derive(Debug, Default)]
+struct CommitStages(Vec<(&'static str, Duration)>);
+
+impl CommitStages {
+    /// Runs `stage`, recording how long it took under `name`.
+    fn measure<T, F>(&mut self, name: &'static str, stage: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        let start = Instant::now();
+        let res = stage();
+        let elapsed = start.elapsed();
+        DIEM_STORAGE_SAVE_TRANSACTIONS_STAGE_SECONDS
+            .with_label_values(&[name])
+            .observe(elapsed.as_secs_f64());
+        self.0.push((name, elapsed));
+        res
+    }
+}
+
 // Convert requested range and order to a range in ascending order.
 fn get_first_seq_num_and_limit(order: Order, cursor: u64, limit: u64) -> Result<(u64, u64)> {
     ensure!(limit > 0, "limit should > 0, got {}", limit);
@@ -63,6 +63,38 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+pub static DIEM_STORAGE_LATEST_STATE_CHECKPOINT_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_latest_state_checkpoint_version",
+        "Latest version the full state tree was written at"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_SCRUBBER_NEXT_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_scrubber_next_version",
+        "Next version the storage scrubber checks"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_SCRUBBER_PASSES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_storage_scrubber_passes",
+        "Number of times the storage scrubber went through the whole ledger"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_SCRUBBER_CORRUPTED_VERSIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_scrubber_corrupted_versions",
+        "Number of versions the storage scrubber found corrupted"
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -87,6 +119,18 @@ pub static DIEM_STORAGE_OTHER_TIMERS_SECONDS: Lazy<HistogramVec> = Lazy::new(||
     .unwrap()
 });
 
+pub static DIEM_STORAGE_SAVE_TRANSACTIONS_STAGE_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "diem_storage_save_transactions_stage_seconds",
+        // metric description
+        "Time spent in each stage of saving transactions, in seconds",
+        // metric labels (dimensions)
+        &["stage"]
+    )
+    .unwrap()
+});
+
 /// Rocksdb metrics
 pub static DIEM_STORAGE_ROCKSDB_PROPERTIES: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
@@ -11,6 +11,7 @@ use crate::{
     },
     schema::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        state_delta::StateDeltaSchema,
     },
 };
 use anyhow::Result;
@@ -20,6 +21,7 @@ use diem_logger::prelude::*;
 use diem_types::transaction::Version;
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
 use std::{
+    collections::BTreeMap,
     iter::Peekable,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -45,6 +47,9 @@ pub(crate) struct Pruner {
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
+    /// Versions held readable by read snapshots, with the number of snapshots pinning each. The
+    /// worker is never asked to prune past the oldest of them.
+    pinned_versions: Mutex<BTreeMap<Version, usize>>,
     /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
     /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
     #[allow(dead_code)]
@@ -69,14 +74,29 @@ impl Pruner {
             historical_versions_to_keep,
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
+            pinned_versions: Mutex::new(BTreeMap::new()),
             worker_progress,
         }
     }
 
+    /// Keeps `version` readable until it is unpinned as many times as it was pinned.
+    pub fn pin(&self, version: Version) {
+        *self.pinned_versions.lock().entry(version).or_insert(0) += 1;
+    }
+
+    pub fn unpin(&self, version: Version) {
+        let mut pinned_versions = self.pinned_versions.lock();
+        if let Some(count) = pinned_versions.get_mut(&version) {
+            *count -= 1;
+            if *count == 0 {
+                pinned_versions.remove(&version);
+            }
+        }
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        if let Some(least_readable_version) = self.least_readable_version(latest_version) {
             self.command_sender
                 .lock()
                 .send(Command::Prune {
@@ -86,14 +106,26 @@ impl Pruner {
         }
     }
 
+    /// The version the worker should prune up to, keeping the pruning window and every pinned
+    /// version readable.
+    fn least_readable_version(&self, latest_version: Version) -> Option<Version> {
+        if latest_version <= self.historical_versions_to_keep {
+            return None;
+        }
+        let least_readable_version = latest_version - self.historical_versions_to_keep;
+        Some(match self.pinned_versions.lock().keys().next() {
+            Some(oldest_pinned) => least_readable_version.min(*oldest_pinned),
+            None => least_readable_version,
+        })
+    }
+
     /// (For tests only.) Notifies the worker thread and waits for it to finish its job by polling
     /// an internal counter.
     #[cfg(test)]
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        if let Some(least_readable_version) = self.least_readable_version(latest_version) {
             // Assuming no big pruning chunks will be issued by a test.
             const TIMEOUT: Duration = Duration::from_secs(10);
             let end = Instant::now() + TIMEOUT;
@@ -404,6 +436,12 @@ pub fn prune_state(
             .into_iter()
             .try_for_each(|index| batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key))?;
         db.write_schemas(batch)?;
+        // The new least readable version has the full tree, so the state deltas up to it are no
+        // longer replayed.
+        db.range_delete::<StateDeltaSchema, Version>(
+            &least_readable_version,
+            &(new_least_readable_version + 1), // end is exclusive
+        )?;
         Ok(new_least_readable_version)
     }
 }
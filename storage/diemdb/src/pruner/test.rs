@@ -100,6 +100,54 @@ fn test_pruner() {
     }
 }
 
+#[test]
+fn test_pruner_keeps_pinned_versions() {
+    let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
+    let value0 = AccountStateBlob::from(vec![0x01]);
+    let value1 = AccountStateBlob::from(vec![0x02]);
+    let value2 = AccountStateBlob::from(vec![0x03]);
+
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir).db;
+    let state_store = &StateStore::new(Arc::clone(&db));
+    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+
+    put_account_state_set(
+        &db,
+        state_store,
+        vec![(address, value0)],
+        0, /* version */
+    );
+    put_account_state_set(
+        &db,
+        state_store,
+        vec![(address, value1.clone())],
+        1, /* version */
+    );
+    put_account_state_set(
+        &db,
+        state_store,
+        vec![(address, value2.clone())],
+        2, /* version */
+    );
+
+    // A pinned version survives pruning past it.
+    pruner.pin(1);
+    pruner.wake_and_wait(2 /* latest_version */).unwrap();
+    assert!(state_store
+        .get_account_state_with_proof_by_version(address, 0)
+        .is_err());
+    verify_state_in_store(state_store, address, Some(&value1), 1);
+
+    // Once unpinned, the next wake prunes it.
+    pruner.unpin(1);
+    pruner.wake_and_wait(2 /* latest_version */).unwrap();
+    assert!(state_store
+        .get_account_state_with_proof_by_version(address, 1)
+        .is_err());
+    verify_state_in_store(state_store, address, Some(&value2), 2);
+}
+
 #[test]
 fn test_worker_quit_eagerly() {
     let address = AccountAddress::new([1u8; AccountAddress::LENGTH]);
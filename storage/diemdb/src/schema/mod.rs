@@ -15,10 +15,12 @@ pub(crate) mod jellyfish_merkle_node;
 pub(crate) mod ledger_counters;
 pub(crate) mod ledger_info;
 pub(crate) mod stale_node_index;
+pub(crate) mod state_delta;
 pub(crate) mod transaction;
 pub(crate) mod transaction_accumulator;
 pub(crate) mod transaction_by_account;
 pub(crate) mod transaction_info;
+pub(crate) mod write_set;
 
 use anyhow::{ensure, Result};
 use schemadb::ColumnFamilyName;
@@ -31,10 +33,12 @@ pub const EVENT_CF_NAME: ColumnFamilyName = "event";
 pub const JELLYFISH_MERKLE_NODE_CF_NAME: ColumnFamilyName = "jellyfish_merkle_node";
 pub const LEDGER_COUNTERS_CF_NAME: ColumnFamilyName = "ledger_counters";
 pub const STALE_NODE_INDEX_CF_NAME: ColumnFamilyName = "stale_node_index";
+pub const STATE_DELTA_CF_NAME: ColumnFamilyName = "state_delta";
 pub const TRANSACTION_CF_NAME: ColumnFamilyName = "transaction";
 pub const TRANSACTION_ACCUMULATOR_CF_NAME: ColumnFamilyName = "transaction_accumulator";
 pub const TRANSACTION_BY_ACCOUNT_CF_NAME: ColumnFamilyName = "transaction_by_account";
 pub const TRANSACTION_INFO_CF_NAME: ColumnFamilyName = "transaction_info";
+pub const WRITE_SET_CF_NAME: ColumnFamilyName = "write_set";
 
 fn ensure_slice_len_eq(data: &[u8], len: usize) -> Result<()> {
     ensure!(
@@ -82,6 +86,7 @@ pub mod fuzzing {
             decode_key_value!(super::ledger_counters::LedgerCountersSchema, data);
             decode_key_value!(super::ledger_info::LedgerInfoSchema, data);
             decode_key_value!(super::stale_node_index::StaleNodeIndexSchema, data);
+            decode_key_value!(super::state_delta::StateDeltaSchema, data);
             decode_key_value!(super::transaction::TransactionSchema, data);
             decode_key_value!(
                 super::transaction_accumulator::TransactionAccumulatorSchema,
@@ -92,6 +97,7 @@ pub mod fuzzing {
                 data
             );
             decode_key_value!(super::transaction_info::TransactionInfoSchema, data);
+            decode_key_value!(super::write_set::WriteSetSchema, data);
         }
     }
 }
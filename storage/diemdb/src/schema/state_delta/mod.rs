@@ -0,0 +1,53 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the account states each version changes, from
+//! which the state tree is replayed between checkpoints when state deltas are enabled.
+//!
+//! Serialized account states, keyed by the hashes of their addresses, identified by version.
+//! ```text
+//! |<--key-->|<------------value------------>|
+//! | version | [(account key, account state)] |
+//! ```
+//!
+//! `Version` is serialized in big endian so that records in RocksDB will be in order of it's
+//! numeric value.
+
+use crate::schema::{ensure_slice_len_eq, STATE_DELTA_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt};
+use diem_crypto::HashValue;
+use diem_types::{account_state_blob::AccountStateBlob, transaction::Version};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+type StateDelta = Vec<(HashValue, AccountStateBlob)>;
+
+define_schema!(StateDeltaSchema, Version, StateDelta, STATE_DELTA_CF_NAME);
+
+impl KeyCodec<StateDeltaSchema> for Version {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_key(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Version>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<StateDeltaSchema> for StateDelta {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::{collection::vec, prelude::*};
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        version in any::<Version>(),
+        state_delta in vec(any::<(HashValue, AccountStateBlob)>(), 1..10),
+    ) {
+        assert_encode_decode::<StateDeltaSchema>(&version, &state_delta);
+    }
+}
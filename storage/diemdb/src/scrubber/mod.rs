@@ -0,0 +1,258 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `Scrubber` which manages a thread re-reading committed data in the
+//! background at a limited rate, so corruption of data that's rarely read, e.g. by disk bitrot, is
+//! caught before it's served.
+//!
+//! Each version is checked against the hashes committed to by the ledger: the transaction info
+//! against its leaf in the transaction accumulator, and the transaction and its events against the
+//! transaction info. Reading the records also has RocksDB verify the checksums of their blocks.
+
+use crate::{
+    event_store::EventStore,
+    ledger_store::LedgerStore,
+    metrics::{
+        DIEM_STORAGE_SCRUBBER_CORRUPTED_VERSIONS, DIEM_STORAGE_SCRUBBER_NEXT_VERSION,
+        DIEM_STORAGE_SCRUBBER_PASSES,
+    },
+    schema::write_set::WriteSetSchema,
+    transaction_store::TransactionStore,
+};
+use accumulator::HashReader;
+use anyhow::{ensure, Result};
+use diem_crypto::hash::{CryptoHash, EventAccumulatorHasher};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::{
+    proof::{accumulator::InMemoryAccumulator, position::Position},
+    transaction::Version,
+};
+use schemadb::DB;
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+#[cfg(test)]
+mod test;
+
+/// The `Scrubber` is meant to be part of a `DiemDB` instance and runs in the background to detect
+/// corrupted data.
+///
+/// It creates a worker thread on construction and joins it on destruction. The worker goes through
+/// the ledger from its first version to the latest one over and over. Corrupted versions are
+/// remembered until the process restarts.
+#[derive(Debug)]
+pub(crate) struct Scrubber {
+    /// The worker thread handle, created upon Scrubber instance construction and joined upon its
+    /// destruction. It only becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// Tells the worker thread to quit.
+    quit_sender: Mutex<Sender<()>>,
+    /// Disjoint ranges of versions found corrupted, the start of each mapped to its end.
+    corrupted_versions: Arc<Mutex<BTreeMap<Version, Version>>>,
+}
+
+impl Scrubber {
+    /// Number of versions checked between two pauses of the worker.
+    const VERSIONS_PER_BATCH: u64 = 100;
+
+    /// Creates a worker thread checking at most `versions_per_sec` versions per second.
+    pub fn new(
+        db: Arc<DB>,
+        ledger_store: Arc<LedgerStore>,
+        transaction_store: Arc<TransactionStore>,
+        event_store: Arc<EventStore>,
+        versions_per_sec: u64,
+    ) -> Self {
+        let (quit_sender, quit_receiver) = channel();
+        let corrupted_versions = Arc::new(Mutex::new(BTreeMap::new()));
+        let worker = Worker {
+            db,
+            ledger_store,
+            transaction_store,
+            event_store,
+            quit_receiver,
+            pause: Duration::from_secs_f64(
+                Self::VERSIONS_PER_BATCH as f64 / versions_per_sec.max(1) as f64,
+            ),
+            corrupted_versions: Arc::clone(&corrupted_versions),
+            next_version: None,
+        };
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_scrubber".into())
+            .spawn(move || worker.work())
+            .expect("Creating scrubber thread should succeed.");
+
+        Self {
+            worker_thread: Some(worker_thread),
+            quit_sender: Mutex::new(quit_sender),
+            corrupted_versions,
+        }
+    }
+
+    /// Returns the ranges of versions found corrupted so far, in ascending order.
+    pub fn corrupted_version_ranges(&self) -> Vec<Range<Version>> {
+        self.corrupted_versions
+            .lock()
+            .iter()
+            .map(|(start, end)| *start..*end)
+            .collect()
+    }
+}
+
+impl Drop for Scrubber {
+    fn drop(&mut self) {
+        self.quit_sender
+            .lock()
+            .send(())
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+struct Worker {
+    db: Arc<DB>,
+    ledger_store: Arc<LedgerStore>,
+    transaction_store: Arc<TransactionStore>,
+    event_store: Arc<EventStore>,
+    quit_receiver: Receiver<()>,
+    /// How long to wait after each batch, limiting the load put on the DB.
+    pause: Duration,
+    corrupted_versions: Arc<Mutex<BTreeMap<Version, Version>>>,
+    /// Next version to check, `None` at the start of a pass.
+    next_version: Option<Version>,
+}
+
+impl Worker {
+    fn work(mut self) {
+        loop {
+            if let Err(error) = self.scrub_next_batch() {
+                warn!(error = ?error, "Storage scrubber failed to find versions to check.");
+            }
+            match self.quit_receiver.recv_timeout(self.pause) {
+                Err(RecvTimeoutError::Timeout) => (),
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+
+    fn scrub_next_batch(&mut self) -> Result<()> {
+        // Versions after the latest ledger info may still be being written.
+        let latest_version = match self.ledger_store.get_latest_ledger_info_option() {
+            Some(ledger_info) => ledger_info.ledger_info().version(),
+            None => return Ok(()),
+        };
+        let start_version = match self.next_version {
+            Some(version) if version <= latest_version => version,
+            next_version => {
+                if next_version.is_some() {
+                    DIEM_STORAGE_SCRUBBER_PASSES.inc();
+                }
+                // A DB restored from a backup doesn't go back to genesis.
+                match self.ledger_store.get_first_transaction_info_version()? {
+                    Some(version) => version,
+                    None => return Ok(()),
+                }
+            }
+        };
+        let end_version = (start_version + Scrubber::VERSIONS_PER_BATCH).min(latest_version + 1);
+
+        for version in start_version..end_version {
+            if let Err(error) = self.scrub_version(version) {
+                error!(
+                    version = version,
+                    error = ?error,
+                    "Storage scrubber found corrupted data."
+                );
+                let mut corrupted_versions = self.corrupted_versions.lock();
+                insert_version(&mut corrupted_versions, version);
+                DIEM_STORAGE_SCRUBBER_CORRUPTED_VERSIONS.set(
+                    corrupted_versions
+                        .iter()
+                        .map(|(start, end)| end - start)
+                        .sum::<u64>() as i64,
+                );
+            }
+        }
+        self.next_version = Some(end_version);
+        DIEM_STORAGE_SCRUBBER_NEXT_VERSION.set(end_version as i64);
+        Ok(())
+    }
+
+    fn scrub_version(&self, version: Version) -> Result<()> {
+        scrub_version(
+            &self.db,
+            &self.ledger_store,
+            &self.transaction_store,
+            &self.event_store,
+            version,
+        )
+    }
+}
+
+/// Checks the data of `version` against the hashes committed to by the ledger.
+fn scrub_version(
+    db: &DB,
+    ledger_store: &LedgerStore,
+    transaction_store: &TransactionStore,
+    event_store: &EventStore,
+    version: Version,
+) -> Result<()> {
+    let txn_info = ledger_store.get_transaction_info(version)?;
+    ensure!(
+        ledger_store.get(Position::from_leaf_index(version))? == txn_info.hash(),
+        "TransactionInfo doesn't match the transaction accumulator."
+    );
+
+    let txn = transaction_store.get_transaction(version)?;
+    ensure!(
+        txn.hash() == txn_info.transaction_hash(),
+        "Transaction doesn't match its TransactionInfo."
+    );
+
+    let event_hashes = event_store
+        .get_events_by_version(version)?
+        .iter()
+        .map(CryptoHash::hash)
+        .collect::<Vec<_>>();
+    ensure!(
+        InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes).root_hash()
+            == txn_info.event_root_hash(),
+        "Events don't match their TransactionInfo."
+    );
+
+    // Write sets aren't committed to by the ledger, nor restored from backups, but still have
+    // their blocks checked by RocksDB when read.
+    db.get::<WriteSetSchema>(&version)?;
+    Ok(())
+}
+
+/// Adds `version` to the disjoint `ranges`, merging it with the ranges right before and after it.
+fn insert_version(ranges: &mut BTreeMap<Version, Version>, version: Version) {
+    let mut start = version;
+    let mut end = version + 1;
+    if let Some((prev_start, prev_end)) = ranges.range(..=version).next_back() {
+        if *prev_end > version {
+            return;
+        }
+        if *prev_end == version {
+            start = *prev_start;
+        }
+    }
+    if let Some(next_end) = ranges.remove(&end) {
+        end = next_end;
+    }
+    ranges.insert(start, end);
+}
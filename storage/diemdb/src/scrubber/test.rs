@@ -0,0 +1,75 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    schema::transaction_info::TransactionInfoSchema, test_helper::arb_blocks_to_commit, DiemDB,
+};
+use diem_temppath::TempPath;
+use diem_types::transaction::TransactionInfo;
+use proptest::prelude::*;
+
+#[test]
+fn test_insert_version() {
+    let mut ranges = BTreeMap::new();
+    insert_version(&mut ranges, 5);
+    insert_version(&mut ranges, 6);
+    insert_version(&mut ranges, 9);
+    assert_eq!(ranges, vec![(5, 7), (9, 10)].into_iter().collect());
+
+    // Already known
+    insert_version(&mut ranges, 5);
+    assert_eq!(ranges, vec![(5, 7), (9, 10)].into_iter().collect());
+
+    // Extends the range after it
+    insert_version(&mut ranges, 8);
+    assert_eq!(ranges, vec![(5, 7), (8, 10)].into_iter().collect());
+
+    // Fills the gap between two ranges
+    insert_version(&mut ranges, 7);
+    assert_eq!(ranges, vec![(5, 10)].into_iter().collect());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_scrub_version(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let scrub = |version| {
+            scrub_version(
+                &db.db,
+                &db.ledger_store,
+                &db.transaction_store,
+                &db.event_store,
+                version,
+            )
+        };
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &input {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        for version in 0..cur_ver {
+            scrub(version).unwrap();
+        }
+
+        // Tamper with the transaction info of the last version
+        let version = cur_ver - 1;
+        let txn_info = db.ledger_store.get_transaction_info(version).unwrap();
+        let tampered_txn_info = TransactionInfo::new(
+            txn_info.transaction_hash(),
+            txn_info.state_root_hash(),
+            txn_info.event_root_hash(),
+            txn_info.gas_used() + 1,
+            txn_info.status().clone(),
+        );
+        db.db
+            .put::<TransactionInfoSchema>(&version, &tampered_txn_info)
+            .unwrap();
+        scrub(version).unwrap_err();
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `Checkpointer` which manages a thread writing the full state tree in the
+//! background when state deltas are enabled, and is meant to be triggered by other threads as
+//! they commit new data to the DB.
+
+use super::StateStore;
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_types::transaction::Version;
+use std::{
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// The `Checkpointer` is meant to be part of a `DiemDB` instance writing state deltas, and runs in
+/// the background to write the full state tree once enough versions went by since the latest
+/// checkpoint.
+///
+/// It creates a worker thread on construction and joins it on destruction.
+#[derive(Debug)]
+pub(crate) struct Checkpointer {
+    /// The worker thread handle, created upon Checkpointer instance construction and joined upon
+    /// its destruction. It only becomes `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// The sender side of the channel talking to the worker thread.
+    command_sender: Mutex<Sender<Command>>,
+}
+
+impl Checkpointer {
+    /// Creates a worker thread that waits on a channel for checkpointing commands.
+    pub fn new(state_store: Arc<StateStore>) -> Self {
+        let (command_sender, command_receiver) = channel();
+
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_checkpointer".into())
+            .spawn(move || Worker::new(state_store, command_receiver).work())
+            .expect("Creating checkpointer thread should succeed.");
+
+        Self {
+            worker_thread: Some(worker_thread),
+            command_sender: Mutex::new(command_sender),
+        }
+    }
+
+    /// Informs the worker thread of the latest committed version, for it to write a checkpoint if
+    /// one is due.
+    pub fn wake(&self, latest_version: Version) {
+        self.command_sender
+            .lock()
+            .send(Command::Checkpoint { latest_version })
+            .expect("Receiver should not destruct prematurely.");
+    }
+}
+
+impl Drop for Checkpointer {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+enum Command {
+    Quit,
+    Checkpoint { latest_version: Version },
+}
+
+struct Worker {
+    state_store: Arc<StateStore>,
+    command_receiver: Receiver<Command>,
+}
+
+impl Worker {
+    fn new(state_store: Arc<StateStore>, command_receiver: Receiver<Command>) -> Self {
+        Self {
+            state_store,
+            command_receiver,
+        }
+    }
+
+    fn work(self) {
+        while let Some(latest_version) = self.receive_commands() {
+            match self.state_store.write_checkpoint_if_due(latest_version) {
+                Ok(true) => debug!(
+                    version = latest_version,
+                    "[state checkpointer worker] Wrote checkpoint."
+                ),
+                Ok(false) => (),
+                Err(e) => error!(
+                    error = ?e,
+                    version = latest_version,
+                    "[state checkpointer worker] Error writing checkpoint.",
+                ),
+            }
+        }
+    }
+
+    /// Blocking waits for the next command, then receives all the pending ones, as only the
+    /// latest version matters.
+    ///
+    /// Returns `None` if `Command::Quit` is received, to break the outer loop and let `work()`
+    /// return.
+    fn receive_commands(&self) -> Option<Version> {
+        let mut command = self
+            .command_receiver
+            .recv()
+            .expect("Sender should not destruct prematurely.");
+        loop {
+            let latest_version = match command {
+                Command::Quit => return None,
+                Command::Checkpoint { latest_version } => latest_version,
+            };
+            command = match self.command_receiver.try_recv() {
+                Ok(command) => command,
+                Err(_) => return Some(latest_version),
+            };
+        }
+    }
+}
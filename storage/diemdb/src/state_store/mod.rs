@@ -2,21 +2,35 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! This file defines state store APIs that are related account state Merkle tree.
+//!
+//! By default, every version writes the tree nodes it creates. With state deltas enabled, versions
+//! only write the account states they change, and the `Checkpointer` writes the full tree every
+//! so many versions in the background. The tree at a version after its latest checkpoint is
+//! replayed in memory from the checkpoint and the deltas since. The replay of the latest versions
+//! is kept and extended as more versions are committed, so the checkpoint interval bounds the
+//! replaying reads and commits do.
 
+mod checkpointer;
 #[cfg(test)]
 mod state_store_test;
 
+pub(crate) use checkpointer::Checkpointer;
+
 use crate::{
     change_set::ChangeSet,
     ledger_counters::LedgerCounter,
+    metrics::DIEM_STORAGE_LATEST_STATE_CHECKPOINT_VERSION,
     schema::{
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        state_delta::StateDeltaSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{ensure, format_err, Result};
 use diem_crypto::HashValue;
+use diem_infallible::Mutex;
 use diem_jellyfish_merkle::{
-    node_type::NodeKey, JellyfishMerkleTree, TreeReader, TreeWriter, ROOT_NIBBLE_HEIGHT,
+    node_type::NodeKey, JellyfishMerkleTree, StaleNodeIndex, StaleNodeIndexBatch, TreeReader,
+    TreeUpdateBatch, TreeWriter, ROOT_NIBBLE_HEIGHT,
 };
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
@@ -25,20 +39,42 @@ use diem_types::{
     transaction::Version,
 };
 use schemadb::{SchemaBatch, DB};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 type LeafNode = diem_jellyfish_merkle::node_type::LeafNode<AccountStateBlob>;
 type Node = diem_jellyfish_merkle::node_type::Node<AccountStateBlob>;
 type NodeBatch = diem_jellyfish_merkle::NodeBatch<AccountStateBlob>;
+type StateDelta = Vec<(HashValue, AccountStateBlob)>;
 
 #[derive(Debug)]
 pub(crate) struct StateStore {
     db: Arc<DB>,
+    /// None writes the tree nodes of every version. Otherwise versions only write their state
+    /// deltas, and the full tree is checkpointed once this many versions went by.
+    checkpoint_interval: Option<u64>,
+    /// The tree replayed up to the latest version read or committed since its checkpoint.
+    tip: Mutex<Option<Arc<ReplayedTree>>>,
+    /// The tree of the versions being saved, which becomes the tip once they are committed.
+    pending_tip: Mutex<Option<Arc<ReplayedTree>>>,
 }
 
 impl StateStore {
     pub fn new(db: Arc<DB>) -> Self {
-        Self { db }
+        Self::new_with_checkpoint_interval(db, None)
+    }
+
+    /// With `checkpoint_interval`, versions only write their state deltas, the full tree being
+    /// written by `write_checkpoint_if_due` once that many versions went by.
+    pub fn new_with_checkpoint_interval(db: Arc<DB>, checkpoint_interval: Option<u64>) -> Self {
+        Self {
+            db,
+            checkpoint_interval,
+            tip: Mutex::new(None),
+            pending_tip: Mutex::new(None),
+        }
     }
 
     /// Get the account state blob given account address and root hash of state Merkle tree
@@ -50,7 +86,18 @@ impl StateStore {
         Option<AccountStateBlob>,
         SparseMerkleProof<AccountStateBlob>,
     )> {
-        JellyfishMerkleTree::new(self).get_with_proof(address.hash(), version)
+        let reader = self.tree_reader(version)?;
+        JellyfishMerkleTree::new(&reader).get_with_proof(address.hash(), version)
+    }
+
+    /// Get the account state blob given account address and version, without its proof.
+    pub fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<Option<AccountStateBlob>> {
+        let reader = self.tree_reader(version)?;
+        JellyfishMerkleTree::new(&reader).get(address.hash(), version)
     }
 
     /// Gets the proof that proves a range of accounts.
@@ -59,11 +106,16 @@ impl StateStore {
         rightmost_key: HashValue,
         version: Version,
     ) -> Result<SparseMerkleRangeProof> {
-        JellyfishMerkleTree::new(self).get_range_proof(rightmost_key, version)
+        let reader = self.tree_reader(version)?;
+        JellyfishMerkleTree::new(&reader).get_range_proof(rightmost_key, version)
     }
 
     /// Put the results generated by `account_state_sets` to `batch` and return the result root
     /// hashes for each write set.
+    ///
+    /// With state deltas enabled, only the account states are put, except for genesis, whose tree
+    /// is the first checkpoint. The tree nodes of the new versions are kept in memory instead, to
+    /// become the tip once `set_committed_version` is called.
     pub fn put_account_state_sets(
         &self,
         account_state_sets: Vec<HashMap<AccountAddress, AccountStateBlob>>,
@@ -80,8 +132,28 @@ impl StateStore {
             })
             .collect::<Vec<_>>();
 
+        let reader = match first_version.checked_sub(1) {
+            Some(base_version) => self.tree_reader(base_version)?,
+            None => StateTreeReader {
+                db: Arc::clone(&self.db),
+                replayed: None,
+            },
+        };
+        let put_deltas = self.checkpoint_interval.is_some() && first_version > 0;
+        if put_deltas {
+            (first_version..)
+                .zip(&blob_sets)
+                .try_for_each(|(version, blob_set)| {
+                    cs.batch.put::<StateDeltaSchema>(&version, blob_set)
+                })?;
+        } else if let Some(replayed) = &reader.replayed {
+            // The versions since the checkpoint only have their deltas written, e.g. as state
+            // deltas were disabled since. Write their tree nodes for the new ones to refer to.
+            replayed.add_to_batch(first_version, &mut cs.batch)?;
+        }
+
         let (new_root_hash_vec, tree_update_batch) =
-            JellyfishMerkleTree::new(self).put_value_sets(blob_sets, first_version)?;
+            JellyfishMerkleTree::new(&reader).put_value_sets(blob_sets, first_version)?;
 
         let num_versions = new_root_hash_vec.len();
         assert_eq!(num_versions, tree_update_batch.node_stats.len());
@@ -97,6 +169,24 @@ impl StateStore {
                 counter_bumps.bump(LedgerCounter::StaleStateNodes, stats.stale_nodes);
                 counter_bumps.bump(LedgerCounter::StaleStateLeaves, stats.stale_leaves);
             });
+
+        if put_deltas {
+            if num_versions > 0 {
+                let checkpoint = reader
+                    .replayed
+                    .as_ref()
+                    .map_or(first_version - 1, |replayed| replayed.checkpoint);
+                *self.pending_tip.lock() = Some(Arc::new(ReplayedTree::new(
+                    checkpoint,
+                    first_version,
+                    first_version + num_versions as u64 - 1,
+                    tree_update_batch,
+                    reader.replayed,
+                )));
+            }
+            return Ok(new_root_hash_vec);
+        }
+
         add_node_batch(&mut cs.batch, &tree_update_batch.node_batch)?;
 
         tree_update_batch
@@ -108,12 +198,208 @@ impl StateStore {
         Ok(new_root_hash_vec)
     }
 
+    /// Informs the store that the versions up to `version` are committed, for the tree replayed
+    /// while putting them to become the tip.
+    pub fn set_committed_version(&self, version: Version) {
+        let pending_tip = self.pending_tip.lock().take();
+        if let Some(pending_tip) = pending_tip {
+            if pending_tip.version == version {
+                self.set_tip(pending_tip);
+            }
+        }
+    }
+
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
-        JellyfishMerkleTree::new(self).get_root_hash(version)
+        let reader = self.tree_reader(version)?;
+        JellyfishMerkleTree::new(&reader).get_root_hash(version)
     }
 
     pub fn get_root_hash_option(&self, version: Version) -> Result<Option<HashValue>> {
-        JellyfishMerkleTree::new(self).get_root_hash_option(version)
+        let reader = self.tree_reader(version)?;
+        JellyfishMerkleTree::new(&reader).get_root_hash_option(version)
+    }
+
+    /// Gets a reader of the tree at `version`, replaying the versions since its checkpoint if the
+    /// tree isn't in the DB in full.
+    pub fn tree_reader(&self, version: Version) -> Result<StateTreeReader> {
+        Ok(StateTreeReader {
+            db: Arc::clone(&self.db),
+            replayed: self.get_replayed_tree(version)?,
+        })
+    }
+
+    /// Writes the full tree at `latest_version` if the latest checkpoint is at least the
+    /// checkpoint interval behind. Returns whether it did.
+    pub fn write_checkpoint_if_due(&self, latest_version: Version) -> Result<bool> {
+        let checkpoint_interval = match self.checkpoint_interval {
+            Some(checkpoint_interval) => checkpoint_interval,
+            None => return Ok(false),
+        };
+        let checkpoint = match self.get_checkpoint_version(latest_version)? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(false),
+        };
+        if latest_version - checkpoint < checkpoint_interval {
+            return Ok(false);
+        }
+        self.write_checkpoint(checkpoint, latest_version)?;
+        Ok(true)
+    }
+
+    /// Writes the full tree at `version` on top of the one at `checkpoint`, putting all the
+    /// account states changed in between at once, as if by a single version. Only the nodes of the
+    /// latest state of each changed account are written, once.
+    fn write_checkpoint(&self, checkpoint: Version, version: Version) -> Result<()> {
+        let state_deltas = self
+            .get_state_deltas(checkpoint + 1, version)?
+            .ok_or_else(|| {
+                format_err!(
+                    "Missing state deltas between checkpoint {} and version {}.",
+                    checkpoint,
+                    version
+                )
+            })?;
+        // Later versions override the account states earlier ones changed.
+        let account_states = state_deltas
+            .into_iter()
+            .flatten()
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let reader = CheckpointReader {
+            db: &self.db,
+            checkpoint_root_key: NodeKey::new_empty_path(checkpoint),
+            base_root_key: NodeKey::new_empty_path(version - 1),
+        };
+        let (root_hashes, tree_update_batch) =
+            JellyfishMerkleTree::new(&reader).put_value_sets(vec![account_states], version)?;
+        let expected_root_hash = self.get_root_hash(version)?;
+        ensure!(
+            root_hashes[0] == expected_root_hash,
+            "Checkpoint at version {} has root hash {:x}, replayed {:x}.",
+            version,
+            root_hashes[0],
+            expected_root_hash,
+        );
+
+        let mut batch = SchemaBatch::new();
+        add_node_batch(&mut batch, &tree_update_batch.node_batch)?;
+        tree_update_batch
+            .stale_node_index_batch
+            .into_iter()
+            .try_for_each(|index| {
+                let index = if index.node_key == reader.base_root_key {
+                    StaleNodeIndex {
+                        stale_since_version: index.stale_since_version,
+                        node_key: reader.checkpoint_root_key.clone(),
+                    }
+                } else {
+                    index
+                };
+                batch.put::<StaleNodeIndexSchema>(&index, &())
+            })?;
+        self.db.write_schemas(batch)?;
+        DIEM_STORAGE_LATEST_STATE_CHECKPOINT_VERSION.set(version as i64);
+
+        Ok(())
+    }
+
+    /// Gets the latest version up to `version` with tree nodes in the DB, i.e. with the full tree
+    /// unless pruned.
+    fn get_checkpoint_version(&self, version: Version) -> Result<Option<Version>> {
+        let mut iter = self
+            .db
+            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+        // The root sorts first among the nodes of a version, so this seeks to the root at
+        // `version` or otherwise to the last node before it.
+        iter.seek_for_prev(&(version, 1u8))?;
+        Ok(iter
+            .next()
+            .transpose()?
+            .map(|(node_key, _node)| node_key.version()))
+    }
+
+    /// Gets the tree replayed since the checkpoint of `version` up to at least `version`, if the
+    /// tree at `version` isn't in the DB in full but its checkpoint and deltas are.
+    fn get_replayed_tree(&self, version: Version) -> Result<Option<Arc<ReplayedTree>>> {
+        if self
+            .db
+            .get::<JellyfishMerkleNodeSchema>(&NodeKey::new_empty_path(version))?
+            .is_some()
+        {
+            return Ok(None);
+        }
+        let checkpoint = match self.get_checkpoint_version(version)? {
+            Some(checkpoint) if checkpoint < version => checkpoint,
+            _ => return Ok(None),
+        };
+
+        let tip = self.tip.lock().clone();
+        let base = match tip {
+            Some(tip) if tip.checkpoint == checkpoint && tip.version >= version => {
+                return Ok(Some(tip))
+            }
+            Some(tip) if tip.checkpoint == checkpoint => Some(tip),
+            _ => None,
+        };
+        let first_version = base.as_ref().map_or(checkpoint, |base| base.version) + 1;
+        let state_deltas = match self.get_state_deltas(first_version, version)? {
+            Some(state_deltas) => state_deltas,
+            None => return Ok(None),
+        };
+        let reader = StateTreeReader {
+            db: Arc::clone(&self.db),
+            replayed: base,
+        };
+        let (_root_hashes, tree_update_batch) =
+            JellyfishMerkleTree::new(&reader).put_value_sets(state_deltas, first_version)?;
+        let replayed = Arc::new(ReplayedTree::new(
+            checkpoint,
+            first_version,
+            version,
+            tree_update_batch,
+            reader.replayed,
+        ));
+        self.set_tip(Arc::clone(&replayed));
+
+        Ok(Some(replayed))
+    }
+
+    /// Makes `replayed` the tip if it is based on a later checkpoint, or goes further from the
+    /// same one.
+    fn set_tip(&self, replayed: Arc<ReplayedTree>) {
+        let mut tip = self.tip.lock();
+        let is_newer = tip.as_ref().map_or(true, |tip| {
+            replayed.checkpoint > tip.checkpoint
+                || (replayed.checkpoint == tip.checkpoint && replayed.version > tip.version)
+        });
+        if is_newer {
+            *tip = Some(replayed);
+        }
+    }
+
+    /// Gets the state deltas from `first_version` to `last_version`, or None if any is missing.
+    fn get_state_deltas(
+        &self,
+        first_version: Version,
+        last_version: Version,
+    ) -> Result<Option<Vec<StateDelta>>> {
+        let mut iter = self.db.iter::<StateDeltaSchema>(Default::default())?;
+        iter.seek(&first_version)?;
+        let mut state_deltas = Vec::new();
+        for (expected_version, res) in (first_version..=last_version).zip(iter) {
+            let (version, state_delta) = res?;
+            if version != expected_version {
+                return Ok(None);
+            }
+            state_deltas.push(state_delta);
+        }
+        if (state_deltas.len() as u64) < last_version - first_version + 1 {
+            return Ok(None);
+        }
+
+        Ok(Some(state_deltas))
     }
 
     /// Finds the rightmost leaf by scanning the entire DB.
@@ -143,70 +429,149 @@ impl StateStore {
     }
 }
 
-impl TreeReader<AccountStateBlob> for StateStore {
+/// The tree nodes of the versions from `first_version` to `version`, replayed from their state
+/// deltas on top of `parent`, or of the checkpoint.
+#[derive(Debug)]
+struct ReplayedTree {
+    checkpoint: Version,
+    first_version: Version,
+    version: Version,
+    nodes: NodeBatch,
+    stale_node_indices: StaleNodeIndexBatch,
+    num_layers: usize,
+    parent: Option<Arc<ReplayedTree>>,
+}
+
+impl ReplayedTree {
+    /// Beyond this many layers, they are merged into one, for node lookups to stay cheap.
+    const MAX_LAYERS: usize = 16;
+
+    fn new(
+        checkpoint: Version,
+        first_version: Version,
+        version: Version,
+        tree_update_batch: TreeUpdateBatch<AccountStateBlob>,
+        parent: Option<Arc<ReplayedTree>>,
+    ) -> Self {
+        let mut tree = Self {
+            checkpoint,
+            first_version,
+            version,
+            nodes: tree_update_batch.node_batch,
+            stale_node_indices: tree_update_batch.stale_node_index_batch,
+            num_layers: 1,
+            parent: None,
+        };
+        match parent {
+            Some(parent) if parent.num_layers < Self::MAX_LAYERS => {
+                tree.num_layers = parent.num_layers + 1;
+                tree.parent = Some(parent);
+            }
+            Some(parent) => {
+                for layer in parent.layers() {
+                    tree.first_version = layer.first_version;
+                    tree.nodes.extend(
+                        layer
+                            .nodes
+                            .iter()
+                            .map(|(node_key, node)| (node_key.clone(), node.clone())),
+                    );
+                    tree.stale_node_indices
+                        .extend(layer.stale_node_indices.iter().cloned());
+                }
+            }
+            None => (),
+        }
+        tree
+    }
+
+    /// Iterates from this layer down to the one replayed first since the checkpoint.
+    fn layers(&self) -> impl Iterator<Item = &ReplayedTree> {
+        std::iter::successors(Some(self), |layer| layer.parent.as_deref())
+    }
+
+    fn get_node(&self, node_key: &NodeKey) -> Option<&Node> {
+        self.layers()
+            .find(|layer| node_key.version() >= layer.first_version)
+            .filter(|layer| node_key.version() <= layer.version)
+            .and_then(|layer| layer.nodes.get(node_key))
+    }
+
+    /// Puts the nodes and stale node indices of the versions before `next_version` to `batch`.
+    fn add_to_batch(&self, next_version: Version, batch: &mut SchemaBatch) -> Result<()> {
+        for layer in self.layers() {
+            layer
+                .nodes
+                .iter()
+                .filter(|(node_key, _node)| node_key.version() < next_version)
+                .try_for_each(|(node_key, node)| {
+                    batch.put::<JellyfishMerkleNodeSchema>(node_key, node)
+                })?;
+            layer
+                .stale_node_indices
+                .iter()
+                .filter(|index| index.stale_since_version < next_version)
+                .try_for_each(|index| batch.put::<StaleNodeIndexSchema>(index, &()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the tree at a version, looking the nodes replayed since its checkpoint up in memory and
+/// the others in the DB.
+#[derive(Debug)]
+pub(crate) struct StateTreeReader {
+    db: Arc<DB>,
+    replayed: Option<Arc<ReplayedTree>>,
+}
+
+impl TreeReader<AccountStateBlob> for StateTreeReader {
     fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
-        self.db.get::<JellyfishMerkleNodeSchema>(node_key)
+        match self
+            .replayed
+            .as_ref()
+            .and_then(|replayed| replayed.get_node(node_key))
+        {
+            Some(node) => Ok(Some(node.clone())),
+            None => self.db.get::<JellyfishMerkleNodeSchema>(node_key),
+        }
     }
 
     fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
-        // Since everything has the same version during restore, we seek to the first node and get
-        // its version.
-        let mut iter = self
-            .db
-            .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-        iter.seek_to_first();
-        let version = match iter.next().transpose()? {
-            Some((node_key, _node)) => node_key.version(),
-            None => return Ok(None),
-        };
+        get_rightmost_leaf(&self.db)
+    }
+}
 
-        // The encoding of key and value in DB looks like:
-        //
-        // | <-------------- key --------------> | <- value -> |
-        // | version | num_nibbles | nibble_path |    node     |
-        //
-        // Here version is fixed. For each num_nibbles, there could be a range of nibble paths
-        // of the same length. If one of them is the rightmost leaf R, it must be at the end of this
-        // range. Otherwise let's assume the R is in the middle of the range, so we
-        // call the node at the end of this range X:
-        //   1. If X is leaf, then X.account_key() > R.account_key(), because the nibble path is a
-        //      prefix of the account key. So R is not the rightmost leaf.
-        //   2. If X is internal node, then X must be on the right side of R, so all its children's
-        //      account keys are larger than R.account_key(). So R is not the rightmost leaf.
-        //
-        // Given that num_nibbles ranges from 0 to ROOT_NIBBLE_HEIGHT, there are only
-        // ROOT_NIBBLE_HEIGHT+1 ranges, so we can just find the node at the end of each range and
-        // then pick the one with the largest account key.
-        let mut ret = None;
+/// Reads the tree at a checkpoint as if it were the tree at the version before the next one, for
+/// the next checkpoint to be put on top of it as a single version.
+struct CheckpointReader<'a> {
+    db: &'a DB,
+    checkpoint_root_key: NodeKey,
+    base_root_key: NodeKey,
+}
 
-        for num_nibbles in 1..=ROOT_NIBBLE_HEIGHT + 1 {
-            let mut iter = self
-                .db
-                .iter::<JellyfishMerkleNodeSchema>(Default::default())?;
-            // nibble_path is always non-empty except for the root, so if we use an empty nibble
-            // path as the seek key, the iterator will end up pointing to the end of the previous
-            // range.
-            let seek_key = (version, num_nibbles as u8);
-            iter.seek_for_prev(&seek_key)?;
-
-            if let Some((node_key, node)) = iter.next().transpose()? {
-                debug_assert_eq!(node_key.version(), version);
-                debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
-
-                if let Node::Leaf(leaf_node) = node {
-                    match ret {
-                        None => ret = Some((node_key, leaf_node)),
-                        Some(ref other) => {
-                            if leaf_node.account_key() > other.1.account_key() {
-                                ret = Some((node_key, leaf_node));
-                            }
-                        }
-                    }
-                }
-            }
+impl<'a> TreeReader<AccountStateBlob> for CheckpointReader<'a> {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        if *node_key == self.base_root_key {
+            self.db
+                .get::<JellyfishMerkleNodeSchema>(&self.checkpoint_root_key)
+        } else {
+            self.db.get::<JellyfishMerkleNodeSchema>(node_key)
         }
+    }
 
-        Ok(ret)
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
+        get_rightmost_leaf(self.db)
+    }
+}
+
+impl TreeReader<AccountStateBlob> for StateStore {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
+        self.db.get::<JellyfishMerkleNodeSchema>(node_key)
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode)>> {
+        get_rightmost_leaf(&self.db)
     }
 }
 
@@ -225,3 +590,60 @@ fn add_node_batch(batch: &mut SchemaBatch, node_batch: &NodeBatch) -> Result<()>
         .collect::<Result<Vec<_>>>()?;
     Ok(())
 }
+
+fn get_rightmost_leaf(db: &DB) -> Result<Option<(NodeKey, LeafNode)>> {
+    // Since everything has the same version during restore, we seek to the first node and get
+    // its version.
+    let mut iter = db.iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+    iter.seek_to_first();
+    let version = match iter.next().transpose()? {
+        Some((node_key, _node)) => node_key.version(),
+        None => return Ok(None),
+    };
+
+    // The encoding of key and value in DB looks like:
+    //
+    // | <-------------- key --------------> | <- value -> |
+    // | version | num_nibbles | nibble_path |    node     |
+    //
+    // Here version is fixed. For each num_nibbles, there could be a range of nibble paths
+    // of the same length. If one of them is the rightmost leaf R, it must be at the end of this
+    // range. Otherwise let's assume the R is in the middle of the range, so we
+    // call the node at the end of this range X:
+    //   1. If X is leaf, then X.account_key() > R.account_key(), because the nibble path is a
+    //      prefix of the account key. So R is not the rightmost leaf.
+    //   2. If X is internal node, then X must be on the right side of R, so all its children's
+    //      account keys are larger than R.account_key(). So R is not the rightmost leaf.
+    //
+    // Given that num_nibbles ranges from 0 to ROOT_NIBBLE_HEIGHT, there are only
+    // ROOT_NIBBLE_HEIGHT+1 ranges, so we can just find the node at the end of each range and
+    // then pick the one with the largest account key.
+    let mut ret = None;
+
+    for num_nibbles in 1..=ROOT_NIBBLE_HEIGHT + 1 {
+        let mut iter = db.iter::<JellyfishMerkleNodeSchema>(Default::default())?;
+        // nibble_path is always non-empty except for the root, so if we use an empty nibble
+        // path as the seek key, the iterator will end up pointing to the end of the previous
+        // range.
+        let seek_key = (version, num_nibbles as u8);
+        iter.seek_for_prev(&seek_key)?;
+
+        if let Some((node_key, node)) = iter.next().transpose()? {
+            debug_assert_eq!(node_key.version(), version);
+            debug_assert!(node_key.nibble_path().num_nibbles() < num_nibbles);
+
+            if let Node::Leaf(leaf_node) = node {
+                match ret {
+                    None => ret = Some((node_key, leaf_node)),
+                    Some(ref other) => {
+                        if leaf_node.account_key() > other.1.account_key() {
+                            ret = Some((node_key, leaf_node));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
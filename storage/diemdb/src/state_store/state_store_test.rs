@@ -9,7 +9,10 @@ use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state_blob::AccountStateBlob,
 };
-use proptest::{collection::hash_map, prelude::*};
+use proptest::{
+    collection::{hash_map, vec},
+    prelude::*,
+};
 
 fn put_account_state_set(
     store: &StateStore,
@@ -360,6 +363,124 @@ proptest! {
         let actual = store2.get_rightmost_leaf().unwrap();
         prop_assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn test_state_deltas(
+        account_state_sets in vec(hash_map(0u8..16, any::<AccountStateBlob>(), 1..5), 1..30),
+        checkpoint_interval in 1u64..10,
+    ) {
+        let addresses: Vec<_> = (0u8..16)
+            .map(|i| AccountAddress::new([i; AccountAddress::LENGTH]))
+            .collect();
+        let account_state_sets: Vec<HashMap<_, _>> = account_state_sets
+            .into_iter()
+            .map(|account_states| {
+                account_states
+                    .into_iter()
+                    .map(|(i, blob)| (addresses[i as usize], blob))
+                    .collect()
+            })
+            .collect();
+        let num_versions = account_state_sets.len() as Version;
+
+        let tmp_dir1 = TempPath::new();
+        let db1 = DiemDB::new_for_test(&tmp_dir1);
+        let store1 = &db1.state_store;
+
+        let tmp_dir2 = TempPath::new();
+        let db2 = DiemDB::new_for_test(&tmp_dir2);
+        let store2 = &StateStore::new_with_checkpoint_interval(
+            Arc::clone(&db2.db),
+            Some(checkpoint_interval),
+        );
+
+        let mut checkpoints = vec![0];
+        for (version, account_states) in (0..).zip(&account_state_sets) {
+            let root1 = put_account_states(store1, account_states.clone(), version);
+            let root2 = put_account_states(store2, account_states.clone(), version);
+            prop_assert_eq!(root1, root2);
+            if store2.write_checkpoint_if_due(version).unwrap() {
+                checkpoints.push(version);
+            }
+        }
+
+        // Only genesis and checkpoints write tree nodes.
+        for version in 0..num_versions {
+            let root_node = store2
+                .db
+                .get::<JellyfishMerkleNodeSchema>(&NodeKey::new_empty_path(version))
+                .unwrap();
+            prop_assert_eq!(root_node.is_some(), checkpoints.contains(&version));
+        }
+
+        // Every version reads the same, whether from the tip or from a store replaying it anew.
+        let store3 = &StateStore::new(Arc::clone(&db2.db));
+        for version in 0..num_versions {
+            let expected_root_hash = store1.get_root_hash(version).unwrap();
+            prop_assert_eq!(store2.get_root_hash(version).unwrap(), expected_root_hash);
+            prop_assert_eq!(store3.get_root_hash(version).unwrap(), expected_root_hash);
+            for address in &addresses {
+                let expected = store1
+                    .get_account_state_with_proof_by_version(*address, version)
+                    .unwrap();
+                let actual2 = store2
+                    .get_account_state_with_proof_by_version(*address, version)
+                    .unwrap();
+                prop_assert_eq!(&actual2, &expected);
+                let actual3 = store3
+                    .get_account_state_with_proof_by_version(*address, version)
+                    .unwrap();
+                prop_assert_eq!(&actual3, &expected);
+            }
+        }
+
+        // With state deltas disabled again, the replayed versions are written in full.
+        let account_states: HashMap<_, _> =
+            std::iter::once((addresses[0], AccountStateBlob::from(vec![0xff]))).collect();
+        let root1 = put_account_states(store1, account_states.clone(), num_versions);
+        let root3 = put_account_states(store3, account_states, num_versions);
+        prop_assert_eq!(root1, root3);
+        for version in *checkpoints.last().unwrap()..=num_versions {
+            prop_assert!(store3
+                .db
+                .get::<JellyfishMerkleNodeSchema>(&NodeKey::new_empty_path(version))
+                .unwrap()
+                .is_some());
+        }
+        verify_state_in_store(
+            store3,
+            addresses[0],
+            Some(&AccountStateBlob::from(vec![0xff])),
+            num_versions,
+            root1,
+        );
+
+        // Pruning up to the latest checkpoint deletes the state deltas it no longer needs.
+        let latest_checkpoint = *checkpoints.last().unwrap();
+        prune_stale_indices(store2, 0, latest_checkpoint, 1000);
+        for version in 1..num_versions {
+            let state_delta = store2.db.get::<StateDeltaSchema>(&version).unwrap();
+            prop_assert_eq!(state_delta.is_some(), version > latest_checkpoint);
+        }
+        prop_assert_eq!(
+            store2.get_root_hash(num_versions - 1).unwrap(),
+            store1.get_root_hash(num_versions - 1).unwrap()
+        );
+    }
+}
+
+fn put_account_states(
+    store: &StateStore,
+    account_states: HashMap<AccountAddress, AccountStateBlob>,
+    version: Version,
+) -> HashValue {
+    let mut cs = ChangeSet::new();
+    let root = store
+        .put_account_state_sets(vec![account_states], version, &mut cs)
+        .unwrap()[0];
+    store.db.write_schemas(cs.batch).unwrap();
+    store.set_committed_version(version);
+    root
 }
 
 // Initializes the state store by inserting one key at each version.
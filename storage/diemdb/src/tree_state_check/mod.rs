@@ -0,0 +1,196 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module recomputes the tree state `StartupInfo` is built from, i.e. the frozen subtree
+//! hashes of the transaction accumulator and the account state root hash at a version, from the
+//! transaction infos, and compares it with what's stored.
+//!
+//! `StartupInfo` reads the frozen subtree hashes from the stored accumulator nodes, so a corrupted
+//! node fails the node on startup. Every node up to the version is recomputed from its leaves and
+//! can be rewritten, once the recomputed accumulator is found to match the root hash signed in the
+//! latest ledger info.
+
+use crate::{
+    ledger_store::LedgerStore, schema::transaction_accumulator::TransactionAccumulatorSchema,
+    state_store::StateStore,
+};
+use accumulator::HashReader;
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+    HashValue,
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    proof::{
+        accumulator::InMemoryAccumulator,
+        position::{FrozenSubTreeIterator, Position},
+        MerkleTreeInternalNode,
+    },
+    transaction::Version,
+};
+use schemadb::{SchemaBatch, DB};
+
+#[cfg(test)]
+mod test;
+
+/// Transaction accumulator node whose stored hash differs from the one recomputed from its leaves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorruptedNode {
+    pub position: Position,
+    /// `None` if the node is missing
+    pub stored_hash: Option<HashValue>,
+    pub hash: HashValue,
+}
+
+/// Outcome of checking the tree state at a version.
+#[derive(Clone, Debug)]
+pub struct TreeStateCheck {
+    pub version: Version,
+    /// Frozen subtree hashes of the accumulator of the transactions up to `version`, recomputed
+    /// from their transaction infos
+    pub frozen_subtree_hashes: Vec<HashValue>,
+    pub corrupted_nodes: Vec<CorruptedNode>,
+    /// Whether the recomputed accumulator root hash matches the one in the latest ledger info,
+    /// `None` if `version` isn't the version of the latest ledger info
+    pub ledger_info_matches: Option<bool>,
+    /// State root hash committed to by the transaction info at `version`
+    pub state_root_hash: HashValue,
+    /// Root hash of the stored state tree at `version`, `None` if it was pruned
+    pub stored_state_root_hash: Option<HashValue>,
+    /// Whether the corrupted nodes were rewritten
+    pub repaired: bool,
+}
+
+impl TreeStateCheck {
+    /// Returns true if the stored tree state at `version` matches the recomputed one.
+    pub fn is_consistent(&self) -> bool {
+        self.corrupted_nodes.is_empty()
+            && self.ledger_info_matches != Some(false)
+            && self
+                .stored_state_root_hash
+                .map_or(true, |hash| hash == self.state_root_hash)
+    }
+}
+
+/// Checks the tree state at `version`, rewriting the corrupted accumulator nodes if `repair`.
+/// Nodes are only rewritten if the recomputed root hash matches the latest ledger info.
+pub(crate) fn check_tree_state(
+    db: &DB,
+    ledger_store: &LedgerStore,
+    state_store: &StateStore,
+    version: Version,
+    repair: bool,
+) -> Result<TreeStateCheck> {
+    // A DB restored from a backup doesn't go back to genesis, start from the frozen subtrees it
+    // was restored with.
+    let first_version = ledger_store
+        .get_first_transaction_info_version()?
+        .ok_or_else(|| format_err!("No transaction info in DB."))?;
+    ensure!(
+        version >= first_version,
+        "Version {} is before the first transaction info, at version {}.",
+        version,
+        first_version,
+    );
+    let mut frozen_subtrees = FrozenSubTreeIterator::new(first_version)
+        .map(|position| Ok((position, ledger_store.get(position)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut corrupted_nodes = vec![];
+    let mut check_node = |position: Position, hash: HashValue| -> Result<()> {
+        let stored_hash = db.get::<TransactionAccumulatorSchema>(&position)?;
+        if stored_hash != Some(hash) {
+            corrupted_nodes.push(CorruptedNode {
+                position,
+                stored_hash,
+                hash,
+            });
+        }
+        Ok(())
+    };
+    let num_txn_infos = version - first_version + 1;
+    let mut leaf_index = first_version;
+    for txn_info in ledger_store.get_transaction_info_iter(first_version, num_txn_infos as usize)? {
+        let mut position = Position::from_leaf_index(leaf_index);
+        let mut hash = txn_info?.hash();
+        check_node(position, hash)?;
+        // Merge with the frozen subtrees on the left, as the accumulator does on append.
+        while position.is_right_child() {
+            let (_, left_hash) = frozen_subtrees
+                .pop()
+                .expect("A right child must have a left sibling.");
+            hash =
+                MerkleTreeInternalNode::<TransactionAccumulatorHasher>::new(left_hash, hash).hash();
+            position = position.parent();
+            check_node(position, hash)?;
+        }
+        frozen_subtrees.push((position, hash));
+        leaf_index += 1;
+    }
+    ensure!(
+        leaf_index == version + 1,
+        "Missing transaction infos, found none at version {}.",
+        leaf_index,
+    );
+
+    let frozen_subtree_hashes: Vec<_> = frozen_subtrees.into_iter().map(|(_, hash)| hash).collect();
+    let ledger_info = ledger_store.get_latest_ledger_info()?;
+    let ledger_info_matches = if ledger_info.ledger_info().version() == version {
+        let root_hash = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            frozen_subtree_hashes.clone(),
+            version + 1,
+        )?
+        .root_hash();
+        Some(root_hash == ledger_info.ledger_info().transaction_accumulator_hash())
+    } else {
+        None
+    };
+
+    let state_root_hash = ledger_store
+        .get_transaction_info(version)?
+        .state_root_hash();
+    let stored_state_root_hash = state_store.get_root_hash_option(version)?;
+
+    let mut check = TreeStateCheck {
+        version,
+        frozen_subtree_hashes,
+        corrupted_nodes,
+        ledger_info_matches,
+        state_root_hash,
+        stored_state_root_hash,
+        repaired: false,
+    };
+    info!(
+        version = version,
+        num_corrupted_nodes = check.corrupted_nodes.len(),
+        ledger_info_matches = check.ledger_info_matches,
+        "Checked tree state."
+    );
+
+    if repair && !check.corrupted_nodes.is_empty() {
+        ensure!(
+            check.ledger_info_matches == Some(true),
+            "Can only repair the tree state at the version of the latest ledger info, once it \
+             matches the ledger info."
+        );
+        repair_nodes(db, &check)?;
+        check.repaired = true;
+    }
+    Ok(check)
+}
+
+/// Rewrites the corrupted accumulator nodes found by `check` with their recomputed hashes.
+fn repair_nodes(db: &DB, check: &TreeStateCheck) -> Result<()> {
+    let mut batch = SchemaBatch::new();
+    for node in &check.corrupted_nodes {
+        batch.put::<TransactionAccumulatorSchema>(&node.position, &node.hash)?;
+    }
+    db.write_schemas(batch)?;
+    warn!(
+        version = check.version,
+        num_repaired_nodes = check.corrupted_nodes.len(),
+        "Repaired corrupted transaction accumulator nodes."
+    );
+    Ok(())
+}
@@ -0,0 +1,59 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{test_helper::arb_blocks_to_commit, DiemDB};
+use diem_temppath::TempPath;
+use proptest::prelude::*;
+use storage_interface::DbReader;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_check_tree_state(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &input {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let version = cur_ver - 1;
+        let startup_info = db.get_startup_info().unwrap().unwrap();
+        let check = db.check_tree_state(None, false).unwrap();
+        prop_assert!(check.is_consistent());
+        prop_assert_eq!(check.version, version);
+        prop_assert_eq!(check.ledger_info_matches, Some(true));
+        prop_assert_eq!(
+            &check.frozen_subtree_hashes,
+            &startup_info.committed_tree_state.ledger_frozen_subtree_hashes
+        );
+
+        // Lose the leftmost frozen subtree root, which StartupInfo can't do without.
+        let position = FrozenSubTreeIterator::new(version + 1).next().unwrap();
+        let hash = db.ledger_store.get(position).unwrap();
+        let mut batch = SchemaBatch::new();
+        batch.delete::<TransactionAccumulatorSchema>(&position).unwrap();
+        db.db.write_schemas(batch).unwrap();
+        db.get_startup_info().unwrap_err();
+
+        let check = db.check_tree_state(None, false).unwrap();
+        prop_assert!(!check.is_consistent());
+        prop_assert_eq!(
+            check.corrupted_nodes,
+            vec![CorruptedNode {
+                position,
+                stored_hash: None,
+                hash,
+            }]
+        );
+        prop_assert!(!check.repaired);
+
+        prop_assert!(db.check_tree_state(None, true).unwrap().repaired);
+        prop_assert!(db.check_tree_state(None, false).unwrap().is_consistent());
+        prop_assert_eq!(db.get_startup_info().unwrap().unwrap(), startup_info);
+    }
+}
@@ -39,6 +39,16 @@ enum Command {
     },
     #[structopt(name = "list-accounts")]
     ListAccounts,
+    /// Recompute the tree state the node starts from and compare it with the stored one
+    #[structopt(name = "check-tree-state")]
+    CheckTreeState {
+        /// By default the version of the latest ledger info
+        #[structopt(long)]
+        version: Option<u64>,
+        /// Rewrite the corrupted transaction accumulator nodes
+        #[structopt(long)]
+        repair: bool,
+    },
 }
 
 /// Print out latest information stored in the DB.
@@ -152,6 +162,51 @@ fn list_accounts(db: &DiemDB) {
     info!("Total Accounts: {}", num_account);
 }
 
+fn check_tree_state(db: &DiemDB, version: Option<u64>, repair: bool) {
+    let check = db
+        .check_tree_state(version, repair)
+        .expect("Unable to check tree state");
+    println!("Version: {}", check.version);
+    println!("Frozen subtree hashes:");
+    for hash in &check.frozen_subtree_hashes {
+        println!("  {}", hash);
+    }
+    for node in &check.corrupted_nodes {
+        match node.stored_hash {
+            Some(stored_hash) => println!(
+                "Corrupted accumulator node {}: stored {}, expected {}",
+                node.position, stored_hash, node.hash
+            ),
+            None => println!(
+                "Missing accumulator node {}: expected {}",
+                node.position, node.hash
+            ),
+        }
+    }
+    match check.ledger_info_matches {
+        Some(true) => println!("Accumulator root hash matches the latest ledger info."),
+        Some(false) => println!(
+            "Accumulator root hash doesn't match the latest ledger info, transaction infos are \
+             corrupted."
+        ),
+        None => println!("Not the version of the latest ledger info, root hash not checked."),
+    }
+    println!("State root hash: {}", check.state_root_hash);
+    match check.stored_state_root_hash {
+        Some(hash) if hash == check.state_root_hash => println!("State tree matches."),
+        Some(hash) => println!("State tree root hash doesn't match: {}", hash),
+        None => println!("State tree pruned at this version, not checked."),
+    }
+    if check.repaired {
+        println!("Repaired {} nodes.", check.corrupted_nodes.len());
+    } else if !check.corrupted_nodes.is_empty() && !repair {
+        println!("Rerun with --repair to rewrite the corrupted nodes.");
+    }
+    if !check.is_consistent() && !check.repaired {
+        std::process::exit(1);
+    }
+}
+
 fn main() {
     ::diem_logger::DiemLogger::builder().build();
 
@@ -167,9 +222,11 @@ fn main() {
     let log_dir = tempfile::tempdir().expect("Unable to get temp dir");
     info!("Opening DB at: {:?}, log at {:?}", p, log_dir.path());
 
+    // Only a repair writes to the DB.
+    let readonly = !matches!(opt.cmd, Some(Command::CheckTreeState { repair: true, .. }));
     let db = DiemDB::open(
         p,
-        true, /* readonly */
+        readonly,
         None, /* pruner */
         RocksdbConfig::default(),
     )
@@ -190,6 +247,9 @@ fn main() {
             Command::ListAccounts => {
                 list_accounts(&db);
             }
+            Command::CheckTreeState { version, repair } => {
+                check_tree_state(&db, version, repair);
+            }
         }
     } else {
         print_head(&db).expect("Unable to read information from DB");
@@ -633,9 +633,54 @@ where
         Ok(SparseMerkleRangeProof::new(siblings))
     }
 
-    #[cfg(test)]
+    /// Returns the value (if applicable), without the cost of computing its merkle proof.
     pub fn get(&self, key: HashValue, version: Version) -> Result<Option<V>> {
-        Ok(self.get_with_proof(key, version)?.0)
+        let mut next_node_key = NodeKey::new_empty_path(version);
+        let nibble_path = NibblePath::new(key.to_vec());
+        let mut nibble_iter = nibble_path.nibbles();
+
+        // We limit the number of loops here deliberately to avoid potential cyclic graph bugs
+        // in the tree structure.
+        for nibble_depth in 0..=ROOT_NIBBLE_HEIGHT {
+            let next_node = self.reader.get_node(&next_node_key).map_err(|err| {
+                if nibble_depth == 0 {
+                    MissingRootError { version }.into()
+                } else {
+                    err
+                }
+            })?;
+            match next_node {
+                Node::Internal(internal_node) => {
+                    let queried_child_index = nibble_iter
+                        .next()
+                        .ok_or_else(|| format_err!("ran out of nibbles"))?;
+                    next_node_key = match internal_node.child(queried_child_index) {
+                        Some(child) => {
+                            next_node_key.gen_child_node_key(child.version, queried_child_index)
+                        }
+                        None => return Ok(None),
+                    };
+                }
+                Node::Leaf(leaf_node) => {
+                    return Ok(if leaf_node.account_key() == key {
+                        Some(leaf_node.value().clone())
+                    } else {
+                        None
+                    });
+                }
+                Node::Null => {
+                    if nibble_depth == 0 {
+                        return Ok(None);
+                    } else {
+                        bail!(
+                            "Non-root null node exists with node key {:?}",
+                            next_node_key
+                        );
+                    }
+                }
+            }
+        }
+        bail!("Jellyfish Merkle tree has cyclic graph inside.");
     }
 
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
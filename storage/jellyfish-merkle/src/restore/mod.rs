@@ -276,6 +276,12 @@ where
         Ok(partial_nodes)
     }
 
+    /// Returns the key of the last account restored, including by a previous restoration attempt
+    /// this one resumes.
+    pub fn previous_key_hash(&self) -> Option<HashValue> {
+        self.previous_leaf.as_ref().map(LeafNode::account_key)
+    }
+
     /// Restores a chunk of accounts. This function will verify that the given chunk is correct
     /// using the proof and root hash, then write things to storage. If the chunk is invalid, an
     /// error will be returned and nothing will be written to storage.
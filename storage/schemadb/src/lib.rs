@@ -40,6 +40,9 @@ pub type ReadOptions = rocksdb::ReadOptions;
 /// Type alias to `rocksdb::Options`.
 pub type Options = rocksdb::Options;
 
+/// Type alias to `rocksdb::DBPath`.
+pub type DBPath = rocksdb::DBPath;
+
 /// Type alias to improve readability.
 pub type ColumnFamilyName = &'static str;
 
@@ -383,6 +386,24 @@ impl DB {
 
     /// Writes a group of records wrapped in a [`SchemaBatch`].
     pub fn write_schemas(&self, batch: SchemaBatch) -> Result<()> {
+        self.write_schemas_opt(batch, &default_write_options())
+    }
+
+    /// Writes a group of records wrapped in a [`SchemaBatch`] without waiting for the write-ahead
+    /// log to reach the disk. The records are only persisted once [`DB::sync_wal`] returns.
+    pub fn write_schemas_unsynced(&self, batch: SchemaBatch) -> Result<()> {
+        self.write_schemas_opt(batch, &rocksdb::WriteOptions::default())
+    }
+
+    /// Syncs the write-ahead log to disk, persisting all records written so far.
+    pub fn sync_wal(&self) -> Result<()> {
+        // A synced write syncs the whole write-ahead log up to it, so an empty batch is enough.
+        self.inner
+            .write_opt(rocksdb::WriteBatch::default(), &default_write_options())?;
+        Ok(())
+    }
+
+    fn write_schemas_opt(&self, batch: SchemaBatch, opts: &rocksdb::WriteOptions) -> Result<()> {
         let _timer = DIEM_SCHEMADB_BATCH_COMMIT_LATENCY_SECONDS
             .with_label_values(&[self.name])
             .start_timer();
@@ -399,7 +420,7 @@ impl DB {
         }
         let serialized_size = db_batch.size_in_bytes();
 
-        self.inner.write_opt(db_batch, &default_write_options())?;
+        self.inner.write_opt(db_batch, opts)?;
 
         // Bump counters only after DB write succeeds.
         for (cf_name, rows) in &batch.rows {
@@ -3,10 +3,14 @@
 
 #![forbid(unsafe_code)]
 
-use anyhow::Result;
+mod state_view;
+
+pub use state_view::StorageStateView;
+
+use anyhow::{format_err, Result};
 use diem_crypto::HashValue;
-use diem_infallible::Mutex;
-use diem_logger::warn;
+use diem_infallible::{duration_since_epoch, Mutex};
+use diem_logger::{debug, warn};
 use diem_secure_net::NetworkClient;
 use diem_types::{
     account_address::AccountAddress,
@@ -18,24 +22,67 @@ use diem_types::{
     proof::{AccumulatorConsistencyProof, SparseMerkleProof},
     transaction::{TransactionListWithProof, TransactionToCommit, TransactionWithProof, Version},
 };
+use rand::Rng;
 use serde::de::DeserializeOwned;
-use std::net::SocketAddr;
+use std::{
+    cell::RefCell,
+    net::SocketAddr,
+    thread,
+    time::{Duration, Instant},
+};
 use storage_interface::{
-    DbReader, DbWriter, Error, GetAccountStateWithProofByVersionRequest, Order,
-    SaveTransactionsRequest, StartupInfo, StorageRequest, TreeState,
+    state_view::AccountWithProof, CreateReadSnapshotRequest, DbReader, DbWriter, Error,
+    GetAccountStateWithProofBySnapshotRequest, GetAccountStateWithProofByVersionRequest,
+    GetAccountStatesWithProofByVersionRequest, Order, ReadSnapshot, SaveTransactionsRequest,
+    StartupInfo, StorageRequest, TraceContext, TreeState, WithDeadlineRequest, WithTraceRequest,
 };
 
+thread_local! {
+    /// Trace ID of the requests sent from this thread, see `with_trace_id`.
+    static TRACE_ID: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Runs `f`, tagging the storage requests it sends from this thread with `trace_id`, so the
+/// storage service's logs of them can be found from the caller's trace.
+pub fn with_trace_id<T>(trace_id: String, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous trace ID, even if `f` panics.
+    struct Restore(Option<String>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TRACE_ID.with(|trace_id| *trace_id.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(TRACE_ID.with(|current| current.replace(Some(trace_id))));
+    f()
+}
+
 pub struct StorageClient {
     network_client: Mutex<NetworkClient>,
+    /// How long a request is waited for, in milliseconds.
+    timeout: u64,
+    /// Whether the account state reads of this client may be shed while commits are slow.
+    low_priority: bool,
 }
 
 impl StorageClient {
     pub fn new(server_address: &SocketAddr, timeout: u64) -> Self {
         Self {
             network_client: Mutex::new(NetworkClient::new("storage", *server_address, timeout)),
+            timeout,
+            low_priority: false,
         }
     }
 
+    /// Marks the account state reads of this client low priority, letting the service shed them
+    /// while commits are slow. Shed reads are retried after the delay the service asks for. Meant
+    /// for bulk readers such as exports; executors must not use it.
+    pub fn low_priority(mut self) -> Self {
+        self.low_priority = true;
+        self
+    }
+
     fn process_one_message(&self, input: &[u8]) -> Result<Vec<u8>, Error> {
         let mut client = self.network_client.lock();
         client.write(&input)?;
@@ -43,18 +90,69 @@ impl StorageClient {
     }
 
     fn request<T: DeserializeOwned>(&self, input: StorageRequest) -> std::result::Result<T, Error> {
-        let input_message = bcs::to_bytes(&input)?;
+        self.request_impl(input, false)
+    }
+
+    /// Sends a read request, which the service skips once this client stopped waiting for it
+    /// instead of building a response nobody reads. Every attempt gets a fresh deadline.
+    fn read_request<T: DeserializeOwned>(
+        &self,
+        input: StorageRequest,
+    ) -> std::result::Result<T, Error> {
+        self.request_impl(input, true)
+    }
+
+    fn request_impl<T: DeserializeOwned>(
+        &self,
+        input: StorageRequest,
+        with_deadline: bool,
+    ) -> std::result::Result<T, Error> {
+        // Generated once, so the service's logs of every attempt share it
+        let trace = TraceContext {
+            request_id: format!("{:x}", rand::thread_rng().gen::<u64>()),
+            trace_id: TRACE_ID.with(|trace_id| trace_id.borrow().clone()),
+        };
+        let start = Instant::now();
         let result = loop {
+            let request = if with_deadline {
+                let deadline_ms = duration_since_epoch().as_millis() as u64 + self.timeout;
+                StorageRequest::WithDeadlineRequest(Box::new(WithDeadlineRequest::new(
+                    deadline_ms,
+                    input.clone(),
+                )))
+            } else {
+                input.clone()
+            };
+            let input_message = bcs::to_bytes(&StorageRequest::WithTraceRequest(Box::new(
+                WithTraceRequest::new(trace.clone(), request),
+            )))?;
             match self.process_one_message(&input_message) {
                 Err(err) => warn!(
                     error = ?err,
                     request = ?input,
+                    request_id = trace.request_id,
                     "Failed to communicate with storage service.",
                 ),
-                Ok(value) => break value,
+                Ok(value) => match bcs::from_bytes(&value)? {
+                    Err(Error::ServiceBusy { retry_after_ms }) => {
+                        debug!(
+                            request_id = trace.request_id,
+                            retry_after_ms = retry_after_ms,
+                            "Storage service busy, retrying.",
+                        );
+                        thread::sleep(Duration::from_millis(retry_after_ms));
+                    }
+                    response => break response,
+                },
             }
         };
-        bcs::from_bytes(&result)?
+        debug!(
+            request_id = trace.request_id,
+            trace_id = trace.trace_id,
+            latency_ms = start.elapsed().as_millis() as u64,
+            "Storage request served.",
+        );
+        result
     }
 
     pub fn get_account_state_with_proof_by_version(
@@ -68,10 +166,38 @@ impl StorageClient {
         ),
         Error,
     > {
-        self.request(StorageRequest::GetAccountStateWithProofByVersionRequest(
-            Box::new(GetAccountStateWithProofByVersionRequest::new(
-                address, version,
-            )),
+        let mut request = GetAccountStateWithProofByVersionRequest::new(address, version);
+        request.low_priority = self.low_priority;
+        self.read_request(StorageRequest::GetAccountStateWithProofByVersionRequest(
+            Box::new(request),
+        ))
+    }
+
+    /// Reads the states of `addresses` at `version` in one round trip, in order.
+    pub fn get_account_states_with_proof_by_version(
+        &self,
+        addresses: Vec<AccountAddress>,
+        version: Version,
+    ) -> std::result::Result<Vec<AccountWithProof>, Error> {
+        let mut request = GetAccountStatesWithProofByVersionRequest::new(addresses, version);
+        request.low_priority = self.low_priority;
+        self.read_request(StorageRequest::GetAccountStatesWithProofByVersionRequest(
+            Box::new(request),
+        ))
+    }
+
+    /// Reads the account state without its proof, for trusted consumers that don't verify it.
+    /// Fails with `Error::SkipProofsNotAllowed` unless the service allows it.
+    pub fn get_account_state_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> std::result::Result<Option<AccountStateBlob>, Error> {
+        let mut request =
+            GetAccountStateWithProofByVersionRequest::new(address, version).skip_proofs();
+        request.low_priority = self.low_priority;
+        self.read_request(StorageRequest::GetAccountStateWithProofByVersionRequest(
+            Box::new(request),
         ))
     }
 
@@ -89,6 +215,52 @@ impl StorageClient {
             SaveTransactionsRequest::new(txns_to_commit, first_version, ledger_info_with_sigs),
         )))
     }
+
+    /// Pins the latest committed version for up to `ttl_ms` milliseconds, so that a sequence of
+    /// reads through the returned snapshot is consistent across concurrent commits.
+    pub fn create_read_snapshot(&self, ttl_ms: u64) -> std::result::Result<ReadSnapshot, Error> {
+        self.request(StorageRequest::CreateReadSnapshotRequest(Box::new(
+            CreateReadSnapshotRequest::new(ttl_ms),
+        )))
+    }
+
+    pub fn get_account_state_with_proof_by_snapshot(
+        &self,
+        snapshot: &ReadSnapshot,
+        address: AccountAddress,
+    ) -> std::result::Result<
+        (
+            Option<AccountStateBlob>,
+            SparseMerkleProof<AccountStateBlob>,
+        ),
+        Error,
+    > {
+        self.read_request(StorageRequest::GetAccountStateWithProofBySnapshotRequest(
+            Box::new(GetAccountStateWithProofBySnapshotRequest::new(
+                snapshot.id,
+                address,
+            )),
+        ))
+    }
+
+    /// Reads the account state through a snapshot without its proof, as
+    /// `get_account_state_by_version` does.
+    pub fn get_account_state_by_snapshot(
+        &self,
+        snapshot: &ReadSnapshot,
+        address: AccountAddress,
+    ) -> std::result::Result<Option<AccountStateBlob>, Error> {
+        self.read_request(StorageRequest::GetAccountStateWithProofBySnapshotRequest(
+            Box::new(
+                GetAccountStateWithProofBySnapshotRequest::new(snapshot.id, address).skip_proofs(),
+            ),
+        ))
+    }
+
+    /// Releases a snapshot before it expires, letting the pruner reclaim its version.
+    pub fn release_read_snapshot(&self, snapshot: ReadSnapshot) -> std::result::Result<(), Error> {
+        self.request(StorageRequest::ReleaseReadSnapshotRequest(snapshot.id))
+    }
 }
 
 impl DbReader for StorageClient {
@@ -111,9 +283,11 @@ impl DbReader for StorageClient {
 
     fn get_latest_account_state(
         &self,
-        _address: AccountAddress,
+        address: AccountAddress,
     ) -> Result<Option<AccountStateBlob>> {
-        unimplemented!()
+        let (version, _) = DbReader::get_latest_state_root(self)?;
+        let (blob, _proof) = Self::get_account_state_with_proof_by_version(self, address, version)?;
+        Ok(blob)
     }
 
     fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
@@ -190,7 +364,15 @@ impl DbReader for StorageClient {
     }
 
     fn get_latest_state_root(&self) -> Result<(u64, HashValue)> {
-        unimplemented!()
+        let startup_info = Self::get_startup_info(self)?
+            .ok_or_else(|| format_err!("No transaction committed in storage."))?;
+        let tree_state = startup_info
+            .synced_tree_state
+            .unwrap_or(startup_info.committed_tree_state);
+        Ok((
+            tree_state.num_transactions - 1,
+            tree_state.account_state_root_hash,
+        ))
     }
 
     fn get_latest_tree_state(&self) -> Result<TreeState> {
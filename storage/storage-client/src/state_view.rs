@@ -0,0 +1,141 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::StorageClient;
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::HashValue;
+use diem_infallible::RwLock;
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    account_address::{AccountAddress, HashAccountAddress},
+    account_state::AccountState,
+    transaction::Version,
+};
+use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+use storage_interface::{state_view::AccountWithProof, DbReader};
+
+/// Most accounts read by one request, so a large prefetch doesn't hold the service for long.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// `StorageStateView` is a read-only view of the state at a version of a remote storage service,
+/// for validating or executing transactions without a local copy of the DB, e.g. from tooling.
+/// Account states are verified against the state root of the version and cached, so every
+/// account is read at most once over the lifetime of the view.
+pub struct StorageStateView {
+    id: StateViewId,
+    client: Arc<StorageClient>,
+    version: Version,
+    state_root: HashValue,
+    /// Verified account states read so far, `None` for accounts that don't exist.
+    account_states: RwLock<HashMap<AccountAddress, Option<AccountState>>>,
+}
+
+impl StorageStateView {
+    /// Constructs a view of the state at `version`, whose root hash is `state_root`.
+    pub fn new(
+        id: StateViewId,
+        client: Arc<StorageClient>,
+        version: Version,
+        state_root: HashValue,
+    ) -> Self {
+        Self {
+            id,
+            client,
+            version,
+            state_root,
+            account_states: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Constructs a view of the latest state synced by the storage service.
+    pub fn latest(id: StateViewId, client: Arc<StorageClient>) -> Result<Self> {
+        let (version, state_root) = client.get_latest_state_root()?;
+        Ok(Self::new(id, client, version, state_root))
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Reads the states of the accounts in `addresses` not read yet, batching them into as few
+    /// requests as possible. Callers knowing which accounts a transaction touches save a round
+    /// trip per account.
+    pub fn prefetch(&self, addresses: &[AccountAddress]) -> Result<()> {
+        let mut missing: Vec<_> = {
+            let account_states = self.account_states.read();
+            addresses
+                .iter()
+                .filter(|address| !account_states.contains_key(address))
+                .copied()
+                .collect()
+        };
+        missing.sort();
+        missing.dedup();
+        for chunk in missing.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            let accounts = self
+                .client
+                .get_account_states_with_proof_by_version(chunk.to_vec(), self.version)?;
+            ensure!(
+                accounts.len() == chunk.len(),
+                "Expected {} account states, got {}.",
+                chunk.len(),
+                accounts.len()
+            );
+            for (address, account) in chunk.iter().zip(accounts) {
+                self.verify_and_cache(*address, account)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_and_cache(
+        &self,
+        address: AccountAddress,
+        (blob, proof): AccountWithProof,
+    ) -> Result<()> {
+        proof
+            .verify(self.state_root, address.hash(), blob.as_ref())
+            .map_err(|err| {
+                format_err!(
+                    "Proof is invalid for address {:?} with state root hash {:?}: {}",
+                    address,
+                    self.state_root,
+                    err
+                )
+            })?;
+        let account_state = blob.as_ref().map(AccountState::try_from).transpose()?;
+        self.account_states
+            .write()
+            .entry(address)
+            .or_insert(account_state);
+        Ok(())
+    }
+}
+
+impl StateView for StorageStateView {
+    fn id(&self) -> StateViewId {
+        self.id
+    }
+
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let address = access_path.address;
+        if !self.account_states.read().contains_key(&address) {
+            let account = self
+                .client
+                .get_account_state_with_proof_by_version(address, self.version)?;
+            self.verify_and_cache(address, account)?;
+        }
+        Ok(self
+            .account_states
+            .read()
+            .get(&address)
+            .and_then(|account_state| account_state.as_ref())
+            .and_then(|account_state| account_state.get(&access_path.path))
+            .cloned())
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
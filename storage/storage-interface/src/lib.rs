@@ -16,8 +16,8 @@ use diem_types::{
     move_resource::MoveStorage,
     proof::{definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof},
     transaction::{
-        TransactionInfo, TransactionListWithProof, TransactionToCommit, TransactionWithProof,
-        Version,
+        TransactionInfo, TransactionListWithProof, TransactionOutput, TransactionToCommit,
+        TransactionWithProof, Version,
     },
 };
 use itertools::Itertools;
@@ -25,12 +25,15 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom,
+    ops::Range,
     sync::Arc,
 };
 use thiserror::Error;
 
 #[cfg(any(feature = "testing", feature = "fuzzing"))]
 pub mod mock;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_storage;
 pub mod state_view;
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -93,6 +96,32 @@ impl StartupInfo {
     }
 }
 
+/// The validator set of an epoch and the ledger infos that mark its boundaries.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EpochInfo {
+    /// The validators of the epoch and their voting power.
+    pub epoch_state: EpochState,
+    /// The ledger info that ended the previous epoch and carries `epoch_state`.
+    pub start_ledger_info: LedgerInfoWithSignatures,
+    /// The ledger info that ended the epoch, signed by its validators. `None` if the epoch
+    /// hasn't ended yet.
+    pub end_ledger_info: Option<LedgerInfoWithSignatures>,
+}
+
+/// Aggregate statistics of the events emitted under an event key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventStats {
+    /// The number of events emitted under the key.
+    pub count: u64,
+    /// The sequence number and transaction version of the oldest event in the DB, which is the
+    /// first event emitted unless the DB was restored from a state snapshot.
+    pub first_sequence_number: u64,
+    pub first_version: Version,
+    /// The sequence number and transaction version of the latest event.
+    pub last_sequence_number: u64,
+    pub last_version: Version,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TreeState {
     pub num_transactions: LeafCount,
@@ -124,6 +153,18 @@ impl TreeState {
     }
 }
 
+/// Errors of a Diem DB that callers may handle, returned in an `anyhow::Error` by `DbReader` and
+/// `DbWriter` and carried as is by the storage service, so they can be matched on.
+#[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum DiemDbError {
+    /// A requested item is not found.
+    #[error("{0} not found.")]
+    NotFound(String),
+    /// Requested too many items.
+    #[error("Too many items requested: at least {0} requested, max is {1}")]
+    TooManyRequested(u64, u64),
+}
+
 #[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
     #[error("Service error: {:?}", error)]
@@ -131,12 +172,36 @@ pub enum Error {
 
     #[error("Serialization error: {0}")]
     SerializationError(String),
+
+    /// The service is shedding low priority reads while commits are slow; the request can be
+    /// retried.
+    #[error("Service busy committing transactions, retry after {retry_after_ms} ms")]
+    ServiceBusy { retry_after_ms: u64 },
+
+    /// The read snapshot was released or has expired.
+    #[error("Read snapshot {snapshot_id} not found, it may have expired")]
+    SnapshotNotFound { snapshot_id: u64 },
+
+    /// The client stopped waiting for the response before the request was served.
+    #[error("Deadline {deadline_ms} exceeded before the request was served")]
+    DeadlineExceeded { deadline_ms: u64 },
+
+    /// Reads skipping proofs aren't allowed by the service's config.
+    #[error("Reads without proofs are not allowed by this storage service")]
+    SkipProofsNotAllowed,
+
+    /// The DB failed the request with an error callers may handle.
+    #[error(transparent)]
+    DiemDbError(#[from] DiemDbError),
 }
 
 impl From<anyhow::Error> for Error {
     fn from(error: anyhow::Error) -> Self {
-        Self::ServiceError {
-            error: format!("{}", error),
+        match error.downcast::<DiemDbError>() {
+            Ok(error) => Self::DiemDbError(error),
+            Err(error) => Self::ServiceError {
+                error: format!("{}", error),
+            },
         }
     }
 }
@@ -185,6 +250,20 @@ pub trait DbReader: Send + Sync {
         fetch_events: bool,
     ) -> Result<TransactionListWithProof>;
 
+    /// Returns the outputs (write set, events, gas used and status) of up to `limit`
+    /// transactions starting at `start_version`, as of `ledger_version`.
+    ///
+    /// Write sets are only recorded for transactions committed through `save_transactions`, so
+    /// versions restored from a backup have none and the call fails for them.
+    fn get_transaction_outputs(
+        &self,
+        _start_version: Version,
+        _limit: u64,
+        _ledger_version: Version,
+    ) -> Result<Vec<TransactionOutput>> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -204,6 +283,16 @@ pub trait DbReader: Send + Sync {
         known_version: Option<u64>,
     ) -> Result<Vec<EventWithProof>>;
 
+    /// Returns the statistics of the events emitted under `event_key` by transactions with
+    /// versions no greater than `ledger_version`, or `None` if there's no such event.
+    fn get_event_stats(
+        &self,
+        _event_key: &EventKey,
+        _ledger_version: Version,
+    ) -> Result<Option<EventStats>> {
+        unimplemented!()
+    }
+
     /// See [`DiemDB::get_block_timestamp`].
     ///
     /// [`DiemDB::get_block_timestamp`]:
@@ -302,6 +391,31 @@ pub trait DbReader: Send + Sync {
         SparseMerkleProof<AccountStateBlob>,
     )>;
 
+    /// Returns the value stored under `path` in the state of `address` at `version`, or `None`
+    /// if the account or the resource does not exist, along with the account state it was read
+    /// from and its proof, as returned by `get_account_state_with_proof_by_version`.
+    ///
+    /// The state tree commits to an account's whole blob, so no proof can be given for an
+    /// individual resource: the value is verified by checking the proof of the account state
+    /// against the state root at `version`, then that the account state holds the value.
+    fn get_account_resource_by_version(
+        &self,
+        address: AccountAddress,
+        path: &[u8],
+        version: Version,
+    ) -> Result<(
+        Option<Vec<u8>>,
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        let (blob, proof) = self.get_account_state_with_proof_by_version(address, version)?;
+        let value = match &blob {
+            Some(blob) => AccountState::try_from(blob)?.get(path).cloned(),
+            None => None,
+        };
+        Ok((value, blob, proof))
+    }
+
     /// See [`DiemDB::get_latest_state_root`].
     ///
     /// [`DiemDB::get_latest_state_root`]:
@@ -315,6 +429,12 @@ pub trait DbReader: Send + Sync {
     /// Get the ledger info of the epoch that `known_version` belongs to.
     fn get_epoch_ending_ledger_info(&self, known_version: u64) -> Result<LedgerInfoWithSignatures>;
 
+    /// Returns the validator set of `epoch`, which must be at least 1, along with the ledger
+    /// infos that started it and, if it has ended, ended it.
+    fn get_epoch_info(&self, _epoch: u64) -> Result<EpochInfo> {
+        unimplemented!()
+    }
+
     /// Gets the latest transaction info.
     /// N.B. Unlike get_startup_info(), even if the db is not bootstrapped, this can return `Some`
     /// -- those from a db-restore run.
@@ -327,6 +447,12 @@ pub trait DbReader: Send + Sync {
     fn get_accumulator_root_hash(&self, _version: Version) -> Result<HashValue> {
         unimplemented!()
     }
+
+    /// Returns the ranges of versions found corrupted by the storage scrubber, empty if it found
+    /// none or isn't running.
+    fn get_corrupted_version_ranges(&self) -> Result<Vec<Range<Version>>> {
+        Ok(vec![])
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -448,6 +574,12 @@ pub enum StorageRequest {
     GetAccountStateWithProofByVersionRequest(Box<GetAccountStateWithProofByVersionRequest>),
     GetStartupInfoRequest,
     SaveTransactionsRequest(Box<SaveTransactionsRequest>),
+    CreateReadSnapshotRequest(Box<CreateReadSnapshotRequest>),
+    GetAccountStateWithProofBySnapshotRequest(Box<GetAccountStateWithProofBySnapshotRequest>),
+    ReleaseReadSnapshotRequest(u64),
+    WithDeadlineRequest(Box<WithDeadlineRequest>),
+    GetAccountStatesWithProofByVersionRequest(Box<GetAccountStatesWithProofByVersionRequest>),
+    WithTraceRequest(Box<WithTraceRequest>),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -457,12 +589,64 @@ pub struct GetAccountStateWithProofByVersionRequest {
 
     /// The version the query is based on.
     pub version: Version,
+
+    /// Whether to respond with the account state blob only, `Option<AccountStateBlob>`, instead
+    /// of the blob and its proof. Only served if the service allows it.
+    pub skip_proofs: bool,
+
+    /// Whether the service may shed the request with `Error::ServiceBusy` while commits are
+    /// slow. Set by bulk readers such as exports, never by executors.
+    pub low_priority: bool,
 }
 
 impl GetAccountStateWithProofByVersionRequest {
     /// Constructor.
     pub fn new(address: AccountAddress, version: Version) -> Self {
-        Self { address, version }
+        Self {
+            address,
+            version,
+            skip_proofs: false,
+            low_priority: false,
+        }
+    }
+
+    /// Asks for the account state blob only, without its proof.
+    pub fn skip_proofs(mut self) -> Self {
+        self.skip_proofs = true;
+        self
+    }
+
+    /// Lets the service shed the request while commits are slow.
+    pub fn low_priority(mut self) -> Self {
+        self.low_priority = true;
+        self
+    }
+}
+
+/// Reads the states of several accounts at the same version in one round trip. Responds with
+/// the account state blob and its proof of each address, in order.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct GetAccountStatesWithProofByVersionRequest {
+    pub addresses: Vec<AccountAddress>,
+    pub version: Version,
+    /// As for `GetAccountStateWithProofByVersionRequest`.
+    pub low_priority: bool,
+}
+
+impl GetAccountStatesWithProofByVersionRequest {
+    /// Constructor.
+    pub fn new(addresses: Vec<AccountAddress>, version: Version) -> Self {
+        Self {
+            addresses,
+            version,
+            low_priority: false,
+        }
+    }
+
+    /// Lets the service shed the request while commits are slow.
+    pub fn low_priority(mut self) -> Self {
+        self.low_priority = true;
+        self
     }
 }
 
@@ -487,3 +671,99 @@ impl SaveTransactionsRequest {
         }
     }
 }
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CreateReadSnapshotRequest {
+    /// How long the snapshot stays usable, in milliseconds. The service caps this at its
+    /// configured maximum.
+    pub ttl_ms: u64,
+}
+
+impl CreateReadSnapshotRequest {
+    /// Constructor.
+    pub fn new(ttl_ms: u64) -> Self {
+        Self { ttl_ms }
+    }
+}
+
+/// A handle to the latest committed ledger version, kept readable by the storage service until
+/// the snapshot is released or expires. Reads through the same snapshot see the same state no
+/// matter what is committed in between.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ReadSnapshot {
+    pub id: u64,
+    /// The ledger version all reads through this snapshot are served at.
+    pub version: Version,
+    /// How long the snapshot stays usable, after capping the requested ttl.
+    pub ttl_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct GetAccountStateWithProofBySnapshotRequest {
+    pub snapshot_id: u64,
+    pub address: AccountAddress,
+    /// Whether to respond with the account state blob only, as for
+    /// `GetAccountStateWithProofByVersionRequest`.
+    pub skip_proofs: bool,
+}
+
+impl GetAccountStateWithProofBySnapshotRequest {
+    /// Constructor.
+    pub fn new(snapshot_id: u64, address: AccountAddress) -> Self {
+        Self {
+            snapshot_id,
+            address,
+            skip_proofs: false,
+        }
+    }
+
+    /// Asks for the account state blob only, without its proof.
+    pub fn skip_proofs(mut self) -> Self {
+        self.skip_proofs = true;
+        self
+    }
+}
+
+/// A request the client stops waiting for at a deadline. Once the deadline has passed, the
+/// service answers with `Error::DeadlineExceeded` instead of serving the request, e.g. when it
+/// was queued behind a slow commit.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WithDeadlineRequest {
+    /// The deadline, in milliseconds since the Unix epoch.
+    pub deadline_ms: u64,
+    pub request: StorageRequest,
+}
+
+impl WithDeadlineRequest {
+    /// Constructor.
+    pub fn new(deadline_ms: u64, request: StorageRequest) -> Self {
+        Self {
+            deadline_ms,
+            request,
+        }
+    }
+}
+
+/// Identifies a request to the storage service, so that the service's logs and audit log of it
+/// can be correlated with what its caller observed.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TraceContext {
+    /// Unique to the request, and shared by its retries.
+    pub request_id: String,
+    /// ID of the caller's trace the request is part of, e.g. of a JSON-RPC request.
+    pub trace_id: Option<String>,
+}
+
+/// A request carrying its trace context. Storage clients send every request wrapped in one.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WithTraceRequest {
+    pub trace: TraceContext,
+    pub request: StorageRequest,
+}
+
+impl WithTraceRequest {
+    /// Constructor.
+    pub fn new(trace: TraceContext, request: StorageRequest) -> Self {
+        Self { trace, request }
+    }
+}
@@ -0,0 +1,1069 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an in-memory storage for tests.
+//!
+//! [`MockStorage`] implements [`DbReader`] and [`DbWriter`] the way `DiemDB` does, keeping the
+//! transaction accumulator, the event accumulators and the state tree in memory so that the
+//! proofs it returns verify against the ledger infos committed to it. Tests of the components
+//! built on top of storage can use it instead of a temporary RocksDB instance.
+
+use crate::{DbReader, DbWriter, EpochInfo, EventStats, Order, StartupInfo, TreeState};
+use accumulator::{HashReader, MerkleAccumulator};
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::{
+    hash::{
+        CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher,
+        SPARSE_MERKLE_PLACEHOLDER_HASH,
+    },
+    HashValue,
+};
+use diem_jellyfish_merkle::{
+    node_type::{LeafNode, Node, NodeKey},
+    JellyfishMerkleTree, TreeReader,
+};
+use diem_types::{
+    account_address::{AccountAddress, HashAccountAddress},
+    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    contract_event::{ContractEvent, EventWithProof},
+    epoch_change::EpochChangeProof,
+    event::EventKey,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{
+        position::Position, AccountStateProof, AccumulatorConsistencyProof, EventProof,
+        SparseMerkleProof, TransactionInfoWithProof, TransactionListProof,
+    },
+    transaction::{
+        Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
+        TransactionStatus, TransactionToCommit, TransactionWithProof, Version,
+    },
+    write_set::WriteSet,
+};
+use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap};
+
+type TransactionAccumulator = MerkleAccumulator<InMemoryAccumulator, TransactionAccumulatorHasher>;
+type EventAccumulator = MerkleAccumulator<InMemoryAccumulator, EventAccumulatorHasher>;
+
+/// Storage keeping the whole ledger in memory, see the module documentation.
+#[derive(Default)]
+pub struct MockStorage {
+    inner: RwLock<Ledger>,
+}
+
+impl MockStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Default)]
+struct Ledger {
+    transactions: Vec<Transaction>,
+    write_sets: Vec<WriteSet>,
+    events: Vec<Vec<ContractEvent>>,
+    event_accumulators: Vec<InMemoryAccumulator>,
+    transaction_infos: Vec<TransactionInfo>,
+    transaction_accumulator: InMemoryAccumulator,
+    state_tree: InMemoryTree,
+    /// Version of each user transaction, by sender and sequence number.
+    transactions_by_account: HashMap<(AccountAddress, u64), Version>,
+    /// Transaction version and index in the transaction of each event, by key and sequence number.
+    events_by_key: BTreeMap<(EventKey, u64), (Version, u64)>,
+    /// The latest ledger info of each epoch.
+    ledger_infos: BTreeMap<u64, LedgerInfoWithSignatures>,
+    /// The epoch ended by each epoch ending ledger info, by version.
+    epochs_by_version: BTreeMap<Version, u64>,
+    latest_ledger_info: Option<LedgerInfoWithSignatures>,
+}
+
+/// The frozen nodes of an accumulator, by position.
+#[derive(Default)]
+struct InMemoryAccumulator(HashMap<Position, HashValue>);
+
+impl HashReader for InMemoryAccumulator {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        self.0
+            .get(&position)
+            .copied()
+            .ok_or_else(|| format_err!("Accumulator error. {} does not exist.", position))
+    }
+}
+
+/// The nodes of the state tree at all versions.
+#[derive(Default)]
+struct InMemoryTree(HashMap<NodeKey, Node<AccountStateBlob>>);
+
+impl TreeReader<AccountStateBlob> for InMemoryTree {
+    fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node<AccountStateBlob>>> {
+        Ok(self.0.get(node_key).cloned())
+    }
+
+    fn get_rightmost_leaf(&self) -> Result<Option<(NodeKey, LeafNode<AccountStateBlob>)>> {
+        Ok(self
+            .0
+            .iter()
+            .filter_map(|(node_key, node)| match node {
+                Node::Leaf(leaf_node) => Some((node_key.clone(), leaf_node.clone())),
+                _ => None,
+            })
+            .max_by_key(|(_, leaf_node)| leaf_node.account_key()))
+    }
+}
+
+impl Ledger {
+    fn latest_ledger_info(&self) -> Result<&LedgerInfoWithSignatures> {
+        self.latest_ledger_info
+            .as_ref()
+            .ok_or_else(|| format_err!("Genesis LedgerInfo not found."))
+    }
+
+    fn latest_version(&self) -> Result<Version> {
+        Ok(self.latest_ledger_info()?.ledger_info().version())
+    }
+
+    fn num_transactions(&self) -> u64 {
+        self.transactions.len() as u64
+    }
+
+    fn transaction(&self, version: Version) -> Result<&Transaction> {
+        self.transactions
+            .get(version as usize)
+            .ok_or_else(|| format_err!("Txn {} not found.", version))
+    }
+
+    fn transaction_info(&self, version: Version) -> Result<&TransactionInfo> {
+        self.transaction_infos
+            .get(version as usize)
+            .ok_or_else(|| format_err!("No TransactionInfo at version {}", version))
+    }
+
+    fn transaction_events(&self, version: Version) -> Result<&Vec<ContractEvent>> {
+        self.events
+            .get(version as usize)
+            .ok_or_else(|| format_err!("Events of txn {} not found.", version))
+    }
+
+    fn transaction_info_with_proof(
+        &self,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<TransactionInfoWithProof> {
+        ensure!(
+            ledger_version < self.num_transactions(),
+            "Ledger version {} not committed.",
+            ledger_version
+        );
+        Ok(TransactionInfoWithProof::new(
+            TransactionAccumulator::get_proof(
+                &self.transaction_accumulator,
+                ledger_version + 1,
+                version,
+            )?,
+            self.transaction_info(version)?.clone(),
+        ))
+    }
+
+    fn transaction_with_proof(
+        &self,
+        version: Version,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionWithProof> {
+        let events = if fetch_events {
+            Some(self.transaction_events(version)?.clone())
+        } else {
+            None
+        };
+        Ok(TransactionWithProof {
+            version,
+            transaction: self.transaction(version)?.clone(),
+            events,
+            proof: self.transaction_info_with_proof(version, ledger_version)?,
+        })
+    }
+
+    fn tree_state(&self, num_transactions: u64) -> Result<TreeState> {
+        let state_root_hash = match num_transactions.checked_sub(1) {
+            Some(version) => self.transaction_info(version)?.state_root_hash(),
+            None => *SPARSE_MERKLE_PLACEHOLDER_HASH,
+        };
+        Ok(TreeState::new(
+            num_transactions,
+            TransactionAccumulator::get_frozen_subtree_hashes(
+                &self.transaction_accumulator,
+                num_transactions,
+            )?,
+            state_root_hash,
+        ))
+    }
+
+    fn epoch(&self, version: Version) -> u64 {
+        // If the last epoch ending at or before `version` ended before it, `version` belongs to
+        // the next epoch.
+        match self.epochs_by_version.range(..=version).next_back() {
+            Some((&end_version, &epoch)) if end_version < version => epoch + 1,
+            Some((_, &epoch)) => epoch,
+            None => 0,
+        }
+    }
+
+    fn ledger_info_of_epoch(&self, epoch: u64) -> Result<&LedgerInfoWithSignatures> {
+        self.ledger_infos
+            .get(&epoch)
+            .ok_or_else(|| format_err!("Last LedgerInfo of epoch {} not found.", epoch))
+    }
+
+    fn epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<Vec<LedgerInfoWithSignatures>> {
+        ensure!(
+            start_epoch <= end_epoch,
+            "Bad epoch range [{}, {})",
+            start_epoch,
+            end_epoch,
+        );
+        let latest_epoch = self.latest_ledger_info()?.ledger_info().next_block_epoch();
+        ensure!(
+            end_epoch <= latest_epoch,
+            "Unable to provide epoch change ledger info for still open epoch. asked upper bound: {}, last sealed epoch: {}",
+            end_epoch,
+            latest_epoch - 1,
+        );
+        (start_epoch..end_epoch)
+            .map(|epoch| {
+                let ledger_info = self.ledger_info_of_epoch(epoch)?;
+                ensure!(
+                    ledger_info.ledger_info().ends_epoch(),
+                    "Missing epoch ending ledger info for epoch {}",
+                    epoch
+                );
+                Ok(ledger_info.clone())
+            })
+            .collect()
+    }
+
+    fn account_state_with_proof_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        JellyfishMerkleTree::new(&self.state_tree).get_with_proof(address.hash(), version)
+    }
+
+    fn events_with_proofs(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithProof>> {
+        ensure!(limit > 0, "limit should > 0, got {}", limit);
+        let keyed = self
+            .events_by_key
+            .range((*event_key, 0)..=(*event_key, u64::max_value()))
+            .filter(|(_, (version, _))| *version <= ledger_version);
+        let locations: Vec<_> = match order {
+            Order::Ascending => keyed
+                .skip_while(|((_, seq), _)| *seq < start)
+                .take(limit as usize)
+                .collect(),
+            Order::Descending => {
+                let mut locations: Vec<_> = keyed
+                    .rev()
+                    .skip_while(|((_, seq), _)| *seq > start)
+                    .take(limit as usize)
+                    .collect();
+                // Like `DiemDB`, a descending query starting beyond the latest event is a bad
+                // request, unless it asks for the latest events with `u64::max_value()`.
+                if start != u64::max_value()
+                    && locations
+                        .first()
+                        .map_or(false, |((_, seq), _)| *seq < start)
+                {
+                    locations.clear();
+                }
+                locations.reverse();
+                locations
+            }
+        };
+
+        let mut events_with_proof = locations
+            .into_iter()
+            .map(|(&(_, seq), &(version, index))| {
+                let events = self.transaction_events(version)?;
+                let event = events[index as usize].clone();
+                ensure!(
+                    seq == event.sequence_number(),
+                    "Index broken, expected seq:{}, actual:{}",
+                    seq,
+                    event.sequence_number()
+                );
+                let event_proof = EventAccumulator::get_proof(
+                    &self.event_accumulators[version as usize],
+                    events.len() as u64,
+                    index,
+                )?;
+                let proof = EventProof::new(
+                    self.transaction_info_with_proof(version, ledger_version)?,
+                    event_proof,
+                );
+                Ok(EventWithProof::new(version, index, event, proof))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if order == Order::Descending {
+            events_with_proof.reverse();
+        }
+        Ok(events_with_proof)
+    }
+
+    fn event_stats(&self, event_key: &EventKey, ledger_version: Version) -> Option<EventStats> {
+        let keyed = || {
+            self.events_by_key
+                .range((*event_key, 0)..=(*event_key, u64::max_value()))
+        };
+        let (&(_, last_sequence_number), &(last_version, _)) = keyed()
+            .rev()
+            .find(|(_, (version, _))| *version <= ledger_version)?;
+        // Like `DiemDB`, the first event is the oldest one of the key.
+        let (&(_, first_sequence_number), &(first_version, _)) = keyed().next()?;
+        Some(EventStats {
+            count: last_sequence_number + 1,
+            first_sequence_number,
+            first_version,
+            last_sequence_number,
+            last_version,
+        })
+    }
+
+    /// Returns the block metadata transactions up to `ledger_version`, with their versions.
+    fn block_timestamps(
+        &self,
+        ledger_version: Version,
+    ) -> impl DoubleEndedIterator<Item = (Version, u64)> + '_ {
+        self.transactions
+            .iter()
+            .take(ledger_version.saturating_add(1) as usize)
+            .enumerate()
+            .filter_map(|(version, transaction)| match transaction {
+                Transaction::BlockMetadata(block_metadata) => {
+                    Some((version as Version, block_metadata.timestamp_usec()))
+                }
+                _ => None,
+            })
+    }
+}
+
+impl DbReader for MockStorage {
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof> {
+        let ledger_infos = self
+            .inner
+            .read()
+            .epoch_ending_ledger_infos(start_epoch, end_epoch)?;
+        Ok(EpochChangeProof::new(ledger_infos, /* more = */ false))
+    }
+
+    fn get_transactions(
+        &self,
+        start_version: Version,
+        batch_size: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        if start_version > ledger_version || batch_size == 0 {
+            return Ok(TransactionListWithProof::new_empty());
+        }
+        let ledger = self.inner.read();
+        let limit = std::cmp::min(batch_size, ledger_version - start_version + 1);
+        let versions = start_version..start_version + limit;
+
+        let transactions = versions
+            .clone()
+            .map(|version| ledger.transaction(version).cloned())
+            .collect::<Result<Vec<_>>>()?;
+        let transaction_infos = versions
+            .clone()
+            .map(|version| ledger.transaction_info(version).cloned())
+            .collect::<Result<Vec<_>>>()?;
+        let events = if fetch_events {
+            Some(
+                versions
+                    .map(|version| ledger.transaction_events(version).cloned())
+                    .collect::<Result<Vec<_>>>()?,
+            )
+        } else {
+            None
+        };
+        let proof = TransactionListProof::new(
+            TransactionAccumulator::get_range_proof(
+                &ledger.transaction_accumulator,
+                ledger_version + 1,
+                Some(start_version),
+                limit,
+            )?,
+            transaction_infos,
+        );
+        Ok(TransactionListWithProof::new(
+            transactions,
+            events,
+            Some(start_version),
+            proof,
+        ))
+    }
+
+    fn get_transaction_outputs(
+        &self,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<TransactionOutput>> {
+        if start_version > ledger_version || limit == 0 {
+            return Ok(Vec::new());
+        }
+        let ledger = self.inner.read();
+        let limit = std::cmp::min(limit, ledger_version - start_version + 1);
+        (start_version..start_version + limit)
+            .map(|version| {
+                let transaction_info = ledger.transaction_info(version)?;
+                Ok(TransactionOutput::new(
+                    ledger.write_sets[version as usize].clone(),
+                    ledger.transaction_events(version)?.clone(),
+                    transaction_info.gas_used(),
+                    TransactionStatus::Keep(transaction_info.status().clone()),
+                ))
+            })
+            .collect()
+    }
+
+    fn get_events(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        limit: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        Ok(self
+            .get_events_with_proofs(event_key, start, order, limit, None)?
+            .into_iter()
+            .map(|event| (event.transaction_version, event.event))
+            .collect())
+    }
+
+    fn get_events_with_proofs(
+        &self,
+        event_key: &EventKey,
+        start: u64,
+        order: Order,
+        limit: u64,
+        known_version: Option<u64>,
+    ) -> Result<Vec<EventWithProof>> {
+        let ledger = self.inner.read();
+        let ledger_version = match known_version {
+            Some(version) => version,
+            None => ledger.latest_version()?,
+        };
+        ledger.events_with_proofs(event_key, start, order, limit, ledger_version)
+    }
+
+    fn get_event_stats(
+        &self,
+        event_key: &EventKey,
+        ledger_version: Version,
+    ) -> Result<Option<EventStats>> {
+        Ok(self.inner.read().event_stats(event_key, ledger_version))
+    }
+
+    fn get_block_timestamp(&self, version: u64) -> Result<u64> {
+        // Genesis timestamp is 0
+        Ok(self
+            .inner
+            .read()
+            .block_timestamps(version)
+            .next_back()
+            .map_or(0, |(_, timestamp)| timestamp))
+    }
+
+    fn get_last_version_before_timestamp(
+        &self,
+        timestamp: u64,
+        ledger_version: Version,
+    ) -> Result<Version> {
+        let ledger = self.inner.read();
+        let mut blocks = ledger.block_timestamps(ledger_version).enumerate();
+        let (block_index, (version, _)) = blocks
+            .find(|(_, (_, block_timestamp))| *block_timestamp >= timestamp)
+            .ok_or_else(|| {
+                format_err!(
+                    "No new block found beyond timestamp {}, so can't determine the last version before it.",
+                    timestamp,
+                )
+            })?;
+        ensure!(
+            block_index > 0,
+            "First block started at or after timestamp {}.",
+            timestamp,
+        );
+        Ok(version - 1)
+    }
+
+    fn get_latest_account_state(
+        &self,
+        address: AccountAddress,
+    ) -> Result<Option<AccountStateBlob>> {
+        let ledger = self.inner.read();
+        let version = ledger.latest_version()?;
+        let (blob, _proof) = ledger.account_state_with_proof_by_version(address, version)?;
+        Ok(blob)
+    }
+
+    fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        Ok(self.inner.read().latest_ledger_info()?.clone())
+    }
+
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        let ledger = self.inner.read();
+        let latest_ledger_info = match &ledger.latest_ledger_info {
+            Some(ledger_info) => ledger_info.clone(),
+            None => return Ok(None),
+        };
+        // If the latest LedgerInfo doesn't carry a validator set, the last LedgerInfo of the
+        // previous epoch does.
+        let latest_epoch_state = match latest_ledger_info.ledger_info().next_epoch_state() {
+            Some(_) => None,
+            None => Some(
+                ledger
+                    .ledger_info_of_epoch(latest_ledger_info.ledger_info().epoch() - 1)?
+                    .ledger_info()
+                    .next_epoch_state()
+                    .cloned()
+                    .ok_or_else(|| {
+                        format_err!("Last LedgerInfo in epoch must carry next_epoch_state.")
+                    })?,
+            ),
+        };
+
+        let committed_num_transactions = latest_ledger_info.ledger_info().version() + 1;
+        let synced_num_transactions = ledger.num_transactions();
+        let committed_tree_state = ledger.tree_state(committed_num_transactions)?;
+        let synced_tree_state = if synced_num_transactions > committed_num_transactions {
+            Some(ledger.tree_state(synced_num_transactions)?)
+        } else {
+            None
+        };
+        Ok(Some(StartupInfo::new(
+            latest_ledger_info,
+            latest_epoch_state,
+            committed_tree_state,
+            synced_tree_state,
+        )))
+    }
+
+    fn get_txn_by_account(
+        &self,
+        address: AccountAddress,
+        seq_num: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Option<TransactionWithProof>> {
+        let ledger = self.inner.read();
+        ledger
+            .transactions_by_account
+            .get(&(address, seq_num))
+            .filter(|version| **version <= ledger_version)
+            .map(|version| ledger.transaction_with_proof(*version, ledger_version, fetch_events))
+            .transpose()
+    }
+
+    fn get_state_proof_with_ledger_info(
+        &self,
+        known_version: u64,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(EpochChangeProof, AccumulatorConsistencyProof)> {
+        let ledger = self.inner.read();
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        ensure!(
+            known_version <= ledger_info.version(),
+            "Client known_version {} larger than ledger version {}.",
+            known_version,
+            ledger_info.version(),
+        );
+        let known_epoch = ledger.epoch(known_version);
+        let epoch_change_proof = if known_epoch < ledger_info.next_block_epoch() {
+            EpochChangeProof::new(
+                ledger.epoch_ending_ledger_infos(known_epoch, ledger_info.next_block_epoch())?,
+                /* more = */ false,
+            )
+        } else {
+            EpochChangeProof::new(vec![], /* more = */ false)
+        };
+        let consistency_proof = TransactionAccumulator::get_consistency_proof(
+            &ledger.transaction_accumulator,
+            ledger_info.version() + 1,
+            known_version + 1,
+        )?;
+        Ok((epoch_change_proof, consistency_proof))
+    }
+
+    fn get_state_proof(
+        &self,
+        known_version: u64,
+    ) -> Result<(
+        LedgerInfoWithSignatures,
+        EpochChangeProof,
+        AccumulatorConsistencyProof,
+    )> {
+        let ledger_info_with_sigs = self.get_latest_ledger_info()?;
+        let (epoch_change_proof, consistency_proof) =
+            self.get_state_proof_with_ledger_info(known_version, ledger_info_with_sigs.clone())?;
+        Ok((ledger_info_with_sigs, epoch_change_proof, consistency_proof))
+    }
+
+    fn get_account_state_with_proof(
+        &self,
+        address: AccountAddress,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<AccountStateWithProof> {
+        ensure!(
+            version <= ledger_version,
+            "The queried version {} should be equal to or older than ledger version {}.",
+            version,
+            ledger_version
+        );
+        let ledger = self.inner.read();
+        let latest_version = ledger.latest_version()?;
+        ensure!(
+            ledger_version <= latest_version,
+            "ledger_version specified {} is greater than committed version {}.",
+            ledger_version,
+            latest_version
+        );
+        let transaction_info_with_proof =
+            ledger.transaction_info_with_proof(version, ledger_version)?;
+        let (blob, sparse_merkle_proof) =
+            ledger.account_state_with_proof_by_version(address, version)?;
+        Ok(AccountStateWithProof::new(
+            version,
+            blob,
+            AccountStateProof::new(transaction_info_with_proof, sparse_merkle_proof),
+        ))
+    }
+
+    fn get_account_state_with_proof_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        self.inner
+            .read()
+            .account_state_with_proof_by_version(address, version)
+    }
+
+    fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
+        let ledger = self.inner.read();
+        let version = ledger
+            .num_transactions()
+            .checked_sub(1)
+            .ok_or_else(|| format_err!("Genesis TransactionInfo not found."))?;
+        Ok((version, ledger.transaction_info(version)?.state_root_hash()))
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        let ledger = self.inner.read();
+        ledger.tree_state(ledger.num_transactions())
+    }
+
+    fn get_epoch_ending_ledger_info(&self, known_version: u64) -> Result<LedgerInfoWithSignatures> {
+        let ledger = self.inner.read();
+        let epoch = ledger.epoch(known_version);
+        let ledger_info = ledger.ledger_info_of_epoch(epoch)?;
+        ensure!(
+            ledger_info.ledger_info().version() == known_version
+                && ledger_info.ledger_info().ends_epoch(),
+            "Not an epoch change at version {}",
+            known_version
+        );
+        Ok(ledger_info.clone())
+    }
+
+    fn get_epoch_info(&self, epoch: u64) -> Result<EpochInfo> {
+        ensure!(epoch > 0, "EpochInfo only queryable for epoch >= 1.");
+        let ledger = self.inner.read();
+        let latest_epoch = ledger
+            .latest_ledger_info()?
+            .ledger_info()
+            .next_block_epoch();
+        ensure!(
+            epoch <= latest_epoch,
+            "Epoch {} hasn't started yet, latest epoch: {}",
+            epoch,
+            latest_epoch,
+        );
+        let start_ledger_info = ledger.ledger_info_of_epoch(epoch - 1)?.clone();
+        let epoch_state = start_ledger_info
+            .ledger_info()
+            .next_epoch_state()
+            .cloned()
+            .ok_or_else(|| format_err!("Last LedgerInfo in epoch must carry next_epoch_state."))?;
+        let end_ledger_info = if epoch < latest_epoch {
+            Some(ledger.ledger_info_of_epoch(epoch)?.clone())
+        } else {
+            None
+        };
+        Ok(EpochInfo {
+            epoch_state,
+            start_ledger_info,
+            end_ledger_info,
+        })
+    }
+
+    fn get_latest_transaction_info_option(&self) -> Result<Option<(Version, TransactionInfo)>> {
+        let ledger = self.inner.read();
+        Ok(ledger
+            .transaction_infos
+            .last()
+            .map(|transaction_info| (ledger.num_transactions() - 1, transaction_info.clone())))
+    }
+
+    fn get_accumulator_root_hash(&self, version: Version) -> Result<HashValue> {
+        TransactionAccumulator::get_root_hash(
+            &self.inner.read().transaction_accumulator,
+            version + 1,
+        )
+    }
+}
+
+impl DbWriter for MockStorage {
+    /// Commits `txns_to_commit` like `DiemDB::save_transactions`, all or nothing.
+    fn save_transactions(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        let num_txns = txns_to_commit.len() as u64;
+        ensure!(
+            ledger_info_with_sigs.is_some() || num_txns > 0,
+            "txns_to_commit is empty while ledger_info_with_sigs is None.",
+        );
+        if let Some(x) = ledger_info_with_sigs {
+            let claimed_last_version = x.ledger_info().version();
+            ensure!(
+                claimed_last_version + 1 == first_version + num_txns,
+                "Transaction batch not applicable: first_version {}, num_txns {}, last_version {}",
+                first_version,
+                num_txns,
+                claimed_last_version,
+            );
+        }
+
+        let mut ledger = self.inner.write();
+        ensure!(
+            first_version == ledger.num_transactions(),
+            "Transaction batch not applicable: first_version {}, {} transactions committed",
+            first_version,
+            ledger.num_transactions(),
+        );
+
+        // Compute everything the batch changes before changing anything.
+        let (state_root_hashes, node_batch) = if num_txns > 0 {
+            let (state_root_hashes, tree_update_batch) =
+                JellyfishMerkleTree::new(&ledger.state_tree).put_value_sets(
+                    txns_to_commit
+                        .iter()
+                        .map(|txn_to_commit| {
+                            txn_to_commit
+                                .account_states()
+                                .iter()
+                                .map(|(address, blob)| (address.hash(), blob.clone()))
+                                .collect()
+                        })
+                        .collect(),
+                    first_version,
+                )?;
+            (state_root_hashes, tree_update_batch.node_batch)
+        } else {
+            (vec![], BTreeMap::new())
+        };
+        let event_accumulators = txns_to_commit
+            .iter()
+            .map(|txn_to_commit| {
+                let event_hashes: Vec<_> = txn_to_commit
+                    .events()
+                    .iter()
+                    .map(CryptoHash::hash)
+                    .collect();
+                let mut accumulator = InMemoryAccumulator::default();
+                let (root_hash, writes) = EventAccumulator::append(&accumulator, 0, &event_hashes)?;
+                accumulator.0.extend(writes);
+                Ok((root_hash, accumulator))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let transaction_infos = txns_to_commit
+            .iter()
+            .zip(&state_root_hashes)
+            .zip(&event_accumulators)
+            .map(|((txn_to_commit, state_root_hash), (event_root_hash, _))| {
+                TransactionInfo::new(
+                    txn_to_commit.transaction().hash(),
+                    *state_root_hash,
+                    *event_root_hash,
+                    txn_to_commit.gas_used(),
+                    txn_to_commit.status().clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let transaction_hashes: Vec<_> = transaction_infos.iter().map(CryptoHash::hash).collect();
+        let (root_hash, writes) = TransactionAccumulator::append(
+            &ledger.transaction_accumulator,
+            first_version,
+            &transaction_hashes,
+        )?;
+        if let Some(x) = ledger_info_with_sigs {
+            let expected_root_hash = x.ledger_info().transaction_accumulator_hash();
+            ensure!(
+                root_hash == expected_root_hash,
+                "Root hash calculated doesn't match expected. {:?} vs {:?}",
+                root_hash,
+                expected_root_hash,
+            );
+        }
+
+        // Apply the batch.
+        ledger.state_tree.0.extend(node_batch);
+        ledger.transaction_accumulator.0.extend(writes);
+        for ((version, txn_to_commit), (_, event_accumulator)) in (first_version..)
+            .zip(txns_to_commit)
+            .zip(event_accumulators)
+        {
+            if let Transaction::UserTransaction(txn) = txn_to_commit.transaction() {
+                ledger
+                    .transactions_by_account
+                    .insert((txn.sender(), txn.sequence_number()), version);
+            }
+            for (index, event) in txn_to_commit.events().iter().enumerate() {
+                ledger.events_by_key.insert(
+                    (*event.key(), event.sequence_number()),
+                    (version, index as u64),
+                );
+            }
+            ledger
+                .transactions
+                .push(txn_to_commit.transaction().clone());
+            ledger.write_sets.push(txn_to_commit.write_set().clone());
+            ledger.events.push(txn_to_commit.events().to_vec());
+            ledger.event_accumulators.push(event_accumulator);
+        }
+        ledger.transaction_infos.extend(transaction_infos);
+        if let Some(x) = ledger_info_with_sigs {
+            let ledger_info = x.ledger_info();
+            if ledger_info.ends_epoch() {
+                ledger
+                    .epochs_by_version
+                    .insert(ledger_info.version(), ledger_info.epoch());
+            }
+            ledger.ledger_infos.insert(ledger_info.epoch(), x.clone());
+            ledger.latest_ledger_info = Some(x.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_types::{
+        block_info::BlockInfo, block_metadata::BlockMetadata, epoch_state::EpochState,
+        ledger_info::LedgerInfo, vm_status::KeptVMStatus,
+    };
+    use move_core_types::language_storage::TypeTag;
+
+    /// A block metadata transaction of `round`, emitting event `round` on `event_key` and
+    /// writing `round` to the state of `address`.
+    fn block(round: u64, address: AccountAddress, event_key: EventKey) -> TransactionToCommit {
+        let mut account_states = HashMap::new();
+        account_states.insert(address, AccountStateBlob::from(vec![round as u8]));
+        TransactionToCommit::new(
+            Transaction::BlockMetadata(BlockMetadata::new(
+                HashValue::zero(),
+                round,
+                round * 1000,
+                vec![],
+                address,
+            )),
+            account_states,
+            WriteSet::default(),
+            vec![ContractEvent::new(event_key, round, TypeTag::Bool, vec![])],
+            0,
+            KeptVMStatus::Executed,
+        )
+    }
+
+    fn ledger_info(epoch: u64, version: Version, root_hash: HashValue) -> LedgerInfoWithSignatures {
+        // Only the ledger info ending epoch 0 carries a validator set
+        let next_epoch_state = if epoch == 0 {
+            Some(EpochState::empty())
+        } else {
+            None
+        };
+        LedgerInfoWithSignatures::new(
+            LedgerInfo::new(
+                BlockInfo::new(
+                    epoch,
+                    version,
+                    HashValue::zero(),
+                    root_hash,
+                    version,
+                    version * 1000,
+                    next_epoch_state,
+                ),
+                HashValue::zero(),
+            ),
+            BTreeMap::new(),
+        )
+    }
+
+    /// Commits `blocks` and the ledger info of `epoch` at the last of them.
+    fn commit(
+        storage: &MockStorage,
+        blocks: &[TransactionToCommit],
+        epoch: u64,
+    ) -> LedgerInfoWithSignatures {
+        let first_version = storage
+            .get_latest_transaction_info_option()
+            .unwrap()
+            .map_or(0, |(version, _)| version + 1);
+        storage
+            .save_transactions(blocks, first_version, None)
+            .unwrap();
+        let version = first_version + blocks.len() as u64 - 1;
+        let li = ledger_info(
+            epoch,
+            version,
+            storage.get_accumulator_root_hash(version).unwrap(),
+        );
+        storage
+            .save_transactions(&[], version + 1, Some(&li))
+            .unwrap();
+        li
+    }
+
+    #[test]
+    fn test_proofs_verify() {
+        let storage = MockStorage::new();
+        let address = AccountAddress::random();
+        let event_key = EventKey::new_from_address(&address, 0);
+
+        let genesis_li = commit(&storage, &[block(0, address, event_key)], 0);
+        let blocks: Vec<_> = (1..4)
+            .map(|round| block(round, address, event_key))
+            .collect();
+        let li = commit(&storage, &blocks, 1);
+        assert_eq!(storage.get_latest_ledger_info().unwrap(), li);
+
+        storage
+            .get_transactions(0, 10, 3, true)
+            .unwrap()
+            .verify(li.ledger_info(), Some(0))
+            .unwrap();
+        storage
+            .get_account_state_with_proof(address, 2, 3)
+            .unwrap()
+            .verify(li.ledger_info(), 2, address)
+            .unwrap();
+        assert_eq!(
+            storage.get_latest_account_state(address).unwrap(),
+            Some(AccountStateBlob::from(vec![3]))
+        );
+
+        let events = storage
+            .get_events_with_proofs(&event_key, 1, Order::Ascending, 10, None)
+            .unwrap();
+        assert_eq!(events.len(), 3);
+        for (seq, event) in (1..).zip(&events) {
+            event
+                .verify(li.ledger_info(), &event_key, seq, seq, 0)
+                .unwrap();
+        }
+        let events = storage
+            .get_events(&event_key, 2, Order::Descending, 10)
+            .unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .map(|(version, _)| *version)
+                .collect::<Vec<_>>(),
+            vec![2, 1, 0]
+        );
+
+        assert_eq!(
+            storage.get_event_stats(&event_key, 2).unwrap(),
+            Some(EventStats {
+                count: 3,
+                first_sequence_number: 0,
+                first_version: 0,
+                last_sequence_number: 2,
+                last_version: 2,
+            })
+        );
+        assert_eq!(
+            storage
+                .get_event_stats(&EventKey::new_from_address(&address, 1), 3)
+                .unwrap(),
+            None
+        );
+        assert!(storage
+            .get_events(&event_key, 5, Order::Descending, 10)
+            .unwrap()
+            .is_empty());
+
+        let startup_info = storage.get_startup_info().unwrap().unwrap();
+        assert_eq!(startup_info.latest_ledger_info, li);
+        assert_eq!(startup_info.latest_epoch_state, Some(EpochState::empty()));
+        assert_eq!(startup_info.committed_tree_state.num_transactions, 4);
+        assert_eq!(storage.get_block_timestamp(3).unwrap(), 3000);
+        assert_eq!(
+            storage.get_last_version_before_timestamp(2500, 3).unwrap(),
+            2
+        );
+        assert_eq!(
+            storage.get_epoch_info(1).unwrap().start_ledger_info,
+            genesis_li
+        );
+        assert_eq!(storage.get_epoch_ending_ledger_info(0).unwrap(), genesis_li);
+    }
+
+    #[test]
+    fn test_save_transactions_rejects_wrong_root_hash() {
+        let storage = MockStorage::new();
+        let address = AccountAddress::random();
+        let li = ledger_info(0, 0, HashValue::zero());
+        storage
+            .save_transactions(
+                &[block(0, address, EventKey::new_from_address(&address, 0))],
+                0,
+                Some(&li),
+            )
+            .unwrap_err();
+
+        // Nothing of the failed batch was committed
+        assert!(storage
+            .get_latest_transaction_info_option()
+            .unwrap()
+            .is_none());
+        assert!(storage.get_startup_info().unwrap().is_none());
+    }
+}
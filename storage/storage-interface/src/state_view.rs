@@ -78,8 +78,18 @@ pub struct VerifiedStateView<'a> {
     /// ```
     account_to_state_cache: RwLock<HashMap<AccountAddress, AccountState>>,
     account_to_proof_cache: RwLock<HashMap<HashValue, SparseMerkleProof<AccountStateBlob>>>,
+
+    /// Account state blobs and proofs read from persistent storage ahead of time, used instead of
+    /// querying `reader` when they verify against `latest_persistent_state_root`.
+    prefetched_accounts: HashMap<AccountAddress, AccountWithProof>,
 }
 
+/// An account state blob, `None` if the account does not exist, and the proof of it.
+pub type AccountWithProof = (
+    Option<AccountStateBlob>,
+    SparseMerkleProof<AccountStateBlob>,
+);
+
 impl<'a> VerifiedStateView<'a> {
     /// Constructs a [`VerifiedStateView`] with persistent state view represented by
     /// `latest_persistent_state_root` plus a storage reader, and the in-memory speculative state
@@ -108,8 +118,64 @@ impl<'a> VerifiedStateView<'a> {
             speculative_state,
             account_to_state_cache: RwLock::new(HashMap::new()),
             account_to_proof_cache: RwLock::new(HashMap::new()),
+            prefetched_accounts: HashMap::new(),
         }
     }
+
+    /// Seeds the view with account states read from persistent storage ahead of time, saving
+    /// the round trip to `reader`. Accounts read at another state root fail verification and are
+    /// read from `reader` as usual.
+    pub fn with_prefetched_accounts(
+        mut self,
+        prefetched_accounts: HashMap<AccountAddress, AccountWithProof>,
+    ) -> Self {
+        self.prefetched_accounts = prefetched_accounts;
+        self
+    }
+
+    fn prefetched_account(
+        &self,
+        address: &AccountAddress,
+        address_hash: HashValue,
+    ) -> Option<AccountWithProof> {
+        let (blob, proof) = self.prefetched_accounts.get(address)?;
+        proof
+            .verify(
+                self.latest_persistent_state_root,
+                address_hash,
+                blob.as_ref(),
+            )
+            .ok()?;
+        Some((blob.clone(), proof.clone()))
+    }
+
+    fn read_account(
+        &self,
+        address: AccountAddress,
+        address_hash: HashValue,
+    ) -> Result<AccountWithProof> {
+        let (blob, proof) = match self.latest_persistent_version {
+            Some(version) => self
+                .reader
+                .get_account_state_with_proof_by_version(address, version)?,
+            None => (None, SparseMerkleProof::new(None, vec![])),
+        };
+        proof
+            .verify(
+                self.latest_persistent_state_root,
+                address_hash,
+                blob.as_ref(),
+            )
+            .map_err(|err| {
+                format_err!(
+                    "Proof is invalid for address {:?} with state root hash {:?}: {}",
+                    address,
+                    self.latest_persistent_state_root,
+                    err
+                )
+            })?;
+        Ok((blob, proof))
+    }
 }
 
 impl<'a> From<VerifiedStateView<'a>>
@@ -148,26 +214,10 @@ impl<'a> StateView for VerifiedStateView<'a> {
             // No matter it is in db or unknown, we have to query from db since even the
             // former case, we don't have the blob data but only its hash.
             AccountStatus::ExistsInDB | AccountStatus::Unknown => {
-                let (blob, proof) = match self.latest_persistent_version {
-                    Some(version) => self
-                        .reader
-                        .get_account_state_with_proof_by_version(address, version)?,
-                    None => (None, SparseMerkleProof::new(None, vec![])),
+                let (blob, proof) = match self.prefetched_account(&address, address_hash) {
+                    Some(prefetched) => prefetched,
+                    None => self.read_account(address, address_hash)?,
                 };
-                proof
-                    .verify(
-                        self.latest_persistent_state_root,
-                        address.hash(),
-                        blob.as_ref(),
-                    )
-                    .map_err(|err| {
-                        format_err!(
-                            "Proof is invalid for address {:?} with state root hash {:?}: {}",
-                            address,
-                            self.latest_persistent_state_root,
-                            err
-                        )
-                    })?;
 
                 // multiple threads may enter this code, and another thread might add
                 // an address before this one. Thus the insertion might return a None here.
@@ -0,0 +1,100 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Audit log of the requests served by the storage service, written as one JSON object per line
+//! and rotated by size.
+
+use anyhow::Result;
+use diem_config::config::StorageAuditLogConfig;
+use diem_types::transaction::Version;
+use serde::Serialize;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+/// A request served by the storage service.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord {
+    pub timestamp_usecs: u64,
+    /// Address of the client, if it is still connected
+    pub peer: Option<SocketAddr>,
+    pub request: &'static str,
+    /// First and last versions read or written, if the request touches any
+    pub versions: Option<(Version, Version)>,
+    pub request_bytes: usize,
+    pub response_bytes: usize,
+    /// ID the client gave the request, if it sent a trace context
+    pub request_id: Option<String>,
+    /// ID of the caller's trace the request is part of, if any
+    pub trace_id: Option<String>,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    max_file_bytes: u64,
+    max_rotated_files: usize,
+    file: File,
+    file_bytes: u64,
+}
+
+impl AuditLog {
+    /// Opens the log at `path`, appending to it if it exists.
+    pub fn open(path: PathBuf, config: &StorageAuditLogConfig) -> Result<Self> {
+        let file = open_append(&path)?;
+        let file_bytes = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_file_bytes: config.max_file_bytes,
+            max_rotated_files: config.max_rotated_files,
+            file,
+            file_bytes,
+        })
+    }
+
+    /// Appends `record`, rotating the log first if it would grow past its maximum size.
+    pub fn record(&mut self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if self.file_bytes > 0 && self.file_bytes + line.len() as u64 > self.max_file_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.file_bytes += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        if self.max_rotated_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = rotated_path(&self.path, self.max_rotated_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for index in (1..self.max_rotated_files).rev() {
+                let rotated = rotated_path(&self.path, index);
+                if rotated.exists() {
+                    fs::rename(&rotated, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.file = open_append(&self.path)?;
+        self.file_bytes = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// Path of the `index`th most recently rotated log.
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
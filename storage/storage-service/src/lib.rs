@@ -8,35 +8,200 @@
 //! The user of storage service is supposed to use it via client lib provided in
 //! [`storage-client`](../storage-client/index.html) instead of via
 
+use crate::{
+    audit_log::{AuditLog, AuditRecord},
+    metrics::DIEM_STORAGE_SERVICE_REQUEST_LATENCY_SECONDS,
+};
 use anyhow::Result;
 use diem_config::config::NodeConfig;
+use diem_infallible::{duration_since_epoch, Mutex};
 use diem_logger::prelude::*;
 use diem_secure_net::NetworkServer;
-use diem_types::{account_state_blob::AccountStateBlob, proof::SparseMerkleProof};
-use diemdb::DiemDB;
+use diem_types::{
+    account_state_blob::AccountStateBlob, proof::SparseMerkleProof, transaction::Version,
+};
+use diemdb::{metrics::DIEM_STORAGE_SAVE_TRANSACTIONS_STAGE_SECONDS, DiemDB};
 use std::{
+    collections::HashMap,
+    net::SocketAddr,
     sync::Arc,
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use storage_interface::{
+    state_view::AccountWithProof, CreateReadSnapshotRequest, DbReader, DbWriter, Error,
+    GetAccountStateWithProofByVersionRequest, ReadSnapshot, StartupInfo, StorageRequest,
+    TraceContext,
 };
-use storage_interface::{DbReader, DbWriter, Error, StartupInfo};
+
+mod audit_log;
+mod metrics;
+
+/// Requests served slower than this are logged as warnings, with their trace context.
+const SLOW_REQUEST_LATENCY: Duration = Duration::from_secs(1);
 
 /// Starts storage service with a given DiemDB
 pub fn start_storage_service_with_db(config: &NodeConfig, diem_db: Arc<DiemDB>) -> JoinHandle<()> {
-    let storage_service = StorageService { db: diem_db };
+    let audit_log = match (&config.storage.audit_log, config.storage.audit_log_path()) {
+        (Some(audit_log_config), Some(path)) => {
+            Some(AuditLog::open(path, audit_log_config).expect("Failed to open storage audit log."))
+        }
+        _ => None,
+    };
+    let storage_service = StorageService::new(
+        diem_db,
+        config
+            .storage
+            .read_shedding_commit_latency_ms
+            .map(Duration::from_millis),
+        Duration::from_millis(config.storage.max_read_snapshot_ttl_ms),
+        config.storage.allow_skip_proofs,
+        audit_log,
+    );
     storage_service.run(config)
 }
 
 #[derive(Clone)]
 pub struct StorageService {
     db: Arc<DiemDB>,
+    /// Commit latency above which low priority reads are shed, if load shedding is enabled.
+    read_shedding_commit_latency: Option<Duration>,
+    /// Low priority reads are rejected until this instant, set after a slow commit.
+    shed_reads_until: Arc<Mutex<Option<Instant>>>,
+    /// Longest a read snapshot stays usable.
+    max_read_snapshot_ttl: Duration,
+    /// Read snapshots handed out to clients and not yet released or expired.
+    read_snapshots: Arc<Mutex<ReadSnapshots>>,
+    /// Whether reads skipping proofs are served.
+    allow_skip_proofs: bool,
+    /// Log of the requests served, if enabled.
+    audit_log: Option<Arc<Mutex<AuditLog>>>,
+}
+
+/// A read snapshot handed out to a client. Its version stays pinned in the DB until the snapshot
+/// is released or expires.
+struct PinnedSnapshot {
+    version: Version,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct ReadSnapshots {
+    next_id: u64,
+    pinned: HashMap<u64, PinnedSnapshot>,
 }
 
 impl StorageService {
-    fn handle_message(&self, input_message: Vec<u8>) -> Result<Vec<u8>, Error> {
-        let input = bcs::from_bytes(&input_message)?;
+    fn new(
+        db: Arc<DiemDB>,
+        read_shedding_commit_latency: Option<Duration>,
+        max_read_snapshot_ttl: Duration,
+        allow_skip_proofs: bool,
+        audit_log: Option<AuditLog>,
+    ) -> Self {
+        Self {
+            db,
+            read_shedding_commit_latency,
+            shed_reads_until: Arc::new(Mutex::new(None)),
+            max_read_snapshot_ttl,
+            read_snapshots: Arc::new(Mutex::new(ReadSnapshots::default())),
+            allow_skip_proofs,
+            audit_log: audit_log.map(|audit_log| Arc::new(Mutex::new(audit_log))),
+        }
+    }
+
+    fn handle_message(
+        &self,
+        input_message: Vec<u8>,
+        peer: Option<SocketAddr>,
+    ) -> Result<Vec<u8>, Error> {
+        let decode_start = Instant::now();
+        let (trace, input) = match bcs::from_bytes(&input_message)? {
+            StorageRequest::WithTraceRequest(req) => (Some(req.trace), req.request),
+            input => (None, input),
+        };
+        if let storage_interface::StorageRequest::SaveTransactionsRequest(_) = input {
+            DIEM_STORAGE_SAVE_TRANSACTIONS_STAGE_SECONDS
+                .with_label_values(&["decode"])
+                .observe(decode_start.elapsed().as_secs_f64());
+        }
+
+        let timestamp_usecs = duration_since_epoch().as_micros() as u64;
+        let request = request_name(&input);
+        let versions = if self.audit_log.is_some() {
+            self.request_versions(&input)
+        } else {
+            None
+        };
+        let start = Instant::now();
+        let output = self.handle_request(input)?;
+        let latency = start.elapsed();
+        DIEM_STORAGE_SERVICE_REQUEST_LATENCY_SECONDS
+            .with_label_values(&[request])
+            .observe(latency.as_secs_f64());
+        log_request(request, trace.as_ref(), latency);
+        if self.audit_log.is_some() {
+            self.audit(&AuditRecord {
+                timestamp_usecs,
+                peer,
+                request,
+                versions,
+                request_bytes: input_message.len(),
+                response_bytes: output.len(),
+                request_id: trace.as_ref().map(|trace| trace.request_id.clone()),
+                trace_id: trace.and_then(|trace| trace.trace_id),
+            });
+        }
+        Ok(output)
+    }
+
+    /// First and last versions `request` reads or writes, for the audit log.
+    fn request_versions(&self, request: &StorageRequest) -> Option<(Version, Version)> {
+        match request {
+            StorageRequest::GetAccountStateWithProofByVersionRequest(req) => {
+                Some((req.version, req.version))
+            }
+            StorageRequest::SaveTransactionsRequest(req) => {
+                let num_txns = req.txns_to_commit.len() as u64;
+                (num_txns > 0).then(|| (req.first_version, req.first_version + num_txns - 1))
+            }
+            StorageRequest::GetAccountStateWithProofBySnapshotRequest(req) => self
+                .read_snapshots
+                .lock()
+                .pinned
+                .get(&req.snapshot_id)
+                .map(|snapshot| (snapshot.version, snapshot.version)),
+            StorageRequest::WithDeadlineRequest(req) => self.request_versions(&req.request),
+            StorageRequest::WithTraceRequest(req) => self.request_versions(&req.request),
+            StorageRequest::GetAccountStatesWithProofByVersionRequest(req) => {
+                Some((req.version, req.version))
+            }
+            StorageRequest::GetStartupInfoRequest
+            | StorageRequest::CreateReadSnapshotRequest(_)
+            | StorageRequest::ReleaseReadSnapshotRequest(_) => None,
+        }
+    }
+
+    /// Appends `record` to the audit log. Failing to do so doesn't fail the request.
+    fn audit(&self, record: &AuditRecord) {
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(e) = audit_log.lock().record(record) {
+                warn!(
+                    error = ?e,
+                    "Failed to write storage audit log.",
+                );
+            }
+        }
+    }
+
+    fn handle_request(&self, input: storage_interface::StorageRequest) -> Result<Vec<u8>, Error> {
         let output = match input {
             storage_interface::StorageRequest::GetAccountStateWithProofByVersionRequest(req) => {
-                bcs::to_bytes(&self.get_account_state_with_proof_by_version(&req))
+                if req.skip_proofs {
+                    bcs::to_bytes(&self.get_account_state_by_version(&req))
+                } else {
+                    bcs::to_bytes(&self.get_account_state_with_proof_by_version(&req))
+                }
             }
             storage_interface::StorageRequest::GetStartupInfoRequest => {
                 bcs::to_bytes(&self.get_startup_info())
@@ -44,6 +209,31 @@ impl StorageService {
             storage_interface::StorageRequest::SaveTransactionsRequest(req) => {
                 bcs::to_bytes(&self.save_transactions(&req))
             }
+            storage_interface::StorageRequest::CreateReadSnapshotRequest(req) => {
+                bcs::to_bytes(&self.create_read_snapshot(&req))
+            }
+            storage_interface::StorageRequest::GetAccountStateWithProofBySnapshotRequest(req) => {
+                if req.skip_proofs {
+                    bcs::to_bytes(&self.get_account_state_by_snapshot(&req))
+                } else {
+                    bcs::to_bytes(&self.get_account_state_with_proof_by_snapshot(&req))
+                }
+            }
+            storage_interface::StorageRequest::ReleaseReadSnapshotRequest(snapshot_id) => {
+                bcs::to_bytes(&self.release_read_snapshot(snapshot_id))
+            }
+            storage_interface::StorageRequest::WithDeadlineRequest(req) => {
+                match check_deadline(req.deadline_ms) {
+                    Ok(()) => return self.handle_request(req.request),
+                    Err(e) => bcs::to_bytes(&Result::<(), Error>::Err(e)),
+                }
+            }
+            storage_interface::StorageRequest::GetAccountStatesWithProofByVersionRequest(req) => {
+                bcs::to_bytes(&self.get_account_states_with_proof_by_version(&req))
+            }
+            storage_interface::StorageRequest::WithTraceRequest(req) => {
+                return self.handle_request(req.request)
+            }
         };
         Ok(output?)
     }
@@ -58,11 +248,40 @@ impl StorageService {
         ),
         Error,
     > {
+        self.admit_read(req.low_priority)?;
         Ok(self
             .db
             .get_account_state_with_proof_by_version(req.address, req.version)?)
     }
 
+    fn get_account_states_with_proof_by_version(
+        &self,
+        req: &storage_interface::GetAccountStatesWithProofByVersionRequest,
+    ) -> Result<Vec<AccountWithProof>, Error> {
+        self.admit_read(req.low_priority)?;
+        req.addresses
+            .iter()
+            .map(|address| {
+                Ok(self
+                    .db
+                    .get_account_state_with_proof_by_version(*address, req.version)?)
+            })
+            .collect()
+    }
+
+    fn get_account_state_by_version(
+        &self,
+        req: &storage_interface::GetAccountStateWithProofByVersionRequest,
+    ) -> Result<Option<AccountStateBlob>, Error> {
+        if !self.allow_skip_proofs {
+            return Err(Error::SkipProofsNotAllowed);
+        }
+        self.admit_read(req.low_priority)?;
+        Ok(self
+            .db
+            .get_account_state_by_version(req.address, req.version)?)
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>, Error> {
         Ok(self.db.get_startup_info()?)
     }
@@ -71,11 +290,136 @@ impl StorageService {
         &self,
         req: &storage_interface::SaveTransactionsRequest,
     ) -> Result<(), Error> {
-        Ok(self.db.save_transactions(
+        // Let the pruner reclaim versions pinned by expired snapshots before it is woken up.
+        self.expire_read_snapshots(&mut self.read_snapshots.lock());
+        let start = Instant::now();
+        let result = self.db.save_transactions(
             &req.txns_to_commit,
             req.first_version,
             req.ledger_info_with_signatures.as_ref(),
-        )?)
+        );
+        self.record_commit_latency(start.elapsed());
+        Ok(result?)
+    }
+
+    /// Pins the latest committed version for reads that must not be torn across commits, e.g. an
+    /// export spanning many requests.
+    fn create_read_snapshot(&self, req: &CreateReadSnapshotRequest) -> Result<ReadSnapshot, Error> {
+        let version = self.db.get_latest_version()?;
+        let ttl = Duration::from_millis(req.ttl_ms).min(self.max_read_snapshot_ttl);
+
+        let mut snapshots = self.read_snapshots.lock();
+        self.expire_read_snapshots(&mut snapshots);
+        let id = snapshots.next_id;
+        snapshots.next_id += 1;
+        self.db.pin_version(version);
+        snapshots.pinned.insert(
+            id,
+            PinnedSnapshot {
+                version,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        Ok(ReadSnapshot {
+            id,
+            version,
+            ttl_ms: ttl.as_millis() as u64,
+        })
+    }
+
+    fn get_account_state_with_proof_by_snapshot(
+        &self,
+        req: &storage_interface::GetAccountStateWithProofBySnapshotRequest,
+    ) -> Result<
+        (
+            Option<AccountStateBlob>,
+            SparseMerkleProof<AccountStateBlob>,
+        ),
+        Error,
+    > {
+        let version = self.snapshot_version(req.snapshot_id)?;
+        self.get_account_state_with_proof_by_version(
+            &GetAccountStateWithProofByVersionRequest::new(req.address, version),
+        )
+    }
+
+    fn get_account_state_by_snapshot(
+        &self,
+        req: &storage_interface::GetAccountStateWithProofBySnapshotRequest,
+    ) -> Result<Option<AccountStateBlob>, Error> {
+        let version = self.snapshot_version(req.snapshot_id)?;
+        self.get_account_state_by_version(
+            &GetAccountStateWithProofByVersionRequest::new(req.address, version).skip_proofs(),
+        )
+    }
+
+    /// Returns the version pinned by a live snapshot.
+    fn snapshot_version(&self, snapshot_id: u64) -> Result<Version, Error> {
+        let mut snapshots = self.read_snapshots.lock();
+        self.expire_read_snapshots(&mut snapshots);
+        snapshots
+            .pinned
+            .get(&snapshot_id)
+            .map(|snapshot| snapshot.version)
+            .ok_or(Error::SnapshotNotFound { snapshot_id })
+    }
+
+    fn release_read_snapshot(&self, snapshot_id: u64) -> Result<(), Error> {
+        let mut snapshots = self.read_snapshots.lock();
+        self.expire_read_snapshots(&mut snapshots);
+        let snapshot = snapshots
+            .pinned
+            .remove(&snapshot_id)
+            .ok_or(Error::SnapshotNotFound { snapshot_id })?;
+        self.db.unpin_version(snapshot.version);
+        Ok(())
+    }
+
+    /// Drops expired snapshots and unpins their versions.
+    fn expire_read_snapshots(&self, snapshots: &mut ReadSnapshots) {
+        let now = Instant::now();
+        let db = &self.db;
+        snapshots.pinned.retain(|_, snapshot| {
+            let live = snapshot.expires_at > now;
+            if !live {
+                db.unpin_version(snapshot.version);
+            }
+            live
+        });
+    }
+
+    /// Starts shedding low priority reads if a commit took longer than the configured threshold.
+    /// They stay shed for as long as the slow commit took, giving the writer that much headroom.
+    fn record_commit_latency(&self, latency: Duration) {
+        match self.read_shedding_commit_latency {
+            Some(threshold) if latency > threshold => {
+                warn!(
+                    latency_ms = latency.as_millis() as u64,
+                    "Slow commit, shedding low priority storage reads.",
+                );
+                *self.shed_reads_until.lock() = Some(Instant::now() + latency);
+            }
+            _ => (),
+        }
+    }
+
+    /// Rejects low priority reads with a retriable error while reads are being shed. Other reads,
+    /// e.g. the executor's, are always served: failing them would fail block execution.
+    fn admit_read(&self, low_priority: bool) -> Result<(), Error> {
+        if !low_priority {
+            return Ok(());
+        }
+        let mut shed_reads_until = self.shed_reads_until.lock();
+        if let Some(until) = *shed_reads_until {
+            let now = Instant::now();
+            if now < until {
+                return Err(Error::ServiceBusy {
+                    retry_after_ms: (until - now).as_millis() as u64,
+                });
+            }
+            *shed_reads_until = None;
+        }
+        Ok(())
     }
 
     fn run(self, config: &NodeConfig) -> JoinHandle<()> {
@@ -95,11 +439,78 @@ impl StorageService {
 
     fn process_one_message(&self, network_server: &mut NetworkServer) -> Result<(), Error> {
         let request = network_server.read()?;
-        let response = self.handle_message(request)?;
+        let response = self.handle_message(request, network_server.remote_peer())?;
         network_server.write(&response)?;
         Ok(())
     }
 }
 
+/// Name of `request` in the audit log. Requests with a deadline are named after the request they
+/// wrap.
+fn request_name(request: &StorageRequest) -> &'static str {
+    match request {
+        StorageRequest::GetAccountStateWithProofByVersionRequest(req) if req.skip_proofs => {
+            "get_account_state_by_version"
+        }
+        StorageRequest::GetAccountStateWithProofByVersionRequest(_) => {
+            "get_account_state_with_proof_by_version"
+        }
+        StorageRequest::GetStartupInfoRequest => "get_startup_info",
+        StorageRequest::SaveTransactionsRequest(_) => "save_transactions",
+        StorageRequest::CreateReadSnapshotRequest(_) => "create_read_snapshot",
+        StorageRequest::GetAccountStateWithProofBySnapshotRequest(req) if req.skip_proofs => {
+            "get_account_state_by_snapshot"
+        }
+        StorageRequest::GetAccountStateWithProofBySnapshotRequest(_) => {
+            "get_account_state_with_proof_by_snapshot"
+        }
+        StorageRequest::ReleaseReadSnapshotRequest(_) => "release_read_snapshot",
+        StorageRequest::WithDeadlineRequest(req) => request_name(&req.request),
+        StorageRequest::WithTraceRequest(req) => request_name(&req.request),
+        StorageRequest::GetAccountStatesWithProofByVersionRequest(_) => {
+            "get_account_states_with_proof_by_version"
+        }
+    }
+}
+
+/// Logs a served request with its trace context, as a warning if it was slow, so that slow
+/// requests can be traced back to their caller.
+fn log_request(request: &'static str, trace: Option<&TraceContext>, latency: Duration) {
+    let request_id = trace.map(|trace| trace.request_id.as_str());
+    let trace_id = trace.and_then(|trace| trace.trace_id.as_deref());
+    let latency_ms = latency.as_millis() as u64;
+    if latency >= SLOW_REQUEST_LATENCY {
+        warn!(
+            request = request,
+            request_id = request_id,
+            trace_id = trace_id,
+            latency_ms = latency_ms,
+            "Slow storage request.",
+        );
+    } else {
+        debug!(
+            request = request,
+            request_id = request_id,
+            trace_id = trace_id,
+            latency_ms = latency_ms,
+            "Storage request served.",
+        );
+    }
+}
+
+/// Fails once `deadline_ms` has passed, as nobody is waiting for the response anymore. Deadlines
+/// are compared against the local clock, as storage clients run on the same node.
+fn check_deadline(deadline_ms: u64) -> Result<(), Error> {
+    if duration_since_epoch().as_millis() as u64 >= deadline_ms {
+        debug!(
+            deadline_ms = deadline_ms,
+            "Skipping request past its deadline."
+        );
+        Err(Error::DeadlineExceeded { deadline_ms })
+    } else {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod storage_service_test;
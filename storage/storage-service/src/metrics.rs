@@ -0,0 +1,17 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_histogram_vec, HistogramVec};
+use once_cell::sync::Lazy;
+
+pub static DIEM_STORAGE_SERVICE_REQUEST_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "diem_storage_service_request_latency_seconds",
+        // metric description
+        "Time the storage service took to serve a request, in seconds",
+        // metric labels (dimensions)
+        &["request"]
+    )
+    .unwrap()
+});
@@ -2,20 +2,42 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use diem_config::{config::NodeConfig, utils};
-use diem_types::account_address::HashAccountAddress;
+use crate::audit_log::rotated_path;
+use diem_config::{
+    config::{NodeConfig, StorageAuditLogConfig},
+    utils,
+};
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{
+    access_path::AccessPath,
+    account_address::{AccountAddress, HashAccountAddress},
+    account_state::AccountState,
+};
 #[cfg(test)]
 use diemdb::test_helper::arb_blocks_to_commit;
 use itertools::zip_eq;
 use proptest::prelude::*;
 use std::{
     collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fs,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::Path,
 };
-use storage_client::StorageClient;
+use storage_client::{StorageClient, StorageStateView};
+use storage_interface::DiemDbError;
 
-fn start_test_storage_with_client() -> (JoinHandle<()>, diem_temppath::TempPath, StorageClient) {
+fn start_test_storage_with_client(
+    allow_skip_proofs: bool,
+) -> (JoinHandle<()>, diem_temppath::TempPath, StorageClient) {
     let mut config = NodeConfig::random();
+    config.storage.allow_skip_proofs = allow_skip_proofs;
+    start_test_storage_with_config(config)
+}
+
+fn start_test_storage_with_config(
+    mut config: NodeConfig,
+) -> (JoinHandle<()>, diem_temppath::TempPath, StorageClient) {
     let tmp_dir = diem_temppath::TempPath::new();
 
     let server_port = utils::get_available_port();
@@ -35,7 +57,7 @@ proptest! {
     #[test]
     fn test_simple_storage_service(blocks in arb_blocks_to_commit().no_shrink()) {
         let (_handle, _tmp_dir, client) =
-            start_test_storage_with_client();
+            start_test_storage_with_client(false);
 
         let mut version = 0;
         let mut all_accounts = BTreeMap::new();
@@ -84,3 +106,395 @@ proptest! {
         }
     }
 }
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+    #[test]
+    fn test_read_snapshot(blocks in arb_blocks_to_commit().no_shrink()) {
+        let (_handle, _tmp_dir, client) =
+            start_test_storage_with_client(false);
+
+        let (first_block, first_ledger_info) = &blocks[0];
+        client.save_transactions(
+            first_block.clone(),
+            0, /* first_version */
+            Some(first_ledger_info.clone()),
+        ).unwrap();
+        let snapshot = client.create_read_snapshot(60_000).unwrap();
+        prop_assert_eq!(snapshot.version, first_block.len() as u64 - 1);
+
+        let mut account_states = HashMap::new();
+        first_block.iter().for_each(|txn_to_commit| {
+            account_states.extend(txn_to_commit.account_states().clone())
+        });
+
+        // Later commits don't show through the snapshot.
+        let mut version = first_block.len() as u64;
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks[1..] {
+            client.save_transactions(
+                txns_to_commit.clone(),
+                version, /* first_version */
+                Some(ledger_info_with_sigs.clone()),
+            ).unwrap();
+            version += txns_to_commit.len() as u64;
+        }
+        for (address, blob) in account_states {
+            let (snapshot_blob, _proof) = client
+                .get_account_state_with_proof_by_snapshot(&snapshot, address)
+                .unwrap();
+            prop_assert_eq!(snapshot_blob, Some(blob));
+        }
+
+        // Released and expired snapshots can't be read through.
+        let address = AccountAddress::random();
+        client.release_read_snapshot(snapshot).unwrap();
+        prop_assert_eq!(
+            client.get_account_state_with_proof_by_snapshot(&snapshot, address).unwrap_err(),
+            Error::SnapshotNotFound { snapshot_id: snapshot.id }
+        );
+        let expired = client.create_read_snapshot(0).unwrap();
+        prop_assert_eq!(
+            client.get_account_state_with_proof_by_snapshot(&expired, address).unwrap_err(),
+            Error::SnapshotNotFound { snapshot_id: expired.id }
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+    #[test]
+    fn test_skip_proofs(blocks in arb_blocks_to_commit().no_shrink()) {
+        let (_handle, _tmp_dir, client) =
+            start_test_storage_with_client(true);
+
+        let mut version = 0;
+        let mut account_states = HashMap::new();
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks {
+            client.save_transactions(
+                txns_to_commit.clone(),
+                version, /* first_version */
+                Some(ledger_info_with_sigs.clone()),
+            ).unwrap();
+            version += txns_to_commit.len() as u64;
+            txns_to_commit.iter().for_each(|txn_to_commit| {
+                account_states.extend(txn_to_commit.account_states().clone())
+            });
+        }
+
+        let snapshot = client.create_read_snapshot(60_000).unwrap();
+        for (address, blob) in account_states {
+            prop_assert_eq!(
+                client.get_account_state_by_version(address, version - 1).unwrap(),
+                Some(blob.clone())
+            );
+            prop_assert_eq!(
+                client.get_account_state_by_snapshot(&snapshot, address).unwrap(),
+                Some(blob)
+            );
+        }
+        prop_assert_eq!(
+            client.get_account_state_by_version(AccountAddress::random(), version - 1).unwrap(),
+            None
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+    #[test]
+    fn test_storage_state_view(blocks in arb_blocks_to_commit().no_shrink()) {
+        let (_handle, _tmp_dir, client) =
+            start_test_storage_with_client(false);
+
+        let mut version = 0;
+        let mut account_states = HashMap::new();
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks {
+            client.save_transactions(
+                txns_to_commit.clone(),
+                version, /* first_version */
+                Some(ledger_info_with_sigs.clone()),
+            ).unwrap();
+            version += txns_to_commit.len() as u64;
+            txns_to_commit.iter().for_each(|txn_to_commit| {
+                account_states.extend(txn_to_commit.account_states().clone())
+            });
+        }
+
+        let view = StorageStateView::latest(StateViewId::Miscellaneous, Arc::new(client)).unwrap();
+        prop_assert_eq!(view.version(), version - 1);
+        let missing_address = AccountAddress::random();
+        let mut addresses: Vec<_> = account_states.keys().copied().collect();
+        addresses.push(missing_address);
+        view.prefetch(&addresses).unwrap();
+
+        for (address, blob) in account_states {
+            let account_state = AccountState::try_from(&blob).unwrap();
+            for (path, value) in account_state.iter() {
+                prop_assert_eq!(
+                    view.get(&AccessPath::new(address, path.clone())).unwrap(),
+                    Some(value.clone())
+                );
+            }
+        }
+        prop_assert_eq!(
+            view.get(&AccessPath::new(missing_address, vec![])).unwrap(),
+            None
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+    #[test]
+    fn test_low_priority_reads_retried_while_shed(blocks in arb_blocks_to_commit().no_shrink()) {
+        let mut config = NodeConfig::random();
+        // Every commit sheds low priority reads for as long as it took
+        config.storage.read_shedding_commit_latency_ms = Some(0);
+        let (_handle, _tmp_dir, client) = start_test_storage_with_config(config);
+        let client = client.low_priority();
+
+        let mut version = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in &blocks {
+            client.save_transactions(
+                txns_to_commit.clone(),
+                version, /* first_version */
+                Some(ledger_info_with_sigs.clone()),
+            ).unwrap();
+            version += txns_to_commit.len() as u64;
+
+            let mut account_states = HashMap::new();
+            txns_to_commit.iter().for_each(|txn_to_commit| {
+                account_states.extend(txn_to_commit.account_states().clone())
+            });
+
+            // Reads shed right after the commit are retried until served
+            for (address, blob) in account_states {
+                let (returned, _proof) = client
+                    .get_account_state_with_proof_by_version(address, version - 1)
+                    .unwrap();
+                prop_assert_eq!(returned, Some(blob));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_skip_proofs_not_allowed() {
+    let tmp_dir = diem_temppath::TempPath::new();
+    let db = Arc::new(DiemDB::new_for_test(&tmp_dir));
+    let service = StorageService::new(db, None, Duration::from_secs(60), false, None);
+    let req = storage_interface::GetAccountStateWithProofByVersionRequest::new(
+        AccountAddress::random(),
+        0,
+    )
+    .skip_proofs();
+
+    assert_eq!(
+        service.get_account_state_by_version(&req).unwrap_err(),
+        Error::SkipProofsNotAllowed
+    );
+}
+
+#[test]
+fn test_reads_shed_after_slow_commit() {
+    let tmp_dir = diem_temppath::TempPath::new();
+    let db = Arc::new(DiemDB::new_for_test(&tmp_dir));
+    let service = StorageService::new(
+        db,
+        Some(Duration::from_millis(100)),
+        Duration::from_secs(60),
+        false,
+        None,
+    );
+    let req = storage_interface::GetAccountStateWithProofByVersionRequest::new(
+        AccountAddress::random(),
+        0,
+    );
+    let low_priority_req = req.clone().low_priority();
+
+    // a fast commit doesn't affect reads
+    service.record_commit_latency(Duration::from_millis(10));
+    assert!(service.admit_read(true).is_ok());
+
+    // a slow commit sheds low priority reads with a retriable error
+    service.record_commit_latency(Duration::from_secs(60));
+    match service.get_account_state_with_proof_by_version(&low_priority_req) {
+        Err(Error::ServiceBusy { retry_after_ms }) => assert!(retry_after_ms > 0),
+        other => panic!("expected ServiceBusy, got {:?}", other),
+    }
+    let low_priority_batch_req =
+        storage_interface::GetAccountStatesWithProofByVersionRequest::new(vec![req.address], 0)
+            .low_priority();
+    assert!(matches!(
+        service.get_account_states_with_proof_by_version(&low_priority_batch_req),
+        Err(Error::ServiceBusy { .. })
+    ));
+
+    // other reads, e.g. the executor's, are still served
+    assert!(service.admit_read(false).is_ok());
+    assert!(!matches!(
+        service.get_account_state_with_proof_by_version(&req),
+        Err(Error::ServiceBusy { .. })
+    ));
+}
+
+#[test]
+fn test_requests_past_deadline_are_skipped() {
+    let tmp_dir = diem_temppath::TempPath::new();
+    let db = Arc::new(DiemDB::new_for_test(&tmp_dir));
+    let service = StorageService::new(db, None, Duration::from_secs(60), false, None);
+    let request = |deadline_ms| {
+        storage_interface::StorageRequest::WithDeadlineRequest(Box::new(
+            storage_interface::WithDeadlineRequest::new(
+                deadline_ms,
+                storage_interface::StorageRequest::GetStartupInfoRequest,
+            ),
+        ))
+    };
+    let now_ms = duration_since_epoch().as_millis() as u64;
+
+    // requests within their deadline are served
+    let response = service.handle_request(request(now_ms + 60_000)).unwrap();
+    let startup_info: Result<Option<StartupInfo>, Error> = bcs::from_bytes(&response).unwrap();
+    assert!(startup_info.is_ok());
+
+    // requests past their deadline are not
+    let response = service.handle_request(request(now_ms - 1)).unwrap();
+    match bcs::from_bytes::<Result<Option<StartupInfo>, Error>>(&response).unwrap() {
+        Err(Error::DeadlineExceeded { deadline_ms }) => assert_eq!(deadline_ms, now_ms - 1),
+        other => panic!("expected DeadlineExceeded, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_db_errors_keep_their_kind() {
+    let db_error = || DiemDbError::NotFound("Txn 10".to_string());
+    let error = Error::from(anyhow::Error::from(db_error()).context("reading txn"));
+    assert_eq!(error, Error::DiemDbError(db_error()));
+
+    // as received by storage clients
+    let response = bcs::to_bytes(&Result::<(), Error>::Err(error)).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<Result<(), Error>>(&response).unwrap(),
+        Err(Error::DiemDbError(db_error()))
+    );
+
+    // other errors are only described
+    assert!(matches!(
+        Error::from(anyhow::format_err!("disk full")),
+        Error::ServiceError { .. }
+    ));
+}
+
+fn read_audit_log(path: &Path) -> Vec<serde_json::Value> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_audit_log() {
+    let tmp_dir = diem_temppath::TempPath::new();
+    let db = Arc::new(DiemDB::new_for_test(&tmp_dir));
+    let log_path = diem_temppath::TempPath::new();
+    let audit_log = AuditLog::open(
+        log_path.path().to_path_buf(),
+        &StorageAuditLogConfig::default(),
+    )
+    .unwrap();
+    let service = StorageService::new(db, None, Duration::from_secs(60), false, Some(audit_log));
+    let peer = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 6180);
+    let deadline_ms = duration_since_epoch().as_millis() as u64 + 60_000;
+
+    let trace = TraceContext {
+        request_id: "1f".to_string(),
+        trace_id: Some("2e".to_string()),
+    };
+    let requests = vec![
+        storage_interface::StorageRequest::GetStartupInfoRequest,
+        storage_interface::StorageRequest::WithTraceRequest(Box::new(
+            storage_interface::WithTraceRequest::new(
+                trace,
+                storage_interface::StorageRequest::WithDeadlineRequest(Box::new(
+                    storage_interface::WithDeadlineRequest::new(
+                        deadline_ms,
+                        storage_interface::StorageRequest::GetAccountStateWithProofByVersionRequest(
+                            Box::new(GetAccountStateWithProofByVersionRequest::new(
+                                AccountAddress::random(),
+                                0,
+                            )),
+                        ),
+                    ),
+                )),
+            ),
+        )),
+    ];
+    let mut sizes = vec![];
+    for request in requests {
+        let request = bcs::to_bytes(&request).unwrap();
+        let request_bytes = request.len();
+        let response = service.handle_message(request, Some(peer)).unwrap();
+        sizes.push((request_bytes, response.len()));
+    }
+
+    let records = read_audit_log(log_path.path());
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["request"], "get_startup_info");
+    assert!(records[0]["versions"].is_null());
+    assert_eq!(
+        records[1]["request"],
+        "get_account_state_with_proof_by_version"
+    );
+    assert_eq!(records[1]["versions"], serde_json::json!([0, 0]));
+    assert!(records[0]["request_id"].is_null());
+    assert_eq!(records[1]["request_id"], "1f");
+    assert_eq!(records[1]["trace_id"], "2e");
+    for (record, (request_bytes, response_bytes)) in records.iter().zip(sizes) {
+        assert_eq!(record["peer"], peer.to_string());
+        assert_eq!(record["request_bytes"], request_bytes);
+        assert_eq!(record["response_bytes"], response_bytes);
+    }
+}
+
+#[test]
+fn test_audit_log_rotation() {
+    let log_path = diem_temppath::TempPath::new();
+    let path = log_path.path().to_path_buf();
+    let config = StorageAuditLogConfig {
+        path: path.clone(),
+        max_file_bytes: 1,
+        max_rotated_files: 2,
+    };
+    let mut audit_log = AuditLog::open(path.clone(), &config).unwrap();
+    for request_bytes in 0..4 {
+        audit_log
+            .record(&AuditRecord {
+                timestamp_usecs: 0,
+                peer: None,
+                request: "get_startup_info",
+                versions: None,
+                request_bytes,
+                response_bytes: 0,
+                request_id: None,
+                trace_id: None,
+            })
+            .unwrap();
+    }
+
+    // every record but the first rotates the log, and the oldest record is dropped
+    for (log, request_bytes) in vec![
+        (path.clone(), 3),
+        (rotated_path(&path, 1), 2),
+        (rotated_path(&path, 2), 1),
+    ] {
+        let records = read_audit_log(&log);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["request_bytes"], request_bytes);
+    }
+    assert!(!rotated_path(&path, 3).exists());
+    for index in 1..=2 {
+        fs::remove_file(rotated_path(&path, index)).unwrap();
+    }
+}
@@ -5,7 +5,7 @@
 //! The concept of known users can be helpful for testing to provide reproducible results.
 
 use crate::{mnemonic::Mnemonic, wallet_library::WalletLibrary};
-use anyhow::Result;
+use anyhow::{ensure, format_err, Context, Result};
 use std::{
     fs::File,
     io::{BufRead, BufReader, Write},
@@ -15,18 +15,65 @@ use std::{
 /// Delimiter used to ser/deserialize account data.
 pub const DELIMITER: &str = ";";
 
+/// First word of the header line of recovery files, followed by their format version.
+const RECOVERY_HEADER: &str = "diem-wallet-recovery";
+
+/// Format version of the recovery files written by this wallet. Bump it and append a migration
+/// from the previous version to `MIGRATIONS` whenever the format changes.
+const RECOVERY_VERSION: u64 = 1;
+
+/// Migrations of recovery file entries, the `i`th one upgrading an entry from version `i` to
+/// `i + 1`.
+const MIGRATIONS: &[fn(String) -> Result<String>] = &[migrate_v0];
+
+/// Version 0 files have no header line, and 0L ones may have the mnemonic without a key leaf.
+fn migrate_v0(entry: String) -> Result<String> {
+    if entry.contains(DELIMITER) {
+        Ok(entry)
+    } else {
+        Ok(format!("{}{}0", entry, DELIMITER))
+    }
+}
+
 /// Recover wallet from the path specified.
 pub fn recover<P: AsRef<Path>>(path: &P) -> Result<WalletLibrary> {
     let input = File::open(path)?;
-    let mut buffered = BufReader::new(input);
+    let mut lines = BufReader::new(input).lines();
+
+    let mut line = lines.next().transpose()?.unwrap_or_default();
+    let version = match line.strip_prefix(RECOVERY_HEADER) {
+        Some(version) => {
+            let version = version
+                .trim()
+                .parse::<u64>()
+                .with_context(|| format!("Invalid recovery file header '{}'", line))?;
+            line = lines
+                .next()
+                .transpose()?
+                .ok_or_else(|| format_err!("Recovery file has no entry"))?;
+            version
+        }
+        None => 0,
+    };
+    ensure!(
+        version <= RECOVERY_VERSION,
+        "Recovery file has format version {}, but this wallet only reads up to version {}. \
+         Upgrade the client.",
+        version,
+        RECOVERY_VERSION,
+    );
+    let mut entry = line.trim().to_string();
+    for migrate in &MIGRATIONS[version as usize..] {
+        entry = migrate(entry)?;
+    }
 
-    let mut line = String::new();
-    let _ = buffered.read_line(&mut line)?;
-    let parts: Vec<&str> = line.split(DELIMITER).collect();
-    //////// 0L ////////
-    // ensure!(parts.len() == 2, format!("Invalid entry '{}'", line));
+    let parts: Vec<&str> = entry.split(DELIMITER).collect();
+    ensure!(parts.len() == 2, "Invalid entry '{}'", entry);
+    let _key_leaf = parts[1]
+        .parse::<u64>()
+        .with_context(|| format!("Invalid key leaf '{}'", parts[1]))?;
 
-    let mnemonic = Mnemonic::from(&parts[0].to_string()[..])?;
+    let mnemonic = Mnemonic::from(parts[0])?;
     let mut wallet = WalletLibrary::new_from_mnemonic(mnemonic);
     wallet.generate_addresses(6)?; //////// 0L ////////
 
@@ -36,6 +83,7 @@ pub fn recover<P: AsRef<Path>>(path: &P) -> Result<WalletLibrary> {
 /// Write wallet seed to file.
 pub fn write_recovery<P: AsRef<Path>>(wallet: &WalletLibrary, path: &P) -> Result<()> {
     let mut output = File::create(path)?;
+    writeln!(output, "{} {}", RECOVERY_HEADER, RECOVERY_VERSION)?;
     writeln!(
         output,
         "{}{}{}",
@@ -46,3 +94,39 @@ pub fn write_recovery<P: AsRef<Path>>(wallet: &WalletLibrary, path: &P) -> Resul
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+    use std::fs;
+
+    const MNEMONIC: &str = "legal winner thank year wave sausage worth useful legal winner thank \
+                            year wave sausage worth useful legal winner thank year wave sausage \
+                            worth title";
+
+    #[test]
+    fn test_recover_versions() {
+        let file = TempPath::new();
+        let path = file.path();
+        let wallet = WalletLibrary::new_from_mnemonic(Mnemonic::from(MNEMONIC).unwrap());
+        write_recovery(&wallet, &path).unwrap();
+        assert_eq!(recover(&path).unwrap().mnemonic(), MNEMONIC);
+
+        // Files from before the header, with or without a key leaf, are migrated.
+        for legacy in &[format!("{};0\n", MNEMONIC), format!("{}\n", MNEMONIC)] {
+            fs::write(path, legacy).unwrap();
+            assert_eq!(recover(&path).unwrap().mnemonic(), MNEMONIC);
+        }
+
+        // Files from newer wallets are rejected.
+        let newer = format!(
+            "{} {}\n{};0\n",
+            RECOVERY_HEADER,
+            RECOVERY_VERSION + 1,
+            MNEMONIC
+        );
+        fs::write(path, newer).unwrap();
+        assert!(recover(&path).is_err());
+    }
+}
@@ -18,7 +18,7 @@ use crate::{
     mnemonic::Mnemonic,
 };
 use anyhow::Result;
-use diem_crypto::ed25519::Ed25519PrivateKey;
+use diem_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey};
 use diem_global_constants::SALT_0L;
 use diem_types::{
     account_address::AccountAddress,
@@ -176,6 +176,43 @@ impl WalletLibrary {
         }
     }
 
+    /// Signs a multi-agent Diem RawTransaction with the PrivateKeys associated to its sender and
+    /// to each of the `secondary_signers`, in that order. If any of them is not contained in the
+    /// addr_map, then this function will return an Error
+    pub fn sign_multi_agent_txn(
+        &self,
+        txn: RawTransaction,
+        secondary_signers: Vec<AccountAddress>,
+    ) -> Result<SignedTransaction> {
+        let sender_key = self.get_private_key(&txn.sender())?;
+        let secondary_keys = secondary_signers
+            .iter()
+            .map(|address| self.get_private_key(address))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(txn
+            .sign_multi_agent(
+                &sender_key,
+                secondary_signers,
+                secondary_keys.iter().collect(),
+            )?
+            .into_inner())
+    }
+
+    /// Signs a Diem RawTransaction whose gas is paid by `fee_payer` with the PrivateKeys
+    /// associated to its sender and to the fee payer. If either of them is not contained in the
+    /// addr_map, then this function will return an Error
+    pub fn sign_fee_payer_txn(
+        &self,
+        txn: RawTransaction,
+        fee_payer: AccountAddress,
+    ) -> Result<SignedTransaction> {
+        let sender_key = self.get_private_key(&txn.sender())?;
+        let fee_payer_key = self.get_private_key(&fee_payer)?;
+        Ok(txn
+            .sign_fee_payer(&sender_key, fee_payer, &fee_payer_key)?
+            .into_inner())
+    }
+
     /// Return private key for an address in the wallet
     pub fn get_private_key(&self, address: &AccountAddress) -> Result<Ed25519PrivateKey> {
         if let Some(child) = self.addr_map.get(&address) {
@@ -185,6 +222,24 @@ impl WalletLibrary {
         }
     }
 
+    /// Return public key for an address in the wallet
+    pub fn get_public_key(&self, address: &AccountAddress) -> Result<Ed25519PublicKey> {
+        if let Some(child) = self.addr_map.get(&address) {
+            Ok(self.key_factory.private_child(*child)?.get_public())
+        } else {
+            Err(WalletError::DiemWalletGeneric("missing address".to_string()).into())
+        }
+    }
+
+    /// Return the ChildNumber an address in the wallet was derived at
+    pub fn get_child_number(&self, address: &AccountAddress) -> Result<ChildNumber> {
+        if let Some(child) = self.addr_map.get(&address) {
+            Ok(*child)
+        } else {
+            Err(WalletError::DiemWalletGeneric("missing address".to_string()).into())
+        }
+    }
+
     /// Return authentication key (AuthenticationKey) for an address in the wallet
     pub fn get_authentication_key(&self, address: &AccountAddress) -> Result<AuthenticationKey> {
         if let Some(child) = self.addr_map.get(&address) {
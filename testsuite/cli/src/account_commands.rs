@@ -11,7 +11,7 @@ pub struct AccountCommand {}
 
 impl Command for AccountCommand {
     fn get_aliases(&self) -> Vec<&'static str> {
-        vec!["account", "a"]
+        vec!["account", "accounts", "a"]
     }
     fn get_description(&self) -> &'static str {
         "Account operations"
@@ -19,9 +19,12 @@ impl Command for AccountCommand {
     fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
         let commands: Vec<Box<dyn Command>> = vec![
             Box::new(AccountCommandCreateLocal {}),
+            Box::new(AccountCommandAddWatchOnly {}),
             Box::new(AccountCommandListAccounts {}),
             Box::new(AccountCommandRecoverWallet {}),
             Box::new(AccountCommandWriteRecovery {}),
+            Box::new(AccountCommandAuditDerivations {}),
+            Box::new(AccountCommandExportDerivations {}),
             Box::new(AccountCommandMint {}),
             Box::new(AccountCommandAddCurrency {}),
             //////// 0L ////////
@@ -61,6 +64,34 @@ impl Command for AccountCommandCreateLocal {
     }
 }
 
+/// Sub command to add an account by address or public key only, e.g. one held by an external
+/// signer. This does not have any on-chain effect.
+pub struct AccountCommandAddWatchOnly {}
+
+impl Command for AccountCommandAddWatchOnly {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["watch", "wo"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<account_address>|<public_key>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Add a watch-only account, without its private key, to query it and prepare unsigned \
+         transactions for it. Returns reference ID to use in other operations"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Adding watch-only account");
+        match client.add_watch_only_account(params) {
+            Ok(account_data) => println!(
+                "Added watch-only account #{} address {}",
+                account_data.index,
+                hex::encode(account_data.address)
+            ),
+            Err(e) => report_error("Error adding watch-only account", e),
+        }
+    }
+}
+
 /// Sub command to recover wallet from the file specified.
 pub struct AccountCommandRecoverWallet {}
 
@@ -113,6 +144,80 @@ impl Command for AccountCommandWriteRecovery {
     }
 }
 
+/// Sub command to check the wallet accounts derived from the mnemonic against the chain.
+pub struct AccountCommandAuditDerivations {}
+
+impl Command for AccountCommandAuditDerivations {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["audit", "au"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Derive the wallet accounts again from the mnemonic, and flag those whose authentication \
+         key on chain doesn't match the derived one"
+    }
+    fn execute(&self, client: &mut ClientProxy, _params: &[&str]) {
+        println!(">> Auditing wallet account derivations");
+        match client.audit_derivations() {
+            Ok(derivations) => {
+                for derivation in &derivations {
+                    println!(
+                        "Derivation #{} address {}, authentication key {}, status: {:?}{}",
+                        derivation.derivation_index,
+                        hex::encode(derivation.address),
+                        derivation.authentication_key,
+                        derivation.status,
+                        if derivation.mismatch {
+                            format!(
+                                ", MISMATCH: authentication key on chain is {}",
+                                derivation
+                                    .on_chain_authentication_key
+                                    .as_deref()
+                                    .unwrap_or_default()
+                            )
+                        } else {
+                            String::new()
+                        },
+                    );
+                }
+                let mismatches = derivations.iter().filter(|d| d.mismatch).count();
+                println!(
+                    "Audited {} accounts, {} mismatched on chain",
+                    derivations.len(),
+                    mismatches
+                );
+            }
+            Err(e) => report_error("Error auditing wallet account derivations", e),
+        }
+    }
+}
+
+/// Sub command to export the public metadata of the wallet accounts to the file specified.
+pub struct AccountCommandExportDerivations {}
+
+impl Command for AccountCommandExportDerivations {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["export", "ex"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<file_path>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Export derivation index, public key, authentication key and on-chain status of the \
+         wallet accounts to the file path as JSON. Private keys are not exported"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Exporting wallet account derivations");
+        match client.export_derivations(&params) {
+            Ok(derivations) => println!(
+                "Exported {} accounts, {} mismatched on chain",
+                derivations.len(),
+                derivations.iter().filter(|d| d.mismatch).count()
+            ),
+            Err(e) => report_error("Error exporting wallet account derivations", e),
+        }
+    }
+}
+
 /// Sub command to list all accounts information.
 pub struct AccountCommandListAccounts {}
 
@@ -121,7 +226,7 @@ impl Command for AccountCommandListAccounts {
         vec!["list", "la"]
     }
     fn get_description(&self) -> &'static str {
-        "Print all accounts that were created or loaded"
+        "Print the alias, address, reference ID and on-chain status of all accounts that were created or loaded"
     }
     fn execute(&self, client: &mut ClientProxy, _params: &[&str]) {
         client.print_all_accounts();
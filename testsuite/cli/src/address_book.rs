@@ -0,0 +1,164 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{format_err, Context, Result};
+use diem_types::account_address::AccountAddress;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// User-chosen names for accounts. Names are accepted wherever the client expects an account,
+/// and shown next to the addresses they stand for.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddressBook {
+    /// File the address book is kept in, if it is kept between runs.
+    path: Option<PathBuf>,
+    entries: BTreeMap<String, AccountAddress>,
+}
+
+impl AddressBook {
+    /// Loads the address book kept in `path`, or an empty one if there is none yet. Changes are
+    /// saved to `path` from now on.
+    pub fn load(path: &Path) -> Result<Self> {
+        let entries = if path.exists() {
+            let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+            serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            entries,
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&self.entries)?)
+            .with_context(|| format!("writing {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, path).with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Names `address`, replacing what `name` stood for before.
+    pub fn add(&mut self, name: &str, address: AccountAddress) -> Result<()> {
+        let previous = self.entries.insert(name.to_string(), address);
+        self.save().map_err(|e| {
+            match previous {
+                Some(previous) => self.entries.insert(name.to_string(), previous),
+                None => self.entries.remove(name),
+            };
+            e
+        })
+    }
+
+    /// Removes `name`, returning the address it stood for.
+    pub fn remove(&mut self, name: &str) -> Result<AccountAddress> {
+        let address = self
+            .entries
+            .remove(name)
+            .ok_or_else(|| format_err!("No address book entry named {}", name))?;
+        self.save().map_err(|e| {
+            self.entries.insert(name.to_string(), address);
+            e
+        })?;
+        Ok(address)
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<AccountAddress> {
+        self.entries.get(name).copied()
+    }
+
+    /// Returns the name of `address`, if it has one.
+    pub fn name_of(&self, address: &AccountAddress) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, named)| *named == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &AccountAddress)> {
+        self.entries
+            .iter()
+            .map(|(name, address)| (name.as_str(), address))
+    }
+
+    /// Appends the name of every named address found in `text` after it, e.g. to make printed
+    /// transactions and events easier to read. Addresses are matched as whole hex words in either
+    /// case, so event keys and hashes that merely contain an address are left alone.
+    pub fn annotate(&self, text: &str) -> String {
+        let mut annotated = text.to_string();
+        for (name, address) in &self.entries {
+            let address_hex = hex::encode(address);
+            let lower_text = annotated.to_ascii_lowercase();
+            let mut result = String::with_capacity(annotated.len());
+            let mut copied = 0;
+            for (start, _) in lower_text.match_indices(&address_hex) {
+                let end = start + address_hex.len();
+                let is_word = !lower_text[..start].ends_with(is_hex_digit)
+                    && !lower_text[end..].starts_with(is_hex_digit);
+                if is_word {
+                    result.push_str(&annotated[copied..end]);
+                    result.push_str(&format!(" ({})", name));
+                    copied = end;
+                }
+            }
+            result.push_str(&annotated[copied..]);
+            annotated = result;
+        }
+        annotated
+    }
+}
+
+fn is_hex_digit(c: char) -> bool {
+    c.is_ascii_hexdigit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_add_remove_and_reload() {
+        let file = TempPath::new();
+        let mut address_book = AddressBook::load(file.path()).unwrap();
+        assert_eq!(address_book.entries().count(), 0);
+
+        let alice = AccountAddress::random();
+        let bob = AccountAddress::random();
+        address_book.add("alice", alice).unwrap();
+        address_book.add("bob", bob).unwrap();
+        assert_eq!(address_book.resolve("alice"), Some(alice));
+        assert_eq!(address_book.name_of(&bob), Some("bob"));
+
+        assert_eq!(address_book.remove("alice").unwrap(), alice);
+        address_book.remove("alice").unwrap_err();
+
+        let reloaded = AddressBook::load(file.path()).unwrap();
+        assert_eq!(reloaded, address_book);
+        assert_eq!(reloaded.resolve("alice"), None);
+        assert_eq!(reloaded.resolve("bob"), Some(bob));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let bob = AccountAddress::random();
+        let mut address_book = AddressBook::default();
+        address_book.add("bob", bob).unwrap();
+
+        assert_eq!(
+            address_book.annotate(&format!("sender: {}, receiver: {:?}", bob, bob)),
+            format!("sender: {} (bob), receiver: {} (bob)", bob, bob)
+        );
+        // Event keys end with the address they belong to.
+        let event_key = format!("0000000000000000{}", hex::encode(bob));
+        assert_eq!(address_book.annotate(&event_key), event_key);
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    client_proxy::ClientProxy,
+    commands::{report_error, subcommand_execute, Command},
+};
+
+/// Major command for the address book.
+pub struct AliasCommand {}
+
+impl Command for AliasCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["alias", "al"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Address book operations. Aliases can be used wherever an account is expected"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(AliasCommandAdd {}),
+            Box::new(AliasCommandRemove {}),
+            Box::new(AliasCommandList {}),
+        ];
+        subcommand_execute(&params[0], commands, client, &params[1..]);
+    }
+}
+
+/// Sub command to name an account in the address book.
+pub struct AliasCommandAdd {}
+
+impl Command for AliasCommandAdd {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["add", "a"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<alias> <account_ref_id>|<account_address>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Name an account in the address book, replacing the account the alias named before"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        match client.add_alias(&params) {
+            Ok(address) => println!("{} is now {}", params[1], hex::encode(address)),
+            Err(e) => report_error("Error adding alias", e),
+        }
+    }
+}
+
+/// Sub command to remove an alias from the address book.
+pub struct AliasCommandRemove {}
+
+impl Command for AliasCommandRemove {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["rm", "r"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<alias>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Remove an alias from the address book"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        match client.remove_alias(&params) {
+            Ok(address) => println!("{} no longer names {}", params[1], hex::encode(address)),
+            Err(e) => report_error("Error removing alias", e),
+        }
+    }
+}
+
+/// Sub command to list the address book.
+pub struct AliasCommandList {}
+
+impl Command for AliasCommandList {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["list", "l"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Print all aliases in the address book with their addresses"
+    }
+    fn execute(&self, client: &mut ClientProxy, _params: &[&str]) {
+        client.print_address_book();
+    }
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    address_book::AddressBook,
     commands::{is_address, is_authentication_key},
+    derivations::{self, Derivation},
     diem_client::DiemClient,
+    module_bundle,
+    session::{PendingTransaction, Session, WatchOnlyAccount},
     AccountData, AccountStatus,
 };
 use anyhow::{bail, ensure, format_err, Error, Result};
@@ -12,9 +16,11 @@ use diem_client::{WaitForTransactionError, views::{self, WaypointView}};
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     test_utils::KeyPair,
+    traits::ValidCryptoMaterialStringExt,
 };
 use diem_json_rpc_client::views::{OracleUpgradeStateView, TowerStateResourceView};
 use diem_logger::prelude::{error, info};
+use diem_sdk::transaction_builder::TransactionFactory;
 use diem_temppath::TempPath;
 use diem_transaction_builder::stdlib as transaction_builder;
 use diem_types::{
@@ -34,9 +40,8 @@ use diem_types::{
     chain_id::ChainId,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{
-        authenticator::AuthenticationKey,
-        helpers::{create_unsigned_txn, create_user_txn, TransactionSigner},
-        parse_transaction_argument, ChangeSet, Module, RawTransaction, Script, SignedTransaction,
+        authenticator::AuthenticationKey, helpers::TransactionSigner, parse_transaction_argument,
+        ChangeSet, Module, ModuleBundle, RawTransaction, Script, SignedTransaction,
         TransactionArgument, TransactionPayload, Version, WriteSetPayload,
     },
     waypoint::Waypoint,
@@ -64,7 +69,7 @@ use ol_types::{block::VDFProof, account::ValConfigs};
 const CLIENT_WALLET_MNEMONIC_FILE: &str = "client.mnemonic";
 const GAS_UNIT_PRICE: u64 = 0;
 const MAX_GAS_AMOUNT: u64 = 1_000_000;
-const TX_EXPIRATION: i64 = 100;
+const TX_EXPIRATION: u64 = 100;
 const DEFAULT_WAIT_TIMEOUT: time::Duration = time::Duration::from_secs(60);
 
 /// Enum used for error formatting.
@@ -126,6 +131,12 @@ pub struct ClientProxy {
     sync_on_wallet_recovery: bool,
     /// temp files (alive for duration of program)
     temp_files: Vec<PathBuf>,
+    /// Directory the session is saved to, if it is kept between runs.
+    session_dir: Option<PathBuf>,
+    /// Pending transactions and last seen version of this session.
+    session: Session,
+    /// Names the user gave to accounts.
+    address_book: AddressBook,
     // invariant self.address_to_ref_id.values().iter().all(|i| i < self.accounts.len())
 }
 
@@ -225,6 +236,9 @@ impl ClientProxy {
             //////// 0L ////////
             sync_on_wallet_recovery,
             temp_files: vec![],
+            session_dir: None,
+            session: Session::default(),
+            address_book: AddressBook::default(),
             quiet_wait,
         })
     }
@@ -291,6 +305,44 @@ impl ClientProxy {
         Ok(self.insert_account_data(account_data))
     }
 
+    /// Add an account by address or hex-encoded public key only, e.g. one whose keys are held by
+    /// an external signer. The client can query it and prepare its transactions, but not sign
+    /// them.
+    pub fn add_watch_only_account(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<AddressAndIndex> {
+        ensure!(
+            space_delim_strings.len() == 2,
+            "Invalid number of arguments for adding a watch-only account"
+        );
+        let (address, authentication_key) =
+            match Ed25519PublicKey::from_encoded_string(space_delim_strings[1]) {
+                Ok(public_key) => {
+                    let authentication_key = AuthenticationKey::ed25519(&public_key);
+                    (
+                        authentication_key.derived_address(),
+                        Some(authentication_key),
+                    )
+                }
+                Err(_) => self.get_account_address_from_parameter(space_delim_strings[1])?,
+            };
+        ensure!(
+            self.get_account_data(&address).is_err(),
+            "Account {} is already in the wallet",
+            address
+        );
+        let mut account_data = Self::get_account_data_from_address(
+            &self.client,
+            address,
+            true,
+            None,
+            authentication_key.map(|key| key.to_vec()),
+        )?;
+        account_data.watch_only = true;
+        Ok(self.insert_account_data(account_data))
+    }
+
     /// Returns the ledger info corresonding to the latest epoch change
     /// (could further be used for e.g., generating a waypoint)
     pub fn latest_epoch_change_li(&mut self) -> Option<&LedgerInfoWithSignatures> {
@@ -298,24 +350,46 @@ impl ClientProxy {
         self.client.latest_epoch_change_li()
     }
 
-    /// Print index and address of all accounts.
-    pub fn print_all_accounts(&self) {
+    /// Print index, alias, address and on-chain status of all accounts, refreshing the status
+    /// from the validator.
+    pub fn print_all_accounts(&mut self) {
         if self.accounts.is_empty() {
             println!("No user accounts");
         } else {
-            for (ref index, ref account) in self.accounts.iter().enumerate() {
+            let addresses: Vec<_> = self
+                .accounts
+                .iter()
+                .map(|account| account.address)
+                .collect();
+            for (index, address) in addresses.into_iter().enumerate() {
+                if let Err(e) = self.get_account_and_update(&address) {
+                    error!("Failed to get account from validator, error: {:?}", e);
+                    self.accounts[index].status = AccountStatus::Unknown;
+                }
+                let account = &self.accounts[index];
                 //////// 0L ////////
                 println!(
-                    "User account index: {}, address: {}, sequence number: {}, status: {:?}",
+                    "User account index: {}, alias: {}, address: {}, sequence number: {}, status: {:?}{}",
                     index,
+                    self.address_book.name_of(&account.address).unwrap_or("-"),
                     hex::encode(&account.address),
                     // hex::encode(&self.wallet.get_private_key(&account.address).unwrap().to_bytes()),
                     account.sequence_number,
                     account.status,
+                    if account.watch_only { ", watch-only" } else { "" },
                 );
             }
         }
 
+        self.refresh_pending_transactions();
+        for pending in &self.session.pending_transactions {
+            println!(
+                "Pending transaction sender: {}, sequence number: {}",
+                hex::encode(&pending.sender),
+                pending.sequence_number,
+            );
+        }
+
         if let Some(diem_root_account) = &self.diem_root_account {
             println!(
                 "AssocRoot account address: {}, sequence_number: {}, status: {:?}",
@@ -816,6 +890,64 @@ impl ClientProxy {
         Ok(())
     }
 
+    //////// 0L ////////
+    /// Submits the VDF proof in a proof file to the tower of the sender, or of the owner account
+    /// given as last argument when the sender is its operator. Waits for the transaction to
+    /// execute, and returns the tower height verified on chain.
+    pub fn submit_proof(&mut self, space_delim_strings: &[&str]) -> Result<u64> {
+        ensure!(
+            space_delim_strings.len() == 3 || space_delim_strings.len() == 4,
+            "Invalid number of arguments to submit proof. Did you pass your account and the file path?"
+        );
+        let path = space_delim_strings[2];
+        let block: VDFProof = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| format_err!("Could not parse proof file {}: {}", path, e))?;
+
+        let (sender_address, _) =
+            self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let (tower_address, program) = match space_delim_strings.get(3) {
+            Some(owner) => {
+                let (owner_address, _) = self.get_account_address_from_parameter(owner)?;
+                let program =
+                    transaction_builder::encode_minerstate_commit_by_operator_script_function(
+                        owner_address,
+                        block.preimage.clone(),
+                        block.proof.clone(),
+                        block.difficulty(),
+                        block.security(),
+                    );
+                (owner_address, program)
+            }
+            None => {
+                let program = transaction_builder::encode_minerstate_commit_script_function(
+                    block.preimage.clone(),
+                    block.proof.clone(),
+                    block.difficulty(),
+                    block.security(),
+                );
+                (sender_address, program)
+            }
+        };
+
+        let sender_ref_id = self.get_account_ref_id(&sender_address)?;
+        let sender = self.accounts.get(sender_ref_id).unwrap();
+        let txn = self.create_txn_to_submit(
+            program,
+            &sender,
+            Some(1000000),           /* max_gas_amount */
+            Some(1),                 /* gas_unit_price */
+            Some("GAS".to_string()), /* gas_currency_code */
+        )?;
+        self.client.submit_transaction(&txn)?;
+        self.wait_for_signed_transaction(&txn)?;
+
+        let tower = self
+            .client
+            .get_miner_state(&tower_address)?
+            .ok_or_else(|| format_err!("No tower state found for {}", tower_address))?;
+        Ok(tower.verified_tower_height)
+    }
+
     //////// 0L ////////
     /// Get balance from validator for the account specified.
     pub fn get_miner_state(
@@ -1171,6 +1303,10 @@ impl ClientProxy {
                 .checked_add(1)
                 .ok_or_else(|| format_err!("seqnum can't reach u64::max"))?;
             self.update_account_seq(&txn.sender(), seq);
+            self.session.pending_transactions.push(PendingTransaction {
+                sender: txn.sender(),
+                sequence_number: txn.sequence_number(),
+            });
         }
         Ok(())
     }
@@ -1205,10 +1341,13 @@ impl ClientProxy {
         }
         match ret {
             Ok(t) => Ok(t),
-            Err(WaitForTransactionError::TransactionExecutionFailed(txn)) => Err(format_err!(
-                "Transaction failed to execute; status: {:?}!",
-                txn.vm_status
-            )),
+            Err(WaitForTransactionError::TransactionExecutionFailed(mut txn)) => {
+                txn.vm_status.explain_abort();
+                Err(format_err!(
+                    "Transaction failed to execute; status: {}",
+                    txn.vm_status
+                ))
+            }
             Err(e) => Err(anyhow::Error::new(e)),
         }
     }
@@ -1275,18 +1414,65 @@ impl ClientProxy {
             vec![],
         );
 
-        Ok(create_unsigned_txn(
+        Ok(self.build_unsigned_txn(
             program,
             sender_address,
             sender_sequence_number,
-            max_gas_amount.unwrap_or(MAX_GAS_AMOUNT),
-            gas_unit_price.unwrap_or(GAS_UNIT_PRICE),
-            gas_currency_code.unwrap_or_else(|| XUS_NAME.to_owned()),
-            TX_EXPIRATION,
-            self.chain_id,
+            max_gas_amount,
+            gas_unit_price,
+            gas_currency_code,
         ))
     }
 
+    /// Prepare a transfer from any account, e.g. a watch-only one, at its sequence number on
+    /// chain: return the unsigned raw transaction, to be signed elsewhere.
+    pub fn prepare_transfer(&mut self, space_delim_strings: &[&str]) -> Result<RawTransaction> {
+        ensure!(
+            space_delim_strings.len() >= 5 && space_delim_strings.len() <= 7,
+            "Invalid number of arguments for preparing a transfer"
+        );
+
+        let (sender_address, _) =
+            self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let (receiver_address, _) =
+            self.get_account_address_from_parameter(space_delim_strings[2])?;
+
+        let transfer_currency = space_delim_strings[4];
+        let num_coins =
+            self.convert_to_on_chain_representation(space_delim_strings[3], transfer_currency)?;
+
+        let gas_unit_price = space_delim_strings
+            .get(5)
+            .map(|price| {
+                price.parse::<u64>().map_err(|error| {
+                    format_parse_data_error("gas_unit_price", InputType::UnsignedInt, price, error)
+                })
+            })
+            .transpose()?;
+        let max_gas_amount = space_delim_strings
+            .get(6)
+            .map(|amount| {
+                amount.parse::<u64>().map_err(|error| {
+                    format_parse_data_error("max_gas_amount", InputType::UnsignedInt, amount, error)
+                })
+            })
+            .transpose()?;
+
+        let sequence_number = self
+            .get_account_resource_and_update(&sender_address)?
+            .sequence_number;
+        self.prepare_transfer_coins(
+            sender_address,
+            sequence_number,
+            receiver_address,
+            num_coins,
+            transfer_currency.to_owned(),
+            gas_unit_price,
+            max_gas_amount,
+            Some(transfer_currency.to_owned()),
+        )
+    }
+
     /// Transfers coins from sender to receiver.
     pub fn transfer_coins(
         &mut self,
@@ -1358,7 +1544,12 @@ impl ClientProxy {
             "inconsistent command '{}' for compile_program",
             space_delim_strings[0]
         );
-        let file_path = space_delim_strings[1];
+        self.run_move_build(&space_delim_strings[1..2], &space_delim_strings[2..])
+    }
+
+    /// Compiles the Move `sources` together against `dependencies`, returning the paths of the
+    /// compiled modules and scripts.
+    fn run_move_build(&mut self, sources: &[&str], dependencies: &[&str]) -> Result<Vec<String>> {
         let mut tmp_output_dir = TempPath::new();
         tmp_output_dir.persist();
         tmp_output_dir
@@ -1369,10 +1560,10 @@ impl ClientProxy {
 
         let mut args = format!(
             "run -p move-lang --bin move-build -- {} -o {}",
-            file_path,
+            sources.join(" "),
             tmp_output_path.display(),
         );
-        for dep in &space_delim_strings[2..] {
+        for dep in dependencies {
             args.push_str(&format!(" -d {}", dep));
         }
 
@@ -1421,38 +1612,96 @@ impl ClientProxy {
         &mut self,
         space_delim_strings: &[&str],
         program: TransactionPayload,
-    ) -> Result<()> {
+    ) -> Result<views::TransactionView> {
         let (sender_address, _) =
             self.get_account_address_from_parameter(space_delim_strings[1])?;
         let sender = self.get_account_data(&sender_address)?;
         let txn = self.create_txn_to_submit(program, &sender, None, None, None)?;
 
-        self.submit_and_wait(&txn, true)?;
-        Ok(())
+        self.client.submit_transaction(&txn)?;
+        self.wait_for_signed_transaction(&txn)
     }
 
-    /// Publish Move module
-    pub fn publish_module(&mut self, space_delim_strings: &[&str]) -> Result<()> {
+    /// Reads the Move bytecode at `path`. Move source files are compiled against the Diem
+    /// framework first, and must compile to a single module or script.
+    fn load_bytecode(&mut self, path: &str) -> Result<Vec<u8>> {
+        if Path::new(path).extension().and_then(|ext| ext.to_str()) != Some("move") {
+            return Ok(fs::read(path)?);
+        }
+        let dependencies = diem_framework::diem_stdlib_files();
+        let mut params = vec!["compile", path];
+        params.extend(dependencies.iter().map(String::as_str));
+        let mut output_files = self.compile_program(&params)?;
+        ensure!(
+            output_files.len() == 1,
+            "{} compiled to {} files, expected one",
+            path,
+            output_files.len()
+        );
+        Ok(fs::read(output_files.remove(0))?)
+    }
+
+    /// Publish Move module, compiling it first if given as source
+    pub fn publish_module(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<views::TransactionView> {
         ensure!(
             space_delim_strings[0] == "publish" || space_delim_strings[0] == "p",
             "inconsistent command '{}' for publish_module",
             space_delim_strings[0]
         );
-        let module_bytes = fs::read(space_delim_strings[2])?;
+        let module_bytes = self.load_bytecode(space_delim_strings[2])?;
         self.submit_program(
             space_delim_strings,
             TransactionPayload::Module(Module::new(module_bytes)),
         )
     }
 
-    /// Execute custom script
-    pub fn execute_script(&mut self, space_delim_strings: &[&str]) -> Result<()> {
+    /// Publish Move modules as a bundle, in dependency order, all of them or none of them. Move
+    /// source files are compiled together against the Diem framework first.
+    pub fn publish_module_bundle(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<views::TransactionView> {
+        ensure!(
+            space_delim_strings[0] == "publish_bundle" || space_delim_strings[0] == "pb",
+            "inconsistent command '{}' for publish_module_bundle",
+            space_delim_strings[0]
+        );
+        let (sources, compiled): (Vec<&str>, Vec<&str>) =
+            space_delim_strings[2..].iter().copied().partition(|path| {
+                Path::new(path).extension().and_then(|ext| ext.to_str()) == Some("move")
+            });
+        let mut modules = compiled
+            .into_iter()
+            .map(fs::read)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        if !sources.is_empty() {
+            let dependencies = diem_framework::diem_stdlib_files();
+            let dependencies: Vec<_> = dependencies.iter().map(String::as_str).collect();
+            for output_file in self.run_move_build(&sources, &dependencies)? {
+                modules.push(fs::read(output_file)?);
+            }
+        }
+        let modules = module_bundle::order_by_dependencies(modules)?;
+        self.submit_program(
+            space_delim_strings,
+            TransactionPayload::ModuleBundle(ModuleBundle::new(modules)),
+        )
+    }
+
+    /// Execute custom script, compiling it first if given as source
+    pub fn execute_script(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<views::TransactionView> {
         ensure!(
             space_delim_strings[0] == "execute" || space_delim_strings[0] == "e",
             "inconsistent command '{}' for execute_script",
             space_delim_strings[0]
         );
-        let script_bytes = fs::read(space_delim_strings[2])?;
+        let script_bytes = self.load_bytecode(space_delim_strings[2])?;
         let arguments: Vec<_> = space_delim_strings[3..]
             .iter()
             .filter_map(|arg| parse_transaction_argument_for_client(arg).ok())
@@ -1464,6 +1713,81 @@ impl ClientProxy {
         )
     }
 
+    /// Execute custom script signed by the sender and by comma separated secondary signers, all
+    /// of which have to be managed by the wallet.
+    pub fn execute_multi_agent_script(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<views::TransactionView> {
+        ensure!(
+            space_delim_strings[0] == "execute_multi_agent" || space_delim_strings[0] == "em",
+            "inconsistent command '{}' for execute_multi_agent_script",
+            space_delim_strings[0]
+        );
+        let (sender_address, _) =
+            self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let secondary_signers = space_delim_strings[2]
+            .split(',')
+            .map(|signer| Ok(self.get_account_address_from_parameter(signer)?.0))
+            .collect::<Result<Vec<_>>>()?;
+        let script_bytes = self.load_bytecode(space_delim_strings[3])?;
+        let arguments: Vec<_> = space_delim_strings[4..]
+            .iter()
+            .filter_map(|arg| parse_transaction_argument_for_client(arg).ok())
+            .collect();
+        let sender = self.get_account_data(&sender_address)?;
+        let txn = self.wallet.sign_multi_agent_txn(
+            self.build_unsigned_txn(
+                TransactionPayload::Script(Script::new(script_bytes, vec![], arguments)),
+                sender.address,
+                sender.sequence_number,
+                None,
+                None,
+                None,
+            ),
+            secondary_signers,
+        )?;
+
+        self.client.submit_transaction(&txn)?;
+        self.wait_for_signed_transaction(&txn)
+    }
+
+    /// Execute custom script signed by the sender and by a fee payer paying for its gas, both of
+    /// which have to be managed by the wallet.
+    pub fn execute_fee_payer_script(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<views::TransactionView> {
+        ensure!(
+            space_delim_strings[0] == "execute_fee_payer" || space_delim_strings[0] == "ef",
+            "inconsistent command '{}' for execute_fee_payer_script",
+            space_delim_strings[0]
+        );
+        let (sender_address, _) =
+            self.get_account_address_from_parameter(space_delim_strings[1])?;
+        let (fee_payer, _) = self.get_account_address_from_parameter(space_delim_strings[2])?;
+        let script_bytes = self.load_bytecode(space_delim_strings[3])?;
+        let arguments: Vec<_> = space_delim_strings[4..]
+            .iter()
+            .filter_map(|arg| parse_transaction_argument_for_client(arg).ok())
+            .collect();
+        let sender = self.get_account_data(&sender_address)?;
+        let txn = self.wallet.sign_fee_payer_txn(
+            self.build_unsigned_txn(
+                TransactionPayload::Script(Script::new(script_bytes, vec![], arguments)),
+                sender.address,
+                sender.sequence_number,
+                None,
+                None,
+                None,
+            ),
+            fee_payer,
+        )?;
+
+        self.client.submit_transaction(&txn)?;
+        self.wait_for_signed_transaction(&txn)
+    }
+
     /// Submit a writeset transaction signed by local diem root account.
     pub fn submit_writeset(&mut self, space_delim_strings: &[&str]) -> Result<()> {
         ensure!(
@@ -1598,6 +1922,13 @@ impl ClientProxy {
             Ok((auth_key.derived_address(), Some(auth_key)))
         } else if is_address(para) {
             Ok((ClientProxy::address_from_strings(para)?, None))
+        } else if let Some(address) = self.address_book.resolve(para) {
+            let authentication_key = self
+                .address_to_ref_id
+                .get(&address)
+                .and_then(|ref_id| self.accounts[*ref_id].authentication_key.clone())
+                .and_then(|bytes| AuthenticationKey::try_from(bytes).ok());
+            Ok((address, authentication_key))
         } else {
             let account_ref_id = para.parse::<usize>().map_err(|error| {
                 format_parse_data_error(
@@ -1716,6 +2047,188 @@ impl ClientProxy {
         Ok(self.set_accounts(account_data))
     }
 
+    /// Derive every account of the wallet again from the mnemonic, and check each against the
+    /// account at its address on chain.
+    pub fn audit_derivations(&mut self) -> Result<Vec<Derivation>> {
+        let mut derivations = Vec::new();
+        for address in self.wallet.get_addresses()? {
+            let derivation_index = *self.wallet.get_child_number(&address)?.as_ref();
+            let public_key = self.wallet.get_public_key(&address)?;
+            let derivation = match self.get_account_and_update(&address) {
+                Ok(Some(account)) => Derivation::new(
+                    derivation_index,
+                    &public_key,
+                    AccountStatus::Persisted,
+                    Some(account.authentication_key.inner()),
+                ),
+                Ok(None) => {
+                    Derivation::new(derivation_index, &public_key, AccountStatus::Local, None)
+                }
+                Err(e) => {
+                    error!("Failed to get account from validator, error: {:?}", e);
+                    Derivation::new(derivation_index, &public_key, AccountStatus::Unknown, None)
+                }
+            };
+            derivations.push(derivation);
+        }
+        Ok(derivations)
+    }
+
+    /// Export the derivations of all wallet accounts, checked against the chain, from command
+    /// 'export <file>'. Private keys are never exported.
+    pub fn export_derivations(&mut self, space_delim_strings: &[&str]) -> Result<Vec<Derivation>> {
+        ensure!(
+            space_delim_strings.len() == 2,
+            "Invalid number of arguments for exporting derivations"
+        );
+        let derivations = self.audit_derivations()?;
+        derivations::export(&derivations, Path::new(space_delim_strings[1]))?;
+        Ok(derivations)
+    }
+
+    /// Keep the session in `dir` between runs: restores the accounts, pending transactions and
+    /// last seen version saved there, and saves to it from now on. Returns the
+    /// accounts that were restored. Watch-only accounts are restored after the wallet ones.
+    pub fn load_session(&mut self, dir: &Path) -> Result<Vec<AddressAndIndex>> {
+        let session = Session::load(dir)?;
+        let mut restored = Vec::new();
+        for (index, address) in session.accounts.iter().enumerate() {
+            if index >= self.accounts.len() {
+                restored.push(self.create_next_account(self.sync_on_wallet_recovery)?);
+            }
+            ensure!(
+                self.accounts[index].address == *address,
+                "the session in {} was created with a different wallet: account #{} is {}, \
+                 expected {}",
+                dir.display(),
+                index,
+                self.accounts[index].address,
+                address,
+            );
+        }
+        for watch_only in &session.watch_only_accounts {
+            if self.get_account_data(&watch_only.address).is_ok() {
+                continue;
+            }
+            let mut account_data = Self::get_account_data_from_address(
+                &self.client,
+                watch_only.address,
+                self.sync_on_wallet_recovery,
+                None,
+                watch_only.authentication_key.clone(),
+            )?;
+            account_data.watch_only = true;
+            restored.push(self.insert_account_data(account_data));
+        }
+        self.session = session;
+        self.session_dir = Some(dir.to_path_buf());
+        self.refresh_pending_transactions();
+        Ok(restored)
+    }
+
+    /// Save the session, if it is kept between runs.
+    pub fn save_session(&mut self) -> Result<()> {
+        let dir = match &self.session_dir {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        self.session.accounts = self
+            .accounts
+            .iter()
+            .filter(|account| !account.watch_only)
+            .map(|account| account.address)
+            .collect();
+        self.session.watch_only_accounts = self
+            .accounts
+            .iter()
+            .filter(|account| account.watch_only)
+            .map(|account| WatchOnlyAccount {
+                address: account.address,
+                authentication_key: account.authentication_key.clone(),
+            })
+            .collect();
+        self.session.last_seen_version = std::cmp::max(
+            self.session.last_seen_version,
+            self.client.trusted_state().version(),
+        );
+        self.session.save(dir)
+    }
+
+    /// Latest ledger version seen by this client, including in previous runs of the session.
+    pub fn last_seen_version(&self) -> Version {
+        std::cmp::max(
+            self.session.last_seen_version,
+            self.client.trusted_state().version(),
+        )
+    }
+
+    /// Keep the address book in `path` between runs, loading the entries saved there.
+    pub fn load_address_book(&mut self, path: &Path) -> Result<()> {
+        self.address_book = AddressBook::load(path)?;
+        Ok(())
+    }
+
+    /// Add an entry to the address book from command 'alias add <name> <address|ref_id>', so
+    /// that `name` can be used wherever an account is expected.
+    pub fn add_alias(&mut self, space_delim_strings: &[&str]) -> Result<AccountAddress> {
+        ensure!(
+            space_delim_strings.len() == 3,
+            "Invalid number of arguments for adding an alias"
+        );
+        let alias = space_delim_strings[1];
+        ensure!(
+            alias.parse::<usize>().is_err()
+                && !alias.starts_with("0x")
+                && !is_address(alias)
+                && !is_authentication_key(alias),
+            "Alias {} could be mistaken for an account reference id or address",
+            alias
+        );
+        let (address, _) = self.get_account_address_from_parameter(space_delim_strings[2])?;
+        self.address_book.add(alias, address)?;
+        Ok(address)
+    }
+
+    /// Remove an entry from the address book from command 'alias rm <name>'.
+    pub fn remove_alias(&mut self, space_delim_strings: &[&str]) -> Result<AccountAddress> {
+        ensure!(
+            space_delim_strings.len() == 2,
+            "Invalid number of arguments for removing an alias"
+        );
+        self.address_book.remove(space_delim_strings[1])
+    }
+
+    /// Print all entries of the address book.
+    pub fn print_address_book(&self) {
+        let mut entries = self.address_book.entries().peekable();
+        if entries.peek().is_none() {
+            println!("No address book entries");
+        }
+        for (alias, address) in entries {
+            println!("Alias: {}, address: {}", alias, hex::encode(address));
+        }
+    }
+
+    /// Name every address in `text` that has an entry in the address book.
+    pub fn annotate_addresses(&self, text: &str) -> String {
+        self.address_book.annotate(text)
+    }
+
+    /// Drop the pending transactions that the validator has since committed.
+    fn refresh_pending_transactions(&mut self) {
+        let client = &self.client;
+        self.session.pending_transactions.retain(|pending| {
+            match client.get_account(&pending.sender) {
+                Ok(Some(account)) => account.sequence_number <= pending.sequence_number,
+                Ok(None) => true,
+                Err(e) => {
+                    error!("Failed to get account from validator, error: {:?}", e);
+                    true
+                }
+            }
+        });
+    }
+
     /// Insert the account data to Client::accounts and return its address and index.s
     pub fn insert_account_data(&mut self, account_data: AccountData) -> AddressAndIndex {
         let address = account_data.address;
@@ -1845,6 +2358,7 @@ impl ClientProxy {
             key_pair,
             sequence_number,
             status,
+            watch_only: false,
         })
     }
 
@@ -1857,8 +2371,9 @@ impl ClientProxy {
             file_path
         };
 
-        let wallet = if let Ok(recovered_wallet) = io_utils::recover(&wallet_recovery_file_path) {
-            recovered_wallet
+        // Never overwrite a recovery file that fails to parse, e.g. one from a newer client.
+        let wallet = if wallet_recovery_file_path.exists() {
+            io_utils::recover(&wallet_recovery_file_path)?
         } else {
             let new_wallet = WalletLibrary::new();
             new_wallet.write_recovery(&wallet_recovery_file_path)?;
@@ -2087,21 +2602,46 @@ impl ClientProxy {
         gas_unit_price: Option<u64>,
         gas_currency_code: Option<String>,
     ) -> Result<SignedTransaction> {
+        ensure!(
+            !sender_account.watch_only,
+            "Account {} is watch-only: prepare its transactions with 'tx prepare' and sign them \
+             with its external signer",
+            sender_account.address
+        );
         let signer: Box<&dyn TransactionSigner> = match &sender_account.key_pair {
             Some(key_pair) => Box::new(key_pair),
             None => Box::new(&self.wallet),
         };
-        create_user_txn(
-            *signer,
+        signer.sign_txn(self.build_unsigned_txn(
             program,
             sender_account.address,
             sender_account.sequence_number,
-            max_gas_amount.unwrap_or(MAX_GAS_AMOUNT),
-            gas_unit_price.unwrap_or(GAS_UNIT_PRICE),
-            gas_currency_code.unwrap_or_else(|| XUS_NAME.to_owned()),
-            TX_EXPIRATION,
-            self.chain_id,
-        )
+            max_gas_amount,
+            gas_unit_price,
+            gas_currency_code,
+        ))
+    }
+
+    /// Build a raw transaction for the connected chain, falling back to the CLI's gas defaults
+    /// for any parameter not given.
+    fn build_unsigned_txn(
+        &self,
+        program: TransactionPayload,
+        sender_address: AccountAddress,
+        sender_sequence_number: u64,
+        max_gas_amount: Option<u64>,
+        gas_unit_price: Option<u64>,
+        gas_currency_code: Option<String>,
+    ) -> RawTransaction {
+        TransactionFactory::new(self.chain_id)
+            .with_transaction_expiration_time(TX_EXPIRATION)
+            .payload(program)
+            .sender(sender_address)
+            .sequence_number(sender_sequence_number)
+            .max_gas_amount(max_gas_amount.unwrap_or(MAX_GAS_AMOUNT))
+            .gas_unit_price(gas_unit_price.unwrap_or(GAS_UNIT_PRICE))
+            .gas_currency_code(gas_currency_code.unwrap_or_else(|| XUS_NAME.to_owned()))
+            .build()
     }
 }
 
@@ -2170,18 +2710,14 @@ mod tests {
     use diem_wallet::io_utils;
     use proptest::prelude::*;
 
-    fn generate_accounts_from_wallet(count: usize) -> (ClientProxy, Vec<AddressAndIndex>) {
-        let mut accounts = Vec::new();
-        accounts.reserve(count);
-        let file = TempPath::new();
-        let mnemonic_path = file.path().to_str().unwrap().to_string();
+    fn client_proxy_with_mnemonic(mnemonic_path: String) -> ClientProxy {
         let waypoint =
             Waypoint::new_epoch_boundary(&LedgerInfo::mock_genesis(Some(ValidatorSet::empty())))
                 .unwrap();
 
         // Note: `client_proxy` won't actually connect to URL - it will be used only to
         // generate random accounts
-        let mut client_proxy = ClientProxy::new(
+        ClientProxy::new(
             ChainId::test(),
             "http://localhost:8080/v1",
             &"",
@@ -2194,7 +2730,15 @@ mod tests {
             waypoint,
             true,
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    fn generate_accounts_from_wallet(count: usize) -> (ClientProxy, Vec<AddressAndIndex>) {
+        let mut accounts = Vec::new();
+        accounts.reserve(count);
+        let file = TempPath::new();
+        let mut client_proxy =
+            client_proxy_with_mnemonic(file.path().to_str().unwrap().to_string());
         for _ in 0..count {
             accounts.push(client_proxy.create_next_account(false).unwrap());
         }
@@ -2313,6 +2857,66 @@ mod tests {
         assert_eq!(client.wallet.mnemonic(), wallet.mnemonic());
     }
 
+    #[test]
+    fn test_session_restore() {
+        let mnemonic_file = TempPath::new();
+        let mnemonic_path = mnemonic_file.path().to_str().unwrap().to_string();
+        let session_dir = TempPath::new();
+
+        let mut client = client_proxy_with_mnemonic(mnemonic_path.clone());
+        assert!(client.load_session(session_dir.path()).unwrap().is_empty());
+        let accounts: Vec<_> = (0..3)
+            .map(|_| client.create_next_account(false).unwrap().address)
+            .collect();
+        client.save_session().unwrap();
+
+        let mut restored_client = client_proxy_with_mnemonic(mnemonic_path);
+        let restored: Vec<_> = restored_client
+            .load_session(session_dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|account| account.address)
+            .collect();
+        assert_eq!(restored, accounts);
+
+        // A session can't be picked up by a different wallet.
+        let other_mnemonic_file = TempPath::new();
+        let mut other_client =
+            client_proxy_with_mnemonic(other_mnemonic_file.path().to_str().unwrap().to_string());
+        other_client.load_session(session_dir.path()).unwrap_err();
+    }
+
+    #[test]
+    fn test_address_book() {
+        let address_book_file = TempPath::new();
+        let (mut client, accounts) = generate_accounts_from_wallet(2);
+        client.load_address_book(address_book_file.path()).unwrap();
+        assert_eq!(
+            client.add_alias(&["add", "bob", "1"]).unwrap(),
+            accounts[1].address
+        );
+        client.add_alias(&["add", "2", "1"]).unwrap_err();
+
+        let (mut other_client, _) = generate_accounts_from_wallet(0);
+        other_client
+            .load_address_book(address_book_file.path())
+            .unwrap();
+        assert_eq!(
+            other_client
+                .get_account_address_from_parameter("bob")
+                .unwrap()
+                .0,
+            accounts[1].address
+        );
+        assert_eq!(
+            other_client.remove_alias(&["rm", "bob"]).unwrap(),
+            accounts[1].address
+        );
+        other_client
+            .get_account_address_from_parameter("bob")
+            .unwrap_err();
+    }
+
     proptest! {
         // Proptest is used to verify that the conversion will not panic with random input.
         #[test]
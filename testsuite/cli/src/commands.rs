@@ -2,10 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    account_commands::AccountCommand, client_proxy::ClientProxy, counters::COUNTER_CLIENT_ERRORS,
-    dev_commands::DevCommand, info_commands::InfoCommand, query_commands::QueryCommand,
+    account_commands::AccountCommand,
+    alias_commands::AliasCommand,
+    client_proxy::ClientProxy,
+    counters::COUNTER_CLIENT_ERRORS,
+    dev_commands::DevCommand,
+    info_commands::InfoCommand,
+    proof_commands::{ExportProofCommand, VerifyProofCommand},
+    query_commands::QueryCommand,
+    transaction_commands::TransactionCommand,
     transfer_commands::TransferCommand,
 };
+use crate::ol_miner_commands::MinerCommand;
 use crate::ol_node_commands::NodeCommand;
 use crate::ol_oracle_commands::OracleCommand;
 
@@ -49,12 +57,17 @@ pub fn get_commands(
 ) {
     let mut commands: Vec<Arc<dyn Command>> = vec![
         Arc::new(AccountCommand {}),
+        Arc::new(AliasCommand {}),
         Arc::new(QueryCommand {}),
         Arc::new(TransferCommand {}),
+        Arc::new(TransactionCommand {}),
+        Arc::new(ExportProofCommand {}),
+        Arc::new(VerifyProofCommand {}),
         Arc::new(InfoCommand {}),
         ///////// 0L ////////
         Arc::new(NodeCommand {}),
-        Arc::new(OracleCommand {}),        
+        Arc::new(OracleCommand {}),
+        Arc::new(MinerCommand {}),
     ];
     if include_dev {
         commands.push(Arc::new(DevCommand {}));
@@ -0,0 +1,116 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::AccountStatus;
+use anyhow::{Context, Result};
+use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// A wallet account as derived from the mnemonic, next to what is on chain at its address. Only
+/// public material is kept, so derivations can be exported for recovery audits.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Derivation {
+    /// Index of the child key the account is derived from.
+    pub derivation_index: u64,
+    /// Address derived from the authentication key.
+    pub address: AccountAddress,
+    /// Hex encoded public key of the child key.
+    pub public_key: String,
+    /// Hex encoded authentication key derived from the public key.
+    pub authentication_key: String,
+    /// Whether the account exists on chain, or if that couldn't be checked.
+    pub status: AccountStatus,
+    /// Hex encoded authentication key of the account on chain, if it exists.
+    pub on_chain_authentication_key: Option<String>,
+    /// Whether the account exists on chain with another authentication key than the derived one,
+    /// so the derived key can't sign for it: its key was rotated, or it was derived with another
+    /// mnemonic.
+    pub mismatch: bool,
+}
+
+impl Derivation {
+    /// Derivation of the account at `derivation_index` with `public_key`, given the
+    /// authentication key on chain if the account exists there.
+    pub fn new(
+        derivation_index: u64,
+        public_key: &Ed25519PublicKey,
+        status: AccountStatus,
+        on_chain_authentication_key: Option<&[u8]>,
+    ) -> Self {
+        let authentication_key = AuthenticationKey::ed25519(public_key);
+        Self {
+            derivation_index,
+            address: authentication_key.derived_address(),
+            public_key: hex::encode(public_key.to_bytes()),
+            authentication_key: hex::encode(authentication_key.as_ref()),
+            status,
+            on_chain_authentication_key: on_chain_authentication_key.map(hex::encode),
+            mismatch: on_chain_authentication_key
+                .map_or(false, |key| key != authentication_key.as_ref()),
+        }
+    }
+}
+
+/// Writes `derivations` to `path` as JSON, replacing the file if it exists.
+pub fn export(derivations: &[Derivation], path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_vec_pretty(derivations)?)
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_mismatch() {
+        let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        let authentication_key = AuthenticationKey::ed25519(&public_key);
+
+        let local = Derivation::new(0, &public_key, AccountStatus::Local, None);
+        assert_eq!(local.address, authentication_key.derived_address());
+        assert!(!local.mismatch);
+
+        let on_chain = Derivation::new(
+            0,
+            &public_key,
+            AccountStatus::Persisted,
+            Some(authentication_key.as_ref()),
+        );
+        assert!(!on_chain.mismatch);
+
+        // e.g. the key was rotated on chain
+        let rotated_key = [7u8; AuthenticationKey::LENGTH];
+        let rotated = Derivation::new(
+            0,
+            &public_key,
+            AccountStatus::Persisted,
+            Some(&rotated_key[..]),
+        );
+        assert!(rotated.mismatch);
+        assert_eq!(
+            rotated.on_chain_authentication_key,
+            Some(hex::encode(rotated_key))
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let file = TempPath::new();
+        let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        let derivations = vec![Derivation::new(
+            3,
+            &public_key,
+            AccountStatus::Unknown,
+            None,
+        )];
+        export(&derivations, file.path()).unwrap();
+
+        let exported: Vec<Derivation> =
+            serde_json::from_slice(&fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(exported, derivations);
+    }
+}
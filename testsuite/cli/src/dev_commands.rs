@@ -23,7 +23,10 @@ impl Command for DevCommand {
         let commands: Vec<Box<dyn Command>> = vec![
             Box::new(DevCommandCompile {}),
             Box::new(DevCommandPublish {}),
+            Box::new(DevCommandPublishBundle {}),
             Box::new(DevCommandExecute {}),
+            Box::new(DevCommandExecuteMultiAgent {}),
+            Box::new(DevCommandExecuteFeePayer {}),
             Box::new(DevCommandUpgradeStdlib {}),
             Box::new(DevCommandGenWaypoint {}),
             Box::new(DevCommandChangeDiemVersion {}),
@@ -75,11 +78,11 @@ impl Command for DevCommandPublish {
     }
 
     fn get_params_help(&self) -> &'static str {
-        "<sender_account_address>|<sender_account_ref_id> <compiled_module_path>"
+        "<sender_account_address>|<sender_account_ref_id> <compiled_module_path>|<module_source_path>"
     }
 
     fn get_description(&self) -> &'static str {
-        "Publish Move module on-chain"
+        "Publish Move module on-chain, compiling .move sources against the Diem framework first"
     }
 
     fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
@@ -88,7 +91,43 @@ impl Command for DevCommandPublish {
             return;
         }
         match client.publish_module(params) {
-            Ok(_) => println!("Successfully published module"),
+            Ok(txn) => println!(
+                "Successfully published module at version {}, gas used: {}",
+                txn.version, txn.gas_used
+            ),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Sub command to publish Move modules depending on each other in one transaction
+pub struct DevCommandPublishBundle {}
+
+impl Command for DevCommandPublishBundle {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["publish_bundle", "pb"]
+    }
+
+    fn get_params_help(&self) -> &'static str {
+        "<sender_account_address>|<sender_account_ref_id> \
+         <compiled_module_path>|<module_source_path>..."
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Publish Move modules on-chain in one transaction, ordered by their dependencies, so that \
+         either all of them or none of them get published"
+    }
+
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() < 3 {
+            println!("Invalid number of arguments to publish module bundle");
+            return;
+        }
+        match client.publish_module_bundle(params) {
+            Ok(txn) => println!(
+                "Successfully published module bundle at version {}, gas used: {}",
+                txn.version, txn.gas_used
+            ),
             Err(e) => println!("{}", e),
         }
     }
@@ -103,11 +142,12 @@ impl Command for DevCommandExecute {
     }
 
     fn get_params_help(&self) -> &'static str {
-        "<sender_account_address>|<sender_account_ref_id> <compiled_module_path> [parameters]"
+        "<sender_account_address>|<sender_account_ref_id> <compiled_script_path>|<script_source_path> \
+         [parameters]"
     }
 
     fn get_description(&self) -> &'static str {
-        "Execute custom Move script"
+        "Execute custom Move script, compiling .move sources against the Diem framework first"
     }
 
     fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
@@ -116,7 +156,75 @@ impl Command for DevCommandExecute {
             return;
         }
         match client.execute_script(params) {
-            Ok(_) => println!("Successfully finished execution"),
+            Ok(txn) => println!(
+                "Successfully finished execution at version {}, gas used: {}",
+                txn.version, txn.gas_used
+            ),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Sub command to execute a custom Move script with secondary signers
+pub struct DevCommandExecuteMultiAgent {}
+
+impl Command for DevCommandExecuteMultiAgent {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["execute_multi_agent", "em"]
+    }
+
+    fn get_params_help(&self) -> &'static str {
+        "<sender_account_address>|<sender_account_ref_id> \
+         <secondary_signer_address>|<secondary_signer_ref_id>[,...] <compiled_module_path> \
+         [parameters]"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Execute custom Move script taking a signer for the sender and each secondary signer"
+    }
+
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() < 4 {
+            println!("Invalid number of arguments to execute multi-agent script");
+            return;
+        }
+        match client.execute_multi_agent_script(params) {
+            Ok(txn) => println!(
+                "Successfully finished execution at version {}, gas used: {}",
+                txn.version, txn.gas_used
+            ),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Sub command to execute a custom Move script whose gas is paid by a fee payer
+pub struct DevCommandExecuteFeePayer {}
+
+impl Command for DevCommandExecuteFeePayer {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["execute_fee_payer", "ef"]
+    }
+
+    fn get_params_help(&self) -> &'static str {
+        "<sender_account_address>|<sender_account_ref_id> \
+         <fee_payer_address>|<fee_payer_ref_id> <compiled_module_path> [parameters]"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Execute custom Move script with its gas paid by the fee payer"
+    }
+
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() < 4 {
+            println!("Invalid number of arguments to execute fee payer script");
+            return;
+        }
+        match client.execute_fee_payer_script(params) {
+            Ok(txn) => println!(
+                "Successfully finished execution at version {}, gas used: {}",
+                txn.version, txn.gas_used
+            ),
             Err(e) => println!("{}", e),
         }
     }
@@ -117,6 +117,27 @@ impl DiemClient {
         }
     }
 
+    /// Gets the state proof of the latest ledger info, with the epoch changes since the epoch of
+    /// `from_version`
+    pub fn get_state_proof(&self, from_version: Version) -> Result<views::StateProofView> {
+        self.client
+            .get_state_proof(from_version)
+            .map_err(Into::into)
+            .map(Response::into_inner)
+    }
+
+    /// Gets the state of `account` at `version`, with its proof against the ledger at `version`
+    pub fn get_account_state_with_proof(
+        &self,
+        account: &AccountAddress,
+        version: Version,
+    ) -> Result<views::AccountStateWithProofView> {
+        self.client
+            .get_account_state_with_proof(*account, Some(version), Some(version))
+            .map_err(Into::into)
+            .map(Response::into_inner)
+    }
+
     pub fn get_events(
         &self,
         event_key: EventKey,
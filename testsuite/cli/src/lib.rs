@@ -19,18 +19,26 @@ use diem_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
 
 mod account_commands;
+mod address_book;
+mod alias_commands;
 /// Main instance of client holding corresponding information, e.g. account address.
 pub mod client_proxy;
 /// Command struct to interact with client.
 pub mod commands;
 mod counters;
+mod derivations;
 mod dev_commands;
 /// Client wrapper to connect to validator.
 pub mod diem_client; //////// 0L ////////
 mod info_commands;
+mod module_bundle;
+mod proof_commands;
 mod query_commands;
+mod session;
+mod transaction_commands;
 mod transfer_commands;
 //////// 0L ////////
+mod ol_miner_commands;
 mod ol_node_commands;
 mod ol_oracle_commands;
 /// Struct used to store data for each created account.  We track the sequence number
@@ -48,6 +56,10 @@ pub struct AccountData {
     pub sequence_number: u64,
     /// Whether the account is initialized on chain, cached local only, or status unknown.
     pub status: AccountStatus,
+    /// Whether the account was added by address or public key only, so the client can query it
+    /// and prepare its transactions but not sign them.
+    #[serde(default)]
+    pub watch_only: bool,
 }
 
 /// Enum used to represent account status.
@@ -11,10 +11,12 @@ use cli::{
     client_proxy::ClientProxy,
     commands::{get_commands, parse_cmd, report_error, Command},
 };
+use diem_transaction_builder::error_explain::{self, CustomErrorMapping};
 use diem_types::{chain_id::ChainId, waypoint::Waypoint};
 use rustyline::{config::CompletionType, error::ReadlineError, Config, Editor};
 use ol_keys::wallet;
 use std::{
+    path::PathBuf,
     str::FromStr,
     time::{Duration, UNIX_EPOCH},
 };
@@ -80,6 +82,17 @@ struct Args {
     /// Verbose output.
     #[structopt(short = "v", long = "verbose")]
     pub verbose: bool,
+    /// Directory to keep the session in between runs: created accounts, pending transactions
+    /// and the last seen ledger version. Nothing is kept if not passed.
+    #[structopt(short = "s", long, parse(from_os_str))]
+    pub session_dir: Option<PathBuf>,
+    /// File to keep the address book in, created when the first alias is added.
+    #[structopt(long, parse(from_os_str), default_value = "address_book.json")]
+    pub address_book: PathBuf,
+    /// TOML file naming abort codes the framework error descriptions don't cover, used to
+    /// explain failed transactions.
+    #[structopt(long, parse(from_os_str))]
+    pub abort_codes: Option<PathBuf>,
 }
 
 fn main() {
@@ -166,6 +179,37 @@ fn main() {
             Err(e) => report_error("Error recovering Diem wallet", e),
         }
     }
+    if let Err(e) = client_proxy.load_address_book(&args.address_book) {
+        report_error("Error loading address book", e);
+    }
+    if let Some(path) = &args.abort_codes {
+        if let Err(e) =
+            CustomErrorMapping::load(path).and_then(error_explain::register_custom_explanations)
+        {
+            report_error("Error loading abort codes", e);
+        }
+    }
+    if let Some(session_dir) = &args.session_dir {
+        match client_proxy.load_session(session_dir) {
+            Ok(account_data) => {
+                for data in account_data {
+                    println!(
+                        "Restored account #{} address {}",
+                        data.index,
+                        hex::encode(data.address)
+                    );
+                }
+                let last_seen_version = client_proxy.last_seen_version();
+                if block_metadata.version < last_seen_version {
+                    println!(
+                        "Warning: validator is at version {}, behind version {} seen in a previous session",
+                        block_metadata.version, last_seen_version
+                    );
+                }
+            }
+            Err(e) => report_error("Error loading session", e),
+        }
+    }
     print_help(&cli_info, &commands);
     println!("Please, input commands: \n");
 
@@ -189,6 +233,9 @@ fn main() {
                             println!("{}", Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
                         }
                         cmd.execute(&mut client_proxy, &params);
+                        if let Err(e) = client_proxy.save_session() {
+                            report_error("Error saving session", e);
+                        }
                     }
                     None => match params[0] {
                         "quit" | "q!" => break,
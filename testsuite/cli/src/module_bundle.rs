@@ -0,0 +1,125 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ordering of the modules of a bundle, published by one transaction.
+
+use anyhow::{ensure, format_err, Result};
+use move_binary_format::{access::ModuleAccess, CompiledModule};
+use move_core_types::language_storage::ModuleId;
+use std::collections::HashMap;
+
+/// Orders `modules` so that every module comes after the modules of the bundle it depends on or
+/// declares as friends, as the VM publishes a bundle in order. Fails if a module is invalid or
+/// appears twice, or if modules depend on each other cyclically.
+pub fn order_by_dependencies(modules: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let compiled_modules = modules
+        .iter()
+        .map(|code| {
+            CompiledModule::deserialize(code).map_err(|err| format_err!("Invalid module: {}", err))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut indices = HashMap::new();
+    for (index, module) in compiled_modules.iter().enumerate() {
+        let module_id = module.self_id();
+        ensure!(
+            indices.insert(module_id.clone(), index).is_none(),
+            "Module {} appears twice in the bundle",
+            module_id
+        );
+    }
+
+    let mut order = Ordering {
+        compiled_modules: &compiled_modules,
+        indices: &indices,
+        visiting: vec![false; modules.len()],
+        visited: vec![false; modules.len()],
+        order: vec![],
+    };
+    for index in 0..modules.len() {
+        order.visit(index)?;
+    }
+    let mut modules: Vec<_> = modules.into_iter().map(Some).collect();
+    Ok(order
+        .order
+        .into_iter()
+        .map(|index| modules[index].take().expect("Modules are ordered once"))
+        .collect())
+}
+
+/// Depth-first traversal of the modules of a bundle, listing every module after its dependencies.
+struct Ordering<'a> {
+    compiled_modules: &'a [CompiledModule],
+    indices: &'a HashMap<ModuleId, usize>,
+    visiting: Vec<bool>,
+    visited: Vec<bool>,
+    order: Vec<usize>,
+}
+
+impl Ordering<'_> {
+    fn visit(&mut self, index: usize) -> Result<()> {
+        if self.visited[index] {
+            return Ok(());
+        }
+        let compiled_modules = self.compiled_modules;
+        let module = &compiled_modules[index];
+        ensure!(
+            !self.visiting[index],
+            "Modules of the bundle depend on each other cyclically, through {}",
+            module.self_id()
+        );
+        self.visiting[index] = true;
+        // The VM requires the friends of a module to be published before it, like its
+        // dependencies.
+        let dependencies = module
+            .immediate_dependencies()
+            .into_iter()
+            .chain(module.immediate_friends());
+        for dependency in dependencies {
+            if let Some(&dependency_index) = self.indices.get(&dependency) {
+                self.visit(dependency_index)?;
+            }
+        }
+        self.visiting[index] = false;
+        self.visited[index] = true;
+        self.order.push(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use compiler::Compiler;
+    use diem_types::account_address::AccountAddress;
+
+    fn compile(address: AccountAddress, code: &str, deps: Vec<CompiledModule>) -> Vec<u8> {
+        Compiler {
+            address,
+            skip_stdlib_deps: true,
+            extra_deps: deps,
+        }
+        .into_module_blob("file_name", code)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_order_by_dependencies() {
+        let address = AccountAddress::random();
+        let a = compile(address, "module A { public f() { return; } }", vec![]);
+        let b = compile(
+            address,
+            &format!(
+                "module B {{ import 0x{}.A; public g() {{ A.f(); return; }} }}",
+                address
+            ),
+            vec![CompiledModule::deserialize(&a).unwrap()],
+        );
+        let c = compile(address, "module C { public h() { return; } }", vec![]);
+
+        let ordered = order_by_dependencies(vec![b.clone(), c.clone(), a.clone()]).unwrap();
+        assert_eq!(ordered, vec![a.clone(), b, c]);
+
+        assert!(order_by_dependencies(vec![a.clone(), a]).is_err());
+        assert!(order_by_dependencies(vec![vec![0xff]]).is_err());
+    }
+}
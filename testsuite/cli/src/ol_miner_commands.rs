@@ -0,0 +1,47 @@
+use crate::{
+    client_proxy::ClientProxy,
+    commands::{report_error, subcommand_execute, Command},
+};
+
+/// Major command for tower (miner) operations.
+pub struct MinerCommand {}
+
+impl Command for MinerCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["miner"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Tower proof related commands"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        let commands: Vec<Box<dyn Command>> = vec![Box::new(MinerCommandSubmitProof {})];
+
+        subcommand_execute(&params[0], commands, client, &params[1..]);
+    }
+}
+
+/// Sub command to submit a VDF proof to a tower.
+pub struct MinerCommandSubmitProof {}
+
+impl Command for MinerCommandSubmitProof {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["submit-proof", "sp"]
+    }
+
+    fn get_params_help(&self) -> &'static str {
+        "<sender_account_address>|<sender_account_ref_id> <proof_file_path> [owner_account_address]"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Submit the VDF proof of a proof file (e.g. proof_1.json) to the tower of the sender, or \
+         of the owner account when the sender is its operator"
+    }
+
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        println!(">> Submitting proof");
+        match client.submit_proof(params) {
+            Ok(height) => println!("Proof committed, tower height is now {}", height),
+            Err(e) => report_error("Failed to submit proof", e),
+        }
+    }
+}
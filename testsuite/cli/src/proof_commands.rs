@@ -0,0 +1,298 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proof bundles: the state of an account along with everything needed to verify it against a
+//! waypoint, so it can be checked offline, e.g. by an auditor without access to the network.
+//!
+//! A bundle is a JSON file holding the responses of the `get_state_proof` and
+//! `get_account_state_with_proof` JSON-RPC methods, so it can be put together from any node.
+
+use crate::{
+    client_proxy::ClientProxy,
+    commands::{report_error, Command},
+};
+use anyhow::{ensure, Result};
+use diem_client::views::{AccountStateWithProofView, StateProofView};
+use diem_crypto::hash::CryptoHash;
+use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    epoch_change::EpochChangeProof,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::AccumulatorConsistencyProof,
+    transaction::Version,
+    trusted_state::TrustedState,
+    waypoint::Waypoint,
+};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fs, str::FromStr};
+
+/// The state of an account, and the proof of the ledger info it's proven against.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProofBundle {
+    /// Latest ledger info, with the epoch changes since the epoch of the verifier's waypoint.
+    pub state_proof: StateProofView,
+    pub address: AccountAddress,
+    /// State of `address`, proven against the ledger info of `state_proof`.
+    pub account_state_with_proof: AccountStateWithProofView,
+}
+
+/// What a valid bundle proves.
+struct Verdict {
+    ledger_info: LedgerInfo,
+    num_signatures: usize,
+    address: AccountAddress,
+    version: Version,
+    blob: Option<AccountStateBlob>,
+}
+
+/// Command to export the proof bundle of an account.
+pub struct ExportProofCommand {}
+
+impl Command for ExportProofCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["export-proof"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<account_address>|<account_ref_id> <waypoint_version> <file_path>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Export the latest state of an account with its proof, to be verified offline against a \
+         waypoint at <waypoint_version> or earlier in the same epoch"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() != 4 {
+            println!("Invalid number of arguments to export proof");
+            return;
+        }
+        match export_proof(client, params[1], params[2], params[3]) {
+            Ok(version) => println!(
+                "Exported the proof of the state of {} at version {} to {}",
+                params[1], version, params[3]
+            ),
+            Err(e) => report_error("Failed to export proof", e),
+        }
+    }
+}
+
+/// Command to verify a proof bundle offline.
+pub struct VerifyProofCommand {}
+
+impl Command for VerifyProofCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["verify-proof"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<file_path> <waypoint>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Verify a proof bundle against a waypoint, without connecting to the network"
+    }
+    fn execute(&self, _client: &mut ClientProxy, params: &[&str]) {
+        if params.len() != 3 {
+            println!("Invalid number of arguments to verify proof");
+            return;
+        }
+        let waypoint = match Waypoint::from_str(params[2]) {
+            Ok(waypoint) => waypoint,
+            Err(e) => return report_error("Failed to parse waypoint", e),
+        };
+        let bundle = match read_bundle(params[1]) {
+            Ok(bundle) => bundle,
+            Err(e) => return report_error("Failed to read proof bundle", e),
+        };
+        match verify_bundle(&bundle, waypoint) {
+            Ok(verdict) => print_verdict(&verdict, waypoint),
+            Err(e) => println!(
+                "INVALID: the proof bundle doesn't verify against {}: {}",
+                waypoint, e
+            ),
+        }
+    }
+}
+
+/// Writes the bundle of the latest state of `account` to `path`, returning its version.
+fn export_proof(
+    client: &mut ClientProxy,
+    account: &str,
+    waypoint_version: &str,
+    path: &str,
+) -> Result<Version> {
+    let (address, _) = client.get_account_address_from_parameter(account)?;
+    let state_proof = client
+        .client
+        .get_state_proof(waypoint_version.parse::<Version>()?)?;
+    let ledger_info: LedgerInfoWithSignatures =
+        bcs::from_bytes(state_proof.ledger_info_with_signatures.inner())?;
+    let version = ledger_info.ledger_info().version();
+    let bundle = ProofBundle {
+        state_proof,
+        address,
+        account_state_with_proof: client
+            .client
+            .get_account_state_with_proof(&address, version)?,
+    };
+    fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok(version)
+}
+
+fn read_bundle(path: &str) -> Result<ProofBundle> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// Verifies the ledger info of `bundle` against `waypoint`, then the account state against it.
+fn verify_bundle(bundle: &ProofBundle, waypoint: Waypoint) -> Result<Verdict> {
+    let (ledger_info, epoch_change_proof, _): (
+        LedgerInfoWithSignatures,
+        EpochChangeProof,
+        AccumulatorConsistencyProof,
+    ) = TryFrom::try_from(&bundle.state_proof)?;
+    let version = ledger_info.ledger_info().version();
+    let trusted_state = TrustedState::from(waypoint);
+    let state = trusted_state
+        .verify_and_ratchet(&ledger_info, &epoch_change_proof)?
+        .new_state()
+        .unwrap_or(trusted_state);
+    // Truncated epoch change proofs only ratchet up to their last epoch change.
+    ensure!(
+        state.version() == version,
+        "The epoch changes end at version {}, before the ledger info at version {}",
+        state.version(),
+        version,
+    );
+
+    let account_state = AccountStateWithProof::try_from(&bundle.account_state_with_proof)?;
+    account_state.verify(
+        ledger_info.ledger_info(),
+        account_state.version,
+        bundle.address,
+    )?;
+
+    Ok(Verdict {
+        num_signatures: ledger_info.signatures().len(),
+        ledger_info: ledger_info.ledger_info().clone(),
+        address: bundle.address,
+        version: account_state.version,
+        blob: account_state.blob,
+    })
+}
+
+fn print_verdict(verdict: &Verdict, waypoint: Waypoint) {
+    println!("VALID: the proof bundle verifies against {}", waypoint);
+    println!(
+        "Ledger info: version {}, epoch {}, timestamp {} us, signed by {} validators",
+        verdict.ledger_info.version(),
+        verdict.ledger_info.epoch(),
+        verdict.ledger_info.timestamp_usecs(),
+        verdict.num_signatures,
+    );
+    match &verdict.blob {
+        Some(blob) => println!(
+            "Account {}: exists at version {}, state hash {}",
+            verdict.address,
+            verdict.version,
+            blob.hash()
+        ),
+        None => println!(
+            "Account {}: doesn't exist at version {}",
+            verdict.address, verdict.version
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_client::views::BytesView;
+    use diem_crypto::HashValue;
+    use diem_types::{
+        block_info::BlockInfo,
+        epoch_state::EpochState,
+        proof::{
+            AccountStateProof, SparseMerkleLeafNode, SparseMerkleProof,
+            TransactionAccumulatorProof, TransactionInfoWithProof,
+        },
+        transaction::TransactionInfo,
+        vm_status::KeptVMStatus,
+    };
+    use std::collections::BTreeMap;
+
+    /// A bundle of the only account of a ledger of a single transaction ending the genesis epoch,
+    /// and the waypoint of that ledger.
+    fn single_account_bundle(blob: AccountStateBlob) -> (ProofBundle, Waypoint) {
+        let address = AccountAddress::random();
+        let leaf = SparseMerkleLeafNode::new(address.hash(), blob.hash());
+        let txn_info = TransactionInfo::new(
+            HashValue::random(),
+            leaf.hash(),
+            HashValue::zero(),
+            0,
+            KeptVMStatus::Executed,
+        );
+        let ledger_info = LedgerInfo::new(
+            BlockInfo::new(
+                0,
+                0,
+                HashValue::zero(),
+                txn_info.hash(),
+                0,
+                0,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        );
+        let ledger_info_with_sigs =
+            LedgerInfoWithSignatures::new(ledger_info.clone(), BTreeMap::new());
+        let account_state_with_proof = AccountStateWithProof::new(
+            0,
+            Some(blob),
+            AccountStateProof::new(
+                TransactionInfoWithProof::new(TransactionAccumulatorProof::new(vec![]), txn_info),
+                SparseMerkleProof::new(Some(leaf), vec![]),
+            ),
+        );
+        let bundle = ProofBundle {
+            state_proof: StateProofView::try_from((
+                ledger_info_with_sigs.clone(),
+                EpochChangeProof::new(vec![ledger_info_with_sigs], false),
+                AccumulatorConsistencyProof::new(vec![]),
+            ))
+            .unwrap(),
+            address,
+            account_state_with_proof: AccountStateWithProofView::try_from(account_state_with_proof)
+                .unwrap(),
+        };
+        (bundle, Waypoint::new_any(&ledger_info))
+    }
+
+    #[test]
+    fn test_verify_bundle() {
+        let blob = AccountStateBlob::from(vec![1, 2, 3]);
+        let (bundle, waypoint) = single_account_bundle(blob.clone());
+        let verdict = verify_bundle(&bundle, waypoint).unwrap();
+        assert_eq!(verdict.address, bundle.address);
+        assert_eq!(verdict.version, 0);
+        assert_eq!(verdict.blob, Some(blob));
+
+        // Round trips through its file format
+        let json = serde_json::to_string(&bundle).unwrap();
+        let bundle: ProofBundle = serde_json::from_str(&json).unwrap();
+        verify_bundle(&bundle, waypoint).unwrap();
+
+        // Another ledger
+        let (_, other_waypoint) = single_account_bundle(AccountStateBlob::from(vec![1, 2, 3]));
+        verify_bundle(&bundle, other_waypoint).unwrap_err();
+
+        // Another account
+        let mut tampered = serde_json::from_str::<ProofBundle>(&json).unwrap();
+        tampered.address = AccountAddress::random();
+        verify_bundle(&tampered, waypoint).unwrap_err();
+
+        // Another state
+        let mut tampered = serde_json::from_str::<ProofBundle>(&json).unwrap();
+        tampered.account_state_with_proof.blob = Some(BytesView::new(
+            bcs::to_bytes(&AccountStateBlob::from(vec![4, 5, 6])).unwrap(),
+        ));
+        verify_bundle(&tampered, waypoint).unwrap_err();
+    }
+}
@@ -172,7 +172,10 @@ impl Command for QueryCommandGetTxnByAccountSeq {
             Ok(txn_view) => {
                 match txn_view {
                     Some(txn_view) => {
-                        println!("Committed transaction: {:#?}", txn_view);
+                        println!(
+                            "Committed transaction: {}",
+                            client.annotate_addresses(&format!("{:#?}", txn_view))
+                        );
                     }
                     None => println!("Transaction not available"),
                 };
@@ -207,7 +210,11 @@ impl Command for QueryCommandGetTxnByRange {
                 // if the version wasn't able to be parsed in the first place
                 let mut cur_version = params[1].parse::<u64>().expect("Unable to parse version");
                 for txn_view in comm_txns_and_events {
-                    println!("Transaction at version {}: {:#?}", cur_version, txn_view,);
+                    println!(
+                        "Transaction at version {}: {}",
+                        cur_version,
+                        client.annotate_addresses(&format!("{:#?}", txn_view))
+                    );
                     cur_version += 1;
                 }
             }
@@ -237,7 +244,7 @@ impl Command for QueryCommandGetEvent {
                     println!("No events returned");
                 } else {
                     for event in events {
-                        println!("{:?}", event);
+                        println!("{}", client.annotate_addresses(&format!("{:?}", event)));
                     }
                 }
                 println!("Last event state: {:#?}", last_event_state);
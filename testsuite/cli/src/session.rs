@@ -0,0 +1,184 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{ensure, format_err, Context, Result};
+use diem_types::{account_address::AccountAddress, transaction::Version};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{fs, path::Path};
+
+const SESSION_FILE: &str = "session.json";
+
+/// Format version of the session files written by this client, kept in their `version` field.
+/// Bump it and append a migration from the previous version to `MIGRATIONS` whenever the format
+/// changes.
+const SESSION_VERSION: u64 = 1;
+
+/// Migrations of session files, the `i`th one upgrading a file from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(Value) -> Result<Value>] = &[migrate_v0];
+
+/// Version 0 files predate the `version` field, and may predate watch-only accounts.
+fn migrate_v0(mut session: Value) -> Result<Value> {
+    session
+        .as_object_mut()
+        .ok_or_else(|| format_err!("Session is not a JSON object"))?
+        .entry("watch_only_accounts")
+        .or_insert_with(|| Value::Array(vec![]));
+    Ok(session)
+}
+
+/// A transaction submitted without waiting for it to be committed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PendingTransaction {
+    /// Sender of the transaction.
+    pub sender: AccountAddress,
+    /// Sequence number of the transaction.
+    pub sequence_number: u64,
+}
+
+/// An account added by address or public key only, whose transactions are signed elsewhere.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WatchOnlyAccount {
+    /// Address of the account.
+    pub address: AccountAddress,
+    /// Authentication key of the account, if known.
+    pub authentication_key: Option<Vec<u8>>,
+}
+
+/// Client state that is kept between runs when the client is given a session directory.
+///
+/// Keys are never stored: accounts are re-derived from the wallet mnemonic on startup, and the
+/// stored addresses are only used to check that the same wallet is in use.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Session {
+    /// Addresses of the wallet accounts created so far, by local index.
+    pub accounts: Vec<AccountAddress>,
+    /// Watch-only accounts added so far.
+    pub watch_only_accounts: Vec<WatchOnlyAccount>,
+    /// Transactions submitted without waiting that have not been seen committed yet.
+    pub pending_transactions: Vec<PendingTransaction>,
+    /// Latest ledger version the client has seen.
+    pub last_seen_version: Version,
+}
+
+impl Session {
+    /// Loads the session kept in `dir`, or an empty one if there is none yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(SESSION_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&bytes)
+            .map_err(Into::into)
+            .and_then(Self::from_versioned)
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Parses a session of any known format version, migrating it to the current one. Fails on
+    /// sessions written by a newer client rather than dropping what it doesn't know about.
+    fn from_versioned(mut session: Value) -> Result<Self> {
+        let version = match session.as_object_mut() {
+            Some(fields) => match fields.remove("version") {
+                Some(version) => version
+                    .as_u64()
+                    .ok_or_else(|| format_err!("Invalid session version {}", version))?,
+                None => 0,
+            },
+            None => return Err(format_err!("Session is not a JSON object")),
+        };
+        ensure!(
+            version <= SESSION_VERSION,
+            "Session has format version {}, but this client only reads up to version {}. \
+             Upgrade the client.",
+            version,
+            SESSION_VERSION,
+        );
+        for migrate in &MIGRATIONS[version as usize..] {
+            session = migrate(session)?;
+        }
+        Ok(serde_json::from_value(session)?)
+    }
+
+    fn to_versioned(&self) -> Result<Value> {
+        let mut session = serde_json::to_value(self)?;
+        session
+            .as_object_mut()
+            .expect("Sessions serialize to JSON objects")
+            .insert("version".to_string(), SESSION_VERSION.into());
+        Ok(session)
+    }
+
+    /// Saves the session to `dir`, creating the directory if needed. The previous session is
+    /// only replaced once the new one has been fully written.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let path = dir.join(SESSION_FILE);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&self.to_versioned()?)?)
+            .with_context(|| format!("writing {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path).with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempPath::new();
+        assert_eq!(Session::load(dir.path()).unwrap(), Session::default());
+
+        let address = AccountAddress::random();
+        let mut session = Session {
+            accounts: vec![address, AccountAddress::random()],
+            watch_only_accounts: vec![WatchOnlyAccount {
+                address: AccountAddress::random(),
+                authentication_key: Some(vec![7; 32]),
+            }],
+            last_seen_version: 42,
+            ..Session::default()
+        };
+        session.pending_transactions.push(PendingTransaction {
+            sender: address,
+            sequence_number: 3,
+        });
+
+        session.save(dir.path()).unwrap();
+        assert_eq!(Session::load(dir.path()).unwrap(), session);
+    }
+
+    #[test]
+    fn test_load_versions() {
+        let dir = TempPath::new();
+        fs::create_dir_all(dir.path()).unwrap();
+        let path = dir.path().join(SESSION_FILE);
+        let address = AccountAddress::random();
+
+        // Sessions from before the version tag are migrated.
+        let v0 = serde_json::json!({
+            "accounts": [address],
+            "pending_transactions": [],
+            "last_seen_version": 7,
+        });
+        fs::write(&path, v0.to_string()).unwrap();
+        let session = Session::load(dir.path()).unwrap();
+        assert_eq!(session.accounts, vec![address]);
+        assert_eq!(session.last_seen_version, 7);
+        assert!(session.watch_only_accounts.is_empty());
+
+        // Sessions from newer clients, or with fields this client doesn't know, are rejected.
+        let mut newer = session.to_versioned().unwrap();
+        newer["version"] = (SESSION_VERSION + 1).into();
+        fs::write(&path, newer.to_string()).unwrap();
+        assert!(Session::load(dir.path()).is_err());
+        let mut unknown_field = session.to_versioned().unwrap();
+        unknown_field["trusted_state"] = Value::Null;
+        fs::write(&path, unknown_field.to_string()).unwrap();
+        assert!(Session::load(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    client_proxy::ClientProxy,
+    commands::{report_error, subcommand_execute, Command},
+};
+use anyhow::Result;
+use diem_crypto::hash::{CryptoHash, CryptoHasher};
+use diem_types::transaction::{RawTransaction, SignedTransaction, Transaction};
+
+/// Major command for converting transactions between their BCS and JSON forms, and preparing
+/// unsigned ones.
+pub struct TransactionCommand {}
+
+impl Command for TransactionCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["tx"]
+    }
+    fn get_description(&self) -> &'static str {
+        "Transaction encoding utilities"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        let commands: Vec<Box<dyn Command>> = vec![
+            Box::new(TransactionCommandDecode {}),
+            Box::new(TransactionCommandEncode {}),
+            Box::new(TransactionCommandPrepare {}),
+        ];
+        subcommand_execute(&params[0], commands, client, &params[1..]);
+    }
+}
+
+/// Sub command to decode a hex-encoded BCS signed transaction.
+pub struct TransactionCommandDecode {}
+
+impl Command for TransactionCommandDecode {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["decode", "d"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<hex_encoded_signed_transaction>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Print a hex-encoded BCS signed transaction as JSON, with its hash and signature status"
+    }
+    fn execute(&self, _client: &mut ClientProxy, params: &[&str]) {
+        if params.len() != 2 {
+            println!("Invalid number of arguments to decode transaction");
+            return;
+        }
+        match decode_transaction(params[1]) {
+            Ok(txn) => print_transaction(&txn),
+            Err(e) => report_error("Failed to decode transaction", e),
+        }
+    }
+}
+
+/// Sub command to encode a signed transaction given as JSON.
+pub struct TransactionCommandEncode {}
+
+impl Command for TransactionCommandEncode {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["encode", "e"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "<json_signed_transaction>"
+    }
+    fn get_description(&self) -> &'static str {
+        "Print a JSON signed transaction as hex-encoded BCS, with its hash and signature status"
+    }
+    fn execute(&self, _client: &mut ClientProxy, params: &[&str]) {
+        if params.len() < 2 {
+            println!("Invalid number of arguments to encode transaction");
+            return;
+        }
+        // The command line is split on whitespace, put the JSON back together.
+        match encode_transaction(&params[1..].join(" ")) {
+            Ok(txn) => print_transaction(&txn),
+            Err(e) => report_error("Failed to encode transaction", e),
+        }
+    }
+}
+
+/// Sub command to prepare an unsigned transfer, e.g. from a watch-only account.
+pub struct TransactionCommandPrepare {}
+
+impl Command for TransactionCommandPrepare {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["prepare", "p"]
+    }
+    fn get_params_help(&self) -> &'static str {
+        "\n\t<sender_account_address>|<sender_account_ref_id> \
+         <receiver_account_address>|<receiver_account_ref_id> <number_of_coins> <currency_code> \
+         [gas_unit_price_in_micro_diems (default=0)] [max_gas_amount_in_micro_diems (default 1_000_000)]"
+    }
+    fn get_description(&self) -> &'static str {
+        "Print an unsigned transfer at the sender's sequence number on chain, with the message \
+         for an external signer to sign"
+    }
+    fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() < 5 || params.len() > 7 {
+            println!("Invalid number of arguments to prepare transaction");
+            return;
+        }
+        match client.prepare_transfer(params) {
+            Ok(raw_txn) => print_raw_transaction(&raw_txn),
+            Err(e) => report_error("Failed to prepare transaction", e),
+        }
+    }
+}
+
+/// Parses a hex-encoded BCS signed transaction, with or without a `0x` prefix.
+fn decode_transaction(hex_txn: &str) -> Result<SignedTransaction> {
+    let bytes = hex::decode(hex_txn.trim_start_matches("0x"))?;
+    Ok(bcs::from_bytes(&bytes)?)
+}
+
+/// Parses a signed transaction from the JSON printed by `tx decode`.
+fn encode_transaction(json_txn: &str) -> Result<SignedTransaction> {
+    Ok(serde_json::from_str(json_txn)?)
+}
+
+fn print_transaction(txn: &SignedTransaction) {
+    match serde_json::to_string_pretty(txn) {
+        Ok(json) => println!("{}", json),
+        Err(e) => report_error("Failed to print transaction as JSON", e.into()),
+    }
+    match bcs::to_bytes(txn) {
+        Ok(bytes) => println!("BCS: {}", hex::encode(bytes)),
+        Err(e) => report_error("Failed to encode transaction", e.into()),
+    }
+    println!("Hash: {}", Transaction::UserTransaction(txn.clone()).hash());
+    println!("Trace ID: {}", txn.trace_id());
+    match txn.clone().check_signature() {
+        Ok(_) => println!("Signature: valid"),
+        Err(e) => println!("Signature: INVALID ({})", e),
+    }
+}
+
+/// The bytes an Ed25519 signature of `raw_txn` is computed over: the domain separation seed of
+/// raw transactions followed by the BCS of `raw_txn`.
+fn signing_message(raw_txn: &RawTransaction) -> Result<Vec<u8>> {
+    let mut message = <RawTransaction as CryptoHash>::Hasher::seed().to_vec();
+    message.extend(bcs::to_bytes(raw_txn)?);
+    Ok(message)
+}
+
+fn print_raw_transaction(raw_txn: &RawTransaction) {
+    match serde_json::to_string_pretty(raw_txn) {
+        Ok(json) => println!("{}", json),
+        Err(e) => report_error("Failed to print transaction as JSON", e.into()),
+    }
+    match bcs::to_bytes(raw_txn) {
+        Ok(bytes) => println!("BCS: {}", hex::encode(bytes)),
+        Err(e) => report_error("Failed to encode transaction", e.into()),
+    }
+    match signing_message(raw_txn) {
+        Ok(message) => println!("Signing message: {}", hex::encode(message)),
+        Err(e) => report_error("Failed to encode transaction", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Signature, SigningKey, Uniform};
+    use diem_types::{
+        account_address::AccountAddress,
+        test_helpers::transaction_test_helpers::get_test_signed_txn,
+    };
+
+    #[test]
+    fn test_decode_and_encode() {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let txn = get_test_signed_txn(
+            AccountAddress::random(),
+            7,
+            &private_key,
+            private_key.public_key(),
+            None,
+        );
+
+        let hex_txn = hex::encode(bcs::to_bytes(&txn).unwrap());
+        assert_eq!(decode_transaction(&hex_txn).unwrap(), txn);
+        assert_eq!(decode_transaction(&format!("0x{}", hex_txn)).unwrap(), txn);
+        decode_transaction(&hex_txn[..hex_txn.len() - 2]).unwrap_err();
+
+        let json_txn = serde_json::to_string(&txn).unwrap();
+        assert_eq!(encode_transaction(&json_txn).unwrap(), txn);
+        encode_transaction("{}").unwrap_err();
+    }
+
+    #[test]
+    fn test_signing_message() {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let txn = get_test_signed_txn(
+            AccountAddress::random(),
+            7,
+            &private_key,
+            private_key.public_key(),
+            None,
+        );
+        let raw_txn = txn.into_raw_transaction();
+
+        let signature = private_key.sign(&raw_txn);
+        signature
+            .verify_arbitrary_msg(
+                &signing_message(&raw_txn).unwrap(),
+                &private_key.public_key(),
+            )
+            .unwrap();
+    }
+}
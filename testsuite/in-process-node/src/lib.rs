@@ -0,0 +1,322 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A single-process Diem node for end-to-end integration tests.
+//!
+//! [`InProcessNode`] bootstraps a fresh `DiemDB` from the test genesis, wires it to an executor and
+//! a JSON-RPC endpoint, and stands in for mempool and consensus: transactions submitted through
+//! the API (or [`InProcessNode::submit`]) are queued until the test calls
+//! [`InProcessNode::commit_pending`], which executes them as one block and commits it with a
+//! ledger info signed by the only validator. This keeps tests deterministic without starting a
+//! swarm of processes.
+
+use anyhow::{bail, ensure, Result};
+use diem_client::BlockingClient;
+use diem_config::{
+    config::{
+        ApiKeysConfig, QueryBudgetsConfig, ResourceWatchesConfig, ResponseCompressionConfig,
+        RoleType, WebhooksConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
+        DEFAULT_MIN_VERSION_WAIT_MS, DEFAULT_PAGE_SIZE_LIMIT,
+    },
+    utils,
+};
+use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue};
+use diem_infallible::Mutex;
+use diem_mempool::{MempoolClientRequest, SubmissionStatus};
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress,
+    account_state::AccountState,
+    chain_id::ChainId,
+    contract_event::ContractEvent,
+    event::EventKey,
+    mempool_status::{MempoolStatus, MempoolStatusCode},
+    transaction::{SignedTransaction, Transaction, TransactionStatus, Version, WriteSetPayload},
+    validator_signer::ValidatorSigner,
+};
+use diem_vm::DiemVM;
+use executor::Executor;
+use executor_test_helpers::{
+    gen_ledger_info_with_sigs, integration_test_impl::create_db_and_executor,
+};
+use executor_types::BlockExecutor;
+use futures::{channel::mpsc, StreamExt};
+use std::{
+    convert::TryFrom,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use storage_interface::{DbReader, DbReaderWriter, Order};
+use subscription_service::CommitEventBus;
+use tokio::runtime::Runtime;
+
+/// Transactions accepted by the stub mempool and not yet committed.
+type PendingTransactions = Arc<Mutex<Vec<SignedTransaction>>>;
+
+pub struct InProcessNode {
+    db: DbReaderWriter,
+    executor: Executor<DiemVM>,
+    signer: ValidatorSigner,
+    pending: PendingTransactions,
+    next_block: u64,
+    json_rpc_address: SocketAddr,
+    // Dropping the runtime shuts down the JSON-RPC server and the stub mempool.
+    _runtime: Runtime,
+    _db_path: TempPath,
+}
+
+impl InProcessNode {
+    /// Bootstraps a single-validator test genesis into a temporary database and starts serving
+    /// JSON-RPC on an available local port.
+    pub fn start() -> Self {
+        let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+        let signer = ValidatorSigner::new(validators[0].owner_address, validators[0].key.clone());
+
+        let db_path = TempPath::new();
+        db_path.create_as_dir().unwrap();
+        let (diem_db, db, executor, _waypoint) =
+            create_db_and_executor(db_path.path(), &genesis_txn);
+
+        let json_rpc_address =
+            SocketAddr::new(Ipv4Addr::LOCALHOST.into(), utils::get_available_port());
+        let (mp_sender, mut mp_events) = mpsc::channel(1_024);
+        let runtime = diem_json_rpc::bootstrap(
+            json_rpc_address,
+            DEFAULT_BATCH_SIZE_LIMIT,
+            DEFAULT_PAGE_SIZE_LIMIT,
+            DEFAULT_MIN_VERSION_WAIT_MS,
+            DEFAULT_CONTENT_LENGTH_LIMIT,
+            &ApiKeysConfig::default(),
+            &QueryBudgetsConfig::default(),
+            &ResponseCompressionConfig::default(),
+            None,
+            &None,
+            &None,
+            &WebhooksConfig::default(),
+            &ResourceWatchesConfig::default(),
+            &CommitEventBus::new(),
+            diem_db,
+            mp_sender,
+            RoleType::Validator,
+            ChainId::test(),
+        );
+
+        // Stub mempool: accept everything and leave validation to the VM at commit time.
+        let pending = PendingTransactions::default();
+        let mempool_pending = pending.clone();
+        runtime.spawn(async move {
+            while let Some(request) = mp_events.next().await {
+                let callback = match request {
+                    MempoolClientRequest::SubmitTransaction(txn, callback) => {
+                        mempool_pending.lock().push(txn);
+                        callback
+                    }
+                    MempoolClientRequest::ValidateTransaction(_, callback) => callback,
+                    MempoolClientRequest::AllocateSequenceNumber(_, callback) => {
+                        let _ = callback.send(Ok(None));
+                        continue;
+                    }
+                };
+                let status: SubmissionStatus =
+                    (MempoolStatus::new(MempoolStatusCode::Accepted), None);
+                let _ = callback.send(Ok(status));
+            }
+        });
+
+        Self {
+            db,
+            executor,
+            signer,
+            pending,
+            next_block: 1,
+            json_rpc_address,
+            _runtime: runtime,
+            _db_path: db_path,
+        }
+    }
+
+    /// The private key controlling the genesis association and treasury compliance accounts.
+    pub fn genesis_key(&self) -> &'static Ed25519PrivateKey {
+        &vm_genesis::GENESIS_KEYPAIR.0
+    }
+
+    pub fn chain_id(&self) -> ChainId {
+        ChainId::test()
+    }
+
+    pub fn json_rpc_url(&self) -> String {
+        format!("http://{}/v1", self.json_rpc_address)
+    }
+
+    pub fn client(&self) -> BlockingClient {
+        BlockingClient::new(self.json_rpc_url())
+    }
+
+    pub fn db_reader(&self) -> Arc<dyn DbReader> {
+        self.db.reader.clone()
+    }
+
+    /// Queues a transaction for the next block without going through the JSON-RPC endpoint.
+    pub fn submit(&self, txn: SignedTransaction) {
+        self.pending.lock().push(txn);
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Executes every queued transaction as a single block and commits it, returning the
+    /// per-transaction statuses in submission order. Discarded transactions are reported but do
+    /// not advance the ledger.
+    pub fn commit_pending(&mut self) -> Result<Vec<TransactionStatus>> {
+        let txns: Vec<_> = self
+            .pending
+            .lock()
+            .drain(..)
+            .map(Transaction::UserTransaction)
+            .collect();
+        self.commit_block(txns)
+    }
+
+    /// Executes and commits `txns` as the next block, bypassing the pending queue.
+    pub fn commit_block(&mut self, txns: Vec<Transaction>) -> Result<Vec<TransactionStatus>> {
+        let block_id = HashValue::sha3_256_of(&self.next_block.to_le_bytes());
+        self.next_block += 1;
+
+        let epoch = self
+            .db
+            .reader
+            .get_latest_ledger_info()?
+            .ledger_info()
+            .next_block_epoch();
+        let parent_block_id = self.executor.committed_block_id();
+        let output = self
+            .executor
+            .execute_block((block_id, txns), parent_block_id)?;
+        let statuses = output.compute_status().clone();
+        let ledger_info_with_sigs =
+            gen_ledger_info_with_sigs(epoch, output, block_id, vec![&self.signer]);
+        self.executor
+            .commit_blocks(vec![block_id], ledger_info_with_sigs)?;
+        Ok(statuses)
+    }
+
+    pub fn latest_version(&self) -> Result<Version> {
+        self.db.reader.get_latest_version()
+    }
+
+    pub fn account_state(&self, address: AccountAddress) -> Result<Option<AccountState>> {
+        self.db
+            .reader
+            .get_latest_account_state(address)?
+            .map(|blob| AccountState::try_from(&blob))
+            .transpose()
+    }
+
+    pub fn sequence_number(&self, address: AccountAddress) -> Result<u64> {
+        Ok(self
+            .account_state(address)?
+            .and_then(|state| state.get_account_resource().transpose())
+            .transpose()?
+            .map_or(0, |resource| resource.sequence_number()))
+    }
+
+    /// Returns up to `limit` events emitted under `key`, oldest first.
+    pub fn events(&self, key: &EventKey, start: u64, limit: u64) -> Result<Vec<ContractEvent>> {
+        Ok(self
+            .db
+            .reader
+            .get_events(key, start, Order::Ascending, limit)?
+            .into_iter()
+            .map(|(_version, event)| event)
+            .collect())
+    }
+
+    /// Asserts that `txn` has been committed and kept, returning its version.
+    pub fn assert_committed(&self, txn: &SignedTransaction) -> Result<Version> {
+        let ledger_version = self.latest_version()?;
+        let committed = self.db.reader.get_txn_by_account(
+            txn.sender(),
+            txn.sequence_number(),
+            ledger_version,
+            false,
+        )?;
+        match committed {
+            Some(committed) => {
+                ensure!(
+                    committed.transaction == Transaction::UserTransaction(txn.clone()),
+                    "a different transaction was committed at {}:{}",
+                    txn.sender(),
+                    txn.sequence_number(),
+                );
+                Ok(committed.version)
+            }
+            None => bail!(
+                "transaction {}:{} is not committed",
+                txn.sender(),
+                txn.sequence_number()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{PrivateKey, Uniform};
+    use diem_transaction_builder::stdlib::encode_create_parent_vasp_account_script;
+    use diem_types::{
+        account_config::{treasury_compliance_account_address, xus_tag},
+        test_helpers::transaction_test_helpers::get_test_signed_txn,
+        transaction::authenticator::AuthenticationKey,
+    };
+
+    #[test]
+    fn test_submit_over_json_rpc_and_commit() {
+        let mut node = InProcessNode::start();
+        let tc_account = treasury_compliance_account_address();
+        let genesis_key = node.genesis_key();
+
+        let new_key = Ed25519PrivateKey::generate_for_testing();
+        let auth_key = AuthenticationKey::ed25519(&new_key.public_key());
+        let new_account = auth_key.derived_address();
+        let txn = get_test_signed_txn(
+            tc_account,
+            node.sequence_number(tc_account).unwrap(),
+            genesis_key,
+            genesis_key.public_key(),
+            Some(encode_create_parent_vasp_account_script(
+                xus_tag(),
+                0,
+                new_account,
+                auth_key.prefix().to_vec(),
+                vec![],
+                false, /* add all currencies */
+            )),
+        );
+
+        node.client().submit(&txn).unwrap();
+        assert_eq!(node.pending_count(), 1);
+        assert!(node.account_state(new_account).unwrap().is_none());
+
+        let statuses = node.commit_pending().unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(matches!(statuses[0], TransactionStatus::Keep(_)));
+
+        let version = node.assert_committed(&txn).unwrap();
+        assert_eq!(version, node.latest_version().unwrap());
+        assert!(node.account_state(new_account).unwrap().is_some());
+        assert_eq!(
+            node.client()
+                .get_account(new_account)
+                .unwrap()
+                .into_inner()
+                .unwrap()
+                .sequence_number,
+            0
+        );
+    }
+}
@@ -11,14 +11,18 @@ use diem_client::{
 };
 use diem_types::{
     account_address::AccountAddress,
-    account_config::constants::addresses::{
-        diem_root_address, testnet_dd_account_address, treasury_compliance_account_address,
-        validator_set_address,
+    account_config::{
+        constants::addresses::{
+            diem_root_address, testnet_dd_account_address, treasury_compliance_account_address,
+            validator_set_address,
+        },
+        AccountResource,
     },
     event::{EventHandle, EventKey},
     transaction::Version,
     trusted_state::TrustedState,
 };
+use move_core_types::move_resource::MoveResource;
 use proptest::{collection::vec, prelude::*, sample::select};
 use std::cmp::max;
 use tokio::runtime::Builder;
@@ -50,7 +54,7 @@ impl Environment {
         let trusted_state = TrustedState::from(genesis_waypoint);
         let storage = InMemoryStorage::new();
         let verifying_client =
-            VerifyingClient::new_with_state(client.clone(), trusted_state, storage);
+            VerifyingClient::new_with_state(client.clone(), trusted_state, storage).unwrap();
 
         Self {
             _env: env,
@@ -254,8 +258,13 @@ fn arb_request(
         })
     });
 
+    let arb_account_resource = (arb_account.clone(), arb_version.clone()).prop_map(|(a, v)| {
+        MethodRequest::get_account_resource(a, &AccountResource::resource_path(), Some(v))
+    });
+
     prop_oneof![
         (arb_account, arb_version).prop_map(|(a, v)| MethodRequest::GetAccount(a, Some(v))),
+        arb_account_resource,
         (arb_version_and_limit, arb_include_events)
             .prop_map(|((v, l), i)| MethodRequest::GetTransactions(v, l, i)),
         arb_events.prop_map(|(k, s, l)| MethodRequest::GetEvents(k, s, l)),
@@ -327,3 +336,44 @@ fn test_client_equivalence() {
         assert_batches_equal(recv_nv, recv_v);
     });
 }
+
+#[test]
+fn test_trusted_node() {
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    let env = Environment::new();
+    let trusted_client = env
+        .verifying_client
+        .with_inner(env.client.clone())
+        .with_trusted_node();
+
+    // Responses of a trusted node are the node's own, and leave the trusted state as it was.
+    let waypoint = trusted_client.waypoint();
+    let batch = vec![
+        MethodRequest::get_account(diem_root_address()),
+        MethodRequest::get_currencies(),
+        MethodRequest::get_network_status(),
+    ];
+    let (recv_nv, recv_trusted) = rt.block_on(async {
+        tokio::join!(
+            env.client.batch(batch.clone()),
+            trusted_client.batch(batch.clone())
+        )
+    });
+    assert_batches_equal(recv_nv, recv_trusted);
+    assert_eq!(trusted_client.waypoint(), waypoint);
+
+    // The same batch is verified by a client verifying proofs on its own threads
+    let verifying_client = env
+        .verifying_client
+        .with_inner(env.client.clone())
+        .with_verification_threads(2)
+        .unwrap();
+    rt.block_on(verifying_client.sync()).unwrap();
+    let (recv_nv, recv_v) = rt.block_on(async {
+        tokio::join!(
+            env.client.batch(batch.clone()),
+            verifying_client.batch(batch)
+        )
+    });
+    assert_batches_equal(recv_nv, recv_v);
+}
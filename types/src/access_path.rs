@@ -51,7 +51,7 @@ pub struct AccessPath {
     pub path: Vec<u8>,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, Ord, PartialOrd)]
 pub enum Path {
     Code(ModuleId),
     Resource(StructTag),
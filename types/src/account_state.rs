@@ -16,6 +16,7 @@ use crate::{
     ol_oracle_upgrade_state::OracleResource,
     ol_upgrade_payload::UpgradePayloadResource,
     ol_validators_stats::ValidatorsStatsResource,
+    ol_vouch::VouchResource,
     on_chain_config::{
         ConfigurationResource, DiemVersion, OnChainConfig, RegisteredCurrencies,
         VMPublishingOption, ValidatorSet,
@@ -231,6 +232,12 @@ impl AccountState {
         self.get_resource()
     }
 
+    //////// 0L ////////
+    /// validators vouching for this account
+    pub fn get_vouch_resource(&self) -> Result<Option<VouchResource>> {
+        self.get_resource()
+    }
+
     //////// 0L ////////
     /// Fetch VDF difficulty params from chain.
     pub fn get_tower_params(&self) -> Result<Option<VDFDifficulty>>{
@@ -45,6 +45,7 @@ pub mod ol_validators_stats;
 pub mod ol_miner_state;
 pub mod ol_oracle_upgrade_state;
 pub mod ol_vdf_difficulty;
+pub mod ol_vouch;
 
 #[cfg(test)]
 mod unit_tests;
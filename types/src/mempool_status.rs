@@ -53,6 +53,10 @@ pub enum MempoolStatusCode {
     // transaction didn't pass vm_validation
     VmError = 5,
     UnknownStatus = 6,
+    // Transaction runs a script the node operator has filtered out
+    RejectedByFilter = 7,
+    // Transaction expires sooner or later than the node accepts
+    ExpirationOutOfBounds = 8,
 }
 
 impl TryFrom<u64> for MempoolStatusCode {
@@ -67,6 +71,8 @@ impl TryFrom<u64> for MempoolStatusCode {
             4 => Ok(MempoolStatusCode::InvalidUpdate),
             5 => Ok(MempoolStatusCode::VmError),
             6 => Ok(MempoolStatusCode::UnknownStatus),
+            7 => Ok(MempoolStatusCode::RejectedByFilter),
+            8 => Ok(MempoolStatusCode::ExpirationOutOfBounds),
             _ => Err("invalid StatusCode"),
         }
     }
@@ -0,0 +1,37 @@
+//! vouch resource struct for parsing chain state
+
+use crate::account_config::constants::CORE_CODE_ADDRESS;
+use move_core_types::{
+    account_address::AccountAddress,
+    ident_str,
+    identifier::IdentStr,
+    language_storage::StructTag,
+    move_resource::{MoveResource, MoveStructType},
+};
+use serde::{Deserialize, Serialize};
+
+/// The validators vouching for the validator holding this resource
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VouchResource {
+    /// Addresses of the vouching validators
+    pub vals: Vec<AccountAddress>,
+}
+
+impl VouchResource {
+    ///
+    pub fn struct_tag() -> StructTag {
+        StructTag {
+            address: CORE_CODE_ADDRESS,
+            module: VouchResource::module_identifier(),
+            name: VouchResource::struct_identifier(),
+            type_params: vec![],
+        }
+    }
+}
+
+impl MoveStructType for VouchResource {
+    const MODULE_NAME: &'static IdentStr = ident_str!("Vouch");
+    const STRUCT_NAME: &'static IdentStr = ident_str!("Vouch");
+}
+
+impl MoveResource for VouchResource {}
@@ -24,5 +24,20 @@ pub const DIEM_VERSION_2: DiemVersion = DiemVersion { major: 2 };
 //  - Multi-agent transactions
 pub const DIEM_VERSION_3: DiemVersion = DiemVersion { major: 3 };
 
+// NOTE: version number for module bundles
+// Items gated by this version number include:
+//  - the ModuleBundle payload type
+pub const DIEM_VERSION_4: DiemVersion = DiemVersion { major: 4 };
+
+// NOTE: version number for signature schemes beyond Ed25519
+// Items gated by this version number include:
+//  - signatures verified through `AccountAuthenticator::Generic`, e.g. secp256k1 ones
+pub const DIEM_VERSION_5: DiemVersion = DiemVersion { major: 5 };
+
+// NOTE: version number for fee payer transactions
+// Items gated by this version number include:
+//  - transactions whose gas is paid by a fee payer rather than by their sender
+pub const DIEM_VERSION_6: DiemVersion = DiemVersion { major: 6 };
+
 // Maximum current known version
-pub const DIEM_MAX_KNOWN_VERSION: DiemVersion = DIEM_VERSION_3;
+pub const DIEM_MAX_KNOWN_VERSION: DiemVersion = DIEM_VERSION_6;
@@ -24,7 +24,10 @@ mod vm_config;
 mod vm_publishing_option;
 
 pub use self::{
-    diem_version::{DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3},
+    diem_version::{
+        DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
+        DIEM_VERSION_5, DIEM_VERSION_6,
+    },
     registered_currencies::RegisteredCurrencies,
     validator_set::ValidatorSet,
     vm_config::VMConfig,
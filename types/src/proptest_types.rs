@@ -355,6 +355,16 @@ fn new_raw_transaction(
             expiration_time_secs,
             chain_id,
         ),
+        TransactionPayload::ModuleBundle(modules) => RawTransaction::new_module_bundle(
+            sender,
+            sequence_number,
+            modules,
+            max_gas_amount,
+            gas_unit_price,
+            gas_currency_code,
+            expiration_time_secs,
+            chain_id,
+        ),
         TransactionPayload::WriteSet(WriteSetPayload::Direct(write_set)) => {
             // It's a bit unfortunate that max_gas_amount etc is generated but
             // not used, but it isn't a huge deal.
@@ -792,6 +802,8 @@ pub struct TransactionToCommitGen {
     /// N.B. the transaction sender and event owners must be updated to reflect information such as
     /// sequence numbers so that test data generated through this is more realistic and logical.
     account_state_gens: Vec<(Index, AccountStateBlobGen)>,
+    /// Write set produced by the transaction.
+    write_set: WriteSet,
     /// Gas used.
     gas_used: u64,
     /// Transaction status
@@ -825,6 +837,7 @@ impl TransactionToCommitGen {
         TransactionToCommit::new(
             Transaction::UserTransaction(transaction),
             account_states,
+            self.write_set,
             events,
             self.gas_used,
             self.status,
@@ -851,11 +864,12 @@ impl Arbitrary for TransactionToCommitGen {
                 0..=2,
             ),
             vec((any::<Index>(), any::<AccountStateBlobGen>()), 0..=1),
+            any::<WriteSet>(),
             any::<u64>(),
             any::<KeptVMStatus>(),
         )
             .prop_map(
-                |(sender, event_emitters, mut touched_accounts, gas_used, status)| {
+                |(sender, event_emitters, mut touched_accounts, write_set, gas_used, status)| {
                     // To reflect change of account/event sequence numbers, txn sender account and
                     // event emitter accounts must be updated.
                     let (sender_index, sender_blob_gen, txn_gen) = sender;
@@ -871,6 +885,7 @@ impl Arbitrary for TransactionToCommitGen {
                         transaction_gen: (sender_index, txn_gen),
                         event_gens,
                         account_state_gens: touched_accounts,
+                        write_set,
                         gas_used,
                         status,
                     }
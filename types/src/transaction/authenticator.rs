@@ -5,12 +5,13 @@ use crate::{
     account_address::AccountAddress,
     transaction::{RawTransaction, RawTransactionWithData},
 };
-use anyhow::{ensure, Error, Result};
+use anyhow::{bail, ensure, Error, Result};
 use diem_crypto::{
-    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
-    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
-    traits::Signature,
+    multi_ed25519::{MultiEd25519PrivateKey, MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{Secp256k1PrivateKey, Secp256k1PublicKey, Secp256k1Signature},
+    traits::{PrivateKey, Signature, SigningKey},
     CryptoMaterialError, HashValue, ValidCryptoMaterial, ValidCryptoMaterialStringExt,
 };
 use diem_crypto_derive::{CryptoHasher, DeserializeKey, SerializeKey};
@@ -57,6 +58,15 @@ pub enum TransactionAuthenticator {
         secondary_signer_addresses: Vec<AccountAddress>,
         secondary_signers: Vec<AccountAuthenticator>,
     },
+    /// Single signature of any scheme, for the schemes without a variant above.
+    SingleSender { sender: AccountAuthenticator },
+    /// Transaction whose gas is paid by a fee payer rather than by its sender. Both sign the
+    /// transaction together with the address of the fee payer.
+    FeePayer {
+        sender: AccountAuthenticator,
+        fee_payer_address: AccountAddress,
+        fee_payer: AccountAuthenticator,
+    },
 }
 
 impl TransactionAuthenticator {
@@ -92,12 +102,43 @@ impl TransactionAuthenticator {
         }
     }
 
+    /// Create the authenticator of a transaction signed by its sender only, whatever the signature
+    /// scheme. Ed25519 and MultiEd25519 signatures keep their own variants, so the transactions
+    /// signed with them stay the same.
+    pub fn single_sender(sender: AccountAuthenticator) -> Self {
+        match sender {
+            AccountAuthenticator::Ed25519 {
+                public_key,
+                signature,
+            } => Self::ed25519(public_key, signature),
+            AccountAuthenticator::MultiEd25519 {
+                public_key,
+                signature,
+            } => Self::multi_ed25519(public_key, signature),
+            sender => Self::SingleSender { sender },
+        }
+    }
+
+    /// Create the authenticator of a transaction sponsored by `fee_payer_address`
+    pub fn fee_payer(
+        sender: AccountAuthenticator,
+        fee_payer_address: AccountAddress,
+        fee_payer: AccountAuthenticator,
+    ) -> Self {
+        Self::FeePayer {
+            sender,
+            fee_payer_address,
+            fee_payer,
+        }
+    }
+
     /// Return Ok if all AccountAuthenticator's public keys match their signatures, Err otherwise
     pub fn verify(&self, raw_txn: &RawTransaction) -> Result<()> {
         let num_sigs: usize = self.sender().number_of_signatures()
             + self
                 .secondary_signers()
                 .iter()
+                .chain(self.fee_payer_signer().iter())
                 .map(|auth| auth.number_of_signatures())
                 .sum::<usize>();
         if num_sigs > MAX_NUM_OF_SIGS {
@@ -127,9 +168,29 @@ impl TransactionAuthenticator {
                 }
                 Ok(())
             }
+            Self::SingleSender { sender } => sender.verify(raw_txn),
+            Self::FeePayer {
+                sender,
+                fee_payer_address,
+                fee_payer,
+            } => {
+                let message =
+                    RawTransactionWithData::new_fee_payer(raw_txn.clone(), *fee_payer_address);
+                sender.verify(&message)?;
+                fee_payer.verify(&message)
+            }
         }
     }
 
+    /// Returns whether any signature of the transaction is of a scheme without its own variant,
+    /// i.e. one verified through `AccountAuthenticator::Generic`.
+    pub fn has_generic_signatures(&self) -> bool {
+        std::iter::once(self.sender())
+            .chain(self.secondary_signers())
+            .chain(self.fee_payer_signer())
+            .any(|signer| matches!(signer, AccountAuthenticator::Generic { .. }))
+    }
+
     pub fn sender(&self) -> AccountAuthenticator {
         match self {
             Self::Ed25519 {
@@ -140,7 +201,9 @@ impl TransactionAuthenticator {
                 public_key,
                 signature,
             } => AccountAuthenticator::multi_ed25519(public_key.clone(), signature.clone()),
-            Self::MultiAgent { sender, .. } => sender.clone(),
+            Self::MultiAgent { sender, .. }
+            | Self::SingleSender { sender }
+            | Self::FeePayer { sender, .. } => sender.clone(),
         }
     }
 
@@ -150,7 +213,9 @@ impl TransactionAuthenticator {
             | Self::MultiEd25519 {
                 public_key: _,
                 signature: _,
-            } => vec![],
+            }
+            | Self::SingleSender { .. }
+            | Self::FeePayer { .. } => vec![],
             Self::MultiAgent {
                 sender: _,
                 secondary_signer_addresses,
@@ -165,7 +230,9 @@ impl TransactionAuthenticator {
             | Self::MultiEd25519 {
                 public_key: _,
                 signature: _,
-            } => vec![],
+            }
+            | Self::SingleSender { .. }
+            | Self::FeePayer { .. } => vec![],
             Self::MultiAgent {
                 sender: _,
                 secondary_signer_addresses: _,
@@ -173,6 +240,25 @@ impl TransactionAuthenticator {
             } => secondary_signers.to_vec(),
         }
     }
+
+    /// Returns the address of the account paying the gas of the transaction, if not the sender
+    pub fn fee_payer_address(&self) -> Option<AccountAddress> {
+        match self {
+            Self::FeePayer {
+                fee_payer_address, ..
+            } => Some(*fee_payer_address),
+            _ => None,
+        }
+    }
+
+    /// Returns the authenticator of the account paying the gas of the transaction, if not the
+    /// sender
+    pub fn fee_payer_signer(&self) -> Option<AccountAuthenticator> {
+        match self {
+            Self::FeePayer { fee_payer, .. } => Some(fee_payer.clone()),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TransactionAuthenticator {
@@ -221,6 +307,29 @@ impl fmt::Display for TransactionAuthenticator {
                     sender, sec_addrs, sec_signers,
                 )
             }
+            Self::SingleSender { sender } => {
+                write!(
+                    f,
+                    "TransactionAuthenticator[scheme: {}, sender: {}]",
+                    sender.scheme(),
+                    sender
+                )
+            }
+            Self::FeePayer {
+                sender,
+                fee_payer_address,
+                fee_payer,
+            } => {
+                write!(
+                    f,
+                    "TransactionAuthenticator[\n\
+                        \tscheme: FeePayer, \n\
+                        \tsender: {}\n\
+                        \tfee payer address: {}\n\
+                        \tfee payer: {}]",
+                    sender, fee_payer_address, fee_payer,
+                )
+            }
         }
     }
 }
@@ -233,12 +342,14 @@ impl fmt::Display for TransactionAuthenticator {
 /// `AuthenticationKeyPreimage`).
 
 // TODO: in the future, can tie these to the AccountAuthenticator enum directly with https://github.com/rust-lang/rust/issues/60553
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum Scheme {
     Ed25519 = 0,
     MultiEd25519 = 1,
-    // ... add more schemes here
+    /// ECDSA over secp256k1, for hardware wallets. Accepted from `DIEM_VERSION_5` on.
+    Secp256k1 = 2,
+    // ... add more schemes here, verified through `AccountAuthenticator::Generic`
 }
 
 impl fmt::Display for Scheme {
@@ -246,6 +357,7 @@ impl fmt::Display for Scheme {
         let display = match self {
             Scheme::Ed25519 => "Ed25519",
             Scheme::MultiEd25519 => "MultiEd25519",
+            Scheme::Secp256k1 => "Secp256k1",
         };
         write!(f, "Scheme::{}", display)
     }
@@ -263,7 +375,15 @@ pub enum AccountAuthenticator {
         public_key: MultiEd25519PublicKey,
         signature: MultiEd25519Signature,
     },
-    // ... add more schemes here
+    /// Single signature of any other scheme, with the key and signature in the encoding of the
+    /// scheme. New schemes only need a `Scheme` identifier and a verifier in `verify_generic`.
+    Generic {
+        scheme: Scheme,
+        #[serde(with = "serde_bytes")]
+        public_key: Vec<u8>,
+        #[serde(with = "serde_bytes")]
+        signature: Vec<u8>,
+    },
 }
 
 impl AccountAuthenticator {
@@ -272,6 +392,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { .. } => Scheme::Ed25519,
             Self::MultiEd25519 { .. } => Scheme::MultiEd25519,
+            Self::Generic { scheme, .. } => *scheme,
         }
     }
 
@@ -294,6 +415,15 @@ impl AccountAuthenticator {
         }
     }
 
+    /// Create a single-signature authenticator of a scheme without its own variant
+    pub fn generic(scheme: Scheme, public_key: Vec<u8>, signature: Vec<u8>) -> Self {
+        Self::Generic {
+            scheme,
+            public_key,
+            signature,
+        }
+    }
+
     /// Return Ok if the authenticator's public key matches its signature, Err otherwise
     pub fn verify<T: Serialize + CryptoHash>(&self, message: &T) -> Result<()> {
         match self {
@@ -305,6 +435,11 @@ impl AccountAuthenticator {
                 public_key,
                 signature,
             } => signature.verify(message, public_key),
+            Self::Generic {
+                scheme,
+                public_key,
+                signature,
+            } => verify_generic(*scheme, public_key, signature, message),
         }
     }
 
@@ -313,6 +448,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { public_key, .. } => public_key.to_bytes().to_vec(),
             Self::MultiEd25519 { public_key, .. } => public_key.to_bytes().to_vec(),
+            Self::Generic { public_key, .. } => public_key.clone(),
         }
     }
 
@@ -321,6 +457,7 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { signature, .. } => signature.to_bytes().to_vec(),
             Self::MultiEd25519 { signature, .. } => signature.to_bytes().to_vec(),
+            Self::Generic { signature, .. } => signature.clone(),
         }
     }
 
@@ -339,7 +476,62 @@ impl AccountAuthenticator {
         match self {
             Self::Ed25519 { .. } => 1,
             Self::MultiEd25519 { signature, .. } => signature.signatures().len(),
+            Self::Generic { .. } => 1,
+        }
+    }
+}
+
+/// Verifies `signature` of `message` by `public_key`, in the encoding of `scheme`, for the schemes
+/// verified through `AccountAuthenticator::Generic`.
+fn verify_generic<T: Serialize + CryptoHash>(
+    scheme: Scheme,
+    public_key: &[u8],
+    signature: &[u8],
+    message: &T,
+) -> Result<()> {
+    match scheme {
+        Scheme::Ed25519 | Scheme::MultiEd25519 => {
+            bail!("{} signatures have their own authenticator", scheme)
         }
+        Scheme::Secp256k1 => verify_secp256k1(public_key, signature, message),
+    }
+}
+
+fn verify_secp256k1<T: Serialize + CryptoHash>(
+    public_key: &[u8],
+    signature: &[u8],
+    message: &T,
+) -> Result<()> {
+    let public_key = Secp256k1PublicKey::try_from(public_key)?;
+    Secp256k1Signature::try_from(signature)?.verify(message, &public_key)
+}
+
+/// A private key signing as an account, whatever the scheme of the key, so that code signing
+/// transactions needn't change for every new scheme.
+pub trait AccountSigner {
+    /// Signs `message`, returning the signature with the public key to verify it.
+    fn sign_message<T: Serialize + CryptoHash>(&self, message: &T) -> AccountAuthenticator;
+}
+
+impl AccountSigner for Ed25519PrivateKey {
+    fn sign_message<T: Serialize + CryptoHash>(&self, message: &T) -> AccountAuthenticator {
+        AccountAuthenticator::ed25519(self.public_key(), self.sign(message))
+    }
+}
+
+impl AccountSigner for MultiEd25519PrivateKey {
+    fn sign_message<T: Serialize + CryptoHash>(&self, message: &T) -> AccountAuthenticator {
+        AccountAuthenticator::multi_ed25519(self.public_key(), self.sign(message))
+    }
+}
+
+impl AccountSigner for Secp256k1PrivateKey {
+    fn sign_message<T: Serialize + CryptoHash>(&self, message: &T) -> AccountAuthenticator {
+        AccountAuthenticator::generic(
+            Scheme::Secp256k1,
+            self.public_key().to_bytes().to_vec(),
+            self.sign(message).to_bytes().to_vec(),
+        )
     }
 }
 
@@ -385,6 +577,11 @@ impl AuthenticationKey {
         Self::from_preimage(&AuthenticationKeyPreimage::multi_ed25519(public_key))
     }
 
+    /// Create an authentication key from a secp256k1 public key
+    pub fn secp256k1(public_key: &Secp256k1PublicKey) -> Self {
+        Self::from_preimage(&AuthenticationKeyPreimage::secp256k1(public_key))
+    }
+
     /// Return an address derived from the last `AccountAddress::LENGTH` bytes of this
     /// authentication key.
     pub fn derived_address(&self) -> AccountAddress {
@@ -440,6 +637,11 @@ impl AuthenticationKeyPreimage {
         Self::new(public_key.to_bytes(), Scheme::MultiEd25519)
     }
 
+    /// Construct a preimage from a secp256k1 public key
+    pub fn secp256k1(public_key: &Secp256k1PublicKey) -> AuthenticationKeyPreimage {
+        Self::new(public_key.to_bytes().to_vec(), Scheme::Secp256k1)
+    }
+
     /// Construct a vector from this authentication key
     pub fn into_vec(self) -> Vec<u8> {
         self.0
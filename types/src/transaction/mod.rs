@@ -10,7 +10,7 @@ use crate::{
     contract_event::ContractEvent,
     ledger_info::LedgerInfo,
     proof::{accumulator::InMemoryAccumulator, TransactionInfoWithProof, TransactionListProof},
-    transaction::authenticator::{AccountAuthenticator, TransactionAuthenticator},
+    transaction::authenticator::{AccountAuthenticator, AccountSigner, TransactionAuthenticator},
     vm_status::{DiscardedVMStatus, KeptVMStatus, StatusCode, StatusType, VMStatus},
     write_set::WriteSet,
 };
@@ -40,17 +40,21 @@ pub mod helpers;
 pub mod metadata;
 mod module;
 mod script;
+mod trace_id;
 mod transaction_argument;
+mod write_set_builder;
 
 pub use change_set::ChangeSet;
-pub use module::Module;
+pub use module::{Module, ModuleBundle};
 pub use script::{
     ArgumentABI, Script, ScriptABI, ScriptFunction, ScriptFunctionABI, TransactionScriptABI,
     TypeArgumentABI,
 };
 
 use std::{collections::BTreeSet, ops::Deref};
+pub use trace_id::TraceId;
 pub use transaction_argument::{parse_transaction_argument, TransactionArgument};
+pub use write_set_builder::{AffectedAccessPath, DryRunWriteSet, WriteSetBuilder};
 
 pub type Version = u64; // Height - also used for MVCC in StateDB
 
@@ -196,6 +200,31 @@ impl RawTransaction {
         }
     }
 
+    /// Create a new `RawTransaction` with a module bundle.
+    ///
+    /// The modules are published in order, all of them or none of them.
+    pub fn new_module_bundle(
+        sender: AccountAddress,
+        sequence_number: u64,
+        modules: ModuleBundle,
+        max_gas_amount: u64,
+        gas_unit_price: u64,
+        gas_currency_code: String,
+        expiration_timestamp_secs: u64,
+        chain_id: ChainId,
+    ) -> Self {
+        RawTransaction {
+            sender,
+            sequence_number,
+            payload: TransactionPayload::ModuleBundle(modules),
+            max_gas_amount,
+            gas_unit_price,
+            gas_currency_code,
+            expiration_timestamp_secs,
+            chain_id,
+        }
+    }
+
     pub fn new_write_set(
         sender: AccountAddress,
         sequence_number: u64,
@@ -269,6 +298,15 @@ impl RawTransaction {
         )))
     }
 
+    /// Signs the given `RawTransaction` with `signer`, the private key of the sender of any
+    /// signature scheme.
+    pub fn sign_with<S: AccountSigner>(self, signer: &S) -> Result<SignatureCheckedTransaction> {
+        let sender = signer.sign_message(&self);
+        Ok(SignatureCheckedTransaction(
+            SignedTransaction::new_single_sender(self, sender),
+        ))
+    }
+
     /// Signs the given multi-agent `RawTransaction`, which is a transaction with secondary
     /// signers in addition to a sender. The private keys of the sender and the
     /// secondary signers are used to sign the transaction.
@@ -313,6 +351,22 @@ impl RawTransaction {
         ))
     }
 
+    /// Signs the given `RawTransaction` on behalf of its sender and of `fee_payer_address`,
+    /// which pays for its gas. Both sign the transaction together with the fee payer address.
+    pub fn sign_fee_payer<S: AccountSigner, F: AccountSigner>(
+        self,
+        sender_signer: &S,
+        fee_payer_address: AccountAddress,
+        fee_payer_signer: &F,
+    ) -> Result<SignatureCheckedTransaction> {
+        let message = RawTransactionWithData::new_fee_payer(self.clone(), fee_payer_address);
+        let sender = sender_signer.sign_message(&message);
+        let fee_payer = fee_payer_signer.sign_message(&message);
+        Ok(SignatureCheckedTransaction(
+            SignedTransaction::new_fee_payer(self, sender, fee_payer_address, fee_payer),
+        ))
+    }
+
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn multi_sign_for_testing(
         self,
@@ -341,6 +395,7 @@ impl RawTransaction {
                 script_fn.args().to_vec(),
             ),
             TransactionPayload::Module(_) => ("module publishing".to_string(), vec![]),
+            TransactionPayload::ModuleBundle(_) => ("module bundle publishing".to_string(), vec![]),
         };
         let mut f_args: String = "".to_string();
         for arg in args {
@@ -386,6 +441,10 @@ pub enum RawTransactionWithData {
         raw_txn: RawTransaction,
         secondary_signer_addresses: Vec<AccountAddress>,
     },
+    FeePayer {
+        raw_txn: RawTransaction,
+        fee_payer_address: AccountAddress,
+    },
 }
 
 impl RawTransactionWithData {
@@ -398,6 +457,13 @@ impl RawTransactionWithData {
             secondary_signer_addresses,
         }
     }
+
+    pub fn new_fee_payer(raw_txn: RawTransaction, fee_payer_address: AccountAddress) -> Self {
+        Self::FeePayer {
+            raw_txn,
+            fee_payer_address,
+        }
+    }
 }
 
 /// Different kinds of transactions.
@@ -411,13 +477,17 @@ pub enum TransactionPayload {
     Module(Module),
     /// A transaction that executes an existing script function published on-chain.
     ScriptFunction(ScriptFunction),
+    /// A transaction that publishes modules depending on each other, atomically.
+    ModuleBundle(ModuleBundle),
 }
 
 impl TransactionPayload {
     pub fn should_trigger_reconfiguration_by_default(&self) -> bool {
         match self {
             Self::WriteSet(ws) => ws.should_trigger_reconfiguration_by_default(),
-            Self::Script(_) | Self::ScriptFunction(_) | Self::Module(_) => false,
+            Self::Script(_) | Self::ScriptFunction(_) | Self::Module(_) | Self::ModuleBundle(_) => {
+                false
+            }
         }
     }
 
@@ -533,6 +603,14 @@ impl SignedTransaction {
         }
     }
 
+    /// Creates a transaction signed by its sender only, with a signature of any scheme.
+    pub fn new_single_sender(raw_txn: RawTransaction, sender: AccountAuthenticator) -> Self {
+        SignedTransaction {
+            raw_txn,
+            authenticator: TransactionAuthenticator::single_sender(sender),
+        }
+    }
+
     pub fn new_multi_agent(
         raw_txn: RawTransaction,
         sender: AccountAuthenticator,
@@ -549,6 +627,23 @@ impl SignedTransaction {
         }
     }
 
+    /// Creates a transaction whose gas is paid by `fee_payer_address` rather than by its sender.
+    pub fn new_fee_payer(
+        raw_txn: RawTransaction,
+        sender: AccountAuthenticator,
+        fee_payer_address: AccountAddress,
+        fee_payer: AccountAuthenticator,
+    ) -> Self {
+        SignedTransaction {
+            raw_txn,
+            authenticator: TransactionAuthenticator::fee_payer(
+                sender,
+                fee_payer_address,
+                fee_payer,
+            ),
+        }
+    }
+
     pub fn authenticator(&self) -> TransactionAuthenticator {
         self.authenticator.clone()
     }
@@ -604,6 +699,7 @@ impl SignedTransaction {
 
     pub fn contains_duplicate_signers(&self) -> bool {
         let mut all_signer_addresses = self.authenticator.secondary_signer_addreses();
+        all_signer_addresses.extend(self.authenticator.fee_payer_address());
         all_signer_addresses.push(self.sender());
         let mut s = BTreeSet::new();
         all_signer_addresses.iter().any(|a| !s.insert(*a))
@@ -626,6 +722,16 @@ impl SignedTransaction {
             TransactionAuthenticator::MultiAgent { .. }
         )
     }
+
+    /// Returns the address of the account paying the gas of the transaction, if not the sender.
+    pub fn fee_payer(&self) -> Option<AccountAddress> {
+        self.authenticator.fee_payer_address()
+    }
+
+    /// Returns the ID the transaction is logged with, see `TraceId`.
+    pub fn trace_id(&self) -> TraceId {
+        TraceId::from_hash(&Transaction::UserTransaction(self.clone()).hash())
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -991,6 +1097,7 @@ impl Display for TransactionInfo {
 pub struct TransactionToCommit {
     transaction: Transaction,
     account_states: HashMap<AccountAddress, AccountStateBlob>,
+    write_set: WriteSet,
     events: Vec<ContractEvent>,
     gas_used: u64,
     status: KeptVMStatus,
@@ -1000,6 +1107,7 @@ impl TransactionToCommit {
     pub fn new(
         transaction: Transaction,
         account_states: HashMap<AccountAddress, AccountStateBlob>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         gas_used: u64,
         status: KeptVMStatus,
@@ -1007,6 +1115,7 @@ impl TransactionToCommit {
         TransactionToCommit {
             transaction,
             account_states,
+            write_set,
             events,
             gas_used,
             status,
@@ -1021,6 +1130,10 @@ impl TransactionToCommit {
         &self.account_states
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -27,3 +27,39 @@ impl fmt::Debug for Module {
             .finish()
     }
 }
+
+/// Modules published together by one transaction, in order, so that modules depending on each
+/// other are published all at once or not at all. A module may only depend on modules published
+/// before it, either on chain or earlier in the bundle.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ModuleBundle {
+    codes: Vec<Module>,
+}
+
+impl ModuleBundle {
+    pub fn new(codes: Vec<Vec<u8>>) -> ModuleBundle {
+        ModuleBundle {
+            codes: codes.into_iter().map(Module::new).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Module> {
+        self.codes.iter()
+    }
+
+    pub fn into_inner(self) -> Vec<Vec<u8>> {
+        self.codes.into_iter().map(|module| module.code).collect()
+    }
+}
+
+impl From<Module> for ModuleBundle {
+    fn from(module: Module) -> ModuleBundle {
+        ModuleBundle {
+            codes: vec![module],
+        }
+    }
+}
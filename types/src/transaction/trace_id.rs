@@ -0,0 +1,30 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_crypto::HashValue;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Identifies a user transaction in the logs of every subsystem and node it goes through, from
+/// submission to commit. It is the prefix of the transaction hash, so clients, admission control
+/// and peers receiving the transaction all derive the same one, and operators can grep for the
+/// hash a client was returned.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct TraceId([u8; TraceId::LENGTH]);
+
+impl TraceId {
+    pub const LENGTH: usize = 8;
+
+    /// The trace ID of the transaction with hash `hash`.
+    pub fn from_hash(hash: &HashValue) -> Self {
+        let mut bytes = [0u8; Self::LENGTH];
+        bytes.copy_from_slice(&hash.as_ref()[..Self::LENGTH]);
+        Self(bytes)
+    }
+}
+
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
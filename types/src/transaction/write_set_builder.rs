@@ -0,0 +1,183 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Construction of the write set transactions used for admin operations: patching the state of
+//! accounts directly, or hot-upgrading modules on private networks.
+//!
+//! A write set is built with a `WriteSetBuilder`, reviewed through its `preview`, then dry-run
+//! against the state of the network into a `DryRunWriteSet`, the only form in which it can be
+//! turned into a transaction.
+
+use crate::{
+    access_path::{self, AccessPath},
+    account_address::AccountAddress,
+    chain_id::{ChainId, NamedChain},
+    transaction::{ChangeSet, TransactionOutput, TransactionStatus, WriteSetPayload},
+    vm_status::KeptVMStatus,
+    write_set::{WriteOp, WriteSetMut},
+};
+use anyhow::{bail, ensure, Result};
+use move_core_types::{
+    language_storage::{ModuleId, ResourceKey, StructTag},
+    move_resource::MoveResource,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Builds the change set of a direct write set transaction. Each access path is written at most
+/// once, so a write can't silently override an earlier one.
+#[derive(Clone, Debug)]
+pub struct WriteSetBuilder {
+    chain_id: ChainId,
+    writes: BTreeMap<AccessPath, WriteOp>,
+}
+
+impl WriteSetBuilder {
+    /// Starts a write set for the network `chain_id`.
+    pub fn new(chain_id: ChainId) -> Self {
+        Self {
+            chain_id,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Writes `resource` under `address`, replacing the one it holds if any.
+    pub fn write_resource<T: MoveResource + Serialize>(
+        self,
+        address: AccountAddress,
+        resource: &T,
+    ) -> Result<Self> {
+        self.write_resource_bytes(address, T::struct_tag(), bcs::to_bytes(resource)?)
+    }
+
+    /// Writes the BCS serialized resource `bytes` of type `struct_tag` under `address`.
+    pub fn write_resource_bytes(
+        self,
+        address: AccountAddress,
+        struct_tag: StructTag,
+        bytes: Vec<u8>,
+    ) -> Result<Self> {
+        self.write(
+            AccessPath::resource_access_path(ResourceKey::new(address, struct_tag)),
+            WriteOp::Value(bytes),
+        )
+    }
+
+    /// Deletes the resource of type `struct_tag` under `address`.
+    pub fn delete_resource(self, address: AccountAddress, struct_tag: StructTag) -> Result<Self> {
+        self.write(
+            AccessPath::resource_access_path(ResourceKey::new(address, struct_tag)),
+            WriteOp::Deletion,
+        )
+    }
+
+    /// Publishes the compiled module `bytes` as `module_id`, replacing the published one if any.
+    /// Modules are only hot-upgraded on private networks: on mainnet, they're upgraded through the
+    /// governance flow.
+    pub fn publish_module(self, module_id: ModuleId, bytes: Vec<u8>) -> Result<Self> {
+        ensure!(
+            self.chain_id.id() != NamedChain::MAINNET.id(),
+            "Modules can't be hot-upgraded on {}",
+            NamedChain::MAINNET
+        );
+        self.write(
+            AccessPath::code_access_path(module_id),
+            WriteOp::Value(bytes),
+        )
+    }
+
+    fn write(mut self, access_path: AccessPath, op: WriteOp) -> Result<Self> {
+        if let Some(written) = self.writes.get(&access_path) {
+            bail!(
+                "Conflicting writes: {}, then {}",
+                AffectedAccessPath::new(&access_path, written),
+                AffectedAccessPath::new(&access_path, &op)
+            );
+        }
+        self.writes.insert(access_path, op);
+        Ok(self)
+    }
+
+    /// Lists the access paths the write set affects, for review before it's dry-run.
+    pub fn preview(&self) -> Vec<AffectedAccessPath> {
+        self.writes
+            .iter()
+            .map(|(access_path, op)| AffectedAccessPath::new(access_path, op))
+            .collect()
+    }
+
+    pub fn build(self) -> Result<WriteSetPayload> {
+        ensure!(!self.writes.is_empty(), "The write set is empty");
+        let write_set = WriteSetMut::new(self.writes.into_iter().collect()).freeze()?;
+        Ok(WriteSetPayload::Direct(ChangeSet::new(write_set, vec![])))
+    }
+}
+
+/// An access path written by a write set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AffectedAccessPath {
+    pub address: AccountAddress,
+    pub path: access_path::Path,
+    /// Size of the written value, `None` for deletions.
+    pub size: Option<usize>,
+}
+
+impl AffectedAccessPath {
+    fn new(access_path: &AccessPath, op: &WriteOp) -> Self {
+        Self {
+            address: access_path.address,
+            path: access_path.get_path(),
+            size: match op {
+                WriteOp::Value(bytes) => Some(bytes.len()),
+                WriteOp::Deletion => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for AffectedAccessPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, name) = match &self.path {
+            access_path::Path::Code(module_id) => ("module", module_id.to_string()),
+            access_path::Path::Resource(struct_tag) => ("resource", struct_tag.to_string()),
+        };
+        match self.size {
+            Some(size) => write!(
+                f,
+                "write {} {} under {} ({} bytes)",
+                kind, name, self.address, size
+            ),
+            None => write!(f, "delete {} {} under {}", kind, name, self.address),
+        }
+    }
+}
+
+/// A write set executed successfully by a dry-run against the state of the network it's for.
+#[derive(Clone, Debug)]
+pub struct DryRunWriteSet {
+    payload: WriteSetPayload,
+    output: TransactionOutput,
+}
+
+impl DryRunWriteSet {
+    /// Checks the `output` of dry-running `payload`.
+    pub fn new(payload: WriteSetPayload, output: TransactionOutput) -> Result<Self> {
+        match output.status() {
+            TransactionStatus::Keep(KeptVMStatus::Executed) => Ok(Self { payload, output }),
+            status => bail!("The dry-run of the write set failed: {:?}", status),
+        }
+    }
+
+    pub fn payload(&self) -> &WriteSetPayload {
+        &self.payload
+    }
+
+    /// The output of the dry-run, including the writes of the prologue and epilogue.
+    pub fn output(&self) -> &TransactionOutput {
+        &self.output
+    }
+
+    pub fn into_payload(self) -> WriteSetPayload {
+        self.payload
+    }
+}
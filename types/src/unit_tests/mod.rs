@@ -10,4 +10,5 @@ mod currency_code_test;
 mod transaction_test;
 mod trusted_state_test;
 mod validator_set_test;
+mod write_set_builder_test;
 mod write_set_test;
@@ -6,14 +6,16 @@ use crate::{
     account_config::XUS_NAME,
     chain_id::ChainId,
     transaction::{
-        metadata, GovernanceRole, RawTransaction, Script, SignedTransaction, Transaction,
+        authenticator::{AccountAuthenticator, Scheme, TransactionAuthenticator},
+        metadata, GovernanceRole, RawTransaction, Script, SignedTransaction, TraceId, Transaction,
         TransactionInfo, TransactionListWithProof, TransactionPayload, TransactionWithProof,
     },
 };
 use bcs::test_helpers::assert_canonical_encode_decode;
 use diem_crypto::{
     ed25519::{self, Ed25519PrivateKey, Ed25519Signature},
-    PrivateKey, Uniform,
+    hash::CryptoHash,
+    PrivateKey, SigningKey, Uniform,
 };
 use proptest::prelude::*;
 use std::convert::TryFrom;
@@ -38,6 +40,45 @@ fn test_invalid_signature() {
         .expect_err("signature checking should fail");
 }
 
+#[test]
+fn test_unsupported_scheme() {
+    let raw_txn = RawTransaction::new_script(
+        AccountAddress::random(),
+        0,
+        Script::new(vec![], vec![], vec![]),
+        0,
+        0,
+        XUS_NAME.to_owned(),
+        0,
+        ChainId::test(),
+    );
+    // Ed25519 signatures only verify with their own authenticator.
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let signature = private_key.sign(&raw_txn);
+    let txn = SignedTransaction::new_single_sender(
+        raw_txn.clone(),
+        AccountAuthenticator::generic(
+            Scheme::Ed25519,
+            private_key.public_key().to_bytes().to_vec(),
+            signature.to_bytes().to_vec(),
+        ),
+    );
+    assert!(matches!(
+        txn.authenticator(),
+        TransactionAuthenticator::SingleSender { .. }
+    ));
+    txn.check_signature()
+        .expect_err("signature checking should fail");
+
+    // Nor do malformed secp256k1 keys and signatures.
+    let txn = SignedTransaction::new_single_sender(
+        raw_txn,
+        AccountAuthenticator::generic(Scheme::Secp256k1, vec![2; 33], vec![1; 64]),
+    );
+    txn.check_signature()
+        .expect_err("signature checking should fail");
+}
+
 #[test]
 fn test_role_ordering() {
     use GovernanceRole::*;
@@ -89,6 +130,65 @@ proptest! {
         assert!(signed_txn.check_signature().is_ok());
     }
 
+    #[test]
+    fn test_sign_with(raw_txn in any::<RawTransaction>(), keypair in ed25519::keypair_strategy()) {
+        let signed_txn = raw_txn.clone().sign_with(&keypair.private_key).unwrap().into_inner();
+        assert!(matches!(signed_txn.authenticator(), TransactionAuthenticator::Ed25519 { .. }));
+        assert_eq!(
+            signed_txn,
+            raw_txn.sign(&keypair.private_key, keypair.public_key).unwrap().into_inner()
+        );
+    }
+
+    #[test]
+    fn test_sign_with_secp256k1(
+        raw_txn in any::<RawTransaction>(),
+        keypair in diem_crypto::secp256k1::keypair_strategy(),
+        other_raw_txn in any::<RawTransaction>(),
+    ) {
+        let signed_txn = raw_txn.clone().sign_with(&keypair.private_key).unwrap().into_inner();
+        let sender = signed_txn.authenticator().sender();
+        assert!(matches!(sender, AccountAuthenticator::Generic { scheme: Scheme::Secp256k1, .. }));
+        assert!(signed_txn.clone().check_signature().is_ok());
+        assert_canonical_encode_decode(signed_txn);
+
+        // The signature doesn't verify for another transaction.
+        if other_raw_txn != raw_txn {
+            let forged_txn = SignedTransaction::new_single_sender(other_raw_txn, sender);
+            assert!(forged_txn.check_signature().is_err());
+        }
+    }
+
+    #[test]
+    fn test_sign_fee_payer(
+        raw_txn in any::<RawTransaction>(),
+        sender in ed25519::keypair_strategy(),
+        fee_payer_address in any::<AccountAddress>(),
+        fee_payer in diem_crypto::secp256k1::keypair_strategy(),
+        other_fee_payer_address in any::<AccountAddress>(),
+    ) {
+        let signed_txn = raw_txn
+            .sign_fee_payer(&sender.private_key, fee_payer_address, &fee_payer.private_key)
+            .unwrap()
+            .into_inner();
+        assert_eq!(signed_txn.fee_payer(), Some(fee_payer_address));
+        assert!(signed_txn.authenticator().has_generic_signatures());
+        assert!(signed_txn.clone().check_signature().is_ok());
+        assert_canonical_encode_decode(signed_txn.clone());
+
+        // The signatures don't verify for another fee payer.
+        if other_fee_payer_address != fee_payer_address {
+            let authenticator = signed_txn.authenticator();
+            let forged_txn = SignedTransaction::new_fee_payer(
+                signed_txn.into_raw_transaction(),
+                authenticator.sender(),
+                other_fee_payer_address,
+                authenticator.fee_payer_signer().unwrap(),
+            );
+            assert!(forged_txn.check_signature().is_err());
+        }
+    }
+
     #[test]
     fn transaction_payload_bcs_roundtrip(txn_payload in any::<TransactionPayload>()) {
         assert_canonical_encode_decode(txn_payload);
@@ -108,6 +208,14 @@ proptest! {
     fn transaction_info_bcs_roundtrip(txn_info in any::<TransactionInfo>()) {
         assert_canonical_encode_decode(txn_info);
     }
+
+    #[test]
+    fn trace_id_is_hash_prefix(signed_txn in any::<SignedTransaction>()) {
+        let hash = Transaction::UserTransaction(signed_txn.clone()).hash();
+        let trace_id = signed_txn.trace_id();
+        assert_eq!(trace_id, TraceId::from_hash(&hash));
+        assert!(hash.to_hex().starts_with(&trace_id.to_string()));
+    }
 }
 
 proptest! {
@@ -0,0 +1,82 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    access_path,
+    account_address::AccountAddress,
+    account_config::{BalanceResource, CORE_CODE_ADDRESS},
+    chain_id::{ChainId, NamedChain},
+    transaction::{
+        DryRunWriteSet, TransactionOutput, TransactionStatus, WriteSetBuilder, WriteSetPayload,
+    },
+    vm_status::{KeptVMStatus, StatusCode},
+    write_set::WriteSet,
+};
+use move_core_types::{
+    identifier::Identifier, language_storage::ModuleId, move_resource::MoveStructType,
+};
+
+#[test]
+fn test_write_set_builder() {
+    let address = AccountAddress::random();
+    let module_id = ModuleId::new(CORE_CODE_ADDRESS, Identifier::new("Patched").unwrap());
+    let builder = WriteSetBuilder::new(ChainId::test())
+        .write_resource(address, &BalanceResource::new(100))
+        .unwrap()
+        .publish_module(module_id.clone(), vec![0; 10])
+        .unwrap();
+
+    let preview = builder.preview();
+    assert_eq!(preview.len(), 2);
+    assert!(preview.iter().any(|affected| affected.address == address
+        && affected.path == access_path::Path::Resource(BalanceResource::struct_tag())
+        && affected.size == Some(8)));
+    assert!(preview
+        .iter()
+        .any(|affected| affected.address == CORE_CODE_ADDRESS
+            && affected.path == access_path::Path::Code(module_id.clone())
+            && affected.size == Some(10)));
+
+    // Access paths are written at most once
+    builder
+        .clone()
+        .delete_resource(address, BalanceResource::struct_tag())
+        .unwrap_err();
+
+    match builder.build().unwrap() {
+        WriteSetPayload::Direct(change_set) => assert_eq!(change_set.write_set().iter().count(), 2),
+        WriteSetPayload::Script { .. } => panic!("Expected a direct write set"),
+    }
+    WriteSetBuilder::new(ChainId::test()).build().unwrap_err();
+
+    // Modules aren't hot-upgraded on mainnet
+    WriteSetBuilder::new(ChainId::new(NamedChain::MAINNET.id()))
+        .publish_module(module_id, vec![0; 10])
+        .unwrap_err();
+}
+
+#[test]
+fn test_dry_run_write_set() {
+    let payload = WriteSetBuilder::new(ChainId::test())
+        .write_resource(AccountAddress::random(), &BalanceResource::new(100))
+        .unwrap()
+        .build()
+        .unwrap();
+    let output = |status| TransactionOutput::new(WriteSet::default(), vec![], 0, status);
+
+    DryRunWriteSet::new(
+        payload.clone(),
+        output(TransactionStatus::Keep(KeptVMStatus::Executed)),
+    )
+    .unwrap();
+    DryRunWriteSet::new(
+        payload.clone(),
+        output(TransactionStatus::Keep(KeptVMStatus::MiscellaneousError)),
+    )
+    .unwrap_err();
+    DryRunWriteSet::new(
+        payload,
+        output(TransactionStatus::Discard(StatusCode::REJECTED_WRITE_SET)),
+    )
+    .unwrap_err();
+}
@@ -6,7 +6,7 @@ use anyhow::Result;
 use diem_state_view::StateView;
 use diem_types::{
     account_address::AccountAddress,
-    on_chain_config::OnChainConfigPayload,
+    on_chain_config::{OnChainConfigPayload, VMConfig},
     transaction::{GovernanceRole, SignedTransaction, VMValidatorResult},
     vm_status::StatusCode,
 };
@@ -74,4 +74,10 @@ impl TransactionValidation for MockVMValidator {
     fn restart(&mut self, _config: OnChainConfigPayload) -> Result<()> {
         unimplemented!();
     }
+
+    fn vm_config(&self) -> Option<&VMConfig> {
+        None
+    }
+
+    fn notify_commit(&self) {}
 }
@@ -90,6 +90,32 @@ fn test_validate_transaction() {
     assert_eq!(ret.status(), None);
 }
 
+#[test]
+fn test_validate_transaction_account_cache() {
+    let vm_validator = TestValidator::new();
+
+    let address = account_config::diem_root_address();
+    let program = encode_peer_to_peer_with_metadata_script(xus_tag(), address, 100, vec![], vec![]);
+    let transaction = transaction_test_helpers::get_test_signed_txn(
+        address,
+        1,
+        &vm_genesis::GENESIS_KEYPAIR.0,
+        vm_genesis::GENESIS_KEYPAIR.1.clone(),
+        Some(program),
+    );
+    // The sender is the Diem root, so only one account is read and cached.
+    for _ in 0..2 {
+        let ret = vm_validator
+            .validate_transaction(transaction.clone())
+            .unwrap();
+        assert_eq!(ret.status(), None);
+        assert_eq!(vm_validator.account_cache.lock().accounts.len(), 1);
+    }
+
+    vm_validator.notify_commit();
+    assert!(vm_validator.account_cache.lock().accounts.is_empty());
+}
+
 #[test]
 fn test_validate_invalid_signature() {
     let vm_validator = TestValidator::new();
@@ -2,18 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::Result;
+use diem_infallible::Mutex;
 use diem_state_view::StateViewId;
 use diem_types::{
     account_address::AccountAddress,
-    account_config::AccountResource,
+    account_config::{diem_root_address, AccountResource},
     on_chain_config::{DiemVersion, OnChainConfigPayload, VMConfig, VMPublishingOption},
-    transaction::{SignedTransaction, VMValidatorResult},
+    transaction::{SignedTransaction, VMValidatorResult, Version},
 };
 use diem_vm::DiemVMValidator;
 use fail::fail_point;
 use scratchpad::SparseMerkleTree;
-use std::{convert::TryFrom, sync::Arc};
-use storage_interface::{state_view::VerifiedStateView, DbReader};
+use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+use storage_interface::{
+    state_view::{AccountWithProof, VerifiedStateView},
+    DbReader,
+};
 
 #[cfg(test)]
 #[path = "unit_tests/vm_validator_test.rs"]
@@ -27,12 +31,56 @@ pub trait TransactionValidation: Send + Sync + Clone {
 
     /// Restart the transaction validation instance
     fn restart(&mut self, config: OnChainConfigPayload) -> Result<()>;
+
+    /// On-chain VM config, holding the gas schedule, of the validation instance
+    fn vm_config(&self) -> Option<&VMConfig>;
+
+    /// Notify the validation instance that transactions were committed, so state it read from
+    /// storage before is stale
+    fn notify_commit(&self);
+}
+
+/// Maximum number of accounts cached for a version; further accounts are read from storage on
+/// every validation.
+const MAX_CACHED_ACCOUNTS: usize = 10_000;
+
+/// Account states read from storage at a version, shared by the validations against that version so
+/// bursts of transactions from the same sender read its account once.
+#[derive(Default)]
+struct AccountCache {
+    version: Option<Version>,
+    accounts: HashMap<AccountAddress, AccountWithProof>,
+}
+
+impl AccountCache {
+    fn get(&self, version: Version, address: &AccountAddress) -> Option<AccountWithProof> {
+        if self.version != Some(version) {
+            return None;
+        }
+        self.accounts.get(address).cloned()
+    }
+
+    fn insert(&mut self, version: Version, address: AccountAddress, account: AccountWithProof) {
+        if self.version != Some(version) {
+            self.version = Some(version);
+            self.accounts.clear();
+        }
+        if self.accounts.len() < MAX_CACHED_ACCOUNTS {
+            self.accounts.insert(address, account);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.version = None;
+        self.accounts.clear();
+    }
 }
 
 #[derive(Clone)]
 pub struct VMValidator {
     db_reader: Arc<dyn DbReader>,
     vm: DiemVMValidator,
+    account_cache: Arc<Mutex<AccountCache>>,
 }
 
 impl VMValidator {
@@ -48,7 +96,38 @@ impl VMValidator {
         );
 
         let vm = DiemVMValidator::new(&state_view);
-        VMValidator { db_reader, vm }
+        VMValidator {
+            db_reader,
+            vm,
+            account_cache: Arc::new(Mutex::new(AccountCache::default())),
+        }
+    }
+
+    /// Returns the states of the accounts at `addresses` at `version`, read from storage only if
+    /// not cached yet.
+    fn cached_accounts(
+        &self,
+        version: Version,
+        addresses: &[AccountAddress],
+    ) -> Result<HashMap<AccountAddress, AccountWithProof>> {
+        let mut accounts = HashMap::new();
+        for address in addresses {
+            let cached = self.account_cache.lock().get(version, address);
+            let account = match cached {
+                Some(account) => account,
+                None => {
+                    let account = self
+                        .db_reader
+                        .get_account_state_with_proof_by_version(*address, version)?;
+                    self.account_cache
+                        .lock()
+                        .insert(version, *address, account.clone());
+                    account
+                }
+            };
+            accounts.insert(*address, account);
+        }
+        Ok(accounts)
     }
 }
 
@@ -64,6 +143,9 @@ impl TransactionValidation for VMValidator {
         use diem_vm::VMValidator;
 
         let (version, state_root) = self.db_reader.get_latest_state_root()?;
+        // Every validation reads the sender's account and the on-chain state under the Diem root.
+        let prefetched_accounts =
+            self.cached_accounts(version, &[txn.sender(), diem_root_address()])?;
         let db_reader = Arc::clone(&self.db_reader);
         let vm = self.vm.clone();
 
@@ -76,7 +158,8 @@ impl TransactionValidation for VMValidator {
             Some(version),
             state_root,
             &smt,
-        );
+        )
+        .with_prefetched_accounts(prefetched_accounts);
 
         Ok(vm.validate_transaction(txn, &state_view))
     }
@@ -89,6 +172,14 @@ impl TransactionValidation for VMValidator {
         self.vm = DiemVMValidator::init_with_config(version, vm_config, publishing_option);
         Ok(())
     }
+
+    fn vm_config(&self) -> Option<&VMConfig> {
+        self.vm.vm_config()
+    }
+
+    fn notify_commit(&self) {
+        self.account_cache.lock().clear();
+    }
 }
 
 /// returns account's sequence number from storage